@@ -1,103 +1,261 @@
-use quote::quote;
-use syn::{DeriveInput, Fields, Ident, Variant};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{Attribute, DeriveInput, Fields, Generics, Ident, LitStr, Member, Type, Variant};
 
 use crate::abi_path;
 
 pub fn derive_interstice_type_macro(input: DeriveInput) -> proc_macro2::TokenStream {
     let struct_name = input.ident.clone();
 
-    return match input.data.clone() {
-        syn::Data::Struct(s) => derive_interstice_type_macro_struct(struct_name, s.fields),
+    let result = match input.data.clone() {
+        syn::Data::Struct(s) => derive_interstice_type_macro_struct(&input, s.fields),
         syn::Data::Enum(s) => {
-            let variants: Vec<Variant> = s.variants.into_iter().map(|f| f).collect();
-            derive_interstice_type_macro_enum(struct_name, variants)
+            let variants: Vec<Variant> = s.variants.into_iter().collect();
+            derive_interstice_type_macro_enum(&input, variants)
         }
-        _ => syn::Error::new_spanned(
+        _ => Err(syn::Error::new_spanned(
             struct_name,
             "IntersticeType can only be derived for struct or enum",
-        )
-        .to_compile_error()
-        .into(),
+        )),
     };
+    result.unwrap_or_else(|err| err.to_compile_error())
 }
 
-fn derive_interstice_type_macro_struct(
-    struct_name: Ident,
-    fields: Fields,
-) -> proc_macro2::TokenStream {
-    let abi = abi_path();
+/// Options of an `#[interstice(...)]` attribute.
+#[derive(Default)]
+struct IntersticeAttributes {
+    /// Name the type, variant or field has in values and type definitions.
+    rename: Option<String>,
+    /// Fill the field with `Default::default()` when a value lacks it. The default is
+    /// recorded in the type definition, so stored rows can be migrated with it.
+    default: bool,
+}
 
-    let struct_name_str = struct_name.to_string();
+fn interstice_attributes(attrs: &[Attribute]) -> syn::Result<IntersticeAttributes> {
+    let mut result = IntersticeAttributes::default();
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("interstice")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                let name: LitStr = meta.value()?.parse()?;
+                result.rename = Some(name.value());
+                Ok(())
+            } else if meta.path.is_ident("default") {
+                result.default = true;
+                Ok(())
+            } else {
+                Err(meta.error("expected `rename = \"...\"` or `default`"))
+            }
+        })?;
+    }
+    Ok(result)
+}
 
-    let named_fields = match fields {
-        syn::Fields::Named(f) => f.named,
-        _ => panic!("IntersticeType requires named fields"),
-    };
+/// Attributes of a type or variant, where only `rename` applies.
+fn item_attributes(attrs: &[Attribute]) -> syn::Result<IntersticeAttributes> {
+    let attributes = interstice_attributes(attrs)?;
+    if attributes.default {
+        let attr = attrs.iter().find(|attr| attr.path().is_ident("interstice"));
+        return Err(syn::Error::new_spanned(
+            attr,
+            "#[interstice(default)] only applies to fields",
+        ));
+    }
+    Ok(attributes)
+}
 
-    let mut field_names = Vec::new();
-    let mut field_types = Vec::new();
+/// A field of a struct or struct-like variant.
+struct FieldSpec {
+    member: Member,
+    /// Name of the field in values and type definitions.
+    name: String,
+    ty: Type,
+    default: bool,
+}
 
-    for field in named_fields {
-        let ident = field.ident.unwrap();
-        let ty = field.ty;
+/// Fields of a struct. Tuple struct fields are named after their position.
+fn field_specs(fields: &Fields) -> syn::Result<Vec<FieldSpec>> {
+    fields
+        .iter()
+        .enumerate()
+        .map(|(index, field)| {
+            let attributes = interstice_attributes(&field.attrs)?;
+            let member = match &field.ident {
+                Some(ident) => Member::Named(ident.clone()),
+                None => Member::Unnamed(index.into()),
+            };
+            let name = attributes.rename.unwrap_or_else(|| match &field.ident {
+                Some(ident) => ident.to_string(),
+                None => index.to_string(),
+            });
+            Ok(FieldSpec {
+                member,
+                name,
+                ty: field.ty.clone(),
+                default: attributes.default,
+            })
+        })
+        .collect()
+}
 
-        field_names.push(ident);
-        field_types.push(ty);
+/// Expression of the name of the type in values and type definitions. Instances
+/// of generic types are named after their type arguments, e.g. `Page<Player>`.
+fn type_name_expr(name: &str, generics: &Generics) -> TokenStream {
+    let abi = abi_path();
+    let params: Vec<&Ident> = generics.type_params().map(|param| &param.ident).collect();
+    if params.is_empty() {
+        quote! { #name.to_string() }
+    } else {
+        quote! {
+            #abi::IntersticeType::generic_name(
+                #name,
+                &[#(<#params as #abi::IntersticeTyped>::interstice_type()),*],
+            )
+        }
     }
+}
 
-    let field_name_strings: Vec<String> = field_names.iter().map(|f| f.to_string()).collect();
-    let field_assignments = field_names
-        .iter()
-        .zip(field_types.iter())
-        .zip(field_name_strings.iter())
-        .map(|((field_ident, field_ty), field_name_str)| {
-            if let Some(inner_ty) = extract_option_inner_type(field_ty) {
-                quote! {
-                    #field_ident: {
-                        let value = map
-                            .remove(#field_name_str)
-                            .ok_or_else(|| #abi::IntersticeAbiError::ConversionError(
-                                format!("Missing field {}", #field_name_str)
-                            ))?;
-                        match value {
-                            #abi::IntersticeValue::Option(opt) => match opt {
-                                Some(inner) => Some(<#inner_ty as TryFrom<#abi::IntersticeValue>>::try_from(*inner)?),
-                                None => None,
-                            },
-                            #abi::IntersticeValue::Void => None,
-                            other => {
-                                return Err(#abi::IntersticeAbiError::ConversionError(format!(
-                                    "Expected option for field {}, got {:?}", #field_name_str, other
-                                )));
-                            }
-                        }
-                    }
-                }
-            } else {
-                quote! {
-                    #field_ident: {
-                        let value = map
-                            .remove(#field_name_str)
-                            .ok_or_else(|| #abi::IntersticeAbiError::ConversionError(
-                                format!("Missing field {}", #field_name_str)
-                            ))?;
-                        <#field_ty as TryFrom<#abi::IntersticeValue>>::try_from(value)?
-                    }
+/// Generics of the impls, bounding type parameters and field types by what the
+/// conversions need.
+fn impl_generics(generics: &Generics, field_types: &[&Type]) -> syn::Result<Generics> {
+    let abi = abi_path();
+    let mut generics = generics.clone();
+    if let Some(param) = generics.lifetimes().next().map(|param| param.lifetime.clone()) {
+        return Err(syn::Error::new_spanned(
+            param,
+            "IntersticeType cannot be derived for types with lifetime parameters",
+        ));
+    }
+    if let Some(param) = generics.const_params().next().map(|param| param.ident.clone()) {
+        return Err(syn::Error::new_spanned(
+            param,
+            "IntersticeType cannot be derived for types with const parameters",
+        ));
+    }
+    if generics.type_params().next().is_none() {
+        return Ok(generics);
+    }
+
+    let params: Vec<Ident> = generics.type_params().map(|param| param.ident.clone()).collect();
+    let where_clause = generics.make_where_clause();
+    for param in params {
+        where_clause.predicates.push(syn::parse_quote! {
+            #param: Into<#abi::IntersticeValue>
+                + TryFrom<#abi::IntersticeValue, Error = #abi::IntersticeAbiError>
+                + #abi::IntersticeTyped
+                + 'static
+        });
+    }
+    for ty in field_types {
+        where_clause.predicates.push(syn::parse_quote! {
+            #ty: Into<#abi::IntersticeValue>
+                + TryFrom<#abi::IntersticeValue, Error = #abi::IntersticeAbiError>
+                + #abi::IntersticeTyped
+        });
+    }
+    Ok(generics)
+}
+
+/// Expression of the default value recorded in the definition of `field`.
+fn field_default(field: &FieldSpec) -> TokenStream {
+    let abi = abi_path();
+    let field_ty = &field.ty;
+    if field.default {
+        quote! { Some(Into::<#abi::IntersticeValue>::into(<#field_ty as Default>::default())) }
+    } else {
+        quote! { None }
+    }
+}
+
+/// Expression converting the value of `field` taken out of `map`.
+fn field_conversion(field: &FieldSpec, context: &TokenStream) -> TokenStream {
+    let abi = abi_path();
+    let field_name_str = &field.name;
+    let field_ty = &field.ty;
+    let missing = if field.default {
+        quote! { None => Default::default() }
+    } else {
+        quote! {
+            None => {
+                return Err(#abi::IntersticeAbiError::ConversionError(format!(
+                    "Missing field {}{}", #field_name_str, #context
+                )));
+            }
+        }
+    };
+
+    if let Some(inner_ty) = extract_option_inner_type(field_ty) {
+        quote! {
+            match map.remove(#field_name_str) {
+                Some(#abi::IntersticeValue::Option(opt)) => match opt {
+                    Some(inner) => Some(<#inner_ty as TryFrom<#abi::IntersticeValue>>::try_from(*inner)?),
+                    None => None,
+                },
+                Some(#abi::IntersticeValue::Void) => None,
+                Some(other) => {
+                    return Err(#abi::IntersticeAbiError::ConversionError(format!(
+                        "Expected option for field {}{}, got {:?}", #field_name_str, #context, other
+                    )));
                 }
+                #missing
             }
-        })
-        .collect::<Vec<_>>();
+        }
+    } else {
+        quote! {
+            match map.remove(#field_name_str) {
+                Some(value) => <#field_ty as TryFrom<#abi::IntersticeValue>>::try_from(value).map_err(|err| {
+                    #abi::IntersticeAbiError::ConversionError(format!(
+                        "Failed to convert field {}{}: {}", #field_name_str, #context, err
+                    ))
+                })?,
+                #missing
+            }
+        }
+    }
+}
 
+/// Statements adding the definitions of the types of `fields` to `definitions`.
+fn field_type_definitions(field_types: &[&Type]) -> TokenStream {
+    let abi = abi_path();
     quote! {
-        impl Into<#abi::IntersticeValue> for #struct_name {
+        #(<#field_types as #abi::IntersticeTyped>::type_definitions(definitions);)*
+    }
+}
+
+fn derive_interstice_type_macro_struct(
+    input: &DeriveInput,
+    fields: Fields,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let abi = abi_path();
+
+    let struct_name = &input.ident;
+    let attributes = item_attributes(&input.attrs)?;
+    let struct_name_str = attributes.rename.unwrap_or_else(|| struct_name.to_string());
+    let type_name = type_name_expr(&struct_name_str, &input.generics);
+
+    let fields = field_specs(&fields)?;
+    let field_types: Vec<&Type> = fields.iter().map(|field| &field.ty).collect();
+    let generics = impl_generics(&input.generics, &field_types)?;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let field_members: Vec<&Member> = fields.iter().map(|field| &field.member).collect();
+    let field_name_strings: Vec<&String> = fields.iter().map(|field| &field.name).collect();
+    let field_assignments = fields
+        .iter()
+        .map(|field| field_conversion(field, &quote! { "" }))
+        .collect::<Vec<_>>();
+    let field_defaults: Vec<TokenStream> = fields.iter().map(field_default).collect();
+    let field_type_definitions = field_type_definitions(&field_types);
+
+    Ok(quote! {
+        impl #impl_generics Into<#abi::IntersticeValue> for #struct_name #ty_generics #where_clause {
             fn into(self) -> #abi::IntersticeValue {
                 #abi::IntersticeValue::Struct {
-                    name: #struct_name_str.to_string(),
+                    name: #type_name,
                     fields: vec![
                         #(
                             #abi::Field {
                                 name: #field_name_strings.to_string(),
-                                value: self.#field_names.into(),
+                                value: self.#field_members.into(),
                             }
                         ),*
                     ],
@@ -105,26 +263,58 @@ fn derive_interstice_type_macro_struct(
             }
         }
 
-        impl TryFrom<#abi::IntersticeValue> for #struct_name {
+        impl #impl_generics TryFrom<#abi::IntersticeValue> for #struct_name #ty_generics #where_clause {
             type Error = #abi::IntersticeAbiError;
 
             fn try_from(value: #abi::IntersticeValue) -> Result<Self, Self::Error> {
+                let type_name: String = #type_name;
                 match value {
-                    #abi::IntersticeValue::Struct { name, fields } if name == #struct_name_str => {
+                    #abi::IntersticeValue::Struct { name, fields } if name == type_name => {
                         let mut map = std::collections::HashMap::new();
                         for field in fields {
                             map.insert(field.name, field.value);
                         }
 
                         Ok(Self {
-                            #(#field_assignments,)*
+                            #(#field_members: #field_assignments,)*
                         })
                     }
                     _ => Err(Self::Error::ConversionError("Expected struct".to_string())),
                 }
             }
         }
-    }
+
+        impl #impl_generics #abi::IntersticeTyped for #struct_name #ty_generics #where_clause {
+            fn interstice_type() -> #abi::IntersticeType {
+                #abi::IntersticeType::Named(#type_name)
+            }
+
+            fn type_definitions(
+                definitions: &mut std::collections::HashMap<String, #abi::IntersticeTypeDef>,
+            ) {
+                let name = #type_name;
+                if definitions.contains_key(&name) {
+                    return;
+                }
+                definitions.insert(
+                    name.clone(),
+                    #abi::IntersticeTypeDef::Struct {
+                        name,
+                        fields: vec![
+                            #(
+                                #abi::FieldDef {
+                                    name: #field_name_strings.to_string(),
+                                    field_type: <#field_types as #abi::IntersticeTyped>::interstice_type(),
+                                    default: #field_defaults,
+                                }
+                            ),*
+                        ],
+                    },
+                );
+                #field_type_definitions
+            }
+        }
+    })
 }
 
 fn extract_option_inner_type(ty: &syn::Type) -> Option<syn::Type> {
@@ -144,98 +334,66 @@ fn extract_option_inner_type(ty: &syn::Type) -> Option<syn::Type> {
     None
 }
 
+/// A variant of an enum, with the name it has in values and type definitions.
+struct VariantSpec {
+    ident: Ident,
+    name: String,
+    fields: Fields,
+}
+
 fn derive_interstice_type_macro_enum(
-    enum_name: Ident,
+    input: &DeriveInput,
     variants: Vec<Variant>,
-) -> proc_macro2::TokenStream {
+) -> syn::Result<proc_macro2::TokenStream> {
     let abi = abi_path();
 
-    let enum_name_str = enum_name.to_string();
+    let enum_name = &input.ident;
+    let attributes = item_attributes(&input.attrs)?;
+    let enum_name_str = attributes.rename.unwrap_or_else(|| enum_name.to_string());
+    let type_name = type_name_expr(&enum_name_str, &input.generics);
+
+    let mut specs = Vec::new();
+    let mut field_types: Vec<&Type> = Vec::new();
+    for variant in &variants {
+        let attributes = item_attributes(&variant.attrs)?;
+        if let Fields::Unnamed(fields) = &variant.fields
+            && let Some(field) = fields.unnamed.iter().find(|field| {
+                field.attrs.iter().any(|attr| attr.path().is_ident("interstice"))
+            })
+        {
+            return Err(syn::Error::new_spanned(
+                field,
+                "#[interstice] attributes only apply to named fields",
+            ));
+        }
+        field_types.extend(variant.fields.iter().map(|field| &field.ty));
+        specs.push(VariantSpec {
+            ident: variant.ident.clone(),
+            name: attributes.rename.unwrap_or_else(|| variant.ident.to_string()),
+            fields: variant.fields.clone(),
+        });
+    }
+    let generics = impl_generics(&input.generics, &field_types)?;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
-    let match_arms = variants.clone().into_iter().map(|variant| {
-        let variant_ident = variant.ident;
-        let variant_name_str = variant_ident.to_string();
+    let mut match_arms = Vec::new();
+    let mut reverse_match_arms = Vec::new();
+    let mut variant_definitions = Vec::new();
+    for variant in &specs {
+        let variant_ident = &variant.ident;
+        let variant_name_str = &variant.name;
 
-        match variant.fields {
+        match &variant.fields {
             // ---------------- UNIT ----------------
             Fields::Unit => {
-                quote! {
+                match_arms.push(quote! {
                     #enum_name::#variant_ident => #abi::IntersticeValue::Enum {
-                        name: #enum_name_str.to_string(),
+                        name: #type_name,
                         variant: #variant_name_str.to_string(),
                         value: Box::new(#abi::IntersticeValue::Void),
                     }
-                }
-            }
-
-            // ---------------- TUPLE VARIANT ----------------
-            Fields::Unnamed(fields) => {
-                let bindings: Vec<_> = (0..fields.unnamed.len())
-                    .map(|i| syn::Ident::new(&format!("v{i}"), variant_ident.span()))
-                    .collect();
-
-                if bindings.len() == 1 {
-                    let v0 = &bindings[0];
-                    quote! {
-                        #enum_name::#variant_ident(#v0) => #abi::IntersticeValue::Enum {
-                            name: #enum_name_str.to_string(),
-                            variant: #variant_name_str.to_string(),
-                            value: Box::new(#v0.into()),
-                        }
-                    }
-                } else {
-                    quote! {
-                        #enum_name::#variant_ident(#(#bindings),*) => #abi::IntersticeValue::Enum {
-                            name: #enum_name_str.to_string(),
-                            variant: #variant_name_str.to_string(),
-                            value: Box::new(#abi::IntersticeValue::Tuple(vec![
-                                #(#bindings.into()),*
-                            ])),
-                        }
-                    }
-                }
-            }
-
-            // ---------------- STRUCT VARIANT ----------------
-            Fields::Named(fields) => {
-                let field_idents: Vec<_> = fields
-                    .named
-                    .iter()
-                    .map(|f| f.ident.clone().unwrap())
-                    .collect();
-
-                let field_names: Vec<String> =
-                    field_idents.iter().map(|id| id.to_string()).collect();
-
-                quote! {
-                    #enum_name::#variant_ident { #(#field_idents),* } => #abi::IntersticeValue::Enum {
-                        name: #enum_name_str.to_string(),
-                        variant: #variant_name_str.to_string(),
-                        value: Box::new(#abi::IntersticeValue::Struct {
-                            name: #variant_name_str.to_string(),
-                            fields: vec![
-                                #(
-                                    #abi::Field {
-                                        name: #field_names.to_string(),
-                                        value: #field_idents.into(),
-                                    }
-                                ),*
-                            ],
-                        }),
-                    }
-                }
-            }
-        }
-    });
-
-    let reverse_match_arms = variants.iter().map(|variant| {
-        let variant_ident = &variant.ident;
-        let variant_name_str = variant_ident.to_string();
-
-        match &variant.fields {
-            // -------- UNIT --------
-            Fields::Unit => {
-                quote! {
+                });
+                reverse_match_arms.push(quote! {
                     #variant_name_str => {
                         match *value {
                             #abi::IntersticeValue::Void => Ok(#enum_name::#variant_ident),
@@ -247,16 +405,34 @@ fn derive_interstice_type_macro_enum(
                             ))),
                         }
                     }
-                }
+                });
+                variant_definitions.push(quote! {
+                    #abi::FieldDef {
+                        name: #variant_name_str.to_string(),
+                        field_type: #abi::IntersticeType::Void,
+                        default: None,
+                    }
+                });
             }
 
-            // -------- TUPLE --------
+            // ---------------- TUPLE VARIANT ----------------
             Fields::Unnamed(fields) => {
-                let arity = fields.unnamed.len();
+                let bindings: Vec<_> = (0..fields.unnamed.len())
+                    .map(|i| format_ident!("v{i}", span = variant_ident.span()))
+                    .collect();
+                let types: Vec<_> = fields.unnamed.iter().map(|f| &f.ty).collect();
 
-                if arity == 1 {
-                    let ty = &fields.unnamed.first().unwrap().ty;
-                    quote! {
+                if bindings.len() == 1 {
+                    let v0 = &bindings[0];
+                    let ty = types[0];
+                    match_arms.push(quote! {
+                        #enum_name::#variant_ident(#v0) => #abi::IntersticeValue::Enum {
+                            name: #type_name,
+                            variant: #variant_name_str.to_string(),
+                            value: Box::new(#v0.into()),
+                        }
+                    });
+                    reverse_match_arms.push(quote! {
                         #variant_name_str => {
                             let inner: #ty = (*value).try_into().map_err(|_| {
                                 #abi::IntersticeAbiError::ConversionError(format!(
@@ -267,12 +443,27 @@ fn derive_interstice_type_macro_enum(
                             })?;
                             Ok(#enum_name::#variant_ident(inner))
                         }
-                    }
+                    });
+                    variant_definitions.push(quote! {
+                        #abi::FieldDef {
+                            name: #variant_name_str.to_string(),
+                            field_type: <#ty as #abi::IntersticeTyped>::interstice_type(),
+                            default: None,
+                        }
+                    });
                 } else {
-                    let indices: Vec<syn::Index> = (0..arity).map(|i| syn::Index::from(i)).collect();
-                    let types: Vec<_> = fields.unnamed.iter().map(|f| &f.ty).collect();
-
-                    quote! {
+                    let arity = bindings.len();
+                    let indices: Vec<syn::Index> = (0..arity).map(syn::Index::from).collect();
+                    match_arms.push(quote! {
+                        #enum_name::#variant_ident(#(#bindings),*) => #abi::IntersticeValue::Enum {
+                            name: #type_name,
+                            variant: #variant_name_str.to_string(),
+                            value: Box::new(#abi::IntersticeValue::Tuple(vec![
+                                #(#bindings.into()),*
+                            ])),
+                        }
+                    });
+                    reverse_match_arms.push(quote! {
                         #variant_name_str => {
                             match *value {
                                 #abi::IntersticeValue::Tuple(vec) => {
@@ -302,49 +493,66 @@ fn derive_interstice_type_macro_enum(
                                 ))),
                             }
                         }
-                    }
+                    });
+                    variant_definitions.push(quote! {
+                        #abi::FieldDef {
+                            name: #variant_name_str.to_string(),
+                            field_type: #abi::IntersticeType::Tuple(vec![
+                                #(<#types as #abi::IntersticeTyped>::interstice_type()),*
+                            ]),
+                            default: None,
+                        }
+                    });
                 }
             }
 
-            // -------- STRUCT --------
-            Fields::Named(fields) => {
-                let field_idents: Vec<_> = fields.named.iter().map(|f| f.ident.clone().unwrap()).collect();
-                let field_names: Vec<String> = field_idents.iter().map(|id| id.to_string()).collect();
-                let field_types: Vec<_> = fields.named.iter().map(|f| &f.ty).collect();
+            // ---------------- STRUCT VARIANT ----------------
+            // The payload is a struct named `Enum::Variant`, defined next to the enum.
+            Fields::Named(_) => {
+                let fields = field_specs(&variant.fields)?;
+                let field_members: Vec<&Member> = fields.iter().map(|field| &field.member).collect();
+                let field_names: Vec<&String> = fields.iter().map(|field| &field.name).collect();
+                let variant_field_types: Vec<&Type> = fields.iter().map(|field| &field.ty).collect();
+                let context = quote! { format!(" in {}::{}", #enum_name_str, #variant_name_str) };
+                let field_assignments: Vec<TokenStream> = fields
+                    .iter()
+                    .map(|field| field_conversion(field, &context))
+                    .collect();
+                let field_defaults: Vec<TokenStream> = fields.iter().map(field_default).collect();
+                let variant_struct_name = quote! { format!("{}::{}", #type_name, #variant_name_str) };
 
-                quote! {
+                match_arms.push(quote! {
+                    #enum_name::#variant_ident { #(#field_members),* } => #abi::IntersticeValue::Enum {
+                        name: #type_name,
+                        variant: #variant_name_str.to_string(),
+                        value: Box::new(#abi::IntersticeValue::Struct {
+                            name: #variant_struct_name,
+                            fields: vec![
+                                #(
+                                    #abi::Field {
+                                        name: #field_names.to_string(),
+                                        value: #field_members.into(),
+                                    }
+                                ),*
+                            ],
+                        }),
+                    }
+                });
+                reverse_match_arms.push(quote! {
                     #variant_name_str => {
                         match *value {
-                            #abi::IntersticeValue::Struct { name: struct_name, fields } => {
-                                if struct_name != #variant_name_str {
-                                    return Err(#abi::IntersticeAbiError::ConversionError(format!(
-                                        "Struct name mismatch for {}::{}",
-                                        #enum_name_str,
-                                        #variant_name_str
-                                    )));
-                                }
+                            // Values written before struct variants had their own type
+                            // definition name the struct after the variant only.
+                            #abi::IntersticeValue::Struct { name: struct_name, fields }
+                                if struct_name == #variant_struct_name || struct_name == #variant_name_str =>
+                            {
                                 let mut map = std::collections::HashMap::new();
                                 for field in fields {
                                     map.insert(field.name, field.value);
                                 }
 
                                 Ok(#enum_name::#variant_ident {
-                                    #(
-                                        #field_idents: <#field_types as TryFrom<#abi::IntersticeValue>>::try_from(
-                                            map.remove(#field_names)
-                                                .ok_or_else(|| #abi::IntersticeAbiError::ConversionError(format!(
-                                                    "Missing field {} in {}::{}",
-                                                    #field_names,
-                                                    #enum_name_str,
-                                                    #variant_name_str
-                                                )))?
-                                        ).map_err(|_| #abi::IntersticeAbiError::ConversionError(format!(
-                                            "Failed to convert field {} in {}::{}",
-                                            #field_names,
-                                            #enum_name_str,
-                                            #variant_name_str
-                                        )))?
-                                    ),*
+                                    #(#field_members: #field_assignments),*
                                 })
                             }
                             other => Err(#abi::IntersticeAbiError::ConversionError(format!(
@@ -355,13 +563,40 @@ fn derive_interstice_type_macro_enum(
                             ))),
                         }
                     }
-                }
+                });
+                variant_definitions.push(quote! {
+                    {
+                        let variant_struct_name = #variant_struct_name;
+                        definitions.insert(
+                            variant_struct_name.clone(),
+                            #abi::IntersticeTypeDef::Struct {
+                                name: variant_struct_name.clone(),
+                                fields: vec![
+                                    #(
+                                        #abi::FieldDef {
+                                            name: #field_names.to_string(),
+                                            field_type: <#variant_field_types as #abi::IntersticeTyped>::interstice_type(),
+                                            default: #field_defaults,
+                                        }
+                                    ),*
+                                ],
+                            },
+                        );
+                        #abi::FieldDef {
+                            name: #variant_name_str.to_string(),
+                            field_type: #abi::IntersticeType::Named(variant_struct_name),
+                            default: None,
+                        }
+                    }
+                });
             }
         }
-    });
+    }
 
-    quote! {
-        impl Into<#abi::IntersticeValue> for #enum_name {
+    let field_type_definitions = field_type_definitions(&field_types);
+
+    Ok(quote! {
+        impl #impl_generics Into<#abi::IntersticeValue> for #enum_name #ty_generics #where_clause {
             fn into(self) -> #abi::IntersticeValue {
                 match self {
                     #(#match_arms,)*
@@ -369,12 +604,13 @@ fn derive_interstice_type_macro_enum(
             }
         }
 
-        impl TryFrom<#abi::IntersticeValue> for #enum_name {
+        impl #impl_generics TryFrom<#abi::IntersticeValue> for #enum_name #ty_generics #where_clause {
             type Error = #abi::IntersticeAbiError;
 
             fn try_from(value: #abi::IntersticeValue) -> Result<Self, Self::Error> {
+                let type_name: String = #type_name;
                 match value {
-                    #abi::IntersticeValue::Enum { name, variant, value } if name == #enum_name_str => {
+                    #abi::IntersticeValue::Enum { name, variant, value } if name == type_name => {
                         match variant.as_str() {
                             #(#reverse_match_arms,)*
                             _ => Err(#abi::IntersticeAbiError::ConversionError(format!(
@@ -392,5 +628,22 @@ fn derive_interstice_type_macro_enum(
             }
         }
 
-    }
+        impl #impl_generics #abi::IntersticeTyped for #enum_name #ty_generics #where_clause {
+            fn interstice_type() -> #abi::IntersticeType {
+                #abi::IntersticeType::Named(#type_name)
+            }
+
+            fn type_definitions(
+                definitions: &mut std::collections::HashMap<String, #abi::IntersticeTypeDef>,
+            ) {
+                let name = #type_name;
+                if definitions.contains_key(&name) {
+                    return;
+                }
+                let variants = vec![#(#variant_definitions),*];
+                definitions.insert(name.clone(), #abi::IntersticeTypeDef::Enum { name, variants });
+                #field_type_definitions
+            }
+        }
+    })
 }
@@ -8,7 +8,7 @@ use quote::quote;
 use syn::Ident;
 use syn::parse_macro_input;
 
-#[proc_macro_derive(IntersticeType)]
+#[proc_macro_derive(IntersticeType, attributes(interstice))]
 pub fn derive_interstice_type(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as syn::DeriveInput);
     derive_interstice_type_macro(input).into()
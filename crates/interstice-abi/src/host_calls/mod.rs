@@ -4,6 +4,11 @@ mod gpu;
 mod input;
 mod module;
 mod network;
+mod reducer_call;
+mod replica;
+mod schedule;
+mod table_query;
+mod time_travel;
 
 pub use audio::*;
 pub use file::*;
@@ -11,6 +16,11 @@ pub use gpu::*;
 pub use input::*;
 pub use module::*;
 pub use network::*;
+pub use reducer_call::*;
+pub use replica::*;
+pub use schedule::*;
+pub use table_query::*;
+pub use time_travel::*;
 
 use crate::{IndexKey, IntersticeValue, Row};
 use serde::{Deserialize, Serialize};
@@ -18,17 +28,24 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug, Deserialize, Serialize)]
 pub enum HostCall {
     CallReducer(CallReducerRequest),
-    Schedule(ScheduleRequest),
+    Schedule(ScheduleCall),
     CallQuery(CallQueryRequest),
     TableScan(TableScanRequest),
     TableGetByPrimaryKey(TableGetByPrimaryKeyRequest),
     TableIndexScan(TableIndexScanRequest),
+    TableScanOpen(TableScanOpenRequest),
+    TableScanNext(TableScanNextRequest),
+    TableScanClose(TableScanCloseRequest),
+    TableQuery(TableQueryRequest),
     Gpu(GpuCall),
     Audio(AudioCall),
     File(FileCall),
     Module(ModuleCall),
     Network(NetworkCall),
     CurrentNodeId,
+    ReplicaStatus(ReplicaStatusRequest),
+    ReducerCallStatus(ReducerCallStatusRequest),
+    TimeTravel(TimeTravelCall),
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
@@ -43,18 +60,6 @@ pub enum ModuleSelection {
     Other(String),
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ScheduleRequest {
-    pub reducer_name: String,
-    pub delay_ms: u64,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub enum ScheduleResponse {
-    Ok,
-    Err(String),
-}
-
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CallReducerRequest {
     pub node_selection: NodeSelection,
@@ -63,9 +68,12 @@ pub struct CallReducerRequest {
     pub input: IntersticeValue,
 }
 
+/// Reducers of the current node have run when `Ok` comes back. Calls to other
+/// nodes are sent without waiting; their `call_id` follows the outcome with
+/// `ReducerCallStatus`.
 #[derive(Debug, Serialize, Deserialize)]
 pub enum CallReducerResponse {
-    Ok,
+    Ok { call_id: Option<String> },
     Err(String),
 }
 
@@ -183,7 +191,7 @@ pub enum TableGetByPrimaryKeyResponse {
     Err(String),
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub enum IndexQuery {
     Eq(IndexKey),
     Lt(IndexKey),
@@ -196,6 +204,18 @@ pub enum IndexQuery {
         include_min: bool,
         include_max: bool,
     },
+    /// Composite btree indexes: rows whose leading key fields equal `prefix`,
+    /// optionally bounded on the field that follows it (`None` leaves that side
+    /// open).
+    Prefix {
+        prefix: Vec<IndexKey>,
+        min: Option<IndexKey>,
+        max: Option<IndexKey>,
+        include_min: bool,
+        include_max: bool,
+    },
+    /// Btree indexes: every row, in key order.
+    All,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -204,6 +224,7 @@ pub struct TableIndexScanRequest {
     pub table_name: String,
     pub field_name: String,
     pub query: IndexQuery,
+    pub options: ScanOptions,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -212,6 +233,66 @@ pub enum TableIndexScanResponse {
     Err(String),
 }
 
+/// Order and window of a scan. Rows come in storage order for table scans and in
+/// key order for btree index scans; `offset` and `limit` count the rows the
+/// caller can see.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ScanOptions {
+    pub offset: u64,
+    pub limit: Option<u64>,
+    pub reverse: bool,
+}
+
+/// Index to scan with a cursor instead of the whole table.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TableScanIndex {
+    pub field_name: String,
+    pub query: IndexQuery,
+}
+
+/// Open a cursor over a table or index scan. The rows are then read
+/// `batch_size` at a time with `TableScanNext`, so a module only copies the
+/// rows it consumes. Cursors live until closed or until the reducer or query
+/// that opened them returns.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TableScanOpenRequest {
+    pub module_selection: ModuleSelection,
+    pub table_name: String,
+    pub index: Option<TableScanIndex>,
+    pub options: ScanOptions,
+    pub batch_size: u32,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub enum TableScanOpenResponse {
+    Ok { cursor_id: u64 },
+    Err(String),
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TableScanNextRequest {
+    pub cursor_id: u64,
+}
+
+/// The next batch of a cursor. `done` is set with the last batch, after which
+/// the cursor is closed.
+#[derive(Debug, Deserialize, Serialize)]
+pub enum TableScanNextResponse {
+    Ok { rows: Vec<Row>, done: bool },
+    Err(String),
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TableScanCloseRequest {
+    pub cursor_id: u64,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub enum TableScanCloseResponse {
+    Ok,
+    Err(String),
+}
+
 pub fn get_reducer_wrapper_name(reducer_name: &str) -> String {
     format!("__interstice_reducer_wrapper_{}", reducer_name)
 }
@@ -7,6 +7,12 @@ pub enum ModuleCall {
         node_selection: NodeSelection,
         wasm_binary: Vec<u8>,
     },
+    /// Replace a loaded module with a new version of it in place, keeping its
+    /// subscriptions and table contents.
+    Upgrade {
+        node_selection: NodeSelection,
+        wasm_binary: Vec<u8>,
+    },
     /// Unload a module from the runtime while keeping its persisted table data,
     /// so it can be loaded again later and resume with its state intact.
     Unload {
@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+
+/// Outcome of a reducer call made on another node, identified by the `call_id`
+/// returned by `CallReducer`. The current status is returned right away, the call
+/// is `Pending` until the node answers.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReducerCallStatusRequest {
+    pub call_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum ReducerCallStatusResponse {
+    Ok(ReducerCallStatus),
+    Err(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReducerCallStatus {
+    /// The called node has not answered yet.
+    Pending,
+    /// The reducer ran and its changes were committed. Commits are numbered from
+    /// the start of the called node.
+    Committed { commit_sequence: u64 },
+    /// The call was refused, the reducer is unknown, its arguments did not match
+    /// or it failed: nothing was committed.
+    Failed { error: String },
+    /// The called node disconnected before answering: the reducer may or may not
+    /// have committed.
+    Unknown { reason: String },
+}
+
+impl ReducerCallStatus {
+    pub fn is_pending(&self) -> bool {
+        matches!(self, ReducerCallStatus::Pending)
+    }
+}
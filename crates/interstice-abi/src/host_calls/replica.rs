@@ -0,0 +1,47 @@
+use serde::{Deserialize, Serialize};
+
+/// Replication status of one of the calling module's `replicated_tables`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReplicaStatusRequest {
+    pub node_name: String,
+    pub module_name: String,
+    pub table_name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum ReplicaStatusResponse {
+    Ok(ReplicaStatus),
+    Err(String),
+}
+
+/// Where a replica stands against its source table. Every event of a public table
+/// has a sequence number, counted from the start of the source node.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplicaStatus {
+    pub state: ReplicaState,
+    /// Sequence number of the last source event applied to the replica.
+    pub last_sequence: u64,
+    /// Highest sequence number received from the source table.
+    pub source_sequence: u64,
+    /// Unix time in milliseconds of the last completed sync, `None` before the first.
+    pub last_sync_unix_ms: Option<u64>,
+}
+
+impl ReplicaStatus {
+    /// Source events received but not applied yet.
+    pub fn lag(&self) -> u64 {
+        self.source_sequence.saturating_sub(self.last_sequence)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReplicaState {
+    /// Waiting for the first copy of the table.
+    Syncing,
+    /// Up to date, applying the source events as they come.
+    Live,
+    /// Catching up on missed events after a gap or a reconnection.
+    Resyncing,
+    /// The source node is unreachable; the replica keeps its rows until it is back.
+    Disconnected,
+}
@@ -0,0 +1,54 @@
+use serde::{Deserialize, Serialize};
+
+use crate::IntersticeValue;
+
+/// Schedule host calls. Schedules belong to the calling module and only target its
+/// own reducers; they are persisted with the module's data and survive restarts.
+#[derive(Debug, Serialize, Deserialize)]
+pub enum ScheduleCall {
+    Create(ScheduleRequest),
+    Cancel { schedule_id: u64 },
+    List,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduleRequest {
+    pub reducer_name: String,
+    /// Reducer arguments (without the context), as an `IntersticeValue::Vec`.
+    pub input: IntersticeValue,
+    pub timing: ScheduleTiming,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScheduleTiming {
+    /// Run once, `delay_ms` from now.
+    Once { delay_ms: u64 },
+    /// Run `delay_ms` from now, then every `interval_ms`.
+    Interval { delay_ms: u64, interval_ms: u64 },
+    /// Run at every minute matching a five-field cron expression
+    /// (`minute hour day-of-month month day-of-week`, UTC).
+    Cron(String),
+}
+
+/// A pending schedule as reported by `ScheduleCall::List`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScheduleInfo {
+    pub id: u64,
+    pub reducer_name: String,
+    pub input: IntersticeValue,
+    pub timing: ScheduleTiming,
+    /// Unix time in milliseconds of the next run.
+    pub next_run_unix_ms: u64,
+    /// Runs of a recurring schedule that were skipped because the node was down
+    /// (or too busy) when they came due.
+    pub missed_runs: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum ScheduleResponse {
+    Scheduled(u64),
+    /// Whether a pending schedule with this id existed.
+    Cancelled(bool),
+    List(Vec<ScheduleInfo>),
+    Err(String),
+}
@@ -0,0 +1,278 @@
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{IndexKey, IntersticeValue, ModuleSelection, Row, ScanOptions, TableSchema};
+
+/// Rows of a table matching `filter`, evaluated by the host. Rows are returned in
+/// `order_by` order when set, in scan order otherwise; `options.reverse` flips the
+/// order and `offset`/`limit` keep a window of the result.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TableQueryRequest {
+    pub module_selection: ModuleSelection,
+    pub table_name: String,
+    pub filter: Option<RowFilter>,
+    pub order_by: Option<String>,
+    pub options: ScanOptions,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub enum TableQueryResponse {
+    Ok { rows: Vec<Row> },
+    Err(String),
+}
+
+/// Predicate over the fields of a table row (the primary key included, by name).
+/// Fields of struct values are named by path, e.g. `pos.x`. Used by `TableQuery`
+/// and to filter the table events a node subscribes to.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub enum RowFilter {
+    Compare {
+        field: String,
+        op: CompareOp,
+        value: IntersticeValue,
+    },
+    In {
+        field: String,
+        values: Vec<IntersticeValue>,
+    },
+    /// String fields starting with `prefix`.
+    StartsWith {
+        field: String,
+        prefix: String,
+    },
+    And(Vec<RowFilter>),
+    Or(Vec<RowFilter>),
+    Not(Box<RowFilter>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Lte,
+    Gt,
+    Gte,
+}
+
+impl RowFilter {
+    /// Check that every field the filter names exists in the table.
+    pub fn validate(&self, schema: &TableSchema) -> Result<(), String> {
+        match self {
+            RowFilter::Compare { field, .. }
+            | RowFilter::In { field, .. }
+            | RowFilter::StartsWith { field, .. } => schema
+                .field_position(field.split('.').next().unwrap_or_default())
+                .map(|_| ())
+                .ok_or_else(|| format!("table '{}' has no field '{}'", schema.name, field)),
+            RowFilter::And(filters) | RowFilter::Or(filters) => {
+                filters.iter().try_for_each(|filter| filter.validate(schema))
+            }
+            RowFilter::Not(filter) => filter.validate(schema),
+        }
+    }
+
+    pub fn matches(&self, schema: &TableSchema, row: &Row) -> bool {
+        match self {
+            RowFilter::Compare { field, op, value } => {
+                let Some(ordering) = schema
+                    .field_value(row, field)
+                    .and_then(|field_value| compare_values(field_value, value))
+                else {
+                    return *op == CompareOp::Ne;
+                };
+                match op {
+                    CompareOp::Eq => ordering == Ordering::Equal,
+                    CompareOp::Ne => ordering != Ordering::Equal,
+                    CompareOp::Lt => ordering == Ordering::Less,
+                    CompareOp::Lte => ordering != Ordering::Greater,
+                    CompareOp::Gt => ordering == Ordering::Greater,
+                    CompareOp::Gte => ordering != Ordering::Less,
+                }
+            }
+            RowFilter::In { field, values } => schema.field_value(row, field).is_some_and(|field_value| {
+                values
+                    .iter()
+                    .any(|value| compare_values(field_value, value) == Some(Ordering::Equal))
+            }),
+            RowFilter::StartsWith { field, prefix } => matches!(
+                schema.field_value(row, field),
+                Some(IntersticeValue::String(value)) if value.starts_with(prefix.as_str())
+            ),
+            RowFilter::And(filters) => filters.iter().all(|filter| filter.matches(schema, row)),
+            RowFilter::Or(filters) => filters.iter().any(|filter| filter.matches(schema, row)),
+            RowFilter::Not(filter) => !filter.matches(schema, row),
+        }
+    }
+}
+
+/// Order of two field values of the same type. Floats compare numerically; other
+/// values compare like index keys. `None` when the values cannot be compared.
+pub fn compare_values(left: &IntersticeValue, right: &IntersticeValue) -> Option<Ordering> {
+    match (left, right) {
+        (IntersticeValue::F32(left), IntersticeValue::F32(right)) => left.partial_cmp(right),
+        (IntersticeValue::F64(left), IntersticeValue::F64(right)) => left.partial_cmp(right),
+        (IntersticeValue::Option(Some(left)), IntersticeValue::Option(Some(right))) => {
+            compare_values(left, right)
+        }
+        _ => {
+            let left = IndexKey::try_from(left).ok()?;
+            let right = IndexKey::try_from(right).ok()?;
+            (std::mem::discriminant(&left) == std::mem::discriminant(&right))
+                .then(|| left.cmp(&right))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        IntersticeType, PersistenceKind, TableLimits, TableVisibility,
+        interstice_type_def::FieldDef,
+    };
+
+    fn schema() -> TableSchema {
+        let field = |name: &str, field_type| FieldDef {
+            name: name.into(),
+            field_type,
+            default: None,
+        };
+        TableSchema {
+            name: "players".into(),
+            type_name: "Players".into(),
+            visibility: TableVisibility::Private,
+            fields: vec![
+                field("name", IntersticeType::String),
+                field("score", IntersticeType::U32),
+                field("speed", IntersticeType::F32),
+            ],
+            primary_key: field("id", IntersticeType::U64),
+            primary_key_auto_inc: false,
+            indexes: Vec::new(),
+            persistence: PersistenceKind::Logged,
+            limits: TableLimits::default(),
+            migrations: Vec::new(),
+            view: None,
+        }
+    }
+
+    fn player(id: u64, name: &str, score: u32, speed: f32) -> Row {
+        Row {
+            primary_key: IntersticeValue::U64(id),
+            entries: vec![
+                IntersticeValue::String(name.into()),
+                IntersticeValue::U32(score),
+                IntersticeValue::F32(speed),
+            ],
+        }
+    }
+
+    fn compare(field: &str, op: CompareOp, value: IntersticeValue) -> RowFilter {
+        RowFilter::Compare {
+            field: field.into(),
+            op,
+            value,
+        }
+    }
+
+    #[test]
+    fn filters_match_fields_and_primary_key() {
+        let schema = schema();
+        let ada = player(1, "ada", 12, 1.5);
+        let bob = player(2, "bob", 7, 3.0);
+
+        let strong = compare("score", CompareOp::Gt, IntersticeValue::U32(10));
+        assert!(strong.matches(&schema, &ada));
+        assert!(!strong.matches(&schema, &bob));
+
+        let fast_or_first = RowFilter::Or(vec![
+            compare("speed", CompareOp::Gte, IntersticeValue::F32(2.0)),
+            compare("id", CompareOp::Eq, IntersticeValue::U64(1)),
+        ]);
+        assert!(fast_or_first.matches(&schema, &ada));
+        assert!(fast_or_first.matches(&schema, &bob));
+
+        let named = RowFilter::And(vec![
+            RowFilter::StartsWith {
+                field: "name".into(),
+                prefix: "b".into(),
+            },
+            RowFilter::In {
+                field: "score".into(),
+                values: vec![IntersticeValue::U32(7), IntersticeValue::U32(8)],
+            },
+        ]);
+        assert!(!named.matches(&schema, &ada));
+        assert!(named.matches(&schema, &bob));
+        assert!(RowFilter::Not(Box::new(named)).matches(&schema, &ada));
+
+        // Values of another type never compare equal.
+        assert!(!compare("score", CompareOp::Eq, IntersticeValue::U64(12)).matches(&schema, &ada));
+        assert!(compare("score", CompareOp::Ne, IntersticeValue::U64(12)).matches(&schema, &ada));
+    }
+
+    #[test]
+    fn filters_read_struct_fields() {
+        let mut schema = schema();
+        schema.fields.push(FieldDef {
+            name: "pos".into(),
+            field_type: IntersticeType::Named("Vec2".into()),
+            default: None,
+        });
+        let at = |x: f32, y: f32| {
+            let mut row = player(1, "ada", 12, 1.5);
+            row.entries.push(IntersticeValue::Struct {
+                name: "Vec2".into(),
+                fields: vec![
+                    crate::Field {
+                        name: "x".into(),
+                        value: IntersticeValue::F32(x),
+                    },
+                    crate::Field {
+                        name: "y".into(),
+                        value: IntersticeValue::F32(y),
+                    },
+                ],
+            });
+            row
+        };
+        let in_box = RowFilter::And(vec![
+            compare("pos.x", CompareOp::Gte, IntersticeValue::F32(0.0)),
+            compare("pos.x", CompareOp::Lte, IntersticeValue::F32(10.0)),
+            compare("pos.y", CompareOp::Gte, IntersticeValue::F32(0.0)),
+            compare("pos.y", CompareOp::Lte, IntersticeValue::F32(10.0)),
+        ]);
+        assert!(in_box.validate(&schema).is_ok());
+        assert!(in_box.matches(&schema, &at(5.0, 10.0)));
+        assert!(!in_box.matches(&schema, &at(5.0, 11.0)));
+        assert!(!compare("pos.z", CompareOp::Eq, IntersticeValue::F32(0.0)).matches(&schema, &at(0.0, 0.0)));
+    }
+
+    #[test]
+    fn unknown_fields_are_rejected() {
+        let schema = schema();
+        let filter = RowFilter::And(vec![
+            compare("score", CompareOp::Gt, IntersticeValue::U32(10)),
+            compare("level", CompareOp::Eq, IntersticeValue::U32(1)),
+        ]);
+        assert!(filter.validate(&schema).is_err());
+        assert!(compare("id", CompareOp::Eq, IntersticeValue::U64(1)).validate(&schema).is_ok());
+    }
+
+    #[test]
+    fn byte_filters_match_byte_vectors_written_before_bytes() {
+        let mut schema = schema();
+        schema.fields.push(FieldDef {
+            name: "avatar".into(),
+            field_type: IntersticeType::Bytes,
+            default: None,
+        });
+        let mut row = player(1, "ada", 12, 1.5);
+        row.entries
+            .push(IntersticeValue::Vec(vec![IntersticeValue::U8(7), IntersticeValue::U8(8)]));
+        let avatar = compare("avatar", CompareOp::Eq, IntersticeValue::Bytes(vec![7, 8]));
+        assert!(avatar.matches(&schema, &row));
+    }
+}
@@ -0,0 +1,58 @@
+use serde::{Deserialize, Serialize};
+
+use crate::Row;
+
+/// Time travel host calls. They act on every table of the calling module (replicas
+/// excepted). Checkpoints, rewinds and branches are changed from reducers and take
+/// effect when the reducer commits, in order with its table writes; the other
+/// calls may also be made from queries.
+#[derive(Debug, Serialize, Deserialize)]
+pub enum TimeTravelCall {
+    /// Save the rows of the module's tables under `name`, replacing a checkpoint of
+    /// the same name.
+    Checkpoint { name: String },
+    /// Put the rows saved by a checkpoint back. The checkpoint is kept.
+    Rewind { checkpoint: String },
+    DeleteCheckpoint { name: String },
+    ListCheckpoints,
+    /// Start a branch from the current rows. Branches nest: the new branch becomes
+    /// the current one until it is discarded or promoted.
+    Fork { branch: String },
+    /// Drop the current branch, putting back the rows it was forked from along
+    /// with its checkpoints.
+    Discard,
+    /// Keep the changes of the current branch in its parent branch.
+    Promote,
+    /// Branches from the root (`main`) to the current one.
+    Branches,
+    /// Rows of a logged table as they were after the log entry `sequence`.
+    TableAsOf { table_name: String, sequence: u64 },
+    /// Sequence number of the last log entry of a logged table.
+    TableSequence { table_name: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CheckpointInfo {
+    pub name: String,
+    /// Branch the checkpoint was created on.
+    pub branch: String,
+    pub created_unix_ms: u64,
+    /// Log sequence number of each logged table when the checkpoint was created,
+    /// for `TableAsOf`.
+    pub sequences: Vec<(String, u64)>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum TimeTravelResponse {
+    Ok,
+    Checkpoints(Vec<CheckpointInfo>),
+    Branches(Vec<String>),
+    /// Rows of `TableAsOf`, with the branches from `main` the table was on at
+    /// that point.
+    Rows {
+        rows: Vec<Row>,
+        branches: Vec<String>,
+    },
+    Sequence(Option<u64>),
+    Err(String),
+}
@@ -1,3 +1,5 @@
+mod typed;
+
 use crate::error::IntersticeAbiError;
 use serde::{Deserialize, Serialize};
 use std::{
@@ -5,6 +7,8 @@ use std::{
     str::FromStr,
 };
 
+pub use typed::IntersticeTyped;
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub enum IntersticeType {
     Void,
@@ -21,6 +25,45 @@ pub enum IntersticeType {
     Option(Box<IntersticeType>),
     Tuple(Vec<IntersticeType>),
     Named(String), // reference to user-defined struct/enum
+
+    U16,
+    I8,
+    I16,
+    U128,
+    I128,
+    Char,
+    /// `Vec<u8>`, carried as [`IntersticeValue::Bytes`](crate::IntersticeValue::Bytes).
+    Bytes,
+    /// `HashMap<K, V>` or `BTreeMap<K, V>`.
+    Map(Box<IntersticeType>, Box<IntersticeType>),
+}
+
+impl IntersticeType {
+    /// Name of the instance of the generic type `name` with the type `arguments`,
+    /// e.g. `Page<Player>`. Generic interstice types are defined once per instance,
+    /// under this name.
+    pub fn generic_name(name: &str, arguments: &[IntersticeType]) -> String {
+        let arguments: Vec<String> = arguments.iter().map(|ty| ty.to_string()).collect();
+        format!("{}<{}>", name, arguments.join(", "))
+    }
+
+    /// Whether values of both types are encoded the same way. `Vec<u8>` and `Bytes`
+    /// are the same type: schemas written before `Bytes` existed carry the former.
+    pub fn same_layout(&self, other: &IntersticeType) -> bool {
+        use IntersticeType::*;
+        match (self, other) {
+            (Bytes, Vec(inner)) | (Vec(inner), Bytes) => matches!(**inner, U8),
+            (Vec(a), Vec(b)) | (Option(a), Option(b)) => a.same_layout(b),
+            (Map(key_a, value_a), Map(key_b, value_b)) => {
+                key_a.same_layout(key_b) && value_a.same_layout(value_b)
+            }
+            (Tuple(a), Tuple(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.same_layout(b))
+            }
+            (Named(a), Named(b)) => a == b,
+            _ => std::mem::discriminant(self) == std::mem::discriminant(other),
+        }
+    }
 }
 
 impl Display for IntersticeType {
@@ -52,6 +95,18 @@ impl Display for IntersticeType {
             }
 
             IntersticeType::Named(s) => write!(f, "{s}"),
+
+            IntersticeType::U16 => write!(f, "u16"),
+            IntersticeType::I8 => write!(f, "i8"),
+            IntersticeType::I16 => write!(f, "i16"),
+            IntersticeType::U128 => write!(f, "u128"),
+            IntersticeType::I128 => write!(f, "i128"),
+            IntersticeType::Char => write!(f, "char"),
+            // Written as Rust types, so that generated bindings can use them as is.
+            IntersticeType::Bytes => write!(f, "Vec<u8>"),
+            IntersticeType::Map(key, value) => {
+                write!(f, "std::collections::HashMap<{key}, {value}>")
+            }
         }
     }
 }
@@ -142,6 +197,19 @@ impl<'a> Parser<'a> {
         Ok(ident)
     }
 
+    /// Last segment of a path like `std::collections::HashMap`.
+    fn parse_path(&mut self) -> Result<String, IntersticeAbiError> {
+        let mut ident = self.parse_ident()?;
+        loop {
+            self.consume_ws();
+            if !self.rest().starts_with("::") {
+                return Ok(ident);
+            }
+            self.pos += 2;
+            ident = self.parse_ident()?;
+        }
+    }
+
     fn parse_type(&mut self) -> Result<IntersticeType, IntersticeAbiError> {
         self.consume_ws();
 
@@ -179,37 +247,58 @@ impl<'a> Parser<'a> {
         }
 
         // ---- Identifier ----
-        let ident = self.parse_ident()?;
+        let ident = self.parse_path()?;
 
         // ---- Primitives ----
         let primitive = match ident.as_str() {
             "u8" => Some(IntersticeType::U8),
+            "u16" => Some(IntersticeType::U16),
             "u32" => Some(IntersticeType::U32),
             "u64" => Some(IntersticeType::U64),
+            "u128" => Some(IntersticeType::U128),
+            "i8" => Some(IntersticeType::I8),
+            "i16" => Some(IntersticeType::I16),
             "i32" => Some(IntersticeType::I32),
             "i64" => Some(IntersticeType::I64),
+            "i128" => Some(IntersticeType::I128),
             "f32" => Some(IntersticeType::F32),
             "f64" => Some(IntersticeType::F64),
             "bool" => Some(IntersticeType::Bool),
+            "char" => Some(IntersticeType::Char),
             "String" => Some(IntersticeType::String),
-            "Vec" | "Option" => None,
+            "Vec" | "Option" | "HashMap" | "BTreeMap" => None,
             _ => None,
         };
 
-        // ---- Generics like Vec<T> / Option<T> ----
+        // ---- Generics like Vec<T> / Option<T> / HashMap<K, V> / Page<T> ----
         self.consume_ws();
         if self.peek() == Some('<') {
             self.bump(); // '<'
-            let inner = self.parse_type()?;
+            let mut arguments = vec![self.parse_type()?];
             self.consume_ws();
+            while self.peek() == Some(',') {
+                self.bump();
+                arguments.push(self.parse_type()?);
+                self.consume_ws();
+            }
             self.eat('>')?;
 
-            return match ident.as_str() {
-                "Vec" => Ok(IntersticeType::Vec(Box::new(inner))),
-                "Option" => Ok(IntersticeType::Option(Box::new(inner))),
-                _ => Err(IntersticeAbiError::ConversionError(format!(
-                    "Unknown generic type '{}'",
-                    ident
+            return match (ident.as_str(), arguments.as_slice()) {
+                ("Vec", [IntersticeType::U8]) => Ok(IntersticeType::Bytes),
+                ("Vec", [inner]) => Ok(IntersticeType::Vec(Box::new(inner.clone()))),
+                ("Option", [inner]) => Ok(IntersticeType::Option(Box::new(inner.clone()))),
+                ("HashMap" | "BTreeMap", [key, value]) => Ok(IntersticeType::Map(
+                    Box::new(key.clone()),
+                    Box::new(value.clone()),
+                )),
+                ("Vec" | "Option" | "HashMap" | "BTreeMap", _) => {
+                    Err(IntersticeAbiError::ConversionError(format!(
+                        "Wrong number of type arguments for '{}'",
+                        ident
+                    )))
+                }
+                _ => Ok(IntersticeType::Named(IntersticeType::generic_name(
+                    &ident, &arguments,
                 ))),
             };
         }
@@ -222,3 +311,82 @@ impl<'a> Parser<'a> {
         Ok(IntersticeType::Named(ident))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> IntersticeType {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn types_parse_from_rust_syntax() {
+        assert!(matches!(parse("Vec < u8 >"), IntersticeType::Bytes));
+        assert!(matches!(parse("Vec<u16>"), IntersticeType::Vec(inner) if matches!(*inner, IntersticeType::U16)));
+        assert!(matches!(parse("char"), IntersticeType::Char));
+        assert!(matches!(parse("i128"), IntersticeType::I128));
+        assert!(matches!(
+            parse("std :: collections :: BTreeMap < String , Vec<u8> >"),
+            IntersticeType::Map(key, value)
+                if matches!(*key, IntersticeType::String) && matches!(*value, IntersticeType::Bytes)
+        ));
+        assert!("HashMap<String>".parse::<IntersticeType>().is_err());
+        assert!("Option<u8, u8>".parse::<IntersticeType>().is_err());
+    }
+
+    #[test]
+    fn byte_vectors_have_the_layout_of_bytes() {
+        let legacy = IntersticeType::Vec(Box::new(IntersticeType::U8));
+        assert!(legacy.same_layout(&IntersticeType::Bytes));
+        assert!(IntersticeType::Bytes.same_layout(&legacy));
+        assert!(parse("HashMap<String, Option<Vec<u8>>>").same_layout(&IntersticeType::Map(
+            Box::new(IntersticeType::String),
+            Box::new(IntersticeType::Option(Box::new(legacy.clone()))),
+        )));
+        assert!(!parse("Vec<u16>").same_layout(&IntersticeType::Bytes));
+        assert!(!parse("Vec<u8>").same_layout(&parse("Option<u8>")));
+        assert!(!parse("(u8, u8)").same_layout(&parse("(u8, u16)")));
+        assert!(!parse("Player").same_layout(&parse("Team")));
+    }
+
+    #[test]
+    fn generic_types_are_named_after_their_instance() {
+        let ty = parse("Page < Vec < u8 > , Pair<crate::Player, u32> >");
+        assert_eq!(ty.to_string(), "Page<Vec<u8>, Pair<Player, u32>>");
+        assert_eq!(
+            ty.to_string(),
+            IntersticeType::generic_name(
+                "Page",
+                &[IntersticeType::Bytes, parse("Pair<Player, u32>")]
+            )
+        );
+    }
+
+    #[test]
+    fn display_parses_back() {
+        for ty in [
+            "Vec<u8>",
+            "std::collections::HashMap<(u16, i8), Option<char>>",
+            "Vec<Vec<u128>>",
+            "Player",
+        ] {
+            assert_eq!(parse(ty).to_string(), ty);
+            assert_eq!(parse(&parse(ty).to_string()).to_string(), ty);
+        }
+    }
+
+    #[test]
+    fn types_round_trip_through_toml() {
+        #[derive(Serialize, Deserialize)]
+        struct Field {
+            field_type: IntersticeType,
+        }
+        let field = Field {
+            field_type: parse("HashMap<String, Vec<u8>>"),
+        };
+        let toml = toml::to_string(&field).unwrap();
+        let field: Field = toml::from_str(&toml).unwrap();
+        assert_eq!(field.field_type.to_string(), "std::collections::HashMap<String, Vec<u8>>");
+    }
+}
@@ -0,0 +1,246 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{IntersticeType, IntersticeTypeDef};
+
+/// Rust types with an [`IntersticeType`]. Implemented by `#[derive(IntersticeType)]`,
+/// which is how generic interstice types learn the names of their instances.
+pub trait IntersticeTyped {
+    fn interstice_type() -> IntersticeType;
+
+    /// Add the definitions of the named types this type is made of, keyed by name.
+    fn type_definitions(_definitions: &mut HashMap<String, IntersticeTypeDef>) {}
+}
+
+macro_rules! impl_interstice_typed {
+    ($variant:ident, $ty:ty) => {
+        impl IntersticeTyped for $ty {
+            fn interstice_type() -> IntersticeType {
+                IntersticeType::$variant
+            }
+        }
+    };
+}
+
+impl_interstice_typed!(Void, ());
+impl_interstice_typed!(U8, u8);
+impl_interstice_typed!(U16, u16);
+impl_interstice_typed!(U32, u32);
+impl_interstice_typed!(U64, u64);
+impl_interstice_typed!(U128, u128);
+impl_interstice_typed!(I8, i8);
+impl_interstice_typed!(I16, i16);
+impl_interstice_typed!(I32, i32);
+impl_interstice_typed!(I64, i64);
+impl_interstice_typed!(I128, i128);
+impl_interstice_typed!(F32, f32);
+impl_interstice_typed!(F64, f64);
+impl_interstice_typed!(Bool, bool);
+impl_interstice_typed!(Char, char);
+impl_interstice_typed!(String, String);
+
+impl<T: IntersticeTyped> IntersticeTyped for Vec<T> {
+    fn interstice_type() -> IntersticeType {
+        match T::interstice_type() {
+            IntersticeType::U8 => IntersticeType::Bytes,
+            inner => IntersticeType::Vec(Box::new(inner)),
+        }
+    }
+
+    fn type_definitions(definitions: &mut HashMap<String, IntersticeTypeDef>) {
+        T::type_definitions(definitions);
+    }
+}
+
+impl<T: IntersticeTyped> IntersticeTyped for Option<T> {
+    fn interstice_type() -> IntersticeType {
+        IntersticeType::Option(Box::new(T::interstice_type()))
+    }
+
+    fn type_definitions(definitions: &mut HashMap<String, IntersticeTypeDef>) {
+        T::type_definitions(definitions);
+    }
+}
+
+macro_rules! impl_interstice_typed_map {
+    ($map:ident) => {
+        impl<K: IntersticeTyped, V: IntersticeTyped> IntersticeTyped for $map<K, V> {
+            fn interstice_type() -> IntersticeType {
+                IntersticeType::Map(Box::new(K::interstice_type()), Box::new(V::interstice_type()))
+            }
+
+            fn type_definitions(definitions: &mut HashMap<String, IntersticeTypeDef>) {
+                K::type_definitions(definitions);
+                V::type_definitions(definitions);
+            }
+        }
+    };
+}
+
+impl_interstice_typed_map!(HashMap);
+impl_interstice_typed_map!(BTreeMap);
+
+macro_rules! impl_interstice_typed_tuple {
+    ( $( $name:ident ),+ ) => {
+        impl<$( $name: IntersticeTyped ),+> IntersticeTyped for ( $( $name ),+ ) {
+            fn interstice_type() -> IntersticeType {
+                IntersticeType::Tuple(vec![$( $name::interstice_type() ),+])
+            }
+
+            fn type_definitions(definitions: &mut HashMap<String, IntersticeTypeDef>) {
+                $( $name::type_definitions(definitions); )+
+            }
+        }
+    };
+}
+
+impl_interstice_typed_tuple!(A, B);
+impl_interstice_typed_tuple!(A, B, C);
+impl_interstice_typed_tuple!(A, B, C, D);
+impl_interstice_typed_tuple!(A, B, C, D, E);
+impl_interstice_typed_tuple!(A, B, C, D, E, F);
+impl_interstice_typed_tuple!(A, B, C, D, E, F, G);
+impl_interstice_typed_tuple!(A, B, C, D, E, F, G, H);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interstice_abi_macros::IntersticeType;
+    use crate::{
+        Field, FieldDef, IntersticeValue, ModuleSchema, ReducerSchema, validate_value_detailed,
+    };
+
+    #[derive(IntersticeType, Clone, Debug, PartialEq)]
+    struct PlayerId(u64);
+
+    #[derive(IntersticeType, Clone, Debug, PartialEq)]
+    struct Page<T> {
+        items: Vec<T>,
+        next: Option<PlayerId>,
+    }
+
+    #[derive(IntersticeType, Clone, Debug, PartialEq)]
+    enum Shape {
+        Point,
+        Circle { radius: f32 },
+        Rect(f32, f32),
+    }
+
+    #[derive(IntersticeType, Clone, Debug, PartialEq)]
+    #[interstice(rename = "Hero")]
+    struct Player {
+        #[interstice(rename = "hp")]
+        health: u32,
+        #[interstice(default)]
+        level: u8,
+    }
+
+    /// `value` converted to an `IntersticeValue` that validates against its type,
+    /// and back.
+    fn round_trip<T>(value: T) -> T
+    where
+        T: Into<IntersticeValue> + TryFrom<IntersticeValue, Error = crate::IntersticeAbiError>,
+        T: IntersticeTyped,
+    {
+        let mut definitions = HashMap::new();
+        T::type_definitions(&mut definitions);
+        let value: IntersticeValue = value.into();
+        validate_value_detailed(&value, &T::interstice_type(), &definitions).unwrap();
+        T::try_from(value).unwrap()
+    }
+
+    #[test]
+    fn generic_types_are_defined_per_instance() {
+        let page = Page {
+            items: vec![Shape::Circle { radius: 2.0 }, Shape::Rect(1.0, 3.0), Shape::Point],
+            next: Some(PlayerId(7)),
+        };
+        assert_eq!(round_trip(page.clone()), page);
+        assert_eq!(Page::<Shape>::interstice_type().to_string(), "Page<Shape>");
+        assert_eq!(Page::<u32>::interstice_type().to_string(), "Page<u32>");
+
+        let mut definitions = HashMap::new();
+        Page::<Shape>::type_definitions(&mut definitions);
+        let mut names: Vec<&String> = definitions.keys().collect();
+        names.sort();
+        assert_eq!(names, ["Page<Shape>", "PlayerId", "Shape", "Shape::Circle"]);
+        match &definitions["PlayerId"] {
+            IntersticeTypeDef::Struct { fields, .. } => {
+                assert_eq!(fields[0].name, "0");
+                assert_eq!(fields[0].field_type.to_string(), "u64");
+            }
+            other => panic!("unexpected definition {:?}", other),
+        }
+    }
+
+    #[test]
+    fn renamed_and_default_fields() {
+        let player = Player { health: 10, level: 3 };
+        assert_eq!(round_trip(player.clone()), player);
+        assert_eq!(Player::interstice_type().to_string(), "Hero");
+
+        let stored = IntersticeValue::Struct {
+            name: "Hero".into(),
+            fields: vec![Field {
+                name: "hp".into(),
+                value: IntersticeValue::U32(4),
+            }],
+        };
+        assert_eq!(Player::try_from(stored).unwrap(), Player { health: 4, level: 0 });
+        let missing = IntersticeValue::Struct {
+            name: "Hero".into(),
+            fields: Vec::new(),
+        };
+        assert!(Player::try_from(missing).is_err());
+
+        let mut definitions = HashMap::new();
+        Player::type_definitions(&mut definitions);
+        match &definitions["Hero"] {
+            IntersticeTypeDef::Struct { fields, .. } => {
+                assert_eq!(fields[0].default, None);
+                assert_eq!(fields[1].default, Some(IntersticeValue::U8(0)));
+            }
+            other => panic!("unexpected definition {:?}", other),
+        }
+    }
+
+    #[test]
+    fn types_gaining_defaulted_fields_stay_compatible() {
+        let module = |hero_fields: Vec<FieldDef>| {
+            let mut schema = ModuleSchema::empty();
+            schema.reducers.push(ReducerSchema::new(
+                "greet",
+                vec![FieldDef {
+                    name: "hero".into(),
+                    field_type: Player::interstice_type(),
+                    default: None,
+                }],
+                Vec::new(),
+                Vec::new(),
+                Vec::new(),
+                Vec::new(),
+            ));
+            schema.type_definitions.insert(
+                "Hero".into(),
+                IntersticeTypeDef::Struct {
+                    name: "Hero".into(),
+                    fields: hero_fields,
+                },
+            );
+            schema
+        };
+        let mut definitions = HashMap::new();
+        Player::type_definitions(&mut definitions);
+        let IntersticeTypeDef::Struct { fields, .. } = definitions.remove("Hero").unwrap() else {
+            panic!("Hero is a struct");
+        };
+        let bound = module(fields[..1].to_vec());
+
+        assert!(module(fields.clone()).check_compatible(&bound).is_ok());
+        let mut required = fields;
+        required[1].default = None;
+        assert_eq!(
+            module(required).check_compatible(&bound),
+            Err("type 'Hero' changed or was removed".to_string())
+        );
+    }
+}
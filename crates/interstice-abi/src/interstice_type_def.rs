@@ -1,4 +1,4 @@
-use crate::IntersticeType;
+use crate::{IntersticeType, IntersticeValue};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -26,4 +26,8 @@ impl IntersticeTypeDef {
 pub struct FieldDef {
     pub name: String,
     pub field_type: IntersticeType,
+    /// Value of the field when a value or a stored row lacks it, declared with
+    /// `#[interstice(default)]`.
+    #[serde(default)]
+    pub default: Option<IntersticeValue>,
 }
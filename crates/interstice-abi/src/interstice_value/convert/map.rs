@@ -0,0 +1,78 @@
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+
+use crate::{IntersticeAbiError, IntersticeValue};
+
+impl<K, V> From<HashMap<K, V>> for IntersticeValue
+where
+    K: Into<IntersticeValue>,
+    V: Into<IntersticeValue>,
+{
+    fn from(map: HashMap<K, V>) -> Self {
+        IntersticeValue::Map(map.into_iter().map(|(k, v)| (k.into(), v.into())).collect())
+    }
+}
+
+impl<K, V> From<BTreeMap<K, V>> for IntersticeValue
+where
+    K: Into<IntersticeValue>,
+    V: Into<IntersticeValue>,
+{
+    fn from(map: BTreeMap<K, V>) -> Self {
+        IntersticeValue::Map(map.into_iter().map(|(k, v)| (k.into(), v.into())).collect())
+    }
+}
+
+/// Entries of the `Map` value `value`, converted.
+fn map_entries<K, V>(
+    value: IntersticeValue,
+) -> Result<impl Iterator<Item = Result<(K, V), IntersticeAbiError>>, IntersticeAbiError>
+where
+    K: TryFrom<IntersticeValue>,
+    K::Error: std::fmt::Display,
+    V: TryFrom<IntersticeValue>,
+    V::Error: std::fmt::Display,
+{
+    let IntersticeValue::Map(entries) = value else {
+        return Err(IntersticeAbiError::ConversionError(
+            "Expected IntersticeValue::Map".into(),
+        ));
+    };
+    Ok(entries.into_iter().map(|(k, v)| {
+        let key = K::try_from(k).map_err(|e| {
+            IntersticeAbiError::ConversionError(format!("Map key conversion failed: {}", e))
+        })?;
+        let value = V::try_from(v).map_err(|e| {
+            IntersticeAbiError::ConversionError(format!("Map value conversion failed: {}", e))
+        })?;
+        Ok((key, value))
+    }))
+}
+
+impl<K, V> TryFrom<IntersticeValue> for HashMap<K, V>
+where
+    K: TryFrom<IntersticeValue> + Eq + Hash,
+    K::Error: std::fmt::Display,
+    V: TryFrom<IntersticeValue>,
+    V::Error: std::fmt::Display,
+{
+    type Error = IntersticeAbiError;
+
+    fn try_from(value: IntersticeValue) -> Result<Self, Self::Error> {
+        map_entries(value)?.collect()
+    }
+}
+
+impl<K, V> TryFrom<IntersticeValue> for BTreeMap<K, V>
+where
+    K: TryFrom<IntersticeValue> + Ord,
+    K::Error: std::fmt::Display,
+    V: TryFrom<IntersticeValue>,
+    V::Error: std::fmt::Display,
+{
+    type Error = IntersticeAbiError;
+
+    fn try_from(value: IntersticeValue) -> Result<Self, Self::Error> {
+        map_entries(value)?.collect()
+    }
+}
@@ -1,3 +1,4 @@
+pub mod map;
 pub mod option;
 pub mod primitives;
 pub mod tuple;
@@ -66,12 +66,18 @@ macro_rules! impl_to_interstice_value {
 }
 
 impl_to_interstice_value!(U8, u8);
+impl_to_interstice_value!(U16, u16);
 impl_to_interstice_value!(U32, u32);
 impl_to_interstice_value!(U64, u64);
+impl_to_interstice_value!(U128, u128);
+impl_to_interstice_value!(I8, i8);
+impl_to_interstice_value!(I16, i16);
 impl_to_interstice_value!(I32, i32);
 impl_to_interstice_value!(I64, i64);
+impl_to_interstice_value!(I128, i128);
 impl_to_interstice_value!(F32, f32);
 impl_to_interstice_value!(F64, f64);
+impl_to_interstice_value!(Char, char);
 
 macro_rules! impl_tryfrom_numeric {
     ($variant:ident, $ty:ty) => {
@@ -92,9 +98,15 @@ macro_rules! impl_tryfrom_numeric {
 }
 
 impl_tryfrom_numeric!(U8, u8);
+impl_tryfrom_numeric!(U16, u16);
 impl_tryfrom_numeric!(U32, u32);
 impl_tryfrom_numeric!(U64, u64);
+impl_tryfrom_numeric!(U128, u128);
+impl_tryfrom_numeric!(I8, i8);
+impl_tryfrom_numeric!(I16, i16);
 impl_tryfrom_numeric!(I32, i32);
 impl_tryfrom_numeric!(I64, i64);
+impl_tryfrom_numeric!(I128, i128);
 impl_tryfrom_numeric!(F32, f32);
 impl_tryfrom_numeric!(F64, f64);
+impl_tryfrom_numeric!(Char, char);
@@ -1,37 +1,58 @@
+use std::any::Any;
+
 use crate::{IntersticeAbiError, IntersticeValue};
 
+/// `value` as a `B` when `A` and `B` are the same type, so that `Vec<u8>` can be
+/// carried as [`IntersticeValue::Bytes`] instead of one value per byte.
+fn same_type<A: 'static, B: 'static>(value: A) -> Result<B, A> {
+    let mut value = Some(value);
+    if let Some(same) = (&mut value as &mut dyn Any).downcast_mut::<Option<B>>() {
+        return Ok(same.take().expect("value is set"));
+    }
+    Err(value.expect("value is set"))
+}
+
 impl<T> Into<IntersticeValue> for Vec<T>
 where
-    T: Into<IntersticeValue>,
+    T: Into<IntersticeValue> + 'static,
 {
     fn into(self) -> IntersticeValue {
-        let values = self.into_iter().map(|x| x.into()).collect();
-        IntersticeValue::Vec(values)
+        match same_type::<_, Vec<u8>>(self) {
+            Ok(bytes) => IntersticeValue::Bytes(bytes),
+            Err(values) => IntersticeValue::Vec(values.into_iter().map(|x| x.into()).collect()),
+        }
     }
 }
 impl<T> TryFrom<IntersticeValue> for Vec<T>
 where
-    T: TryFrom<IntersticeValue>,
+    T: TryFrom<IntersticeValue> + 'static,
     T::Error: std::fmt::Display,
 {
     type Error = IntersticeAbiError;
 
     fn try_from(value: IntersticeValue) -> Result<Self, Self::Error> {
-        match value {
-            IntersticeValue::Vec(v) => v
-                .into_iter()
-                .map(|x| {
-                    T::try_from(x).map_err(|e| {
-                        IntersticeAbiError::ConversionError(format!(
-                            "Vec element conversion failed: {}",
-                            e
-                        ))
-                    })
+        let values = match value {
+            IntersticeValue::Vec(v) => v,
+            IntersticeValue::Bytes(bytes) => match same_type::<_, Vec<T>>(bytes) {
+                Ok(values) => return Ok(values),
+                Err(bytes) => bytes.into_iter().map(IntersticeValue::U8).collect(),
+            },
+            _ => {
+                return Err(IntersticeAbiError::ConversionError(
+                    "Expected IntersticeValue::Vec".into(),
+                ));
+            }
+        };
+        values
+            .into_iter()
+            .map(|x| {
+                T::try_from(x).map_err(|e| {
+                    IntersticeAbiError::ConversionError(format!(
+                        "Vec element conversion failed: {}",
+                        e
+                    ))
                 })
-                .collect(),
-            _ => Err(IntersticeAbiError::ConversionError(
-                "Expected IntersticeValue::Vec".into(),
-            )),
-        }
+            })
+            .collect()
     }
 }
@@ -13,6 +13,13 @@ pub enum IndexKey {
     String(String),
     Option(Option<Box<IndexKey>>),
     Tuple(Vec<IndexKey>),
+    U16(u16),
+    I8(i8),
+    I16(i16),
+    U128(u128),
+    I128(i128),
+    Char(char),
+    Bytes(Vec<u8>),
 }
 
 impl PartialOrd for IndexKey {
@@ -34,6 +41,13 @@ impl Ord for IndexKey {
             String(_) => 6,
             Option(_) => 7,
             Tuple(_) => 8,
+            U16(_) => 9,
+            I8(_) => 10,
+            I16(_) => 11,
+            U128(_) => 12,
+            I128(_) => 13,
+            Char(_) => 14,
+            Bytes(_) => 15,
         };
 
         let self_rank = rank(self);
@@ -50,6 +64,13 @@ impl Ord for IndexKey {
             (I64(a), I64(b)) => a.cmp(b),
             (Bool(a), Bool(b)) => a.cmp(b),
             (String(a), String(b)) => a.cmp(b),
+            (U16(a), U16(b)) => a.cmp(b),
+            (I8(a), I8(b)) => a.cmp(b),
+            (I16(a), I16(b)) => a.cmp(b),
+            (U128(a), U128(b)) => a.cmp(b),
+            (I128(a), I128(b)) => a.cmp(b),
+            (Char(a), Char(b)) => a.cmp(b),
+            (Bytes(a), Bytes(b)) => a.cmp(b),
             (Option(a), Option(b)) => match (a, b) {
                 (None, None) => std::cmp::Ordering::Equal,
                 (None, Some(_)) => std::cmp::Ordering::Less,
@@ -82,6 +103,13 @@ impl TryFrom<&IntersticeValue> for IndexKey {
             IntersticeValue::I64(v) => Ok(IndexKey::I64(*v)),
             IntersticeValue::Bool(v) => Ok(IndexKey::Bool(*v)),
             IntersticeValue::String(v) => Ok(IndexKey::String(v.clone())),
+            IntersticeValue::U16(v) => Ok(IndexKey::U16(*v)),
+            IntersticeValue::I8(v) => Ok(IndexKey::I8(*v)),
+            IntersticeValue::I16(v) => Ok(IndexKey::I16(*v)),
+            IntersticeValue::U128(v) => Ok(IndexKey::U128(*v)),
+            IntersticeValue::I128(v) => Ok(IndexKey::I128(*v)),
+            IntersticeValue::Char(v) => Ok(IndexKey::Char(*v)),
+            IntersticeValue::Bytes(v) => Ok(IndexKey::Bytes(v.clone())),
             IntersticeValue::Option(v) => match v {
                 Some(inner) => Ok(IndexKey::Option(Some(Box::new(IndexKey::try_from(
                     inner.as_ref(),
@@ -99,10 +127,24 @@ impl TryFrom<&IntersticeValue> for IndexKey {
             IntersticeValue::F32(_) | IntersticeValue::F64(_) => {
                 Err("Float values cannot be used as index keys".to_string())
             }
-            IntersticeValue::Vec(_)
+            // Byte vectors written before `Bytes` existed key like their `Bytes` form.
+            IntersticeValue::Vec(items) => items
+                .iter()
+                .map(|item| match item {
+                    IntersticeValue::U8(byte) => Some(*byte),
+                    _ => None,
+                })
+                .collect::<Option<Vec<u8>>>()
+                .map(IndexKey::Bytes)
+                .ok_or_else(|| {
+                    "Only primitive, bytes, Option, and Tuple values are supported as index keys"
+                        .to_string()
+                }),
+            IntersticeValue::Map(_)
             | IntersticeValue::Struct { .. }
             | IntersticeValue::Enum { .. } => Err(
-                "Only primitive, Option, and Tuple values are supported as index keys".to_string(),
+                "Only primitive, bytes, Option, and Tuple values are supported as index keys"
+                    .to_string(),
             ),
         }
     }
@@ -122,6 +164,7 @@ impl TryFrom<IntersticeValue> for IndexKey {
             IntersticeValue::Bool(v) => Ok(IndexKey::Bool(v)),
             // Move the String out instead of calling the &ref path which would clone it.
             IntersticeValue::String(s) => Ok(IndexKey::String(s)),
+            IntersticeValue::Bytes(bytes) => Ok(IndexKey::Bytes(bytes)),
             other => IndexKey::try_from(&other),
         }
     }
@@ -137,6 +180,13 @@ impl From<IndexKey> for IntersticeValue {
             IndexKey::I64(v) => IntersticeValue::I64(v),
             IndexKey::Bool(v) => IntersticeValue::Bool(v),
             IndexKey::String(v) => IntersticeValue::String(v),
+            IndexKey::U16(v) => IntersticeValue::U16(v),
+            IndexKey::I8(v) => IntersticeValue::I8(v),
+            IndexKey::I16(v) => IntersticeValue::I16(v),
+            IndexKey::U128(v) => IntersticeValue::U128(v),
+            IndexKey::I128(v) => IntersticeValue::I128(v),
+            IndexKey::Char(v) => IntersticeValue::Char(v),
+            IndexKey::Bytes(v) => IntersticeValue::Bytes(v),
             IndexKey::Option(v) => IntersticeValue::Option(v.map(|inner| {
                 let converted: IntersticeValue = (*inner).into();
                 Box::new(converted)
@@ -37,6 +37,16 @@ pub enum IntersticeValue {
         variant: String,
         value: Box<IntersticeValue>,
     },
+
+    U16(u16),
+    I8(i8),
+    I16(i16),
+    U128(u128),
+    I128(i128),
+    Char(char),
+    Bytes(Vec<u8>),
+    /// Entries of a map, as key-value pairs.
+    Map(Vec<(IntersticeValue, IntersticeValue)>),
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
@@ -45,6 +55,9 @@ pub struct Field {
     pub value: IntersticeValue,
 }
 
+/// Leading bytes of a `Bytes` value shown when displaying it.
+const DISPLAYED_BYTES: usize = 32;
+
 impl Display for IntersticeValue {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         self.fmt_with_indent(f, 0)
@@ -115,6 +128,36 @@ impl IntersticeValue {
                 value.fmt_with_indent(f, indent)?;
                 write!(f, ")")
             }
+            IntersticeValue::U16(value) => write!(f, "{value}"),
+            IntersticeValue::I8(value) => write!(f, "{value}"),
+            IntersticeValue::I16(value) => write!(f, "{value}"),
+            IntersticeValue::U128(value) => write!(f, "{value}"),
+            IntersticeValue::I128(value) => write!(f, "{value}"),
+            IntersticeValue::Char(value) => write!(f, "{value:?}"),
+            IntersticeValue::Bytes(bytes) => {
+                write!(f, "<{} bytes", bytes.len())?;
+                if !bytes.is_empty() {
+                    write!(f, ": ")?;
+                    for byte in bytes.iter().take(DISPLAYED_BYTES) {
+                        write!(f, "{byte:02x}")?;
+                    }
+                    if bytes.len() > DISPLAYED_BYTES {
+                        write!(f, "..")?;
+                    }
+                }
+                write!(f, ">")
+            }
+            IntersticeValue::Map(entries) => {
+                writeln!(f, "{{")?;
+                for (key, value) in entries {
+                    write!(f, "{next_indent_str}")?;
+                    key.fmt_with_indent(f, indent + 1)?;
+                    write!(f, ": ")?;
+                    value.fmt_with_indent(f, indent + 1)?;
+                    writeln!(f, ",")?;
+                }
+                write!(f, "{indent_str}}}")
+            }
         }
     }
 }
@@ -1,6 +1,6 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use crate::{IntersticeType, IntersticeTypeDef, IntersticeValue};
+use crate::{IndexKey, IntersticeType, IntersticeTypeDef, IntersticeValue};
 
 pub fn validate_value(
     value: &IntersticeValue,
@@ -46,15 +46,67 @@ pub fn validate_value_detailed(
         IntersticeValue::String(_) => {
             if let IntersticeType::String = ty { Ok(()) } else { Err(format!("expected String, got {:?}", ty)) }
         }
+        IntersticeValue::U16(_) => {
+            if let IntersticeType::U16 = ty { Ok(()) } else { Err(format!("expected U16, got {:?}", ty)) }
+        }
+        IntersticeValue::I8(_) => {
+            if let IntersticeType::I8 = ty { Ok(()) } else { Err(format!("expected I8, got {:?}", ty)) }
+        }
+        IntersticeValue::I16(_) => {
+            if let IntersticeType::I16 = ty { Ok(()) } else { Err(format!("expected I16, got {:?}", ty)) }
+        }
+        IntersticeValue::U128(_) => {
+            if let IntersticeType::U128 = ty { Ok(()) } else { Err(format!("expected U128, got {:?}", ty)) }
+        }
+        IntersticeValue::I128(_) => {
+            if let IntersticeType::I128 = ty { Ok(()) } else { Err(format!("expected I128, got {:?}", ty)) }
+        }
+        IntersticeValue::Char(_) => {
+            if let IntersticeType::Char = ty { Ok(()) } else { Err(format!("expected Char, got {:?}", ty)) }
+        }
+        IntersticeValue::Bytes(_) => match ty {
+            IntersticeType::Bytes => Ok(()),
+            IntersticeType::Vec(inner) if matches!(**inner, IntersticeType::U8) => Ok(()),
+            _ => Err(format!("expected Bytes, got {:?}", ty)),
+        },
         IntersticeValue::Vec(v) => {
-            if let IntersticeType::Vec(inner) = ty {
-                for (i, x) in v.iter().enumerate() {
-                    validate_value_detailed(x, inner, type_definitions)
-                        .map_err(|e| format!("Vec[{}]: {}", i, e))?;
+            // Values written before `Bytes` existed carry byte vectors element by element.
+            let inner = match ty {
+                IntersticeType::Vec(inner) => inner.as_ref(),
+                IntersticeType::Bytes => &IntersticeType::U8,
+                _ => return Err(format!("expected Vec, got {:?}", ty)),
+            };
+            for (i, x) in v.iter().enumerate() {
+                validate_value_detailed(x, inner, type_definitions)
+                    .map_err(|e| format!("Vec[{}]: {}", i, e))?;
+            }
+            Ok(())
+        }
+        IntersticeValue::Map(entries) => {
+            if let IntersticeType::Map(key_ty, value_ty) = ty {
+                // Keys that can't be index keys (structs, enums) are compared one by one.
+                let mut keys = HashSet::new();
+                let mut other_keys = Vec::new();
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    validate_value_detailed(key, key_ty, type_definitions)
+                        .map_err(|e| format!("Map key [{}]: {}", i, e))?;
+                    let duplicate = match IndexKey::try_from(key) {
+                        Ok(index_key) => !keys.insert(index_key),
+                        Err(_) if other_keys.contains(&key) => true,
+                        Err(_) => {
+                            other_keys.push(key);
+                            false
+                        }
+                    };
+                    if duplicate {
+                        return Err(format!("Map key [{}]: duplicate key {}", i, key));
+                    }
+                    validate_value_detailed(value, value_ty, type_definitions)
+                        .map_err(|e| format!("Map[{}]: {}", key, e))?;
                 }
                 Ok(())
             } else {
-                Err(format!("expected Vec, got {:?}", ty))
+                Err(format!("expected Map, got {:?}", ty))
             }
         }
         IntersticeValue::Option(None) => {
@@ -150,3 +202,72 @@ pub fn validate_value_detailed(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn ty(s: &str) -> IntersticeType {
+        s.parse().unwrap()
+    }
+
+    fn validate(value: &IntersticeValue, s: &str) -> Result<(), String> {
+        validate_value_detailed(value, &ty(s), &HashMap::new())
+    }
+
+    #[test]
+    fn byte_vectors_are_carried_as_bytes() {
+        let value: IntersticeValue = vec![1u8, 2, 3].into();
+        assert_eq!(value, IntersticeValue::Bytes(vec![1, 2, 3]));
+        assert_eq!(Vec::<u8>::try_from(value.clone()).unwrap(), vec![1, 2, 3]);
+        assert!(validate(&value, "Vec<u8>").is_ok());
+        assert!(validate(&value, "Vec<u16>").is_err());
+
+        // Byte vectors written one value per byte still convert and validate.
+        let legacy = IntersticeValue::Vec(vec![IntersticeValue::U8(1), IntersticeValue::U8(2)]);
+        assert_eq!(Vec::<u8>::try_from(legacy.clone()).unwrap(), vec![1, 2]);
+        assert!(validate(&legacy, "Vec<u8>").is_ok());
+
+        let words: IntersticeValue = vec![1u16, 2].into();
+        assert_eq!(
+            words,
+            IntersticeValue::Vec(vec![IntersticeValue::U16(1), IntersticeValue::U16(2)])
+        );
+    }
+
+    #[test]
+    fn maps_convert_and_validate() {
+        let map = BTreeMap::from([('a', -1i8), ('b', 2)]);
+        let value: IntersticeValue = map.clone().into();
+        assert!(validate(&value, "BTreeMap<char, i8>").is_ok());
+        assert_eq!(
+            validate(&value, "HashMap<char, i16>"),
+            Err("Map['a']: expected I8, got I16".to_string())
+        );
+        assert_eq!(BTreeMap::<char, i8>::try_from(value.clone()).unwrap(), map);
+        assert_eq!(
+            HashMap::<char, i8>::try_from(value).unwrap(),
+            HashMap::from([('a', -1), ('b', 2)])
+        );
+        assert!(HashMap::<char, i8>::try_from(IntersticeValue::Void).is_err());
+
+        let duplicated = IntersticeValue::Map(vec![
+            ('a'.into(), 1i8.into()),
+            ('b'.into(), 2i8.into()),
+            ('a'.into(), 3i8.into()),
+        ]);
+        assert_eq!(
+            validate(&duplicated, "HashMap<char, i8>"),
+            Err("Map key [2]: duplicate key 'a'".to_string())
+        );
+    }
+
+    #[test]
+    fn wide_integers_validate() {
+        assert!(validate(&u128::MAX.into(), "u128").is_ok());
+        assert!(validate(&i128::MIN.into(), "i128").is_ok());
+        assert!(validate(&7u16.into(), "u32").is_err());
+        assert!(validate(&(-7i16).into(), "i8").is_err());
+    }
+}
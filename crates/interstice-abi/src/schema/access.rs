@@ -0,0 +1,68 @@
+use serde::{Deserialize, Serialize};
+
+/// Who may call a module's reducers and queries from another node, declared in
+/// `interstice_module!` or on a `#[reducer]` / `#[query]`. Calls from modules on
+/// the same node are not affected.
+///
+/// An empty policy lets every peer through. Otherwise the caller's node id must be
+/// in `nodes` or hold one of `roles` (when either list is set), and the `policy`
+/// query, a `fn(QueryContext, String) -> bool` of the module receiving the name of
+/// the called reducer or query, must return `true` (when set).
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct AccessPolicy {
+    #[serde(default)]
+    pub nodes: Vec<String>,
+    #[serde(default)]
+    pub roles: Vec<String>,
+    #[serde(default)]
+    pub policy: Option<String>,
+}
+
+impl AccessPolicy {
+    pub fn is_open(&self) -> bool {
+        self.nodes.is_empty() && self.roles.is_empty() && self.policy.is_none()
+    }
+
+    /// Whether the allow-lists admit a caller with this node id and roles. The
+    /// `policy` query is checked separately by the runtime.
+    pub fn allows(&self, node_id: &str, roles: &[String]) -> bool {
+        if self.nodes.is_empty() && self.roles.is_empty() {
+            return true;
+        }
+        self.nodes.iter().any(|node| node == node_id)
+            || self.roles.iter().any(|role| roles.contains(role))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AccessPolicy;
+
+    #[test]
+    fn allow_lists_match_node_or_role() {
+        let open = AccessPolicy::default();
+        assert!(open.is_open());
+        assert!(open.allows("any", &[]));
+
+        let policy = AccessPolicy {
+            nodes: vec!["node-a".into()],
+            roles: vec!["admin".into()],
+            policy: None,
+        };
+        assert!(!policy.is_open());
+        assert!(policy.allows("node-a", &[]));
+        assert!(policy.allows("node-b", &["admin".into()]));
+        assert!(!policy.allows("node-b", &["viewer".into()]));
+    }
+
+    #[test]
+    fn policy_only_leaves_allow_lists_open() {
+        let policy = AccessPolicy {
+            nodes: Vec::new(),
+            roles: Vec::new(),
+            policy: Some("can_call".into()),
+        };
+        assert!(!policy.is_open());
+        assert!(policy.allows("node-b", &[]));
+    }
+}
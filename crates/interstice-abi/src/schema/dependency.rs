@@ -1,10 +1,14 @@
-use crate::Version;
+use crate::{ModuleSchema, VersionReq};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ModuleDependency {
     pub module_name: String,
-    pub version: Version,
+    pub version: VersionReq,
+    /// Public schema the bindings were generated from. When present, the runtime
+    /// checks that the loaded module still exposes everything it describes.
+    #[serde(default)]
+    pub bound_schema: Option<Box<ModuleSchema>>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -1,4 +1,5 @@
 use crate::NodeSelection;
+use crate::interstice_abi_macros::IntersticeType;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
@@ -38,4 +39,38 @@ pub enum SubscriptionEventSchema {
     ModuleRemove,
     Connect,
     Disconnect,
+    /// Fixed-timestep tick every `period_us` microseconds of the runtime's
+    /// monotonic clock.
+    Tick {
+        period_us: u64,
+        overrun: TickOverrun,
+    },
+}
+
+/// What a tick subscription does with the ticks that came due while its reducer
+/// was still running.
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub enum TickOverrun {
+    /// Drop the missed ticks and wait for the next period boundary.
+    #[default]
+    Skip,
+    /// Run every missed tick back to back until the clock is caught up.
+    CatchUp,
+    /// Run a single tick standing for all the missed ones.
+    Coalesce,
+}
+
+/// Argument of the reducers subscribed to a tick.
+#[derive(Debug, Clone, Deserialize, Serialize, IntersticeType)]
+pub struct TickEvent {
+    /// Index of the tick since the subscription started, from 0. Tick `n` is due
+    /// `n + 1` periods after the start.
+    pub index: u64,
+    /// Real time elapsed since the previous tick was delivered (or since the
+    /// subscription started for the first one).
+    pub elapsed_us: u64,
+    /// Fixed steps this tick stands for: more than 1 only for coalesced ticks.
+    pub steps: u64,
+    /// Ticks dropped right before this one by the `Skip` overrun policy.
+    pub skipped: u64,
 }
@@ -1,3 +1,4 @@
+mod access;
 mod dependency;
 mod event;
 mod module;
@@ -8,6 +9,7 @@ mod subscription;
 mod table;
 mod version;
 
+pub use access::*;
 pub use dependency::*;
 pub use event::*;
 pub use module::*;
@@ -1,7 +1,7 @@
 use crate::{
-    ABI_VERSION, Authority, IntersticeType, ModuleDependency, NodeDependency, QuerySchema,
+    ABI_VERSION, AccessPolicy, Authority, IntersticeType, ModuleDependency, NodeDependency, QuerySchema,
     ReducerSchema, SubscriptionSchema, TableSchema, TableVisibility, Version,
-    interstice_type_def::IntersticeTypeDef,
+    interstice_type_def::IntersticeTypeDef, schema::table::same_fields,
 };
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -21,6 +21,27 @@ pub enum ModuleVisibility {
     Private,
 }
 
+/// Resource limits declared by a module in `interstice_module!`.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct ModuleLimits {
+    /// Default fuel budget for every reducer and query call of the module. A call
+    /// that burns through its budget traps and its transactions are discarded.
+    /// `None` means unlimited.
+    pub fuel: Option<u64>,
+    /// Ceiling on the linear memory of each of the module's wasm instances, in MiB.
+    /// Checked on load and enforced whenever the guest grows its memory.
+    pub max_memory_mb: Option<u32>,
+}
+
+impl ModuleLimits {
+    pub const fn unlimited() -> Self {
+        Self {
+            fuel: None,
+            max_memory_mb: None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ModuleSchema {
     pub abi_version: u16,
@@ -36,6 +57,11 @@ pub struct ModuleSchema {
     pub module_dependencies: Vec<ModuleDependency>,
     pub node_dependencies: Vec<NodeDependency>,
     pub replicated_tables: Vec<ReplicatedTableSchema>,
+    #[serde(default)]
+    pub limits: ModuleLimits,
+    /// Default access policy for remote calls to the module's reducers and queries.
+    #[serde(default)]
+    pub access: AccessPolicy,
 }
 
 impl ModuleSchema {
@@ -58,9 +84,12 @@ impl ModuleSchema {
             module_dependencies: Vec::new(),
             node_dependencies: Vec::new(),
             replicated_tables: Vec::new(),
+            limits: ModuleLimits::default(),
+            access: AccessPolicy::default(),
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         name: impl Into<String>,
         version: Version,
@@ -74,6 +103,8 @@ impl ModuleSchema {
         module_dependencies: Vec<ModuleDependency>,
         node_dependencies: Vec<NodeDependency>,
         replicated_tables: Vec<ReplicatedTableSchema>,
+        limits: ModuleLimits,
+        access: AccessPolicy,
     ) -> Self {
         Self {
             abi_version: ABI_VERSION,
@@ -89,6 +120,8 @@ impl ModuleSchema {
             module_dependencies,
             node_dependencies,
             replicated_tables,
+            limits,
+            access,
         }
     }
 
@@ -102,6 +135,10 @@ impl ModuleSchema {
                 IntersticeType::Vec(inner) | IntersticeType::Option(inner) => {
                     extract_named_types(inner, names);
                 }
+                IntersticeType::Map(key, value) => {
+                    extract_named_types(key, names);
+                    extract_named_types(value, names);
+                }
                 IntersticeType::Tuple(types) => {
                     for ty in types {
                         extract_named_types(ty, names);
@@ -235,10 +272,78 @@ impl ModuleSchema {
             subscriptions: Vec::new(),
             type_definitions,
             authorities: self.authorities,
-            module_dependencies: self.module_dependencies,
+            module_dependencies: self
+                .module_dependencies
+                .into_iter()
+                .map(|dependency| ModuleDependency {
+                    bound_schema: None,
+                    ..dependency
+                })
+                .collect(),
             node_dependencies: self.node_dependencies,
             replicated_tables: self.replicated_tables,
+            limits: self.limits,
+            access: self.access,
+        }
+    }
+
+    /// Check that this module still exposes the public interface of `bound`, the
+    /// schema a dependent module generated its bindings from. Additions are fine,
+    /// including new struct fields declared with `#[interstice(default)]`; removed or
+    /// changed reducers, queries, tables and types are reported.
+    pub fn check_compatible(&self, bound: &ModuleSchema) -> Result<(), String> {
+        let exposed = self.clone().to_public();
+
+        for reducer in &bound.reducers {
+            match exposed.reducers.iter().find(|r| r.name == reducer.name) {
+                Some(current) if same_fields(&current.arguments, &reducer.arguments) => {}
+                Some(_) => return Err(format!("reducer '{}' changed its arguments", reducer.name)),
+                None => return Err(format!("reducer '{}' is no longer exposed", reducer.name)),
+            }
+        }
+        for query in &bound.queries {
+            match exposed.queries.iter().find(|q| q.name == query.name) {
+                Some(current)
+                    if same_fields(&current.arguments, &query.arguments)
+                        && current.return_type.same_layout(&query.return_type) => {}
+                Some(_) => return Err(format!("query '{}' changed its signature", query.name)),
+                None => return Err(format!("query '{}' is no longer exposed", query.name)),
+            }
+        }
+        for table in &bound.tables {
+            match exposed.tables.iter().find(|t| t.name == table.name) {
+                Some(current) if current.same_layout(table) => {}
+                Some(_) => return Err(format!("table '{}' changed its layout", table.name)),
+                None => return Err(format!("table '{}' is no longer public", table.name)),
+            }
+        }
+        for (name, type_def) in &bound.type_definitions {
+            let unchanged = match (exposed.type_definitions.get(name), type_def) {
+                // Values of the bound type still convert when the struct only gained
+                // fields with a default, after the existing ones.
+                (
+                    Some(IntersticeTypeDef::Struct { fields: current, .. }),
+                    IntersticeTypeDef::Struct { fields, .. },
+                ) => {
+                    current.len() >= fields.len()
+                        && same_fields(&current[..fields.len()], fields)
+                        && current[fields.len()..].iter().all(|field| field.default.is_some())
+                }
+                (
+                    Some(IntersticeTypeDef::Enum {
+                        variants: current, ..
+                    }),
+                    IntersticeTypeDef::Enum {
+                        variants: fields, ..
+                    },
+                ) => same_fields(current, fields),
+                _ => false,
+            };
+            if !unchanged {
+                return Err(format!("type '{}' changed or was removed", name));
+            }
         }
+        Ok(())
     }
 
     pub fn from_toml_string(toml_string: &str) -> Result<Self, toml::de::Error> {
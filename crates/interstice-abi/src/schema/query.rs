@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-use crate::{FieldDef, IntersticeType};
+use crate::{AccessPolicy, FieldDef, IntersticeType};
 use super::reducer::ReducerTableRef;
 
 /// Declared read access for a query (same [`ReducerTableRef`] rules as reducer `reads`).
@@ -11,6 +11,12 @@ pub struct QuerySchema {
     pub return_type: IntersticeType,
     #[serde(default)]
     pub reads: Vec<ReducerTableRef>,
+    /// Access policy for remote calls overriding the module default
+    /// ([`ModuleSchema::access`]). `None` falls back to the module default.
+    ///
+    /// [`ModuleSchema::access`]: crate::ModuleSchema::access
+    #[serde(default)]
+    pub access: Option<AccessPolicy>,
 }
 
 impl QuerySchema {
@@ -33,8 +39,14 @@ impl QuerySchema {
             arguments,
             return_type,
             reads,
+            access: None,
         }
     }
+
+    pub fn with_access(mut self, access: Option<AccessPolicy>) -> Self {
+        self.access = access;
+        self
+    }
 }
 
 #[cfg(test)]
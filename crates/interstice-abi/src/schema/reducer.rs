@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 
 use crate::interstice_type_def::FieldDef;
-use crate::{ModuleSelection, NodeSelection};
+use crate::{AccessPolicy, ModuleSelection, NodeSelection};
 
 /// Declared table access for a reducer: [`NodeSelection`] + [`ModuleSelection`] + `table_name`.
 /// Use [`ModuleSelection::Current`] for the module that contains the reducer (no embedded crate name);
@@ -21,6 +21,23 @@ pub struct ReducerSchema {
     pub inserts: Vec<ReducerTableRef>,
     pub updates: Vec<ReducerTableRef>,
     pub deletes: Vec<ReducerTableRef>,
+    /// Per-call fuel budget overriding the module default ([`ModuleLimits::fuel`]).
+    /// `None` falls back to the module default.
+    ///
+    /// [`ModuleLimits::fuel`]: crate::ModuleLimits::fuel
+    #[serde(default)]
+    pub fuel: Option<u64>,
+    /// Declared with `#[reducer(atomic)]`: the reducer and every local reducer it calls
+    /// (directly or transitively) commit together, or not at all when one fails or
+    /// one of their writes fails its table's checks.
+    #[serde(default)]
+    pub atomic: bool,
+    /// Access policy for remote calls overriding the module default
+    /// ([`ModuleSchema::access`]). `None` falls back to the module default.
+    ///
+    /// [`ModuleSchema::access`]: crate::ModuleSchema::access
+    #[serde(default)]
+    pub access: Option<AccessPolicy>,
 }
 
 impl ReducerSchema {
@@ -39,8 +56,26 @@ impl ReducerSchema {
             inserts,
             updates,
             deletes,
+            fuel: None,
+            atomic: false,
+            access: None,
         }
     }
+
+    pub fn with_fuel(mut self, fuel: Option<u64>) -> Self {
+        self.fuel = fuel;
+        self
+    }
+
+    pub fn with_atomic(mut self, atomic: bool) -> Self {
+        self.atomic = atomic;
+        self
+    }
+
+    pub fn with_access(mut self, access: Option<AccessPolicy>) -> Self {
+        self.access = access;
+        self
+    }
 }
 
 #[cfg(test)]
@@ -55,6 +90,7 @@ mod tests {
             vec![FieldDef {
                 name: "name".to_string(),
                 field_type: IntersticeType::String,
+                default: None,
             }],
             vec![ReducerTableRef {
                 node_selection: NodeSelection::Current,
@@ -85,4 +121,22 @@ mod tests {
         assert_eq!(decoded.reads.len(), 1);
         assert_eq!(decoded.reads[0].table_name, "users");
     }
+
+    #[test]
+    fn reducer_schema_round_trip_preserves_fuel() {
+        let schema = ReducerSchema::new(
+            "spin",
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+        )
+        .with_fuel(Some(10_000));
+
+        let bytes = encode(&schema).expect("encode reducer schema");
+        let decoded: ReducerSchema = decode(&bytes).expect("decode reducer schema");
+
+        assert_eq!(decoded.fuel, Some(10_000));
+    }
 }
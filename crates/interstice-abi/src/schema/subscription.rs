@@ -1,9 +1,13 @@
 use serde::{Deserialize, Serialize};
 
-use crate::SubscriptionEventSchema;
+use crate::{RowFilter, SubscriptionEventSchema};
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct SubscriptionSchema {
     pub reducer_name: String,
     pub event: SubscriptionEventSchema,
+    /// Set with `#[reducer(on = "node.module.table.event", filter = "...")]`: the
+    /// remote node only sends the events of rows matching it.
+    #[serde(default)]
+    pub filter: Option<RowFilter>,
 }
@@ -1,6 +1,9 @@
 use std::collections::HashMap;
 
-use crate::{IntersticeTypeDef, Row, interstice_type_def::FieldDef, validate_value_detailed};
+use crate::{
+    Field, IntersticeTypeDef, IntersticeType, IntersticeValue, Row, interstice_type_def::FieldDef,
+    validate_value_detailed,
+};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -13,6 +16,34 @@ pub struct TableSchema {
     pub primary_key_auto_inc: bool,
     pub indexes: Vec<IndexSchema>,
     pub persistence: PersistenceKind,
+    #[serde(default)]
+    pub limits: TableLimits,
+    #[serde(default)]
+    pub migrations: Vec<FieldMigration>,
+    /// Query of the owning module declared with `#[table(public, view = "...")]`. It
+    /// receives a row and decides whether the reading module or node may see it.
+    #[serde(default)]
+    pub view: Option<String>,
+}
+
+/// Size quota declared with `#[table(max_rows = N, max_bytes = N)]`. Inserts and
+/// updates that would exceed it are rejected. `None` means unlimited.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct TableLimits {
+    pub max_rows: Option<u64>,
+    /// Upper bound on the summed encoded size of the table's rows.
+    pub max_bytes: Option<u64>,
+}
+
+/// Declared with `#[migrate(...)]` on a table field: how rows persisted under an
+/// older layout of the table fill the field when the module is upgraded.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct FieldMigration {
+    pub field_name: String,
+    /// Name of the field in the previous layout.
+    pub renamed_from: Option<String>,
+    /// Value given to rows that predate the field.
+    pub default: Option<IntersticeValue>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
@@ -36,10 +67,31 @@ pub enum IndexType {
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct IndexSchema {
+    /// Name the index is scanned by: the indexed field for single-field indexes,
+    /// the index name for composite ones.
     pub field_name: String,
     pub index_type: IndexType,
     pub unique: bool,
     pub auto_inc: bool,
+    /// Fields of a composite index declared with `#[table(index(...))]`, in key
+    /// order. Empty for single-field indexes.
+    #[serde(default)]
+    pub fields: Vec<String>,
+}
+
+impl IndexSchema {
+    /// Fields making up the index key, in order.
+    pub fn key_fields(&self) -> &[String] {
+        if self.fields.is_empty() {
+            std::slice::from_ref(&self.field_name)
+        } else {
+            &self.fields
+        }
+    }
+
+    pub fn is_composite(&self) -> bool {
+        !self.fields.is_empty()
+    }
 }
 
 impl TableSchema {
@@ -67,4 +119,288 @@ impl TableSchema {
         }
         Ok(())
     }
+
+    /// Position of a field in the row: `None` for the primary key, `Some(i)` for
+    /// `entries[i]`. The outer `None` means the table has no such field.
+    pub fn field_position(&self, name: &str) -> Option<Option<usize>> {
+        if name == self.primary_key.name {
+            return Some(None);
+        }
+        self.fields
+            .iter()
+            .position(|field| field.name == name)
+            .map(Some)
+    }
+
+    /// Value of a field of the row. A dotted name such as `pos.x` reads the field `x`
+    /// of the struct stored in `pos`.
+    pub fn field_value<'a>(&self, row: &'a Row, name: &str) -> Option<&'a IntersticeValue> {
+        let mut path = name.split('.');
+        let mut value = match self.field_position(path.next()?)? {
+            None => &row.primary_key,
+            Some(position) => row.entries.get(position)?,
+        };
+        for segment in path {
+            value = match value {
+                IntersticeValue::Struct { fields, .. } => {
+                    &fields.iter().find(|field| field.name == segment)?.value
+                }
+                _ => return None,
+            };
+        }
+        Some(value)
+    }
+
+    /// The row as the value of the table's struct type, as a view query receives it.
+    pub fn row_value(&self, row: &Row) -> IntersticeValue {
+        let mut fields = vec![Field {
+            name: self.primary_key.name.clone(),
+            value: row.primary_key.clone(),
+        }];
+        fields.extend(self.fields.iter().zip(row.entries.iter()).map(|(field, value)| Field {
+            name: field.name.clone(),
+            value: value.clone(),
+        }));
+        IntersticeValue::Struct {
+            name: self.type_name.clone(),
+            fields,
+        }
+    }
+
+    /// Whether rows stored under `previous` can be read with this schema as-is.
+    pub fn same_layout(&self, previous: &TableSchema) -> bool {
+        same_field(&self.primary_key, &previous.primary_key)
+            && same_fields(&self.fields, &previous.fields)
+    }
+
+    /// Convert a row stored under the `previous` layout of this table. Fields are
+    /// carried over by name (or by their `renamed_from` name), fields that no longer
+    /// exist are dropped and new fields take their declared default (from
+    /// `#[migrate(default = ...)]`, then `#[interstice(default)]`), or `None` for
+    /// optional fields.
+    pub fn migrate_row(&self, previous: &TableSchema, row: Row) -> Result<Row, String> {
+        if !same_field(&self.primary_key, &previous.primary_key) {
+            return Err(format!(
+                "primary key changed from '{}: {}' to '{}: {}'",
+                previous.primary_key.name,
+                previous.primary_key.field_type,
+                self.primary_key.name,
+                self.primary_key.field_type
+            ));
+        }
+        if row.entries.len() != previous.fields.len() {
+            return Err(format!(
+                "field count mismatch: expected {}, got {}",
+                previous.fields.len(),
+                row.entries.len()
+            ));
+        }
+
+        let mut old_entries: Vec<Option<IntersticeValue>> =
+            row.entries.into_iter().map(Some).collect();
+        let mut entries = Vec::with_capacity(self.fields.len());
+        for field in &self.fields {
+            let migration = self.migrations.iter().find(|m| m.field_name == field.name);
+            let source_name = migration
+                .and_then(|m| m.renamed_from.as_ref())
+                .unwrap_or(&field.name);
+            let source = previous
+                .fields
+                .iter()
+                .position(|f| &f.name == source_name);
+
+            let value = match source {
+                Some(position) => {
+                    let old_field = &previous.fields[position];
+                    if !old_field.field_type.same_layout(&field.field_type) {
+                        return Err(format!(
+                            "field '{}' changed type from {} to {}",
+                            field.name, old_field.field_type, field.field_type
+                        ));
+                    }
+                    old_entries[position].take().ok_or_else(|| {
+                        format!("field '{}' is migrated from twice", old_field.name)
+                    })?
+                }
+                None => match (
+                    migration.and_then(|m| m.default.clone()).or_else(|| field.default.clone()),
+                    &field.field_type,
+                ) {
+                    (Some(default), _) => default,
+                    (None, IntersticeType::Option(_)) => IntersticeValue::Option(None),
+                    (None, _) => {
+                        return Err(format!(
+                            "new field '{}' needs a default value, e.g. #[migrate(default = ...)] or #[interstice(default)]",
+                            field.name
+                        ));
+                    }
+                },
+            };
+            entries.push(value);
+        }
+
+        Ok(Row {
+            primary_key: row.primary_key,
+            entries,
+        })
+    }
+}
+
+fn same_field(a: &FieldDef, b: &FieldDef) -> bool {
+    a.name == b.name && a.field_type.same_layout(&b.field_type)
+}
+
+pub(crate) fn same_fields(a: &[FieldDef], b: &[FieldDef]) -> bool {
+    a.len() == b.len() && a.iter().zip(b.iter()).all(|(a, b)| same_field(a, b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(name: &str, field_type: IntersticeType) -> FieldDef {
+        FieldDef {
+            name: name.into(),
+            field_type,
+            default: None,
+        }
+    }
+
+    fn schema(fields: Vec<FieldDef>, migrations: Vec<FieldMigration>) -> TableSchema {
+        TableSchema {
+            name: "players".into(),
+            type_name: "Players".into(),
+            visibility: TableVisibility::Private,
+            fields,
+            primary_key: field("id", IntersticeType::U64),
+            primary_key_auto_inc: false,
+            indexes: Vec::new(),
+            persistence: PersistenceKind::Logged,
+            limits: TableLimits::default(),
+            migrations,
+            view: None,
+        }
+    }
+
+    #[test]
+    fn migrate_row_renames_drops_and_defaults() {
+        let previous = schema(
+            vec![
+                field("name", IntersticeType::String),
+                field("legacy", IntersticeType::Bool),
+            ],
+            Vec::new(),
+        );
+        let current = schema(
+            vec![
+                field("nickname", IntersticeType::String),
+                field("score", IntersticeType::U32),
+                field("team", IntersticeType::Option(Box::new(IntersticeType::String))),
+            ],
+            vec![
+                FieldMigration {
+                    field_name: "nickname".into(),
+                    renamed_from: Some("name".into()),
+                    default: None,
+                },
+                FieldMigration {
+                    field_name: "score".into(),
+                    renamed_from: None,
+                    default: Some(IntersticeValue::U32(10)),
+                },
+            ],
+        );
+        assert!(!current.same_layout(&previous));
+
+        let row = Row {
+            primary_key: IntersticeValue::U64(1),
+            entries: vec![IntersticeValue::String("ada".into()), IntersticeValue::Bool(true)],
+        };
+        let migrated = current.migrate_row(&previous, row).unwrap();
+        assert_eq!(
+            migrated.entries,
+            vec![
+                IntersticeValue::String("ada".into()),
+                IntersticeValue::U32(10),
+                IntersticeValue::Option(None),
+            ]
+        );
+    }
+
+    #[test]
+    fn migrate_row_rejects_missing_default_and_type_change() {
+        let previous = schema(vec![field("name", IntersticeType::String)], Vec::new());
+        let row = Row {
+            primary_key: IntersticeValue::U64(1),
+            entries: vec![IntersticeValue::String("ada".into())],
+        };
+
+        let added = schema(
+            vec![
+                field("name", IntersticeType::String),
+                field("score", IntersticeType::U32),
+            ],
+            Vec::new(),
+        );
+        assert!(added.migrate_row(&previous, row.clone()).is_err());
+
+        let retyped = schema(vec![field("name", IntersticeType::U32)], Vec::new());
+        assert!(retyped.migrate_row(&previous, row).is_err());
+    }
+
+    #[test]
+    fn migrate_row_fills_fields_with_their_type_default() {
+        let previous = schema(vec![field("name", IntersticeType::String)], Vec::new());
+        let mut level = field("level", IntersticeType::U32);
+        level.default = Some(IntersticeValue::U32(0));
+        let current = schema(vec![field("name", IntersticeType::String), level], Vec::new());
+
+        let row = Row {
+            primary_key: IntersticeValue::U64(1),
+            entries: vec![IntersticeValue::String("ada".into())],
+        };
+        assert_eq!(
+            current.migrate_row(&previous, row).unwrap().entries,
+            vec![IntersticeValue::String("ada".into()), IntersticeValue::U32(0)]
+        );
+    }
+
+    #[test]
+    fn byte_vector_fields_keep_their_layout_as_bytes() {
+        let legacy = IntersticeType::Vec(Box::new(IntersticeType::U8));
+        let previous = schema(vec![field("avatar", legacy)], Vec::new());
+        let current = schema(vec![field("avatar", IntersticeType::Bytes)], Vec::new());
+        assert!(current.same_layout(&previous));
+
+        let row = Row {
+            primary_key: IntersticeValue::U64(1),
+            entries: vec![IntersticeValue::Vec(vec![IntersticeValue::U8(7)])],
+        };
+        assert_eq!(current.migrate_row(&previous, row.clone()).unwrap(), row);
+    }
+
+    #[test]
+    fn row_value_names_primary_key_and_fields() {
+        let table = schema(vec![field("name", IntersticeType::String)], Vec::new());
+        let row = Row {
+            primary_key: IntersticeValue::U64(7),
+            entries: vec![IntersticeValue::String("ada".into())],
+        };
+        assert_eq!(
+            table.row_value(&row),
+            IntersticeValue::Struct {
+                name: "Players".into(),
+                fields: vec![
+                    Field {
+                        name: "id".into(),
+                        value: IntersticeValue::U64(7),
+                    },
+                    Field {
+                        name: "name".into(),
+                        value: IntersticeValue::String("ada".into()),
+                    },
+                ],
+            }
+        );
+    }
 }
@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
 
-#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Version {
     pub major: u16,
     pub minor: u16,
@@ -25,3 +26,174 @@ impl Into<Version> for &str {
         }
     }
 }
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+pub enum VersionOp {
+    /// `^1.2.3`: same left-most non-zero component, at least the given version.
+    Caret,
+    /// `~1.2.3`: same major and minor (or same major for `~1`), at least the given version.
+    Tilde,
+    /// `=1.2.3`: exactly the given components.
+    Exact,
+}
+
+/// Versions of a module a dependency accepts, written like a Cargo requirement
+/// (`^1.2`, `~0.5.1`, `=1.0.0`). A bare version is read as a caret requirement.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(try_from = "String", into = "String")]
+pub struct VersionReq {
+    pub op: VersionOp,
+    pub major: u16,
+    pub minor: Option<u16>,
+    pub patch: Option<u16>,
+}
+
+impl VersionReq {
+    pub fn matches(&self, version: &Version) -> bool {
+        let lowest = Version {
+            major: self.major,
+            minor: self.minor.unwrap_or(0),
+            patch: self.patch.unwrap_or(0),
+        };
+        match self.op {
+            VersionOp::Exact => {
+                version.major == self.major
+                    && self.minor.is_none_or(|minor| version.minor == minor)
+                    && self.patch.is_none_or(|patch| version.patch == patch)
+            }
+            VersionOp::Tilde => {
+                *version >= lowest
+                    && version.major == self.major
+                    && self.minor.is_none_or(|minor| version.minor == minor)
+            }
+            VersionOp::Caret => {
+                if *version < lowest || version.major != self.major {
+                    return false;
+                }
+                if self.major > 0 {
+                    return true;
+                }
+                match (self.minor, self.patch) {
+                    (None, _) => true,
+                    (Some(minor), _) if minor > 0 => version.minor == minor,
+                    (Some(_), None) => version.minor == 0,
+                    (Some(_), Some(patch)) => version.minor == 0 && version.patch == patch,
+                }
+            }
+        }
+    }
+}
+
+impl From<Version> for VersionReq {
+    fn from(version: Version) -> Self {
+        Self {
+            op: VersionOp::Caret,
+            major: version.major,
+            minor: Some(version.minor),
+            patch: Some(version.patch),
+        }
+    }
+}
+
+impl FromStr for VersionReq {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let trimmed = s.trim();
+        let (op, rest) = if let Some(rest) = trimmed.strip_prefix('^') {
+            (VersionOp::Caret, rest)
+        } else if let Some(rest) = trimmed.strip_prefix('~') {
+            (VersionOp::Tilde, rest)
+        } else if let Some(rest) = trimmed.strip_prefix('=') {
+            (VersionOp::Exact, rest)
+        } else {
+            (VersionOp::Caret, trimmed)
+        };
+
+        let parts = rest
+            .trim()
+            .split('.')
+            .map(|part| part.parse::<u16>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| format!("Invalid version requirement '{}'", s))?;
+        if parts.is_empty() || parts.len() > 3 {
+            return Err(format!("Invalid version requirement '{}'", s));
+        }
+        Ok(Self {
+            op,
+            major: parts[0],
+            minor: parts.get(1).copied(),
+            patch: parts.get(2).copied(),
+        })
+    }
+}
+
+impl TryFrom<String> for VersionReq {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<VersionReq> for String {
+    fn from(req: VersionReq) -> Self {
+        req.to_string()
+    }
+}
+
+impl fmt::Display for VersionReq {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = match self.op {
+            VersionOp::Caret => "^",
+            VersionOp::Tilde => "~",
+            VersionOp::Exact => "=",
+        };
+        write!(f, "{}{}", op, self.major)?;
+        if let Some(minor) = self.minor {
+            write!(f, ".{}", minor)?;
+        }
+        if let Some(patch) = self.patch {
+            write!(f, ".{}", patch)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(req: &str, version: &str) -> bool {
+        req.parse::<VersionReq>().unwrap().matches(&version.into())
+    }
+
+    #[test]
+    fn requirements_follow_cargo_semantics() {
+        assert!(matches("^1.2", "1.2.0"));
+        assert!(matches("^1.2", "1.9.3"));
+        assert!(!matches("^1.2", "1.1.9"));
+        assert!(!matches("^1.2", "2.0.0"));
+        assert!(matches("0.5.1", "0.5.4"));
+        assert!(!matches("0.5.1", "0.6.0"));
+        assert!(matches("^0.0.3", "0.0.3"));
+        assert!(!matches("^0.0.3", "0.0.4"));
+
+        assert!(matches("~0.5.1", "0.5.9"));
+        assert!(!matches("~0.5.1", "0.5.0"));
+        assert!(!matches("~0.5.1", "0.6.0"));
+        assert!(matches("~1", "1.7.0"));
+
+        assert!(matches("=1.2", "1.2.7"));
+        assert!(!matches("=1.2.3", "1.2.4"));
+        assert!("1.x".parse::<VersionReq>().is_err());
+    }
+
+    #[test]
+    fn requirement_round_trips_through_string() {
+        let req: VersionReq = "~0.5.1".parse().unwrap();
+        assert_eq!(req.to_string(), "~0.5.1");
+        let bytes = crate::encode(&req).unwrap();
+        assert_eq!(crate::decode::<VersionReq>(&bytes).unwrap(), req);
+    }
+}
@@ -1,9 +1,9 @@
-use crate::node_client::handshake_with_node;
+use crate::node_client::{handshake_with_node, read_reply};
 use crate::node_registry::NodeRegistry;
 use interstice_core::{
     IntersticeError, NetworkPacket,
     interstice_abi::{Field, IntersticeValue},
-    packet::{read_packet, write_packet},
+    secure::SecureSession,
 };
 use tokio::time::{Duration, Instant, sleep};
 use uuid::Uuid;
@@ -90,10 +90,11 @@ async fn run_simple(args: &[String]) -> Result<(), IntersticeError> {
         .ok_or_else(|| IntersticeError::Internal(format!("Unknown node '{}'", node)))?;
     let run_id = format!("bench-{}", Uuid::new_v4());
 
-    let (mut stream, _) = handshake_with_node(&address).await?;
-    write_packet(
-        &mut stream,
-        &NetworkPacket::ReducerCall {
+    let (mut session, _) = handshake_with_node(&address).await?;
+    session
+        .writer
+        .write_packet(&NetworkPacket::ReducerCall {
+            request_id: None,
             module_name: module.clone(),
             reducer_name: "bench_begin_run".to_string(),
             input: IntersticeValue::Vec(vec![
@@ -103,20 +104,22 @@ async fn run_simple(args: &[String]) -> Result<(), IntersticeError> {
                 IntersticeValue::U64(requests),
                 IntersticeValue::U64(payload_bytes),
             ]),
-        },
-    )
-    .await
-    .map_err(|err| IntersticeError::Internal(format!(
-        "{err} — node dropped the connection immediately after handshake; \
-        another benchmark process with the same CLI identity may already be connected"
-    )))?;
+        })
+        .await
+        .map_err(|err| {
+            IntersticeError::Internal(format!(
+                "{err} — node dropped the connection immediately after handshake; \
+                another benchmark process with the same CLI identity may already be connected"
+            ))
+        })?;
 
     if operation == "insert" {
         // bench_tx_insert only declares Insert caps — all calls are non-conflicting and run in parallel.
         for seq in 0..requests {
-            write_packet(
-                &mut stream,
-                &NetworkPacket::ReducerCall {
+            session
+                .writer
+                .write_packet(&NetworkPacket::ReducerCall {
+                    request_id: None,
                     module_name: module.clone(),
                     reducer_name: "bench_tx_insert".to_string(),
                     input: IntersticeValue::Vec(vec![
@@ -124,29 +127,29 @@ async fn run_simple(args: &[String]) -> Result<(), IntersticeError> {
                         IntersticeValue::U64(payload_bytes),
                         IntersticeValue::String(table.clone()),
                     ]),
-                },
-            )
-            .await?;
+                })
+                .await?;
         }
         // bench_end_run reads the target table, so the scheduler runs it after all inserts complete.
-        write_packet(
-            &mut stream,
-            &NetworkPacket::ReducerCall {
+        session
+            .writer
+            .write_packet(&NetworkPacket::ReducerCall {
+                request_id: None,
                 module_name: module.clone(),
                 reducer_name: "bench_end_run".to_string(),
                 input: IntersticeValue::Vec(vec![
                     IntersticeValue::String(run_id.clone()),
                     IntersticeValue::U64(requests),
                 ]),
-            },
-        )
-        .await?;
+            })
+            .await?;
     } else {
         // update/delete inherently conflict (Update+Update, Delete+Delete) — use serialized bench_tx.
         for seq in 0..requests {
-            write_packet(
-                &mut stream,
-                &NetworkPacket::ReducerCall {
+            session
+                .writer
+                .write_packet(&NetworkPacket::ReducerCall {
+                    request_id: None,
                     module_name: module.clone(),
                     reducer_name: "bench_tx".to_string(),
                     input: IntersticeValue::Vec(vec![
@@ -154,9 +157,8 @@ async fn run_simple(args: &[String]) -> Result<(), IntersticeError> {
                         IntersticeValue::U64(seq),
                         IntersticeValue::U64(payload_bytes),
                     ]),
-                },
-            )
-            .await?;
+                })
+                .await?;
         }
     }
     if poll_ms == 0 {
@@ -167,7 +169,7 @@ async fn run_simple(args: &[String]) -> Result<(), IntersticeError> {
     let client_start = Instant::now();
     let timeout = Duration::from_secs(30 * 60);
     let snapshot = loop {
-        let snapshot = query_snapshot_on_stream(&mut stream, &module, &run_id).await?;
+        let snapshot = query_snapshot(&mut session, &module, &run_id).await?;
         if snapshot.completed && snapshot.committed >= requests {
             break snapshot;
         }
@@ -179,7 +181,7 @@ async fn run_simple(args: &[String]) -> Result<(), IntersticeError> {
         }
         sleep(Duration::from_millis(poll_ms)).await;
     };
-    let _ = write_packet(&mut stream, &NetworkPacket::Close).await;
+    let _ = session.writer.write_packet(&NetworkPacket::Close).await;
     println!("Benchmark Simple Report");
     println!("  node: {}", node);
     println!("  module: {}", module);
@@ -195,8 +197,8 @@ async fn run_simple(args: &[String]) -> Result<(), IntersticeError> {
     Ok(())
 }
 
-async fn query_snapshot_on_stream(
-    stream: &mut tokio::net::TcpStream,
+async fn query_snapshot(
+    session: &mut SecureSession,
     module_name: &str,
     run_id: &str,
 ) -> Result<Snapshot, IntersticeError> {
@@ -207,9 +209,9 @@ async fn query_snapshot_on_stream(
         query_name: "bench_snapshot".to_string(),
         input: IntersticeValue::Vec(vec![IntersticeValue::String(run_id.to_string())]),
     };
-    write_packet(stream, &packet).await?;
+    session.writer.write_packet(&packet).await?;
     loop {
-        let packet = read_packet(stream).await?;
+        let packet = read_reply(session).await?;
         match packet {
             NetworkPacket::QueryResponse {
                 request_id: response_id,
@@ -1,10 +1,6 @@
-use crate::node_client::handshake_with_node;
+use crate::node_client::{handshake_with_node, read_reply};
 use crate::node_registry::NodeRegistry;
-use interstice_core::{
-    IntersticeError, NetworkPacket,
-    interstice_abi::IntersticeValue,
-    packet::{read_packet, write_packet},
-};
+use interstice_core::{IntersticeError, NetworkPacket, interstice_abi::IntersticeValue};
 
 pub async fn call_query(
     node_ref: String,
@@ -17,7 +13,7 @@ pub async fn call_query(
         .resolve_address(&node_ref)
         .ok_or_else(|| IntersticeError::Internal("Unknown node".into()))?;
     // connect to node
-    let (mut stream, _handshake) = handshake_with_node(&node_address).await?;
+    let (mut session, _handshake) = handshake_with_node(&node_address).await?;
 
     // Send call query packet to node
     let request_id = uuid::Uuid::new_v4().to_string();
@@ -27,10 +23,10 @@ pub async fn call_query(
         input,
         request_id: request_id.clone(),
     };
-    write_packet(&mut stream, &packet).await?;
+    session.writer.write_packet(&packet).await?;
 
     // Wait receiving query response packet from node
-    let response_packet = read_packet(&mut stream).await?;
+    let response_packet = read_reply(&mut session).await?;
     match response_packet {
         NetworkPacket::QueryResponse {
             request_id: _response_request_id,
@@ -45,7 +41,7 @@ pub async fn call_query(
 
     // Close connection properly
     let packet = NetworkPacket::Close;
-    write_packet(&mut stream, &packet).await?;
+    session.writer.write_packet(&packet).await?;
 
     Ok(())
 }
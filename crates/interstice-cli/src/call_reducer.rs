@@ -1,7 +1,8 @@
-use crate::node_client::handshake_with_node;
+use crate::node_client::{handshake_with_node, read_reply};
 use crate::node_registry::NodeRegistry;
 use interstice_core::{
-    IntersticeError, NetworkPacket, interstice_abi::IntersticeValue, packet::write_packet,
+    IntersticeError, NetworkPacket,
+    interstice_abi::{IntersticeValue, ReducerCallStatus},
 };
 
 pub async fn call_reducer(
@@ -15,19 +16,49 @@ pub async fn call_reducer(
         .resolve_address(&node_ref)
         .ok_or_else(|| IntersticeError::Internal("Unknown node".into()))?;
     // connect to node
-    let (mut stream, _handshake) = handshake_with_node(&node_address).await?;
+    let (mut session, _handshake) = handshake_with_node(&node_address).await?;
 
     // Send call reducer packet to node
+    let request_id = uuid::Uuid::new_v4().to_string();
     let packet = NetworkPacket::ReducerCall {
+        request_id: Some(request_id.clone()),
         module_name,
         reducer_name,
         input,
     };
-    write_packet(&mut stream, &packet).await?;
+    session.writer.write_packet(&packet).await?;
+
+    // Wait for the outcome of the call
+    loop {
+        match read_reply(&mut session).await? {
+            NetworkPacket::ReducerResult {
+                request_id: response_request_id,
+                status,
+            } if response_request_id == request_id => {
+                match status {
+                    ReducerCallStatus::Committed { commit_sequence } => {
+                        println!("Reducer committed (commit sequence {})", commit_sequence)
+                    }
+                    ReducerCallStatus::Failed { error } => println!("Reducer failed: {}", error),
+                    status => println!("Unexpected reducer status: {:?}", status),
+                }
+                break;
+            }
+            NetworkPacket::Error(err) => {
+                println!("Reducer call failed: {}", err);
+                break;
+            }
+            NetworkPacket::Close => {
+                println!("Connection closed before the reducer result");
+                return Ok(());
+            }
+            packet => println!("Unexpected packet received: {:?}", packet),
+        }
+    }
 
     // Close connection properly
     let packet = NetworkPacket::Close;
-    write_packet(&mut stream, &packet).await?;
+    session.writer.write_packet(&packet).await?;
 
     Ok(())
 }
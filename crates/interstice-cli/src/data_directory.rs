@@ -1,5 +1,6 @@
 use directories::ProjectDirs;
 use interstice_core::IntersticeError;
+use interstice_core::persistence::{NodeIdentity, PeerKeyStore};
 use serde::{Deserialize, Serialize};
 use std::{fs, path::PathBuf};
 use uuid::Uuid;
@@ -57,6 +58,17 @@ pub fn load_cli_identity() -> Result<CliIdentity, IntersticeError> {
     }
 }
 
+/// Key pair the CLI authenticates to nodes with.
+pub fn load_cli_key() -> Result<NodeIdentity, IntersticeError> {
+    NodeIdentity::load_or_create(data_file().join("cli_key.toml"))
+}
+
+/// Identity keys of the nodes the CLI has connected to (trust on first use unless
+/// `mode = "pinned"` is set in the file).
+pub fn load_node_keys() -> Result<PeerKeyStore, IntersticeError> {
+    PeerKeyStore::load_or_create(data_file().join("node_keys.toml"))
+}
+
 fn create_and_save_identity(path: &PathBuf) -> Result<CliIdentity, IntersticeError> {
     let identity = CliIdentity {
         cli_id: Uuid::new_v4().to_string(),
@@ -9,5 +9,6 @@ pub mod module;
 pub mod node_client;
 pub mod node_registry;
 pub mod node_utils;
+pub mod replay;
 pub mod start;
 pub mod update;
@@ -10,8 +10,9 @@ use interstice_cli::{
     call_reducer::call_reducer,
     example::example,
     init::init,
-    module::{load, remove, unload},
+    module::{load, remove, unload, upgrade},
     node_utils::handle_node_command,
+    replay::replay,
     update::update,
 };
 use interstice_core::{IntersticeError, interstice_abi::IntersticeValue};
@@ -48,6 +49,15 @@ async fn main() -> Result<(), IntersticeError> {
             let module_project_path = Path::new(&args[3]);
             load(node_ref, module_project_path).await
         }
+        "upgrade" => {
+            if args.len() < 4 {
+                print_help();
+                return Ok(());
+            }
+            let node_ref = args[2].clone();
+            let module_project_path = Path::new(&args[3]);
+            upgrade(node_ref, module_project_path).await
+        }
         "update" => update(),
         "unload" => {
             if args.len() < 4 {
@@ -98,6 +108,13 @@ async fn main() -> Result<(), IntersticeError> {
             call_query(node_ref, module_name, query_name, input.into()).await
         }
         "benchmark" => handle_benchmark_command(&args).await,
+        "replay" => {
+            if args.len() < 3 {
+                print_help();
+                return Ok(());
+            }
+            replay(Path::new(&args[2])).await
+        }
         "help" | "-h" | "--help" => {
             print_help();
             Ok(())
@@ -123,7 +140,7 @@ fn print_help() {
     println!("  node remove <name|id>                Remove a node entry");
     println!("  node rename <old> <new>              Rename a node entry");
     println!("  node show <name|id>                  Show node details");
-    println!("  node start <name|id>                 Start a local node by name");
+    println!("  node start <name|id> [--record <file>]  Start a local node by name, recording its inputs");
     println!("  node ping <name|id>                  Check node connectivity");
     println!("  node schema <name|id> [out]          Fetch node schema");
     println!("  bindings add module <node> <module> [project_path]  Add module binding");
@@ -135,6 +152,7 @@ fn print_help() {
         "  init                                   Initialize a new interstice module project in the current directory"
     );
     println!("  load <node> <module_path>      Load a module onto a node");
+    println!("  upgrade <node> <module_path>   Replace a loaded module in place, keeping its state");
     println!("  unload <node> <module_name>    Unload a module but keep its data");
     println!("  remove <node> <module_name>    Remove a module and delete its data");
     println!("  update                          Update the interstice CLI");
@@ -145,6 +163,7 @@ fn print_help() {
         "  call_query <node> <module_name> <query_name>        Call a query of a module on a node"
     );
     println!("  benchmark simple <...>             Run simple benchmark workload");
+    println!("  replay <file>                      Replay a node recording and check its final state");
     println!("  help                             Show this help message");
     println!();
     println!("OPTIONS:");
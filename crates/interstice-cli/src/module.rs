@@ -1,6 +1,6 @@
 use crate::node_client::handshake_with_node;
 use crate::node_registry::NodeRegistry;
-use interstice_core::{IntersticeError, ModuleEventInstance, NetworkPacket, packet::write_packet};
+use interstice_core::{IntersticeError, ModuleEventInstance, NetworkPacket};
 use serde_json::Value;
 use std::path::{Path, PathBuf};
 
@@ -13,42 +13,41 @@ pub async fn load(node_ref: String, module_project_path: &Path) -> Result<(), In
     let node_address = registry
         .resolve_address(&node_ref)
         .ok_or_else(|| IntersticeError::Internal("Unknown node".into()))?;
-    let (mut stream, _handshake) = handshake_with_node(&node_address).await?;
+    let (mut session, _handshake) = handshake_with_node(&node_address).await?;
 
-    // Build module using cargo
-    println!(
-        "Building module from {} using cargo...",
-        module_project_path.display()
-    );
-    let manifest_path = module_project_path.join("Cargo.toml");
-    let output = std::process::Command::new("cargo")
-        .args([
-            "build",
-            "--release",
-            "--target",
-            "wasm32-unknown-unknown",
-            "--manifest-path",
-            manifest_path.to_string_lossy().as_ref(),
-        ])
-        .output()
-        .expect("Failed to execute cargo build command");
-    if !output.status.success() {
-        return Err(IntersticeError::Internal(format!(
-            "Cargo build failed: {}",
-            String::from_utf8_lossy(&output.stderr)
-        )));
-    }
-    // Read generated wasm file
-    let wasm_path = resolve_wasm_path(&manifest_path)?;
-    let wasm_binary = std::fs::read(wasm_path).expect("Failed to read generated wasm file");
+    let wasm_binary = build_module(module_project_path)?;
 
     // Send wasm binary to node
     let packet = NetworkPacket::ModuleEvent(ModuleEventInstance::Load { wasm_binary });
-    write_packet(&mut stream, &packet).await?;
+    session.writer.write_packet(&packet).await?;
+
+    // Close connection properly
+    let packet = NetworkPacket::Close;
+    session.writer.write_packet(&packet).await?;
+
+    Ok(())
+}
+
+pub async fn upgrade(node_ref: String, module_project_path: &Path) -> Result<(), IntersticeError> {
+    // Build the new version of a loaded module and swap it in place on the node,
+    // keeping its subscriptions and table contents.
+
+    // connect to node
+    let registry = NodeRegistry::load()?;
+    let node_address = registry
+        .resolve_address(&node_ref)
+        .ok_or_else(|| IntersticeError::Internal("Unknown node".into()))?;
+    let (mut session, _handshake) = handshake_with_node(&node_address).await?;
+
+    let wasm_binary = build_module(module_project_path)?;
+
+    // Send wasm binary to node
+    let packet = NetworkPacket::ModuleEvent(ModuleEventInstance::Upgrade { wasm_binary });
+    session.writer.write_packet(&packet).await?;
 
     // Close connection properly
     let packet = NetworkPacket::Close;
-    write_packet(&mut stream, &packet).await?;
+    session.writer.write_packet(&packet).await?;
 
     Ok(())
 }
@@ -62,17 +61,17 @@ pub async fn unload(node_ref: String, module_name: &str) -> Result<(), Interstic
     let node_address = registry
         .resolve_address(&node_ref)
         .ok_or_else(|| IntersticeError::Internal("Unknown node".into()))?;
-    let (mut stream, _handshake) = handshake_with_node(&node_address).await?;
+    let (mut session, _handshake) = handshake_with_node(&node_address).await?;
 
     // Send unload request to node
     let packet = NetworkPacket::ModuleEvent(ModuleEventInstance::Unload {
         module_name: module_name.into(),
     });
-    write_packet(&mut stream, &packet).await?;
+    session.writer.write_packet(&packet).await?;
 
     // Close connection properly
     let packet = NetworkPacket::Close;
-    write_packet(&mut stream, &packet).await?;
+    session.writer.write_packet(&packet).await?;
 
     Ok(())
 }
@@ -85,21 +84,52 @@ pub async fn remove(node_ref: String, module_name: &str) -> Result<(), Interstic
     let node_address = registry
         .resolve_address(&node_ref)
         .ok_or_else(|| IntersticeError::Internal("Unknown node".into()))?;
-    let (mut stream, _handshake) = handshake_with_node(&node_address).await?;
+    let (mut session, _handshake) = handshake_with_node(&node_address).await?;
 
     // Send wasm binary to node
     let packet = NetworkPacket::ModuleEvent(ModuleEventInstance::Remove {
         module_name: module_name.into(),
     });
-    write_packet(&mut stream, &packet).await?;
+    session.writer.write_packet(&packet).await?;
 
     // Close connection properly
     let packet = NetworkPacket::Close;
-    write_packet(&mut stream, &packet).await?;
+    session.writer.write_packet(&packet).await?;
 
     Ok(())
 }
 
+/// Build the module project for wasm32 with cargo and read the generated binary.
+fn build_module(module_project_path: &Path) -> Result<Vec<u8>, IntersticeError> {
+    // Build module using cargo
+    println!(
+        "Building module from {} using cargo...",
+        module_project_path.display()
+    );
+    let manifest_path = module_project_path.join("Cargo.toml");
+    let output = std::process::Command::new("cargo")
+        .args([
+            "build",
+            "--release",
+            "--target",
+            "wasm32-unknown-unknown",
+            "--manifest-path",
+            manifest_path.to_string_lossy().as_ref(),
+        ])
+        .output()
+        .expect("Failed to execute cargo build command");
+    if !output.status.success() {
+        return Err(IntersticeError::Internal(format!(
+            "Cargo build failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+    // Read generated wasm file
+    let wasm_path = resolve_wasm_path(&manifest_path)?;
+    let wasm_binary = std::fs::read(wasm_path).expect("Failed to read generated wasm file");
+    Ok(wasm_binary)
+}
+
 fn resolve_wasm_path(manifest_path: &Path) -> Result<PathBuf, IntersticeError> {
     let output = std::process::Command::new("cargo")
         .args([
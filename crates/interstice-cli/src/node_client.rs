@@ -1,9 +1,11 @@
-use crate::data_directory::load_cli_identity;
+use crate::data_directory::{load_cli_identity, load_cli_key, load_node_keys};
 use interstice_core::{
-    IntersticeError, NetworkPacket,
+    IntersticeError, NetworkPacket, NodeId,
+    config::NetworkConfig,
     interstice_abi::NodeSchema,
-    packet::{read_packet, write_packet},
+    secure::{SecureSession, secure_handshake},
 };
+use parking_lot::Mutex;
 use uuid::Uuid;
 
 pub struct HandshakeInfo {
@@ -13,29 +15,45 @@ pub struct HandshakeInfo {
 
 pub async fn handshake_with_node(
     address: &str,
-) -> Result<(tokio::net::TcpStream, HandshakeInfo), IntersticeError> {
+) -> Result<(SecureSession, HandshakeInfo), IntersticeError> {
     let cli_identity = load_cli_identity()?;
-    let mut stream = tokio::net::TcpStream::connect(address)
+    let cli_id = NodeId::parse_str(&cli_identity.cli_id)
+        .map_err(|err| IntersticeError::Internal(format!("Invalid CLI id: {err}")))?;
+    let cli_key = load_cli_key()?;
+    let node_keys = Mutex::new(load_node_keys()?);
+    let stream = tokio::net::TcpStream::connect(address)
         .await
         .map_err(|_| IntersticeError::Internal("Failed to connect to node".into()))?;
     // Use a placeholder address since the CLI doesn't listen for incoming connections.
     // The actual address is provided by environment variable or defaults to localhost.
     let cli_address =
         std::env::var("INTERSTICE_CLI_ADDRESS").unwrap_or_else(|_| "127.0.0.1:0".to_string());
-    let packet = NetworkPacket::Handshake {
-        node_id: cli_identity.cli_id,
-        address: cli_address,
-        token: cli_identity.cli_token,
+    let session = secure_handshake(
+        stream,
+        cli_id,
+        cli_address,
+        cli_identity.cli_token,
+        &cli_key,
+        &node_keys,
+        &NetworkConfig::default(),
+        true,
+    )
+    .await?;
+    let handshake = HandshakeInfo {
+        node_id: session.peer_id.to_string(),
+        address: session.peer_address.clone(),
     };
-    write_packet(&mut stream, &packet).await?;
-    let response = read_packet(&mut stream).await?;
-    match response {
-        NetworkPacket::Handshake {
-            node_id, address, ..
-        } => Ok((stream, HandshakeInfo { node_id, address })),
-        _ => Err(IntersticeError::ProtocolError(
-            "Expected handshake response".into(),
-        )),
+    Ok((session, handshake))
+}
+
+/// Next packet from the node, answering the keepalive pings it sends meanwhile.
+pub async fn read_reply(session: &mut SecureSession) -> Result<NetworkPacket, IntersticeError> {
+    loop {
+        match session.reader.read_packet().await? {
+            NetworkPacket::Ping => session.writer.write_packet(&NetworkPacket::Pong).await?,
+            NetworkPacket::Pong => {}
+            packet => return Ok(packet),
+        }
     }
 }
 
@@ -43,14 +61,14 @@ pub async fn fetch_node_schema(
     address: &str,
     node_name: &str,
 ) -> Result<(NodeSchema, HandshakeInfo), IntersticeError> {
-    let (mut stream, handshake) = handshake_with_node(address).await?;
+    let (mut session, handshake) = handshake_with_node(address).await?;
     let request_id = Uuid::new_v4().to_string();
     let packet = NetworkPacket::SchemaRequest {
         request_id: request_id.clone(),
         node_name: node_name.to_string(),
     };
-    write_packet(&mut stream, &packet).await?;
-    let response = read_packet(&mut stream).await?;
+    session.writer.write_packet(&packet).await?;
+    let response = read_reply(&mut session).await?;
     match response {
         NetworkPacket::SchemaResponse {
             request_id: response_id,
@@ -146,7 +146,17 @@ pub async fn handle_node_command(args: &[String]) -> Result<(), IntersticeError>
             let parsed_node_id = node_id
                 .parse()
                 .map_err(|_| IntersticeError::Internal("Invalid node id".into()))?;
-            start(parsed_node_id, port, node.address.clone()).await?;
+            let record = match args.get(4).map(String::as_str) {
+                Some("--record") => match args.get(5) {
+                    Some(path) => Some(Path::new(path)),
+                    None => {
+                        print_node_help();
+                        return Ok(());
+                    }
+                },
+                _ => None,
+            };
+            start(parsed_node_id, port, node.address.clone(), record).await?;
         }
         "ping" => {
             if args.len() < 4 {
@@ -156,7 +166,7 @@ pub async fn handle_node_command(args: &[String]) -> Result<(), IntersticeError>
             let address = registry
                 .resolve_address(&args[3])
                 .ok_or_else(|| IntersticeError::Internal("Unknown node".into()))?;
-            let (_stream, handshake) = handshake_with_node(&address).await?;
+            let (_session, handshake) = handshake_with_node(&address).await?;
             registry.set_last_seen(&args[3]);
             registry.set_node_id(&args[3], handshake.node_id);
             registry.save()?;
@@ -205,7 +215,7 @@ fn print_node_help() {
     println!("  interstice node remove <name|id>");
     println!("  interstice node rename <old> <new>");
     println!("  interstice node show <name|id>");
-    println!("  interstice node start <name|id>");
+    println!("  interstice node start <name|id> [--record <file>]");
     println!("  interstice node ping <name|id>");
     println!("  interstice node schema <name|id> [out]");
 }
@@ -0,0 +1,25 @@
+use interstice_core::{IntersticeError, recording};
+use std::path::Path;
+
+/// Replay a recording on an empty node and check it ends with the recorded state.
+pub async fn replay(path: &Path) -> Result<(), IntersticeError> {
+    let report = recording::replay(path).await?;
+    println!("Replayed {} jobs.", report.jobs);
+    match report.recorded_state_hash {
+        Some(_) if report.matches() => {
+            println!("State hash {:016x} matches the recording.", report.state_hash);
+            Ok(())
+        }
+        Some(recorded_state_hash) => Err(IntersticeError::Internal(format!(
+            "State hash {:016x} differs from the recorded {:016x}",
+            report.state_hash, recorded_state_hash
+        ))),
+        None => {
+            println!(
+                "State hash {:016x}. The recording was not finished, so there is nothing to compare it with.",
+                report.state_hash
+            );
+            Ok(())
+        }
+    }
+}
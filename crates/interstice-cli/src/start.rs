@@ -1,8 +1,34 @@
 use crate::data_directory::nodes_dir;
 use interstice_core::{IntersticeError, Node, NodeId};
+use std::path::Path;
 
-pub async fn start(id: NodeId, port: u32, public_address: String) -> Result<(), IntersticeError> {
+/// Start a local node. With `record`, its inputs are recorded to that file until
+/// the node is stopped with Ctrl-C.
+pub async fn start(
+    id: NodeId,
+    port: u32,
+    public_address: String,
+    record: Option<&Path>,
+) -> Result<(), IntersticeError> {
     let node = Node::load(&nodes_dir(), id, port, public_address).await?;
+    if let Some(path) = record {
+        let recording = node.record(path)?;
+        let path = path.to_path_buf();
+        tokio::spawn(async move {
+            if tokio::signal::ctrl_c().await.is_err() {
+                return;
+            }
+            match recording.finish() {
+                Ok(state_hash) => println!(
+                    "Recording saved to {} (state hash {:016x})",
+                    path.display(),
+                    state_hash
+                ),
+                Err(err) => eprintln!("Failed to finish the recording: {}", err),
+            }
+            std::process::exit(0);
+        });
+    }
     node.start(&[]).await?;
     Ok(())
 }
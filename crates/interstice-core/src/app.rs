@@ -7,7 +7,7 @@ use crate::{
             gpu::{GpuCallRequest, GpuCallResult, GpuState},
             input::from_winit::get_input_event_from_device_event,
         },
-        reducer::{CompletionToken, ReducerJob},
+        reducer::{CompletionToken, JobSource, ReducerJob},
     },
 };
 use interstice_abi::{Authority, IntersticeValue};
@@ -113,7 +113,11 @@ impl ApplicationHandler for App {
                         input: IntersticeValue::Vec(vec![]),
                         caller_node_id: self.node_id,
                         caller_module_name: String::new(),
+                        request_id: None,
                         completion: Some(token),
+                        outcome: None,
+                        source: JobSource::Internal,
+                        rng_seed: None,
                     });
                     self.wait_for_render_completion(done_rx);
                 }
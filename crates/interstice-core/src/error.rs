@@ -1,6 +1,6 @@
 use std::fmt;
 
-use interstice_abi::{Authority, Version};
+use interstice_abi::{Authority, Version, VersionReq};
 
 use crate::node::NodeId;
 
@@ -13,8 +13,14 @@ pub enum IntersticeError {
     Unauthorized(Authority),
     // ─── Module / Reducer resolution ──────────────────────────────────────
     ModuleAlreadyExists(String),
+    InvalidModuleName(String),
     ModuleNotFound(String, String),
-    ModuleVersionMismatch(String, String, Version, Version),
+    ModuleVersionMismatch(String, String, VersionReq, Version),
+    IncompatibleDependency {
+        module: String,
+        dependency: String,
+        reason: String,
+    },
 
     TableNotFound {
         module_name: String,
@@ -39,10 +45,43 @@ pub enum IntersticeError {
         table_name: String,
         field_name: String,
     },
+    InvalidTableQuery {
+        table_name: String,
+        reason: String,
+    },
     AutoIncUpdateNotAllowed {
         table_name: String,
         field_name: String,
     },
+    TableQuotaExceeded {
+        table_name: String,
+        quota: String,
+    },
+    SchemaMigrationFailed {
+        module_name: String,
+        table_name: String,
+        reason: String,
+    },
+    InvalidTableView {
+        module_name: String,
+        table_name: String,
+        reason: String,
+    },
+    InvalidTableIndex {
+        module_name: String,
+        table_name: String,
+        index_name: String,
+        reason: String,
+    },
+    InvalidAccessPolicy {
+        module_name: String,
+        reason: String,
+    },
+    InvalidSubscription {
+        module_name: String,
+        reducer_name: String,
+        reason: String,
+    },
     InvalidRow {
         module: String,
         table: String,
@@ -51,6 +90,11 @@ pub enum IntersticeError {
         module: String,
         reducer: String,
     },
+    TransactionAborted {
+        module: String,
+        reducer: String,
+        reason: String,
+    },
 
     // ─── WASM loading / linking ────────────────────────────────────────────
     MissingExport(&'static str),
@@ -64,6 +108,15 @@ pub enum IntersticeError {
 
     // ─── WASM execution ────────────────────────────────────────────────────
     WasmTrap(String),
+    FuelExhausted {
+        module: String,
+        function: String,
+        fuel: u64,
+    },
+    MemoryLimitExceeded {
+        module: String,
+        limit_mb: u32,
+    },
 
     // ─── Memory handling ───────────────────────────────────────────────────
     MemoryRead,
@@ -73,6 +126,13 @@ pub enum IntersticeError {
     NetworkSendFailed,
     UnknownPeer,
     ProtocolError(String),
+    /// A packet larger than the receiving side accepts.
+    FrameTooLarge {
+        size: u64,
+        limit: u32,
+    },
+    /// A frame was received whole but its packet could not be decoded.
+    PacketDecodeFailed(String),
 
     // ─── Internal invariants ───────────────────────────────────────────────
     Internal(String),
@@ -97,13 +157,31 @@ impl fmt::Display for IntersticeError {
                     "module '{}' requires module {} with version {} but is {}",
                     module_name,
                     dependency_module_name,
-                    Into::<String>::into(req_version.clone()),
+                    req_version,
                     Into::<String>::into(actual_version.clone())
                 )
             }
+            IncompatibleDependency {
+                module,
+                dependency,
+                reason,
+            } => {
+                write!(
+                    f,
+                    "module '{}' is incompatible with its dependency '{}': {}",
+                    module, dependency, reason
+                )
+            }
             ModuleAlreadyExists(name) => {
                 write!(f, "module '{}' already exists", name)
             }
+            InvalidModuleName(name) => {
+                write!(
+                    f,
+                    "invalid module name '{}': expected a single path segment",
+                    name
+                )
+            }
             AuthorityAlreadyTaken(name, authority, in_place_module_name) => {
                 write!(
                     f,
@@ -154,6 +232,9 @@ impl fmt::Display for IntersticeError {
                     table_name, field_name
                 )
             }
+            InvalidTableQuery { table_name, reason } => {
+                write!(f, "invalid query on table '{}': {}", table_name, reason)
+            }
             AutoIncUpdateNotAllowed {
                 table_name,
                 field_name,
@@ -164,6 +245,64 @@ impl fmt::Display for IntersticeError {
                     field_name, table_name
                 )
             }
+            TableQuotaExceeded { table_name, quota } => {
+                write!(f, "table '{}' quota exceeded ({})", table_name, quota)
+            }
+            SchemaMigrationFailed {
+                module_name,
+                table_name,
+                reason,
+            } => {
+                write!(
+                    f,
+                    "cannot migrate table '{}' of module '{}': {}",
+                    table_name, module_name, reason
+                )
+            }
+            InvalidTableView {
+                module_name,
+                table_name,
+                reason,
+            } => {
+                write!(
+                    f,
+                    "invalid view on table '{}' of module '{}': {}",
+                    table_name, module_name, reason
+                )
+            }
+            InvalidTableIndex {
+                module_name,
+                table_name,
+                index_name,
+                reason,
+            } => {
+                write!(
+                    f,
+                    "invalid index '{}' on table '{}' of module '{}': {}",
+                    index_name, table_name, module_name, reason
+                )
+            }
+            InvalidAccessPolicy {
+                module_name,
+                reason,
+            } => {
+                write!(
+                    f,
+                    "invalid access policy in module '{}': {}",
+                    module_name, reason
+                )
+            }
+            InvalidSubscription {
+                module_name,
+                reducer_name,
+                reason,
+            } => {
+                write!(
+                    f,
+                    "invalid subscription of reducer '{}' in module '{}': {}",
+                    reducer_name, module_name, reason
+                )
+            }
             TableNotFound {
                 module_name: module,
                 table_name: table,
@@ -187,6 +326,17 @@ impl fmt::Display for IntersticeError {
                     module, reducer
                 )
             }
+            TransactionAborted {
+                module,
+                reducer,
+                reason,
+            } => {
+                write!(
+                    f,
+                    "atomic reducer '{}::{}' rolled back: {}",
+                    module, reducer, reason
+                )
+            }
             MissingExport(name) => {
                 write!(f, "missing required wasm export '{}'", name)
             }
@@ -209,6 +359,24 @@ impl fmt::Display for IntersticeError {
             WasmTrap(msg) => {
                 write!(f, "wasm trapped: {}", msg)
             }
+            FuelExhausted {
+                module,
+                function,
+                fuel,
+            } => {
+                write!(
+                    f,
+                    "'{}::{}' exhausted its fuel budget of {}",
+                    module, function, fuel
+                )
+            }
+            MemoryLimitExceeded { module, limit_mb } => {
+                write!(
+                    f,
+                    "module '{}' exceeded its memory limit of {} MiB",
+                    module, limit_mb
+                )
+            }
             MemoryRead => {
                 write!(f, "failed to read from wasm memory")
             }
@@ -224,6 +392,12 @@ impl fmt::Display for IntersticeError {
             ProtocolError(msg) => {
                 write!(f, "Network protocol error: {msg}")
             }
+            FrameTooLarge { size, limit } => {
+                write!(f, "packet of {size} bytes exceeds the frame limit of {limit} bytes")
+            }
+            PacketDecodeFailed(msg) => {
+                write!(f, "failed to decode packet: {msg}")
+            }
             Internal(msg) => {
                 write!(f, "internal error: {}", msg)
             }
@@ -5,10 +5,14 @@ mod logger;
 mod network;
 mod node;
 pub mod persistence;
+pub mod recording;
 mod runtime;
+pub mod testing;
 
 pub use crate::node::{Node, NodeId};
 pub use error::*;
 pub use interstice_abi;
+pub use network::config;
 pub use network::packet;
+pub use network::secure;
 pub use network::protocol::*;
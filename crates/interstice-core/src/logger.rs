@@ -15,6 +15,13 @@ impl Logger {
         }
     }
 
+    /// Logger writing to `sink` instead of a log file.
+    pub fn with_sink(sink: impl Write + Send + 'static) -> Self {
+        Self {
+            log_sink: Arc::new(Mutex::new(Box::new(sink))),
+        }
+    }
+
     pub fn log(&self, message: &str, source: LogSource, level: LogLevel) {
         let mut file = self.log_sink.lock();
         let message = format!("[{}] [{}] {}", source, level, message);
@@ -0,0 +1,169 @@
+//! Network settings, from the optional `network.toml` of the node data directory:
+//!
+//! ```toml
+//! ping_interval_ms = 5000
+//! peer_timeout_ms = 15000
+//! reconnect_min_delay_ms = 500
+//! reconnect_max_delay_ms = 30000
+//! max_frame_bytes = 33554432
+//! compression = true
+//! compression_min_bytes = 16384
+//! ```
+//!
+//! Every connection pings its peer each `ping_interval_ms` and is closed when
+//! nothing was received from the peer for `peer_timeout_ms`. Nodes that loaded
+//! modules depend on are dialed again after a disconnection, waiting
+//! `reconnect_min_delay_ms` before the first attempt and doubling the delay after
+//! each failure, up to `reconnect_max_delay_ms`.
+//!
+//! `max_frame_bytes` is the largest packet the node accepts, checked before the
+//! frame is read. It is announced in the handshake so peers refuse to send larger
+//! packets instead of losing the connection. With `compression`, packets of at
+//! least `compression_min_bytes` (table syncs mostly) are compressed for the
+//! peers that support it.
+
+use crate::error::IntersticeError;
+use serde::Deserialize;
+use std::path::Path;
+use std::time::Duration;
+
+/// Name of the optional network settings file in the node data directory.
+pub const NETWORK_CONFIG: &str = "network.toml";
+
+/// Smallest `max_frame_bytes`, leaving room for handshakes and error replies.
+pub const MIN_FRAME_BYTES: u32 = 64 * 1024;
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct NetworkConfigFile {
+    ping_interval_ms: u64,
+    peer_timeout_ms: u64,
+    reconnect_min_delay_ms: u64,
+    reconnect_max_delay_ms: u64,
+    max_frame_bytes: u32,
+    compression: bool,
+    compression_min_bytes: u32,
+}
+
+impl Default for NetworkConfigFile {
+    fn default() -> Self {
+        Self {
+            ping_interval_ms: 5_000,
+            peer_timeout_ms: 15_000,
+            reconnect_min_delay_ms: 500,
+            reconnect_max_delay_ms: 30_000,
+            max_frame_bytes: 32 * 1024 * 1024,
+            compression: true,
+            compression_min_bytes: 16 * 1024,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NetworkConfig {
+    pub ping_interval: Duration,
+    pub peer_timeout: Duration,
+    pub reconnect_min_delay: Duration,
+    pub reconnect_max_delay: Duration,
+    pub max_frame_bytes: u32,
+    pub compression: bool,
+    pub compression_min_bytes: u32,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self::from_file(NetworkConfigFile::default())
+    }
+}
+
+impl NetworkConfig {
+    /// Load `network.toml` from the node data directory, defaults without it.
+    pub fn load(data_path: &Path) -> Result<Self, IntersticeError> {
+        let config_path = data_path.join(NETWORK_CONFIG);
+        if !config_path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = std::fs::read_to_string(&config_path).map_err(|err| {
+            IntersticeError::Internal(format!(
+                "Failed to read {}: {err}",
+                config_path.display()
+            ))
+        })?;
+        Self::parse(&contents).map_err(|err| {
+            IntersticeError::Internal(format!(
+                "Failed to parse {}: {err}",
+                config_path.display()
+            ))
+        })
+    }
+
+    fn parse(contents: &str) -> Result<Self, String> {
+        let file: NetworkConfigFile = toml::from_str(contents).map_err(|err| err.to_string())?;
+        if file.ping_interval_ms == 0 || file.reconnect_min_delay_ms == 0 {
+            return Err("ping_interval_ms and reconnect_min_delay_ms must be positive".into());
+        }
+        if file.peer_timeout_ms <= file.ping_interval_ms {
+            return Err("peer_timeout_ms must be longer than ping_interval_ms".into());
+        }
+        if file.reconnect_max_delay_ms < file.reconnect_min_delay_ms {
+            return Err("reconnect_max_delay_ms must not be shorter than reconnect_min_delay_ms".into());
+        }
+        if file.max_frame_bytes < MIN_FRAME_BYTES {
+            return Err(format!("max_frame_bytes must be at least {}", MIN_FRAME_BYTES));
+        }
+        Ok(Self::from_file(file))
+    }
+
+    fn from_file(file: NetworkConfigFile) -> Self {
+        Self {
+            ping_interval: Duration::from_millis(file.ping_interval_ms),
+            peer_timeout: Duration::from_millis(file.peer_timeout_ms),
+            reconnect_min_delay: Duration::from_millis(file.reconnect_min_delay_ms),
+            reconnect_max_delay: Duration::from_millis(file.reconnect_max_delay_ms),
+            max_frame_bytes: file.max_frame_bytes,
+            compression: file.compression,
+            compression_min_bytes: file.compression_min_bytes,
+        }
+    }
+
+    /// Delay before the next reconnection attempt, after waiting `delay`.
+    pub fn next_reconnect_delay(&self, delay: Duration) -> Duration {
+        (delay * 2).min(self.reconnect_max_delay)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_settings_keep_their_default() {
+        let config = NetworkConfig::parse("peer_timeout_ms = 4000\nping_interval_ms = 1000").unwrap();
+        assert_eq!(config.ping_interval, Duration::from_secs(1));
+        assert_eq!(config.peer_timeout, Duration::from_secs(4));
+        assert_eq!(config.reconnect_min_delay, NetworkConfig::default().reconnect_min_delay);
+        assert_eq!(NetworkConfig::parse("").unwrap(), NetworkConfig::default());
+    }
+
+    #[test]
+    fn inconsistent_settings_are_rejected() {
+        assert!(NetworkConfig::parse("ping_interval_ms = 0").is_err());
+        assert!(NetworkConfig::parse("peer_timeout_ms = 5000").is_err());
+        assert!(NetworkConfig::parse("reconnect_max_delay_ms = 100").is_err());
+        assert!(NetworkConfig::parse("ping_interval = 5").is_err());
+        assert!(NetworkConfig::parse("max_frame_bytes = 1024").is_err());
+    }
+
+    #[test]
+    fn reconnect_delay_doubles_up_to_the_maximum() {
+        let config = NetworkConfig::default();
+        let mut delay = config.reconnect_min_delay;
+        let mut delays = Vec::new();
+        for _ in 0..8 {
+            delays.push(delay.as_millis());
+            delay = config.next_reconnect_delay(delay);
+        }
+        assert_eq!(delays, vec![500, 1000, 2000, 4000, 8000, 16000, 30000, 30000]);
+    }
+}
@@ -1,25 +1,30 @@
 use crate::error::IntersticeError;
 use crate::logger::{LogLevel, LogSource, Logger};
-use crate::network::peer::PeerHandle;
-use crate::network::protocol::NetworkPacket;
+use crate::network::config::NetworkConfig;
+use crate::network::peer::{PeerHandle, PeerLink};
+use crate::network::protocol::{NetworkPacket, RemoteError};
 use crate::node::NodeId;
-use crate::persistence::PeerTokenStore;
+use crate::persistence::{NodeIdentity, PeerKeyStore, PeerTokenStore};
+use crate::runtime::access::{AccessCallKind, AccessGuard};
 use crate::runtime::event::EventInstance;
-use crate::runtime::reducer::ReducerJob;
+use crate::runtime::reducer::{JobSource, ReducerJob};
 use crossbeam_channel::Sender as CbSender;
-use interstice_abi::{NodeSelection, SubscriptionEventSchema};
-use packet::{read_packet, write_packet};
+use interstice_abi::{NodeSelection, ReducerCallStatus, SubscriptionEventSchema};
 use parking_lot::Mutex;
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Instant;
+use secure::{SecureReader, SecureWriter, secure_handshake};
 use tokio::net::{TcpListener, TcpStream};
 use tokio::sync::{mpsc, watch};
 use tokio::task::JoinHandle;
 use uuid::Uuid;
 
+pub mod config;
 pub mod packet;
 mod peer;
 pub mod protocol;
+pub mod secure;
 
 const CHANNEL_SIZE: usize = 1000;
 
@@ -29,6 +34,10 @@ pub struct Network {
     public_address: String,
     peers: Arc<Mutex<HashMap<NodeId, PeerHandle>>>,
     peer_tokens: Arc<Mutex<PeerTokenStore>>,
+    identity: Arc<NodeIdentity>,
+    peer_keys: Arc<Mutex<PeerKeyStore>>,
+    /// Checks remote reducer calls against module access policies before queuing them.
+    access_guard: Arc<AccessGuard>,
     runtime_event_sender: mpsc::UnboundedSender<(
         EventInstance,
         Option<crate::runtime::reducer::CompletionToken>,
@@ -36,6 +45,7 @@ pub struct Network {
     /// Direct bounded channel to the reducer executor — bypasses the event channel
     /// to prevent unbounded buffering of remote reducer calls.
     reducer_sender: CbSender<ReducerJob>,
+    config: NetworkConfig,
     logger: Logger,
 
     /// Packets coming *from* connection tasks
@@ -51,12 +61,16 @@ pub struct NetworkHandle {
     pub address: String,
     peers: Arc<Mutex<HashMap<NodeId, PeerHandle>>>,
     peer_tokens: Arc<Mutex<PeerTokenStore>>,
+    identity: Arc<NodeIdentity>,
+    peer_keys: Arc<Mutex<PeerKeyStore>>,
+    access_guard: Arc<AccessGuard>,
     packet_sender: mpsc::Sender<(NodeId, NetworkPacket)>,
     runtime_event_sender: mpsc::UnboundedSender<(
         EventInstance,
         Option<crate::runtime::reducer::CompletionToken>,
     )>,
     reducer_sender: CbSender<ReducerJob>,
+    pub config: NetworkConfig,
     logger: Logger,
     /// Stored at construction time (from async context) so that `send_packet` can
     /// call `tokio::spawn` even when invoked from a non-tokio OS thread (e.g. wasm-reducer-*).
@@ -64,37 +78,24 @@ pub struct NetworkHandle {
 }
 
 impl NetworkHandle {
-    pub async fn connect_to_peer(&mut self, node_address: String) {
+    pub async fn connect_to_peer(&mut self, node_address: String) -> Result<(), IntersticeError> {
         if self.address == node_address {
             self.logger.log(
                 "Cannot connect to self, skipping",
                 LogSource::Network,
                 LogLevel::Warning,
             );
-            return;
+            return Ok(());
         }
         let mut cloned_peers = self.peers.clone();
         let packet_sender = self.packet_sender.clone();
         let address = self.address.clone();
         let my_node_id = self.node_id.clone();
-        let mut stream = TcpStream::connect(&node_address)
+        let stream = TcpStream::connect(&node_address)
             .await
-            .map_err(|_| IntersticeError::Internal("Failed to connect to node".into()))
-            .unwrap();
-
-        let local_token = { self.peer_tokens.lock().local_token() };
-
-        // Send our handshake
-        write_packet(
-            &mut stream,
-            &NetworkPacket::Handshake {
-                address: address.clone(),
-                node_id: my_node_id.to_string(),
-                token: local_token,
-            },
-        )
-        .await
-        .unwrap();
+            .map_err(|err| {
+                IntersticeError::Internal(format!("Failed to connect to node {}: {}", node_address, err))
+            })?;
 
         if let Err(e) = handshake_incoming(
             my_node_id,
@@ -106,7 +107,11 @@ impl NetworkHandle {
             self.reducer_sender.clone(),
             self.logger.clone(),
             self.peer_tokens.clone(),
-            false,
+            self.identity.clone(),
+            self.peer_keys.clone(),
+            self.access_guard.clone(),
+            self.config,
+            true,
         )
         .await
         {
@@ -115,22 +120,24 @@ impl NetworkHandle {
                 LogSource::Network,
                 LogLevel::Error,
             );
+            return Err(e);
         }
+        Ok(())
     }
 
+    /// Queue a packet for a peer. Packets to the same peer keep their order unless
+    /// its queue is full; table replicas detect the reordering and resync.
     pub fn send_packet(&self, node_id: NodeId, packet: NetworkPacket) {
-        let peers = self.peers.clone();
-        // Use the stored handle so this works even when called from a non-tokio thread
-        // (e.g. wasm-reducer-* OS threads).
-        self.tokio_handle.spawn(async move {
-            let peer = {
-                let peers = peers.lock();
-                peers.get(&node_id).cloned()
-            };
-            if let Some(peer) = peer {
+        let Some(peer) = self.peers.lock().get(&node_id).cloned() else {
+            return;
+        };
+        if let Err(mpsc::error::TrySendError::Full(packet)) = peer.sender.try_send(packet) {
+            // Use the stored handle so this works even when called from a non-tokio thread
+            // (e.g. wasm-reducer-* OS threads).
+            self.tokio_handle.spawn(async move {
                 let _ = peer.send(packet).await;
-            }
-        });
+            });
+        }
     }
 
     pub fn get_node_id_from_adress(&self, address: &String) -> Result<NodeId, IntersticeError> {
@@ -157,6 +164,10 @@ impl Network {
         )>,
         reducer_sender: CbSender<ReducerJob>,
         peer_tokens: Arc<Mutex<PeerTokenStore>>,
+        identity: Arc<NodeIdentity>,
+        peer_keys: Arc<Mutex<PeerKeyStore>>,
+        access_guard: Arc<AccessGuard>,
+        config: NetworkConfig,
         logger: Logger,
     ) -> Self {
         let (sender, receiver) = mpsc::channel(CHANNEL_SIZE);
@@ -167,10 +178,14 @@ impl Network {
             public_address,
             peers: Arc::new(Mutex::new(HashMap::new())),
             peer_tokens,
+            identity,
+            peer_keys,
+            access_guard,
             packet_receiver: receiver,
             packet_sender: sender,
             runtime_event_sender: event_sender,
             reducer_sender,
+            config,
             logger,
         }
     }
@@ -181,9 +196,13 @@ impl Network {
             address: self.public_address.clone(),
             peers: self.peers.clone(),
             peer_tokens: self.peer_tokens.clone(),
+            identity: self.identity.clone(),
+            peer_keys: self.peer_keys.clone(),
+            access_guard: self.access_guard.clone(),
             packet_sender: self.packet_sender.clone(),
             runtime_event_sender: self.runtime_event_sender.clone(),
             reducer_sender: self.reducer_sender.clone(),
+            config: self.config,
             logger: self.logger.clone(),
             tokio_handle: tokio::runtime::Handle::current(),
         }
@@ -195,9 +214,13 @@ impl Network {
         let event_sender = self.runtime_event_sender.clone();
         let reducer_sender = self.reducer_sender.clone();
         let peer_tokens = self.peer_tokens.clone();
+        let identity = self.identity.clone();
+        let peer_keys = self.peer_keys.clone();
+        let access_guard = self.access_guard.clone();
         let bind_address = self.bind_address.clone();
         let public_address = self.public_address.clone();
         let my_node_id = self.node_id.clone();
+        let config = self.config;
         let logger = self.logger.clone();
         let listener = TcpListener::bind(&bind_address).await.map_err(|err| {
             IntersticeError::Internal(format!(
@@ -220,6 +243,9 @@ impl Network {
                         let public_address = public_address.clone();
                         let logger = logger.clone();
                         let peer_tokens = peer_tokens.clone();
+                        let identity = identity.clone();
+                        let peer_keys = peer_keys.clone();
+                        let access_guard = access_guard.clone();
                         let reducer_sender = reducer_sender.clone();
                         tokio::spawn(async move {
                             if let Err(e) = handshake_incoming(
@@ -232,7 +258,11 @@ impl Network {
                                 reducer_sender,
                                 logger.clone(),
                                 peer_tokens.clone(),
-                                true,
+                                identity,
+                                peer_keys,
+                                access_guard,
+                                config,
+                                false,
                             )
                             .await
                             {
@@ -264,19 +294,19 @@ impl Network {
         return tokio::spawn(async move {
             while let Some((node_id, packet)) = self.packet_receiver.recv().await {
                 match packet {
-                    NetworkPacket::Handshake { .. } => {
+                    NetworkPacket::Handshake { .. } | NetworkPacket::HandshakeAuth { .. } => {
                         self.logger.log(
                             &format!("Received unexpected handshake from {}", node_id),
                             LogSource::Network,
                             LogLevel::Warning,
                         );
                     }
-                    NetworkPacket::Close => {
-                        self.disconnect_peer_remote(node_id);
-                    }
-                    NetworkPacket::ReducerCall { .. } => {
-                        // ReducerCall is now dispatched directly in connection_task's read_loop
-                        // (via reducer_sender) — this path should not be reached.
+                    NetworkPacket::Close
+                    | NetworkPacket::Ping
+                    | NetworkPacket::Pong
+                    | NetworkPacket::ReducerCall { .. } => {
+                        // Handled by connection_task's read_loop (ReducerCall dispatched
+                        // directly via reducer_sender) — this path should not be reached.
                     }
                     NetworkPacket::QueryCall {
                         request_id,
@@ -296,6 +326,13 @@ impl Network {
                             None,
                         ))
                         .unwrap(),
+                    NetworkPacket::ReducerResult { request_id, status } => self
+                        .runtime_event_sender
+                        .send((
+                            EventInstance::RemoteReducerResult { request_id, status },
+                            None,
+                        ))
+                        .unwrap(),
                     NetworkPacket::QueryResponse { request_id, result } => self
                         .runtime_event_sender
                         .send((
@@ -331,6 +368,7 @@ impl Network {
                                         }
                                     }
                                 },
+                                filter: request_subscription.filter,
                             },
                             None,
                         ))
@@ -363,6 +401,7 @@ impl Network {
                                         }
                                     }
                                 },
+                                filter: request_subscription.filter,
                             },
                             None,
                         ))
@@ -370,6 +409,8 @@ impl Network {
                     NetworkPacket::RequestTableSync {
                         module_name,
                         table_name,
+                        filter,
+                        since,
                     } => self
                         .runtime_event_sender
                         .send((
@@ -377,6 +418,8 @@ impl Network {
                                 requesting_node_id: node_id,
                                 module_name,
                                 table_name,
+                                filter,
+                                since,
                             },
                             None,
                         ))
@@ -384,6 +427,7 @@ impl Network {
                     NetworkPacket::TableSyncResponse {
                         module_name,
                         table_name,
+                        position,
                         rows,
                     } => self
                         .runtime_event_sender
@@ -392,51 +436,42 @@ impl Network {
                                 source_node_id: node_id,
                                 module_name,
                                 table_name,
+                                position,
                                 rows,
                             },
                             None,
                         ))
                         .unwrap(),
-                    NetworkPacket::TableEvent(subscription_event) => {
-                        let event = match subscription_event {
-                            protocol::TableEventInstance::TableInsertEvent {
-                                module_name,
-                                table_name,
-                                inserted_row,
-                            } => EventInstance::TableInsertEvent {
-                                source_node_id: Some(node_id),
-                                module_name,
-                                table_name,
-                                inserted_row,
-                            },
-                            protocol::TableEventInstance::TableUpdateEvent {
-                                module_name,
-                                table_name,
-                                old_row,
-                                new_row,
-                            } => EventInstance::TableUpdateEvent {
-                                source_node_id: Some(node_id),
-                                module_name,
-                                table_name,
-                                old_row,
-                                new_row,
-                            },
-                            protocol::TableEventInstance::TableDeleteEvent {
-                                module_name,
-                                table_name,
-                                deleted_row,
-                            } => EventInstance::TableDeleteEvent {
-                                source_node_id: Some(node_id),
+                    NetworkPacket::TableChangesResponse {
+                        module_name,
+                        table_name,
+                        since,
+                        position,
+                        events,
+                    } => self
+                        .runtime_event_sender
+                        .send((
+                            EventInstance::RemoteTableChanges {
+                                source_node_id: node_id,
                                 module_name,
                                 table_name,
-                                deleted_row,
+                                since,
+                                position,
+                                events: events
+                                    .into_iter()
+                                    .map(|event| remote_table_event(node_id, event))
+                                    .collect(),
                             },
-                        };
+                            None,
+                        ))
+                        .unwrap(),
+                    NetworkPacket::TableEvent(subscription_event) => {
+                        let event = remote_table_event(node_id, subscription_event);
                         self.runtime_event_sender.send((event, None)).unwrap();
                     }
                     NetworkPacket::Error(err) => {
                         self.logger.log(
-                            &format!("Received error from {}: {}", node_id, err),
+                            &format!("Received error from {} ({:?}): {}", node_id, err.code(), err),
                             LogSource::Network,
                             LogLevel::Error,
                         );
@@ -453,6 +488,10 @@ impl Network {
                                     None,
                                 ))
                                 .unwrap(),
+                            protocol::ModuleEventInstance::Upgrade { wasm_binary } => self
+                                .runtime_event_sender
+                                .send((EventInstance::UpgradeModule { wasm_binary }, None))
+                                .unwrap(),
                             protocol::ModuleEventInstance::Unload { module_name } => self
                                 .runtime_event_sender
                                 .send((
@@ -499,28 +538,6 @@ impl Network {
             }
         });
     }
-
-    fn disconnect_peer_remote(&self, node_id: NodeId) {
-        let peer = { self.peers.lock().remove(&node_id) };
-
-        if let Some(peer) = peer {
-            peer.close();
-            self.logger.log(
-                &format!("Peer {} disconnected", node_id),
-                LogSource::Network,
-                LogLevel::Info,
-            );
-            let _ = self
-                .runtime_event_sender
-                .send((EventInstance::NodeDisconnect { node_id }, None));
-        } else {
-            self.logger.log(
-                &format!("Received close from unknown peer {}", node_id),
-                LogSource::Network,
-                LogLevel::Info,
-            );
-        }
-    }
 }
 
 //
@@ -528,21 +545,33 @@ impl Network {
 //
 
 async fn connection_task(
-    node_id: NodeId,
-    stream: TcpStream,
+    link: PeerLink,
+    mut reader: SecureReader,
+    mut writer: SecureWriter,
     mut receiver: mpsc::Receiver<NetworkPacket>,
+    peer_sender: mpsc::Sender<NetworkPacket>,
     sender: mpsc::Sender<(NodeId, NetworkPacket)>,
     reducer_sender: CbSender<ReducerJob>,
+    access_guard: Arc<AccessGuard>,
     close_receiver: watch::Receiver<bool>,
+    config: NetworkConfig,
     logger: Logger,
 ) {
-    let (mut reader, mut writer) = stream.into_split();
+    let node_id = link.node_id;
     let write_logger = logger.clone();
-    let read_logger = logger;
+    let read_logger = logger.clone();
+    let health_logger = logger;
     let mut write_close = close_receiver.clone();
-    let mut read_close = close_receiver;
-    let write_sender = sender.clone();
+    let mut read_close = close_receiver.clone();
+    let mut health_close = close_receiver;
+    let write_link = link.clone();
+    let read_link = link.clone();
+    let health_link = link;
     let read_sender = sender;
+    let ping_sender = peer_sender.clone();
+    // Last time anything was received from the peer.
+    let last_seen = Arc::new(Mutex::new(Instant::now()));
+    let read_last_seen = last_seen.clone();
 
     let write_loop = tokio::spawn(async move {
         loop {
@@ -555,15 +584,25 @@ async fn connection_task(
                 packet = receiver.recv() => {
                     match packet {
                         Some(packet) => {
-                            if let Err(e) = write_packet(&mut writer, &packet).await {
+                            let mut result = writer.write_packet(&packet).await;
+                            if let Err(err @ IntersticeError::FrameTooLarge { .. }) = &result {
+                                // Nothing was written: tell the peer its request
+                                // won't be answered and keep the connection.
+                                write_logger.log(
+                                    &format!("Packet to {} dropped: {}", node_id, err),
+                                    LogSource::Network,
+                                    LogLevel::Error,
+                                );
+                                let error = NetworkPacket::Error(RemoteError::from(err));
+                                result = writer.write_packet(&error).await;
+                            }
+                            if let Err(e) = result {
                                 write_logger.log(
                                     &format!("Write error to {}: {:?}", node_id, e),
                                     LogSource::Network,
                                     LogLevel::Error,
                                 );
-                                let _ = write_sender
-                                    .send((node_id, NetworkPacket::Close))
-                                    .await;
+                                write_link.close("write error");
                                 break;
                             }
                         }
@@ -582,9 +621,25 @@ async fn connection_task(
                         break;
                     }
                 }
-                packet = read_packet(&mut reader) => {
+                packet = reader.read_packet() => {
+                    if matches!(packet, Ok(_) | Err(IntersticeError::PacketDecodeFailed(_))) {
+                        *read_last_seen.lock() = Instant::now();
+                    }
                     match packet {
-                        Ok(NetworkPacket::ReducerCall { module_name, reducer_name, input }) => {
+                        Ok(NetworkPacket::Ping) => {
+                            let _ = peer_sender.try_send(NetworkPacket::Pong);
+                        }
+                        Ok(NetworkPacket::Pong) => {}
+                        Ok(NetworkPacket::Close) => {
+                            read_link.close("closed by peer");
+                            break;
+                        }
+                        Ok(NetworkPacket::ReducerCall {
+                            request_id,
+                            module_name,
+                            reducer_name,
+                            input,
+                        }) => {
                             // Hot path: bypass the intermediate packet channel — dispatch
                             // directly to the crossbeam reducer queue for zero-copy dispatch.
                             // Must use blocking `send`: `try_send` dropped work when the queue
@@ -596,18 +651,84 @@ async fn connection_task(
                                 input,
                                 caller_node_id: node_id,
                                 caller_module_name: String::new(),
+                                request_id,
                                 completion: None,
+                                outcome: None,
+                                source: JobSource::Input,
+                                rng_seed: None,
                             };
+                            // The policy may call a query of the module and the send may
+                            // block, so both run on the blocking pool. Awaiting them keeps
+                            // the peer's calls in order.
+                            let guard = access_guard.clone();
                             let sender = reducer_sender.clone();
-                            tokio::task::block_in_place(move || {
+                            let dispatched = tokio::task::spawn_blocking(move || {
+                                if let Err(denied) = guard.check(
+                                    &job.module_name,
+                                    &job.reducer_name,
+                                    AccessCallKind::Reducer,
+                                    job.caller_node_id,
+                                ) {
+                                    return Err((denied, job.request_id));
+                                }
                                 let _ = sender.send(job);
-                            });
+                                Ok(())
+                            })
+                            .await;
+                            match dispatched {
+                                Ok(Ok(())) => {}
+                                Ok(Err((denied, request_id))) => {
+                                    read_logger.log(
+                                        &format!("Remote reducer call from {} refused: {}", node_id, denied),
+                                        LogSource::Network,
+                                        LogLevel::Warning,
+                                    );
+                                    let reply = match request_id {
+                                        Some(request_id) => NetworkPacket::ReducerResult {
+                                            request_id,
+                                            status: ReducerCallStatus::Failed {
+                                                error: denied.to_string(),
+                                            },
+                                        },
+                                        None => NetworkPacket::Error(denied),
+                                    };
+                                    let _ = peer_sender.send(reply).await;
+                                }
+                                Err(err) => {
+                                    read_logger.log(
+                                        &format!("Remote reducer call from {} dropped: {}", node_id, err),
+                                        LogSource::Network,
+                                        LogLevel::Error,
+                                    );
+                                }
+                            }
                         }
                         Ok(packet) => {
                             if read_sender.send((node_id, packet)).await.is_err() {
                                 break;
                             }
                         }
+                        Err(err @ IntersticeError::PacketDecodeFailed(_)) => {
+                            // The frame was read whole, so the connection is still in
+                            // sync: skip the packet, e.g. one from a newer protocol.
+                            read_logger.log(
+                                &format!("Skipped packet from {}: {}", node_id, err),
+                                LogSource::Network,
+                                LogLevel::Warning,
+                            );
+                            let _ = peer_sender.try_send(NetworkPacket::Error(RemoteError::from(&err)));
+                        }
+                        Err(err @ IntersticeError::FrameTooLarge { .. }) => {
+                            read_logger.log(
+                                &format!("Frame from {} refused: {}", node_id, err),
+                                LogSource::Network,
+                                LogLevel::Error,
+                            );
+                            // Best effort: the unread frame leaves the stream out of sync.
+                            let _ = peer_sender.try_send(NetworkPacket::Error(RemoteError::from(&err)));
+                            read_link.close("frame too large");
+                            break;
+                        }
                         Err(e) => {
                             if is_disconnect_error(&e) {
                                 read_logger.log(
@@ -615,16 +736,15 @@ async fn connection_task(
                                     LogSource::Network,
                                     LogLevel::Info,
                                 );
+                                read_link.close("connection closed");
                             } else {
                                 read_logger.log(
                                     &format!("Read error from {}: {:?}", node_id, e),
                                     LogSource::Network,
                                     LogLevel::Error,
                                 );
+                                read_link.close("read error");
                             }
-                            let _ = read_sender
-                                .send((node_id, NetworkPacket::Close))
-                                .await;
                             break;
                         }
                     }
@@ -633,7 +753,100 @@ async fn connection_task(
         }
     });
 
-    let _ = tokio::join!(write_loop, read_loop);
+    // Ping the peer while it is quiet and close the connection when it stops answering.
+    let health_loop = tokio::spawn(async move {
+        let mut ticks = tokio::time::interval_at(
+            tokio::time::Instant::now() + config.ping_interval,
+            config.ping_interval,
+        );
+        ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                _ = health_close.changed() => {
+                    if *health_close.borrow() {
+                        break;
+                    }
+                }
+                _ = ticks.tick() => {
+                    let silence = last_seen.lock().elapsed();
+                    if silence >= config.peer_timeout {
+                        health_logger.log(
+                            &format!("Peer {} did not answer for {:?}", node_id, silence),
+                            LogSource::Network,
+                            LogLevel::Warning,
+                        );
+                        health_link.close("timed out");
+                        break;
+                    }
+                    if silence >= config.ping_interval {
+                        let _ = ping_sender.try_send(NetworkPacket::Ping);
+                    }
+                }
+            }
+        }
+    });
+
+    let _ = tokio::join!(write_loop, read_loop, health_loop);
+}
+
+/// Runtime event of a table event received from `node_id`.
+fn remote_table_event(node_id: NodeId, event: protocol::TableEventInstance) -> EventInstance {
+    match event {
+        protocol::TableEventInstance::TableInsertEvent {
+            filter,
+            sequence,
+            module_name,
+            table_name,
+            inserted_row,
+        } => EventInstance::TableInsertEvent {
+            source_node_id: Some(node_id),
+            filter,
+            sequence,
+            module_name,
+            table_name,
+            inserted_row,
+        },
+        protocol::TableEventInstance::TableUpdateEvent {
+            filter,
+            sequence,
+            module_name,
+            table_name,
+            old_row,
+            new_row,
+        } => EventInstance::TableUpdateEvent {
+            source_node_id: Some(node_id),
+            filter,
+            sequence,
+            module_name,
+            table_name,
+            old_row,
+            new_row,
+        },
+        protocol::TableEventInstance::TableDeleteEvent {
+            filter,
+            sequence,
+            module_name,
+            table_name,
+            deleted_row,
+        } => EventInstance::TableDeleteEvent {
+            source_node_id: Some(node_id),
+            filter,
+            sequence,
+            module_name,
+            table_name,
+            deleted_row,
+        },
+        protocol::TableEventInstance::TableEventHidden {
+            sequence,
+            module_name,
+            table_name,
+        } => EventInstance::RemoteTableEventHidden {
+            source_node_id: node_id,
+            module_name,
+            table_name,
+            sequence,
+        },
+    }
 }
 
 fn is_disconnect_error(err: &IntersticeError) -> bool {
@@ -657,7 +870,7 @@ fn is_disconnect_error(err: &IntersticeError) -> bool {
 async fn handshake_incoming(
     my_node_id: NodeId,
     my_address: String,
-    mut stream: TcpStream,
+    stream: TcpStream,
     peers: &mut Arc<Mutex<HashMap<NodeId, PeerHandle>>>,
     packet_sender: mpsc::Sender<(NodeId, NetworkPacket)>,
     runtime_event_sender: mpsc::UnboundedSender<(
@@ -667,96 +880,85 @@ async fn handshake_incoming(
     reducer_sender: CbSender<ReducerJob>,
     logger: Logger,
     peer_tokens: Arc<Mutex<PeerTokenStore>>,
-    send_handshake_response: bool,
+    identity: Arc<NodeIdentity>,
+    peer_keys: Arc<Mutex<PeerKeyStore>>,
+    access_guard: Arc<AccessGuard>,
+    config: NetworkConfig,
+    initiator: bool,
 ) -> Result<(), IntersticeError> {
-    let packet = read_packet(&mut stream).await?;
-
-    let (peer_id_str, peer_address, peer_token) = match packet {
-        NetworkPacket::Handshake {
-            node_id,
-            address,
-            token,
-        } => (node_id, address, token),
-        _ => {
-            return Err(IntersticeError::ProtocolError(
-                "Expected handshake packet".into(),
-            ));
-        }
-    };
-    let peer_id = NodeId::parse_str(&peer_id_str).expect("Couldn't parse node id");
+    let local_token = { peer_tokens.lock().local_token() };
+    // The responder replies with its handshake immediately so the remote side
+    // won't block waiting for it (prevents their read from hitting EOF if we drop
+    // the connection due to a duplicate).
+    let session = secure_handshake(
+        stream,
+        my_node_id,
+        my_address,
+        local_token,
+        &identity,
+        &peer_keys,
+        &config,
+        initiator,
+    )
+    .await?;
+    let peer_id = session.peer_id;
+    let peer_address = session.peer_address;
+    let protocol_version = session.protocol_version;
 
     {
         let mut store = peer_tokens.lock();
         if let Some(existing) = store.get_peer_token(&peer_id) {
-            if existing != peer_token {
+            if existing != session.peer_token {
                 return Err(IntersticeError::ProtocolError(format!(
                     "Peer token mismatch for {}",
                     peer_id
                 )));
             }
         } else {
-            store.set_peer_token(&peer_id, peer_token)?;
+            store.set_peer_token(&peer_id, session.peer_token)?;
         }
     }
 
-    if send_handshake_response {
-        let local_token = { peer_tokens.lock().local_token() };
-        // Reply with our handshake immediately so the remote side won't block
-        // waiting for it (prevents their read from hitting EOF if we drop
-        // the connection due to a duplicate).
-        write_packet(
-            &mut stream,
-            &NetworkPacket::Handshake {
-                node_id: my_node_id.to_string(),
-                address: my_address,
-                token: local_token,
-            },
-        )
-        .await?;
-    }
-
-    let mut peers = peers.lock();
+    // Create channel
+    let (sender, receiver) = mpsc::channel(CHANNEL_SIZE);
+    let (close_sender, close_receiver) = watch::channel(false);
 
-    // If already connected, drop duplicate
-    if peers.contains_key(&peer_id) {
+    // Register peer, dropping duplicate connections
+    let handle = PeerHandle::new(peer_id, peer_address, sender.clone(), close_sender);
+    let Some(link) = PeerLink::register(
+        handle,
+        peers.clone(),
+        runtime_event_sender,
+        logger.clone(),
+    ) else {
         logger.log(
             &format!("Duplicate incoming connection from {}, dropping", peer_id),
             LogSource::Network,
             LogLevel::Warning,
         );
         return Ok(());
-    }
-
-    // Create channel
-    let (sender, receiver) = mpsc::channel(CHANNEL_SIZE);
-    let (close_sender, close_receiver) = watch::channel(false);
-
-    // Register peer
-    let handle = PeerHandle {
-        node_id: peer_id,
-        address: peer_address,
-        sender,
-        close_sender,
     };
-    peers.insert(peer_id, handle);
 
     // Spawn connection task
     tokio::spawn(connection_task(
-        peer_id,
-        stream,
+        link,
+        session.reader,
+        session.writer,
         receiver,
+        sender,
         packet_sender.clone(),
         reducer_sender,
+        access_guard,
         close_receiver,
+        config,
         logger.clone(),
     ));
 
     logger.log(
-        &format!("Accepted peer {}", peer_id),
+        &format!("Accepted peer {} (protocol version {})", peer_id, protocol_version),
         LogSource::Network,
         LogLevel::Info,
     );
-    let _ = runtime_event_sender.send((EventInstance::NodeConnect { node_id: peer_id }, None));
 
     Ok(())
 }
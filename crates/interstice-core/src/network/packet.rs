@@ -1,36 +1,183 @@
+//! Framing of packets on a connection: a `u32` length, then the frame. The length
+//! is checked against the receiver's limit before the frame is read.
+//!
+//! Sealed frames carry a flags byte before the encoded packet, telling whether it
+//! was compressed.
+
 use crate::{error::IntersticeError, network::protocol::NetworkPacket};
+use flate2::Compression;
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
 use interstice_abi::{decode, encode};
+use std::io::{Read, Write};
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
+/// Largest frame read before the peer is authenticated.
+pub const HANDSHAKE_FRAME_LIMIT: u32 = 16 * 1024;
+
+/// Bytes a sealed frame adds to its packet: the flags byte and the AEAD tag.
+pub const SEALED_FRAME_OVERHEAD: u32 = 1 + 16;
+
+const FLAG_COMPRESSED: u8 = 1 << 0;
+
 pub async fn write_packet<W: AsyncWriteExt + Unpin>(
     writer: &mut W,
     packet: &NetworkPacket,
 ) -> Result<(), IntersticeError> {
-    let bytes = encode(packet).map_err(|err| {
-        IntersticeError::Internal(format!("Couldn't encode network packet: {}", err))
-    })?;
+    write_frame(writer, &encode_packet(packet)?).await
+}
+
+pub async fn read_packet<R: AsyncReadExt + Unpin>(
+    reader: &mut R,
+    limit: u32,
+) -> Result<NetworkPacket, IntersticeError> {
+    decode_packet(&read_frame(reader, limit).await?)
+}
+
+pub async fn write_frame<W: AsyncWriteExt + Unpin>(
+    writer: &mut W,
+    frame: &[u8],
+) -> Result<(), IntersticeError> {
     writer
-        .write_u32(bytes.len() as u32)
+        .write_u32(frame.len() as u32)
         .await
         .map_err(|err| IntersticeError::Internal(format!("Failed to write packet: {err}")))?;
     writer
-        .write_all(&bytes)
+        .write_all(frame)
         .await
         .map_err(|err| IntersticeError::Internal(format!("Failed to write packet: {err}")))?;
     Ok(())
 }
 
-pub async fn read_packet<R: AsyncReadExt + Unpin>(
+pub async fn read_frame<R: AsyncReadExt + Unpin>(
     reader: &mut R,
-) -> Result<NetworkPacket, IntersticeError> {
+    limit: u32,
+) -> Result<Vec<u8>, IntersticeError> {
     let len = reader
         .read_u32()
         .await
         .map_err(|err| IntersticeError::Internal(format!("Failed to read packet: {err}")))?;
+    if len > limit {
+        return Err(IntersticeError::FrameTooLarge {
+            size: len as u64,
+            limit,
+        });
+    }
     let mut buf = vec![0u8; len as usize];
     reader
         .read_exact(&mut buf)
         .await
         .map_err(|err| IntersticeError::Internal(format!("Failed to read packet: {err}")))?;
-    Ok(decode(&buf).map_err(|_err| IntersticeError::Internal("Failed to decode packet".into()))?)
+    Ok(buf)
+}
+
+pub fn encode_packet(packet: &NetworkPacket) -> Result<Vec<u8>, IntersticeError> {
+    encode(packet)
+        .map_err(|err| IntersticeError::Internal(format!("Couldn't encode network packet: {}", err)))
+}
+
+pub fn decode_packet(bytes: &[u8]) -> Result<NetworkPacket, IntersticeError> {
+    decode(bytes).map_err(|err| IntersticeError::PacketDecodeFailed(err.to_string()))
+}
+
+/// Plaintext of a sealed frame for the encoded packet `bytes`, compressed when it
+/// is at least `compression_min_bytes` long and compression makes it smaller.
+pub fn frame_payload(bytes: Vec<u8>, compression_min_bytes: Option<u32>) -> Vec<u8> {
+    if let Some(min_bytes) = compression_min_bytes
+        && bytes.len() >= min_bytes as usize
+    {
+        let mut encoder = DeflateEncoder::new(vec![FLAG_COMPRESSED], Compression::fast());
+        if encoder.write_all(&bytes).is_ok()
+            && let Ok(compressed) = encoder.finish()
+            && compressed.len() < bytes.len()
+        {
+            return compressed;
+        }
+    }
+    let mut payload = Vec::with_capacity(bytes.len() + 1);
+    payload.push(0);
+    payload.extend_from_slice(&bytes);
+    payload
+}
+
+/// Packet of a sealed frame plaintext. Compressed packets may not expand beyond
+/// `limit` bytes.
+pub fn decode_frame_payload(payload: &[u8], limit: u32) -> Result<NetworkPacket, IntersticeError> {
+    let Some((&flags, bytes)) = payload.split_first() else {
+        return Err(IntersticeError::PacketDecodeFailed("empty frame".into()));
+    };
+    if flags & FLAG_COMPRESSED == 0 {
+        return decode_packet(bytes);
+    }
+    let mut decompressed = Vec::new();
+    DeflateDecoder::new(bytes)
+        .take(limit as u64 + 1)
+        .read_to_end(&mut decompressed)
+        .map_err(|err| IntersticeError::PacketDecodeFailed(format!("bad compressed frame: {err}")))?;
+    if decompressed.len() > limit as usize {
+        return Err(IntersticeError::ProtocolError(format!(
+            "Compressed packet expands beyond the frame limit of {} bytes",
+            limit
+        )));
+    }
+    decode_packet(&decompressed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn large_error(len: usize) -> NetworkPacket {
+        NetworkPacket::Error("x".repeat(len).into())
+    }
+
+    fn message(packet: NetworkPacket) -> String {
+        match packet {
+            NetworkPacket::Error(err) => err.to_string(),
+            other => panic!("unexpected packet {:?}", other),
+        }
+    }
+
+    #[test]
+    fn large_payloads_are_compressed_when_enabled() {
+        let bytes = encode_packet(&large_error(100_000)).unwrap();
+        let plain = frame_payload(bytes.clone(), None);
+        let compressed = frame_payload(bytes.clone(), Some(1024));
+        assert_eq!(plain.len(), bytes.len() + 1);
+        assert!(compressed.len() < bytes.len() / 10);
+
+        let limit = bytes.len() as u32;
+        assert_eq!(message(decode_frame_payload(&plain, limit).unwrap()).len(), 100_000);
+        assert_eq!(message(decode_frame_payload(&compressed, limit).unwrap()).len(), 100_000);
+        assert!(decode_frame_payload(&compressed, limit - 1).is_err());
+    }
+
+    #[test]
+    fn small_payloads_stay_uncompressed() {
+        let bytes = encode_packet(&large_error(10)).unwrap();
+        assert_eq!(frame_payload(bytes, Some(1024))[0], 0);
+        // Kept as is when compressing does not make it smaller.
+        let varied = encode_packet(&NetworkPacket::Error(
+            "0123456789abcdefghijklmnopqrstuvwxyz".to_string().into(),
+        ))
+        .unwrap();
+        assert_eq!(frame_payload(varied, Some(0))[0], 0);
+    }
+
+    #[tokio::test]
+    async fn oversized_frames_are_refused_before_reading() {
+        let (mut client, mut server) = tokio::io::duplex(64);
+        let writer = tokio::spawn(async move {
+            let _ = write_frame(&mut client, &[0u8; 1024]).await;
+        });
+        assert!(matches!(
+            read_frame(&mut server, 512).await,
+            Err(IntersticeError::FrameTooLarge {
+                size: 1024,
+                limit: 512
+            })
+        ));
+        drop(server);
+        let _ = writer.await;
+    }
 }
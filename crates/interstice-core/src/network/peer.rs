@@ -1,15 +1,44 @@
-use crate::{error::IntersticeError, network::protocol::NetworkPacket, node::NodeId};
+use crate::{
+    error::IntersticeError,
+    logger::{LogLevel, LogSource, Logger},
+    network::protocol::NetworkPacket,
+    node::NodeId,
+    runtime::{event::EventInstance, reducer::CompletionToken},
+};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 use tokio::sync::{mpsc, watch};
 
+static NEXT_CONNECTION_ID: AtomicU64 = AtomicU64::new(0);
+
 #[derive(Clone, Debug)]
 pub struct PeerHandle {
     pub node_id: NodeId,
     pub address: String,
+    /// Tells this connection apart from earlier and later ones to the same peer.
+    pub connection_id: u64,
     pub sender: mpsc::Sender<NetworkPacket>,
     pub close_sender: watch::Sender<bool>,
 }
 
 impl PeerHandle {
+    pub fn new(
+        node_id: NodeId,
+        address: String,
+        sender: mpsc::Sender<NetworkPacket>,
+        close_sender: watch::Sender<bool>,
+    ) -> Self {
+        Self {
+            node_id,
+            address,
+            connection_id: NEXT_CONNECTION_ID.fetch_add(1, Ordering::Relaxed),
+            sender,
+            close_sender,
+        }
+    }
+
     pub async fn send(&self, packet: NetworkPacket) -> Result<(), IntersticeError> {
         self.sender
             .send(packet)
@@ -21,3 +50,76 @@ impl PeerHandle {
         let _ = self.close_sender.send(true);
     }
 }
+
+/// A registered connection, as seen by its tasks. The handshake registers the
+/// connection and fires `connect`; the first task to end the connection removes
+/// it and fires `disconnect`. A connection that was replaced in the meantime
+/// leaves the new one alone, so each connection fires both events exactly once.
+#[derive(Clone)]
+pub(crate) struct PeerLink {
+    pub node_id: NodeId,
+    connection_id: u64,
+    peers: Arc<Mutex<HashMap<NodeId, PeerHandle>>>,
+    runtime_event_sender: mpsc::UnboundedSender<(EventInstance, Option<CompletionToken>)>,
+    logger: Logger,
+}
+
+impl PeerLink {
+    /// Register `peer`, unless the node is already connected.
+    pub fn register(
+        peer: PeerHandle,
+        peers: Arc<Mutex<HashMap<NodeId, PeerHandle>>>,
+        runtime_event_sender: mpsc::UnboundedSender<(EventInstance, Option<CompletionToken>)>,
+        logger: Logger,
+    ) -> Option<Self> {
+        let link = Self {
+            node_id: peer.node_id,
+            connection_id: peer.connection_id,
+            peers,
+            runtime_event_sender,
+            logger,
+        };
+        {
+            let mut peers = link.peers.lock();
+            if peers.contains_key(&link.node_id) {
+                return None;
+            }
+            peers.insert(link.node_id, peer);
+        }
+        let _ = link.runtime_event_sender.send((
+            EventInstance::NodeConnect {
+                node_id: link.node_id,
+            },
+            None,
+        ));
+        Some(link)
+    }
+
+    /// End the connection if it is still the peer's current one.
+    pub fn close(&self, reason: &str) {
+        let peer = {
+            let mut peers = self.peers.lock();
+            match peers.get(&self.node_id) {
+                Some(peer) if peer.connection_id == self.connection_id => {
+                    peers.remove(&self.node_id)
+                }
+                _ => None,
+            }
+        };
+        let Some(peer) = peer else {
+            return;
+        };
+        peer.close();
+        self.logger.log(
+            &format!("Peer {} disconnected: {}", self.node_id, reason),
+            LogSource::Network,
+            LogLevel::Info,
+        );
+        let _ = self.runtime_event_sender.send((
+            EventInstance::NodeDisconnect {
+                node_id: self.node_id,
+            },
+            None,
+        ));
+    }
+}
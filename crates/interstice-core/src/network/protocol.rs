@@ -1,16 +1,45 @@
+use crate::error::IntersticeError;
 use interstice_abi::NodeSchema;
-use interstice_abi::{IntersticeValue, Row};
+use interstice_abi::{IntersticeValue, ReducerCallStatus, Row, RowFilter};
 use serde::{Deserialize, Serialize};
 
+/// Version of the node-to-node protocol, bumped with every incompatible change
+/// to the packets below.
+pub const PROTOCOL_VERSION: u16 = 1;
+/// Oldest protocol version this node still speaks.
+pub const MIN_PROTOCOL_VERSION: u16 = 1;
+
+/// Optional protocol features, announced in the handshake. A connection uses the
+/// features both peers announced.
+pub const FEATURE_COMPRESSION: u64 = 1 << 0;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub enum NetworkPacket {
+    /// Sent in the clear when a connection opens. `public_key` is the node's Ed25519
+    /// identity key and `ephemeral_key` a fresh X25519 key for this connection.
+    /// `protocol_version` comes first so any later version can still read it.
     Handshake {
+        protocol_version: u16,
+        min_protocol_version: u16,
+        features: u64,
+        /// Largest packet the node accepts, see `max_frame_bytes` in `network.toml`.
+        max_frame_bytes: u32,
         node_id: String,
         address: String,
+        public_key: Vec<u8>,
+        ephemeral_key: Vec<u8>,
+    },
+    /// First sealed packet of each side: its identity signature over the handshake
+    /// transcript and its peer token.
+    HandshakeAuth {
+        signature: Vec<u8>,
         token: String,
     },
     Close,
+    /// Calls with a `request_id` are answered with a `ReducerResult`, the others
+    /// are fire-and-forget.
     ReducerCall {
+        request_id: Option<String>,
         module_name: String,
         reducer_name: String,
         input: IntersticeValue,
@@ -30,12 +59,28 @@ pub enum NetworkPacket {
     RequestTableSync {
         module_name: String,
         table_name: String,
+        /// Same filter as the subscriptions of the replica, see [`RequestSubscription`].
+        filter: Option<RowFilter>,
+        /// Position of an unfiltered replica catching up: the events after it are
+        /// sent when the source still has them, the whole table otherwise.
+        since: Option<ReplicaPosition>,
     },
     TableSyncResponse {
         module_name: String,
         table_name: String,
+        /// Position of the source table the rows were read at.
+        position: ReplicaPosition,
         rows: Vec<Row>,
     },
+    /// Answer to a `RequestTableSync` with `since`: the events of the table after
+    /// `since`, up to `position`. Events the requester may not see are left out.
+    TableChangesResponse {
+        module_name: String,
+        table_name: String,
+        since: u64,
+        position: ReplicaPosition,
+        events: Vec<TableEventInstance>,
+    },
     TableEvent(TableEventInstance),
     ModuleEvent(ModuleEventInstance),
     SchemaRequest {
@@ -46,7 +91,135 @@ pub enum NetworkPacket {
         request_id: String,
         schema: NodeSchema,
     },
-    Error(String),
+    Error(RemoteError),
+    /// Keepalive sent while a connection is quiet; answered with `Pong`.
+    Ping,
+    Pong,
+    /// Outcome of a `ReducerCall`, never `Pending`.
+    ReducerResult {
+        request_id: String,
+        status: ReducerCallStatus,
+    },
+}
+
+/// Failure reported back to the node whose request could not be served.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RemoteError {
+    Failed {
+        code: ErrorCode,
+        message: String,
+    },
+    /// A remote reducer or query call was refused by the module's access policy.
+    AccessDenied {
+        module_name: String,
+        call_name: String,
+        reason: AccessDeniedReason,
+    },
+}
+
+/// What went wrong with a request, for the requester to act on without parsing
+/// messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ErrorCode {
+    /// The module, table, reducer or query does not exist on the node.
+    NotFound,
+    /// The request does not match the schema it targets.
+    InvalidRequest,
+    /// The access policy of the module refused the call.
+    AccessDenied,
+    /// The reducer or query failed while running.
+    ExecutionFailed,
+    /// The packet could not be decoded, e.g. one added by a newer protocol.
+    UnsupportedPacket,
+    /// The packet is larger than the receiver's `max_frame_bytes`.
+    FrameTooLarge,
+    Internal,
+}
+
+impl RemoteError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        RemoteError::Failed {
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            RemoteError::Failed { code, .. } => *code,
+            RemoteError::AccessDenied { .. } => ErrorCode::AccessDenied,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AccessDeniedReason {
+    /// The caller is neither an allowed node nor holds an allowed role.
+    NotAllowed,
+    /// The policy query returned `false`.
+    PolicyRejected { policy: String },
+    /// The policy query could not be evaluated.
+    PolicyFailed { policy: String, error: String },
+}
+
+impl std::fmt::Display for RemoteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RemoteError::Failed { message, .. } => write!(f, "{}", message),
+            RemoteError::AccessDenied {
+                module_name,
+                call_name,
+                reason,
+            } => write!(
+                f,
+                "access to '{}.{}' denied: {}",
+                module_name, call_name, reason
+            ),
+        }
+    }
+}
+
+impl std::fmt::Display for AccessDeniedReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AccessDeniedReason::NotAllowed => write!(f, "caller is not in the allowed nodes or roles"),
+            AccessDeniedReason::PolicyRejected { policy } => {
+                write!(f, "rejected by policy '{}'", policy)
+            }
+            AccessDeniedReason::PolicyFailed { policy, error } => {
+                write!(f, "policy '{}' failed: {}", policy, error)
+            }
+        }
+    }
+}
+
+impl From<String> for RemoteError {
+    fn from(message: String) -> Self {
+        RemoteError::new(ErrorCode::Internal, message)
+    }
+}
+
+impl From<&IntersticeError> for RemoteError {
+    fn from(err: &IntersticeError) -> Self {
+        let code = match err {
+            IntersticeError::ModuleNotFound(..)
+            | IntersticeError::TableNotFound { .. }
+            | IntersticeError::ReducerNotFound { .. }
+            | IntersticeError::IndexNotFound { .. }
+            | IntersticeError::WasmFuncNotFound(_) => ErrorCode::NotFound,
+            IntersticeError::InvalidTableQuery { .. }
+            | IntersticeError::IndexQueryUnsupported { .. }
+            | IntersticeError::InvalidRow { .. } => ErrorCode::InvalidRequest,
+            IntersticeError::WasmTrap(_)
+            | IntersticeError::FuelExhausted { .. }
+            | IntersticeError::MemoryLimitExceeded { .. }
+            | IntersticeError::TransactionAborted { .. } => ErrorCode::ExecutionFailed,
+            IntersticeError::FrameTooLarge { .. } => ErrorCode::FrameTooLarge,
+            IntersticeError::PacketDecodeFailed(_) => ErrorCode::UnsupportedPacket,
+            _ => ErrorCode::Internal,
+        };
+        RemoteError::new(code, err.to_string())
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -54,6 +227,9 @@ pub struct RequestSubscription {
     pub module_name: String,
     pub table_name: String,
     pub event: TableEvent,
+    /// Only the events of rows matching the filter are sent. An update moving a
+    /// row into or out of the filter is sent as an insert or a delete.
+    pub filter: Option<RowFilter>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Copy)]
@@ -63,29 +239,53 @@ pub enum TableEvent {
     Delete,
 }
 
+/// Position in the event sequence of a public table. `epoch` changes whenever the
+/// source node restarts, which starts the sequence over.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplicaPosition {
+    pub epoch: String,
+    pub sequence: u64,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub enum TableEventInstance {
+    /// `filter` is the filter of the subscription the event is sent for, and
+    /// `sequence` the sequence number of the event in its table.
     TableInsertEvent {
+        filter: Option<RowFilter>,
+        sequence: u64,
         module_name: String,
         table_name: String,
         inserted_row: Row,
     },
     TableUpdateEvent {
+        filter: Option<RowFilter>,
+        sequence: u64,
         module_name: String,
         table_name: String,
         old_row: Row,
         new_row: Row,
     },
     TableDeleteEvent {
+        filter: Option<RowFilter>,
+        sequence: u64,
         module_name: String,
         table_name: String,
         deleted_row: Row,
     },
+    /// Sent to unfiltered subscriptions in place of an event the table view hides
+    /// from the subscriber, so its replicas do not take it for a lost event.
+    TableEventHidden {
+        sequence: u64,
+        module_name: String,
+        table_name: String,
+    },
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub enum ModuleEventInstance {
     Load { wasm_binary: Vec<u8> },
+    Upgrade { wasm_binary: Vec<u8> },
     Unload { module_name: String },
     Remove { module_name: String },
 }
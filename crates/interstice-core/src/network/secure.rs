@@ -0,0 +1,517 @@
+//! Authenticated, encrypted transport between nodes.
+//!
+//! A connection opens with both sides sending a `Handshake` in the clear: protocol
+//! versions, features and frame limit, node id,
+//! long-term Ed25519 identity key and a fresh X25519 key. Session keys are derived
+//! from the X25519 exchange salted with the handshake transcript, one key per
+//! direction. Each side then sends a sealed `HandshakeAuth` signing the transcript
+//! with its identity key, which binds the node id to that key; the binding is checked
+//! against the `PeerKeyStore` (trust on first use, or pinned keys only). Every later
+//! packet is sealed with ChaCha20-Poly1305 under a per-direction nonce counter.
+
+use crate::error::IntersticeError;
+use crate::network::config::NetworkConfig;
+use crate::network::packet::{
+    HANDSHAKE_FRAME_LIMIT, SEALED_FRAME_OVERHEAD, decode_frame_payload, decode_packet,
+    encode_packet, frame_payload, read_frame, write_frame, write_packet,
+};
+use crate::network::protocol::{
+    FEATURE_COMPRESSION, MIN_PROTOCOL_VERSION, NetworkPacket, PROTOCOL_VERSION,
+};
+use crate::node::NodeId;
+use crate::persistence::{NodeIdentity, PeerKeyStore};
+use interstice_abi::{decode, encode};
+use parking_lot::Mutex;
+use ring::aead::{Aad, CHACHA20_POLY1305, LessSafeKey, NONCE_LEN, Nonce, UnboundKey};
+use ring::agreement::{self, EphemeralPrivateKey, UnparsedPublicKey};
+use ring::digest::{self, SHA256};
+use ring::hkdf::{HKDF_SHA256, Salt};
+use ring::rand::SystemRandom;
+use ring::signature::{self, ED25519};
+use tokio::net::TcpStream;
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+
+const TRANSCRIPT_LABEL: &[u8] = b"interstice-handshake-v1";
+const INITIATOR_LABEL: &[u8] = b"interstice initiator";
+const RESPONDER_LABEL: &[u8] = b"interstice responder";
+
+/// The authenticated peer and the two halves of the encrypted channel to it.
+pub struct SecureSession {
+    pub peer_id: NodeId,
+    pub peer_address: String,
+    pub peer_token: String,
+    /// Protocol version both sides speak on this connection.
+    pub protocol_version: u16,
+    /// Features both sides announced, see `FEATURE_*`.
+    pub features: u64,
+    pub reader: SecureReader,
+    pub writer: SecureWriter,
+}
+
+pub struct SecureReader {
+    reader: OwnedReadHalf,
+    key: LessSafeKey,
+    counter: u64,
+    /// This node's `max_frame_bytes`.
+    max_frame_bytes: u32,
+}
+
+pub struct SecureWriter {
+    writer: OwnedWriteHalf,
+    key: LessSafeKey,
+    counter: u64,
+    /// The peer's `max_frame_bytes`.
+    max_frame_bytes: u32,
+    compression_min_bytes: Option<u32>,
+}
+
+impl SecureReader {
+    /// Next packet from the peer. `PacketDecodeFailed` leaves the connection
+    /// usable; any other error ends it.
+    pub async fn read_packet(&mut self) -> Result<NetworkPacket, IntersticeError> {
+        let mut buf = read_frame(
+            &mut self.reader,
+            self.max_frame_bytes.saturating_add(SEALED_FRAME_OVERHEAD),
+        )
+        .await?;
+        let nonce = next_nonce(&mut self.counter)?;
+        let plaintext = self
+            .key
+            .open_in_place(nonce, Aad::empty(), &mut buf)
+            .map_err(|_| IntersticeError::ProtocolError("Failed to decrypt packet".into()))?;
+        decode_frame_payload(plaintext, self.max_frame_bytes)
+    }
+}
+
+impl SecureWriter {
+    /// Send a packet to the peer. Packets over the peer's frame limit are refused
+    /// with `FrameTooLarge` before anything is written.
+    pub async fn write_packet(&mut self, packet: &NetworkPacket) -> Result<(), IntersticeError> {
+        let bytes = encode_packet(packet)?;
+        if bytes.len() > self.max_frame_bytes as usize {
+            return Err(IntersticeError::FrameTooLarge {
+                size: bytes.len() as u64,
+                limit: self.max_frame_bytes,
+            });
+        }
+        let mut frame = frame_payload(bytes, self.compression_min_bytes);
+        let nonce = next_nonce(&mut self.counter)?;
+        self.key
+            .seal_in_place_append_tag(nonce, Aad::empty(), &mut frame)
+            .map_err(|_| IntersticeError::Internal("Failed to encrypt packet".into()))?;
+        write_frame(&mut self.writer, &frame).await
+    }
+}
+
+/// Run the handshake on a fresh connection. The initiator (the side that dialed)
+/// sends its `Handshake` first; the responder answers right away.
+#[allow(clippy::too_many_arguments)]
+pub async fn secure_handshake(
+    mut stream: TcpStream,
+    my_node_id: NodeId,
+    my_address: String,
+    my_token: String,
+    identity: &NodeIdentity,
+    peer_keys: &Mutex<PeerKeyStore>,
+    config: &NetworkConfig,
+    initiator: bool,
+) -> Result<SecureSession, IntersticeError> {
+    let rng = SystemRandom::new();
+    let ephemeral = EphemeralPrivateKey::generate(&agreement::X25519, &rng)
+        .map_err(|_| IntersticeError::Internal("Failed to generate session key".into()))?;
+    let ephemeral_public = ephemeral
+        .compute_public_key()
+        .map_err(|_| IntersticeError::Internal("Failed to generate session key".into()))?;
+    let my_features = if config.compression {
+        FEATURE_COMPRESSION
+    } else {
+        0
+    };
+    let my_hello = NetworkPacket::Handshake {
+        protocol_version: PROTOCOL_VERSION,
+        min_protocol_version: MIN_PROTOCOL_VERSION,
+        features: my_features,
+        max_frame_bytes: config.max_frame_bytes,
+        node_id: my_node_id.to_string(),
+        address: my_address,
+        public_key: identity.public_key(),
+        ephemeral_key: ephemeral_public.as_ref().to_vec(),
+    };
+
+    let peer_hello = if initiator {
+        write_packet(&mut stream, &my_hello).await?;
+        read_hello(&mut stream).await?
+    } else {
+        let peer_hello = read_hello(&mut stream).await?;
+        write_packet(&mut stream, &my_hello).await?;
+        peer_hello
+    };
+    let NetworkPacket::Handshake {
+        protocol_version: peer_version,
+        min_protocol_version: peer_min_version,
+        features: peer_features,
+        max_frame_bytes: peer_max_frame_bytes,
+        node_id: peer_id,
+        address: peer_address,
+        public_key: peer_public_key,
+        ephemeral_key: peer_ephemeral_key,
+    } = &peer_hello
+    else {
+        return Err(IntersticeError::ProtocolError(
+            "Expected handshake packet".into(),
+        ));
+    };
+    let peer_id = NodeId::parse_str(peer_id)
+        .map_err(|err| IntersticeError::ProtocolError(format!("Invalid peer node id: {err}")))?;
+    let protocol_version = negotiate_version(*peer_version, *peer_min_version)?;
+    let features = my_features & peer_features;
+
+    let (initiator_hello, responder_hello) = if initiator {
+        (&my_hello, &peer_hello)
+    } else {
+        (&peer_hello, &my_hello)
+    };
+    let transcript = transcript_hash(initiator_hello, responder_hello)?;
+
+    let (initiator_key, responder_key) = agreement::agree_ephemeral(
+        ephemeral,
+        &UnparsedPublicKey::new(&agreement::X25519, peer_ephemeral_key),
+        |shared_secret| {
+            let prk = Salt::new(HKDF_SHA256, &transcript).extract(shared_secret);
+            Ok::<_, IntersticeError>((
+                session_key(&prk, INITIATOR_LABEL)?,
+                session_key(&prk, RESPONDER_LABEL)?,
+            ))
+        },
+    )
+    .map_err(|_| IntersticeError::ProtocolError("Invalid peer session key".into()))??;
+    let (my_key, peer_key, my_label, peer_label) = if initiator {
+        (initiator_key, responder_key, INITIATOR_LABEL, RESPONDER_LABEL)
+    } else {
+        (responder_key, initiator_key, RESPONDER_LABEL, INITIATOR_LABEL)
+    };
+
+    let (reader, writer) = stream.into_split();
+    let mut reader = SecureReader {
+        reader,
+        key: peer_key,
+        counter: 0,
+        max_frame_bytes: config.max_frame_bytes,
+    };
+    let mut writer = SecureWriter {
+        writer,
+        key: my_key,
+        counter: 0,
+        max_frame_bytes: *peer_max_frame_bytes,
+        compression_min_bytes: (features & FEATURE_COMPRESSION != 0)
+            .then_some(config.compression_min_bytes),
+    };
+
+    writer
+        .write_packet(&NetworkPacket::HandshakeAuth {
+            signature: identity.sign(&signed_message(my_label, &transcript)),
+            token: my_token,
+        })
+        .await?;
+    let NetworkPacket::HandshakeAuth {
+        signature: peer_signature,
+        token: peer_token,
+    } = reader.read_packet().await?
+    else {
+        return Err(IntersticeError::ProtocolError(
+            "Expected handshake authentication packet".into(),
+        ));
+    };
+    signature::UnparsedPublicKey::new(&ED25519, peer_public_key)
+        .verify(&signed_message(peer_label, &transcript), &peer_signature)
+        .map_err(|_| {
+            IntersticeError::ProtocolError(format!(
+                "Invalid identity signature from peer {}",
+                peer_id
+            ))
+        })?;
+    peer_keys.lock().verify_peer_key(&peer_id, peer_public_key)?;
+
+    Ok(SecureSession {
+        peer_id,
+        peer_address: peer_address.clone(),
+        peer_token,
+        protocol_version,
+        features,
+        reader,
+        writer,
+    })
+}
+
+/// Read the peer's `Handshake`, telling a peer on another protocol version
+/// apart from a broken one.
+async fn read_hello(stream: &mut TcpStream) -> Result<NetworkPacket, IntersticeError> {
+    let frame = read_frame(stream, HANDSHAKE_FRAME_LIMIT).await?;
+    decode_packet(&frame).map_err(|err| match peek_protocol_version(&frame) {
+        Some(version) if version != PROTOCOL_VERSION => IntersticeError::ProtocolError(format!(
+            "Peer speaks protocol version {}, this node speaks versions {} to {}",
+            version, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION
+        )),
+        _ => IntersticeError::ProtocolError(format!("Invalid handshake: {err}")),
+    })
+}
+
+/// `protocol_version` of an encoded `Handshake`, which every protocol version
+/// starts the same way.
+fn peek_protocol_version(frame: &[u8]) -> Option<u16> {
+    match decode::<(u32, u16)>(frame) {
+        Ok((0, version)) => Some(version),
+        _ => None,
+    }
+}
+
+/// Highest protocol version both sides speak.
+fn negotiate_version(peer_version: u16, peer_min_version: u16) -> Result<u16, IntersticeError> {
+    let version = PROTOCOL_VERSION.min(peer_version);
+    if version < MIN_PROTOCOL_VERSION || version < peer_min_version {
+        return Err(IntersticeError::ProtocolError(format!(
+            "Incompatible protocol versions: this node speaks {} to {}, the peer {} to {}",
+            MIN_PROTOCOL_VERSION, PROTOCOL_VERSION, peer_min_version, peer_version
+        )));
+    }
+    Ok(version)
+}
+
+fn transcript_hash(
+    initiator_hello: &NetworkPacket,
+    responder_hello: &NetworkPacket,
+) -> Result<Vec<u8>, IntersticeError> {
+    let encode_hello = |hello| {
+        encode(hello).map_err(|err| {
+            IntersticeError::Internal(format!("Couldn't encode network packet: {}", err))
+        })
+    };
+    let mut context = digest::Context::new(&SHA256);
+    context.update(TRANSCRIPT_LABEL);
+    context.update(&encode_hello(initiator_hello)?);
+    context.update(&encode_hello(responder_hello)?);
+    Ok(context.finish().as_ref().to_vec())
+}
+
+fn signed_message(label: &[u8], transcript: &[u8]) -> Vec<u8> {
+    [label, transcript].concat()
+}
+
+fn session_key(prk: &ring::hkdf::Prk, label: &[u8]) -> Result<LessSafeKey, IntersticeError> {
+    let info = [label];
+    let okm = prk
+        .expand(&info, &CHACHA20_POLY1305)
+        .map_err(|_| IntersticeError::Internal("Failed to derive session key".into()))?;
+    Ok(LessSafeKey::new(UnboundKey::from(okm)))
+}
+
+fn next_nonce(counter: &mut u64) -> Result<Nonce, IntersticeError> {
+    if *counter == u64::MAX {
+        return Err(IntersticeError::ProtocolError(
+            "Session nonce exhausted".into(),
+        ));
+    }
+    let mut nonce = [0u8; NONCE_LEN];
+    nonce[NONCE_LEN - 8..].copy_from_slice(&counter.to_be_bytes());
+    *counter += 1;
+    Ok(Nonce::assume_unique_for_key(nonce))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::PeerKeyMode;
+    use tokio::net::TcpListener;
+    use uuid::Uuid;
+
+    struct TestNode {
+        id: NodeId,
+        identity: NodeIdentity,
+        peer_keys: Mutex<PeerKeyStore>,
+    }
+
+    impl TestNode {
+        fn new(mode: PeerKeyMode) -> Self {
+            Self {
+                id: Uuid::new_v4(),
+                identity: NodeIdentity::generate().unwrap(),
+                peer_keys: Mutex::new(PeerKeyStore::new_in_memory(mode)),
+            }
+        }
+    }
+
+    async fn connect(
+        dialer: &TestNode,
+        listener_node: &TestNode,
+    ) -> (
+        Result<SecureSession, IntersticeError>,
+        Result<SecureSession, IntersticeError>,
+    ) {
+        connect_with(dialer, listener_node, &NetworkConfig::default()).await
+    }
+
+    async fn connect_with(
+        dialer: &TestNode,
+        listener_node: &TestNode,
+        listener_config: &NetworkConfig,
+    ) -> (
+        Result<SecureSession, IntersticeError>,
+        Result<SecureSession, IntersticeError>,
+    ) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let accept = async {
+            let (stream, _) = listener.accept().await.unwrap();
+            secure_handshake(
+                stream,
+                listener_node.id,
+                address.clone(),
+                "listener-token".into(),
+                &listener_node.identity,
+                &listener_node.peer_keys,
+                listener_config,
+                false,
+            )
+            .await
+        };
+        let dial = async {
+            let stream = TcpStream::connect(&address).await.unwrap();
+            secure_handshake(
+                stream,
+                dialer.id,
+                "dialer".into(),
+                "dialer-token".into(),
+                &dialer.identity,
+                &dialer.peer_keys,
+                &NetworkConfig::default(),
+                true,
+            )
+            .await
+        };
+        tokio::join!(dial, accept)
+    }
+
+    #[tokio::test]
+    async fn two_nodes_authenticate_and_exchange_sealed_packets() {
+        let a = TestNode::new(PeerKeyMode::TrustOnFirstUse);
+        let b = TestNode::new(PeerKeyMode::TrustOnFirstUse);
+        let (a_session, b_session) = connect(&a, &b).await;
+        let (mut a_session, mut b_session) = (a_session.unwrap(), b_session.unwrap());
+
+        assert_eq!(a_session.peer_id, b.id);
+        assert_eq!(b_session.peer_id, a.id);
+        assert_eq!(a_session.peer_token, "listener-token");
+        assert_eq!(b_session.peer_token, "dialer-token");
+        assert_eq!(a.peer_keys.lock().get_peer_key(&b.id), Some(b.identity.public_key()));
+
+        for i in 0..3 {
+            a_session
+                .writer
+                .write_packet(&NetworkPacket::Error(format!("ping {i}").into()))
+                .await
+                .unwrap();
+            match b_session.reader.read_packet().await.unwrap() {
+                NetworkPacket::Error(error) => assert_eq!(error.to_string(), format!("ping {i}")),
+                other => panic!("unexpected packet {:?}", other),
+            }
+        }
+        b_session.writer.write_packet(&NetworkPacket::Close).await.unwrap();
+        assert!(matches!(
+            a_session.reader.read_packet().await.unwrap(),
+            NetworkPacket::Close
+        ));
+
+        // Reconnecting with the same identities is accepted.
+        let (a_again, b_again) = connect(&a, &b).await;
+        assert!(a_again.is_ok() && b_again.is_ok());
+    }
+
+    #[tokio::test]
+    async fn changed_identity_key_is_rejected() {
+        let a = TestNode::new(PeerKeyMode::TrustOnFirstUse);
+        let b = TestNode::new(PeerKeyMode::TrustOnFirstUse);
+        let (a_session, b_session) = connect(&a, &b).await;
+        assert!(a_session.is_ok() && b_session.is_ok());
+
+        let impostor = TestNode {
+            id: a.id,
+            identity: NodeIdentity::generate().unwrap(),
+            peer_keys: Mutex::new(PeerKeyStore::new_in_memory(PeerKeyMode::TrustOnFirstUse)),
+        };
+        let (_, b_session) = connect(&impostor, &b).await;
+        assert!(b_session.is_err());
+    }
+
+    #[tokio::test]
+    async fn pinned_mode_only_accepts_listed_peers() {
+        let a = TestNode::new(PeerKeyMode::TrustOnFirstUse);
+        let b = TestNode::new(PeerKeyMode::Pinned);
+        let (_, b_session) = connect(&a, &b).await;
+        assert!(b_session.is_err());
+
+        b.peer_keys
+            .lock()
+            .pin_peer_key(&a.id, &a.identity.public_key())
+            .unwrap();
+        let (a_session, b_session) = connect(&a, &b).await;
+        assert!(a_session.is_ok() && b_session.is_ok());
+    }
+
+    #[tokio::test]
+    async fn frame_limits_and_compression_are_negotiated() {
+        let a = TestNode::new(PeerKeyMode::TrustOnFirstUse);
+        let b = TestNode::new(PeerKeyMode::TrustOnFirstUse);
+        let small = NetworkConfig {
+            max_frame_bytes: 100_000,
+            compression: false,
+            ..NetworkConfig::default()
+        };
+        let (a_session, b_session) = connect_with(&a, &b, &small).await;
+        let (mut a_session, mut b_session) = (a_session.unwrap(), b_session.unwrap());
+        assert_eq!(a_session.protocol_version, PROTOCOL_VERSION);
+        assert_eq!(a_session.features, 0);
+
+        // Refused before sending: the connection stays usable.
+        let too_large = NetworkPacket::Error("x".repeat(200_000).into());
+        assert!(matches!(
+            a_session.writer.write_packet(&too_large).await,
+            Err(IntersticeError::FrameTooLarge { limit: 100_000, .. })
+        ));
+        let fits = NetworkPacket::Error("x".repeat(50_000).into());
+        a_session.writer.write_packet(&fits).await.unwrap();
+        assert!(matches!(
+            b_session.reader.read_packet().await.unwrap(),
+            NetworkPacket::Error(_)
+        ));
+
+        let (a_session, b_session) = connect(&a, &b).await;
+        let (mut a_session, mut b_session) = (a_session.unwrap(), b_session.unwrap());
+        assert_eq!(b_session.features, FEATURE_COMPRESSION);
+        b_session.writer.write_packet(&too_large).await.unwrap();
+        match a_session.reader.read_packet().await.unwrap() {
+            NetworkPacket::Error(error) => assert_eq!(error.to_string().len(), 200_000),
+            other => panic!("unexpected packet {:?}", other),
+        }
+    }
+
+    #[test]
+    fn protocol_versions_are_negotiated() {
+        assert_eq!(negotiate_version(PROTOCOL_VERSION + 3, MIN_PROTOCOL_VERSION).unwrap(), PROTOCOL_VERSION);
+        assert!(negotiate_version(PROTOCOL_VERSION + 3, PROTOCOL_VERSION + 1).is_err());
+        assert!(negotiate_version(MIN_PROTOCOL_VERSION - 1, 0).is_err());
+
+        let hello = encode_packet(&NetworkPacket::Handshake {
+            protocol_version: 7,
+            min_protocol_version: 7,
+            features: 0,
+            max_frame_bytes: 0,
+            node_id: String::new(),
+            address: String::new(),
+            public_key: Vec::new(),
+            ephemeral_key: Vec::new(),
+        })
+        .unwrap();
+        assert_eq!(peek_protocol_version(&hello), Some(7));
+        assert_eq!(peek_protocol_version(&encode_packet(&NetworkPacket::Ping).unwrap()), None);
+    }
+}
@@ -3,11 +3,14 @@ use crate::{
     audio::AudioEngine,
     error::IntersticeError,
     logger::{LogLevel, LogSource, Logger},
-    network::{Network, NetworkHandle},
-    persistence::{PeerTokenStore, TableStore},
+    network::{Network, NetworkHandle, config::NetworkConfig},
+    persistence::{NodeIdentity, PeerKeyStore, PeerTokenStore, TableStore},
+    recording::Recording,
     runtime::{
         Runtime,
+        access::{AccessGuard, AccessRoles},
         event::EventInstance,
+        host_calls::file::FileSandbox,
         module::Module,
         reducer::{CompletionToken, ReducerJob},
     },
@@ -59,6 +62,10 @@ impl Node {
         std::fs::create_dir_all(&data_path).expect("Should be able to create node data path");
         std::fs::create_dir_all(&modules_path).expect("Should be able to create modules path");
         let table_store = TableStore::new(Some(modules_path.clone()));
+        let file_sandbox = FileSandbox::load(&data_path)?;
+        let access_roles = AccessRoles::load(&data_path)?;
+        let access_guard = Arc::new(AccessGuard::default());
+        let network_config = NetworkConfig::load(&data_path)?;
 
         let bind_address = format!("0.0.0.0:{}", port);
 
@@ -71,6 +78,12 @@ impl Node {
         let peer_tokens = Arc::new(Mutex::new(PeerTokenStore::load_or_create(
             data_path.join("peer_tokens.toml"),
         )?));
+        let identity = Arc::new(NodeIdentity::load_or_create(
+            data_path.join("node_identity.toml"),
+        )?);
+        let peer_keys = Arc::new(Mutex::new(PeerKeyStore::load_or_create(
+            data_path.join("peer_keys.toml"),
+        )?));
 
         // Bounded reducer ingress: producers use blocking `send` so a full queue
         // stalls readers / the event loop instead of dropping reducer jobs.
@@ -84,6 +97,10 @@ impl Node {
             event_sender.clone(),
             reducer_sender.clone(),
             peer_tokens,
+            identity,
+            peer_keys,
+            access_guard.clone(),
+            network_config,
             logger.clone(),
         );
         let network_handle = network.get_handle();
@@ -98,6 +115,8 @@ impl Node {
             id,
             Some(modules_path),
             table_store,
+            file_sandbox,
+            access_roles,
             event_sender.clone(),
             network_handle.clone(),
             audio_state,
@@ -107,6 +126,7 @@ impl Node {
             reducer_sender,
             reducer_receiver,
         )?);
+        access_guard.bind(&runtime);
         let gpu_call_receiver = runtime.take_gpu_call_receiver();
         let app = App::new(
             id,
@@ -140,6 +160,10 @@ impl Node {
         let bind_address = format!("0.0.0.0:{}", port);
         let modules_path = data_path.join("modules");
         let table_store = TableStore::new(Some(modules_path.clone()));
+        let file_sandbox = FileSandbox::load(&data_path)?;
+        let access_roles = AccessRoles::load(&data_path)?;
+        let access_guard = Arc::new(AccessGuard::default());
+        let network_config = NetworkConfig::load(&data_path)?;
 
         let (event_sender, event_receiver) = mpsc::unbounded_channel();
 
@@ -153,6 +177,12 @@ impl Node {
         let peer_tokens = Arc::new(Mutex::new(PeerTokenStore::load_or_create(
             data_path.join("peer_tokens.toml"),
         )?));
+        let identity = Arc::new(NodeIdentity::load_or_create(
+            data_path.join("node_identity.toml"),
+        )?);
+        let peer_keys = Arc::new(Mutex::new(PeerKeyStore::load_or_create(
+            data_path.join("peer_keys.toml"),
+        )?));
 
         let (reducer_sender, reducer_receiver) =
             crossbeam_channel::bounded::<ReducerJob>(REDUCER_QUEUE_CAPACITY);
@@ -164,6 +194,10 @@ impl Node {
             event_sender.clone(),
             reducer_sender.clone(),
             peer_tokens,
+            identity,
+            peer_keys,
+            access_guard.clone(),
+            network_config,
             logger.clone(),
         );
         let network_handle = network.get_handle();
@@ -178,6 +212,8 @@ impl Node {
             id,
             Some(modules_path.clone()),
             table_store,
+            file_sandbox,
+            access_roles,
             event_sender.clone(),
             network_handle.clone(),
             audio_state,
@@ -187,6 +223,7 @@ impl Node {
             reducer_sender,
             reducer_receiver,
         )?);
+        access_guard.bind(&runtime);
         let gpu_call_receiver = runtime.take_gpu_call_receiver();
         let app = App::new(
             id,
@@ -273,6 +310,13 @@ impl Node {
         Ok(())
     }
 
+    /// Record the inputs of the node to `path`, for [`crate::recording::replay`].
+    /// Start recording before [`Node::start`] to record the modules as the node
+    /// loads them.
+    pub fn record(&self, path: impl AsRef<Path>) -> Result<Recording, IntersticeError> {
+        Recording::start(self.runtime.clone(), path.as_ref())
+    }
+
     pub async fn schema(&self, name: String) -> NodeSchema {
         NodeSchema {
             name,
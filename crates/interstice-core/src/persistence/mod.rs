@@ -8,10 +8,14 @@
 
 mod config;
 mod log_rotation;
+mod node_identity;
+mod peer_keys;
 mod peer_tokens;
 mod table_store;
 
 pub use config::PersistenceConfig;
 pub use log_rotation::{LogRotator, RotationConfig};
+pub use node_identity::NodeIdentity;
+pub use peer_keys::{PeerKeyMode, PeerKeyStore};
 pub use peer_tokens::PeerTokenStore;
-pub use table_store::{LogOperation, SnapshotPlan, TableStore};
+pub use table_store::{LogOperation, SnapshotPlan, StagedMigration, TableStore};
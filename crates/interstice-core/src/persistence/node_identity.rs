@@ -0,0 +1,88 @@
+use crate::error::IntersticeError;
+use crate::persistence::peer_keys::{decode_hex, encode_hex};
+use ring::rand::SystemRandom;
+use ring::signature::{Ed25519KeyPair, KeyPair};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct NodeIdentityFile {
+    /// PKCS#8 document of the Ed25519 key pair, hex encoded.
+    private_key: String,
+}
+
+/// Long-term Ed25519 key pair a node proves its identity with during handshakes.
+pub struct NodeIdentity {
+    key_pair: Ed25519KeyPair,
+}
+
+impl NodeIdentity {
+    pub fn load_or_create<P: AsRef<Path>>(path: P) -> Result<Self, IntersticeError> {
+        let path = path.as_ref();
+        if path.exists() {
+            let contents = std::fs::read_to_string(path).map_err(|err| {
+                IntersticeError::Internal(format!(
+                    "Failed to read node identity {}: {err}",
+                    path.display()
+                ))
+            })?;
+            let file: NodeIdentityFile = toml::from_str(&contents).map_err(|err| {
+                IntersticeError::Internal(format!(
+                    "Failed to parse node identity {}: {err}",
+                    path.display()
+                ))
+            })?;
+            let pkcs8 = decode_hex(&file.private_key).ok_or_else(|| {
+                IntersticeError::Internal(format!(
+                    "Invalid private key in node identity {}",
+                    path.display()
+                ))
+            })?;
+            return Self::from_pkcs8(&pkcs8);
+        }
+
+        let pkcs8 = Self::generate_pkcs8()?;
+        let contents = toml::to_string_pretty(&NodeIdentityFile {
+            private_key: encode_hex(&pkcs8),
+        })
+        .map_err(|err| {
+            IntersticeError::Internal(format!("Failed to serialize node identity: {err}"))
+        })?;
+        std::fs::write(path, contents).map_err(|err| {
+            IntersticeError::Internal(format!(
+                "Failed to write node identity {}: {err}",
+                path.display()
+            ))
+        })?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let _ = std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600));
+        }
+        Self::from_pkcs8(&pkcs8)
+    }
+
+    pub fn generate() -> Result<Self, IntersticeError> {
+        Self::from_pkcs8(&Self::generate_pkcs8()?)
+    }
+
+    pub fn public_key(&self) -> Vec<u8> {
+        self.key_pair.public_key().as_ref().to_vec()
+    }
+
+    pub fn sign(&self, message: &[u8]) -> Vec<u8> {
+        self.key_pair.sign(message).as_ref().to_vec()
+    }
+
+    fn generate_pkcs8() -> Result<Vec<u8>, IntersticeError> {
+        Ed25519KeyPair::generate_pkcs8(&SystemRandom::new())
+            .map(|document| document.as_ref().to_vec())
+            .map_err(|_| IntersticeError::Internal("Failed to generate node identity key".into()))
+    }
+
+    fn from_pkcs8(pkcs8: &[u8]) -> Result<Self, IntersticeError> {
+        let key_pair = Ed25519KeyPair::from_pkcs8(pkcs8)
+            .map_err(|err| IntersticeError::Internal(format!("Invalid node identity key: {err}")))?;
+        Ok(Self { key_pair })
+    }
+}
@@ -0,0 +1,179 @@
+use crate::error::IntersticeError;
+use crate::node::NodeId;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// How identity keys of unknown peers are handled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PeerKeyMode {
+    /// Record the key a peer presents the first time, reject any other key later.
+    #[default]
+    TrustOnFirstUse,
+    /// Only accept peers whose key is already listed in the store.
+    Pinned,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct PeerKeyFile {
+    #[serde(default)]
+    mode: PeerKeyMode,
+    #[serde(default)]
+    peers: HashMap<String, String>,
+}
+
+/// Identity public keys of known peers, keyed by node id.
+pub struct PeerKeyStore {
+    path: Option<PathBuf>,
+    mode: PeerKeyMode,
+    peers: HashMap<String, String>,
+}
+
+impl PeerKeyStore {
+    pub fn load_or_create<P: AsRef<Path>>(path: P) -> Result<Self, IntersticeError> {
+        let path = path.as_ref().to_path_buf();
+        if !path.exists() {
+            let store = Self {
+                path: Some(path),
+                mode: PeerKeyMode::default(),
+                peers: HashMap::new(),
+            };
+            store.save()?;
+            return Ok(store);
+        }
+
+        let contents = std::fs::read_to_string(&path).map_err(|err| {
+            IntersticeError::Internal(format!(
+                "Failed to read peer key store {}: {err}",
+                path.display()
+            ))
+        })?;
+        let file: PeerKeyFile = toml::from_str(&contents).map_err(|err| {
+            IntersticeError::Internal(format!(
+                "Failed to parse peer key store {}: {err}",
+                path.display()
+            ))
+        })?;
+        Ok(Self {
+            path: Some(path),
+            mode: file.mode,
+            peers: file.peers,
+        })
+    }
+
+    pub fn new_in_memory(mode: PeerKeyMode) -> Self {
+        Self {
+            path: None,
+            mode,
+            peers: HashMap::new(),
+        }
+    }
+
+    pub fn mode(&self) -> PeerKeyMode {
+        self.mode
+    }
+
+    pub fn get_peer_key(&self, peer_id: &NodeId) -> Option<Vec<u8>> {
+        self.peers
+            .get(&peer_id.to_string())
+            .and_then(|key| decode_hex(key))
+    }
+
+    pub fn pin_peer_key(
+        &mut self,
+        peer_id: &NodeId,
+        public_key: &[u8],
+    ) -> Result<(), IntersticeError> {
+        self.peers.insert(peer_id.to_string(), encode_hex(public_key));
+        self.save()
+    }
+
+    /// Check that `public_key` is the identity key of `peer_id`, recording it if the
+    /// peer is new and the store trusts on first use.
+    pub fn verify_peer_key(
+        &mut self,
+        peer_id: &NodeId,
+        public_key: &[u8],
+    ) -> Result<(), IntersticeError> {
+        match self.get_peer_key(peer_id) {
+            Some(known) if known == public_key => Ok(()),
+            Some(_) => Err(IntersticeError::ProtocolError(format!(
+                "Identity key mismatch for peer {}",
+                peer_id
+            ))),
+            None if self.mode == PeerKeyMode::Pinned => Err(IntersticeError::ProtocolError(
+                format!("No pinned identity key for peer {}", peer_id),
+            )),
+            None => self.pin_peer_key(peer_id, public_key),
+        }
+    }
+
+    pub fn save(&self) -> Result<(), IntersticeError> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let file = PeerKeyFile {
+            mode: self.mode,
+            peers: self.peers.clone(),
+        };
+        let contents = toml::to_string_pretty(&file).map_err(|err| {
+            IntersticeError::Internal(format!("Failed to serialize peer key store: {err}"))
+        })?;
+        std::fs::write(path, contents).map_err(|err| {
+            IntersticeError::Internal(format!(
+                "Failed to write peer key store {}: {err}",
+                path.display()
+            ))
+        })?;
+        Ok(())
+    }
+}
+
+pub(crate) fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+pub(crate) fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[test]
+    fn trust_on_first_use_records_then_rejects_other_keys() {
+        let mut store = PeerKeyStore::new_in_memory(PeerKeyMode::TrustOnFirstUse);
+        let peer = Uuid::new_v4();
+        store.verify_peer_key(&peer, &[1, 2, 3]).unwrap();
+        assert_eq!(store.get_peer_key(&peer), Some(vec![1, 2, 3]));
+        store.verify_peer_key(&peer, &[1, 2, 3]).unwrap();
+        assert!(store.verify_peer_key(&peer, &[4, 5, 6]).is_err());
+    }
+
+    #[test]
+    fn pinned_mode_rejects_unknown_peers() {
+        let mut store = PeerKeyStore::new_in_memory(PeerKeyMode::Pinned);
+        let peer = Uuid::new_v4();
+        assert!(store.verify_peer_key(&peer, &[1, 2, 3]).is_err());
+        store.pin_peer_key(&peer, &[1, 2, 3]).unwrap();
+        store.verify_peer_key(&peer, &[1, 2, 3]).unwrap();
+    }
+
+    #[test]
+    fn hex_round_trip() {
+        let bytes = vec![0x00, 0x7f, 0xab, 0xff];
+        assert_eq!(encode_hex(&bytes), "007fabff");
+        assert_eq!(decode_hex("007fabff"), Some(bytes));
+        assert_eq!(decode_hex("0g"), None);
+        assert_eq!(decode_hex("abc"), None);
+    }
+}
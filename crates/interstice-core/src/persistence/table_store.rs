@@ -6,19 +6,63 @@ use parking_lot::Mutex;
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 
-use interstice_abi::{IndexKey, PersistenceKind, Row, decode, encode};
-use serde::{Deserialize, Serialize};
+use interstice_abi::{
+    IndexKey, PersistenceKind, Row, ScheduleInfo, TableSchema, decode, encode,
+};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
 
-use crate::{error::IntersticeError, runtime::table::Table};
+use crate::{error::IntersticeError, runtime::table::Table, runtime::time_travel::{Timeline, TimelineRows}};
 
-const SNAPSHOT_VERSION: u16 = 1;
+/// Version 2 added the branch lineage of the table.
+const SNAPSHOT_VERSION: u16 = 2;
 const SNAPSHOT_INTERVAL: u64 = 256;
+/// Table layouts a module's persisted rows were written with.
+const TABLE_LAYOUTS_FILE: &str = "tables.bin";
+/// Fully written migration, applied on the next load if the node stopped midway.
+const MIGRATION_DIR: &str = "migration";
+/// Migration still being written; discarded if the node stopped midway.
+const MIGRATION_STAGING_DIR: &str = "migration.tmp";
+/// Pending schedules of a module.
+const SCHEDULES_FILE: &str = "schedules.bin";
+/// Checkpoints and branches of a module: the timeline, and the rows saved by each
+/// checkpoint and branch in files of their own.
+const TIMELINE_DIR: &str = "timeline";
+const TIMELINE_FILE: &str = "timeline.bin";
 
 #[derive(Clone, Debug)]
 pub struct SnapshotPlan {
     pub module: String,
     pub table: String,
     pub seq: u64,
+    /// Branch lineage of the table at `seq`.
+    pub branches: Vec<String>,
+}
+
+/// Files of a table migration staged by [`TableStore::stage_module_migration`],
+/// with the migrated tables. Dropping it without committing discards the files.
+pub struct StagedMigration {
+    module_dir: PathBuf,
+    /// Names of the migrated tables.
+    migrated: Vec<String>,
+    tables: Vec<Table>,
+}
+
+impl StagedMigration {
+    fn staging_dir(&self) -> PathBuf {
+        self.module_dir.join(MIGRATION_STAGING_DIR)
+    }
+
+    /// Take the migrated tables, with their rows converted to the new layouts.
+    pub fn take_tables(&mut self) -> Vec<Table> {
+        std::mem::take(&mut self.tables)
+    }
+}
+
+impl Drop for StagedMigration {
+    fn drop(&mut self) {
+        // Already renamed away once committed.
+        let _ = fs::remove_dir_all(self.staging_dir());
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -35,6 +79,36 @@ pub enum LogOperation {
         primary_key: IndexKey,
     },
     Clear,
+    /// Rows replaced by a rewind to a checkpoint.
+    Restore {
+        rows: Vec<Row>,
+    },
+    /// Branch forked from the current one.
+    Fork {
+        branch: String,
+    },
+    /// Current branch dropped, putting back the rows it was forked from.
+    Discard {
+        branch: String,
+        rows: Vec<Row>,
+    },
+    /// Current branch kept in its parent.
+    Promote {
+        branch: String,
+    },
+}
+
+impl LogOperation {
+    /// Follow the branch lineage of a table through this operation.
+    fn apply_lineage(&self, branches: &mut Vec<String>) {
+        match self {
+            LogOperation::Fork { branch } => branches.push(branch.clone()),
+            LogOperation::Discard { .. } | LogOperation::Promote { .. } => {
+                branches.pop();
+            }
+            _ => {}
+        }
+    }
 }
 
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
@@ -57,6 +131,8 @@ struct TableState {
     persistence: PersistenceKind,
     next_seq: u64,
     last_snapshot_seq: u64,
+    /// Branches forked from `main` the table is on, as recorded in its log.
+    branches: Vec<String>,
 }
 
 impl TableState {
@@ -65,6 +141,7 @@ impl TableState {
             persistence,
             next_seq: 0,
             last_snapshot_seq: 0,
+            branches: Vec::new(),
         }
     }
 }
@@ -96,6 +173,8 @@ pub struct TableStore {
     /// `None` value means the row was deleted.
     /// Bounded by the number of unique rows touched (not by operation count).
     dirty_stateful: Arc<Mutex<HashMap<StatefulRowKey, Option<Row>>>>,
+    /// Latest pending schedules per module, written by the background thread.
+    dirty_schedules: Arc<Mutex<HashMap<String, Vec<ScheduleInfo>>>>,
 }
 
 impl TableStore {
@@ -105,6 +184,7 @@ impl TableStore {
             tables: Mutex::new(HashMap::new()),
             wal_writers: Arc::new(Mutex::new(HashMap::new())),
             dirty_stateful: Arc::new(Mutex::new(HashMap::new())),
+            dirty_schedules: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
@@ -127,6 +207,7 @@ impl TableStore {
         guard.persistence = PersistenceKind::Logged;
         let seq = guard.next_seq;
         guard.next_seq += 1;
+        operation.apply_lineage(&mut guard.branches);
 
         let entry = TableLogEntry::new(seq, operation);
         // Write without fsync — background WAL thread fsyncs every 10ms.
@@ -138,6 +219,7 @@ impl TableStore {
                 module: module.to_string(),
                 table: table.to_string(),
                 seq,
+                branches: guard.branches.clone(),
             }))
         } else {
             Ok(None)
@@ -159,14 +241,30 @@ impl TableStore {
         let state = self.get_or_create_state(&plan.module, &plan.table, PersistenceKind::Logged)?;
         let module_paths = self.ensure_module_dirs(root, &plan.module)?;
         let snapshot_path = module_paths.snapshots.join(format!("{}.snap", plan.table));
+        let previous_path = module_paths.snapshots.join(format!("{}.prev.snap", plan.table));
         let log_path = module_paths.logs.join(format!("{}.log", plan.table));
 
         {
             let mut guard = state.lock();
-            Self::write_snapshot_file(&snapshot_path, plan.seq, &rows)?;
-            // compact_log replaces the file; drop the stale WalWriter so it reopens.
-            Self::compact_log(&log_path, plan.seq)?;
-            self.wal_writers.lock().remove(&log_path);
+            // The new snapshot is staged before touching the current one, which
+            // stays in place until the final rename: a crash at any point leaves a
+            // complete `.snap`.
+            let staged = Self::stage_snapshot_file(&snapshot_path, plan.seq, &rows, &plan.branches)?;
+            // The snapshot being replaced is kept, along with the log entries after
+            // it, so `rows_as_of` can reach back at least one snapshot interval.
+            let previous_seq = if snapshot_path.exists() {
+                let previous_seq = Self::read_snapshot_file(&snapshot_path)?.last_seq;
+                Self::keep_previous_snapshot(&snapshot_path, &previous_path)?;
+                Some(previous_seq)
+            } else {
+                None
+            };
+            Self::finalize_snapshot_file(&staged, &snapshot_path)?;
+            if let Some(previous_seq) = previous_seq {
+                // compact_log replaces the file; drop the stale WalWriter so it reopens.
+                Self::compact_log(&log_path, previous_seq)?;
+                self.wal_writers.lock().remove(&log_path);
+            }
             guard.last_snapshot_seq = plan.seq;
         }
 
@@ -326,6 +424,265 @@ impl TableStore {
         }
     }
 
+    /// Record the pending schedules of a module. Like stateful rows they are
+    /// written by the background thread, so frequent recurring schedules only
+    /// cost one file write per flush.
+    pub fn persist_schedules(&self, module: &str, schedules: Vec<ScheduleInfo>) {
+        if self.modules_root.is_none() {
+            return;
+        }
+        self.dirty_schedules
+            .lock()
+            .insert(module.to_string(), schedules);
+    }
+
+    /// Write the schedules recorded since the last flush.
+    /// Called by the background WAL thread every 10ms alongside `flush_stateful`.
+    pub fn flush_schedules(&self) {
+        let Some(root) = &self.modules_root else {
+            return;
+        };
+        let batch = {
+            let mut guard = self.dirty_schedules.lock();
+            if guard.is_empty() {
+                return;
+            }
+            std::mem::take(&mut *guard)
+        };
+
+        for (module, schedules) in batch {
+            let module_dir = root.join(&module);
+            if !module_dir.exists() {
+                // Removed since the schedules were recorded.
+                continue;
+            }
+            let path = module_dir.join(SCHEDULES_FILE);
+            if let Ok(encoded) = encode(&schedules) {
+                let tmp = path.with_extension("bin.tmp");
+                if fs::write(&tmp, encoded).is_ok() {
+                    let _ = fs::rename(&tmp, &path);
+                }
+            }
+        }
+    }
+
+    /// Pending schedules of a module, including ones not flushed yet.
+    pub fn read_schedules(&self, module: &str) -> Result<Vec<ScheduleInfo>, IntersticeError> {
+        let Some(root) = &self.modules_root else {
+            return Ok(Vec::new());
+        };
+        if let Some(schedules) = self.dirty_schedules.lock().get(module) {
+            return Ok(schedules.clone());
+        }
+        let path = root.join(module).join(SCHEDULES_FILE);
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        let bytes = fs::read(&path).map_err(|err| {
+            IntersticeError::Internal(format!("Failed to read {:?}: {}", path, err))
+        })?;
+        decode(&bytes).map_err(|err| {
+            IntersticeError::Internal(format!("Failed to decode schedules {:?}: {}", path, err))
+        })
+    }
+
+    /// Write the checkpoints and branches of a module, without the rows they
+    /// saved. Unlike schedules they are written right away: they change rarely
+    /// and a checkpoint must survive a crash right after the reducer that created
+    /// it.
+    pub(crate) fn persist_timeline(&self, module: &str, timeline: &Timeline) -> Result<(), IntersticeError> {
+        let Some(root) = &self.modules_root else {
+            return Ok(());
+        };
+        let timeline_dir = root.join(module).join(TIMELINE_DIR);
+        let encoded = encode(timeline).map_err(|err| {
+            IntersticeError::Internal(format!("Failed to encode timeline: {err}"))
+        })?;
+        Self::write_timeline_file(&timeline_dir.join(TIMELINE_FILE), &encoded)
+    }
+
+    /// Write the rows saved by a checkpoint or a branch, or remove them when
+    /// `saved` is `None`.
+    pub(crate) fn persist_timeline_rows<T: Serialize>(
+        &self,
+        module: &str,
+        rows: &TimelineRows,
+        saved: Option<&T>,
+    ) -> Result<(), IntersticeError> {
+        let Some(root) = &self.modules_root else {
+            return Ok(());
+        };
+        let path = Self::timeline_rows_path(root, module, rows);
+        let Some(saved) = saved else {
+            return match fs::remove_file(&path) {
+                Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(
+                    IntersticeError::Internal(format!("Failed to remove {:?}: {}", path, err)),
+                ),
+                _ => Ok(()),
+            };
+        };
+        let encoded = encode(saved).map_err(|err| {
+            IntersticeError::Internal(format!("Failed to encode saved rows: {err}"))
+        })?;
+        Self::write_timeline_file(&path, &encoded)
+    }
+
+    /// Checkpoints and branches of a module, without the rows they saved, `None`
+    /// when it has none.
+    pub(crate) fn read_timeline(&self, module: &str) -> Result<Option<Timeline>, IntersticeError> {
+        let Some(root) = &self.modules_root else {
+            return Ok(None);
+        };
+        let path = root.join(module).join(TIMELINE_DIR).join(TIMELINE_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let bytes = fs::read(&path).map_err(|err| {
+            IntersticeError::Internal(format!("Failed to read {:?}: {}", path, err))
+        })?;
+        decode(&bytes).map(Some).map_err(|err| {
+            IntersticeError::Internal(format!("Failed to decode timeline {:?}: {}", path, err))
+        })
+    }
+
+    /// Rows saved by a checkpoint or a branch of the persisted timeline.
+    pub(crate) fn read_timeline_rows<T: DeserializeOwned>(
+        &self,
+        module: &str,
+        rows: &TimelineRows,
+    ) -> Result<T, IntersticeError> {
+        let Some(root) = &self.modules_root else {
+            return Err(IntersticeError::Internal(
+                "Saved rows are only kept by nodes with a data directory".into(),
+            ));
+        };
+        let path = Self::timeline_rows_path(root, module, rows);
+        let bytes = fs::read(&path).map_err(|err| {
+            IntersticeError::Internal(format!("Failed to read {:?}: {}", path, err))
+        })?;
+        decode(&bytes).map_err(|err| {
+            IntersticeError::Internal(format!("Failed to decode saved rows {:?}: {}", path, err))
+        })
+    }
+
+    /// File of the rows saved by a checkpoint or branch, named after its
+    /// hex-encoded name.
+    fn timeline_rows_path(root: &Path, module: &str, rows: &TimelineRows) -> PathBuf {
+        let (dir, name) = match rows {
+            TimelineRows::Checkpoint(name) => ("checkpoints", name),
+            TimelineRows::Branch(name) => ("branches", name),
+        };
+        let file_name = name.bytes().map(|b| format!("{:02x}", b)).collect::<String>();
+        root.join(module)
+            .join(TIMELINE_DIR)
+            .join(dir)
+            .join(format!("{}.bin", file_name))
+    }
+
+    fn write_timeline_file(path: &Path, encoded: &[u8]) -> Result<(), IntersticeError> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|err| {
+                IntersticeError::Internal(format!("Failed to create {:?}: {}", dir, err))
+            })?;
+        }
+        let tmp = path.with_extension("bin.tmp");
+        fs::write(&tmp, encoded)
+            .and_then(|_| fs::rename(&tmp, path))
+            .map_err(|err| {
+                IntersticeError::Internal(format!("Failed to write {:?}: {}", path, err))
+            })
+    }
+
+    /// Sequence number of the last log entry of a logged table.
+    pub fn table_sequence(&self, module: &str, table: &str) -> Option<u64> {
+        self.modules_root.as_ref()?;
+        let state = self.tables.lock().get(&TableKey::new(module, table))?.clone();
+        let guard = state.lock();
+        if guard.persistence != PersistenceKind::Logged {
+            return None;
+        }
+        guard.next_seq.checked_sub(1)
+    }
+
+    /// Rows of a logged table after its log entry `sequence`, with the branch
+    /// lineage of the table at that point. They are rebuilt from the latest
+    /// snapshot at or before `sequence` and the log entries following it; history
+    /// older than the previous snapshot has been compacted away.
+    pub fn rows_as_of(
+        &self,
+        module: &str,
+        schema: &TableSchema,
+        sequence: u64,
+    ) -> Result<(Vec<Row>, Vec<String>), IntersticeError> {
+        let Some(root) = &self.modules_root else {
+            return Err(IntersticeError::Internal(
+                "Table history is only kept by nodes with a data directory".into(),
+            ));
+        };
+        if schema.persistence != PersistenceKind::Logged {
+            return Err(IntersticeError::Internal(format!(
+                "Table '{}' is not logged, only logged tables keep a history",
+                schema.name
+            )));
+        }
+        let state = self.get_or_create_state(module, &schema.name, PersistenceKind::Logged)?;
+        // Holding the state keeps a snapshot from replacing the files while reading.
+        let guard = state.lock();
+        if sequence >= guard.next_seq {
+            return Err(IntersticeError::Internal(format!(
+                "Table '{}' has no log entry {} yet",
+                schema.name, sequence
+            )));
+        }
+
+        let snapshots = root.join(module).join("snapshots");
+        let mut base = None;
+        for path in [
+            snapshots.join(format!("{}.snap", schema.name)),
+            snapshots.join(format!("{}.prev.snap", schema.name)),
+        ] {
+            if path.exists() {
+                let snapshot = Self::read_snapshot_file(&path)?;
+                if snapshot.last_seq <= sequence {
+                    base = Some(snapshot);
+                    break;
+                }
+            }
+        }
+
+        let mut table = Table::new(schema.clone());
+        let mut branches = Vec::new();
+        let mut next_seq = 0;
+        if let Some(snapshot) = base {
+            table.restore_from_rows(snapshot.rows)?;
+            branches = snapshot.branches;
+            next_seq = snapshot.last_seq + 1;
+        }
+        let log_path = self.log_path(root, module, &schema.name);
+        Self::read_log_entries(&log_path, |entry| {
+            if entry.seq < next_seq || entry.seq > sequence {
+                return Ok(());
+            }
+            if entry.seq != next_seq {
+                return Err(IntersticeError::Internal(format!(
+                    "The history of table '{}' at {} has been compacted",
+                    schema.name, sequence
+                )));
+            }
+            TableStore::apply_entry(&mut table, &entry.operation)?;
+            entry.operation.apply_lineage(&mut branches);
+            next_seq += 1;
+            Ok(())
+        })?;
+        if next_seq <= sequence {
+            return Err(IntersticeError::Internal(format!(
+                "The history of table '{}' at {} has been compacted",
+                schema.name, sequence
+            )));
+        }
+        Ok((table.snapshot_rows(), branches))
+    }
+
     pub fn restore_table(&self, module: &str, table: &mut Table) -> Result<(), IntersticeError> {
         let Some(root) = &self.modules_root else {
             return Ok(());
@@ -372,7 +729,13 @@ impl TableStore {
         // Logged: snapshot + WAL replay
         let table_name = table.schema.name.clone();
         let module_paths = self.ensure_module_dirs(root, module)?;
-        let snapshot_path = module_paths.snapshots.join(format!("{}.snap", table_name));
+        let mut snapshot_path = module_paths.snapshots.join(format!("{}.snap", table_name));
+        let previous_path = module_paths.snapshots.join(format!("{}.prev.snap", table_name));
+        // Snapshots written before they were staged could be interrupted after
+        // moving the current one aside; the log still follows the previous one.
+        if !snapshot_path.exists() && previous_path.exists() {
+            snapshot_path = previous_path;
+        }
         let log_path = module_paths.logs.join(format!("{}.log", table_name));
         // Track whether a snapshot actually exists. Without one, `last_seq`
         // defaults to 0, which collides with the first WAL entry's seq (also 0)
@@ -382,10 +745,12 @@ impl TableStore {
 
         table.restore_from_rows(snapshot.rows)?;
         let mut last_seq = snapshot.last_seq;
+        let mut branches = snapshot.branches;
 
         Self::read_log_entries(&log_path, |entry| {
             if !had_snapshot || entry.seq > snapshot.last_seq {
                 TableStore::apply_entry(table, &entry.operation)?;
+                entry.operation.apply_lineage(&mut branches);
                 last_seq = entry.seq;
             }
             Ok(())
@@ -397,10 +762,284 @@ impl TableStore {
         guard.persistence = table.schema.persistence.clone();
         guard.last_snapshot_seq = last_seq;
         guard.next_seq = last_seq.saturating_add(1);
+        guard.branches = branches;
 
         Ok(())
     }
 
+    /// Record the table layouts the module's rows are persisted with, so a later
+    /// upgrade can tell which tables need migrating.
+    pub fn write_table_layouts(
+        &self,
+        module: &str,
+        tables: &[TableSchema],
+    ) -> Result<(), IntersticeError> {
+        let Some(root) = &self.modules_root else {
+            return Ok(());
+        };
+        Self::write_table_layouts_file(&root.join(module).join(TABLE_LAYOUTS_FILE), tables)
+    }
+
+    /// Rewrite the persisted rows of every logged or stateful table whose layout
+    /// differs from the one recorded by [`write_table_layouts`](Self::write_table_layouts),
+    /// following the tables' `#[migrate]` rules. The new snapshots and row files
+    /// are staged first and swapped in once every table converted, so a failed
+    /// or interrupted migration never leaves a module with half-migrated data.
+    /// Returns the names of the migrated tables.
+    pub fn migrate_module(
+        &self,
+        module: &str,
+        tables: &[TableSchema],
+    ) -> Result<Vec<String>, IntersticeError> {
+        match self.stage_module_migration(module, tables)? {
+            Some(staged) => self.commit_migration(module, staged),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// First half of [`migrate_module`](Self::migrate_module): convert the rows of
+    /// the changed tables and stage their files without touching the module's
+    /// data. The converted tables are returned with the staged files, which are
+    /// dropped again unless [`commit_migration`](Self::commit_migration) is called.
+    pub fn stage_module_migration(
+        &self,
+        module: &str,
+        tables: &[TableSchema],
+    ) -> Result<Option<StagedMigration>, IntersticeError> {
+        let Some(root) = &self.modules_root else {
+            return Ok(None);
+        };
+        let module_dir = root.join(module);
+        Self::finish_migration(&module_dir)?;
+
+        let layouts_path = module_dir.join(TABLE_LAYOUTS_FILE);
+        if !layouts_path.exists() {
+            // Data written before layouts were recorded: nothing to compare against.
+            return Ok(None);
+        }
+        let bytes = fs::read(&layouts_path).map_err(|err| {
+            IntersticeError::Internal(format!("Failed to read {:?}: {}", layouts_path, err))
+        })?;
+        // Layouts recorded by a node with an older ABI may not decode anymore; like
+        // data predating them, there is then nothing to migrate from.
+        let Ok(previous_tables) = decode::<Vec<TableSchema>>(&bytes) else {
+            return Ok(None);
+        };
+
+        let changed: Vec<(&TableSchema, &TableSchema)> = tables
+            .iter()
+            .filter(|table| table.persistence != PersistenceKind::Ephemeral)
+            .filter_map(|table| {
+                previous_tables
+                    .iter()
+                    .find(|previous| {
+                        previous.name == table.name && previous.persistence == table.persistence
+                    })
+                    .filter(|previous| !table.same_layout(previous))
+                    .map(|previous| (previous, table))
+            })
+            .collect();
+        if changed.is_empty() {
+            return Ok(None);
+        }
+
+        // Make sure every pending write of the old layout is on disk before reading it.
+        self.flush_wal();
+        self.flush_stateful();
+
+        let staging = module_dir.join(MIGRATION_STAGING_DIR);
+        if staging.exists() {
+            fs::remove_dir_all(&staging).map_err(|err| {
+                IntersticeError::Internal(format!("Failed to clear {:?}: {}", staging, err))
+            })?;
+        }
+        let mut staged = StagedMigration {
+            module_dir,
+            migrated: changed.iter().map(|(_, table)| table.name.clone()).collect(),
+            tables: Vec::new(),
+        };
+        staged.tables = self.stage_migration(module, &staged.staging_dir(), &changed, tables)?;
+        Ok(Some(staged))
+    }
+
+    /// Second half of [`migrate_module`](Self::migrate_module): swap the staged
+    /// files in. Returns the names of the migrated tables.
+    pub fn commit_migration(
+        &self,
+        module: &str,
+        staged: StagedMigration,
+    ) -> Result<Vec<String>, IntersticeError> {
+        let Some(root) = &self.modules_root else {
+            return Ok(Vec::new());
+        };
+        // The rename is the commit point: from here on the migration is replayed
+        // on the next load if it gets interrupted.
+        fs::rename(staged.staging_dir(), staged.module_dir.join(MIGRATION_DIR)).map_err(|err| {
+            IntersticeError::Internal(format!("Failed to commit migration: {err}"))
+        })?;
+        self.forget_table_states(root, module, &staged.migrated);
+        Self::finish_migration(&staged.module_dir)?;
+
+        Ok(staged.migrated.clone())
+    }
+
+    fn stage_migration(
+        &self,
+        module: &str,
+        staging: &Path,
+        changed: &[(&TableSchema, &TableSchema)],
+        tables: &[TableSchema],
+    ) -> Result<Vec<Table>, IntersticeError> {
+        let mut migrated = Vec::new();
+        for (previous, current) in changed {
+            let failed = |reason: String| IntersticeError::SchemaMigrationFailed {
+                module_name: module.to_string(),
+                table_name: current.name.clone(),
+                reason,
+            };
+
+            let mut old_table = Table::new((*previous).clone());
+            self.restore_table(module, &mut old_table)?;
+            // Inserting into a table of the new layout also re-checks its indexes.
+            let mut new_table = Table::new((*current).clone());
+            for row in old_table.scan() {
+                let row = current.migrate_row(previous, row.clone()).map_err(failed)?;
+                new_table
+                    .insert(row)
+                    .map_err(|err| failed(err.to_string()))?;
+            }
+
+            match current.persistence {
+                PersistenceKind::Logged => {
+                    let state =
+                        self.get_or_create_state(module, &current.name, PersistenceKind::Logged)?;
+                    let (last_seq, branches) = {
+                        let guard = state.lock();
+                        (guard.last_snapshot_seq, guard.branches.clone())
+                    };
+                    let snapshots = staging.join("snapshots");
+                    fs::create_dir_all(&snapshots).map_err(|err| {
+                        IntersticeError::Internal(format!("Failed to create {:?}: {}", snapshots, err))
+                    })?;
+                    Self::write_snapshot_file(
+                        &snapshots.join(format!("{}.snap", current.name)),
+                        last_seq,
+                        new_table.scan(),
+                        &branches,
+                    )?;
+                }
+                PersistenceKind::Stateful => {
+                    let dir = staging.join("stateful").join(&current.name);
+                    fs::create_dir_all(&dir).map_err(|err| {
+                        IntersticeError::Internal(format!("Failed to create {:?}: {}", dir, err))
+                    })?;
+                    for row in new_table.scan() {
+                        let pk = IndexKey::try_from(&row.primary_key)
+                            .map_err(IntersticeError::Internal)?;
+                        let encoded = encode(row).map_err(|err| {
+                            IntersticeError::Internal(format!("Failed to encode row: {err}"))
+                        })?;
+                        fs::write(dir.join(format!("{}.row", Self::pk_filename(&pk))), encoded)
+                            .map_err(|err| {
+                                IntersticeError::Internal(format!("Failed to write row file: {err}"))
+                            })?;
+                    }
+                }
+                PersistenceKind::Ephemeral => {}
+            }
+            migrated.push(new_table);
+        }
+        Self::write_table_layouts_file(&staging.join(TABLE_LAYOUTS_FILE), tables)?;
+        Ok(migrated)
+    }
+
+    /// Move a committed migration into place. Every step can be repeated, so a
+    /// node that stopped halfway simply redoes it on the next load.
+    fn finish_migration(module_dir: &Path) -> Result<(), IntersticeError> {
+        let migration = module_dir.join(MIGRATION_DIR);
+        if !migration.exists() {
+            return Ok(());
+        }
+        let io_err = |err: std::io::Error| {
+            IntersticeError::Internal(format!("Failed to apply migration: {err}"))
+        };
+
+        let snapshots = migration.join("snapshots");
+        if snapshots.exists() {
+            fs::create_dir_all(module_dir.join("snapshots")).map_err(io_err)?;
+            for entry in fs::read_dir(&snapshots).map_err(io_err)? {
+                let path = entry.map_err(io_err)?.path();
+                let Some(table) = path.file_stem().map(|stem| stem.to_owned()) else {
+                    continue;
+                };
+                // Drop the old log and previous snapshot first: they use the old layout.
+                let log_path = module_dir
+                    .join("logs")
+                    .join(format!("{}.log", table.to_string_lossy()));
+                if log_path.exists() {
+                    fs::remove_file(&log_path).map_err(io_err)?;
+                }
+                let previous_path = module_dir
+                    .join("snapshots")
+                    .join(format!("{}.prev.snap", table.to_string_lossy()));
+                if previous_path.exists() {
+                    fs::remove_file(&previous_path).map_err(io_err)?;
+                }
+                fs::rename(&path, module_dir.join("snapshots").join(path.file_name().unwrap()))
+                    .map_err(io_err)?;
+            }
+        }
+
+        let stateful = migration.join("stateful");
+        if stateful.exists() {
+            for entry in fs::read_dir(&stateful).map_err(io_err)? {
+                let path = entry.map_err(io_err)?.path();
+                let target = module_dir.join("stateful").join(path.file_name().unwrap());
+                if target.exists() {
+                    fs::remove_dir_all(&target).map_err(io_err)?;
+                }
+                fs::create_dir_all(module_dir.join("stateful")).map_err(io_err)?;
+                fs::rename(&path, &target).map_err(io_err)?;
+            }
+        }
+
+        let layouts = migration.join(TABLE_LAYOUTS_FILE);
+        if layouts.exists() {
+            fs::rename(&layouts, module_dir.join(TABLE_LAYOUTS_FILE)).map_err(io_err)?;
+        }
+        fs::remove_dir_all(&migration).map_err(io_err)
+    }
+
+    fn write_table_layouts_file(path: &Path, tables: &[TableSchema]) -> Result<(), IntersticeError> {
+        let encoded = encode(&tables.to_vec()).map_err(|err| {
+            IntersticeError::Internal(format!("Failed to encode table layouts: {err}"))
+        })?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|err| {
+                IntersticeError::Internal(format!("Failed to create {:?}: {}", parent, err))
+            })?;
+        }
+        let tmp_path = path.with_extension("bin.tmp");
+        fs::write(&tmp_path, encoded).map_err(|err| {
+            IntersticeError::Internal(format!("Failed to write table layouts: {err}"))
+        })?;
+        fs::rename(&tmp_path, path).map_err(|err| {
+            IntersticeError::Internal(format!("Failed to write table layouts: {err}"))
+        })
+    }
+
+    /// Drop cached sequence numbers and open log handles of tables whose files were
+    /// replaced, so the next restore starts from what is on disk.
+    fn forget_table_states(&self, root: &Path, module: &str, tables: &[String]) {
+        self.tables
+            .lock()
+            .retain(|key, _| key.module != module || !tables.contains(&key.table));
+        let mut wal_writers = self.wal_writers.lock();
+        for table in tables {
+            wal_writers.remove(&self.log_path(root, module, table));
+        }
+    }
+
     pub fn clear_all(&self) -> Result<(), IntersticeError> {
         let Some(root) = &self.modules_root else {
             return Ok(());
@@ -458,17 +1097,22 @@ impl TableStore {
                             })?;
                         }
                         // Don't recreate stateful dir — it's created on demand per table
+
+                        let _ = fs::remove_file(path.join(SCHEDULES_FILE));
+                        let _ = fs::remove_dir_all(path.join(TIMELINE_DIR));
                     }
                 }
             }
         }
 
         self.tables.lock().clear();
+        self.dirty_schedules.lock().clear();
         Ok(())
     }
 
     pub fn cleanup_module(&self, module: &str) {
         self.tables.lock().retain(|key, _| key.module != module);
+        self.dirty_schedules.lock().remove(module);
         // Close WAL writers for this module's log files.
         if let Some(root) = &self.modules_root {
             let module_log_dir = root.join(module).join("logs");
@@ -621,11 +1265,29 @@ impl TableStore {
         Ok(())
     }
 
-    fn write_snapshot_file(path: &Path, seq: u64, rows: &[Row]) -> Result<(), IntersticeError> {
+    fn write_snapshot_file(
+        path: &Path,
+        seq: u64,
+        rows: &[Row],
+        branches: &[String],
+    ) -> Result<(), IntersticeError> {
+        let tmp_path = Self::stage_snapshot_file(path, seq, rows, branches)?;
+        Self::finalize_snapshot_file(&tmp_path, path)
+    }
+
+    /// Write a snapshot next to `path`, to be moved there by
+    /// [`finalize_snapshot_file`](Self::finalize_snapshot_file). Returns its path.
+    fn stage_snapshot_file(
+        path: &Path,
+        seq: u64,
+        rows: &[Row],
+        branches: &[String],
+    ) -> Result<PathBuf, IntersticeError> {
         let snapshot = TableSnapshot {
             version: SNAPSHOT_VERSION,
             last_seq: seq,
             rows: rows.to_vec(),
+            branches: branches.to_vec(),
         };
         let encoded = encode(&snapshot).map_err(|err| {
             IntersticeError::Internal(format!("Failed to encode snapshot: {err}"))
@@ -645,10 +1307,33 @@ impl TableStore {
                 IntersticeError::Internal(format!("Failed to sync snapshot: {err}"))
             })?;
         }
-        fs::rename(&tmp_path, path).map_err(|err| {
+        Ok(tmp_path)
+    }
+
+    fn finalize_snapshot_file(tmp_path: &Path, path: &Path) -> Result<(), IntersticeError> {
+        fs::rename(tmp_path, path).map_err(|err| {
             IntersticeError::Internal(format!("Failed to finalize snapshot {:?}: {}", path, err))
-        })?;
-        Ok(())
+        })
+    }
+
+    /// Keep the current snapshot as the previous one without moving it: hard
+    /// linked where the file system allows it, copied otherwise.
+    fn keep_previous_snapshot(snapshot_path: &Path, previous_path: &Path) -> Result<(), IntersticeError> {
+        let keep_err = |err: std::io::Error| {
+            IntersticeError::Internal(format!(
+                "Failed to keep previous snapshot {:?}: {}",
+                previous_path, err
+            ))
+        };
+        if previous_path.exists() {
+            fs::remove_file(previous_path).map_err(keep_err)?;
+        }
+        if fs::hard_link(snapshot_path, previous_path).is_ok() {
+            return Ok(());
+        }
+        let tmp_path = previous_path.with_extension("snap.tmp");
+        fs::copy(snapshot_path, &tmp_path).map_err(keep_err)?;
+        fs::rename(&tmp_path, previous_path).map_err(keep_err)
     }
 
     fn read_snapshot_file(path: &Path) -> Result<TableSnapshot, IntersticeError> {
@@ -657,13 +1342,25 @@ impl TableStore {
                 version: SNAPSHOT_VERSION,
                 last_seq: 0,
                 rows: Vec::new(),
+                branches: Vec::new(),
             });
         }
         let bytes = fs::read(path).map_err(|err| {
             IntersticeError::Internal(format!("Failed to read snapshot {:?}: {}", path, err))
         })?;
-        decode(&bytes)
-            .map_err(|err| IntersticeError::Internal(format!("Failed to decode snapshot: {err}")))
+        let decode_err =
+            |err| IntersticeError::Internal(format!("Failed to decode snapshot: {err}"));
+        // Every version starts like the first one, which tells the version apart.
+        let snapshot: TableSnapshotV1 = decode(&bytes).map_err(decode_err)?;
+        if snapshot.version >= 2 {
+            return decode(&bytes).map_err(decode_err);
+        }
+        Ok(TableSnapshot {
+            version: snapshot.version,
+            last_seq: snapshot.last_seq,
+            rows: snapshot.rows,
+            branches: Vec::new(),
+        })
     }
 
     fn compact_log(path: &Path, keep_after_seq: u64) -> Result<(), IntersticeError> {
@@ -730,6 +1427,10 @@ impl TableStore {
             LogOperation::Clear => {
                 table.clear();
             }
+            LogOperation::Restore { rows } | LogOperation::Discard { rows, .. } => {
+                table.restore_from_rows(rows.clone())?;
+            }
+            LogOperation::Fork { .. } | LogOperation::Promote { .. } => {}
         }
         Ok(())
     }
@@ -770,4 +1471,394 @@ struct TableSnapshot {
     version: u16,
     last_seq: u64,
     rows: Vec<Row>,
+    branches: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TableSnapshotV1 {
+    version: u16,
+    last_seq: u64,
+    rows: Vec<Row>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use interstice_abi::{
+        FieldDef, FieldMigration, IntersticeType, IntersticeValue, TableLimits, TableVisibility,
+    };
+
+    fn schema(fields: Vec<FieldDef>, migrations: Vec<FieldMigration>) -> TableSchema {
+        TableSchema {
+            name: "players".into(),
+            type_name: "Players".into(),
+            visibility: TableVisibility::Private,
+            fields,
+            primary_key: FieldDef {
+                name: "id".into(),
+                field_type: IntersticeType::U64,
+                default: None,
+            },
+            primary_key_auto_inc: false,
+            indexes: Vec::new(),
+            persistence: PersistenceKind::Logged,
+            limits: TableLimits::default(),
+            migrations,
+            view: None,
+        }
+    }
+
+    #[test]
+    fn migrate_module_rewrites_logged_rows() {
+        let root = std::env::temp_dir().join(format!("interstice-migration-{}", uuid::Uuid::new_v4()));
+        let store = TableStore::new(Some(root.clone()));
+        let name = FieldDef {
+            name: "name".into(),
+            field_type: IntersticeType::String,
+            default: None,
+        };
+        let previous = schema(vec![name.clone()], Vec::new());
+        store.write_table_layouts("game", &[previous]).unwrap();
+        for id in 0..3u64 {
+            store
+                .record_logged_operation(
+                    "game",
+                    "players",
+                    LogOperation::Insert {
+                        primary_key: IndexKey::U64(id),
+                        row: Some(Row {
+                            primary_key: IntersticeValue::U64(id),
+                            entries: vec![IntersticeValue::String(format!("p{id}"))],
+                        }),
+                    },
+                )
+                .unwrap();
+        }
+
+        let current = schema(
+            vec![
+                name,
+                FieldDef {
+                    name: "score".into(),
+                    field_type: IntersticeType::U32,
+                    default: None,
+                },
+            ],
+            vec![FieldMigration {
+                field_name: "score".into(),
+                renamed_from: None,
+                default: Some(IntersticeValue::U32(7)),
+            }],
+        );
+        let migrated = store.migrate_module("game", std::slice::from_ref(&current)).unwrap();
+        assert_eq!(migrated, vec!["players".to_string()]);
+        assert!(!root.join("game").join(MIGRATION_DIR).exists());
+
+        let mut table = Table::new(current.clone());
+        store.restore_table("game", &mut table).unwrap();
+        assert_eq!(table.scan().len(), 3);
+        assert!(table.scan().iter().all(|row| row.entries[1] == IntersticeValue::U32(7)));
+        // The recorded layout is now the new one: loading again migrates nothing.
+        assert!(store.migrate_module("game", &[current]).unwrap().is_empty());
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn migrate_module_fills_new_fields_with_their_default() {
+        let root = std::env::temp_dir().join(format!("interstice-migration-{}", uuid::Uuid::new_v4()));
+        let store = TableStore::new(Some(root.clone()));
+        let name = FieldDef {
+            name: "name".into(),
+            field_type: IntersticeType::String,
+            default: None,
+        };
+        store
+            .write_table_layouts("game", &[schema(vec![name.clone()], Vec::new())])
+            .unwrap();
+        store
+            .record_logged_operation(
+                "game",
+                "players",
+                LogOperation::Insert {
+                    primary_key: IndexKey::U64(1),
+                    row: Some(Row {
+                        primary_key: IntersticeValue::U64(1),
+                        entries: vec![IntersticeValue::String("ada".into())],
+                    }),
+                },
+            )
+            .unwrap();
+
+        // Declared with `#[interstice(default)]` rather than `#[migrate(default = ...)]`.
+        let current = schema(
+            vec![
+                name,
+                FieldDef {
+                    name: "level".into(),
+                    field_type: IntersticeType::U8,
+                    default: Some(IntersticeValue::U8(0)),
+                },
+            ],
+            Vec::new(),
+        );
+        let migrated = store.migrate_module("game", std::slice::from_ref(&current)).unwrap();
+        assert_eq!(migrated, vec!["players".to_string()]);
+
+        let mut table = Table::new(current);
+        store.restore_table("game", &mut table).unwrap();
+        assert_eq!(
+            table.scan()[0].entries,
+            vec![IntersticeValue::String("ada".into()), IntersticeValue::U8(0)]
+        );
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn dropped_migrations_leave_the_data_untouched() {
+        let root = std::env::temp_dir().join(format!("interstice-migration-{}", uuid::Uuid::new_v4()));
+        let store = TableStore::new(Some(root.clone()));
+        let name = FieldDef {
+            name: "name".into(),
+            field_type: IntersticeType::String,
+            default: None,
+        };
+        let previous = schema(vec![name.clone()], Vec::new());
+        store.write_table_layouts("game", std::slice::from_ref(&previous)).unwrap();
+        store
+            .record_logged_operation(
+                "game",
+                "players",
+                LogOperation::Insert {
+                    primary_key: IndexKey::U64(1),
+                    row: Some(Row {
+                        primary_key: IntersticeValue::U64(1),
+                        entries: vec![IntersticeValue::String("p1".into())],
+                    }),
+                },
+            )
+            .unwrap();
+
+        let current = schema(
+            vec![
+                name,
+                FieldDef {
+                    name: "score".into(),
+                    field_type: IntersticeType::U32,
+                    default: None,
+                },
+            ],
+            vec![FieldMigration {
+                field_name: "score".into(),
+                renamed_from: None,
+                default: Some(IntersticeValue::U32(7)),
+            }],
+        );
+        let mut staged = store
+            .stage_module_migration("game", std::slice::from_ref(&current))
+            .unwrap()
+            .unwrap();
+        let tables = staged.take_tables();
+        assert_eq!(tables[0].scan()[0].entries[1], IntersticeValue::U32(7));
+        assert!(root.join("game").join(MIGRATION_STAGING_DIR).exists());
+        drop(staged);
+        assert!(!root.join("game").join(MIGRATION_STAGING_DIR).exists());
+
+        // Still the old layout and rows: the migration is staged again next time.
+        let mut table = Table::new(previous);
+        store.restore_table("game", &mut table).unwrap();
+        assert_eq!(table.scan()[0].entries, vec![IntersticeValue::String("p1".into())]);
+        assert_eq!(store.migrate_module("game", &[current]).unwrap(), vec!["players".to_string()]);
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    fn player(id: u64) -> Row {
+        Row {
+            primary_key: IntersticeValue::U64(id),
+            entries: vec![IntersticeValue::String(format!("p{id}"))],
+        }
+    }
+
+    fn insert_player(store: &TableStore, id: u64) -> Option<SnapshotPlan> {
+        store
+            .record_logged_operation(
+                "game",
+                "players",
+                LogOperation::Insert {
+                    primary_key: IndexKey::U64(id),
+                    row: Some(player(id)),
+                },
+            )
+            .unwrap()
+    }
+
+    fn players_schema() -> TableSchema {
+        schema(
+            vec![FieldDef {
+                name: "name".into(),
+                field_type: IntersticeType::String,
+                default: None,
+            }],
+            Vec::new(),
+        )
+    }
+
+    #[test]
+    fn rows_as_of_reach_back_past_the_latest_snapshot() {
+        let root = std::env::temp_dir().join(format!("interstice-history-{}", uuid::Uuid::new_v4()));
+        let store = TableStore::new(Some(root.clone()));
+        let schema = players_schema();
+        let mut table = Table::new(schema.clone());
+        for id in 0..=2 * SNAPSHOT_INTERVAL {
+            table.insert(player(id)).unwrap();
+            if let Some(plan) = insert_player(&store, id) {
+                store.snapshot_logged_table(plan, table.snapshot_rows()).unwrap();
+            }
+        }
+        assert_eq!(store.table_sequence("game", "players"), Some(2 * SNAPSHOT_INTERVAL));
+
+        // Between the two snapshots, rebuilt from the previous one.
+        let (rows, branches) = store.rows_as_of("game", &schema, SNAPSHOT_INTERVAL + 3).unwrap();
+        assert_eq!(rows.len() as u64, SNAPSHOT_INTERVAL + 4);
+        assert!(branches.is_empty());
+        // Before the previous snapshot, the log has been compacted.
+        assert!(store.rows_as_of("game", &schema, 3).is_err());
+        assert!(store.rows_as_of("game", &schema, 2 * SNAPSHOT_INTERVAL + 1).is_err());
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn tables_survive_a_crash_while_replacing_their_snapshot() {
+        let root = std::env::temp_dir().join(format!("interstice-history-{}", uuid::Uuid::new_v4()));
+        let store = TableStore::new(Some(root.clone()));
+        let schema = players_schema();
+        let mut table = Table::new(schema.clone());
+        for id in 0..=2 * SNAPSHOT_INTERVAL + 2 {
+            table.insert(player(id)).unwrap();
+            if let Some(plan) = insert_player(&store, id) {
+                store.snapshot_logged_table(plan, table.snapshot_rows()).unwrap();
+            }
+        }
+        store.flush_wal();
+        let snapshots = root.join("game").join("snapshots");
+        let snapshot_path = snapshots.join("players.snap");
+        let previous_path = snapshots.join("players.prev.snap");
+        assert!(previous_path.exists());
+        assert!(!snapshots.join("players.snap.tmp").exists());
+
+        // The files as left by a crash between staging the new snapshot and
+        // moving it in place: the current snapshot was kept too.
+        fs::write(snapshots.join("players.snap.tmp"), b"cut short").unwrap();
+        let restored = TableStore::new(Some(root.clone()));
+        let mut restored_table = Table::new(schema.clone());
+        restored.restore_table("game", &mut restored_table).unwrap();
+        assert_eq!(restored_table.snapshot_rows().len() as u64, 2 * SNAPSHOT_INTERVAL + 3);
+
+        // Without the current snapshot, the previous one and its log are used.
+        fs::remove_file(&snapshot_path).unwrap();
+        let restored = TableStore::new(Some(root.clone()));
+        let mut restored_table = Table::new(schema);
+        restored.restore_table("game", &mut restored_table).unwrap();
+        assert_eq!(restored_table.snapshot_rows().len() as u64, 2 * SNAPSHOT_INTERVAL + 3);
+        assert_eq!(restored.table_sequence("game", "players"), Some(2 * SNAPSHOT_INTERVAL + 2));
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn saved_rows_of_the_timeline_are_kept_in_files_of_their_own() {
+        let root = std::env::temp_dir().join(format!("interstice-history-{}", uuid::Uuid::new_v4()));
+        let store = TableStore::new(Some(root.clone()));
+        let checkpoint = TimelineRows::Checkpoint("before/boss".into());
+        let branch = TimelineRows::Branch("before/boss".into());
+        store.persist_timeline("game", &Timeline::default()).unwrap();
+        store.persist_timeline_rows("game", &checkpoint, Some(&vec![player(1)])).unwrap();
+        store
+            .persist_timeline_rows("game", &branch, Some(&vec![player(1), player(2)]))
+            .unwrap();
+
+        let timeline_dir = root.join("game").join(TIMELINE_DIR);
+        assert!(timeline_dir.join(TIMELINE_FILE).exists());
+        assert_eq!(fs::read_dir(timeline_dir.join("checkpoints")).unwrap().count(), 1);
+        let rows: Vec<Row> = store.read_timeline_rows("game", &checkpoint).unwrap();
+        assert_eq!(rows, vec![player(1)]);
+        let rows: Vec<Row> = store.read_timeline_rows("game", &branch).unwrap();
+        assert_eq!(rows.len(), 2);
+
+        store.persist_timeline_rows::<Vec<Row>>("game", &checkpoint, None).unwrap();
+        assert!(store.read_timeline_rows::<Vec<Row>>("game", &checkpoint).is_err());
+        // Removing rows that are already gone is not an error.
+        store.persist_timeline_rows::<Vec<Row>>("game", &checkpoint, None).unwrap();
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn rewinds_and_branches_are_replayed_from_the_log() {
+        let root = std::env::temp_dir().join(format!("interstice-history-{}", uuid::Uuid::new_v4()));
+        let store = TableStore::new(Some(root.clone()));
+        let schema = players_schema();
+        for id in 0..3 {
+            insert_player(&store, id);
+        }
+        store
+            .record_logged_operation("game", "players", LogOperation::Fork { branch: "trial".into() })
+            .unwrap();
+        store
+            .record_logged_operation("game", "players", LogOperation::Restore { rows: vec![player(1)] })
+            .unwrap();
+        store
+            .record_logged_operation("game", "players", LogOperation::Fork { branch: "inner".into() })
+            .unwrap();
+        store
+            .record_logged_operation(
+                "game",
+                "players",
+                LogOperation::Discard {
+                    branch: "inner".into(),
+                    rows: vec![player(1), player(5)],
+                },
+            )
+            .unwrap();
+
+        let (rows, branches) = store.rows_as_of("game", &schema, 3).unwrap();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(branches, vec!["trial".to_string()]);
+        let (_, branches) = store.rows_as_of("game", &schema, 5).unwrap();
+        assert_eq!(branches, vec!["trial".to_string(), "inner".to_string()]);
+
+        // A fresh store restores the rows and lineage from the files.
+        let restored = TableStore::new(Some(root.clone()));
+        let mut table = Table::new(schema.clone());
+        restored.restore_table("game", &mut table).unwrap();
+        assert_eq!(table.scan().to_vec(), vec![player(1), player(5)]);
+        let (_, branches) = restored.rows_as_of("game", &schema, 6).unwrap();
+        assert_eq!(branches, vec!["trial".to_string()]);
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn version_1_snapshots_are_still_read() {
+        let root = std::env::temp_dir().join(format!("interstice-history-{}", uuid::Uuid::new_v4()));
+        let snapshots = root.join("game").join("snapshots");
+        fs::create_dir_all(&snapshots).unwrap();
+        let snapshot = TableSnapshotV1 {
+            version: 1,
+            last_seq: 4,
+            rows: vec![player(1)],
+        };
+        fs::write(snapshots.join("players.snap"), encode(&snapshot).unwrap()).unwrap();
+
+        let store = TableStore::new(Some(root.clone()));
+        let mut table = Table::new(players_schema());
+        store.restore_table("game", &mut table).unwrap();
+        assert_eq!(table.scan().to_vec(), vec![player(1)]);
+        assert_eq!(store.table_sequence("game", "players"), Some(4));
+
+        fs::remove_dir_all(root).unwrap();
+    }
 }
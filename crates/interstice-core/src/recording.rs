@@ -0,0 +1,240 @@
+//! Record the inputs of a node and replay them.
+//!
+//! A recording starts with the modules the node runs and the rows they hold,
+//! then logs every job originating from outside the node's modules (remote
+//! reducer calls, schedule firings, ticks, and the input, audio, file, network
+//! and remote table events) with the logical time and random seed it ran with.
+//! The jobs those trigger on the node are only recorded with the time they ran
+//! at. When finished it ends with a hash of the node's tables.
+//!
+//! A replay loads the recorded modules on an empty in-process node with a
+//! virtual clock, runs the recorded jobs one after the other, each once the
+//! reducers it triggered have run at their recorded times, and compares the resulting state hash with
+//! the recorded one. Replicated tables are not part of the state, and the
+//! reducers triggered by a job run in the order the replay schedules them, which
+//! may differ from the recorded node when they race: a mismatching hash reports
+//! such a divergence.
+
+use crate::{
+    error::IntersticeError,
+    runtime::{
+        Runtime,
+        recorder::{RecordingEntry, read_entries},
+        reducer::{JobSource, ReducerJob},
+    },
+    testing::TestRuntime,
+};
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use uuid::Uuid;
+
+/// Recording of a node's inputs, started with [`crate::Node::record`].
+pub struct Recording {
+    runtime: Arc<Runtime>,
+}
+
+impl Recording {
+    pub(crate) fn start(runtime: Arc<Runtime>, path: &Path) -> Result<Self, IntersticeError> {
+        runtime.start_recording(path)?;
+        Ok(Self { runtime })
+    }
+
+    /// Stop recording and end the recording with the hash of the node's tables,
+    /// which is returned.
+    pub fn finish(self) -> Result<u64, IntersticeError> {
+        self.runtime.finish_recording()
+    }
+}
+
+/// Outcome of a replay.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplayReport {
+    /// Recorded jobs replayed.
+    pub jobs: usize,
+    /// State hash the recording ended with, `None` when it was not finished.
+    pub recorded_state_hash: Option<u64>,
+    /// State hash of the tables after the replay.
+    pub state_hash: u64,
+}
+
+impl ReplayReport {
+    /// Whether the replay ended with the recorded state.
+    pub fn matches(&self) -> bool {
+        self.recorded_state_hash == Some(self.state_hash)
+    }
+}
+
+/// Replay the recording at `path` on an empty node. Must be called from within a
+/// Tokio runtime.
+pub async fn replay(path: impl AsRef<Path>) -> Result<ReplayReport, IntersticeError> {
+    let entries = read_entries(path.as_ref())?;
+    let Some(RecordingEntry::Start { node_id, unix_ms }) = entries.first() else {
+        return Err(IntersticeError::Internal(
+            "The recording doesn't start with the recorded node".into(),
+        ));
+    };
+    let node = TestRuntime::with_node_id(parse_node_id(node_id)?, *unix_ms)?;
+    let runtime = node.runtime().clone();
+    runtime.recorder.replay_internal_jobs(&entries);
+
+    let mut report = ReplayReport {
+        jobs: 0,
+        recorded_state_hash: None,
+        state_hash: 0,
+    };
+    for entry in entries.into_iter().skip(1) {
+        match entry {
+            RecordingEntry::Start { .. } => {
+                return Err(IntersticeError::Internal(
+                    "The recording starts more than once".into(),
+                ));
+            }
+            RecordingEntry::ModuleLoaded {
+                unix_ms,
+                wasm_binary,
+                restored_tables,
+            } => {
+                runtime.clock.set(unix_ms);
+                node.load_module_restoring(&wasm_binary, restored_tables)
+                    .await?;
+            }
+            RecordingEntry::ModuleUpgraded {
+                unix_ms,
+                wasm_binary,
+                tables,
+            } => {
+                runtime.clock.set(unix_ms);
+                node.upgrade_module_restoring(&wasm_binary, tables).await?;
+            }
+            RecordingEntry::ModuleUnloaded { module_name } => {
+                Runtime::unload_module(runtime.clone(), &module_name);
+            }
+            RecordingEntry::Job {
+                unix_ms,
+                call_sequence,
+                rng_seed,
+                caller_node_id,
+                caller_module_name,
+                module_name,
+                reducer_name,
+                input,
+            } => {
+                runtime.clock.set(unix_ms);
+                // Nested calls draw their seeds from the sequence following the job's.
+                runtime
+                    .call_sequence
+                    .store(call_sequence + 1, Ordering::Relaxed);
+                // Recorded jobs may have failed on the node too.
+                let _ = node
+                    .run_job(ReducerJob {
+                        module_name,
+                        reducer_name,
+                        input,
+                        caller_node_id: parse_node_id(&caller_node_id)?,
+                        caller_module_name,
+                        request_id: None,
+                        completion: None,
+                        outcome: None,
+                        source: JobSource::Input,
+                        rng_seed: Some(rng_seed),
+                    })
+                    .await;
+                report.jobs += 1;
+            }
+            // Handed out to the jobs the replayed ones trigger.
+            RecordingEntry::InternalJob { .. } => {}
+            RecordingEntry::Finished { state_hash } => {
+                report.recorded_state_hash = Some(state_hash);
+            }
+        }
+    }
+    report.state_hash = runtime.state_hash();
+    Ok(report)
+}
+
+fn parse_node_id(node_id: &str) -> Result<Uuid, IntersticeError> {
+    Uuid::parse_str(node_id).map_err(|err| {
+        IntersticeError::Internal(format!("Invalid node id '{}' in recording: {}", node_id, err))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::fixture::{FixtureModule, Step, TableEvent, row};
+    use interstice_abi::{IntersticeType, IntersticeValue, PersistenceKind};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn replaying_an_idle_node_matches_its_recording() {
+        let path = std::env::temp_dir().join(format!("recording-{}.bin", Uuid::new_v4()));
+        let node = TestRuntime::starting_at(1_000).unwrap();
+        let recording = Recording::start(node.runtime().clone(), &path).unwrap();
+        assert!(Recording::start(node.runtime().clone(), &path).is_err());
+        let state_hash = recording.finish().unwrap();
+
+        let report = replay(&path).await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(report.jobs, 0);
+        assert_eq!(report.recorded_state_hash, Some(state_hash));
+        assert!(report.matches());
+    }
+
+    #[tokio::test]
+    async fn replaying_jobs_using_time_and_random_numbers_matches_their_recording() {
+        let path = std::env::temp_dir().join(format!("recording-{}.bin", Uuid::new_v4()));
+        let stamp = |table: &str, id| Step::UpdateTimeAndRandom {
+            table: table.into(),
+            id,
+        };
+        let zero = |id| {
+            row(
+                id,
+                vec![IntersticeValue::F64(0.0), IntersticeValue::F64(0.0)],
+            )
+        };
+        let fields = [("time", IntersticeType::F64), ("random", IntersticeType::F64)];
+        let dice = FixtureModule::new("dice")
+            .table("rolls", PersistenceKind::Stateful, &fields)
+            .table("echoes", PersistenceKind::Stateful, &fields)
+            .reducer(
+                "setup",
+                vec![
+                    Step::Insert { table: "rolls".into(), row: zero(1) },
+                    Step::Insert { table: "rolls".into(), row: zero(2) },
+                    Step::Insert { table: "echoes".into(), row: zero(1) },
+                ],
+            )
+            .reducer("roll_first", vec![stamp("rolls", 1)])
+            .reducer("roll_second", vec![stamp("rolls", 2), stamp("rolls", 2)])
+            // Triggered on the node, so only its time is recorded.
+            .reducer("echo", vec![stamp("echoes", 1)])
+            .on_table_event("echo", TableEvent::Update, "dice", "rolls");
+
+        let node = TestRuntime::starting_at(1_000).unwrap();
+        node.load_module(&dice.wasm()).await.unwrap();
+        node.call_reducer("dice", "setup", vec![]).await.unwrap();
+        let recording = Recording::start(node.runtime().clone(), &path).unwrap();
+        for reducer in ["roll_first", "roll_second", "roll_first"] {
+            node.advance(Duration::from_millis(250)).await;
+            node.call_reducer("dice", reducer, vec![]).await.unwrap();
+        }
+        let state_hash = recording.finish().unwrap();
+        let echo = &node.rows("dice", "echoes").unwrap()[0];
+        assert_eq!(echo.entries[0], IntersticeValue::F64(1_750.0));
+        assert_ne!(echo.entries[1], IntersticeValue::F64(0.0));
+        let internal_jobs = read_entries(&path)
+            .unwrap()
+            .into_iter()
+            .filter(|entry| matches!(entry, RecordingEntry::InternalJob { .. }))
+            .count();
+        assert_eq!(internal_jobs, 4);
+
+        let report = replay(&path).await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(report.jobs, 3);
+        assert_eq!(report.recorded_state_hash, Some(state_hash));
+        assert!(report.matches());
+    }
+}
@@ -0,0 +1,248 @@
+//! Access control for remote reducer and query calls.
+//!
+//! A module may restrict who calls it from another node with an `AccessPolicy`,
+//! declared for the whole module in `interstice_module!` or for a single reducer or
+//! query on its attribute (which replaces the module default). Node ids are the
+//! ones authenticated by the transport handshake; roles map node ids to names in
+//! the optional `roles.toml` of the node data directory:
+//!
+//! ```toml
+//! [roles]
+//! admin = ["6f1c…", "0b9e…"]
+//! ```
+//!
+//! Remote reducer calls are checked by the connection before a `ReducerJob` is
+//! queued, remote queries before they run. Denied calls are answered with
+//! [`RemoteError::AccessDenied`].
+
+use crate::{
+    error::IntersticeError,
+    network::protocol::{AccessDeniedReason, ErrorCode, RemoteError},
+    node::NodeId,
+    runtime::Runtime,
+};
+use interstice_abi::{IntersticeType, IntersticeValue, ModuleSchema};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, OnceLock, Weak};
+
+/// Name of the optional role assignment file in the node data directory.
+pub const ACCESS_ROLES_CONFIG: &str = "roles.toml";
+
+#[derive(Debug, Default, Deserialize)]
+struct AccessRolesConfig {
+    #[serde(default)]
+    roles: HashMap<String, Vec<String>>,
+}
+
+/// Roles held by remote nodes, as configured in `roles.toml`.
+#[derive(Debug, Clone, Default)]
+pub struct AccessRoles {
+    roles_by_node: HashMap<String, Vec<String>>,
+}
+
+impl AccessRoles {
+    pub fn new(roles: HashMap<String, Vec<String>>) -> Self {
+        let mut roles_by_node: HashMap<String, Vec<String>> = HashMap::new();
+        for (role, nodes) in roles {
+            for node in nodes {
+                roles_by_node.entry(node).or_default().push(role.clone());
+            }
+        }
+        Self { roles_by_node }
+    }
+
+    /// Load `roles.toml` from the node data directory. Without it no node holds a role.
+    pub fn load(data_path: &Path) -> Result<Self, IntersticeError> {
+        let config_path = data_path.join(ACCESS_ROLES_CONFIG);
+        if !config_path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = std::fs::read_to_string(&config_path).map_err(|err| {
+            IntersticeError::Internal(format!(
+                "Failed to read {}: {err}",
+                config_path.display()
+            ))
+        })?;
+        let config: AccessRolesConfig = toml::from_str(&contents).map_err(|err| {
+            IntersticeError::Internal(format!(
+                "Failed to parse {}: {err}",
+                config_path.display()
+            ))
+        })?;
+        Ok(Self::new(config.roles))
+    }
+
+    pub fn roles_of(&self, node_id: &NodeId) -> &[String] {
+        self.roles_by_node
+            .get(&node_id.to_string())
+            .map(Vec::as_slice)
+            .unwrap_or(&[])
+    }
+}
+
+/// Check that every access policy names a query taking the called name and
+/// returning `bool`.
+pub(crate) fn validate_access_policies(module_schema: &ModuleSchema) -> Result<(), IntersticeError> {
+    let policies = std::iter::once(&module_schema.access)
+        .chain(module_schema.reducers.iter().filter_map(|r| r.access.as_ref()))
+        .chain(module_schema.queries.iter().filter_map(|q| q.access.as_ref()));
+    for policy in policies.filter_map(|access| access.policy.as_ref()) {
+        let invalid = |reason: String| IntersticeError::InvalidAccessPolicy {
+            module_name: module_schema.name.clone(),
+            reason,
+        };
+        let query = module_schema
+            .queries
+            .iter()
+            .find(|query| &query.name == policy)
+            .ok_or_else(|| invalid(format!("query '{}' not found", policy)))?;
+        let takes_name = query.arguments.len() == 1
+            && matches!(query.arguments[0].field_type, IntersticeType::String);
+        if !takes_name || !matches!(query.return_type, IntersticeType::Bool) {
+            return Err(invalid(format!(
+                "query '{}' must have the signature `fn(QueryContext, String) -> bool`",
+                policy
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessCallKind {
+    Reducer,
+    Query,
+}
+
+impl Runtime {
+    /// Whether `caller_node_id` may call the reducer or query `call_name` of
+    /// `module_name` from another node. Unknown modules and calls are let through so
+    /// the regular dispatch reports them.
+    pub(crate) fn check_remote_access(
+        &self,
+        module_name: &str,
+        call_name: &str,
+        kind: AccessCallKind,
+        caller_node_id: NodeId,
+    ) -> Result<(), RemoteError> {
+        let policy = {
+            let modules = self.modules.lock();
+            let Some(module) = modules.get(module_name) else {
+                return Ok(());
+            };
+            let item_policy = match kind {
+                AccessCallKind::Reducer => module
+                    .schema
+                    .reducers
+                    .iter()
+                    .find(|reducer| reducer.name == call_name)
+                    .and_then(|reducer| reducer.access.clone()),
+                AccessCallKind::Query => module
+                    .schema
+                    .queries
+                    .iter()
+                    .find(|query| query.name == call_name)
+                    .and_then(|query| query.access.clone()),
+            };
+            item_policy.unwrap_or_else(|| module.schema.access.clone())
+        };
+        if policy.is_open() {
+            return Ok(());
+        }
+
+        let denied = |reason| RemoteError::AccessDenied {
+            module_name: module_name.to_string(),
+            call_name: call_name.to_string(),
+            reason,
+        };
+        let roles = self.access_roles.roles_of(&caller_node_id);
+        if !policy.allows(&caller_node_id.to_string(), roles) {
+            return Err(denied(AccessDeniedReason::NotAllowed));
+        }
+        let Some(policy) = policy.policy else {
+            return Ok(());
+        };
+
+        let args = IntersticeValue::Vec(vec![IntersticeValue::String(call_name.to_string())]);
+        match self.call_query(module_name, &policy, args, caller_node_id, "") {
+            Ok(IntersticeValue::Bool(true)) => Ok(()),
+            Ok(IntersticeValue::Bool(false)) => {
+                Err(denied(AccessDeniedReason::PolicyRejected { policy }))
+            }
+            Ok(other) => Err(denied(AccessDeniedReason::PolicyFailed {
+                policy,
+                error: format!("returned {:?} instead of a bool", other),
+            })),
+            Err(err) => Err(denied(AccessDeniedReason::PolicyFailed {
+                policy,
+                error: err.to_string(),
+            })),
+        }
+    }
+}
+
+/// Gives the network layer access to the runtime's policy checks. The network is
+/// created before the runtime, so the guard starts unbound and refuses every call
+/// until [`AccessGuard::bind`] is called.
+#[derive(Default)]
+pub struct AccessGuard {
+    runtime: OnceLock<Weak<Runtime>>,
+}
+
+impl AccessGuard {
+    pub fn bind(&self, runtime: &Arc<Runtime>) {
+        let _ = self.runtime.set(Arc::downgrade(runtime));
+    }
+
+    pub fn check(
+        &self,
+        module_name: &str,
+        call_name: &str,
+        kind: AccessCallKind,
+        caller_node_id: NodeId,
+    ) -> Result<(), RemoteError> {
+        match self.runtime.get().and_then(Weak::upgrade) {
+            Some(runtime) => {
+                runtime.check_remote_access(module_name, call_name, kind, caller_node_id)
+            }
+            None => Err(RemoteError::new(
+                ErrorCode::Internal,
+                "the node is not ready to take calls",
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AccessCallKind, AccessGuard, AccessRoles};
+    use std::collections::HashMap;
+    use uuid::Uuid;
+
+    #[test]
+    fn roles_are_indexed_by_node() {
+        let admin = Uuid::new_v4();
+        let other = Uuid::new_v4();
+        let roles = AccessRoles::new(HashMap::from([
+            ("admin".to_string(), vec![admin.to_string()]),
+            ("ops".to_string(), vec![admin.to_string()]),
+        ]));
+        let mut admin_roles = roles.roles_of(&admin).to_vec();
+        admin_roles.sort();
+        assert_eq!(admin_roles, vec!["admin".to_string(), "ops".to_string()]);
+        assert!(roles.roles_of(&other).is_empty());
+    }
+
+    #[test]
+    fn unbound_guards_refuse_calls() {
+        let guard = AccessGuard::default();
+        assert!(
+            guard
+                .check("module", "reducer", AccessCallKind::Reducer, Uuid::new_v4())
+                .is_err()
+        );
+    }
+}
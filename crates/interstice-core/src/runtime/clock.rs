@@ -0,0 +1,70 @@
+//! Wall clock of the runtime, read by the `time` host call and the schedules.
+//!
+//! Nodes follow the system clock. The test runtime uses a virtual clock instead,
+//! which only moves when it is advanced: schedules and ticks then run when the
+//! clock is advanced past them rather than on a timer. Replays set it to the time
+//! each recorded job ran at.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub(crate) enum Clock {
+    System,
+    Virtual { unix_ms: AtomicU64 },
+}
+
+impl Clock {
+    pub fn virtual_at(unix_ms: u64) -> Self {
+        Clock::Virtual {
+            unix_ms: AtomicU64::new(unix_ms),
+        }
+    }
+
+    pub fn is_virtual(&self) -> bool {
+        matches!(self, Clock::Virtual { .. })
+    }
+
+    pub fn now_unix_ms(&self) -> u64 {
+        match self {
+            Clock::System => system_unix_ms(),
+            Clock::Virtual { unix_ms } => unix_ms.load(Ordering::Acquire),
+        }
+    }
+
+    /// Set a virtual clock to `unix_ms`. The system clock cannot be set.
+    pub fn set(&self, unix_ms: u64) {
+        if let Clock::Virtual { unix_ms: now } = self {
+            now.store(unix_ms, Ordering::Release);
+        }
+    }
+
+    /// Move a virtual clock forward. The system clock cannot be advanced.
+    pub fn advance(&self, duration: Duration) {
+        if let Clock::Virtual { unix_ms } = self {
+            unix_ms.fetch_add(duration.as_millis() as u64, Ordering::AcqRel);
+        }
+    }
+}
+
+pub(crate) fn system_unix_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn virtual_clock_only_moves_when_advanced() {
+        let clock = Clock::virtual_at(1_000);
+        assert_eq!(clock.now_unix_ms(), 1_000);
+        clock.advance(Duration::from_millis(250));
+        assert_eq!(clock.now_unix_ms(), 1_250);
+        clock.set(500);
+        assert_eq!(clock.now_unix_ms(), 500);
+        assert!(!Clock::System.is_virtual());
+    }
+}
@@ -1,11 +1,11 @@
 use crate::{
     error::IntersticeError,
-    network::protocol::{NetworkPacket, TableEventInstance},
+    network::protocol::{NetworkPacket, ReplicaPosition, TableEventInstance},
     node::NodeId,
-    runtime::{Runtime, authority::AuthorityEntry},
+    runtime::{Runtime, authority::AuthorityEntry, reducer::JobSource},
 };
 use interstice_abi::{
-    Authority, FileEvent, InputEvent, IntersticeValue, ModuleEvent, NetworkEvent, Row,
+    Authority, FileEvent, InputEvent, IntersticeValue, ModuleEvent, NetworkEvent, Row, RowFilter,
     SubscriptionEventSchema,
 };
 
@@ -13,12 +13,19 @@ use interstice_abi::{
 pub enum EventInstance {
     TableInsertEvent {
         source_node_id: Option<NodeId>,
+        /// Filter of the subscription a remote node sent the event for, `None`
+        /// for local events and unfiltered subscriptions.
+        filter: Option<RowFilter>,
+        /// Sequence number of the event in its table, 0 for non-public tables.
+        sequence: u64,
         module_name: String,
         table_name: String,
         inserted_row: Row,
     },
     TableUpdateEvent {
         source_node_id: Option<NodeId>,
+        filter: Option<RowFilter>,
+        sequence: u64,
         module_name: String,
         table_name: String,
         old_row: Row,
@@ -26,6 +33,8 @@ pub enum EventInstance {
     },
     TableDeleteEvent {
         source_node_id: Option<NodeId>,
+        filter: Option<RowFilter>,
+        sequence: u64,
         module_name: String,
         table_name: String,
         deleted_row: Row,
@@ -55,22 +64,41 @@ pub enum EventInstance {
     RequestSubscription {
         requesting_node_id: NodeId,
         event: SubscriptionEventSchema,
+        filter: Option<RowFilter>,
     },
     RequestUnsubscription {
         requesting_node_id: NodeId,
         event: SubscriptionEventSchema,
+        filter: Option<RowFilter>,
     },
     RequestTableSync {
         requesting_node_id: NodeId,
         module_name: String,
         table_name: String,
+        filter: Option<RowFilter>,
+        since: Option<ReplicaPosition>,
     },
     RemoteTableSync {
         source_node_id: NodeId,
         module_name: String,
         table_name: String,
+        position: ReplicaPosition,
         rows: Vec<Row>,
     },
+    RemoteTableChanges {
+        source_node_id: NodeId,
+        module_name: String,
+        table_name: String,
+        since: u64,
+        position: ReplicaPosition,
+        events: Vec<EventInstance>,
+    },
+    RemoteTableEventHidden {
+        source_node_id: NodeId,
+        module_name: String,
+        table_name: String,
+        sequence: u64,
+    },
     RemoteReducerCall {
         requesting_node_id: NodeId,
         module_name: String,
@@ -88,6 +116,10 @@ pub enum EventInstance {
         request_id: String,
         result: IntersticeValue,
     },
+    RemoteReducerResult {
+        request_id: String,
+        status: interstice_abi::ReducerCallStatus,
+    },
     RemoteSchemaResponse {
         request_id: String,
         schema: interstice_abi::NodeSchema,
@@ -96,6 +128,9 @@ pub enum EventInstance {
         wasm_binary: Vec<u8>,
         source_node_id: NodeId,
     },
+    UpgradeModule {
+        wasm_binary: Vec<u8>,
+    },
     UnloadModule {
         module_name: String,
     },
@@ -117,6 +152,124 @@ pub enum EventInstance {
 }
 
 impl EventInstance {
+    /// Filter of the remote subscription a table event was received for. Only the
+    /// reducers subscribed with that same filter are triggered by it.
+    pub fn filter(&self) -> Option<&RowFilter> {
+        match self {
+            EventInstance::TableInsertEvent { filter, .. }
+            | EventInstance::TableUpdateEvent { filter, .. }
+            | EventInstance::TableDeleteEvent { filter, .. } => filter.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Node a table event was received from, `None` for local events.
+    pub fn source_node_id(&self) -> Option<NodeId> {
+        match self {
+            EventInstance::TableInsertEvent { source_node_id, .. }
+            | EventInstance::TableUpdateEvent { source_node_id, .. }
+            | EventInstance::TableDeleteEvent { source_node_id, .. } => *source_node_id,
+            _ => None,
+        }
+    }
+
+    /// Whether the event comes from outside the node's modules, so the jobs it
+    /// triggers are recorded as inputs of the node.
+    pub fn is_input(&self) -> bool {
+        match self {
+            EventInstance::TableInsertEvent { .. }
+            | EventInstance::TableUpdateEvent { .. }
+            | EventInstance::TableDeleteEvent { .. } => self.source_node_id().is_some(),
+            EventInstance::ReplicaTableSynced { .. }
+            | EventInstance::Input(_)
+            | EventInstance::AudioOutput
+            | EventInstance::AudioInput { .. }
+            | EventInstance::File(_)
+            | EventInstance::Module(_)
+            | EventInstance::Network(_)
+            | EventInstance::NodeConnect { .. }
+            | EventInstance::NodeDisconnect { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// Module, table and sequence number of a table event.
+    pub fn table_event(&self) -> Option<(&str, &str, u64)> {
+        match self {
+            EventInstance::TableInsertEvent {
+                module_name,
+                table_name,
+                sequence,
+                ..
+            }
+            | EventInstance::TableUpdateEvent {
+                module_name,
+                table_name,
+                sequence,
+                ..
+            }
+            | EventInstance::TableDeleteEvent {
+                module_name,
+                table_name,
+                sequence,
+                ..
+            } => Some((module_name, table_name, *sequence)),
+            _ => None,
+        }
+    }
+
+    /// The table event as sent to a node subscribed with `filter`, or the event
+    /// back when it is not a table event.
+    pub(crate) fn into_remote_table_event(
+        self,
+        filter: Option<RowFilter>,
+    ) -> Result<TableEventInstance, Box<EventInstance>> {
+        match self {
+            EventInstance::TableInsertEvent {
+                sequence,
+                module_name,
+                table_name,
+                inserted_row,
+                ..
+            } => Ok(TableEventInstance::TableInsertEvent {
+                filter,
+                sequence,
+                module_name,
+                table_name,
+                inserted_row,
+            }),
+            EventInstance::TableUpdateEvent {
+                sequence,
+                module_name,
+                table_name,
+                old_row,
+                new_row,
+                ..
+            } => Ok(TableEventInstance::TableUpdateEvent {
+                filter,
+                sequence,
+                module_name,
+                table_name,
+                old_row,
+                new_row,
+            }),
+            EventInstance::TableDeleteEvent {
+                sequence,
+                module_name,
+                table_name,
+                deleted_row,
+                ..
+            } => Ok(TableEventInstance::TableDeleteEvent {
+                filter,
+                sequence,
+                module_name,
+                table_name,
+                deleted_row,
+            }),
+            event => Err(Box::new(event)),
+        }
+    }
+
     pub fn has_schema(&self, event_schema: &SubscriptionEventSchema) -> bool {
         match &self {
             EventInstance::TableInsertEvent {
@@ -235,7 +388,10 @@ impl EventInstance {
                         | FileEvent::Deleted { path } => path,
                         FileEvent::Renamed { to, .. } => to,
                     });
-                    let schema_path = std::path::Path::new(path);
+                    // Event paths are virtual (see `FileSandbox::to_virtual`), so compare
+                    // against the normalised form of the declared path.
+                    let schema_path = crate::runtime::host_calls::file::normalize_virtual_path(path);
+                    let schema_path = std::path::Path::new(&schema_path);
 
                     if *recursive {
                         return event_path.starts_with(schema_path);
@@ -274,7 +430,11 @@ impl EventInstance {
 #[derive(Debug)]
 pub enum SubscriptionTarget {
     Local { module: String, reducer: String },
-    Remote(NodeId),
+    /// A node replicating the table, with the filter it subscribed with.
+    Remote {
+        node_id: NodeId,
+        filter: Option<RowFilter>,
+    },
 }
 
 impl Runtime {
@@ -396,7 +556,7 @@ impl Runtime {
         } else {
             for module in self.modules.lock().values() {
                 for sub in &module.schema.subscriptions {
-                    if event.has_schema(&sub.event) {
+                    if event.has_schema(&sub.event) && event.filter() == sub.filter.as_ref() {
                         out.push(SubscriptionTarget::Local {
                             module: module.schema.name.clone(),
                             reducer: sub.reducer_name.clone(),
@@ -406,9 +566,12 @@ impl Runtime {
             }
 
             for (node_id, subscriptions) in self.node_subscriptions.lock().iter() {
-                for sub in subscriptions {
-                    if event.has_schema(&sub) {
-                        out.push(SubscriptionTarget::Remote(*node_id));
+                for (sub, filter) in subscriptions {
+                    if event.has_schema(sub) {
+                        out.push(SubscriptionTarget::Remote {
+                            node_id: *node_id,
+                            filter: filter.clone(),
+                        });
                     }
                 }
             }
@@ -425,15 +588,24 @@ impl Runtime {
     ) -> Result<(), IntersticeError> {
         match target {
             SubscriptionTarget::Local { module, reducer } => {
+                let source = if event.is_input() {
+                    JobSource::Input
+                } else {
+                    JobSource::Internal
+                };
                 let args = match event {
                     EventInstance::TableInsertEvent {
                         source_node_id: _,
+                        filter: _,
+                        sequence: _,
                         module_name: _,
                         table_name: _,
                         inserted_row,
                     } => IntersticeValue::Vec(vec![inserted_row.into()]),
                     EventInstance::TableUpdateEvent {
                         source_node_id: _,
+                        filter: _,
+                        sequence: _,
                         module_name: _,
                         table_name: _,
                         old_row,
@@ -441,6 +613,8 @@ impl Runtime {
                     } => IntersticeValue::Vec(vec![old_row.into(), new_row.into()]),
                     EventInstance::TableDeleteEvent {
                         source_node_id: _,
+                        filter: _,
+                        sequence: _,
                         module_name: _,
                         table_name: _,
                         deleted_row,
@@ -472,7 +646,11 @@ impl Runtime {
                     input: args,
                     caller_node_id: self.network_handle.node_id,
                     caller_module_name: String::new(),
+                    request_id: None,
                     completion,
+                    outcome: None,
+                    source,
+                    rng_seed: None,
                 };
                 // Push to the ordered ingress (unbounded → never blocks this
                 // current-thread runtime). A single forwarder thread does the
@@ -481,54 +659,28 @@ impl Runtime {
                 // could reorder a connection's final `Received` past its `Closed`.
                 let _ = self.reducer_ingress.send(job);
             }
-            SubscriptionTarget::Remote(uuid) => {
-                let packet = match event {
-                    EventInstance::TableInsertEvent {
-                        source_node_id: _,
-                        module_name,
-                        table_name,
-                        inserted_row,
-                    } => NetworkPacket::TableEvent(TableEventInstance::TableInsertEvent {
-                        module_name,
-                        table_name,
-                        inserted_row,
-                    }),
-                    EventInstance::TableUpdateEvent {
-                        source_node_id: _,
-                        module_name,
-                        table_name,
-                        old_row,
-                        new_row,
-                    } => NetworkPacket::TableEvent(TableEventInstance::TableUpdateEvent {
-                        module_name,
-                        table_name,
-                        old_row,
-                        new_row,
-                    }),
-                    EventInstance::TableDeleteEvent {
-                        source_node_id: _,
-                        module_name,
-                        table_name,
-                        deleted_row,
-                    } => NetworkPacket::TableEvent(TableEventInstance::TableDeleteEvent {
-                        module_name,
-                        table_name,
-                        deleted_row,
-                    }),
-                    EventInstance::File(_)
-                    | EventInstance::Input(_)
-                    | EventInstance::Network(_)
-                    | EventInstance::Module(_)
-                    | EventInstance::NodeConnect { .. }
-                    | EventInstance::NodeDisconnect { .. } => {
-                        return Ok(());
-                    }
-                    event => {
-                        return Err(IntersticeError::Internal(format!(
-                            "Tried to send an event {:?} to remote node {}, which is not supported",
-                            event, uuid
-                        )));
-                    }
+            SubscriptionTarget::Remote {
+                node_id: uuid,
+                filter,
+            } => {
+                let packet = match event.into_remote_table_event(filter) {
+                    Ok(table_event) => NetworkPacket::TableEvent(table_event),
+                    Err(event) => match *event {
+                        EventInstance::File(_)
+                        | EventInstance::Input(_)
+                        | EventInstance::Network(_)
+                        | EventInstance::Module(_)
+                        | EventInstance::NodeConnect { .. }
+                        | EventInstance::NodeDisconnect { .. } => {
+                            return Ok(());
+                        }
+                        event => {
+                            return Err(IntersticeError::Internal(format!(
+                                "Tried to send an event {:?} to remote node {}, which is not supported",
+                                event, uuid
+                            )));
+                        }
+                    },
                 };
                 self.network_handle.send_packet(uuid, packet);
             }
@@ -108,6 +108,40 @@ pub(crate) fn start_audio_thread() -> mpsc::Sender<AudioCommand> {
     sender
 }
 
+/// Audio backend without devices: streams open, written samples are discarded
+/// as if played, and no input is ever recorded.
+pub(crate) fn start_silent_audio_thread() -> mpsc::Sender<AudioCommand> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut output_buffers: HashMap<u64, Arc<Mutex<VecDeque<f32>>>> = HashMap::new();
+        loop {
+            match receiver.recv_timeout(Duration::from_millis(10)) {
+                Ok(AudioCommand::OpenOutput {
+                    stream_id,
+                    respond_to,
+                    ..
+                }) => {
+                    let buffer = Arc::new(Mutex::new(VecDeque::new()));
+                    output_buffers.insert(stream_id, buffer.clone());
+                    let _ = respond_to.send(Ok(buffer));
+                }
+                Ok(AudioCommand::OpenInput { respond_to, .. }) => {
+                    let _ = respond_to.send(Ok(Arc::new(Mutex::new(VecDeque::new()))));
+                }
+                Ok(AudioCommand::Close { stream_id }) => {
+                    output_buffers.remove(&stream_id);
+                }
+                Err(mpsc::RecvTimeoutError::Timeout) => {}
+                Err(mpsc::RecvTimeoutError::Disconnected) => return,
+            }
+            for buffer in output_buffers.values() {
+                buffer.lock().clear();
+            }
+        }
+    });
+    sender
+}
+
 fn audio_thread(receiver: mpsc::Receiver<AudioCommand>) {
     let mut output_streams: HashMap<u64, cpal::Stream> = HashMap::new();
     let mut input_streams: HashMap<u64, cpal::Stream> = HashMap::new();
@@ -39,15 +39,19 @@ impl Runtime {
                 let response = match self
                     .handle_call_reducer(&caller_module_schema.name.clone(), call_reducer_request)
                 {
-                    Ok(()) => CallReducerResponse::Ok,
+                    Ok(call_id) => CallReducerResponse::Ok { call_id },
                     Err(err) => CallReducerResponse::Err(err.to_string()),
                 };
                 let result = self.send_data_to_module(response, memory, caller);
                 Ok(Some(result))
             }
-            HostCall::Schedule(schedule_request) => {
-                let response =
-                    self.handle_schedule(caller_module_schema.name.clone(), schedule_request);
+            HostCall::Schedule(schedule_call) => {
+                let response = self.handle_schedule(&caller_module_schema, schedule_call);
+                let result = self.send_data_to_module(response, memory, caller);
+                Ok(Some(result))
+            }
+            HostCall::TimeTravel(time_travel_call) => {
+                let response = self.handle_time_travel(&caller_module_schema, time_travel_call);
                 let result = self.send_data_to_module(response, memory, caller);
                 Ok(Some(result))
             }
@@ -76,6 +80,36 @@ impl Runtime {
                 let result = self.send_data_to_module(response, memory, caller);
                 Ok(Some(result))
             }
+            HostCall::TableScanOpen(request) => {
+                let response = self.handle_table_scan_open(request);
+                let result = self.send_data_to_module(response, memory, caller);
+                Ok(Some(result))
+            }
+            HostCall::TableScanNext(request) => {
+                let response = self.handle_table_scan_next(request);
+                let result = self.send_data_to_module(response, memory, caller);
+                Ok(Some(result))
+            }
+            HostCall::TableScanClose(request) => {
+                let response = self.handle_table_scan_close(request);
+                let result = self.send_data_to_module(response, memory, caller);
+                Ok(Some(result))
+            }
+            HostCall::TableQuery(request) => {
+                let response = self.handle_table_query(request);
+                let result = self.send_data_to_module(response, memory, caller);
+                Ok(Some(result))
+            }
+            HostCall::ReplicaStatus(request) => {
+                let response = self.handle_replica_status(&caller_module_schema.name, request);
+                let result = self.send_data_to_module(response, memory, caller);
+                Ok(Some(result))
+            }
+            HostCall::ReducerCallStatus(request) => {
+                let response = self.handle_reducer_call_status(&caller_module_schema.name, request);
+                let result = self.send_data_to_module(response, memory, caller);
+                Ok(Some(result))
+            }
             HostCall::Gpu(gpu_call) => {
                 let gpu_auth_module = {
                     let auth_modules = self.authority_modules.lock();
@@ -1,12 +1,24 @@
-use crate::{error::IntersticeError, runtime::Runtime, runtime::wasm::StoreState};
+mod sandbox;
+
+pub use sandbox::{
+    FileAccess, FileSandbox, SandboxError, normalize_virtual_path, validate_module_name,
+};
+
+use crate::{
+    error::IntersticeError,
+    logger::{LogLevel, LogSource},
+    runtime::Runtime,
+    runtime::wasm::StoreState,
+};
 use interstice_abi::{
     CopyResponse, CreateDirResponse, DirEntry, FileCall, FileMetadata, FileType, ListDirResponse,
     MetadataResponse, ReadFileResponse, RemoveDirResponse, RemoveFileResponse, RenameResponse,
-    WriteFileResponse,
+    SubscriptionEventSchema, WriteFileResponse,
 };
 use notify::RecommendedWatcher;
 use std::{
     io::Write,
+    path::Path,
     sync::{Arc, Mutex},
 };
 use wasmtime::{Caller, Memory};
@@ -14,6 +26,13 @@ pub struct Filestate {
     pub(crate) file_watchers: Arc<Mutex<Vec<RecommendedWatcher>>>,
 }
 
+/// Watcher of the path of one file subscription, which stops watching when
+/// dropped.
+pub(crate) struct FileWatch {
+    pub(crate) event: SubscriptionEventSchema,
+    pub(crate) _watcher: RecommendedWatcher,
+}
+
 impl Runtime {
     pub fn handle_file_call(
         &self,
@@ -21,36 +40,58 @@ impl Runtime {
         memory: &Memory,
         caller: &mut Caller<'_, StoreState>,
     ) -> Result<Option<i64>, IntersticeError> {
+        // Every path is virtual and resolved inside the caller's sandbox.
+        let module_name = caller.data().module_schema.name.clone();
+        let resolve = |path: &str, access: FileAccess| {
+            self.file_sandbox
+                .resolve(&module_name, path, access)
+                .map_err(|err| self.sandbox_error(&module_name, err))
+        };
+        let resolve_entry = |path: &str, access: FileAccess| {
+            self.file_sandbox
+                .resolve_entry(&module_name, path, access)
+                .map_err(|err| self.sandbox_error(&module_name, err))
+        };
         let packed = match call {
             FileCall::ReadFile(req) => {
-                let response = match std::fs::read(&req.path) {
+                let result = resolve(&req.path, FileAccess::Read)
+                    .and_then(|path| std::fs::read(path).map_err(|err| err.to_string()));
+                let response = match result {
                     Ok(bytes) => ReadFileResponse::Ok(bytes),
-                    Err(err) => ReadFileResponse::Err(err.to_string()),
+                    Err(err) => ReadFileResponse::Err(err),
                 };
                 self.send_data_to_module(response, memory, caller)
             }
             FileCall::WriteFile(req) => {
-                let result = if req.append {
-                    std::fs::OpenOptions::new()
-                        .create(true)
-                        .append(true)
-                        .open(&req.path)
-                        .and_then(|mut file| file.write_all(&req.data))
-                } else {
-                    std::fs::write(&req.path, &req.data)
-                };
+                let result = resolve(&req.path, FileAccess::Write).and_then(|path| {
+                    if req.append {
+                        std::fs::OpenOptions::new()
+                            .create(true)
+                            .append(true)
+                            .open(path)
+                            .and_then(|mut file| file.write_all(&req.data))
+                    } else {
+                        std::fs::write(path, &req.data)
+                    }
+                    .map_err(|err| err.to_string())
+                });
                 let response = match result {
                     Ok(()) => WriteFileResponse::Ok,
-                    Err(err) => WriteFileResponse::Err(err.to_string()),
+                    Err(err) => WriteFileResponse::Err(err),
                 };
                 self.send_data_to_module(response, memory, caller)
             }
             FileCall::ListDir(req) => {
-                let response = match std::fs::read_dir(&req.path) {
+                let result = resolve(&req.path, FileAccess::Read)
+                    .and_then(|path| std::fs::read_dir(path).map_err(|err| err.to_string()));
+                let response = match result {
                     Ok(read_dir) => {
                         let mut entries = Vec::new();
+                        // Entry paths are built from the requested virtual path, so the
+                        // host location of the sandbox never reaches the module.
+                        let dir = normalize_virtual_path(&req.path);
                         for entry in read_dir.flatten() {
-                            let path = entry.path();
+                            let name = entry.file_name().to_string_lossy().to_string();
                             let file_type = match entry.file_type() {
                                 Ok(ft) if ft.is_dir() => FileType::Directory,
                                 Ok(ft) if ft.is_file() => FileType::File,
@@ -59,19 +100,21 @@ impl Runtime {
                                 Err(_) => FileType::Other,
                             };
                             entries.push(DirEntry {
-                                name: entry.file_name().to_string_lossy().to_string(),
-                                path: path.to_string_lossy().to_string(),
+                                path: format!("{}/{}", dir.trim_end_matches('/'), name),
+                                name,
                                 file_type,
                             });
                         }
                         ListDirResponse::Ok(entries)
                     }
-                    Err(err) => ListDirResponse::Err(err.to_string()),
+                    Err(err) => ListDirResponse::Err(err),
                 };
                 self.send_data_to_module(response, memory, caller)
             }
             FileCall::Metadata(req) => {
-                let response = match std::fs::metadata(&req.path) {
+                let result = resolve(&req.path, FileAccess::Read)
+                    .and_then(|path| std::fs::metadata(path).map_err(|err| err.to_string()));
+                let response = match result {
                     Ok(meta) => {
                         let file_type = if meta.is_dir() {
                             FileType::Directory
@@ -102,57 +145,73 @@ impl Runtime {
                             modified,
                         })
                     }
-                    Err(err) => MetadataResponse::Err(err.to_string()),
+                    Err(err) => MetadataResponse::Err(err),
                 };
                 self.send_data_to_module(response, memory, caller)
             }
             FileCall::CreateDir(req) => {
-                let result = if req.recursive {
-                    std::fs::create_dir_all(&req.path)
-                } else {
-                    std::fs::create_dir(&req.path)
-                };
+                let result = resolve(&req.path, FileAccess::Write).and_then(|path| {
+                    if req.recursive {
+                        std::fs::create_dir_all(path)
+                    } else {
+                        std::fs::create_dir(path)
+                    }
+                    .map_err(|err| err.to_string())
+                });
                 let response = match result {
                     Ok(()) => CreateDirResponse::Ok,
-                    Err(err) => CreateDirResponse::Err(err.to_string()),
+                    Err(err) => CreateDirResponse::Err(err),
                 };
                 self.send_data_to_module(response, memory, caller)
             }
             FileCall::RemoveFile(req) => {
-                let response = match std::fs::remove_file(&req.path) {
+                let result = resolve_entry(&req.path, FileAccess::Write)
+                    .and_then(|path| std::fs::remove_file(path).map_err(|err| err.to_string()));
+                let response = match result {
                     Ok(()) => RemoveFileResponse::Ok,
-                    Err(err) => RemoveFileResponse::Err(err.to_string()),
+                    Err(err) => RemoveFileResponse::Err(err),
                 };
                 self.send_data_to_module(response, memory, caller)
             }
             FileCall::RemoveDir(req) => {
-                let result = if req.recursive {
-                    std::fs::remove_dir_all(&req.path)
-                } else {
-                    std::fs::remove_dir(&req.path)
-                };
+                let result = resolve_entry(&req.path, FileAccess::Write).and_then(|path| {
+                    if req.recursive {
+                        std::fs::remove_dir_all(path)
+                    } else {
+                        std::fs::remove_dir(path)
+                    }
+                    .map_err(|err| err.to_string())
+                });
                 let response = match result {
                     Ok(()) => RemoveDirResponse::Ok,
-                    Err(err) => RemoveDirResponse::Err(err.to_string()),
+                    Err(err) => RemoveDirResponse::Err(err),
                 };
                 self.send_data_to_module(response, memory, caller)
             }
             FileCall::Rename(req) => {
-                let response = match std::fs::rename(&req.from, &req.to) {
+                let result = resolve_entry(&req.from, FileAccess::Write).and_then(|from| {
+                    let to = resolve_entry(&req.to, FileAccess::Write)?;
+                    std::fs::rename(from, to).map_err(|err| err.to_string())
+                });
+                let response = match result {
                     Ok(()) => RenameResponse::Ok,
-                    Err(err) => RenameResponse::Err(err.to_string()),
+                    Err(err) => RenameResponse::Err(err),
                 };
                 self.send_data_to_module(response, memory, caller)
             }
             FileCall::Copy(req) => {
-                let result = if req.recursive {
-                    copy_dir_all(&req.from, &req.to)
-                } else {
-                    std::fs::copy(&req.from, &req.to).map(|_| ())
-                };
+                let result = resolve(&req.from, FileAccess::Read).and_then(|from| {
+                    let to = resolve(&req.to, FileAccess::Write)?;
+                    if req.recursive {
+                        copy_dir_all(&from, &to)
+                    } else {
+                        std::fs::copy(&from, &to).map(|_| ())
+                    }
+                    .map_err(|err| err.to_string())
+                });
                 let response = match result {
                     Ok(()) => CopyResponse::Ok,
-                    Err(err) => CopyResponse::Err(err.to_string()),
+                    Err(err) => CopyResponse::Err(err),
                 };
                 self.send_data_to_module(response, memory, caller)
             }
@@ -160,19 +219,32 @@ impl Runtime {
 
         Ok(Some(packed))
     }
+
+    /// Message of a sandbox failure for the module. Its host side is only logged
+    /// on the node.
+    fn sandbox_error(&self, module_name: &str, err: SandboxError) -> String {
+        if let Some(detail) = err.host_detail() {
+            self.logger.log(
+                &format!("File call of module '{}' failed: {}", module_name, detail),
+                LogSource::Runtime,
+                LogLevel::Warning,
+            );
+        }
+        err.to_string()
+    }
 }
 
-fn copy_dir_all(from: &str, to: &str) -> std::io::Result<()> {
+fn copy_dir_all(from: &Path, to: &Path) -> std::io::Result<()> {
     std::fs::create_dir_all(to)?;
     for entry in std::fs::read_dir(from)? {
         let entry = entry?;
         let file_type = entry.file_type()?;
-        let dest_path = std::path::Path::new(to).join(entry.file_name());
+        let dest_path = to.join(entry.file_name());
         if file_type.is_dir() {
-            copy_dir_all(
-                entry.path().to_string_lossy().as_ref(),
-                dest_path.to_string_lossy().as_ref(),
-            )?;
+            copy_dir_all(&entry.path(), &dest_path)?;
+        } else if file_type.is_symlink() {
+            // Symlinks may point outside the sandbox; never follow them while copying.
+            continue;
         } else {
             std::fs::copy(entry.path(), dest_path)?;
         }
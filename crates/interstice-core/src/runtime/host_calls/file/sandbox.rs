@@ -0,0 +1,410 @@
+//! Path sandbox for the File authority.
+//!
+//! Modules never handle host paths. Every path passed to a `FileCall` (or declared
+//! in a `file:` subscription) is a virtual path: `/` is the module's root directory
+//! and `/<mount>/...` addresses one of the extra mounts configured for the node in
+//! `files.toml`. Paths containing `..` and paths that escape their root through a
+//! symlink are rejected.
+
+use crate::error::IntersticeError;
+use serde::Deserialize;
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+
+/// Name of the optional sandbox configuration file in the node data directory.
+pub const FILE_SANDBOX_CONFIG: &str = "files.toml";
+
+/// Host directory exposed to modules as `/<name>`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct FileMount {
+    pub name: String,
+    pub path: PathBuf,
+    #[serde(default)]
+    pub read_only: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileAccess {
+    Read,
+    Write,
+}
+
+/// Failure to resolve a virtual path. It is returned to the module, so it only
+/// names the virtual path; the host side of the failure is kept for the node log.
+#[derive(Debug)]
+pub struct SandboxError {
+    message: String,
+    host_detail: Option<String>,
+}
+
+impl SandboxError {
+    fn new(message: String) -> Self {
+        Self {
+            message,
+            host_detail: None,
+        }
+    }
+
+    fn with_host_detail(message: String, host_detail: String) -> Self {
+        Self {
+            message,
+            host_detail: Some(host_detail),
+        }
+    }
+
+    pub fn host_detail(&self) -> Option<&str> {
+        self.host_detail.as_deref()
+    }
+}
+
+impl fmt::Display for SandboxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// Check that a module name is a single plain path segment, since it names the
+/// module's directory under the sandbox root.
+pub fn validate_module_name(module_name: &str) -> Result<(), IntersticeError> {
+    let mut components = Path::new(module_name).components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(segment)), None) if segment == module_name => Ok(()),
+        _ => Err(IntersticeError::InvalidModuleName(module_name.to_string())),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct FileSandboxConfig {
+    root: Option<PathBuf>,
+    #[serde(default = "default_per_module")]
+    per_module: bool,
+    #[serde(default)]
+    mounts: Vec<FileMount>,
+}
+
+fn default_per_module() -> bool {
+    true
+}
+
+#[derive(Debug, Clone)]
+pub struct FileSandbox {
+    /// Host directory backing `/`.
+    root: PathBuf,
+    /// Give each module its own `<root>/<module>` directory instead of sharing `root`.
+    per_module: bool,
+    mounts: Vec<FileMount>,
+}
+
+impl FileSandbox {
+    pub fn new(root: PathBuf, per_module: bool, mounts: Vec<FileMount>) -> Self {
+        Self {
+            root,
+            per_module,
+            mounts,
+        }
+    }
+
+    /// Load `files.toml` from the node data directory. Without it every module is
+    /// confined to `<data_path>/files/<module>` and no mounts are exposed.
+    pub fn load(data_path: &Path) -> Result<Self, IntersticeError> {
+        let default_root = data_path.join("files");
+        let config_path = data_path.join(FILE_SANDBOX_CONFIG);
+        if !config_path.exists() {
+            return Ok(Self::new(default_root, true, Vec::new()));
+        }
+
+        let contents = std::fs::read_to_string(&config_path).map_err(|err| {
+            IntersticeError::Internal(format!(
+                "Failed to read {}: {err}",
+                config_path.display()
+            ))
+        })?;
+        let config: FileSandboxConfig = toml::from_str(&contents).map_err(|err| {
+            IntersticeError::Internal(format!(
+                "Failed to parse {}: {err}",
+                config_path.display()
+            ))
+        })?;
+
+        let root = match config.root {
+            Some(root) if root.is_relative() => data_path.join(root),
+            Some(root) => root,
+            None => default_root,
+        };
+        for mount in &config.mounts {
+            if normalize(&mount.name)?.len() != 1 {
+                return Err(IntersticeError::Internal(format!(
+                    "Invalid file mount name '{}': expected a single path segment",
+                    mount.name
+                )));
+            }
+        }
+        Ok(Self::new(root, config.per_module, config.mounts))
+    }
+
+    pub fn module_root(&self, module_name: &str) -> PathBuf {
+        if self.per_module {
+            self.root.join(module_name)
+        } else {
+            self.root.clone()
+        }
+    }
+
+    /// Resolve a module-supplied virtual path to a host path inside the sandbox.
+    pub fn resolve(
+        &self,
+        module_name: &str,
+        virtual_path: &str,
+        access: FileAccess,
+    ) -> Result<PathBuf, SandboxError> {
+        let segments =
+            normalize(virtual_path).map_err(|err| SandboxError::new(err.to_string()))?;
+
+        let mount = segments
+            .first()
+            .and_then(|first| self.mounts.iter().find(|m| &m.name == first));
+        let (base, rest) = match mount {
+            Some(mount) => {
+                if access == FileAccess::Write && mount.read_only {
+                    return Err(SandboxError::new(format!(
+                        "Mount '/{}' is read-only",
+                        mount.name
+                    )));
+                }
+                (mount.path.clone(), &segments[1..])
+            }
+            None => {
+                let root = self.module_root(module_name);
+                std::fs::create_dir_all(&root).map_err(|err| {
+                    SandboxError::with_host_detail(
+                        format!("Couldn't create the file root for '{}'", virtual_path),
+                        format!("Couldn't create file root {}: {err}", root.display()),
+                    )
+                })?;
+                (root, &segments[..])
+            }
+        };
+
+        let path = rest.iter().fold(base.clone(), |path, segment| path.join(segment));
+        ensure_within(&base, &path, virtual_path)?;
+        Ok(path)
+    }
+
+    /// Like [`resolve`](Self::resolve), but refuses the module root and mount
+    /// points themselves so they cannot be removed or renamed away.
+    pub fn resolve_entry(
+        &self,
+        module_name: &str,
+        virtual_path: &str,
+        access: FileAccess,
+    ) -> Result<PathBuf, SandboxError> {
+        let segments =
+            normalize(virtual_path).map_err(|err| SandboxError::new(err.to_string()))?;
+        let is_mount_point =
+            segments.len() == 1 && self.mounts.iter().any(|m| m.name == segments[0]);
+        if segments.is_empty() || is_mount_point {
+            return Err(SandboxError::new(format!(
+                "'{}' is a sandbox root",
+                virtual_path
+            )));
+        }
+        self.resolve(module_name, virtual_path, access)
+    }
+
+    /// Map a host path back to the virtual path a module would use for it, or
+    /// `None` when it lies outside the module's sandbox. Paths are also compared
+    /// in canonical form, since file watchers may report them that way.
+    pub fn to_virtual(&self, module_name: &str, host_path: &Path) -> Option<String> {
+        let canonical_path = canonicalize_existing(host_path);
+        let relative = |base: &Path| {
+            host_path.strip_prefix(base).map(Path::to_path_buf).ok().or_else(|| {
+                let base = base.canonicalize().ok()?;
+                canonical_path.as_ref()?.strip_prefix(base).map(Path::to_path_buf).ok()
+            })
+        };
+        for mount in &self.mounts {
+            if let Some(rest) = relative(&mount.path) {
+                return Some(join_virtual(Some(&mount.name), &rest));
+            }
+        }
+        relative(&self.module_root(module_name)).map(|rest| join_virtual(None, &rest))
+    }
+}
+
+/// Canonical `/a/b` form of a virtual path, used to compare subscription paths
+/// against event paths.
+pub fn normalize_virtual_path(virtual_path: &str) -> String {
+    match normalize(virtual_path) {
+        Ok(segments) => format!("/{}", segments.join("/")),
+        Err(_) => virtual_path.to_string(),
+    }
+}
+
+/// Split a virtual path into its segments, rejecting `..` and platform prefixes.
+fn normalize(virtual_path: &str) -> Result<Vec<String>, IntersticeError> {
+    let mut segments = Vec::new();
+    for component in Path::new(virtual_path).components() {
+        match component {
+            Component::RootDir | Component::CurDir => {}
+            Component::Normal(segment) => segments.push(segment.to_string_lossy().to_string()),
+            Component::ParentDir | Component::Prefix(_) => {
+                return Err(IntersticeError::Internal(format!(
+                    "Path '{}' escapes the file sandbox",
+                    virtual_path
+                )));
+            }
+        }
+    }
+    Ok(segments)
+}
+
+fn join_virtual(mount: Option<&str>, rest: &Path) -> String {
+    let mut segments: Vec<String> = mount.map(|m| m.to_string()).into_iter().collect();
+    segments.extend(
+        rest.components()
+            .filter_map(|c| match c {
+                Component::Normal(s) => Some(s.to_string_lossy().to_string()),
+                _ => None,
+            }),
+    );
+    format!("/{}", segments.join("/"))
+}
+
+/// Canonical form of `path`, resolving its deepest existing ancestor so paths of
+/// removed files can still be mapped.
+fn canonicalize_existing(path: &Path) -> Option<PathBuf> {
+    let mut existing = path;
+    while existing.symlink_metadata().is_err() {
+        existing = existing.parent()?;
+    }
+    let rest = path.strip_prefix(existing).ok()?;
+    Some(existing.canonicalize().ok()?.join(rest))
+}
+
+/// Check that the deepest existing ancestor of `path` (symlinks followed) still
+/// lies inside `base`, so a symlink cannot redirect the access outside the sandbox.
+/// Errors name `virtual_path`, the path the module asked for.
+fn ensure_within(base: &Path, path: &Path, virtual_path: &str) -> Result<(), SandboxError> {
+    let unresolved = |detail: String| {
+        SandboxError::with_host_detail(format!("Couldn't resolve '{}'", virtual_path), detail)
+    };
+    let base = base
+        .canonicalize()
+        .map_err(|err| unresolved(format!("Couldn't resolve {}: {err}", base.display())))?;
+
+    let mut existing = path;
+    while existing.symlink_metadata().is_err() {
+        existing = match existing.parent() {
+            Some(parent) => parent,
+            None => return Err(unresolved(format!("Couldn't resolve {}", path.display()))),
+        };
+    }
+    // A dangling symlink fails to canonicalize and is rejected with it.
+    let resolved = existing
+        .canonicalize()
+        .map_err(|err| unresolved(format!("Couldn't resolve {}: {err}", existing.display())))?;
+    if !resolved.starts_with(&base) {
+        return Err(SandboxError::with_host_detail(
+            format!("Path '{}' escapes the file sandbox", virtual_path),
+            format!("Path {} escapes the file sandbox", path.display()),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("interstice-sandbox-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn resolves_inside_module_root_and_rejects_parent_dirs() {
+        let dir = temp_dir();
+        let sandbox = FileSandbox::new(dir.join("files"), true, Vec::new());
+
+        let path = sandbox.resolve("m", "/notes/a.txt", FileAccess::Write).unwrap();
+        assert_eq!(path, dir.join("files").join("m").join("notes").join("a.txt"));
+        assert_eq!(sandbox.to_virtual("m", &path).unwrap(), "/notes/a.txt");
+
+        assert!(sandbox.resolve("m", "../other/a.txt", FileAccess::Read).is_err());
+        assert!(sandbox.resolve("m", "/notes/../../a.txt", FileAccess::Read).is_err());
+        assert!(sandbox.resolve_entry("m", "/", FileAccess::Write).is_err());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn maps_canonical_host_paths_and_rejects_outside_ones() {
+        let dir = temp_dir();
+        std::fs::create_dir_all(dir.join("real")).unwrap();
+        std::os::unix::fs::symlink(dir.join("real"), dir.join("files")).unwrap();
+        let sandbox = FileSandbox::new(dir.join("files"), true, Vec::new());
+        let path = sandbox.resolve("m", "/notes/a.txt", FileAccess::Write).unwrap();
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+
+        let canonical = dir.canonicalize().unwrap().join("real/m/notes/a.txt");
+        assert_eq!(sandbox.to_virtual("m", &canonical).unwrap(), "/notes/a.txt");
+        assert_eq!(sandbox.to_virtual("m", &dir.join("real/other/a.txt")), None);
+        assert_eq!(sandbox.to_virtual("m", &dir.join("outside.txt")), None);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_symlink_escape() {
+        let dir = temp_dir();
+        let outside = dir.join("outside");
+        std::fs::create_dir_all(&outside).unwrap();
+        let sandbox = FileSandbox::new(dir.join("files"), true, Vec::new());
+        let root = sandbox.module_root("m");
+        std::fs::create_dir_all(&root).unwrap();
+        std::os::unix::fs::symlink(&outside, root.join("link")).unwrap();
+
+        let err = sandbox
+            .resolve("m", "link/secret.txt", FileAccess::Write)
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Path 'link/secret.txt' escapes the file sandbox");
+        assert!(err.host_detail().unwrap().contains(&*dir.to_string_lossy()));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn mounts_are_addressed_by_name_and_honour_read_only() {
+        let dir = temp_dir();
+        let assets = dir.join("assets");
+        std::fs::create_dir_all(&assets).unwrap();
+        let sandbox = FileSandbox::new(
+            dir.join("files"),
+            true,
+            vec![FileMount {
+                name: "assets".into(),
+                path: assets.clone(),
+                read_only: true,
+            }],
+        );
+
+        let path = sandbox.resolve("m", "/assets/logo.png", FileAccess::Read).unwrap();
+        assert_eq!(path, assets.join("logo.png"));
+        assert_eq!(sandbox.to_virtual("m", &path).unwrap(), "/assets/logo.png");
+        assert!(sandbox.resolve("m", "/assets/logo.png", FileAccess::Write).is_err());
+        assert!(sandbox.resolve_entry("m", "/assets", FileAccess::Read).is_err());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn module_names_are_single_path_segments() {
+        assert!(validate_module_name("hello").is_ok());
+        for name in ["", ".", "..", "a/b", "/abs", "../up"] {
+            assert!(validate_module_name(name).is_err(), "{name:?} was accepted");
+        }
+    }
+}
@@ -1,6 +1,6 @@
 pub mod audio;
 mod dispatch;
-mod file;
+pub mod file;
 pub mod gpu;
 pub mod input;
 mod log;
@@ -10,6 +10,8 @@ mod node;
 mod query;
 mod random;
 mod reducer;
+mod replica;
 mod schedule;
 mod table;
 mod time;
+mod time_travel;
@@ -91,6 +91,66 @@ impl Runtime {
                     ModuleCallResponse::Ok
                 }
             },
+            ModuleCall::Upgrade {
+                node_selection,
+                wasm_binary,
+            } => match node_selection {
+                NodeSelection::Current => {
+                    self.tokio_handle.spawn(async move {
+                        let result = match Module::from_bytes(runtime.clone(), &wasm_binary).await {
+                            Ok(module) => Runtime::upgrade_module(runtime.clone(), module).await,
+                            Err(err) => Err(err),
+                        };
+                        if let Err(err) = result {
+                            runtime.logger.log(
+                                &format!("Local upgrade failed: {}", err),
+                                crate::logger::LogSource::Runtime,
+                                crate::logger::LogLevel::Error,
+                            );
+                        }
+                    });
+                    ModuleCallResponse::Ok
+                }
+                NodeSelection::Other(node_name) => {
+                    let node_dependency = caller_module_schema
+                        .node_dependencies
+                        .iter()
+                        .find(|n| n.name == node_name);
+                    let node_address = match node_dependency {
+                        Some(dep) => dep.address.clone(),
+                        None => {
+                            return Ok(Some(
+                                self.send_data_to_module(
+                                    ModuleCallResponse::Err(format!(
+                                        "Couldn't find node {node_name} in node dependencies"
+                                    )),
+                                    _memory,
+                                    _caller,
+                                ),
+                            ));
+                        }
+                    };
+                    let node_id = match self.network_handle.get_node_id_from_adress(&node_address) {
+                        Ok(node_id) => node_id,
+                        Err(err) => {
+                            return Ok(Some(
+                                self.send_data_to_module(
+                                    ModuleCallResponse::Err(err.to_string()),
+                                    _memory,
+                                    _caller,
+                                ),
+                            ));
+                        }
+                    };
+                    self.network_handle.send_packet(
+                        node_id,
+                        crate::network::protocol::NetworkPacket::ModuleEvent(
+                            crate::network::protocol::ModuleEventInstance::Upgrade { wasm_binary },
+                        ),
+                    );
+                    ModuleCallResponse::Ok
+                }
+            },
             ModuleCall::Unload {
                 node_selection,
                 module_name,
@@ -3,14 +3,17 @@ use crate::{
     network::protocol::NetworkPacket,
     runtime::{Runtime, reducer::{CallFrameKind, CALL_STACK}},
 };
-use interstice_abi::{CallReducerRequest, ModuleSelection, NodeSelection};
+use interstice_abi::{
+    CallReducerRequest, ModuleSelection, NodeSelection, ReducerCallStatusRequest,
+    ReducerCallStatusResponse,
+};
 
 impl Runtime {
     pub(crate) fn handle_call_reducer(
         &self,
         caller_module_name: &String,
         call_reducer_request: CallReducerRequest,
-    ) -> Result<(), IntersticeError> {
+    ) -> Result<Option<String>, IntersticeError> {
         let in_query = CALL_STACK.with(|s| {
             s.borrow().last().map_or(false, |f| f.kind == CallFrameKind::Query)
         });
@@ -31,8 +34,9 @@ impl Runtime {
                     call_reducer_request.input,
                     self.network_handle.node_id,
                     caller_module_name,
+                    None,
                 )?;
-                Ok(())
+                Ok(None)
             }
             NodeSelection::Other(node_name) => {
                 let modules = self.modules.lock();
@@ -56,16 +60,32 @@ impl Runtime {
                 let node_id = network
                     .get_node_id_from_adress(&node_dependency.address)
                     .map_err(|_| IntersticeError::UnknownPeer)?;
+                let call_id = self.track_reducer_call(caller_module_name, node_id);
                 network.send_packet(
                     node_id,
                     NetworkPacket::ReducerCall {
+                        request_id: Some(call_id.clone()),
                         module_name: module_name.clone(),
                         reducer_name: call_reducer_request.reducer_name.clone(),
                         input: call_reducer_request.input.clone(),
                     },
                 );
-                Ok(())
+                Ok(Some(call_id))
             }
         }
     }
+
+    pub(crate) fn handle_reducer_call_status(
+        &self,
+        caller_module_name: &str,
+        request: ReducerCallStatusRequest,
+    ) -> ReducerCallStatusResponse {
+        match self.reducer_call_status(caller_module_name, &request.call_id) {
+            Some(status) => ReducerCallStatusResponse::Ok(status),
+            None => ReducerCallStatusResponse::Err(format!(
+                "Unknown reducer call '{}' for module '{}'",
+                request.call_id, caller_module_name
+            )),
+        }
+    }
 }
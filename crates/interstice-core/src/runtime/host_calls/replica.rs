@@ -0,0 +1,27 @@
+use crate::runtime::Runtime;
+use interstice_abi::{ReplicaStatusRequest, ReplicaStatusResponse};
+
+impl Runtime {
+    pub(crate) fn handle_replica_status(
+        &self,
+        module_name: &str,
+        request: ReplicaStatusRequest,
+    ) -> ReplicaStatusResponse {
+        self.replica_bindings
+            .lock()
+            .iter()
+            .find(|binding| {
+                binding.owner_module_name == module_name
+                    && binding.source_node_name == request.node_name
+                    && binding.source_module_name == request.module_name
+                    && binding.source_table_name == request.table_name
+            })
+            .map(|binding| ReplicaStatusResponse::Ok(binding.cursor.status()))
+            .unwrap_or_else(|| {
+                ReplicaStatusResponse::Err(format!(
+                    "Module '{}' does not replicate table '{}.{}.{}'",
+                    module_name, request.node_name, request.module_name, request.table_name
+                ))
+            })
+    }
+}
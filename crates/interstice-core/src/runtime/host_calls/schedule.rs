@@ -1,52 +1,95 @@
 use crate::runtime::Runtime;
-use crate::runtime::reducer::CALL_STACK;
-use interstice_abi::{ScheduleRequest, ScheduleResponse};
+use crate::runtime::reducer::{CALL_STACK, CallFrameKind};
+use crate::runtime::transaction::Transaction;
+use interstice_abi::{ModuleSchema, ScheduleCall, ScheduleInfo, ScheduleResponse};
+use std::collections::BTreeMap;
 
 impl Runtime {
+    /// Schedule changes are queued with the reducer's writes, so a reducer that
+    /// fails leaves the schedules as they were.
     pub(crate) fn handle_schedule(
         &self,
-        module_name: String,
-        request: ScheduleRequest,
+        caller_module_schema: &ModuleSchema,
+        call: ScheduleCall,
     ) -> ScheduleResponse {
-        let reducer_exists = CALL_STACK.with(|s| {
-            let stack = s.borrow();
-            let frame = match stack.last() {
-                Some(f) => f,
-                None => return Err("No active call frame".to_string()),
-            };
-            Ok(frame
-                .module_arc
-                .schema
-                .reducers
-                .iter()
-                .find(|reducer| reducer.name == request.reducer_name)
-                .map(|reducer| reducer.arguments.is_empty()))
+        let module_name = &caller_module_schema.name;
+        let in_reducer = CALL_STACK.with(|s| {
+            s.borrow()
+                .last()
+                .is_some_and(|frame| frame.kind == CallFrameKind::Reducer)
         });
-        let reducer_exists = match reducer_exists {
-            Err(msg) => return ScheduleResponse::Err(msg),
-            Ok(v) => v,
-        };
-
-        match reducer_exists {
-            None => {
-                return ScheduleResponse::Err(format!(
-                    "Reducer '{}.{}' not found",
-                    module_name, request.reducer_name
-                ));
+        let transaction = match call {
+            ScheduleCall::Create(_) | ScheduleCall::Cancel { .. } if !in_reducer => {
+                return ScheduleResponse::Err("Schedules can only be changed from a reducer".into());
             }
-            Some(false) => {
-                return ScheduleResponse::Err(format!(
-                    "Reducer '{}.{}' must have no arguments to be scheduled",
-                    module_name, request.reducer_name
-                ));
+            ScheduleCall::Create(request) => {
+                match self.prepare_schedule(caller_module_schema, request) {
+                    Ok(schedule) => Transaction::Schedule {
+                        module_name: module_name.clone(),
+                        schedule,
+                    },
+                    Err(err) => return ScheduleResponse::Err(err.to_string()),
+                }
             }
-            Some(true) => {}
-        }
-
-        let reducer_name = request.reducer_name;
-        let wake_at = std::time::Instant::now() + std::time::Duration::from_millis(request.delay_ms);
-        let _ = self.timer_tx.send((wake_at, module_name, reducer_name));
+            ScheduleCall::Cancel { schedule_id } => {
+                if !self.frame_schedules(module_name).contains_key(&schedule_id) {
+                    return ScheduleResponse::Cancelled(false);
+                }
+                Transaction::CancelSchedule {
+                    module_name: module_name.clone(),
+                    schedule_id,
+                }
+            }
+            ScheduleCall::List => {
+                return ScheduleResponse::List(
+                    self.frame_schedules(module_name).into_values().collect(),
+                );
+            }
+        };
+        let response = match &transaction {
+            Transaction::Schedule { schedule, .. } => ScheduleResponse::Scheduled(schedule.id),
+            _ => ScheduleResponse::Cancelled(true),
+        };
+        CALL_STACK.with(|s| {
+            s.borrow_mut()
+                .last_mut()
+                .unwrap()
+                .transactions
+                .push(transaction);
+        });
+        response
+    }
 
-        ScheduleResponse::Ok
+    /// The module's schedules, with the changes the running reducer queued so far.
+    fn frame_schedules(&self, module_name: &str) -> BTreeMap<u64, ScheduleInfo> {
+        let mut schedules: BTreeMap<u64, ScheduleInfo> = self
+            .list_schedules(module_name)
+            .into_iter()
+            .map(|schedule| (schedule.id, schedule))
+            .collect();
+        CALL_STACK.with(|s| {
+            let stack = s.borrow();
+            let Some(frame) = stack.last() else {
+                return;
+            };
+            for transaction in &frame.transactions {
+                match transaction {
+                    Transaction::Schedule {
+                        module_name: name,
+                        schedule,
+                    } if name == module_name => {
+                        schedules.insert(schedule.id, schedule.clone());
+                    }
+                    Transaction::CancelSchedule {
+                        module_name: name,
+                        schedule_id,
+                    } if name == module_name => {
+                        schedules.remove(schedule_id);
+                    }
+                    _ => {}
+                }
+            }
+        });
+        schedules
     }
 }
@@ -1,12 +1,21 @@
 use crate::{
     runtime::transaction::Transaction,
-    runtime::{Runtime, module::Module, reducer::{CallFrameKind, CALL_STACK}},
+    runtime::{
+        Runtime,
+        module::Module,
+        reducer::{CallFrameKind, CALL_STACK},
+        table::{Table, TableCursor, TableUsage, apply_scan_options},
+    },
 };
 use interstice_abi::{
-    ClearTableRequest, ClearTableResponse, DeleteRowRequest, DeleteRowResponse, InsertRowRequest,
-    InsertRowResponse, ModuleSchema, ModuleSelection, NodeSelection, ReducerTableRef,
+    ClearTableRequest, ClearTableResponse, DeleteRowRequest, DeleteRowResponse, IndexKey,
+    InsertRowRequest, InsertRowResponse, ModuleSchema, ModuleSelection, NodeSelection,
+    ReducerTableRef, Row,
     TableGetByPrimaryKeyRequest, TableGetByPrimaryKeyResponse, TableIndexScanRequest,
-    TableIndexScanResponse, TableScanRequest, TableScanResponse, UpdateRowRequest, UpdateRowResponse,
+    TableIndexScanResponse, TableScanCloseRequest, TableScanCloseResponse, TableScanNextRequest,
+    TableQueryRequest, TableQueryResponse, TableScanNextResponse, TableScanOpenRequest,
+    TableScanOpenResponse, TableScanRequest, TableScanResponse, TableSchema, UpdateRowRequest,
+    UpdateRowResponse,
 };
 use std::sync::Arc;
 
@@ -152,6 +161,8 @@ impl Runtime {
         };
 
         let has_auto_inc;
+        // Encoded size of the row, set when the table has a quota to account against.
+        let mut quota_usage = None;
         {
             let mut tables = module_arc.tables.lock();
             let table = match tables.get_mut(&insert_row_request.table_name) {
@@ -187,7 +198,7 @@ impl Runtime {
             if let Err(err) = CALL_STACK.with(|s| {
                 let mut stack = s.borrow_mut();
                 let frame = stack.last_mut().unwrap();
-                let snapshot = frame.auto_inc_snapshots.get(&insert_row_request.table_name).unwrap();
+                let snapshot = frame.auto_inc_snapshots.get_mut(&insert_row_request.table_name).unwrap();
                 table.apply_auto_inc_from_snapshot(&mut row, snapshot)
             }) {
                 return InsertRowResponse::Err(err.to_string());
@@ -196,6 +207,22 @@ impl Runtime {
             if let Err(err) = table.validate_insert(&row) {
                 return InsertRowResponse::Err(err.to_string());
             }
+
+            let limits = &table.schema.limits;
+            if limits.max_rows.is_some() || limits.max_bytes.is_some() {
+                let row_bytes = table.quota_row_bytes(&row);
+                let pending = CALL_STACK.with(|s| {
+                    s.borrow()
+                        .last()
+                        .and_then(|f| f.pending_table_usage.get(&insert_row_request.table_name))
+                        .map(|pending| pending.usage)
+                        .unwrap_or_default()
+                });
+                if let Err(err) = table.validate_quota(pending, row_bytes) {
+                    return InsertRowResponse::Err(err.to_string());
+                }
+                quota_usage = Some(row_bytes);
+            }
         }
 
         CALL_STACK.with(|s| {
@@ -205,6 +232,14 @@ impl Runtime {
                 return InsertRowResponse::Err("Insert not allowed in query context".into());
             }
 
+            if let Some(row_bytes) = quota_usage {
+                reducer_frame
+                    .pending_table_usage
+                    .entry(insert_row_request.table_name.clone())
+                    .or_default()
+                    .insert(row_bytes);
+            }
+
             if has_auto_inc {
                 let resp_row = row.clone();
                 reducer_frame.transactions.push(Transaction::Insert {
@@ -237,6 +272,9 @@ impl Runtime {
         ) {
             return UpdateRowResponse::Err(err);
         }
+        // Key, stored and new size of the row, set when the table has a byte quota to
+        // account against.
+        let mut quota_update = None;
         {
             let module_arc = match self.current_frame_module_arc() {
                 Ok(m) => m,
@@ -260,6 +298,31 @@ impl Runtime {
             if let Err(err) = table.validate_update(&update_row_request.row) {
                 return UpdateRowResponse::Err(err.to_string());
             }
+
+            if table.schema.limits.max_bytes.is_some() {
+                let key = match IndexKey::try_from(&update_row_request.row.primary_key) {
+                    Ok(key) => key,
+                    Err(err) => return UpdateRowResponse::Err(err),
+                };
+                let stored_bytes = table.quota_stored_bytes(&key);
+                let row_bytes = table.quota_row_bytes(&update_row_request.row);
+                // Earlier writes of the call may already have replaced the stored row.
+                let (pending, counted_bytes) = CALL_STACK.with(|s| {
+                    let stack = s.borrow();
+                    match stack
+                        .last()
+                        .and_then(|f| f.pending_table_usage.get(&update_row_request.table_name))
+                    {
+                        Some(pending) => (pending.usage, pending.row_bytes(&key, stored_bytes)),
+                        None => (TableUsage::default(), Some(stored_bytes)),
+                    }
+                });
+                let added_bytes = row_bytes as i64 - counted_bytes.unwrap_or(0) as i64;
+                if let Err(err) = table.validate_byte_quota(pending, added_bytes) {
+                    return UpdateRowResponse::Err(err.to_string());
+                }
+                quota_update = Some((key, stored_bytes, row_bytes));
+            }
         }
 
         CALL_STACK.with(|s| {
@@ -268,6 +331,13 @@ impl Runtime {
             if reducer_frame.kind == CallFrameKind::Query {
                 return UpdateRowResponse::Err("Update not allowed in query context".into());
             }
+            if let Some((key, stored_bytes, row_bytes)) = quota_update {
+                reducer_frame
+                    .pending_table_usage
+                    .entry(update_row_request.table_name.clone())
+                    .or_default()
+                    .update(key, stored_bytes, row_bytes);
+            }
             reducer_frame.transactions.push(Transaction::Update {
                 module_name,
                 table_name: update_row_request.table_name,
@@ -290,6 +360,8 @@ impl Runtime {
         ) {
             return DeleteRowResponse::Err(err);
         }
+        // Stored size of the row, set when the table has a quota to account against.
+        let mut quota_bytes = None;
         {
             let module_arc = match self.current_frame_module_arc() {
                 Ok(m) => m,
@@ -303,6 +375,10 @@ impl Runtime {
             if let Err(err) = table.validate_delete(&delete_row_request.primary_key) {
                 return DeleteRowResponse::Err(err.to_string());
             }
+            let limits = &table.schema.limits;
+            if limits.max_rows.is_some() || limits.max_bytes.is_some() {
+                quota_bytes = Some(table.quota_stored_bytes(&delete_row_request.primary_key));
+            }
         }
 
         CALL_STACK.with(|s| {
@@ -311,6 +387,13 @@ impl Runtime {
             if reducer_frame.kind == CallFrameKind::Query {
                 return DeleteRowResponse::Err("Delete not allowed in query context".into());
             }
+            if let Some(stored_bytes) = quota_bytes {
+                reducer_frame
+                    .pending_table_usage
+                    .entry(delete_row_request.table_name.clone())
+                    .or_default()
+                    .delete(delete_row_request.primary_key.clone(), stored_bytes);
+            }
             reducer_frame.transactions.push(Transaction::Delete {
                 module_name,
                 table_name: delete_row_request.table_name,
@@ -395,14 +478,18 @@ impl Runtime {
             }
         };
 
-        let tables = module_arc.tables.lock();
-        let table = match tables.get(&table_scan_request.table_name) {
-            Some(table) => table,
-            None => return TableScanResponse::Err("Table not found".into()),
+        let (view_schema, rows) = {
+            let tables = module_arc.tables.lock();
+            let table = match tables.get(&table_scan_request.table_name) {
+                Some(table) => table,
+                None => return TableScanResponse::Err("Table not found".into()),
+            };
+            (view_schema(table), table.scan().to_vec())
         };
 
-        TableScanResponse::Ok {
-            rows: table.scan().to_vec(),
+        match self.visible_rows(&module_name, view_schema, rows) {
+            Ok(rows) => TableScanResponse::Ok { rows },
+            Err(err) => TableScanResponse::Err(err),
         }
     }
 
@@ -436,16 +523,19 @@ impl Runtime {
             }
         };
 
-        let row = {
+        let (view_schema, row) = {
             let tables = module_arc.tables.lock();
             let table = match tables.get(&request.table_name) {
                 Some(table) => table,
                 None => return TableGetByPrimaryKeyResponse::Err("Table not found".into()),
             };
-            table.get_by_primary_key(&request.primary_key).cloned()
+            (view_schema(table), table.get_by_primary_key(&request.primary_key).cloned())
         };
 
-        TableGetByPrimaryKeyResponse::Ok(row)
+        match self.visible_rows(&module_name, view_schema, row.into_iter().collect()) {
+            Ok(rows) => TableGetByPrimaryKeyResponse::Ok(rows.into_iter().next()),
+            Err(err) => TableGetByPrimaryKeyResponse::Err(err),
+        }
     }
 
     pub(crate) fn handle_table_index_scan(
@@ -486,16 +576,285 @@ impl Runtime {
             };
 
             match table.get_by_index(&request.field_name, &request.query) {
-                Ok(rows) => Ok(rows.into_iter().cloned().collect::<Vec<_>>()),
+                Ok(rows) => {
+                    let view_schema = view_schema(table);
+                    // Without a view every matched row is visible, so only the
+                    // rows in the window are copied.
+                    let rows = if view_schema.is_none() {
+                        apply_scan_options(rows, &request.options)
+                    } else {
+                        rows
+                    };
+                    Ok((view_schema, rows.into_iter().cloned().collect::<Vec<_>>()))
+                }
                 Err(err) => Err(err.to_string()),
             }
         };
 
+        let rows = rows.and_then(|(view_schema, rows)| match view_schema {
+            Some(view_schema) => self
+                .visible_rows(&module_name, Some(view_schema), rows)
+                .map(|rows| apply_scan_options(rows, &request.options)),
+            None => Ok(rows),
+        });
         match rows {
             Ok(rows) => TableIndexScanResponse::Ok { rows },
             Err(err) => TableIndexScanResponse::Err(err),
         }
     }
+
+    pub(crate) fn handle_table_query(&self, request: TableQueryRequest) -> TableQueryResponse {
+        let module_name = match self.selected_module_name(&request.module_selection) {
+            Ok(module_name) => module_name,
+            Err(err) => return TableQueryResponse::Err(err),
+        };
+        if let Err(err) = self.ensure_current_frame_table_access(
+            &request.module_selection,
+            &request.table_name,
+            TableAccessOp::Read,
+        ) {
+            return TableQueryResponse::Err(err);
+        }
+
+        let module_arc = match &request.module_selection {
+            ModuleSelection::Current => match self.current_frame_module_arc() {
+                Ok(m) => m,
+                Err(e) => return TableQueryResponse::Err(e),
+            },
+            ModuleSelection::Other(_) => {
+                let modules = self.modules.lock();
+                match modules.get(&module_name) {
+                    Some(m) => m.clone(),
+                    None => return TableQueryResponse::Err("Module not found".into()),
+                }
+            }
+        };
+
+        let rows = {
+            let tables = module_arc.tables.lock();
+            let table = match tables.get(&request.table_name) {
+                Some(table) => table,
+                None => return TableQueryResponse::Err("Table not found".into()),
+            };
+
+            match table.query(request.filter.as_ref(), request.order_by.as_deref()) {
+                Ok(rows) => {
+                    let view_schema = view_schema(table);
+                    let rows = if view_schema.is_none() {
+                        apply_scan_options(rows, &request.options)
+                    } else {
+                        rows
+                    };
+                    Ok((view_schema, rows.into_iter().cloned().collect::<Vec<_>>()))
+                }
+                Err(err) => Err(err.to_string()),
+            }
+        };
+
+        let rows = rows.and_then(|(view_schema, rows)| match view_schema {
+            Some(view_schema) => self
+                .visible_rows(&module_name, Some(view_schema), rows)
+                .map(|rows| apply_scan_options(rows, &request.options)),
+            None => Ok(rows),
+        });
+        match rows {
+            Ok(rows) => TableQueryResponse::Ok { rows },
+            Err(err) => TableQueryResponse::Err(err),
+        }
+    }
+
+    pub(crate) fn handle_table_scan_open(
+        &self,
+        request: TableScanOpenRequest,
+    ) -> TableScanOpenResponse {
+        let module_name = match self.selected_module_name(&request.module_selection) {
+            Ok(module_name) => module_name,
+            Err(err) => return TableScanOpenResponse::Err(err),
+        };
+        if let Err(err) = self.ensure_current_frame_table_access(
+            &request.module_selection,
+            &request.table_name,
+            TableAccessOp::Read,
+        ) {
+            return TableScanOpenResponse::Err(err);
+        }
+
+        let module_arc = match &request.module_selection {
+            ModuleSelection::Current => match self.current_frame_module_arc() {
+                Ok(m) => m,
+                Err(e) => return TableScanOpenResponse::Err(e),
+            },
+            ModuleSelection::Other(_) => {
+                let modules = self.modules.lock();
+                match modules.get(&module_name) {
+                    Some(m) => m.clone(),
+                    None => return TableScanOpenResponse::Err("Module not found".into()),
+                }
+            }
+        };
+
+        let view_schema = {
+            let tables = module_arc.tables.lock();
+            let table = match tables.get(&request.table_name) {
+                Some(table) => table,
+                None => return TableScanOpenResponse::Err("Table not found".into()),
+            };
+            // Check the index and query before handing out the cursor.
+            let index = request
+                .index
+                .as_ref()
+                .map(|index| (index.field_name.as_str(), &index.query));
+            if let Err(err) = table.scan_batch(index, request.options.reverse, None, 0) {
+                return TableScanOpenResponse::Err(err.to_string());
+            }
+            view_schema(table)
+        };
+
+        let cursor = TableCursor {
+            module_name,
+            module_arc,
+            table_name: request.table_name,
+            view_schema,
+            index: request.index,
+            reverse: request.options.reverse,
+            after: None,
+            exhausted: false,
+            skip: request.options.offset,
+            remaining: request.options.limit,
+            batch_size: request.batch_size.max(1) as usize,
+        };
+
+        CALL_STACK.with(|s| {
+            let mut stack = s.borrow_mut();
+            let Some(frame) = stack.last_mut() else {
+                return TableScanOpenResponse::Err("No active call frame".into());
+            };
+            let cursor_id = frame.next_cursor_id;
+            frame.next_cursor_id += 1;
+            frame.table_cursors.insert(cursor_id, cursor);
+            TableScanOpenResponse::Ok { cursor_id }
+        })
+    }
+
+    pub(crate) fn handle_table_scan_next(
+        &self,
+        request: TableScanNextRequest,
+    ) -> TableScanNextResponse {
+        // The cursor is taken out of the frame while reading: filtering a view
+        // runs a query, which pushes its own frame.
+        let cursor = CALL_STACK.with(|s| {
+            s.borrow_mut()
+                .last_mut()
+                .and_then(|frame| frame.table_cursors.remove(&request.cursor_id))
+        });
+        let Some(mut cursor) = cursor else {
+            return TableScanNextResponse::Err(format!(
+                "Table scan cursor {} not found",
+                request.cursor_id
+            ));
+        };
+
+        // Cursors don't outlive their module: an upgrade may change the table.
+        let current = self
+            .modules
+            .lock()
+            .get(&cursor.module_name)
+            .is_some_and(|module| Arc::ptr_eq(module, &cursor.module_arc));
+        if !current {
+            return TableScanNextResponse::Err(format!(
+                "Table scan cursor {} was closed: module '{}' was upgraded or unloaded",
+                request.cursor_id, cursor.module_name
+            ));
+        }
+
+        let mut batch = Vec::new();
+        while batch.len() < cursor.batch_size && !cursor.is_done() {
+            let wanted = cursor.batch_size - batch.len();
+            let rows = {
+                let tables = cursor.module_arc.tables.lock();
+                let Some(table) = tables.get(&cursor.table_name) else {
+                    return TableScanNextResponse::Err("Table not found".into());
+                };
+                let index = cursor
+                    .index
+                    .as_ref()
+                    .map(|index| (index.field_name.as_str(), &index.query));
+                match table.scan_batch(index, cursor.reverse, cursor.after.as_ref(), wanted) {
+                    Ok(rows) => rows,
+                    Err(err) => return TableScanNextResponse::Err(err.to_string()),
+                }
+            };
+            cursor.exhausted = rows.len() < wanted;
+            if let Some((position, _)) = rows.last() {
+                cursor.after = Some(position.clone());
+            }
+            let rows = rows.into_iter().map(|(_, row)| row).collect();
+            match self.visible_rows(&cursor.module_name, cursor.view_schema.clone(), rows) {
+                Ok(rows) => batch.extend(cursor.take_window(rows)),
+                Err(err) => return TableScanNextResponse::Err(err),
+            }
+        }
+
+        let done = cursor.is_done();
+        if !done {
+            CALL_STACK.with(|s| {
+                if let Some(frame) = s.borrow_mut().last_mut() {
+                    frame.table_cursors.insert(request.cursor_id, cursor);
+                }
+            });
+        }
+        TableScanNextResponse::Ok { rows: batch, done }
+    }
+
+    pub(crate) fn handle_table_scan_close(
+        &self,
+        request: TableScanCloseRequest,
+    ) -> TableScanCloseResponse {
+        let closed = CALL_STACK.with(|s| {
+            s.borrow_mut()
+                .last_mut()
+                .and_then(|frame| frame.table_cursors.remove(&request.cursor_id))
+                .is_some()
+        });
+        if closed {
+            TableScanCloseResponse::Ok
+        } else {
+            TableScanCloseResponse::Err(format!(
+                "Table scan cursor {} not found",
+                request.cursor_id
+            ))
+        }
+    }
+
+    /// Apply the table view of another module's table for the module of the
+    /// active call frame.
+    fn visible_rows(
+        &self,
+        module_name: &str,
+        view_schema: Option<TableSchema>,
+        rows: Vec<Row>,
+    ) -> Result<Vec<Row>, String> {
+        let Some(table_schema) = view_schema else {
+            return Ok(rows);
+        };
+        let reader = CALL_STACK
+            .with(|s| s.borrow().last().map(|f| f.module.clone()))
+            .ok_or_else(|| "No active call frame".to_string())?;
+        self.filter_visible_rows(
+            module_name,
+            &table_schema,
+            rows,
+            self.network_handle.node_id,
+            &reader,
+        )
+        .map_err(|err| err.to_string())
+    }
+}
+
+/// Schema of a table that has a view, cloned so rows can be filtered after the
+/// table lock is released (the view query may read the owner's tables).
+fn view_schema(table: &Table) -> Option<TableSchema> {
+    table.schema.view.is_some().then(|| table.schema.clone())
 }
 
 fn op_label(op: &TableAccessOp) -> &'static str {
@@ -506,3 +865,103 @@ fn op_label(op: &TableAccessOp) -> &'static str {
         TableAccessOp::Delete => "delete",
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::TestRuntime;
+    use crate::testing::fixture::{FixtureModule, Step, row};
+    use interstice_abi::{IntersticeType, IntersticeValue, PersistenceKind, encode};
+
+    #[tokio::test]
+    async fn deleted_rows_make_room_for_inserts_of_the_same_call() {
+        let runtime = TestRuntime::starting_at(1_000).unwrap();
+        let note = |id| row(id, vec![IntersticeValue::String("note".into())]);
+        let insert = |table: &str, id| Step::Insert {
+            table: table.into(),
+            row: note(id),
+        };
+        let delete = |table: &str, id| Step::Delete {
+            table: table.into(),
+            id,
+        };
+        let fields = [("text", IntersticeType::String)];
+        let row_bytes = encode(&note(1)).unwrap().len() as u64;
+        let module = FixtureModule::new("quota")
+            .table("slots", PersistenceKind::Ephemeral, &fields)
+            .with_table(|table| table.limits.max_rows = Some(1))
+            .table("notes", PersistenceKind::Ephemeral, &fields)
+            .with_table(|table| table.limits.max_bytes = Some(row_bytes))
+            .reducer("write", vec![insert("slots", 1), insert("notes", 1)])
+            .reducer(
+                "replace",
+                vec![
+                    delete("slots", 1),
+                    insert("slots", 2),
+                    delete("notes", 1),
+                    insert("notes", 2),
+                ],
+            )
+            .reducer("add", vec![insert("slots", 3), insert("notes", 3)]);
+        runtime.load_module(&module.wasm()).await.unwrap();
+        runtime.call_reducer("quota", "write", Vec::new()).await.unwrap();
+
+        runtime.call_reducer("quota", "replace", Vec::new()).await.unwrap();
+        assert_eq!(runtime.rows("quota", "slots").unwrap(), vec![note(2)]);
+        assert_eq!(runtime.rows("quota", "notes").unwrap(), vec![note(2)]);
+
+        runtime.call_reducer("quota", "add", Vec::new()).await.unwrap();
+        assert_eq!(runtime.rows("quota", "slots").unwrap(), vec![note(2)]);
+        assert_eq!(runtime.rows("quota", "notes").unwrap(), vec![note(2)]);
+    }
+
+    #[tokio::test]
+    async fn updates_that_outgrow_the_byte_quota_are_rejected() {
+        let runtime = TestRuntime::starting_at(1_000).unwrap();
+        let note = |text: &str| row(1, vec![IntersticeValue::String(text.into())]);
+        let module = FixtureModule::new("quota")
+            .table(
+                "notes",
+                PersistenceKind::Ephemeral,
+                &[("text", IntersticeType::String)],
+            )
+            .with_table(|table| table.limits.max_bytes = Some(64))
+            .reducer(
+                "write",
+                vec![Step::Insert {
+                    table: "notes".into(),
+                    row: note("short"),
+                }],
+            )
+            .reducer(
+                "shrink",
+                vec![Step::Update {
+                    table: "notes".into(),
+                    row: note("tiny"),
+                }],
+            )
+            .reducer(
+                "grow",
+                vec![Step::Update {
+                    table: "notes".into(),
+                    row: note(&"long".repeat(32)),
+                }],
+            );
+        runtime.load_module(&module.wasm()).await.unwrap();
+        runtime
+            .call_reducer("quota", "write", Vec::new())
+            .await
+            .unwrap();
+
+        runtime
+            .call_reducer("quota", "grow", Vec::new())
+            .await
+            .unwrap();
+        assert_eq!(runtime.rows("quota", "notes").unwrap(), vec![note("short")]);
+
+        runtime
+            .call_reducer("quota", "shrink", Vec::new())
+            .await
+            .unwrap();
+        assert_eq!(runtime.rows("quota", "notes").unwrap(), vec![note("tiny")]);
+    }
+}
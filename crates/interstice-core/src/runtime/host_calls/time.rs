@@ -1,14 +1,12 @@
-use crate::runtime::Runtime;
+use crate::runtime::{Runtime, reducer::JOB_UNIX_MS};
 use interstice_abi::{TimeRequest, TimeResponse};
-use std::time::{SystemTime, UNIX_EPOCH};
 
 impl Runtime {
     pub(crate) fn handle_time(&self, _request: TimeRequest) -> TimeResponse {
-        match SystemTime::now().duration_since(UNIX_EPOCH) {
-            Ok(duration) => TimeResponse::Ok {
-                unix_ms: duration.as_millis() as u64,
-            },
-            Err(err) => TimeResponse::Err(err.to_string()),
+        TimeResponse::Ok {
+            unix_ms: JOB_UNIX_MS
+                .with(|job_unix_ms| job_unix_ms.get())
+                .unwrap_or_else(|| self.clock.now_unix_ms()),
         }
     }
 }
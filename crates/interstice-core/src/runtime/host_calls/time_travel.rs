@@ -0,0 +1,85 @@
+use crate::runtime::Runtime;
+use crate::runtime::reducer::{CALL_STACK, CallFrameKind};
+use crate::runtime::time_travel::{MAIN_BRANCH, TimeTravelOperation};
+use crate::runtime::transaction::Transaction;
+use interstice_abi::{ModuleSchema, TimeTravelCall, TimeTravelResponse};
+
+impl Runtime {
+    pub(crate) fn handle_time_travel(
+        &self,
+        caller_module_schema: &ModuleSchema,
+        call: TimeTravelCall,
+    ) -> TimeTravelResponse {
+        let module_name = &caller_module_schema.name;
+        let operation = match call {
+            TimeTravelCall::Checkpoint { name } => TimeTravelOperation::Checkpoint { name },
+            TimeTravelCall::Rewind { checkpoint } => TimeTravelOperation::Rewind { checkpoint },
+            TimeTravelCall::DeleteCheckpoint { name } => {
+                TimeTravelOperation::DeleteCheckpoint { name }
+            }
+            TimeTravelCall::Fork { branch } => TimeTravelOperation::Fork { branch },
+            TimeTravelCall::Discard => TimeTravelOperation::Discard,
+            TimeTravelCall::Promote => TimeTravelOperation::Promote,
+            TimeTravelCall::ListCheckpoints => {
+                return TimeTravelResponse::Checkpoints(self.list_checkpoints(module_name));
+            }
+            TimeTravelCall::Branches => {
+                return TimeTravelResponse::Branches(self.list_branches(module_name));
+            }
+            TimeTravelCall::TableSequence { table_name } => {
+                return TimeTravelResponse::Sequence(
+                    self.persistence.table_sequence(module_name, &table_name),
+                );
+            }
+            TimeTravelCall::TableAsOf {
+                table_name,
+                sequence,
+            } => {
+                let Some(schema) = caller_module_schema
+                    .tables
+                    .iter()
+                    .find(|table| table.name == table_name)
+                else {
+                    return TimeTravelResponse::Err(format!(
+                        "Module '{}' has no table '{}'",
+                        module_name, table_name
+                    ));
+                };
+                return match self.persistence.rows_as_of(module_name, schema, sequence) {
+                    Ok((rows, branches)) => TimeTravelResponse::Rows {
+                        rows,
+                        branches: std::iter::once(MAIN_BRANCH.to_string())
+                            .chain(branches)
+                            .collect(),
+                    },
+                    Err(err) => TimeTravelResponse::Err(err.to_string()),
+                };
+            }
+        };
+
+        let in_reducer = CALL_STACK.with(|s| {
+            s.borrow()
+                .last()
+                .is_some_and(|frame| frame.kind == CallFrameKind::Reducer)
+        });
+        if !in_reducer {
+            return TimeTravelResponse::Err(
+                "Checkpoints and branches can only be changed from a reducer".into(),
+            );
+        }
+        if let Err(err) = self.check_time_travel(module_name, &operation) {
+            return TimeTravelResponse::Err(err);
+        }
+        CALL_STACK.with(|s| {
+            s.borrow_mut()
+                .last_mut()
+                .unwrap()
+                .transactions
+                .push(Transaction::TimeTravel {
+                    module_name: module_name.clone(),
+                    operation,
+                });
+        });
+        TimeTravelResponse::Ok
+    }
+}
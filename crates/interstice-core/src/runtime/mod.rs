@@ -1,13 +1,23 @@
+pub mod access;
 mod authority;
+pub(crate) mod clock;
 mod deterministic_random;
 pub mod event;
 pub mod host_calls;
 pub mod module;
 mod query;
 pub mod reducer;
+mod reconnect;
+mod reducer_calls;
+pub(crate) mod recorder;
+mod replication;
 mod scheduler;
+mod schedules;
 pub mod table;
+mod tick;
+pub(crate) mod time_travel;
 pub mod transaction;
+mod view;
 mod wasm;
 
 pub(crate) use authority::AuthorityEntry;
@@ -19,25 +29,28 @@ use crate::{
     node::NodeId,
     persistence::TableStore,
     runtime::{
-        event::EventInstance,
+        access::{AccessCallKind, AccessRoles},
+        event::{EventInstance, SubscriptionTarget},
         host_calls::{
             audio::AudioState,
+            file::{FileSandbox, FileWatch},
             gpu::{GpuCallRequest, GpuState},
             network::NetworkState,
         },
         module::Module,
-        reducer::{ACTIVE_COMPLETION, CompletionToken, ReducerJob},
+        recorder::RecordingEntry,
+        reducer::{ACTIVE_COMPLETION, CompletionToken, JOB_UNIX_MS, JobSource, ReducerJob},
         wasm::{StoreState, linker::define_host_calls},
     },
 };
 use interstice_abi::{
-    Authority, IntersticeValue, ModuleEvent, NodeSchema, SubscriptionEventSchema, TableVisibility,
+    Authority, IntersticeValue, ModuleEvent, NodeSchema, ReducerCallStatus, RowFilter,
+    SubscriptionEventSchema, TableVisibility,
 };
-use notify::RecommendedWatcher;
 use std::sync::atomic::{AtomicI32, AtomicU64};
 use parking_lot::Mutex;
 use std::{
-    collections::{HashMap, HashSet},
+    collections::HashMap,
     path::PathBuf,
     sync::{Arc, mpsc},
 };
@@ -82,25 +95,51 @@ pub struct Runtime {
     /// reach the scheduler *after* its `Closed`, truncating the buffered body.
     pub(crate) reducer_ingress: CbSender<ReducerJob>,
     reducer_ingress_rx: Mutex<Option<CbReceiver<ReducerJob>>>,
+    /// Drain/resume requests for the scheduler thread (hot module upgrades).
+    pub(crate) scheduler_control: CbSender<scheduler::SchedulerControl>,
+    scheduler_control_rx: Mutex<Option<CbReceiver<scheduler::SchedulerControl>>>,
     pub(crate) gpu_call_sender: mpsc::Sender<GpuCallRequest>,
     gpu_call_receiver: Mutex<Option<mpsc::Receiver<GpuCallRequest>>>,
     pub(crate) modules_path: Option<PathBuf>,
-    node_subscriptions: Arc<Mutex<HashMap<NodeId, Vec<SubscriptionEventSchema>>>>,
+    node_subscriptions: Arc<Mutex<HashMap<NodeId, Vec<NodeSubscription>>>>,
     pub(crate) node_names_by_id: Arc<Mutex<HashMap<NodeId, String>>>,
     pub(crate) replica_bindings: Arc<Mutex<Vec<ReplicaBinding>>>,
-    pub(crate) emitted_replica_sync_events: Arc<Mutex<HashSet<String>>>,
-    pub(crate) file_watchers: Arc<Mutex<Vec<RecommendedWatcher>>>,
+    /// File watches of each module.
+    pub(crate) file_watchers: Arc<Mutex<HashMap<String, Vec<FileWatch>>>>,
+    /// Confines File authority paths (host calls and watches) to the module roots and mounts.
+    pub(crate) file_sandbox: FileSandbox,
+    /// Roles of remote nodes, checked against module access policies.
+    pub(crate) access_roles: AccessRoles,
     pub(crate) network_state: Arc<NetworkState>,
     pub(crate) call_sequence: AtomicU64,
+    /// Reducer commits since the node started, reported to remote callers.
+    pub(crate) commit_sequence: AtomicU64,
     pub(crate) active_subscription_count: AtomicI32,
-    pub(crate) timer_tx: tokio::sync::mpsc::UnboundedSender<(std::time::Instant, String, String)>,
-    timer_rx: Mutex<Option<tokio::sync::mpsc::UnboundedReceiver<(std::time::Instant, String, String)>>>,
+    /// Clock of the `time` host call and the schedules.
+    pub(crate) clock: clock::Clock,
+    /// Pending `ctx.schedule*` calls of every loaded module.
+    pub(crate) schedules: schedules::Schedules,
+    /// Running `tick(...)` subscriptions of every loaded module.
+    pub(crate) ticks: tick::Ticks,
+    /// Table checkpoints and branches of every loaded module.
+    pub(crate) time_travel: time_travel::TimeTravel,
+    /// Changelogs of the public tables, for the nodes replicating them.
+    pub(crate) replication: replication::Replication,
+    /// Node dependencies being dialed again after a disconnection.
+    pub(crate) reconnects: reconnect::Reconnects,
+    /// Outcomes of the reducer calls modules made on other nodes.
+    pub(crate) reducer_calls: reducer_calls::ReducerCalls,
+    /// Recording of the node's inputs, when one is running.
+    pub(crate) recorder: recorder::Recorder,
     /// Sorted [`TableAccess`] slices per module → reducer for the scheduler hot path.
     /// Nested maps allow `get` with `&str` without allocating lookup keys.
     reducer_access_cache:
         parking_lot::Mutex<HashMap<String, HashMap<String, Arc<[scheduler::TableAccess]>>>>,
 }
 
+/// Table event another node subscribed to, with the filter it subscribed with.
+type NodeSubscription = (SubscriptionEventSchema, Option<RowFilter>);
+
 #[derive(Debug, Clone)]
 pub(crate) struct ReplicaBinding {
     pub owner_module_name: String,
@@ -109,13 +148,25 @@ pub(crate) struct ReplicaBinding {
     pub source_module_name: String,
     pub source_table_name: String,
     pub local_table_name: String,
+    pub cursor: replication::ReplicaCursor,
+}
+
+impl ReplicaBinding {
+    fn replicates(&self, node_id: NodeId, module_name: &str, table_name: &str) -> bool {
+        self.source_node_id == node_id
+            && self.source_module_name == module_name
+            && self.source_table_name == table_name
+    }
 }
 
 impl Runtime {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         node_id: NodeId,
         modules_path: Option<PathBuf>,
         table_store: TableStore,
+        file_sandbox: FileSandbox,
+        access_roles: AccessRoles,
         event_sender: UnboundedSender<(EventInstance, Option<CompletionToken>)>,
         network_handle: NetworkHandle,
         audio_state: Arc<Mutex<AudioState>>,
@@ -128,16 +179,20 @@ impl Runtime {
         // reducer_sender/receiver are pre-created by the Node so the same sender can be
         // shared directly with the Network layer, bypassing the unbounded event channel.
         let (gpu_call_sender, gpu_call_receiver) = mpsc::channel::<GpuCallRequest>();
-        let (timer_tx, timer_rx) = tokio::sync::mpsc::unbounded_channel::<(std::time::Instant, String, String)>();
         let tokio_handle = tokio::runtime::Handle::current();
         let mut config = Config::new();
         config.cranelift_opt_level(wasmtime::OptLevel::Speed);
+        // Fuel metering backs the per-call CPU budgets declared by modules
+        // (`interstice_module!(fuel: N)` / `#[reducer(fuel = N)]`).
+        config.consume_fuel(true);
         let engine = Arc::new(Engine::new(&config).unwrap());
         let mut linker = Linker::new(&engine);
         define_host_calls(&mut linker).map_err(|err| {
             IntersticeError::Internal(format!("Couldn't add host calls to the linker: {}", err))
         })?;
         let (reducer_ingress, reducer_ingress_rx) = crossbeam_channel::unbounded::<ReducerJob>();
+        let (scheduler_control, scheduler_control_rx) =
+            crossbeam_channel::unbounded::<scheduler::SchedulerControl>();
         Ok(Self {
             node_id,
             gpu,
@@ -156,6 +211,8 @@ impl Runtime {
             reducer_receiver,
             reducer_ingress,
             reducer_ingress_rx: Mutex::new(Some(reducer_ingress_rx)),
+            scheduler_control,
+            scheduler_control_rx: Mutex::new(Some(scheduler_control_rx)),
             gpu_call_sender,
             gpu_call_receiver: Mutex::new(Some(gpu_call_receiver)),
             modules_path,
@@ -163,14 +220,22 @@ impl Runtime {
             node_subscriptions: Arc::new(Mutex::new(HashMap::new())),
             node_names_by_id: Arc::new(Mutex::new(HashMap::new())),
             replica_bindings: Arc::new(Mutex::new(Vec::new())),
-            emitted_replica_sync_events: Arc::new(Mutex::new(HashSet::new())),
             logger,
-            file_watchers: Arc::new(Mutex::new(Vec::new())),
+            file_watchers: Arc::new(Mutex::new(HashMap::new())),
+            file_sandbox,
+            access_roles,
             network_state: Arc::new(NetworkState::new()),
             call_sequence: AtomicU64::new(0),
+            commit_sequence: AtomicU64::new(0),
             active_subscription_count: AtomicI32::new(0),
-            timer_tx,
-            timer_rx: Mutex::new(Some(timer_rx)),
+            clock: clock::Clock::System,
+            schedules: schedules::Schedules::new(),
+            ticks: tick::Ticks::new(),
+            time_travel: time_travel::TimeTravel::new(),
+            replication: replication::Replication::new(),
+            reconnects: reconnect::Reconnects::new(),
+            reducer_calls: reducer_calls::ReducerCalls::new(),
+            recorder: recorder::Recorder::new(),
             tokio_handle,
             reducer_access_cache: parking_lot::Mutex::new(HashMap::new()),
         })
@@ -194,11 +259,38 @@ impl Runtime {
 
         {
             let incoming_rx = runtime.reducer_receiver.clone();
+            let control_rx = runtime
+                .scheduler_control_rx
+                .lock()
+                .take()
+                .expect("scheduler_control_rx already taken");
             let rt = runtime.clone();
             std::thread::Builder::new()
                 .name("reducer-scheduler".to_string())
                 .spawn(move || {
                     let mut scheduler = crate::runtime::scheduler::ReducerScheduler::<ReducerJob>::new();
+                    // Module of every running job, so a drain knows when a module is idle.
+                    let mut running_modules: HashMap<u64, String> = HashMap::new();
+                    // Jobs held back while their module is drained for an upgrade.
+                    let mut paused: HashMap<String, Vec<ReducerJob>> = HashMap::new();
+                    let mut pending_drains: Vec<(String, oneshot::Sender<()>)> = Vec::new();
+
+                    let dispatch = |scheduler: &mut scheduler::ReducerScheduler<ReducerJob>,
+                                        running_modules: &mut HashMap<u64, String>,
+                                        job: ReducerJob|
+                     -> bool {
+                        let accesses =
+                            reducer_accesses_cached(&rt, &job.module_name, &job.reducer_name);
+                        if let Some(runnable) = scheduler.enqueue(job, accesses) {
+                            running_modules
+                                .insert(runnable.id, runnable.payload.module_name.clone());
+                            if ready_tx.send((runnable.id, runnable.payload)).is_err() {
+                                return false;
+                            }
+                        }
+                        true
+                    };
+
                     loop {
                         crossbeam_channel::select! {
                             recv(incoming_rx) -> msg => {
@@ -206,12 +298,12 @@ impl Runtime {
                                     Ok(job) => job,
                                     Err(_) => break,
                                 };
-                                let accesses =
-                                    reducer_accesses_cached(&rt, &job.module_name, &job.reducer_name);
-                                if let Some(runnable) = scheduler.enqueue(job, accesses) {
-                                    if ready_tx.send((runnable.id, runnable.payload)).is_err() {
-                                        return;
-                                    }
+                                if let Some(held) = paused.get_mut(&job.module_name) {
+                                    held.push(job);
+                                    continue;
+                                }
+                                if !dispatch(&mut scheduler, &mut running_modules, job) {
+                                    return;
                                 }
                             }
                             recv(done_rx) -> msg => {
@@ -219,11 +311,44 @@ impl Runtime {
                                     Ok(id) => id,
                                     Err(_) => break,
                                 };
+                                running_modules.remove(&done_id);
                                 for runnable in scheduler.complete(done_id) {
+                                    running_modules
+                                        .insert(runnable.id, runnable.payload.module_name.clone());
                                     if ready_tx.send((runnable.id, runnable.payload)).is_err() {
                                         return;
                                     }
                                 }
+                                let (idle, busy): (Vec<_>, Vec<_>) =
+                                    pending_drains.drain(..).partition(|(module_name, _)| {
+                                        !running_modules.values().any(|m| m == module_name)
+                                    });
+                                pending_drains = busy;
+                                for (_, drained) in idle {
+                                    let _ = drained.send(());
+                                }
+                            }
+                            recv(control_rx) -> msg => {
+                                match msg {
+                                    Ok(scheduler::SchedulerControl::Drain { module_name, drained }) => {
+                                        let held = scheduler
+                                            .take_waiting(|job| job.module_name == module_name);
+                                        paused.entry(module_name.clone()).or_default().extend(held);
+                                        if running_modules.values().any(|m| *m == module_name) {
+                                            pending_drains.push((module_name, drained));
+                                        } else {
+                                            let _ = drained.send(());
+                                        }
+                                    }
+                                    Ok(scheduler::SchedulerControl::Resume { module_name }) => {
+                                        for job in paused.remove(&module_name).unwrap_or_default() {
+                                            if !dispatch(&mut scheduler, &mut running_modules, job) {
+                                                return;
+                                            }
+                                        }
+                                    }
+                                    Err(_) => break,
+                                }
                             }
                         }
                     }
@@ -283,13 +408,56 @@ impl Runtime {
                         ACTIVE_COMPLETION.with(|c| *c.borrow_mut() = tls_fork);
                         // Keep the original as the guard for THIS job's share.
                         let _completion_guard = job.completion;
-                        if let Err(err) = rt.call_reducer(
+                        // Inputs are recorded with the time and seed they ran with.
+                        let recorded = (job.source == JobSource::Input
+                            && rt.recorder.is_active())
+                        .then(|| {
+                            let (call_sequence, rng_seed) = rt.reducer_seed(
+                                &job.caller_node_id,
+                                &job.module_name,
+                                &job.reducer_name,
+                            );
+                            let unix_ms = rt.clock.now_unix_ms();
+                            JOB_UNIX_MS.with(|job_unix_ms| job_unix_ms.set(Some(unix_ms)));
+                            (unix_ms, call_sequence, rng_seed, job.input.clone())
+                        });
+                        // Internal jobs are run again by a replay, so only their time is.
+                        let internal_unix_ms = (job.source == JobSource::Internal)
+                            .then(|| rt.internal_job_unix_ms(&job.module_name, &job.reducer_name))
+                            .flatten();
+                        if let Some(unix_ms) = internal_unix_ms {
+                            JOB_UNIX_MS.with(|job_unix_ms| job_unix_ms.set(Some(unix_ms)));
+                        }
+                        let result = rt.call_reducer(
                             &job.module_name,
                             &job.reducer_name,
                             job.input,
                             job.caller_node_id,
                             &job.caller_module_name,
-                        ) {
+                            recorded.as_ref().map(|(_, _, rng_seed, _)| *rng_seed).or(job.rng_seed),
+                        );
+                        if let Some((unix_ms, call_sequence, rng_seed, input)) = recorded {
+                            JOB_UNIX_MS.with(|job_unix_ms| job_unix_ms.set(None));
+                            rt.record(RecordingEntry::Job {
+                                unix_ms,
+                                call_sequence,
+                                rng_seed,
+                                caller_node_id: job.caller_node_id.to_string(),
+                                caller_module_name: job.caller_module_name.clone(),
+                                module_name: job.module_name.clone(),
+                                reducer_name: job.reducer_name.clone(),
+                                input,
+                            });
+                        }
+                        if let Some(unix_ms) = internal_unix_ms {
+                            JOB_UNIX_MS.with(|job_unix_ms| job_unix_ms.set(None));
+                            rt.record(RecordingEntry::InternalJob {
+                                unix_ms,
+                                module_name: job.module_name.clone(),
+                                reducer_name: job.reducer_name.clone(),
+                            });
+                        }
+                        if let Err(err) = &result {
                             rt.logger.log(
                                 &format!(
                                     "Reducer execution failed for '{}.{}': {}",
@@ -299,6 +467,26 @@ impl Runtime {
                                 LogLevel::Error,
                             );
                         }
+                        if let Some(request_id) = job.request_id {
+                            let status = match &result {
+                                Ok(commit_sequence) => ReducerCallStatus::Committed {
+                                    commit_sequence: commit_sequence.unwrap_or_default(),
+                                },
+                                Err(err) => ReducerCallStatus::Failed {
+                                    error: err.to_string(),
+                                },
+                            };
+                            rt.network_handle.send_packet(
+                                job.caller_node_id,
+                                crate::network::protocol::NetworkPacket::ReducerResult {
+                                    request_id,
+                                    status,
+                                },
+                            );
+                        }
+                        if let Some(outcome) = job.outcome {
+                            let _ = outcome.send(result);
+                        }
                         // Clear TLS *before* the guard drops so any event forks
                         // made during call_reducer are counted, and the guard's
                         // drop is the last decrement if no events were dispatched.
@@ -310,65 +498,18 @@ impl Runtime {
         }
 
         // Async flush thread: every 10ms fsyncs logged WAL entries AND drains the
-        // dirty-stateful map to disk.  Both operations are bounded-latency by design.
+        // dirty-stateful and schedule maps to disk.  All are bounded-latency by design.
         {
             let wal_store = runtime.persistence.clone();
             std::thread::spawn(move || loop {
                 std::thread::sleep(std::time::Duration::from_millis(10));
                 wal_store.flush_wal();
                 wal_store.flush_stateful();
+                wal_store.flush_schedules();
             });
         }
 
-        // Timer task pool: single task manages all scheduled timers via a min-heap,
-        // avoiding the overhead of spawning a task per ctx.schedule() call.
-        let timer_rx = runtime.timer_rx.lock().take().expect("timer_rx already taken");
-        let timer_reducer_sender = runtime.reducer_ingress.clone();
-        let timer_node_id = runtime.network_handle.node_id;
-        tokio::spawn(async move {
-            let mut rx = timer_rx;
-            let mut heap: std::collections::BinaryHeap<std::cmp::Reverse<(std::time::Instant, String, String)>> =
-                std::collections::BinaryHeap::new();
-            loop {
-                let sleep_dur = if let Some(std::cmp::Reverse((wake_at, _, _))) = heap.peek() {
-                    let now = std::time::Instant::now();
-                    if *wake_at <= now {
-                        std::time::Duration::ZERO
-                    } else {
-                        *wake_at - now
-                    }
-                } else {
-                    std::time::Duration::from_secs(3600)
-                };
-
-                tokio::select! {
-                    _ = tokio::time::sleep(sleep_dur) => {
-                        let now = std::time::Instant::now();
-                        while let Some(std::cmp::Reverse((wake_at, _, _))) = heap.peek() {
-                            if *wake_at > now { break; }
-                            let std::cmp::Reverse((_, module_name, reducer_name)) = heap.pop().unwrap();
-                            let job = crate::runtime::reducer::ReducerJob {
-                                module_name,
-                                reducer_name,
-                                input: interstice_abi::IntersticeValue::Vec(vec![]),
-                                caller_node_id: timer_node_id,
-                                caller_module_name: String::new(),
-                                completion: None,
-                            };
-                            let _ = timer_reducer_sender.send(job);
-                        }
-                    }
-                    msg = rx.recv() => {
-                        match msg {
-                            Some((wake_at, module_name, reducer_name)) => {
-                                heap.push(std::cmp::Reverse((wake_at, module_name, reducer_name)));
-                            }
-                            None => break,
-                        }
-                    }
-                }
-            }
-        });
+        tokio::spawn(Runtime::run_schedules(runtime.clone()));
 
         while let Some((event, token)) = event_receiver.recv().await {
             Runtime::handle_event(runtime.clone(), event, token, &mut event_receiver).await;
@@ -386,6 +527,12 @@ impl Runtime {
         // ReducerJob holds its own fork, and the signal fires when all of them
         // (and their cascades) complete.
         let fork_token = |t: &Option<CompletionToken>| t.as_ref().map(|t| t.fork());
+        // Node connections also reach module subscriptions below.
+        match &event {
+            EventInstance::NodeConnect { node_id } => runtime.handle_node_connect(*node_id),
+            EventInstance::NodeDisconnect { node_id } => runtime.handle_node_disconnect(*node_id),
+            _ => {}
+        }
         match event {
             EventInstance::RequestAppInitialization => {
                 let app_initialized = runtime.app_initialized.lock();
@@ -431,7 +578,11 @@ impl Runtime {
                     input,
                     caller_node_id: requesting_node_id,
                     caller_module_name: String::new(),
+                    request_id: None,
                     completion: None,
+                    outcome: None,
+                    source: JobSource::Input,
+                    rng_seed: None,
                 };
                 let _ = runtime.reducer_ingress.send(job);
             }
@@ -444,6 +595,23 @@ impl Runtime {
             } => {
                 let runtime = runtime.clone();
                 tokio::task::spawn_blocking(move || {
+                    if let Err(denied) = runtime.check_remote_access(
+                        &module_name,
+                        &query_name,
+                        AccessCallKind::Query,
+                        requesting_node_id,
+                    ) {
+                        runtime.logger.log(
+                            &format!("Remote query from {} refused: {}", requesting_node_id, denied),
+                            LogSource::Runtime,
+                            LogLevel::Warning,
+                        );
+                        runtime.network_handle.send_packet(
+                            requesting_node_id,
+                            crate::network::protocol::NetworkPacket::Error(denied),
+                        );
+                        return;
+                    }
                     match runtime.call_query(&module_name, &query_name, input, requesting_node_id, "")
                     {
                         Ok(value) => {
@@ -466,7 +634,7 @@ impl Runtime {
                             );
                             runtime.network_handle.send_packet(
                                 requesting_node_id,
-                                crate::network::protocol::NetworkPacket::Error(err.to_string()),
+                                crate::network::protocol::NetworkPacket::Error((&err).into()),
                             );
                         }
                     }
@@ -482,6 +650,9 @@ impl Runtime {
                     let _ = sender.send(result);
                 }
             }
+            EventInstance::RemoteReducerResult { request_id, status } => {
+                runtime.settle_reducer_call(&request_id, status);
+            }
             EventInstance::RemoteSchemaResponse { request_id, schema } => {
                 if let Some(sender) = runtime
                     .pending_schema_responses
@@ -495,24 +666,30 @@ impl Runtime {
             EventInstance::RequestSubscription {
                 requesting_node_id,
                 event,
+                filter,
             } => {
-                let mut subscriptions_by_node = runtime.node_subscriptions.lock();
-                let subscriptions = subscriptions_by_node
-                    .entry(requesting_node_id)
-                    .or_insert(Vec::new());
-                if !subscriptions.contains(&event) {
-                    subscriptions.push(event);
-                    runtime.active_subscription_count.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                if let Some(filter) = &filter
+                    && let Err(err) = runtime.validate_subscription_filter(&event, filter)
+                {
+                    runtime.network_handle.send_packet(
+                        requesting_node_id,
+                        crate::network::protocol::NetworkPacket::Error((&err).into()),
+                    );
+                    return;
                 }
+                runtime.add_node_subscription(requesting_node_id, event, filter);
             }
             EventInstance::RequestUnsubscription {
                 requesting_node_id,
                 event,
+                filter,
             } => {
                 let mut subscriptions_by_node = runtime.node_subscriptions.lock();
                 if let Some(subscriptions) = subscriptions_by_node.get_mut(&requesting_node_id) {
                     let before_len = subscriptions.len();
-                    subscriptions.retain(|existing| existing != &event);
+                    subscriptions.retain(|(existing, existing_filter)| {
+                        existing != &event || existing_filter != &filter
+                    });
                     let removed = before_len - subscriptions.len();
                     if removed > 0 {
                         runtime.active_subscription_count.fetch_sub(removed as i32, std::sync::atomic::Ordering::Relaxed);
@@ -526,7 +703,31 @@ impl Runtime {
                 requesting_node_id,
                 module_name,
                 table_name,
+                filter,
+                since,
             } => {
+                // An unfiltered replica catching up gets the events it missed when
+                // they are still in the changelog, the whole table otherwise.
+                let changes = since.filter(|_| filter.is_none()).and_then(|since| {
+                    runtime
+                        .table_changes_since(&module_name, &table_name, &since, requesting_node_id)
+                        .map(|(position, events)| (since.sequence, position, events))
+                });
+                if let Some((since, position, events)) = changes {
+                    runtime.network_handle.send_packet(
+                        requesting_node_id,
+                        crate::network::protocol::NetworkPacket::TableChangesResponse {
+                            module_name,
+                            table_name,
+                            since,
+                            position,
+                            events,
+                        },
+                    );
+                    return;
+                }
+
+                let position = runtime.table_position(&module_name, &table_name);
                 let rows_result = {
                     let modules = runtime.modules.lock();
                     let module = modules.get(&module_name).ok_or_else(|| {
@@ -562,9 +763,19 @@ impl Runtime {
                             }
                         })?;
 
-                        Ok(table.scan().to_vec())
+                        let rows = table.query(filter.as_ref(), None)?;
+                        Ok((table_schema.clone(), rows.into_iter().cloned().collect()))
                     })
                 };
+                let rows_result = rows_result.and_then(|(table_schema, rows)| {
+                    runtime.filter_visible_rows(
+                        &module_name,
+                        &table_schema,
+                        rows,
+                        requesting_node_id,
+                        "",
+                    )
+                });
 
                 match rows_result {
                     Ok(rows) => runtime.network_handle.send_packet(
@@ -572,12 +783,13 @@ impl Runtime {
                         crate::network::protocol::NetworkPacket::TableSyncResponse {
                             module_name,
                             table_name,
+                            position,
                             rows,
                         },
                     ),
                     Err(err) => runtime.network_handle.send_packet(
                         requesting_node_id,
-                        crate::network::protocol::NetworkPacket::Error(err.to_string()),
+                        crate::network::protocol::NetworkPacket::Error((&err).into()),
                     ),
                 }
             }
@@ -585,9 +797,27 @@ impl Runtime {
                 source_node_id,
                 module_name,
                 table_name,
+                position,
                 rows,
             } => {
-                runtime.apply_replica_full_sync(source_node_id, &module_name, &table_name, rows);
+                runtime.apply_replica_sync(source_node_id, &module_name, &table_name, position, rows);
+            }
+            EventInstance::RemoteTableChanges {
+                source_node_id,
+                module_name,
+                table_name,
+                since,
+                position,
+                events,
+            } => {
+                runtime.apply_replica_changes(
+                    source_node_id,
+                    &module_name,
+                    &table_name,
+                    since,
+                    position,
+                    events,
+                );
             }
             EventInstance::LoadModule {
                 wasm_binary,
@@ -664,6 +894,23 @@ impl Runtime {
                     });
                 }
             }
+            EventInstance::UpgradeModule { wasm_binary } => {
+                // Like unload, an upgrade is not routed through the Module authority.
+                let runtime_cloned = runtime.clone();
+                tokio::task::spawn_local(async move {
+                    let result = match Module::from_bytes(runtime_cloned.clone(), &wasm_binary).await {
+                        Ok(module) => Runtime::upgrade_module(runtime_cloned.clone(), module).await,
+                        Err(err) => Err(err),
+                    };
+                    if let Err(err) = result {
+                        runtime_cloned.logger.log(
+                            &format!("Failed to upgrade module: {}", err),
+                            LogSource::Runtime,
+                            LogLevel::Error,
+                        );
+                    }
+                });
+            }
             EventInstance::UnloadModule { module_name } => {
                 // Unload keeps the module's data on disk; it is not routed through
                 // the Module authority (no UnloadRequest subscription event exists).
@@ -700,92 +947,91 @@ impl Runtime {
                     crate::network::protocol::NetworkPacket::SchemaResponse { request_id, schema },
                 );
             }
-            EventInstance::TableInsertEvent {
-                source_node_id,
-                module_name,
-                table_name,
-                inserted_row,
-            } => {
-                if let Some(source_node_id) = source_node_id {
-                    runtime.apply_replica_insert(
+            event @ (EventInstance::TableInsertEvent { .. }
+            | EventInstance::TableUpdateEvent { .. }
+            | EventInstance::TableDeleteEvent { .. }) => {
+                // Replicas are fed by their unfiltered subscription only.
+                if let (Some(source_node_id), None, Some((module_name, table_name, sequence))) =
+                    (event.source_node_id(), event.filter(), event.table_event())
+                {
+                    runtime.apply_replica_event(
                         source_node_id,
-                        &module_name,
-                        &table_name,
-                        inserted_row.clone(),
+                        module_name,
+                        table_name,
+                        sequence,
+                        Some(&event),
                     );
                 }
-
-                let event = EventInstance::TableInsertEvent {
-                    source_node_id,
-                    module_name,
-                    table_name,
-                    inserted_row,
-                };
-                let triggered = runtime.find_subscriptions(&event).unwrap();
-                for sub in triggered {
-                    let _ = runtime.invoke_subscription(sub, event.clone(), fork_token(&completion_token));
-                }
+                runtime.dispatch_table_event(event, &completion_token);
             }
-            EventInstance::TableUpdateEvent {
+            EventInstance::RemoteTableEventHidden {
                 source_node_id,
                 module_name,
                 table_name,
-                old_row,
-                new_row,
+                sequence,
             } => {
-                if let Some(source_node_id) = source_node_id {
-                    runtime.apply_replica_update(
-                        source_node_id,
-                        &module_name,
-                        &table_name,
-                        old_row.clone(),
-                        new_row.clone(),
-                    );
-                }
-
-                let event = EventInstance::TableUpdateEvent {
-                    source_node_id,
-                    module_name,
-                    table_name,
-                    old_row,
-                    new_row,
-                };
-                let triggered = runtime.find_subscriptions(&event).unwrap();
-                for sub in triggered {
-                    let _ = runtime.invoke_subscription(sub, event.clone(), fork_token(&completion_token));
-                }
+                runtime.apply_replica_event(source_node_id, &module_name, &table_name, sequence, None);
             }
-            EventInstance::TableDeleteEvent {
-                source_node_id,
-                module_name,
-                table_name,
-                deleted_row,
-            } => {
-                if let Some(source_node_id) = source_node_id {
-                    runtime.apply_replica_delete(
-                        source_node_id,
-                        &module_name,
-                        &table_name,
-                        deleted_row.clone(),
-                    );
-                }
-
-                let event = EventInstance::TableDeleteEvent {
-                    source_node_id,
-                    module_name,
-                    table_name,
-                    deleted_row,
-                };
+            event => {
                 let triggered = runtime.find_subscriptions(&event).unwrap();
+
                 for sub in triggered {
                     let _ = runtime.invoke_subscription(sub, event.clone(), fork_token(&completion_token));
                 }
             }
-            event => {
-                let triggered = runtime.find_subscriptions(&event).unwrap();
+        }
+    }
 
-                for sub in triggered {
-                    let _ = runtime.invoke_subscription(sub, event.clone(), fork_token(&completion_token));
+    /// Register a subscription of a remote node, once per event and filter.
+    pub(crate) fn add_node_subscription(
+        &self,
+        node_id: NodeId,
+        event: SubscriptionEventSchema,
+        filter: Option<RowFilter>,
+    ) {
+        let mut subscriptions_by_node = self.node_subscriptions.lock();
+        let subscriptions = subscriptions_by_node.entry(node_id).or_default();
+        let subscription = (event, filter);
+        if !subscriptions.contains(&subscription) {
+            subscriptions.push(subscription);
+            self.active_subscription_count.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        }
+    }
+
+    /// Fan a table event out to its subscribers, through the table view if it has one,
+    /// and to the insert and delete subscribers an update moves the row in or out of
+    /// the view or filter of.
+    /// Local events of public tables are numbered first; a replica the event is
+    /// hidden from still gets its sequence number, so it can tell it lost nothing.
+    fn dispatch_table_event(&self, mut event: EventInstance, completion_token: &Option<CompletionToken>) {
+        if event.source_node_id().is_none() {
+            self.record_table_event(&mut event);
+        }
+        for (sub, visible) in self.table_event_deliveries(&event) {
+            match visible {
+                Some(event) => {
+                    let _ = self.invoke_subscription(
+                        sub,
+                        event,
+                        completion_token.as_ref().map(|t| t.fork()),
+                    );
+                }
+                None => {
+                    if let SubscriptionTarget::Remote { node_id, filter: None } = sub
+                        && let Some((module_name, table_name, sequence)) = event.table_event()
+                        && sequence > 0
+                    {
+                        self.network_handle.send_packet(
+                            node_id,
+                            crate::network::protocol::NetworkPacket::TableEvent(
+                                crate::network::protocol::TableEventInstance::TableEventHidden {
+                                    sequence,
+                                    module_name: module_name.to_string(),
+                                    table_name: table_name.to_string(),
+                                },
+                            ),
+                        );
+                    }
                 }
             }
         }
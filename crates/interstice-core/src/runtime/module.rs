@@ -2,28 +2,34 @@ use crate::{
     error::IntersticeError,
     logger::{LogLevel, LogSource},
     network::protocol::{NetworkPacket, RequestSubscription, TableEvent},
+    node::NodeId,
     runtime::{
         Runtime,
         authority::AuthorityEntry,
         event::EventInstance,
+        recorder::{RecordingEntry, TablesSnapshot, tables_snapshot},
+        reducer::CompletionToken,
+        host_calls::file::{FileAccess, FileWatch},
+        replication::ReplicaCursor,
+        scheduler::SchedulerControl,
         table::Table,
         wasm::{StoreState, instance::WasmInstance},
     },
 };
 use interstice_abi::{
-    ABI_VERSION, Authority, FileEvent, IndexKey, IntersticeValue, ModuleSchema, NodeSchema,
-    NodeSelection, RawQueryContext as QueryContext, RawReducerContext as ReducerContext,
-    SubscriptionEventSchema, TableVisibility,
+    ABI_VERSION, Authority, FileEvent, IntersticeValue, ModuleDependency, ModuleSchema,
+    NodeSchema,
+    NodeSelection, PersistenceKind, RawQueryContext as QueryContext,
+    RawReducerContext as ReducerContext, SubscriptionEventSchema, SubscriptionSchema, TableSchema, TableVisibility,
     get_query_wrapper_name, get_reducer_wrapper_name,
 };
 use notify::{RecursiveMode, Watcher};
 use serde::Serialize;
-use std::convert::TryInto;
 use parking_lot::Mutex;
 use std::{
     collections::{HashMap, HashSet},
     path::Path,
-    sync::Arc,
+    sync::{Arc, Weak},
 };
 use tokio::time::{Duration, timeout};
 use uuid::Uuid;
@@ -33,12 +39,22 @@ use wasmtime::{Module as wasmtimeModule, Store};
 /// reducer executions at the cost of extra WASM linear memory per module (TLB pressure).
 const REDUCER_POOL_SIZE: usize = 8;
 
+/// Number of idle WASM instances kept for queries. A query never waits for an
+/// instance: when they are all busy (a table view called from another query of the
+/// module, or concurrent calls) it runs on a new one, dropped afterwards if the pool
+/// is full.
+const QUERY_POOL_SIZE: usize = 2;
+
 pub struct Module {
     /// Pool of WASM instances for reducer execution. Multiple threads draw from this pool,
     /// enabling parallel reducer execution for independent reducers.
     instance_pool: Arc<(parking_lot::Mutex<Vec<WasmInstance>>, parking_lot::Condvar)>,
-    query_instance: Arc<Mutex<WasmInstance>>,
-    wasm_bytes: Vec<u8>,
+    /// Idle WASM instances for query execution.
+    query_pool: Mutex<Vec<WasmInstance>>,
+    /// Compiled module, to instantiate query instances when the pool is empty.
+    wasm_module: wasmtimeModule,
+    runtime: Weak<Runtime>,
+    pub(crate) wasm_bytes: Vec<u8>,
     pub schema: Arc<ModuleSchema>,
     pub tables: Arc<Mutex<HashMap<String, Table>>>,
     pub reducer_names: HashSet<String>,
@@ -77,7 +93,7 @@ impl Module {
             .unwrap();
         let query_instance = WasmInstance::new(query_store, query_raw)?;
 
-        let module = Module::new(instances, query_instance, wasm_bytes).await?;
+        let module = Module::new(instances, query_instance, wasm_module, wasm_bytes).await?;
         Ok(module)
     }
 
@@ -107,13 +123,14 @@ impl Module {
             .unwrap();
         let query_instance = WasmInstance::new(query_store, query_raw)?;
 
-        let module = Module::new(instances, query_instance, wasm_binary.to_vec()).await?;
+        let module = Module::new(instances, query_instance, wasm_module, wasm_binary.to_vec()).await?;
         Ok(module)
     }
 
     pub async fn new(
         mut instances: Vec<WasmInstance>,
         mut query_instance: WasmInstance,
+        wasm_module: wasmtimeModule,
         wasm_bytes: Vec<u8>,
     ) -> Result<Self, IntersticeError> {
         assert!(!instances.is_empty(), "at least one reducer instance required");
@@ -124,6 +141,11 @@ impl Module {
                 found: schema.abi_version,
             });
         }
+        crate::runtime::host_calls::file::validate_module_name(&schema.name)?;
+        crate::runtime::view::validate_table_views(&schema)?;
+        crate::runtime::access::validate_access_policies(&schema)?;
+        crate::runtime::table::validate_table_indexes(&schema)?;
+        crate::runtime::tick::validate_tick_subscriptions(&schema)?;
 
         let tables = schema
             .tables
@@ -140,19 +162,13 @@ impl Module {
             .map(|r| get_reducer_wrapper_name(&r.name))
             .collect();
         for inst in &mut instances {
-            inst.store.data_mut().module_schema = schema.clone();
+            inst.set_schema(schema.clone())?;
             inst.preload_funcs(&reducer_func_names);
             inst.init_scratch(4096).ok();
         }
 
-        let query_func_names: Vec<String> = schema
-            .queries
-            .iter()
-            .map(|q| get_query_wrapper_name(&q.name))
-            .collect();
-        query_instance.store.data_mut().module_schema = schema.clone();
-        query_instance.preload_funcs(&query_func_names);
-        query_instance.init_scratch(4096).ok();
+        prepare_query_instance(&mut query_instance, &schema)?;
+        let runtime = Arc::downgrade(&query_instance.store.data().runtime);
 
         let instance_pool = Arc::new((
             parking_lot::Mutex::new(instances),
@@ -161,7 +177,9 @@ impl Module {
 
         Ok(Self {
             instance_pool,
-            query_instance: Arc::new(Mutex::new(query_instance)),
+            query_pool: Mutex::new(vec![query_instance]),
+            wasm_module,
+            runtime,
             wasm_bytes,
             schema,
             tables: Arc::new(Mutex::new(tables)),
@@ -174,7 +192,13 @@ impl Module {
         reducer: &str,
         args: (ReducerContext, impl Serialize),
     ) -> Result<(), IntersticeError> {
-        let func_name = get_reducer_wrapper_name(reducer);
+        let fuel = self
+            .schema
+            .reducers
+            .iter()
+            .find(|r| r.name == reducer)
+            .and_then(|r| r.fuel)
+            .or(self.schema.limits.fuel);
         // Borrow an instance from the pool; block if all are in use.
         let (lock, cvar) = &*self.instance_pool;
         let mut instance = {
@@ -184,7 +208,7 @@ impl Module {
             }
             pool.pop().unwrap()
         };
-        let result = instance.call_reducer(&func_name, args);
+        let result = instance.call_reducer(reducer, args, fuel);
         // Return the instance to the pool and wake a waiting thread if any.
         {
             let mut pool = lock.lock();
@@ -194,20 +218,80 @@ impl Module {
         result
     }
 
-    pub fn call_query(
+    /// Call a query once per context and arguments, all on the same instance.
+    pub fn call_queries<A: Serialize>(
         &self,
         query: &str,
-        args: (QueryContext, impl Serialize),
-    ) -> Result<IntersticeValue, IntersticeError> {
-        let func_name = get_query_wrapper_name(query);
-        self.query_instance.lock().call_query(&func_name, args)
+        calls: impl IntoIterator<Item = (QueryContext, A)>,
+    ) -> Result<Vec<IntersticeValue>, IntersticeError> {
+        let idle = self.query_pool.lock().pop();
+        let mut instance = match idle {
+            Some(instance) => instance,
+            None => self.new_query_instance()?,
+        };
+        let results = calls
+            .into_iter()
+            .map(|args| instance.call_query(query, args, self.schema.limits.fuel))
+            .collect();
+        let mut pool = self.query_pool.lock();
+        if pool.len() < QUERY_POOL_SIZE {
+            pool.push(instance);
+        }
+        results
     }
+
+    fn new_query_instance(&self) -> Result<WasmInstance, IntersticeError> {
+        let runtime = self
+            .runtime
+            .upgrade()
+            .ok_or_else(|| IntersticeError::Internal("the runtime was dropped".into()))?;
+        let mut store = Store::new(&runtime.engine, StoreState::new(runtime.clone()));
+        let raw = runtime
+            .linker
+            .instantiate(&mut store, &self.wasm_module)
+            .map_err(|err| {
+                IntersticeError::Internal(format!(
+                    "Couldn't instantiate module '{}' for a query: {}",
+                    self.schema.name, err
+                ))
+            })?;
+        let mut instance = WasmInstance::new(store, raw)?;
+        prepare_query_instance(&mut instance, &self.schema)?;
+        Ok(instance)
+    }
+}
+
+fn prepare_query_instance(
+    instance: &mut WasmInstance,
+    schema: &Arc<ModuleSchema>,
+) -> Result<(), IntersticeError> {
+    let query_func_names: Vec<String> = schema
+        .queries
+        .iter()
+        .map(|q| get_query_wrapper_name(&q.name))
+        .collect();
+    instance.set_schema(schema.clone())?;
+    instance.preload_funcs(&query_func_names);
+    instance.init_scratch(4096).ok();
+    Ok(())
 }
 
 impl Runtime {
     pub async fn load_module(
         runtime: Arc<Self>,
         module: Module,
+    ) -> Result<ModuleSchema, IntersticeError> {
+        Runtime::load_module_with_completion(runtime, module, None, None).await
+    }
+
+    /// Load a module, with its `init` and `load` events holding a fork of
+    /// `completion` when given. A replay passes the rows the module was
+    /// recorded with in `restored_tables`, to load it as the node reloaded it.
+    pub(crate) async fn load_module_with_completion(
+        runtime: Arc<Self>,
+        module: Module,
+        completion: Option<&CompletionToken>,
+        restored_tables: Option<TablesSnapshot>,
     ) -> Result<ModuleSchema, IntersticeError> {
         let module_schema = module.schema.clone();
 
@@ -217,11 +301,12 @@ impl Runtime {
         // write it on every load and only delete it on `remove`). On a reload we
         // restore its tables from disk and skip the once-only `init` event,
         // exactly like a node restart; on a genuine first load we fire `init`.
-        let is_reload = runtime
-            .modules_path
-            .as_ref()
-            .map(|path| path.join(&module_schema.name).join("state.toml").exists())
-            .unwrap_or(false);
+        let is_reload = restored_tables.is_some()
+            || runtime
+                .modules_path
+                .as_ref()
+                .map(|path| path.join(&module_schema.name).join("state.toml").exists())
+                .unwrap_or(false);
 
         // If the module requires GPU authority and the app is not initialized yet, initialize it
         if module_schema
@@ -252,48 +337,7 @@ impl Runtime {
                     other_entry.module_name().to_string(),
                 ));
             } else {
-                let module_name = module_schema.name.clone();
-                let entry = match authority {
-                    Authority::Gpu => AuthorityEntry::Gpu {
-                        module_name,
-                        render_reducer: module_schema
-                            .subscriptions
-                            .iter()
-                            .find(|sub| sub.event == SubscriptionEventSchema::Render)
-                            .map(|sub| sub.reducer_name.clone()),
-                    },
-                    Authority::Audio => AuthorityEntry::Audio {
-                        module_name,
-                        output_reducer: module_schema
-                            .subscriptions
-                            .iter()
-                            .find(|sub| sub.event == SubscriptionEventSchema::AudioOutput)
-                            .map(|sub| sub.reducer_name.clone()),
-                        input_reducer: module_schema
-                            .subscriptions
-                            .iter()
-                            .find(|sub| sub.event == SubscriptionEventSchema::AudioInput)
-                            .map(|sub| sub.reducer_name.clone()),
-                    },
-                    Authority::Input => AuthorityEntry::Input {
-                        module_name,
-                        input_reducer: module_schema
-                            .subscriptions
-                            .iter()
-                            .find(|sub| sub.event == SubscriptionEventSchema::Input)
-                            .map(|sub| sub.reducer_name.clone()),
-                    },
-                    Authority::File => AuthorityEntry::File { module_name },
-                    Authority::Module => AuthorityEntry::Module { module_name },
-                    Authority::Network => AuthorityEntry::Network {
-                        module_name,
-                        on_event_reducer: module_schema
-                            .subscriptions
-                            .iter()
-                            .find(|sub| sub.event == SubscriptionEventSchema::Network)
-                            .map(|sub| sub.reducer_name.clone()),
-                    },
-                };
+                let entry = authority_entry(authority, &module_schema);
                 runtime
                     .authority_modules
                     .lock()
@@ -319,14 +363,7 @@ impl Runtime {
             if let Some(dependency_module) =
                 runtime.modules.lock().get(&dependency.module_name)
             {
-                if dependency_module.schema.version != dependency.version {
-                    return Err(IntersticeError::ModuleVersionMismatch(
-                        module.schema.name.clone(),
-                        dependency_module.schema.name.clone(),
-                        module.schema.version.clone(),
-                        dependency_module.schema.version.clone(),
-                    ));
-                }
+                check_dependency(&module.schema.name, dependency, &dependency_module.schema)?;
             } else {
                 return Err(IntersticeError::ModuleNotFound(
                     dependency.module_name.clone(),
@@ -341,9 +378,19 @@ impl Runtime {
         // Connect to node dependencies
         for node_dependency in &module_schema.node_dependencies {
             let network = &mut runtime.network_handle.clone();
-            network
+            if let Err(err) = network
                 .connect_to_peer(node_dependency.address.clone())
-                .await;
+                .await
+            {
+                runtime.logger.log(
+                    &format!(
+                        "Could not connect to node '{}' at {}: {}",
+                        node_dependency.name, node_dependency.address, err
+                    ),
+                    LogSource::Network,
+                    LogLevel::Warning,
+                );
+            }
 
             if let Ok(node_id) = network.get_node_id_from_adress(&node_dependency.address) {
                 runtime
@@ -355,49 +402,13 @@ impl Runtime {
         }
 
         // Send subscription requests to remote subscriptions
-        let network = &mut runtime.network_handle.clone();
         for sub in &module_schema.subscriptions {
-            match sub.event.clone() {
-                SubscriptionEventSchema::Insert {
-                    node_selection: NodeSelection::Other(node_name),
-                    module_name,
-                    table_name,
-                }
-                | SubscriptionEventSchema::Update {
-                    node_selection: NodeSelection::Other(node_name),
-                    module_name,
-                    table_name,
-                }
-                | SubscriptionEventSchema::Delete {
-                    node_selection: NodeSelection::Other(node_name),
-                    module_name,
-                    table_name,
-                } => {
-                    let node_adress = module_schema
-                        .node_dependencies
-                        .iter()
-                        .find(|n| n.name == node_name)
-                        .ok_or(IntersticeError::Internal(format!(
-                            "Couldn't find node {node_name} in the node dependencies"
-                        )))?
-                        .address
-                        .clone();
-                    let node_id = network.get_node_id_from_adress(&node_adress).unwrap();
-                    network.send_packet(
-                        node_id,
-                        NetworkPacket::RequestSubscription(RequestSubscription {
-                            module_name,
-                            table_name,
-                            event: match sub.event {
-                                SubscriptionEventSchema::Insert { .. } => TableEvent::Insert,
-                                SubscriptionEventSchema::Update { .. } => TableEvent::Update,
-                                SubscriptionEventSchema::Delete { .. } => TableEvent::Delete,
-                                _ => unreachable!(),
-                            },
-                        }),
-                    );
-                }
-                _ => {}
+            if let Some((node_id, request)) =
+                remote_table_subscription(&runtime, &module_schema, sub)?
+            {
+                runtime
+                    .network_handle
+                    .send_packet(node_id, NetworkPacket::RequestSubscription(request));
             }
         }
 
@@ -494,31 +505,43 @@ impl Runtime {
                     source_module_name: replicated.module_name.clone(),
                     source_table_name: replicated.table_name.clone(),
                     local_table_name,
+                    cursor: ReplicaCursor::new(),
                 });
         }
 
-        // save module
-        if let Some(modules_path) = &runtime.modules_path {
-            let module_dir = modules_path.join(&module_schema.name);
-            std::fs::create_dir_all(&module_dir).unwrap();
-            std::fs::create_dir_all(module_dir.join("logs")).unwrap();
-            std::fs::create_dir_all(module_dir.join("snapshots")).unwrap();
-            std::fs::write(module_dir.join("module.wasm"), &module.wasm_bytes).unwrap();
-            // Mark the module as loaded so it is auto-loaded again on node startup.
-            write_module_state(&module_dir, true);
+        // On a reload, convert persisted rows whose table layout changed since the
+        // previous version before anything reads them.
+        if is_reload {
+            let migrated = runtime
+                .persistence
+                .migrate_module(&module_schema.name, &module_schema.tables)?;
+            if !migrated.is_empty() {
+                let previous_version = runtime
+                    .modules_path
+                    .as_ref()
+                    .and_then(|path| read_module_state(&path.join(&module_schema.name)))
+                    .and_then(|state| state.version)
+                    .unwrap_or_else(|| "unknown".into());
+                let version: String = module_schema.version.clone().into();
+                runtime.logger.log(
+                    &format!(
+                        "Migrated tables [{}] of module '{}' from version {} to {}",
+                        migrated.join(", "),
+                        module_schema.name,
+                        previous_version,
+                        version
+                    ),
+                    LogSource::Runtime,
+                    LogLevel::Info,
+                );
+            }
         }
 
+        // save module
+        runtime.save_module(&module, true)?;
+
         // Count table subscriptions (Insert/Update/Delete) and add to active_subscription_count
-        let table_sub_count = module_schema
-            .subscriptions
-            .iter()
-            .filter(|s| matches!(
-                s.event,
-                SubscriptionEventSchema::Insert { .. }
-                    | SubscriptionEventSchema::Update { .. }
-                    | SubscriptionEventSchema::Delete { .. }
-            ))
-            .count() as i32;
+        let table_sub_count = table_subscription_count(&module_schema);
         if table_sub_count > 0 {
             runtime
                 .active_subscription_count
@@ -533,14 +556,16 @@ impl Runtime {
         runtime.clear_reducer_access_cache();
 
         // On a reload, repopulate the module's tables from their persisted data.
-        if is_reload {
-            if let Some(module) = runtime.modules.lock().get(&module_schema.name) {
-                let mut tables = module.tables.lock();
-                for table in tables.values_mut() {
-                    runtime
-                        .persistence
-                        .restore_table(&module_schema.name, table)?;
-                }
+        if let Some(restored_tables) = restored_tables {
+            runtime.restore_tables(&module_schema.name, restored_tables)?;
+        } else if is_reload
+            && let Some(module) = runtime.modules.lock().get(&module_schema.name)
+        {
+            let mut tables = module.tables.lock();
+            for table in tables.values_mut() {
+                runtime
+                    .persistence
+                    .restore_table(&module_schema.name, table)?;
             }
         }
 
@@ -557,27 +582,34 @@ impl Runtime {
             .collect::<Vec<_>>();
 
         for binding in replica_bindings {
-            for event in [TableEvent::Insert, TableEvent::Update, TableEvent::Delete] {
-                runtime.network_handle.send_packet(
-                    binding.source_node_id,
-                    NetworkPacket::RequestSubscription(RequestSubscription {
-                        module_name: binding.source_module_name.clone(),
-                        table_name: binding.source_table_name.clone(),
-                        event,
-                    }),
-                );
-            }
+            runtime.subscribe_replica(&binding);
+            runtime.request_replica_sync(&binding);
+        }
 
-            runtime.network_handle.send_packet(
-                binding.source_node_id,
-                NetworkPacket::RequestTableSync {
-                    module_name: binding.source_module_name.clone(),
-                    table_name: binding.source_table_name.clone(),
-                },
-            );
+        let watches = file_watches(&runtime, &module_schema, &module_schema.subscriptions)?;
+        if !watches.is_empty() {
+            runtime
+                .file_watchers
+                .lock()
+                .insert(module_schema.name.clone(), watches);
         }
 
-        setup_file_watches(runtime.clone(), &module_schema)?;
+        // Resume the schedules the module left pending, before its `load` reducers run.
+        runtime.restore_schedules(&module_schema.name)?;
+        runtime.restore_timeline(&module_schema.name);
+
+        runtime.sync_ticks(&module_schema);
+
+        let loaded = runtime.modules.lock().get(&module_schema.name).cloned();
+        if runtime.recorder.is_active()
+            && let Some(module) = loaded
+        {
+            runtime.record(RecordingEntry::ModuleLoaded {
+                unix_ms: runtime.clock.now_unix_ms(),
+                wasm_binary: module.wasm_bytes.clone(),
+                restored_tables: is_reload.then(|| tables_snapshot(&module)),
+            });
+        }
 
         // Trigger startup events asynchronously via the runtime event queue.
         // `init` fires only on the first load; a reload does not re-run it.
@@ -586,7 +618,7 @@ impl Runtime {
                 .event_sender
                 .send((EventInstance::Init {
                     module_name: module_schema.name.clone(),
-                }, None))
+                }, completion.map(CompletionToken::fork)))
                 .map_err(|err| {
                     IntersticeError::Internal(format!("Failed to send Init event: {}", err))
                 })?;
@@ -595,7 +627,7 @@ impl Runtime {
             .event_sender
             .send((EventInstance::Load {
                 module_name: module_schema.name.clone(),
-            }, None))
+            }, completion.map(CompletionToken::fork)))
             .map_err(|err| {
                 IntersticeError::Internal(format!("Failed to send Load event: {}", err))
             })?;
@@ -610,236 +642,367 @@ impl Runtime {
         return Ok(module_schema.as_ref().clone());
     }
 
-    pub(crate) async fn request_node_schema(
-        &self,
-        node_id: crate::node::NodeId,
-        node_name: String,
-    ) -> Result<NodeSchema, IntersticeError> {
-        let request_id = Uuid::new_v4().to_string();
-        let (sender, receiver) = tokio::sync::oneshot::channel();
-        self.pending_schema_responses
+    /// Replace a loaded module with a new version of it without tearing it down.
+    /// Its jobs are drained from the scheduler and the instances swapped in one
+    /// step. Tables whose schema is unchanged keep their rows in memory (ephemeral
+    /// ones included), changed persisted tables are migrated and restored from disk,
+    /// and changed ephemeral tables start empty.
+    pub async fn upgrade_module(
+        runtime: Arc<Self>,
+        module: Module,
+    ) -> Result<ModuleSchema, IntersticeError> {
+        Runtime::upgrade_module_with_completion(runtime, module, None, None).await
+    }
+
+    /// Upgrade a module, with its `load` event holding a fork of `completion`
+    /// when given. A replay passes the rows the upgraded module was recorded with
+    /// in `restored_tables`.
+    pub(crate) async fn upgrade_module_with_completion(
+        runtime: Arc<Self>,
+        module: Module,
+        completion: Option<&CompletionToken>,
+        restored_tables: Option<TablesSnapshot>,
+    ) -> Result<ModuleSchema, IntersticeError> {
+        let module_schema = module.schema.clone();
+        let module_name = module_schema.name.clone();
+        let current = runtime
+            .modules
             .lock()
-            
-            .insert(request_id.clone(), sender);
+            .get(&module_name)
+            .cloned()
+            .ok_or_else(|| {
+                IntersticeError::ModuleNotFound(
+                    module_name.clone(),
+                    "Only a loaded module can be upgraded".into(),
+                )
+            })?;
+        runtime.check_upgrade(&current.schema, &module_schema)?;
+
+        // Let the module's running jobs finish and hold back the others until the
+        // new version is in place.
+        let (drained_tx, drained_rx) = tokio::sync::oneshot::channel();
+        let _ = runtime.scheduler_control.send(SchedulerControl::Drain {
+            module_name: module_name.clone(),
+            drained: drained_tx,
+        });
+        let _ = drained_rx.await;
+        let result = runtime.swap_module(&current, module);
+        let _ = runtime.scheduler_control.send(SchedulerControl::Resume {
+            module_name: module_name.clone(),
+        });
+        result?;
+
+        runtime.sync_ticks(&module_schema);
+
+        if let Some(restored_tables) = restored_tables {
+            runtime.restore_tables(&module_name, restored_tables)?;
+        }
+        let loaded = runtime.modules.lock().get(&module_name).cloned();
+        if runtime.recorder.is_active()
+            && let Some(module) = loaded
+        {
+            runtime.record(RecordingEntry::ModuleUpgraded {
+                unix_ms: runtime.clock.now_unix_ms(),
+                wasm_binary: module.wasm_bytes.clone(),
+                tables: tables_snapshot(&module),
+            });
+        }
 
-        self.network_handle.send_packet(
-            node_id,
-            NetworkPacket::SchemaRequest {
-                request_id: request_id.clone(),
-                node_name,
-            },
+        runtime
+            .event_sender
+            .send((EventInstance::Load {
+                module_name: module_name.clone(),
+            }, completion.map(CompletionToken::fork)))
+            .map_err(|err| {
+                IntersticeError::Internal(format!("Failed to send Load event: {}", err))
+            })?;
+
+        runtime.logger.log(
+            &format!(
+                "Upgraded module '{}' from version {} to {}",
+                module_name,
+                Into::<String>::into(current.schema.version.clone()),
+                Into::<String>::into(module_schema.version.clone())
+            ),
+            LogSource::Runtime,
+            LogLevel::Info,
         );
 
-        match timeout(Duration::from_secs(10), receiver).await {
-            Ok(Ok(schema)) => Ok(schema),
-            Ok(Err(_)) => Err(IntersticeError::Internal(
-                "Schema request channel closed unexpectedly".into(),
-            )),
-            Err(_) => {
-                self.pending_schema_responses
-                    .lock()
-                    
-                    .remove(&request_id);
-                Err(IntersticeError::Internal(
-                    "Timed out while waiting for remote schema response".into(),
-                ))
-            }
-        }
+        Ok(module_schema.as_ref().clone())
     }
 
-    pub(crate) fn apply_replica_insert(
+    /// Reject upgrades that loaded modules or the node cannot accept in place.
+    fn check_upgrade(
         &self,
-        source_node_id: crate::node::NodeId,
-        source_module_name: &str,
-        source_table_name: &str,
-        inserted_row: interstice_abi::Row,
-    ) {
-        let bindings = self
-            .replica_bindings
-            .lock()
-            
-            .iter()
-            .filter(|binding| {
-                binding.source_node_id == source_node_id
-                    && binding.source_module_name == source_module_name
-                    && binding.source_table_name == source_table_name
-            })
-            .cloned()
-            .collect::<Vec<_>>();
-
-        for binding in bindings {
-            let insert_result = self.apply_transaction(
-                crate::runtime::transaction::Transaction::Insert {
-                    module_name: binding.owner_module_name.clone(),
-                    table_name: binding.local_table_name.clone(),
-                    new_row: inserted_row.clone(),
-                },
-                false,
-                None,
-            );
+        current: &ModuleSchema,
+        upgraded: &ModuleSchema,
+    ) -> Result<(), IntersticeError> {
+        {
+            let modules = self.modules.lock();
+            for dependent in modules.values() {
+                for dependency in &dependent.schema.module_dependencies {
+                    if dependency.module_name == upgraded.name {
+                        check_dependency(&dependent.schema.name, dependency, upgraded)?;
+                    }
+                }
+            }
+            for dependency in &upgraded.module_dependencies {
+                match modules.get(&dependency.module_name) {
+                    Some(module) => check_dependency(&upgraded.name, dependency, &module.schema)?,
+                    None => {
+                        return Err(IntersticeError::ModuleNotFound(
+                            dependency.module_name.clone(),
+                            format!("Required by '{}' which depends on it", upgraded.name),
+                        ));
+                    }
+                }
+            }
+        }
 
-            if insert_result.is_err() {
-                let _ = self.apply_transaction(
-                    crate::runtime::transaction::Transaction::Update {
-                        module_name: binding.owner_module_name.clone(),
-                        table_name: binding.local_table_name.clone(),
-                        update_row: inserted_row.clone(),
-                    },
-                    false,
-                    None,
-                );
+        for authority in &upgraded.authorities {
+            let authority_modules = self.authority_modules.lock();
+            let Some(other_entry) = authority_modules.get(authority) else {
+                continue;
+            };
+            if other_entry.module_name() != upgraded.name {
+                return Err(IntersticeError::AuthorityAlreadyTaken(
+                    upgraded.name.clone(),
+                    authority.clone().into(),
+                    other_entry.module_name().to_string(),
+                ));
             }
+        }
 
-            self.emit_replica_sync_event_if_needed(&binding);
+        let same_nodes = current.node_dependencies.len() == upgraded.node_dependencies.len()
+            && current
+                .node_dependencies
+                .iter()
+                .zip(upgraded.node_dependencies.iter())
+                .all(|(a, b)| a.name == b.name && a.address == b.address);
+        if !same_nodes || current.replicated_tables != upgraded.replicated_tables {
+            return Err(IntersticeError::Internal(format!(
+                "Module '{}' changes its node dependencies or replicated tables, which cannot be upgraded in place; unload and load it instead",
+                upgraded.name
+            )));
         }
+        Ok(())
     }
 
-    pub(crate) fn apply_replica_update(
-        &self,
-        source_node_id: crate::node::NodeId,
-        source_module_name: &str,
-        source_table_name: &str,
-        _old_row: interstice_abi::Row,
-        new_row: interstice_abi::Row,
-    ) {
-        let bindings = self
-            .replica_bindings
-            .lock()
-            
+    /// Move the tables of `current` over to `module` and register it in its place.
+    /// The new tables are built and checked in memory, and the migration of the
+    /// persisted rows is committed last, after everything else that can fail.
+    fn swap_module(&self, current: &Arc<Module>, mut module: Module) -> Result<(), IntersticeError> {
+        let module_schema = module.schema.clone();
+        let module_name = &module_schema.name;
+        let mut staged = self
+            .persistence
+            .stage_module_migration(module_name, &module_schema.tables)?;
+        let mut migrated = staged
+            .as_mut()
+            .map(|staged| staged.take_tables())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|table| (table.schema.name.clone(), table))
+            .collect::<HashMap<_, _>>();
+
+        // Remote table subscriptions and file watches to add and remove.
+        let mut subscribe = Vec::new();
+        let same = |left: &SubscriptionSchema, right: &SubscriptionSchema| {
+            left.event == right.event && left.filter == right.filter
+        };
+        for sub in &module_schema.subscriptions {
+            if !current.schema.subscriptions.iter().any(|old| same(old, sub)) {
+                subscribe.extend(remote_table_subscription(self, &module_schema, sub)?);
+            }
+        }
+        let unsubscribe = current
+            .schema
+            .subscriptions
             .iter()
-            .filter(|binding| {
-                binding.source_node_id == source_node_id
-                    && binding.source_module_name == source_module_name
-                    && binding.source_table_name == source_table_name
+            .filter(|old| !module_schema.subscriptions.iter().any(|sub| same(sub, old)))
+            .filter_map(|old| {
+                remote_table_subscription(self, &current.schema, old)
+                    .ok()
+                    .flatten()
             })
+            .collect::<Vec<_>>();
+        let watched = self
+            .file_watchers
+            .lock()
+            .get(module_name)
+            .map(|watches| watches.iter().map(|watch| watch.event.clone()).collect::<Vec<_>>())
+            .unwrap_or_default();
+        let added_watches = module_schema
+            .subscriptions
+            .iter()
+            .filter(|sub| !watched.contains(&sub.event))
             .cloned()
             .collect::<Vec<_>>();
-
-        for binding in bindings {
-            let update_result = self.apply_transaction(
-                crate::runtime::transaction::Transaction::Update {
-                    module_name: binding.owner_module_name.clone(),
-                    table_name: binding.local_table_name.clone(),
-                    update_row: new_row.clone(),
-                },
-                false,
-                None,
-            );
-
-            if let Err(IntersticeError::RowNotFound { .. }) = update_result {
-                let _ = self.apply_transaction(
-                    crate::runtime::transaction::Transaction::Insert {
-                        module_name: binding.owner_module_name.clone(),
-                        table_name: binding.local_table_name.clone(),
-                        new_row: new_row.clone(),
-                    },
-                    false,
-                    None,
-                );
+        let added_watches = file_watches(self, &module_schema, &added_watches)?;
+
+        let mut tables = current.tables.lock();
+        let mut kept = Vec::new();
+        let mut rebuilt = HashMap::new();
+        for table_schema in &module_schema.tables {
+            if let Some(table) = migrated.remove(&table_schema.name) {
+                rebuilt.insert(table_schema.name.clone(), table);
+                continue;
+            }
+            let unchanged = tables.get(&table_schema.name).filter(|table| {
+                table.schema.persistence == table_schema.persistence
+                    && table_schema.same_layout(&table.schema)
+            });
+            match unchanged {
+                Some(table) if same_table_structure(&table.schema, table_schema) => {
+                    kept.push(table_schema.clone());
+                }
+                Some(table) => {
+                    // Same rows, but indexes or quotas changed: reindex them.
+                    let mut new_table = Table::new(table_schema.clone());
+                    for row in table.scan() {
+                        new_table.insert(row.clone())?;
+                    }
+                    rebuilt.insert(table_schema.name.clone(), new_table);
+                }
+                None => {
+                    let mut new_table = Table::new(table_schema.clone());
+                    if table_schema.persistence != PersistenceKind::Ephemeral {
+                        self.persistence.restore_table(module_name, &mut new_table)?;
+                    }
+                    rebuilt.insert(table_schema.name.clone(), new_table);
+                }
             }
-
-            self.emit_replica_sync_event_if_needed(&binding);
         }
-    }
+        // A staged migration writes the new table layouts when committed.
+        self.save_module(&module, staged.is_none())?;
+        if let Some(staged) = staged {
+            self.persistence.commit_migration(module_name, staged)?;
+        }
 
-    pub(crate) fn apply_replica_delete(
-        &self,
-        source_node_id: crate::node::NodeId,
-        source_module_name: &str,
-        source_table_name: &str,
-        deleted_row: interstice_abi::Row,
-    ) {
-        let bindings = self
-            .replica_bindings
+        // From here on nothing fails: swap everything in.
+        let mut next_tables = rebuilt;
+        for table_schema in kept {
+            if let Some(mut table) = tables.remove(&table_schema.name) {
+                table.schema = table_schema;
+                next_tables.insert(table.schema.name.clone(), table);
+            }
+        }
+        // Replica tables are not part of the schema and are carried over as they are.
+        next_tables.extend(tables.drain().filter(|(name, _)| name.starts_with("__replica__")));
+        *tables = next_tables;
+        drop(tables);
+        // Share the table map, so a nested call still running on the old instances
+        // writes to the same tables.
+        module.tables = current.tables.clone();
+
+        self.authority_modules
             .lock()
-            
-            .iter()
-            .filter(|binding| {
-                binding.source_node_id == source_node_id
-                    && binding.source_module_name == source_module_name
-                    && binding.source_table_name == source_table_name
-            })
-            .cloned()
-            .collect::<Vec<_>>();
+            .retain(|_, entry| entry.module_name() != module_name);
+        for authority in &module_schema.authorities {
+            self.authority_modules
+                .lock()
+                .insert(authority.clone(), authority_entry(authority, &module_schema));
+        }
 
-        for binding in bindings {
-            if let Ok(primary_key) = TryInto::<IndexKey>::try_into(deleted_row.primary_key.clone())
-            {
-                let _ = self.apply_transaction(
-                    crate::runtime::transaction::Transaction::Delete {
-                        module_name: binding.owner_module_name.clone(),
-                        table_name: binding.local_table_name.clone(),
-                        deleted_row_id: primary_key,
-                    },
-                    false,
-                    None,
-                );
+        for (node_id, request) in unsubscribe {
+            self.network_handle
+                .send_packet(node_id, NetworkPacket::RequestUnsubscription(request));
+        }
+        for (node_id, request) in subscribe {
+            self.network_handle
+                .send_packet(node_id, NetworkPacket::RequestSubscription(request));
+        }
+        self.active_subscription_count.fetch_add(
+            table_subscription_count(&module_schema) - table_subscription_count(&current.schema),
+            std::sync::atomic::Ordering::Relaxed,
+        );
+        // Stop watching the paths the new version no longer subscribes to.
+        {
+            let mut file_watchers = self.file_watchers.lock();
+            let watches = file_watchers.entry(module_name.clone()).or_default();
+            watches.retain(|watch| {
+                module_schema
+                    .subscriptions
+                    .iter()
+                    .any(|sub| sub.event == watch.event)
+            });
+            watches.extend(added_watches);
+            if watches.is_empty() {
+                file_watchers.remove(module_name);
             }
+        }
+
+        self.modules
+            .lock()
+            .insert(module_name.clone(), Arc::new(module));
+        self.clear_reducer_access_cache();
+        Ok(())
+    }
 
-            self.emit_replica_sync_event_if_needed(&binding);
+    /// Write the module binary, its table layouts (unless `write_layouts` is off)
+    /// and its `state.toml` to its data dir.
+    fn save_module(&self, module: &Module, write_layouts: bool) -> Result<(), IntersticeError> {
+        let Some(modules_path) = &self.modules_path else {
+            return Ok(());
+        };
+        let module_schema = &module.schema;
+        let module_dir = modules_path.join(&module_schema.name);
+        std::fs::create_dir_all(&module_dir).unwrap();
+        std::fs::create_dir_all(module_dir.join("logs")).unwrap();
+        std::fs::create_dir_all(module_dir.join("snapshots")).unwrap();
+        std::fs::write(module_dir.join("module.wasm"), &module.wasm_bytes).unwrap();
+        if write_layouts {
+            self.persistence
+                .write_table_layouts(&module_schema.name, &module_schema.tables)?;
         }
+        // Mark the module as loaded so it is auto-loaded again on node startup.
+        write_module_state(
+            &module_dir,
+            &ModuleState {
+                loaded: true,
+                version: Some(module_schema.version.clone().into()),
+            },
+        );
+        Ok(())
     }
 
-    pub(crate) fn apply_replica_full_sync(
+    pub(crate) async fn request_node_schema(
         &self,
-        source_node_id: crate::node::NodeId,
-        source_module_name: &str,
-        source_table_name: &str,
-        rows: Vec<interstice_abi::Row>,
-    ) {
-        let bindings = self
-            .replica_bindings
+        node_id: crate::node::NodeId,
+        node_name: String,
+    ) -> Result<NodeSchema, IntersticeError> {
+        let request_id = Uuid::new_v4().to_string();
+        let (sender, receiver) = tokio::sync::oneshot::channel();
+        self.pending_schema_responses
             .lock()
             
-            .iter()
-            .filter(|binding| {
-                binding.source_node_id == source_node_id
-                    && binding.source_module_name == source_module_name
-                    && binding.source_table_name == source_table_name
-            })
-            .cloned()
-            .collect::<Vec<_>>();
+            .insert(request_id.clone(), sender);
 
-        for binding in bindings {
-            let mut restored = false;
-            if let Some(module) = self.modules.lock().get(&binding.owner_module_name) {
-                if let Some(table) = module
-                    .tables
+        self.network_handle.send_packet(
+            node_id,
+            NetworkPacket::SchemaRequest {
+                request_id: request_id.clone(),
+                node_name,
+            },
+        );
+
+        match timeout(Duration::from_secs(10), receiver).await {
+            Ok(Ok(schema)) => Ok(schema),
+            Ok(Err(_)) => Err(IntersticeError::Internal(
+                "Schema request channel closed unexpectedly".into(),
+            )),
+            Err(_) => {
+                self.pending_schema_responses
                     .lock()
                     
-                    .get_mut(&binding.local_table_name)
-                {
-                    let _ = table.restore_from_rows(rows.clone());
-                    restored = true;
-                }
-            }
-
-            if restored {
-                self.emit_replica_sync_event_if_needed(&binding);
+                    .remove(&request_id);
+                Err(IntersticeError::Internal(
+                    "Timed out while waiting for remote schema response".into(),
+                ))
             }
         }
     }
 
-    fn emit_replica_sync_event_if_needed(&self, binding: &crate::runtime::ReplicaBinding) {
-        let key = format!(
-            "{}|{}|{}|{}",
-            binding.owner_module_name,
-            binding.source_node_name,
-            binding.source_module_name,
-            binding.source_table_name
-        );
-
-        let should_emit = self.emitted_replica_sync_events.lock().insert(key);
-
-        if should_emit {
-            let _ = self.event_sender.send((EventInstance::ReplicaTableSynced {
-                node_name: binding.source_node_name.clone(),
-                module_name: binding.source_module_name.clone(),
-                table_name: binding.source_table_name.clone(),
-            }, None));
-        }
-    }
-
     /// Tear down a module's live runtime presence: unsubscribe from remote
     /// tables, drop its subscription counts, remove it from the modules map,
     /// clear caches and replica bindings, and release any authorities it held.
@@ -893,6 +1056,7 @@ impl Runtime {
                                         module_name: source_module_name.clone(),
                                         table_name: source_table_name.clone(),
                                         event,
+                                        filter: sub.filter.clone(),
                                     }),
                                 );
                             }
@@ -919,6 +1083,7 @@ impl Runtime {
                                     module_name: replicated.module_name.clone(),
                                     table_name: replicated.table_name.clone(),
                                     event,
+                                    filter: None,
                                 }),
                             );
                         }
@@ -929,16 +1094,7 @@ impl Runtime {
 
         // Subtract table subscriptions from active_subscription_count
         if let Some(schema) = &module_schema {
-            let table_sub_count = schema
-                .subscriptions
-                .iter()
-                .filter(|s| matches!(
-                    s.event,
-                    SubscriptionEventSchema::Insert { .. }
-                        | SubscriptionEventSchema::Update { .. }
-                        | SubscriptionEventSchema::Delete { .. }
-                ))
-                .count() as i32;
+            let table_sub_count = table_subscription_count(schema);
             if table_sub_count > 0 {
                 runtime
                     .active_subscription_count
@@ -947,17 +1103,17 @@ impl Runtime {
         }
 
         runtime.modules.lock().remove(module_name);
+        runtime.file_watchers.lock().remove(module_name);
         runtime.clear_reducer_access_cache();
+        runtime.drop_schedules(module_name);
+        runtime.drop_timeline(module_name);
+        runtime.stop_ticks(module_name);
         runtime
             .replica_bindings
             .lock()
             
             .retain(|binding| binding.owner_module_name != module_name);
-        runtime
-            .emitted_replica_sync_events
-            .lock()
-            
-            .retain(|key| !key.starts_with(&format!("{}|", module_name)));
+        runtime.reset_changelogs(module_name);
 
         // Removing module from authority modules if it has any authority
         let authorities_to_remove = runtime
@@ -979,11 +1135,21 @@ impl Runtime {
     /// module is marked unloaded in its `state.toml` so node startup skips it.
     pub fn unload_module(runtime: Arc<Runtime>, module_name: &str) {
         Self::teardown_module(&runtime, module_name);
+        runtime.record(RecordingEntry::ModuleUnloaded {
+            module_name: module_name.to_string(),
+        });
 
         if let Some(modules_path) = &runtime.modules_path {
             let module_dir = modules_path.join(module_name);
             if module_dir.exists() {
-                write_module_state(&module_dir, false);
+                let version = read_module_state(&module_dir).and_then(|state| state.version);
+                write_module_state(
+                    &module_dir,
+                    &ModuleState {
+                        loaded: false,
+                        version,
+                    },
+                );
             }
         }
 
@@ -997,6 +1163,10 @@ impl Runtime {
     /// Remove a module and delete all of its persisted data (full uninstall).
     pub fn remove_module(runtime: Arc<Runtime>, module_name: &str) {
         Self::teardown_module(&runtime, module_name);
+        runtime.record(RecordingEntry::ModuleUnloaded {
+            module_name: module_name.to_string(),
+        });
+        runtime.persistence.forget_module(module_name);
 
         if let Some(modules_path) = &runtime.modules_path {
             let module_dir = modules_path.join(module_name);
@@ -1018,42 +1188,207 @@ impl Runtime {
 struct ModuleState {
     /// Whether the module should be auto-loaded when the node starts.
     loaded: bool,
+    /// Version of the module that last wrote the data dir.
+    #[serde(default)]
+    version: Option<String>,
 }
 
 /// Write `state.toml` into the given module directory.
-fn write_module_state(module_dir: &Path, loaded: bool) {
-    if let Ok(contents) = toml::to_string(&ModuleState { loaded }) {
+fn write_module_state(module_dir: &Path, state: &ModuleState) {
+    if let Ok(contents) = toml::to_string(state) {
         let _ = std::fs::write(module_dir.join("state.toml"), contents);
     }
 }
 
+fn read_module_state(module_dir: &Path) -> Option<ModuleState> {
+    std::fs::read_to_string(module_dir.join("state.toml"))
+        .ok()
+        .and_then(|contents| toml::from_str(&contents).ok())
+}
+
 /// Whether the module in `module_dir` should be auto-loaded on node startup.
 /// Defaults to `true` when no `state.toml` is present (backward compatible).
 pub(crate) fn module_should_load(module_dir: &Path) -> bool {
-    match std::fs::read_to_string(module_dir.join("state.toml")) {
-        Ok(contents) => toml::from_str::<ModuleState>(&contents)
-            .map(|state| state.loaded)
-            .unwrap_or(true),
-        Err(_) => true,
+    read_module_state(module_dir)
+        .map(|state| state.loaded)
+        .unwrap_or(true)
+}
+
+/// Number of table event subscriptions (Insert/Update/Delete) of a module.
+fn table_subscription_count(module_schema: &ModuleSchema) -> i32 {
+    module_schema
+        .subscriptions
+        .iter()
+        .filter(|s| matches!(
+            s.event,
+            SubscriptionEventSchema::Insert { .. }
+                | SubscriptionEventSchema::Update { .. }
+                | SubscriptionEventSchema::Delete { .. }
+        ))
+        .count() as i32
+}
+
+/// Whether a table with the same layout can keep its in-memory state as-is:
+/// same indexes, auto-increment setting and quotas.
+fn same_table_structure(current: &TableSchema, upgraded: &TableSchema) -> bool {
+    current.primary_key_auto_inc == upgraded.primary_key_auto_inc
+        && current.limits == upgraded.limits
+        && current.indexes.len() == upgraded.indexes.len()
+        && current.indexes.iter().zip(upgraded.indexes.iter()).all(|(a, b)| {
+            a.field_name == b.field_name
+                && a.fields == b.fields
+                && a.index_type == b.index_type
+                && a.unique == b.unique
+                && a.auto_inc == b.auto_inc
+        })
+}
+
+/// Check that `provider` satisfies a dependency of `module_name`: its version
+/// matches the requirement and it still exposes the bound public schema.
+fn check_dependency(
+    module_name: &str,
+    dependency: &ModuleDependency,
+    provider: &ModuleSchema,
+) -> Result<(), IntersticeError> {
+    if !dependency.version.matches(&provider.version) {
+        return Err(IntersticeError::ModuleVersionMismatch(
+            module_name.to_string(),
+            provider.name.clone(),
+            dependency.version.clone(),
+            provider.version.clone(),
+        ));
     }
+    if let Some(bound_schema) = &dependency.bound_schema {
+        provider
+            .check_compatible(bound_schema)
+            .map_err(|reason| IntersticeError::IncompatibleDependency {
+                module: module_name.to_string(),
+                dependency: provider.name.clone(),
+                reason,
+            })?;
+    }
+    Ok(())
 }
 
-fn setup_file_watches(
-    runtime: Arc<Runtime>,
+fn authority_entry(authority: &Authority, module_schema: &ModuleSchema) -> AuthorityEntry {
+    let module_name = module_schema.name.clone();
+    let subscriber = |event: SubscriptionEventSchema| {
+        module_schema
+            .subscriptions
+            .iter()
+            .find(|sub| sub.event == event)
+            .map(|sub| sub.reducer_name.clone())
+    };
+    match authority {
+        Authority::Gpu => AuthorityEntry::Gpu {
+            module_name,
+            render_reducer: subscriber(SubscriptionEventSchema::Render),
+        },
+        Authority::Audio => AuthorityEntry::Audio {
+            module_name,
+            output_reducer: subscriber(SubscriptionEventSchema::AudioOutput),
+            input_reducer: subscriber(SubscriptionEventSchema::AudioInput),
+        },
+        Authority::Input => AuthorityEntry::Input {
+            module_name,
+            input_reducer: subscriber(SubscriptionEventSchema::Input),
+        },
+        Authority::File => AuthorityEntry::File { module_name },
+        Authority::Module => AuthorityEntry::Module { module_name },
+        Authority::Network => AuthorityEntry::Network {
+            module_name,
+            on_event_reducer: subscriber(SubscriptionEventSchema::Network),
+        },
+    }
+}
+
+/// Subscription request to send to the remote node for a table event subscription
+/// on another node, or `None` for local events.
+pub(crate) fn remote_table_subscription(
+    runtime: &Runtime,
     module_schema: &ModuleSchema,
-) -> Result<(), IntersticeError> {
+    subscription: &SubscriptionSchema,
+) -> Result<Option<(NodeId, RequestSubscription)>, IntersticeError> {
+    let (node_name, module_name, table_name, table_event) = match subscription.event.clone() {
+        SubscriptionEventSchema::Insert {
+            node_selection: NodeSelection::Other(node_name),
+            module_name,
+            table_name,
+        } => (node_name, module_name, table_name, TableEvent::Insert),
+        SubscriptionEventSchema::Update {
+            node_selection: NodeSelection::Other(node_name),
+            module_name,
+            table_name,
+        } => (node_name, module_name, table_name, TableEvent::Update),
+        SubscriptionEventSchema::Delete {
+            node_selection: NodeSelection::Other(node_name),
+            module_name,
+            table_name,
+        } => (node_name, module_name, table_name, TableEvent::Delete),
+        _ if subscription.filter.is_some() => {
+            return Err(IntersticeError::Internal(format!(
+                "Subscription of reducer '{}' has a filter, which is only supported on remote table events",
+                subscription.reducer_name
+            )));
+        }
+        _ => return Ok(None),
+    };
+    let node_adress = module_schema
+        .node_dependencies
+        .iter()
+        .find(|n| n.name == node_name)
+        .ok_or(IntersticeError::Internal(format!(
+            "Couldn't find node {node_name} in the node dependencies"
+        )))?
+        .address
+        .clone();
+    let node_id = runtime.network_handle.get_node_id_from_adress(&node_adress)?;
+    Ok(Some((
+        node_id,
+        RequestSubscription {
+            module_name,
+            table_name,
+            event: table_event,
+            filter: subscription.filter.clone(),
+        },
+    )))
+}
+
+/// Start watching the paths of the file `subscriptions` of a module, without
+/// registering the watches yet.
+fn file_watches(
+    runtime: &Runtime,
+    module_schema: &ModuleSchema,
+    subscriptions: &[SubscriptionSchema],
+) -> Result<Vec<FileWatch>, IntersticeError> {
     if !module_schema
         .authorities
         .iter()
         .any(|a| *a == Authority::File)
     {
-        return Ok(());
+        return Ok(Vec::new());
     }
 
-    let mut watchers_for_module = Vec::new();
+    let module_name = module_schema.name.clone();
+    let mut watches = Vec::new();
 
-    for sub in &module_schema.subscriptions {
+    for sub in subscriptions {
         if let SubscriptionEventSchema::File { path, recursive } = &sub.event {
+            // Watches go through the same sandbox as file host calls, and report
+            // virtual paths back to the module.
+            let watch_path = runtime
+                .file_sandbox
+                .resolve(&module_name, path, FileAccess::Read)
+                .map_err(|err| {
+                    IntersticeError::Internal(format!("Cannot watch '{}': {}", path, err))
+                })?;
+            let sandbox = runtime.file_sandbox.clone();
+            let watcher_module_name = module_name.clone();
+            // Host paths that do not map back into the sandbox are dropped rather
+            // than leaked to the module.
+            let to_virtual = move |p: &std::path::PathBuf| {
+                sandbox.to_virtual(&watcher_module_name, p)
+            };
             let event_sender = runtime.event_sender.clone();
             let logger = runtime.logger.clone();
 
@@ -1062,35 +1397,23 @@ fn setup_file_watches(
                     match res {
                         Ok(event) => {
                             let mut events = Vec::new();
+                            let paths = || event.paths.iter().filter_map(&to_virtual);
                             match event.kind {
                                 notify::EventKind::Create(_) => {
-                                    for p in &event.paths {
-                                        events.push(FileEvent::Created {
-                                            path: p.to_string_lossy().to_string(),
-                                        });
-                                    }
+                                    events.extend(paths().map(|path| FileEvent::Created { path }));
                                 }
                                 notify::EventKind::Modify(notify::event::ModifyKind::Name(_)) => {
-                                    if event.paths.len() >= 2 {
-                                        events.push(FileEvent::Renamed {
-                                            from: event.paths[0].to_string_lossy().to_string(),
-                                            to: event.paths[1].to_string_lossy().to_string(),
-                                        });
+                                    if let [from, to, ..] = event.paths.as_slice()
+                                        && let (Some(from), Some(to)) = (to_virtual(from), to_virtual(to))
+                                    {
+                                        events.push(FileEvent::Renamed { from, to });
                                     }
                                 }
                                 notify::EventKind::Modify(_) => {
-                                    for p in &event.paths {
-                                        events.push(FileEvent::Modified {
-                                            path: p.to_string_lossy().to_string(),
-                                        });
-                                    }
+                                    events.extend(paths().map(|path| FileEvent::Modified { path }));
                                 }
                                 notify::EventKind::Remove(_) => {
-                                    for p in &event.paths {
-                                        events.push(FileEvent::Deleted {
-                                            path: p.to_string_lossy().to_string(),
-                                        });
-                                    }
+                                    events.extend(paths().map(|path| FileEvent::Deleted { path }));
                                 }
                                 _ => {}
                             }
@@ -1118,21 +1441,16 @@ fn setup_file_watches(
                 RecursiveMode::NonRecursive
             };
 
-            watcher.watch(Path::new(path), mode).map_err(|err| {
+            watcher.watch(&watch_path, mode).map_err(|err| {
                 IntersticeError::Internal(format!("Failed to watch path: {}", err))
             })?;
 
-            watchers_for_module.push(watcher);
+            watches.push(FileWatch {
+                event: sub.event.clone(),
+                _watcher: watcher,
+            });
         }
     }
 
-    if !watchers_for_module.is_empty() {
-        runtime
-            .file_watchers
-            .lock()
-            
-            .extend(watchers_for_module);
-    }
-
-    Ok(())
+    Ok(watches)
 }
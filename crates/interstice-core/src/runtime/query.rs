@@ -15,6 +15,27 @@ impl Runtime {
         caller_node_id: crate::node::NodeId,
         caller_module_name: &str,
     ) -> Result<IntersticeValue, IntersticeError> {
+        let mut results = self.call_query_batch(
+            module_name,
+            query_name,
+            std::iter::once(args),
+            caller_node_id,
+            caller_module_name,
+        )?;
+        Ok(results.pop().unwrap())
+    }
+
+    /// Call a query once per arguments in `batch`, in a single query frame and on a
+    /// single instance, and return the results in order. The first failing call
+    /// fails the batch.
+    pub(crate) fn call_query_batch<A: Serialize>(
+        &self,
+        module_name: &str,
+        query_name: &str,
+        batch: impl IntoIterator<Item = A>,
+        caller_node_id: crate::node::NodeId,
+        caller_module_name: &str,
+    ) -> Result<Vec<IntersticeValue>, IntersticeError> {
         let module = {
             let modules = self.modules.lock();
             modules
@@ -80,7 +101,12 @@ impl Runtime {
 
         let query_context =
             QueryContext::new(caller_node_id.to_string(), caller_module_name.to_string());
-        let result = module.call_query(query_name, (query_context, args));
+        let result = module.call_queries(
+            query_name,
+            batch
+                .into_iter()
+                .map(|args| (query_context.clone(), args)),
+        );
         CALL_STACK.with(|s| { s.borrow_mut().pop(); });
         result
     }
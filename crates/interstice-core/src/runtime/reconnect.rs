@@ -0,0 +1,110 @@
+//! Reconnection to the nodes loaded modules depend on (`NodeDependency`).
+//!
+//! When such a node disconnects, it is dialed again with an exponential backoff
+//! set by the node's [`NetworkConfig`](crate::network::config::NetworkConfig) until
+//! it is back or no loaded module depends on it anymore. Once it is back, the
+//! module subscriptions to its tables are sent again and the replicas of its
+//! tables catch up. The `connect` and `disconnect` module events fire for every
+//! connection and disconnection, reconnections included.
+
+use crate::{
+    logger::{LogLevel, LogSource},
+    network::protocol::NetworkPacket,
+    node::NodeId,
+    runtime::{Runtime, module::remote_table_subscription},
+};
+use parking_lot::Mutex;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Disconnected nodes being dialed again.
+pub(crate) struct Reconnects {
+    nodes: Mutex<HashSet<NodeId>>,
+}
+
+impl Reconnects {
+    pub fn new() -> Self {
+        Self {
+            nodes: Mutex::new(HashSet::new()),
+        }
+    }
+}
+
+impl Runtime {
+    pub(crate) fn handle_node_disconnect(self: &Arc<Self>, node_id: NodeId) {
+        self.disconnect_replicas(node_id);
+        self.abandon_reducer_calls(node_id);
+        if self.dependency_address(node_id).is_some() && self.reconnects.nodes.lock().insert(node_id)
+        {
+            self.tokio_handle.spawn(Runtime::reconnect(self.clone(), node_id));
+        }
+    }
+
+    pub(crate) fn handle_node_connect(&self, node_id: NodeId) {
+        if !self.reconnects.nodes.lock().remove(&node_id) {
+            return;
+        }
+        self.logger.log(
+            &format!("Node {} is back, subscribing again to its tables", node_id),
+            LogSource::Runtime,
+            LogLevel::Info,
+        );
+
+        let modules: Vec<_> = self.modules.lock().values().cloned().collect();
+        for module in modules {
+            for subscription in &module.schema.subscriptions {
+                if let Ok(Some((subscription_node_id, request))) =
+                    remote_table_subscription(self, &module.schema, subscription)
+                    && subscription_node_id == node_id
+                {
+                    self.network_handle
+                        .send_packet(node_id, NetworkPacket::RequestSubscription(request));
+                }
+            }
+        }
+        self.resync_replicas(node_id);
+    }
+
+    /// Address of `node_id` among the node dependencies of the loaded modules.
+    fn dependency_address(&self, node_id: NodeId) -> Option<String> {
+        let node_name = self.node_names_by_id.lock().get(&node_id).cloned()?;
+        self.modules.lock().values().find_map(|module| {
+            module
+                .schema
+                .node_dependencies
+                .iter()
+                .find(|dependency| dependency.name == node_name)
+                .map(|dependency| dependency.address.clone())
+        })
+    }
+
+    async fn reconnect(runtime: Arc<Runtime>, node_id: NodeId) {
+        let config = runtime.network_handle.config;
+        let mut delay = config.reconnect_min_delay;
+        loop {
+            tokio::time::sleep(delay).await;
+            if !runtime.reconnects.nodes.lock().contains(&node_id) {
+                return;
+            }
+            let Some(address) = runtime.dependency_address(node_id) else {
+                runtime.reconnects.nodes.lock().remove(&node_id);
+                return;
+            };
+            match runtime.network_handle.clone().connect_to_peer(address.clone()).await {
+                Ok(()) => return,
+                Err(err) => runtime.logger.log(
+                    &format!(
+                        "Reconnecting to node {} at {} failed, retrying in {:?}: {}",
+                        node_id,
+                        address,
+                        config.next_reconnect_delay(delay),
+                        err
+                    ),
+                    LogSource::Network,
+                    LogLevel::Warning,
+                ),
+            }
+            delay = config.next_reconnect_delay(delay);
+        }
+    }
+}
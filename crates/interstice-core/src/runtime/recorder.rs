@@ -0,0 +1,371 @@
+//! Recording of the inputs of a node, replayed by [`crate::recording::replay`].
+//!
+//! A recording is a sequence of entries, each a little-endian `u32` length
+//! followed by the postcard-encoded [`RecordingEntry`], like the table logs. It
+//! holds the modules the node runs with the rows they started from, and every
+//! input job with the logical time and random seed it ran with. The jobs those
+//! inputs trigger on the node are run again by the replay, so only the time each
+//! of them ran at is recorded.
+
+use crate::{
+    error::IntersticeError,
+    logger::{LogLevel, LogSource},
+    runtime::{
+        Runtime,
+        deterministic_random::{FNV_OFFSET, fnv1a},
+        module::Module,
+    },
+};
+use interstice_abi::{IntersticeValue, Row, decode, encode};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Rows of a module's tables, by table name.
+pub(crate) type TablesSnapshot = Vec<(String, Vec<Row>)>;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) enum RecordingEntry {
+    Start {
+        node_id: String,
+        unix_ms: u64,
+    },
+    /// `restored_tables` is set when the module was loaded with existing data,
+    /// in which case its `init` reducers did not run.
+    ModuleLoaded {
+        unix_ms: u64,
+        wasm_binary: Vec<u8>,
+        restored_tables: Option<TablesSnapshot>,
+    },
+    ModuleUpgraded {
+        unix_ms: u64,
+        wasm_binary: Vec<u8>,
+        tables: TablesSnapshot,
+    },
+    ModuleUnloaded {
+        module_name: String,
+    },
+    Job {
+        unix_ms: u64,
+        call_sequence: u64,
+        rng_seed: u64,
+        caller_node_id: String,
+        caller_module_name: String,
+        module_name: String,
+        reducer_name: String,
+        input: IntersticeValue,
+    },
+    /// A job triggered on the node, run at `unix_ms`.
+    InternalJob {
+        unix_ms: u64,
+        module_name: String,
+        reducer_name: String,
+    },
+    /// Hash of the table state when the recording was finished.
+    Finished {
+        state_hash: u64,
+    },
+}
+
+/// Recording the runtime writes its inputs to, when one is started.
+pub(crate) struct Recorder {
+    writer: Mutex<Option<BufWriter<File>>>,
+    /// Times of the recorded internal jobs, by module and reducer, handed out
+    /// in order to the same jobs when replaying.
+    replayed_times: Mutex<HashMap<(String, String), VecDeque<u64>>>,
+}
+
+impl Recorder {
+    pub fn new() -> Self {
+        Self {
+            writer: Mutex::new(None),
+            replayed_times: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.writer.lock().is_some()
+    }
+
+    /// Run the internal jobs of a replay at the times they were recorded with.
+    pub fn replay_internal_jobs(&self, entries: &[RecordingEntry]) {
+        let mut replayed_times = self.replayed_times.lock();
+        for entry in entries {
+            if let RecordingEntry::InternalJob {
+                unix_ms,
+                module_name,
+                reducer_name,
+            } = entry
+            {
+                replayed_times
+                    .entry((module_name.clone(), reducer_name.clone()))
+                    .or_default()
+                    .push_back(*unix_ms);
+            }
+        }
+    }
+
+    fn replayed_time(&self, module_name: &str, reducer_name: &str) -> Option<u64> {
+        self.replayed_times
+            .lock()
+            .get_mut(&(module_name.to_string(), reducer_name.to_string()))
+            .and_then(|times| times.pop_front())
+    }
+}
+
+fn write_entry(writer: &mut impl Write, entry: &RecordingEntry) -> Result<(), IntersticeError> {
+    let encoded = encode(entry).map_err(|err| {
+        IntersticeError::Internal(format!("Failed to encode recording entry: {err}"))
+    })?;
+    writer
+        .write_all(&(encoded.len() as u32).to_le_bytes())
+        .and_then(|_| writer.write_all(&encoded))
+        .map_err(|err| IntersticeError::Internal(format!("Failed to write recording entry: {err}")))
+}
+
+pub(crate) fn read_entries(path: &Path) -> Result<Vec<RecordingEntry>, IntersticeError> {
+    let file = File::open(path).map_err(|err| {
+        IntersticeError::Internal(format!("Failed to open recording {:?}: {}", path, err))
+    })?;
+    let mut reader = BufReader::new(file);
+    let mut entries = Vec::new();
+    loop {
+        let mut len_buf = [0u8; 4];
+        if reader.read_exact(&mut len_buf).is_err() {
+            break;
+        }
+        // The length is read back from the file: only allocate what it holds.
+        let len = u32::from_le_bytes(len_buf) as usize;
+        let mut buffer = Vec::new();
+        // An entry cut short by a crash ends the recording.
+        match (&mut reader).take(len as u64).read_to_end(&mut buffer) {
+            Ok(read) if read == len => {}
+            _ => break,
+        }
+        let entry = decode(&buffer).map_err(|err| {
+            IntersticeError::Internal(format!("Failed to decode recording entry: {err}"))
+        })?;
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+/// Rows of the module's own tables, sorted by table name. Replicas are left out:
+/// they mirror tables of other nodes, which a replay does not connect to.
+pub(crate) fn tables_snapshot(module: &Module) -> TablesSnapshot {
+    let mut tables = module
+        .tables
+        .lock()
+        .iter()
+        .filter(|(name, _)| !name.starts_with("__replica__"))
+        .map(|(name, table)| (name.clone(), table.snapshot_rows()))
+        .collect::<Vec<_>>();
+    tables.sort_by(|a, b| a.0.cmp(&b.0));
+    tables
+}
+
+/// Hash of the rows of every module, independent of the order rows were
+/// inserted in.
+pub(crate) fn state_hash(modules: &HashMap<String, Arc<Module>>) -> u64 {
+    let mut names = modules.keys().collect::<Vec<_>>();
+    names.sort();
+    let mut hash = FNV_OFFSET;
+    for name in names {
+        hash = fnv1a(hash, name.as_bytes());
+        for (table_name, rows) in tables_snapshot(&modules[name]) {
+            hash = fnv1a(hash, table_name.as_bytes());
+            hash = fnv1a(hash, &(rows.len() as u64).to_le_bytes());
+            let mut encoded = rows
+                .iter()
+                .map(|row| encode(row).unwrap_or_default())
+                .collect::<Vec<_>>();
+            encoded.sort();
+            for row in encoded {
+                hash = fnv1a(hash, &(row.len() as u64).to_le_bytes());
+                hash = fnv1a(hash, &row);
+            }
+        }
+    }
+    hash
+}
+
+/// Loaded modules ordered so that each one comes after the modules it depends on.
+fn modules_in_load_order(modules: &HashMap<String, Arc<Module>>) -> Vec<Arc<Module>> {
+    let mut remaining = modules.values().cloned().collect::<Vec<_>>();
+    remaining.sort_by(|a, b| a.schema.name.cmp(&b.schema.name));
+    let mut ordered: Vec<Arc<Module>> = Vec::new();
+    while !remaining.is_empty() {
+        let ready = remaining.iter().position(|module| {
+            module.schema.module_dependencies.iter().all(|dependency| {
+                ordered
+                    .iter()
+                    .any(|loaded| loaded.schema.name == dependency.module_name)
+            })
+        });
+        // Dependencies are checked on load, so a module is always ready unless
+        // the map changed under us; keep the rest in name order then.
+        ordered.push(remaining.remove(ready.unwrap_or(0)));
+    }
+    ordered
+}
+
+impl Runtime {
+    /// Start writing the inputs of the node to `path`. The modules already
+    /// loaded are recorded with their current rows.
+    pub(crate) fn start_recording(&self, path: &Path) -> Result<(), IntersticeError> {
+        let mut writer = self.recorder.writer.lock();
+        if writer.is_some() {
+            return Err(IntersticeError::Internal(
+                "The node is already being recorded".into(),
+            ));
+        }
+        let file = File::create(path).map_err(|err| {
+            IntersticeError::Internal(format!("Failed to create recording {:?}: {}", path, err))
+        })?;
+        let mut file = BufWriter::new(file);
+        let unix_ms = self.clock.now_unix_ms();
+        write_entry(
+            &mut file,
+            &RecordingEntry::Start {
+                node_id: self.node_id.to_string(),
+                unix_ms,
+            },
+        )?;
+        let modules = self.modules.lock().clone();
+        for module in modules_in_load_order(&modules) {
+            write_entry(
+                &mut file,
+                &RecordingEntry::ModuleLoaded {
+                    unix_ms,
+                    wasm_binary: module.wasm_bytes.clone(),
+                    restored_tables: Some(tables_snapshot(&module)),
+                },
+            )?;
+        }
+        *writer = Some(file);
+        Ok(())
+    }
+
+    /// Time an internal job runs at: the recorded one when replaying, the
+    /// current one when recording, and `None` otherwise, leaving the job on the
+    /// live clock.
+    pub(crate) fn internal_job_unix_ms(&self, module_name: &str, reducer_name: &str) -> Option<u64> {
+        self.recorder
+            .replayed_time(module_name, reducer_name)
+            .or_else(|| self.recorder.is_active().then(|| self.clock.now_unix_ms()))
+    }
+
+    /// Write an entry to the recording, if one is running.
+    pub(crate) fn record(&self, entry: RecordingEntry) {
+        let mut writer = self.recorder.writer.lock();
+        let Some(file) = writer.as_mut() else {
+            return;
+        };
+        let written = write_entry(file, &entry).and_then(|_| {
+            file.flush().map_err(|err| {
+                IntersticeError::Internal(format!("Failed to write recording: {err}"))
+            })
+        });
+        if let Err(err) = written {
+            self.logger.log(
+                &format!("Stopped recording the node: {}", err),
+                LogSource::Runtime,
+                LogLevel::Error,
+            );
+            *writer = None;
+        }
+    }
+
+    /// Stop the recording, ending it with the current state hash, and return the
+    /// hash.
+    pub(crate) fn finish_recording(&self) -> Result<u64, IntersticeError> {
+        let mut file = self.recorder.writer.lock().take().ok_or_else(|| {
+            IntersticeError::Internal("The node is not being recorded".into())
+        })?;
+        let state_hash = self.state_hash();
+        write_entry(&mut file, &RecordingEntry::Finished { state_hash })?;
+        file.flush().map_err(|err| {
+            IntersticeError::Internal(format!("Failed to write recording: {err}"))
+        })?;
+        Ok(state_hash)
+    }
+
+    pub(crate) fn state_hash(&self) -> u64 {
+        let modules = self.modules.lock().clone();
+        state_hash(&modules)
+    }
+
+    /// Replace the rows of a module's tables with the snapshot ones.
+    pub(crate) fn restore_tables(
+        &self,
+        module_name: &str,
+        snapshot: TablesSnapshot,
+    ) -> Result<(), IntersticeError> {
+        let module = self.modules.lock().get(module_name).cloned().ok_or_else(|| {
+            IntersticeError::ModuleNotFound(
+                module_name.to_string(),
+                "When restoring recorded tables".into(),
+            )
+        })?;
+        let mut tables = module.tables.lock();
+        for (table_name, rows) in snapshot {
+            if let Some(table) = tables.get_mut(&table_name) {
+                table.restore_from_rows(rows)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_round_trip_through_a_file() {
+        let path = std::env::temp_dir().join(format!("recording-{}.bin", uuid::Uuid::new_v4()));
+        {
+            let mut file = BufWriter::new(File::create(&path).unwrap());
+            write_entry(
+                &mut file,
+                &RecordingEntry::Start {
+                    node_id: "node".into(),
+                    unix_ms: 42,
+                },
+            )
+            .unwrap();
+            write_entry(&mut file, &RecordingEntry::Finished { state_hash: 7 }).unwrap();
+        }
+        let entries = read_entries(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(
+            entries.as_slice(),
+            [
+                RecordingEntry::Start { unix_ms: 42, .. },
+                RecordingEntry::Finished { state_hash: 7 }
+            ]
+        ));
+    }
+
+    #[test]
+    fn entries_cut_short_end_the_recording() {
+        let path = std::env::temp_dir().join(format!("recording-{}.bin", uuid::Uuid::new_v4()));
+        {
+            let mut file = BufWriter::new(File::create(&path).unwrap());
+            write_entry(&mut file, &RecordingEntry::Finished { state_hash: 7 }).unwrap();
+            // A length far past the end of the file.
+            file.write_all(&u32::MAX.to_le_bytes()).unwrap();
+            file.write_all(&[0, 1, 2]).unwrap();
+        }
+        let entries = read_entries(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(
+            entries.as_slice(),
+            [RecordingEntry::Finished { state_hash: 7 }]
+        ));
+    }
+}
@@ -1,11 +1,16 @@
 use crate::{
     error::IntersticeError,
     runtime::transaction::Transaction,
-    runtime::{Runtime, module::Module, table::TableAutoIncSnapshot},
+    runtime::{
+        Runtime,
+        event::EventInstance,
+        module::Module,
+        table::{PendingTableUsage, TableAutoIncSnapshot, TableCursor, rewind_auto_inc},
+    },
 };
 use interstice_abi::{IntersticeValue, QuerySchema, RawReducerContext as ReducerContext, ReducerTableRef};
 use serde::Serialize;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::collections::{HashMap, HashSet};
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
@@ -20,6 +25,57 @@ thread_local! {
     /// reducer dispatches downstream events, each new job gets a `fork()` so the
     /// render-done signal is held open until the full cascade completes.
     pub(crate) static ACTIVE_COMPLETION: RefCell<Option<CompletionToken>> = RefCell::new(None);
+
+    /// Open atomic transaction of the `#[reducer(atomic)]` call running on this
+    /// thread, if any. Nested local reducer calls hand their transactions to it
+    /// instead of committing on return.
+    static ATOMIC_TRANSACTION: RefCell<Option<AtomicTransaction>> = const { RefCell::new(None) };
+
+    /// Logical time of the job running on this thread while it is recorded, read
+    /// by the `time` host call instead of the clock so that a replay sees it too.
+    pub(crate) static JOB_UNIX_MS: Cell<Option<u64>> = const { Cell::new(None) };
+}
+
+/// Buffered writes of an atomic reducer call and its nested local calls.
+#[derive(Default)]
+struct AtomicTransaction {
+    /// Per-module transaction batches, in execution order.
+    segments: Vec<(Arc<Module>, Vec<Transaction>)>,
+    /// Set when a nested call failed: the whole transaction is then rolled back.
+    abort_reason: Option<String>,
+    /// Auto-inc snapshots of the calls that returned, whose ids are given back
+    /// when the transaction is rolled back.
+    auto_inc_snapshots: Vec<TableAutoIncSnapshot>,
+}
+
+impl AtomicTransaction {
+    fn push(&mut self, module: &Arc<Module>, transactions: Vec<Transaction>) {
+        if transactions.is_empty() {
+            return;
+        }
+        match self.segments.last_mut() {
+            Some((last, batch)) if Arc::ptr_eq(last, module) => batch.extend(transactions),
+            _ => self.segments.push((module.clone(), transactions)),
+        }
+    }
+}
+
+/// Writes not committed yet of the reducer running on this thread, in execution
+/// order: those an open atomic transaction holds, then the current frame's.
+pub(crate) fn for_each_pending_transaction(mut visit: impl FnMut(&Transaction)) {
+    ATOMIC_TRANSACTION.with(|t| {
+        if let Some(tx) = t.borrow().as_ref() {
+            tx.segments
+                .iter()
+                .flat_map(|(_, batch)| batch)
+                .for_each(&mut visit);
+        }
+    });
+    CALL_STACK.with(|s| {
+        if let Some(frame) = s.borrow().last() {
+            frame.transactions.iter().for_each(&mut visit);
+        }
+    });
 }
 
 #[derive(Debug)]
@@ -31,7 +87,28 @@ pub struct ReducerJob {
     /// Schema name of the calling module, or empty for runtime-originated jobs
     /// (events, render, network). See [`interstice_abi::RawReducerContext`].
     pub caller_module_name: String,
+    /// Set for remote calls awaiting a `ReducerResult`.
+    pub request_id: Option<String>,
     pub completion: Option<CompletionToken>,
+    /// Receives the result of the reducer once it committed or failed.
+    pub outcome: Option<oneshot::Sender<Result<Option<u64>, IntersticeError>>>,
+    pub source: JobSource,
+    /// Seed of the reducer's random numbers, drawn from the call sequence when
+    /// `None`. Set when replaying a recorded job.
+    pub rng_seed: Option<u64>,
+}
+
+/// Where a [`ReducerJob`] originates from. Only inputs are recorded in full: the
+/// jobs they trigger on the node are run again by the replay itself, at the
+/// recorded time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobSource {
+    /// Comes from outside the node's modules: remote calls, schedules, ticks,
+    /// and the input, audio, file, network and remote table events.
+    Input,
+    /// Triggered by the modules themselves: local table events, module startup
+    /// and rendering.
+    Internal,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -48,8 +125,13 @@ pub struct CallFrame {
     pub kind: CallFrameKind,
     pub transactions: Vec<Transaction>,
     pub auto_inc_snapshots: HashMap<String, TableAutoIncSnapshot>,
+    /// Quota usage of the writes queued in `transactions`, per table with a quota.
+    pub(crate) pending_table_usage: HashMap<String, PendingTableUsage>,
     pub rng_state: u64,
     pub table_access: ReducerTableAccess,
+    /// Table scan cursors opened by this call, by cursor id.
+    pub(crate) table_cursors: HashMap<u64, TableCursor>,
+    pub(crate) next_cursor_id: u64,
 }
 
 impl CallFrame {
@@ -68,8 +150,11 @@ impl CallFrame {
             kind,
             transactions: Vec::new(),
             auto_inc_snapshots: HashMap::new(),
+            pending_table_usage: HashMap::new(),
             rng_state,
             table_access,
+            table_cursors: HashMap::new(),
+            next_cursor_id: 1,
         }
     }
 }
@@ -149,6 +234,29 @@ impl Drop for CompletionToken {
 }
 
 impl Runtime {
+    /// Draw the next call sequence number and the random seed of a reducer call
+    /// made with it.
+    pub(crate) fn reducer_seed(
+        &self,
+        caller_node_id: &crate::node::NodeId,
+        module_name: &str,
+        reducer_name: &str,
+    ) -> (u64, u64) {
+        let call_sequence = self
+            .call_sequence
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let rng_seed = crate::runtime::deterministic_random::seed_from_call(
+            caller_node_id,
+            module_name,
+            reducer_name,
+            CallFrameKind::Reducer,
+            call_sequence,
+        );
+        (call_sequence, rng_seed)
+    }
+
+    /// Run a reducer and commit its writes. Returns the commit sequence, or `None`
+    /// when the commit is left to the enclosing atomic transaction.
     pub(crate) fn call_reducer(
         &self,
         module_name: &str,
@@ -156,7 +264,8 @@ impl Runtime {
         args: impl Serialize,
         caller_node_id: crate::node::NodeId,
         caller_module_name: &str,
-    ) -> Result<(), IntersticeError> {
+        rng_seed: Option<u64>,
+    ) -> Result<Option<u64>, IntersticeError> {
         // ── Preamble: module lookup + cycle check + frame push ───────────────
         let module = {
             let mut modules = self.modules.lock();
@@ -202,16 +311,30 @@ impl Runtime {
                 reducer: reducer_name.into(),
             })?;
         let table_access = ReducerTableAccess::from_schema(reducer_schema);
-        let call_sequence = self
-            .call_sequence
-            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-        let rng_seed = crate::runtime::deterministic_random::seed_from_call(
-            &caller_node_id,
-            module_name,
-            reducer_name,
-            CallFrameKind::Reducer,
-            call_sequence,
-        );
+        let rng_seed = rng_seed
+            .unwrap_or_else(|| self.reducer_seed(&caller_node_id, module_name, reducer_name).1);
+
+        // An atomic reducer opens a transaction unless one is already open on this
+        // thread, in which case it simply joins it like any other nested call.
+        let in_transaction = ATOMIC_TRANSACTION.with(|t| t.borrow().is_some());
+        let atomic_root = reducer_schema.atomic && !in_transaction;
+        if atomic_root {
+            ATOMIC_TRANSACTION.with(|t| *t.borrow_mut() = Some(AtomicTransaction::default()));
+        } else if in_transaction {
+            // Hand the caller's writes so far to the transaction first, so the
+            // commit replays them in execution order.
+            if let Some(caller_frame) = CALL_STACK.with(|s| {
+                s.borrow_mut()
+                    .last_mut()
+                    .map(|f| (f.module_arc.clone(), std::mem::take(&mut f.transactions)))
+            }) {
+                ATOMIC_TRANSACTION.with(|t| {
+                    if let Some(tx) = t.borrow_mut().as_mut() {
+                        tx.push(&caller_frame.0, caller_frame.1);
+                    }
+                });
+            }
+        }
 
         CALL_STACK.with(|s| {
             s.borrow_mut().push(CallFrame::new(
@@ -231,20 +354,85 @@ impl Runtime {
         // Pop frame from current thread's stack; remove the entry when stack becomes empty.
         let reducer_frame = CALL_STACK.with(|s| s.borrow_mut().pop().unwrap());
 
-        call_result?;
+        if in_transaction && !atomic_root {
+            // Nested call inside an atomic transaction: defer the commit to the
+            // root, and poison the transaction if this call failed.
+            ATOMIC_TRANSACTION.with(|t| {
+                if let Some(tx) = t.borrow_mut().as_mut() {
+                    tx.auto_inc_snapshots
+                        .extend(reducer_frame.auto_inc_snapshots.into_values());
+                    match &call_result {
+                        Ok(()) => tx.push(&module, reducer_frame.transactions),
+                        Err(err) => {
+                            tx.abort_reason.get_or_insert_with(|| {
+                                format!("'{}::{}' failed: {}", module_name, reducer_name, err)
+                            });
+                        }
+                    }
+                }
+            });
+            return call_result.map(|()| None);
+        }
 
-        // ── Transaction apply ────────────────────────────────────────────────
-        let emitted_events = self.apply_all_transactions(reducer_frame.transactions, &module)?;
+        let emitted_events = if atomic_root {
+            let mut transaction = ATOMIC_TRANSACTION
+                .with(|t| t.borrow_mut().take())
+                .unwrap_or_default();
+            transaction
+                .auto_inc_snapshots
+                .extend(reducer_frame.auto_inc_snapshots.into_values());
+            // A trapped call drops the whole transaction, nested writes included.
+            let aborted = match call_result {
+                Err(err) => Some(err),
+                Ok(()) => transaction.abort_reason.take().map(|reason| {
+                    IntersticeError::TransactionAborted {
+                        module: module_name.into(),
+                        reducer: reducer_name.into(),
+                        reason,
+                    }
+                }),
+            };
+            if let Some(err) = aborted {
+                rewind_auto_inc(transaction.auto_inc_snapshots);
+                return Err(err);
+            }
+            transaction.push(&module, reducer_frame.transactions);
+
+            // ── Transaction apply (every module touched, in execution order) ──
+            // Every segment is checked first: one that fails to apply must not
+            // leave the segments before it committed.
+            self.apply_atomic_transaction(
+                transaction.segments,
+                transaction.auto_inc_snapshots,
+                module_name,
+                reducer_name,
+            )?
+        } else {
+            // A trapped call (including fuel exhaustion) drops the frame, so none of
+            // its buffered transactions are applied.
+            call_result?;
+
+            // ── Transaction apply ────────────────────────────────────────────
+            self.apply_all_transactions(reducer_frame.transactions, &module)?
+        };
+
+        let commit_sequence = self.commit_sequence.fetch_add(1, Ordering::Relaxed) + 1;
 
         // ── Event dispatch ───────────────────────────────────────────────────
-        // For each event, fork the active completion token (if any) so the
-        // render-done signal stays open until the event is fully processed by
-        // the async event loop and any ReducerJobs it dispatches also complete.
-        for ev in emitted_events {
+        // Atomic transactions only get here once everything has committed.
+        self.dispatch_events(emitted_events);
+
+        Ok(Some(commit_sequence))
+    }
+
+    /// Send the events of committed changes to the event loop. Each event forks the
+    /// active completion token (if any) so the render-done signal stays open until
+    /// the event is fully processed by the async event loop and any ReducerJobs it
+    /// dispatches also complete.
+    pub(crate) fn dispatch_events(&self, events: Vec<EventInstance>) {
+        for ev in events {
             let token = ACTIVE_COMPLETION.with(|c| c.borrow().as_ref().map(|t| t.fork()));
             self.event_sender.send((ev, token)).unwrap();
         }
-
-        Ok(())
     }
 }
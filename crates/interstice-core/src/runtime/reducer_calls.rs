@@ -0,0 +1,170 @@
+//! Reducer calls modules make on other nodes.
+//!
+//! Each call gets an id the calling module follows its outcome with. The called
+//! node answers with a `ReducerResult` once the reducer committed or failed;
+//! calls still waiting when that node disconnects end as `Unknown`, since the
+//! reducer may have run without its answer getting through. The outcomes of the
+//! last [`TRACKED_CALLS_CAPACITY`] calls are kept, older calls are forgotten.
+//! Modules poll the status of their calls; a reducer never blocks on one, since the
+//! answer can depend on jobs that conflict with it.
+
+use crate::{node::NodeId, runtime::Runtime};
+use interstice_abi::ReducerCallStatus;
+use parking_lot::Mutex;
+use std::collections::{HashMap, VecDeque};
+use uuid::Uuid;
+
+const TRACKED_CALLS_CAPACITY: usize = 4096;
+
+pub(crate) struct ReducerCalls {
+    calls: Mutex<TrackedCalls>,
+}
+
+impl ReducerCalls {
+    pub fn new() -> Self {
+        Self {
+            calls: Mutex::new(TrackedCalls::default()),
+        }
+    }
+}
+
+#[derive(Default)]
+struct TrackedCalls {
+    by_id: HashMap<String, TrackedCall>,
+    /// Call ids, oldest first.
+    order: VecDeque<String>,
+}
+
+struct TrackedCall {
+    module_name: String,
+    node_id: NodeId,
+    status: ReducerCallStatus,
+}
+
+impl TrackedCalls {
+    fn insert(&mut self, call_id: String, call: TrackedCall) {
+        if self.order.len() == TRACKED_CALLS_CAPACITY
+            && let Some(oldest) = self.order.pop_front()
+        {
+            self.by_id.remove(&oldest);
+        }
+        self.order.push_back(call_id.clone());
+        self.by_id.insert(call_id, call);
+    }
+
+    /// Give `call_id` its outcome, unless it already has one.
+    fn settle(&mut self, call_id: &str, status: ReducerCallStatus) -> bool {
+        match self.by_id.get_mut(call_id) {
+            Some(call) if call.status.is_pending() => {
+                call.status = status;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Settle the pending calls sent to `node_id`.
+    fn abandon(&mut self, node_id: NodeId, reason: &str) -> bool {
+        let mut settled = false;
+        for call in self.by_id.values_mut() {
+            if call.node_id == node_id && call.status.is_pending() {
+                call.status = ReducerCallStatus::Unknown {
+                    reason: reason.to_string(),
+                };
+                settled = true;
+            }
+        }
+        settled
+    }
+}
+
+impl Runtime {
+    /// Track a call `module_name` is sending to `node_id` and return its id.
+    pub(crate) fn track_reducer_call(&self, module_name: &str, node_id: NodeId) -> String {
+        let call_id = Uuid::new_v4().to_string();
+        self.reducer_calls.calls.lock().insert(
+            call_id.clone(),
+            TrackedCall {
+                module_name: module_name.to_string(),
+                node_id,
+                status: ReducerCallStatus::Pending,
+            },
+        );
+        call_id
+    }
+
+    /// Record the outcome a node reported for `call_id`.
+    pub(crate) fn settle_reducer_call(&self, call_id: &str, status: ReducerCallStatus) {
+        self.reducer_calls.calls.lock().settle(call_id, status);
+    }
+
+    /// The calls `node_id` did not answer before disconnecting will never be answered.
+    pub(crate) fn abandon_reducer_calls(&self, node_id: NodeId) {
+        let reason = format!("node {} disconnected before answering", node_id);
+        self.reducer_calls.calls.lock().abandon(node_id, &reason);
+    }
+
+    /// Status of a call `module_name` made. `None` for calls the module did not
+    /// make or that were forgotten.
+    pub(crate) fn reducer_call_status(
+        &self,
+        module_name: &str,
+        call_id: &str,
+    ) -> Option<ReducerCallStatus> {
+        self.reducer_calls
+            .calls
+            .lock()
+            .by_id
+            .get(call_id)
+            .filter(|call| call.module_name == module_name)
+            .map(|call| call.status.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pending(node_id: NodeId) -> TrackedCall {
+        TrackedCall {
+            module_name: "caller".into(),
+            node_id,
+            status: ReducerCallStatus::Pending,
+        }
+    }
+
+    #[test]
+    fn calls_keep_their_first_outcome() {
+        let node_id = Uuid::new_v4();
+        let mut calls = TrackedCalls::default();
+        calls.insert("a".into(), pending(node_id));
+        calls.insert("b".into(), pending(node_id));
+
+        let committed = ReducerCallStatus::Committed { commit_sequence: 7 };
+        assert!(calls.settle("a", committed.clone()));
+        assert!(!calls.settle("a", ReducerCallStatus::Failed { error: "late".into() }));
+        assert!(!calls.settle("unknown", committed.clone()));
+        assert!(calls.abandon(node_id, "gone"));
+        assert!(!calls.abandon(node_id, "gone"));
+
+        assert_eq!(calls.by_id["a"].status, committed);
+        assert_eq!(
+            calls.by_id["b"].status,
+            ReducerCallStatus::Unknown {
+                reason: "gone".into()
+            }
+        );
+    }
+
+    #[test]
+    fn oldest_calls_are_forgotten() {
+        let node_id = Uuid::new_v4();
+        let mut calls = TrackedCalls::default();
+        for call in 0..=TRACKED_CALLS_CAPACITY {
+            calls.insert(call.to_string(), pending(node_id));
+        }
+        assert_eq!(calls.by_id.len(), TRACKED_CALLS_CAPACITY);
+        assert!(!calls.by_id.contains_key("0"));
+        assert!(calls.by_id.contains_key(&TRACKED_CALLS_CAPACITY.to_string()));
+    }
+}
@@ -0,0 +1,577 @@
+//! Sequenced replication of public tables (`replicated_tables`).
+//!
+//! Every event of a public table gets the next sequence number of its table, and
+//! the last events of each table are kept in a changelog. A replica applies the
+//! events of its source table in sequence: a jump means events were lost, and the
+//! replica asks the source for the events after the last one it applied. The
+//! source answers from its changelog, or with the whole table when the changelog
+//! no longer reaches back that far or the source restarted in between (a restart
+//! starts a new epoch). Live events are dropped while a sync is pending, the sync
+//! covers them.
+//!
+//! When the source node disconnects, its replicas keep their rows until it is back
+//! (see `reconnect`), then catch up.
+
+use crate::{
+    logger::{LogLevel, LogSource},
+    network::protocol::{
+        NetworkPacket, ReplicaPosition, RequestSubscription, TableEvent, TableEventInstance,
+    },
+    node::NodeId,
+    runtime::{
+        ReplicaBinding, Runtime,
+        event::{EventInstance, SubscriptionTarget},
+        transaction::Transaction,
+    },
+};
+use interstice_abi::{IndexKey, ReplicaState, ReplicaStatus, Row, TableVisibility};
+use parking_lot::Mutex;
+use std::collections::{HashMap, VecDeque};
+use uuid::Uuid;
+
+/// Events kept per public table for the replicas catching up.
+const CHANGELOG_CAPACITY: usize = 1024;
+
+/// Changelogs of the public tables.
+pub(crate) struct Replication {
+    /// Identifies this run of the node; sequence numbers start over with it.
+    epoch: String,
+    changelogs: Mutex<HashMap<(String, String), TableChangelog>>,
+}
+
+impl Replication {
+    pub fn new() -> Self {
+        Self {
+            epoch: Uuid::new_v4().to_string(),
+            changelogs: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+/// Last events of a public table.
+#[derive(Debug, Default)]
+struct TableChangelog {
+    /// Sequence number of the last event.
+    sequence: u64,
+    /// The events after this sequence number are all kept.
+    base: u64,
+    events: VecDeque<EventInstance>,
+}
+
+impl TableChangelog {
+    /// Number `event` as the next event of the table and keep it.
+    fn record(&mut self, event: &mut EventInstance) {
+        self.sequence += 1;
+        set_sequence(event, self.sequence);
+        self.events.push_back(event.clone());
+        if self.events.len() > CHANGELOG_CAPACITY {
+            self.events.pop_front();
+            self.base += 1;
+        }
+    }
+
+    /// The events after `since`, or `None` when they are not all kept.
+    fn since(&self, since: u64) -> Option<Vec<EventInstance>> {
+        if since < self.base || since > self.sequence {
+            return None;
+        }
+        Some(
+            self.events
+                .iter()
+                .skip((since - self.base) as usize)
+                .cloned()
+                .collect(),
+        )
+    }
+
+    /// Forget the events and skip a sequence number, so every replica syncs the
+    /// whole table again: its rows change without events when its module reloads.
+    fn reset(&mut self) {
+        self.sequence += 1;
+        self.base = self.sequence;
+        self.events.clear();
+    }
+}
+
+fn set_sequence(event: &mut EventInstance, value: u64) {
+    match event {
+        EventInstance::TableInsertEvent { sequence, .. }
+        | EventInstance::TableUpdateEvent { sequence, .. }
+        | EventInstance::TableDeleteEvent { sequence, .. } => *sequence = value,
+        _ => {}
+    }
+}
+
+/// Where a replica stands against its source table.
+#[derive(Debug, Clone)]
+pub(crate) struct ReplicaCursor {
+    pub state: ReplicaState,
+    /// Epoch of the source at the last sync.
+    pub epoch: Option<String>,
+    pub last_sequence: u64,
+    pub source_sequence: u64,
+    pub last_sync_unix_ms: Option<u64>,
+}
+
+/// What to do with a live event of the source table.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Accept {
+    Apply,
+    Ignore,
+    /// Events were lost; the replica now waits for a sync.
+    Gap,
+}
+
+impl ReplicaCursor {
+    pub fn new() -> Self {
+        Self {
+            state: ReplicaState::Syncing,
+            epoch: None,
+            last_sequence: 0,
+            source_sequence: 0,
+            last_sync_unix_ms: None,
+        }
+    }
+
+    pub fn accept(&mut self, sequence: u64) -> Accept {
+        self.source_sequence = self.source_sequence.max(sequence);
+        if self.state != ReplicaState::Live || sequence <= self.last_sequence {
+            return Accept::Ignore;
+        }
+        if sequence == self.last_sequence + 1 {
+            self.last_sequence = sequence;
+            return Accept::Apply;
+        }
+        self.state = ReplicaState::Resyncing;
+        Accept::Gap
+    }
+
+    /// Position to catch up from, `None` before the first sync.
+    pub fn position(&self) -> Option<ReplicaPosition> {
+        self.epoch.as_ref().map(|epoch| ReplicaPosition {
+            epoch: epoch.clone(),
+            sequence: self.last_sequence,
+        })
+    }
+
+    fn awaits_sync(&self) -> bool {
+        matches!(self.state, ReplicaState::Syncing | ReplicaState::Resyncing)
+    }
+
+    /// Whether the changes after `since` in `epoch` continue the replica.
+    fn continues_at(&self, epoch: &str, since: u64) -> bool {
+        self.awaits_sync() && self.epoch.as_deref() == Some(epoch) && self.last_sequence == since
+    }
+
+    fn synced(&mut self, position: &ReplicaPosition, now_unix_ms: u64) {
+        if self.epoch.as_ref() != Some(&position.epoch) {
+            self.source_sequence = position.sequence;
+        }
+        self.state = ReplicaState::Live;
+        self.epoch = Some(position.epoch.clone());
+        self.last_sequence = position.sequence;
+        self.source_sequence = self.source_sequence.max(position.sequence);
+        self.last_sync_unix_ms = Some(now_unix_ms);
+    }
+
+    pub fn status(&self) -> ReplicaStatus {
+        ReplicaStatus {
+            state: self.state,
+            last_sequence: self.last_sequence,
+            source_sequence: self.source_sequence,
+            last_sync_unix_ms: self.last_sync_unix_ms,
+        }
+    }
+}
+
+impl Runtime {
+    /// Number a local event of a public table and keep it in the table's changelog.
+    pub(crate) fn record_table_event(&self, event: &mut EventInstance) {
+        let Some((module_name, table_name, _)) = event.table_event() else {
+            return;
+        };
+        let key = (module_name.to_string(), table_name.to_string());
+        let public = self.modules.lock().get(&key.0).is_some_and(|module| {
+            module
+                .schema
+                .tables
+                .iter()
+                .any(|table| table.name == key.1 && table.visibility == TableVisibility::Public)
+        });
+        if public {
+            self.replication
+                .changelogs
+                .lock()
+                .entry(key)
+                .or_default()
+                .record(event);
+        }
+    }
+
+    /// Current position of a table, sent with its rows on a full sync.
+    pub(crate) fn table_position(&self, module_name: &str, table_name: &str) -> ReplicaPosition {
+        let sequence = self
+            .replication
+            .changelogs
+            .lock()
+            .get(&(module_name.to_string(), table_name.to_string()))
+            .map_or(0, |changelog| changelog.sequence);
+        ReplicaPosition {
+            epoch: self.replication.epoch.clone(),
+            sequence,
+        }
+    }
+
+    /// The events of a table after `since`, as `reader_node_id` may see them, and
+    /// the position they lead to. `None` when the reader needs a full sync.
+    pub(crate) fn table_changes_since(
+        &self,
+        module_name: &str,
+        table_name: &str,
+        since: &ReplicaPosition,
+        reader_node_id: NodeId,
+    ) -> Option<(ReplicaPosition, Vec<TableEventInstance>)> {
+        if since.epoch != self.replication.epoch {
+            return None;
+        }
+        let (sequence, events) = {
+            let changelogs = self.replication.changelogs.lock();
+            let changelog = changelogs.get(&(module_name.to_string(), table_name.to_string()))?;
+            (changelog.sequence, changelog.since(since.sequence)?)
+        };
+        let target = SubscriptionTarget::Remote {
+            node_id: reader_node_id,
+            filter: None,
+        };
+        let events = events
+            .iter()
+            .filter_map(|event| self.visible_table_event(event, &target))
+            .filter_map(|event| event.into_remote_table_event(None).ok())
+            .collect();
+        let position = ReplicaPosition {
+            epoch: self.replication.epoch.clone(),
+            sequence,
+        };
+        Some((position, events))
+    }
+
+    /// Drop the changelogs of a module's tables when it is unloaded.
+    pub(crate) fn reset_changelogs(&self, module_name: &str) {
+        for ((module, _), changelog) in self.replication.changelogs.lock().iter_mut() {
+            if module == module_name {
+                changelog.reset();
+            }
+        }
+    }
+
+    /// Apply an unfiltered event of a remote table to its replicas. `event` is
+    /// `None` for an event hidden from this node, which only moves the sequence on.
+    pub(crate) fn apply_replica_event(
+        &self,
+        source_node_id: NodeId,
+        source_module_name: &str,
+        source_table_name: &str,
+        sequence: u64,
+        event: Option<&EventInstance>,
+    ) {
+        let mut gaps = Vec::new();
+        let applied = {
+            let mut bindings = self.replica_bindings.lock();
+            let mut applied = Vec::new();
+            for binding in bindings.iter_mut().filter(|binding| {
+                binding.replicates(source_node_id, source_module_name, source_table_name)
+            }) {
+                match binding.cursor.accept(sequence) {
+                    Accept::Apply => applied.push(binding.clone()),
+                    Accept::Ignore => {}
+                    Accept::Gap => gaps.push(binding.clone()),
+                }
+            }
+            applied
+        };
+
+        if let Some(event) = event {
+            for binding in &applied {
+                self.apply_replica_change(binding, event);
+            }
+        }
+        for binding in gaps {
+            self.logger.log(
+                &format!(
+                    "Replica of '{}.{}.{}' missed events after {} (received {}), resyncing",
+                    binding.source_node_name,
+                    binding.source_module_name,
+                    binding.source_table_name,
+                    binding.cursor.last_sequence,
+                    sequence
+                ),
+                LogSource::Runtime,
+                LogLevel::Warning,
+            );
+            self.request_replica_sync(&binding);
+        }
+    }
+
+    fn apply_replica_change(&self, binding: &ReplicaBinding, event: &EventInstance) {
+        let insert = |row: &Row| {
+            self.apply_transaction(
+                Transaction::Insert {
+                    module_name: binding.owner_module_name.clone(),
+                    table_name: binding.local_table_name.clone(),
+                    new_row: row.clone(),
+                },
+                false,
+                None,
+            )
+        };
+        let update = |row: &Row| {
+            self.apply_transaction(
+                Transaction::Update {
+                    module_name: binding.owner_module_name.clone(),
+                    table_name: binding.local_table_name.clone(),
+                    update_row: row.clone(),
+                },
+                false,
+                None,
+            )
+        };
+        match event {
+            EventInstance::TableInsertEvent { inserted_row, .. } => {
+                let _ = insert(inserted_row).or_else(|_| update(inserted_row));
+            }
+            EventInstance::TableUpdateEvent { new_row, .. } => {
+                if let Err(crate::IntersticeError::RowNotFound { .. }) = update(new_row) {
+                    let _ = insert(new_row);
+                }
+            }
+            EventInstance::TableDeleteEvent { deleted_row, .. } => {
+                if let Ok(primary_key) = IndexKey::try_from(deleted_row.primary_key.clone()) {
+                    let _ = self.apply_transaction(
+                        Transaction::Delete {
+                            module_name: binding.owner_module_name.clone(),
+                            table_name: binding.local_table_name.clone(),
+                            deleted_row_id: primary_key,
+                        },
+                        false,
+                        None,
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Replace the rows of the replicas waiting for a sync of a remote table.
+    pub(crate) fn apply_replica_sync(
+        &self,
+        source_node_id: NodeId,
+        source_module_name: &str,
+        source_table_name: &str,
+        position: ReplicaPosition,
+        rows: Vec<Row>,
+    ) {
+        let synced = self.sync_replicas(source_node_id, source_module_name, source_table_name, |cursor| {
+            cursor.awaits_sync()
+        }, &position);
+        for binding in synced {
+            if let Some(module) = self.modules.lock().get(&binding.owner_module_name)
+                && let Some(table) = module.tables.lock().get_mut(&binding.local_table_name)
+            {
+                let _ = table.restore_from_rows(rows.clone());
+            }
+            self.emit_replica_synced(&binding);
+        }
+    }
+
+    /// Apply the events a replica missed, when it is still where it asked them from.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn apply_replica_changes(
+        &self,
+        source_node_id: NodeId,
+        source_module_name: &str,
+        source_table_name: &str,
+        since: u64,
+        position: ReplicaPosition,
+        events: Vec<EventInstance>,
+    ) {
+        let synced = self.sync_replicas(source_node_id, source_module_name, source_table_name, |cursor| {
+            cursor.continues_at(&position.epoch, since)
+        }, &position);
+        for binding in synced {
+            for event in &events {
+                self.apply_replica_change(&binding, event);
+            }
+            self.emit_replica_synced(&binding);
+        }
+    }
+
+    /// Move the replicas of a remote table accepted by `accepts` to `position`.
+    fn sync_replicas(
+        &self,
+        source_node_id: NodeId,
+        source_module_name: &str,
+        source_table_name: &str,
+        accepts: impl Fn(&ReplicaCursor) -> bool,
+        position: &ReplicaPosition,
+    ) -> Vec<ReplicaBinding> {
+        let now = self.clock.now_unix_ms();
+        self.replica_bindings
+            .lock()
+            .iter_mut()
+            .filter(|binding| {
+                binding.replicates(source_node_id, source_module_name, source_table_name)
+                    && accepts(&binding.cursor)
+            })
+            .map(|binding| {
+                binding.cursor.synced(position, now);
+                binding.clone()
+            })
+            .collect()
+    }
+
+    fn emit_replica_synced(&self, binding: &ReplicaBinding) {
+        let _ = self.event_sender.send((
+            EventInstance::ReplicaTableSynced {
+                node_name: binding.source_node_name.clone(),
+                module_name: binding.source_module_name.clone(),
+                table_name: binding.source_table_name.clone(),
+            },
+            None,
+        ));
+    }
+
+    pub(crate) fn subscribe_replica(&self, binding: &ReplicaBinding) {
+        for event in [TableEvent::Insert, TableEvent::Update, TableEvent::Delete] {
+            self.network_handle.send_packet(
+                binding.source_node_id,
+                NetworkPacket::RequestSubscription(RequestSubscription {
+                    module_name: binding.source_module_name.clone(),
+                    table_name: binding.source_table_name.clone(),
+                    event,
+                    filter: None,
+                }),
+            );
+        }
+    }
+
+    /// Ask the source for the events the replica missed, or for the whole table
+    /// before its first sync.
+    pub(crate) fn request_replica_sync(&self, binding: &ReplicaBinding) {
+        self.network_handle.send_packet(
+            binding.source_node_id,
+            NetworkPacket::RequestTableSync {
+                module_name: binding.source_module_name.clone(),
+                table_name: binding.source_table_name.clone(),
+                filter: None,
+                since: binding.cursor.position(),
+            },
+        );
+    }
+
+    /// Keep the replicas of a disconnected node until it is back.
+    pub(crate) fn disconnect_replicas(&self, node_id: NodeId) {
+        for binding in self
+            .replica_bindings
+            .lock()
+            .iter_mut()
+            .filter(|binding| binding.source_node_id == node_id)
+        {
+            binding.cursor.state = ReplicaState::Disconnected;
+        }
+    }
+
+    /// Subscribe again to the tables a reconnected node replicates to this one, and
+    /// catch them up.
+    pub(crate) fn resync_replicas(&self, node_id: NodeId) {
+        let bindings: Vec<ReplicaBinding> = self
+            .replica_bindings
+            .lock()
+            .iter_mut()
+            .filter(|binding| binding.source_node_id == node_id)
+            .map(|binding| {
+                binding.cursor.state = match binding.cursor.epoch {
+                    Some(_) => ReplicaState::Resyncing,
+                    None => ReplicaState::Syncing,
+                };
+                binding.clone()
+            })
+            .collect();
+        for binding in bindings {
+            self.subscribe_replica(&binding);
+            self.request_replica_sync(&binding);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use interstice_abi::IntersticeValue;
+
+    fn insert(id: u64) -> EventInstance {
+        EventInstance::TableInsertEvent {
+            source_node_id: None,
+            filter: None,
+            sequence: 0,
+            module_name: "game".into(),
+            table_name: "players".into(),
+            inserted_row: Row {
+                primary_key: IntersticeValue::U64(id),
+                entries: Vec::new(),
+            },
+        }
+    }
+
+    fn sequences(events: Vec<EventInstance>) -> Vec<u64> {
+        events
+            .iter()
+            .filter_map(|event| event.table_event().map(|(_, _, sequence)| sequence))
+            .collect()
+    }
+
+    #[test]
+    fn changelog_serves_the_events_it_kept() {
+        let mut changelog = TableChangelog::default();
+        for id in 0..CHANGELOG_CAPACITY as u64 + 10 {
+            changelog.record(&mut insert(id));
+        }
+        let last = CHANGELOG_CAPACITY as u64 + 10;
+        assert_eq!(sequences(changelog.since(last - 2).unwrap()), vec![last - 1, last]);
+        assert!(changelog.since(last).unwrap().is_empty());
+        // The first ten events were dropped.
+        assert_eq!(changelog.since(10).unwrap().len(), CHANGELOG_CAPACITY);
+        assert!(changelog.since(9).is_none());
+        assert!(changelog.since(last + 1).is_none());
+
+        changelog.reset();
+        assert!(changelog.since(last).is_none());
+        assert!(changelog.since(last + 1).unwrap().is_empty());
+    }
+
+    #[test]
+    fn cursor_applies_events_in_sequence() {
+        let mut cursor = ReplicaCursor::new();
+        // Events before the first sync are covered by it.
+        assert_eq!(cursor.accept(3), Accept::Ignore);
+        let position = |sequence| ReplicaPosition {
+            epoch: "a".into(),
+            sequence,
+        };
+        cursor.synced(&position(3), 1);
+        assert_eq!(cursor.accept(3), Accept::Ignore);
+        assert_eq!(cursor.accept(4), Accept::Apply);
+        assert_eq!(cursor.accept(6), Accept::Gap);
+        assert_eq!(cursor.state, ReplicaState::Resyncing);
+        assert_eq!(cursor.accept(7), Accept::Ignore);
+        assert_eq!(cursor.position(), Some(position(4)));
+        assert_eq!(cursor.status().lag(), 3);
+
+        assert!(!cursor.continues_at("a", 3));
+        assert!(!cursor.continues_at("b", 4));
+        assert!(cursor.continues_at("a", 4));
+        cursor.synced(&position(7), 2);
+        assert_eq!(cursor.state, ReplicaState::Live);
+        assert_eq!(cursor.status().lag(), 0);
+        assert_eq!(cursor.accept(8), Accept::Apply);
+    }
+}
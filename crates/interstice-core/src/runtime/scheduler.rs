@@ -24,6 +24,18 @@ pub struct ScheduledJob<T> {
     pub accesses: Arc<[TableAccess]>,
 }
 
+/// Requests to the scheduler thread, used to quiesce a module during a hot upgrade.
+pub(crate) enum SchedulerControl {
+    /// Stop starting jobs of `module_name` and signal `drained` once none of its
+    /// jobs is running anymore.
+    Drain {
+        module_name: String,
+        drained: tokio::sync::oneshot::Sender<()>,
+    },
+    /// Enqueue the jobs of `module_name` held back since the matching `Drain`.
+    Resume { module_name: String },
+}
+
 #[derive(Debug)]
 pub struct ReducerScheduler<T> {
     next_id: u64,
@@ -64,6 +76,15 @@ impl<T> ReducerScheduler<T> {
         self.promote_waiting()
     }
 
+    /// Pull the waiting jobs selected by `pred` out of the queue, in queue order.
+    /// The caller is expected to enqueue them again later.
+    pub fn take_waiting(&mut self, mut pred: impl FnMut(&T) -> bool) -> Vec<T> {
+        let (taken, kept): (VecDeque<_>, VecDeque<_>) =
+            self.waiting.drain(..).partition(|job| pred(&job.payload));
+        self.waiting = kept;
+        taken.into_iter().map(|job| job.payload).collect()
+    }
+
     fn can_run_now(&self, job: &ScheduledJob<T>) -> bool {
         if self
             .running
@@ -215,6 +236,23 @@ mod tests {
         assert_eq!(promoted[1].payload, "wait2");
     }
 
+    #[test]
+    fn take_waiting_removes_only_selected_jobs() {
+        let mut s = ReducerScheduler::new();
+        let running = s
+            .enqueue("run", arc_accesses(vec![acc("m", "a", TableOp::Update)]))
+            .into_iter()
+            .collect::<Vec<_>>();
+        s.enqueue("m1", arc_accesses(vec![acc("m", "a", TableOp::Read)]));
+        s.enqueue("other", arc_accesses(vec![acc("m", "a", TableOp::Read)]));
+        s.enqueue("m2", arc_accesses(vec![acc("m", "a", TableOp::Read)]));
+
+        assert_eq!(s.take_waiting(|job| job.starts_with('m')), vec!["m1", "m2"]);
+        let promoted = s.complete(running[0].id);
+        assert_eq!(promoted.len(), 1);
+        assert_eq!(promoted[0].payload, "other");
+    }
+
     #[test]
     fn read_conflicts_with_insert_same_table() {
         let mut s = ReducerScheduler::new();
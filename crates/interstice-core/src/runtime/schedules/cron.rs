@@ -0,0 +1,266 @@
+//! Five-field cron expressions (`minute hour day-of-month month day-of-week`),
+//! evaluated in UTC with minute resolution. Each field accepts `*`, values,
+//! ranges (`a-b`), steps (`*/n`, `a-b/n`) and comma separated lists of those.
+//! Day of week runs from 0 (Sunday) to 6, with 7 also meaning Sunday. As in
+//! classic cron, when both day fields are restricted a day matching either runs.
+
+const MINUTE_MS: u64 = 60_000;
+const DAY_MINUTES: i64 = 24 * 60;
+/// Give up looking for a next run after this many years (e.g. `0 0 31 2 *`).
+const SEARCH_YEARS: i64 = 5;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronExpr {
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    any_day_of_month: bool,
+    any_day_of_week: bool,
+}
+
+impl CronExpr {
+    pub fn parse(expr: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        let [minute, hour, day_of_month, month, day_of_week] = fields[..] else {
+            return Err(format!(
+                "invalid cron expression '{}': expected 5 fields, got {}",
+                expr,
+                fields.len()
+            ));
+        };
+        let mut days_of_week = parse_field(day_of_week, 0, 7)?;
+        if days_of_week & (1 << 7) != 0 {
+            days_of_week |= 1;
+        }
+        Ok(Self {
+            minutes: parse_field(minute, 0, 59)?,
+            hours: parse_field(hour, 0, 23)?,
+            days_of_month: parse_field(day_of_month, 1, 31)?,
+            months: parse_field(month, 1, 12)?,
+            days_of_week,
+            any_day_of_month: day_of_month == "*",
+            any_day_of_week: day_of_week == "*",
+        })
+    }
+
+    /// First matching minute strictly after `after_unix_ms`, in Unix milliseconds.
+    pub fn next_after(&self, after_unix_ms: u64) -> Option<u64> {
+        let mut minute = (after_unix_ms / MINUTE_MS) as i64 + 1;
+        let limit = minute + SEARCH_YEARS * 366 * DAY_MINUTES;
+        while minute < limit {
+            let day = minute.div_euclid(DAY_MINUTES);
+            let (year, month, day_of_month) = civil_from_days(day);
+            if !has(self.months, month) {
+                // Jump to the first day of the next month.
+                let (year, month) = if month == 12 { (year + 1, 1) } else { (year, month + 1) };
+                minute = days_from_civil(year, month, 1) * DAY_MINUTES;
+                continue;
+            }
+            if !self.day_matches(day, day_of_month) {
+                minute = (day + 1) * DAY_MINUTES;
+                continue;
+            }
+            let minute_of_day = minute.rem_euclid(DAY_MINUTES);
+            if !has(self.hours, (minute_of_day / 60) as u32) {
+                minute = minute - minute_of_day % 60 + 60;
+                continue;
+            }
+            if !has(self.minutes, (minute_of_day % 60) as u32) {
+                minute += 1;
+                continue;
+            }
+            return Some(minute as u64 * MINUTE_MS);
+        }
+        None
+    }
+
+    /// Number of matching minutes in `(after_unix_ms, until_unix_ms]`. Days the range
+    /// covers whole are counted at once, so long ranges cost one step per day.
+    pub fn count_between(&self, after_unix_ms: u64, until_unix_ms: u64) -> u64 {
+        let first = (after_unix_ms / MINUTE_MS) as i64 + 1;
+        let last = (until_unix_ms / MINUTE_MS) as i64;
+        let per_day = (self.hours.count_ones() * self.minutes.count_ones()) as u64;
+        let mut count = 0;
+        for day in first.div_euclid(DAY_MINUTES)..=last.div_euclid(DAY_MINUTES) {
+            let (_, month, day_of_month) = civil_from_days(day);
+            if !has(self.months, month) || !self.day_matches(day, day_of_month) {
+                continue;
+            }
+            let start = (first - day * DAY_MINUTES).max(0);
+            let end = (last - day * DAY_MINUTES).min(DAY_MINUTES - 1);
+            count += if start == 0 && end == DAY_MINUTES - 1 {
+                per_day
+            } else {
+                (start..=end)
+                    .filter(|minute| {
+                        has(self.hours, (minute / 60) as u32) && has(self.minutes, (minute % 60) as u32)
+                    })
+                    .count() as u64
+            };
+        }
+        count
+    }
+
+    fn day_matches(&self, day: i64, day_of_month: u32) -> bool {
+        // 1970-01-01 was a Thursday.
+        let day_of_week = (day + 4).rem_euclid(7) as u32;
+        let month_day = has(self.days_of_month, day_of_month);
+        let week_day = has(self.days_of_week, day_of_week);
+        match (self.any_day_of_month, self.any_day_of_week) {
+            (true, true) => true,
+            (false, true) => month_day,
+            (true, false) => week_day,
+            (false, false) => month_day || week_day,
+        }
+    }
+}
+
+fn has(set: u64, value: u32) -> bool {
+    set & (1 << value) != 0
+}
+
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64, String> {
+    let mut set = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step
+                    .parse()
+                    .map_err(|_| format!("invalid cron step in '{}'", part))?;
+                if step == 0 {
+                    return Err(format!("invalid cron step in '{}'", part));
+                }
+                (range, step)
+            }
+            None => (part, 1),
+        };
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (parse_value(start, min, max)?, parse_value(end, min, max)?)
+        } else {
+            let value = parse_value(range, min, max)?;
+            // `a/n` runs from `a` to the end of the field.
+            (value, if step > 1 { max } else { value })
+        };
+        if start > end {
+            return Err(format!("invalid cron range '{}'", range));
+        }
+        for value in (start..=end).step_by(step as usize) {
+            set |= 1 << value;
+        }
+    }
+    Ok(set)
+}
+
+fn parse_value(value: &str, min: u32, max: u32) -> Result<u32, String> {
+    match value.parse::<u32>() {
+        Ok(value) if (min..=max).contains(&value) => Ok(value),
+        _ => Err(format!(
+            "invalid cron value '{}': expected {}-{}",
+            value, min, max
+        )),
+    }
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Proleptic Gregorian `(year, month, day)` of a count of days since 1970-01-01.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unix_ms(year: i64, month: u32, day: u32, hour: u64, minute: u64) -> u64 {
+        days_from_civil(year, month, day) as u64 * DAY_MINUTES as u64 * MINUTE_MS
+            + (hour * 60 + minute) * MINUTE_MS
+    }
+
+    #[test]
+    fn civil_date_round_trip() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(civil_from_days(19_782), (2024, 2, 29));
+        for days in [-1, 0, 59, 365, 11_016, 19_782, 30_000] {
+            let (year, month, day) = civil_from_days(days);
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+    }
+
+    #[test]
+    fn next_run_of_common_expressions() {
+        let start = unix_ms(2024, 2, 28, 23, 59);
+        let every_five = CronExpr::parse("*/5 * * * *").unwrap();
+        assert_eq!(every_five.next_after(start), Some(unix_ms(2024, 2, 29, 0, 0)));
+
+        let daily = CronExpr::parse("30 9 * * *").unwrap();
+        assert_eq!(daily.next_after(start), Some(unix_ms(2024, 2, 29, 9, 30)));
+
+        // 2024-03-04 is the first Monday after the start.
+        let mondays = CronExpr::parse("0 12 * * 1").unwrap();
+        assert_eq!(mondays.next_after(start), Some(unix_ms(2024, 3, 4, 12, 0)));
+
+        let yearly = CronExpr::parse("0 0 1 1 *").unwrap();
+        assert_eq!(yearly.next_after(start), Some(unix_ms(2025, 1, 1, 0, 0)));
+
+        let never = CronExpr::parse("0 0 31 2 *").unwrap();
+        assert_eq!(never.next_after(start), None);
+    }
+
+    #[test]
+    fn counts_match_the_listed_runs() {
+        let start = unix_ms(2024, 2, 27, 21, 17) + 500;
+        let until = unix_ms(2024, 3, 5, 6, 42);
+        for expr in ["*/5 * * * *", "30 9 * * *", "0 12 * * 1", "*/7 1-3 28,29 2 *"] {
+            let cron = CronExpr::parse(expr).unwrap();
+            let mut listed = 0;
+            let mut run = start;
+            while let Some(next) = cron.next_after(run).filter(|next| *next <= until) {
+                listed += 1;
+                run = next;
+            }
+            assert_eq!(cron.count_between(start, until), listed, "{expr}");
+        }
+    }
+
+    #[test]
+    fn counts_long_ranges_by_day() {
+        let every_minute = CronExpr::parse("* * * * *").unwrap();
+        let start = unix_ms(2000, 1, 1, 0, 0);
+        let until = unix_ms(2030, 1, 1, 0, 0);
+        assert_eq!(every_minute.count_between(start, until), (until - start) / MINUTE_MS);
+        assert_eq!(every_minute.count_between(until, start), 0);
+    }
+
+    #[test]
+    fn rejects_malformed_expressions() {
+        assert!(CronExpr::parse("* * * *").is_err());
+        assert!(CronExpr::parse("60 * * * *").is_err());
+        assert!(CronExpr::parse("*/0 * * * *").is_err());
+        assert!(CronExpr::parse("5-1 * * * *").is_err());
+        assert!(CronExpr::parse("0 0 * * 7").is_ok());
+    }
+}
@@ -0,0 +1,392 @@
+//! Durable reducer schedules.
+//!
+//! `ctx.schedule*` registers a reducer call of the calling module to run once after
+//! a delay, at a fixed interval or following a cron expression. Schedules get an id
+//! the module can cancel them with, carry reducer arguments checked against the
+//! reducer's schema, and are persisted with the module's data through the
+//! [`TableStore`](crate::persistence::TableStore). When a module is loaded again
+//! (e.g. after a node restart), one-shot schedules that came due in the meantime
+//! run right away, while the runs a recurring schedule missed are skipped and
+//! counted in its `missed_runs`.
+
+mod cron;
+
+use crate::{
+    error::IntersticeError,
+    logger::{LogLevel, LogSource},
+    runtime::{
+        Runtime,
+        reducer::{CompletionToken, JobSource, ReducerJob},
+    },
+};
+use cron::CronExpr;
+use interstice_abi::{
+    IntersticeValue, ReducerSchema, ScheduleInfo, ScheduleRequest, ScheduleTiming,
+    validate_value_detailed,
+};
+use parking_lot::Mutex;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::sync::Notify;
+
+/// How long the timer task sleeps when no schedule is pending.
+const IDLE_WAIT: Duration = Duration::from_secs(3600);
+
+/// Pending schedules of every loaded module.
+pub(crate) struct Schedules {
+    next_id: AtomicU64,
+    by_module: Mutex<HashMap<String, BTreeMap<u64, ScheduleInfo>>>,
+    /// Wakes the timer task when a schedule is added or restored.
+    changed: Notify,
+}
+
+impl Schedules {
+    pub fn new() -> Self {
+        Self {
+            next_id: AtomicU64::new(1),
+            by_module: Mutex::new(HashMap::new()),
+            changed: Notify::new(),
+        }
+    }
+}
+
+/// Next run of a recurring schedule strictly after `after_unix_ms`.
+fn next_recurring_run(timing: &ScheduleTiming, previous_run: u64, after_unix_ms: u64) -> Option<u64> {
+    match timing {
+        ScheduleTiming::Once { .. } => None,
+        ScheduleTiming::Interval { interval_ms, .. } => {
+            let elapsed = after_unix_ms.saturating_sub(previous_run);
+            Some(previous_run + (elapsed / interval_ms + 1) * interval_ms)
+        }
+        ScheduleTiming::Cron(expr) => CronExpr::parse(expr).ok()?.next_after(after_unix_ms),
+    }
+}
+
+/// Number of runs of a recurring schedule in `(previous_run, until_unix_ms]`.
+fn runs_between(timing: &ScheduleTiming, previous_run: u64, until_unix_ms: u64) -> u64 {
+    match timing {
+        ScheduleTiming::Once { .. } => 0,
+        ScheduleTiming::Interval { interval_ms, .. } => {
+            until_unix_ms.saturating_sub(previous_run) / interval_ms
+        }
+        ScheduleTiming::Cron(expr) => CronExpr::parse(expr)
+            .map(|cron| cron.count_between(previous_run, until_unix_ms))
+            .unwrap_or(0),
+    }
+}
+
+fn validate_input(
+    reducer: &ReducerSchema,
+    input: &IntersticeValue,
+    module_schema: &interstice_abi::ModuleSchema,
+) -> Result<(), String> {
+    let IntersticeValue::Vec(args) = input else {
+        return Err("schedule arguments must be an IntersticeValue::Vec".into());
+    };
+    if args.len() != reducer.arguments.len() {
+        return Err(format!(
+            "reducer '{}' takes {} argument(s), {} given",
+            reducer.name,
+            reducer.arguments.len(),
+            args.len()
+        ));
+    }
+    for (arg, field) in args.iter().zip(&reducer.arguments) {
+        validate_value_detailed(arg, &field.field_type, &module_schema.type_definitions)
+            .map_err(|err| format!("argument '{}': {}", field.name, err))?;
+    }
+    Ok(())
+}
+
+impl Runtime {
+    /// Check a schedule request and give it an id. The schedule only runs once
+    /// registered with [`Runtime::add_schedule`], when the reducer that made it commits.
+    pub(crate) fn prepare_schedule(
+        &self,
+        module_schema: &interstice_abi::ModuleSchema,
+        request: ScheduleRequest,
+    ) -> Result<ScheduleInfo, IntersticeError> {
+        let invalid = |reason: String| {
+            IntersticeError::Internal(format!(
+                "Cannot schedule '{}.{}': {}",
+                module_schema.name, request.reducer_name, reason
+            ))
+        };
+        let reducer = module_schema
+            .reducers
+            .iter()
+            .find(|reducer| reducer.name == request.reducer_name)
+            .ok_or_else(|| invalid("reducer not found".into()))?;
+        validate_input(reducer, &request.input, module_schema).map_err(invalid)?;
+
+        let now = self.clock.now_unix_ms();
+        let next_run_unix_ms = match &request.timing {
+            ScheduleTiming::Once { delay_ms } => now + delay_ms,
+            ScheduleTiming::Interval { interval_ms: 0, .. } => {
+                return Err(invalid("interval must be greater than 0".into()));
+            }
+            ScheduleTiming::Interval { delay_ms, .. } => now + delay_ms,
+            ScheduleTiming::Cron(expr) => CronExpr::parse(expr)
+                .map_err(invalid)?
+                .next_after(now)
+                .ok_or_else(|| invalid(format!("cron expression '{}' never runs", expr)))?,
+        };
+
+        Ok(ScheduleInfo {
+            id: self.schedules.next_id.fetch_add(1, Ordering::Relaxed),
+            reducer_name: request.reducer_name,
+            input: request.input,
+            timing: request.timing,
+            next_run_unix_ms,
+            missed_runs: 0,
+        })
+    }
+
+    pub(crate) fn add_schedule(&self, module_name: &str, schedule: ScheduleInfo) {
+        {
+            let mut by_module = self.schedules.by_module.lock();
+            let schedules = by_module.entry(module_name.to_string()).or_default();
+            schedules.insert(schedule.id, schedule);
+            self.persist_schedules(module_name, schedules);
+        }
+        self.schedules.changed.notify_one();
+    }
+
+    /// Cancel a pending schedule of the module. Returns whether it existed.
+    pub(crate) fn cancel_schedule(&self, module_name: &str, schedule_id: u64) -> bool {
+        let mut by_module = self.schedules.by_module.lock();
+        let Some(schedules) = by_module.get_mut(module_name) else {
+            return false;
+        };
+        let cancelled = schedules.remove(&schedule_id).is_some();
+        if cancelled {
+            self.persist_schedules(module_name, schedules);
+        }
+        cancelled
+    }
+
+    pub(crate) fn list_schedules(&self, module_name: &str) -> Vec<ScheduleInfo> {
+        self.schedules
+            .by_module
+            .lock()
+            .get(module_name)
+            .map(|schedules| schedules.values().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// When the earliest pending schedule of any module is due.
+    pub(crate) fn next_schedule_run(&self) -> Option<u64> {
+        self.schedules
+            .by_module
+            .lock()
+            .values()
+            .flat_map(|schedules| schedules.values())
+            .map(|schedule| schedule.next_run_unix_ms)
+            .min()
+    }
+
+    /// Reinstate the persisted schedules of a module being loaded.
+    pub(crate) fn restore_schedules(&self, module_name: &str) -> Result<(), IntersticeError> {
+        let persisted = self.persistence.read_schedules(module_name)?;
+        if persisted.is_empty() {
+            return Ok(());
+        }
+
+        let now = self.clock.now_unix_ms();
+        let mut restored = BTreeMap::new();
+        for mut schedule in persisted {
+            if schedule.next_run_unix_ms <= now
+                && !matches!(schedule.timing, ScheduleTiming::Once { .. })
+            {
+                let missed = 1 + runs_between(&schedule.timing, schedule.next_run_unix_ms, now);
+                match next_recurring_run(&schedule.timing, schedule.next_run_unix_ms, now) {
+                    Some(next_run) => schedule.next_run_unix_ms = next_run,
+                    None => continue,
+                }
+                schedule.missed_runs += missed;
+                self.logger.log(
+                    &format!(
+                        "Schedule {} of '{}.{}' missed {} run(s) while the module was not loaded",
+                        schedule.id, module_name, schedule.reducer_name, missed
+                    ),
+                    LogSource::Runtime,
+                    LogLevel::Warning,
+                );
+            }
+            self.schedules
+                .next_id
+                .fetch_max(schedule.id + 1, Ordering::Relaxed);
+            restored.insert(schedule.id, schedule);
+        }
+
+        {
+            let mut by_module = self.schedules.by_module.lock();
+            self.persist_schedules(module_name, &restored);
+            by_module.insert(module_name.to_string(), restored);
+        }
+        self.schedules.changed.notify_one();
+        Ok(())
+    }
+
+    /// Stop running the schedules of a module being unloaded or removed. They stay
+    /// on disk for the next load.
+    pub(crate) fn drop_schedules(&self, module_name: &str) {
+        self.schedules.by_module.lock().remove(module_name);
+    }
+
+    fn persist_schedules(&self, module_name: &str, schedules: &BTreeMap<u64, ScheduleInfo>) {
+        self.persistence
+            .persist_schedules(module_name, schedules.values().cloned().collect());
+    }
+
+    /// Queue the reducer jobs of every schedule that came due, each holding a fork
+    /// of `completion` when given. Returns when the next pending schedule is due.
+    pub(crate) fn fire_due_schedules(&self, completion: Option<&CompletionToken>) -> Option<u64> {
+        let now = self.clock.now_unix_ms();
+        let mut by_module = self.schedules.by_module.lock();
+        let mut next_wake: Option<u64> = None;
+        for (module_name, schedules) in by_module.iter_mut() {
+            let due: Vec<u64> = schedules
+                .values()
+                .filter(|schedule| schedule.next_run_unix_ms <= now)
+                .map(|schedule| schedule.id)
+                .collect();
+            for id in &due {
+                let schedule = schedules.get_mut(id).expect("due schedule is pending");
+                let _ = self.reducer_ingress.send(ReducerJob {
+                    module_name: module_name.clone(),
+                    reducer_name: schedule.reducer_name.clone(),
+                    input: schedule.input.clone(),
+                    caller_node_id: self.network_handle.node_id,
+                    caller_module_name: String::new(),
+                    request_id: None,
+                    completion: completion.map(CompletionToken::fork),
+                    outcome: None,
+                    source: JobSource::Input,
+                    rng_seed: None,
+                });
+                // Runs that passed while the node could not keep up are skipped.
+                let skipped = runs_between(&schedule.timing, schedule.next_run_unix_ms, now);
+                match next_recurring_run(&schedule.timing, schedule.next_run_unix_ms, now) {
+                    Some(next_run) => {
+                        schedule.next_run_unix_ms = next_run;
+                        schedule.missed_runs += skipped;
+                    }
+                    None => {
+                        schedules.remove(id);
+                    }
+                }
+            }
+            if !due.is_empty() {
+                self.persist_schedules(module_name, schedules);
+            }
+            if let Some(earliest) = schedules.values().map(|s| s.next_run_unix_ms).min() {
+                next_wake = Some(next_wake.map_or(earliest, |wake| wake.min(earliest)));
+            }
+        }
+        next_wake
+    }
+
+    /// Timer task: a single task sleeps until the earliest pending schedule,
+    /// rather than one task per `ctx.schedule()` call. There is none under a
+    /// virtual clock, whose schedules fire as it is advanced.
+    pub(crate) async fn run_schedules(runtime: Arc<Runtime>) {
+        if runtime.clock.is_virtual() {
+            return;
+        }
+        loop {
+            let wait = match runtime.fire_due_schedules(None) {
+                Some(next_run) => {
+                    Duration::from_millis(next_run.saturating_sub(runtime.clock.now_unix_ms()))
+                }
+                None => IDLE_WAIT,
+            };
+            tokio::select! {
+                _ = tokio::time::sleep(wait) => {}
+                _ = runtime.schedules.changed.notified() => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestRuntime;
+    use crate::testing::fixture::{FixtureModule, Step};
+
+    #[test]
+    fn interval_runs_skip_past_occurrences() {
+        let timing = ScheduleTiming::Interval {
+            delay_ms: 0,
+            interval_ms: 100,
+        };
+        assert_eq!(next_recurring_run(&timing, 1_000, 1_000), Some(1_100));
+        assert_eq!(next_recurring_run(&timing, 1_000, 1_350), Some(1_400));
+        assert_eq!(runs_between(&timing, 1_000, 1_350), 3);
+        assert_eq!(runs_between(&timing, 1_000, 1_050), 0);
+    }
+
+    #[test]
+    fn once_has_no_next_run() {
+        let timing = ScheduleTiming::Once { delay_ms: 10 };
+        assert_eq!(next_recurring_run(&timing, 1_000, 2_000), None);
+        assert_eq!(runs_between(&timing, 1_000, 2_000), 0);
+    }
+
+    #[test]
+    fn cron_runs_are_counted() {
+        let timing = ScheduleTiming::Cron("*/10 * * * *".into());
+        // 00:00 → 01:00 contains the 00:10 … 01:00 runs.
+        assert_eq!(runs_between(&timing, 0, 3_600_000), 6);
+        assert_eq!(next_recurring_run(&timing, 0, 3_600_000), Some(4_200_000));
+    }
+
+    #[tokio::test]
+    async fn failed_reducers_leave_schedules_untouched() {
+        use interstice_abi::{HostCall, ScheduleCall, ScheduleRequest, ScheduleTiming};
+        let runtime = TestRuntime::starting_at(1_000).unwrap();
+        let schedule = || {
+            Step::HostCall(HostCall::Schedule(ScheduleCall::Create(ScheduleRequest {
+                reducer_name: "tick".into(),
+                input: IntersticeValue::Vec(Vec::new()),
+                timing: ScheduleTiming::Once { delay_ms: 1_000 },
+            })))
+        };
+        // Ids are handed out when a schedule is requested: the failed request takes 1.
+        let cancel = Step::HostCall(HostCall::Schedule(ScheduleCall::Cancel { schedule_id: 2 }));
+        let module = FixtureModule::new("timers")
+            .reducer("tick", Vec::new())
+            .reducer("plan", vec![schedule()])
+            .reducer("plan_then_fail", vec![schedule(), Step::Spin])
+            .with_reducer(|reducer| reducer.fuel = Some(10_000))
+            .reducer("cancel_then_fail", vec![cancel, Step::Spin])
+            .with_reducer(|reducer| reducer.fuel = Some(10_000));
+        runtime.load_module(&module.wasm()).await.unwrap();
+
+        assert!(
+            runtime
+                .call_reducer("timers", "plan_then_fail", Vec::new())
+                .await
+                .is_err()
+        );
+        assert!(runtime.schedules("timers").is_empty());
+
+        runtime
+            .call_reducer("timers", "plan", Vec::new())
+            .await
+            .unwrap();
+        assert_eq!(runtime.schedules("timers").len(), 1);
+        assert_eq!(runtime.schedules("timers")[0].id, 2);
+
+        assert!(
+            runtime
+                .call_reducer("timers", "cancel_then_fail", Vec::new())
+                .await
+                .is_err()
+        );
+        assert_eq!(runtime.schedules("timers").len(), 1);
+    }
+}
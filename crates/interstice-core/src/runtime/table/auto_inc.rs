@@ -118,6 +118,14 @@ impl AutoIncCounter {
         Ok(())
     }
 
+    /// Move the counter back from `current` to `start`, unless it is no longer at
+    /// `current`. Returns whether it moved.
+    pub fn rewind(&self, current: u64, start: u64) -> bool {
+        self.raw
+            .compare_exchange(current, start, Ordering::AcqRel, Ordering::Relaxed)
+            .is_ok()
+    }
+
     pub fn _reset(&self) {
         self.raw.store(0, Ordering::Relaxed);
     }
@@ -129,10 +137,75 @@ impl AutoIncCounter {
 /// concurrent reducer calls will ever receive the same ID.
 #[derive(Debug)]
 pub(crate) struct TableAutoIncSnapshot {
-    pub primary: Option<AutoIncCounter>,
-    pub indexes: Vec<Option<AutoIncCounter>>,
+    pub primary: Option<SnapshotCounter>,
+    pub indexes: Vec<Option<SnapshotCounter>>,
+}
+
+/// A counter of a [`TableAutoIncSnapshot`], with what the call drew from it, so an
+/// aborted atomic transaction can give its ids back.
+#[derive(Debug)]
+pub(crate) struct SnapshotCounter {
+    counter: AutoIncCounter,
+    /// Value of the counter when the snapshot was taken.
+    start: u64,
+    /// Ids drawn through the snapshot since.
+    drawn: u64,
+}
+
+impl SnapshotCounter {
+    fn new(counter: AutoIncCounter) -> Self {
+        let start = counter.raw.load(Ordering::Acquire);
+        Self {
+            counter,
+            start,
+            drawn: 0,
+        }
+    }
+
+    fn next_value(&mut self) -> Result<IntersticeValue, IntersticeError> {
+        // The counter moves even when the value overflows its type.
+        self.drawn += 1;
+        self.counter.next_value()
+    }
+}
+
+/// Give back the ids an aborted atomic transaction drew through `snapshots`, taken
+/// by its calls. A counter goes back to its value at the earliest snapshot only if
+/// every id drawn from it since came from these snapshots: otherwise another call
+/// holds an id past them and they stay used.
+pub(crate) fn rewind_auto_inc(snapshots: impl IntoIterator<Item = TableAutoIncSnapshot>) {
+    let mut counters: Vec<SnapshotCounter> = Vec::new();
+    let drawn = snapshots.into_iter().flat_map(|snapshot| {
+        snapshot
+            .primary
+            .into_iter()
+            .chain(snapshot.indexes.into_iter().flatten())
+    });
+    for counter in drawn {
+        match counters
+            .iter_mut()
+            .find(|seen| Arc::ptr_eq(&seen.counter.raw, &counter.counter.raw))
+        {
+            Some(seen) => {
+                seen.start = seen.start.min(counter.start);
+                seen.drawn += counter.drawn;
+            }
+            None => counters.push(counter),
+        }
+    }
+    for SnapshotCounter {
+        counter,
+        start,
+        drawn,
+    } in counters
+    {
+        if drawn > 0 {
+            counter.rewind(start + drawn, start);
+        }
+    }
 }
 
+#[derive(Clone)]
 pub enum IndexImpl {
     Hash(FastHashMap<IndexKey, Vec<usize>>),
     BTree(BTreeMap<IndexKey, Vec<usize>>),
@@ -144,11 +217,14 @@ impl Table {
     /// threads all advance the *same* atomic and receive non-overlapping IDs.
     pub(crate) fn auto_inc_snapshot(&self) -> TableAutoIncSnapshot {
         TableAutoIncSnapshot {
-            primary: self.primary_key_auto_inc_counter.clone(),
+            primary: self
+                .primary_key_auto_inc_counter
+                .clone()
+                .map(SnapshotCounter::new),
             indexes: self
                 .indexes
                 .iter()
-                .map(|index| index.auto_inc_counter.clone())
+                .map(|index| index.auto_inc_counter.clone().map(SnapshotCounter::new))
                 .collect(),
         }
     }
@@ -156,10 +232,10 @@ impl Table {
     pub(crate) fn apply_auto_inc_from_snapshot(
         &self,
         row: &mut Row,
-        snapshot: &TableAutoIncSnapshot,
+        snapshot: &mut TableAutoIncSnapshot,
     ) -> Result<(), IntersticeError> {
         if self.primary_key_auto_inc {
-            let counter = snapshot.primary.as_ref().ok_or_else(|| {
+            let counter = snapshot.primary.as_mut().ok_or_else(|| {
                 IntersticeError::Internal(format!(
                     "auto_inc counter missing for primary key in table '{}'",
                     self.schema.name
@@ -174,8 +250,8 @@ impl Table {
             }
             let counter = snapshot
                 .indexes
-                .get(index)
-                .and_then(|s| s.as_ref())
+                .get_mut(index)
+                .and_then(|s| s.as_mut())
                 .ok_or_else(|| {
                     IntersticeError::Internal(format!(
                         "auto_inc counter missing for field '{}' in table '{}'",
@@ -183,7 +259,7 @@ impl Table {
                     ))
                 })?;
             let value = counter.next_value()?;
-            if let Some(entry) = row.entries.get_mut(table_index.field_index) {
+            if let Some(entry) = row.entries.get_mut(table_index.field_indices[0]) {
                 *entry = value;
             } else {
                 return Err(IntersticeError::Internal(
@@ -0,0 +1,266 @@
+use crate::IntersticeError;
+use crate::runtime::module::Module;
+use crate::runtime::table::Table;
+use interstice_abi::{IndexKey, IndexQuery, Row, ScanOptions, TableScanIndex, TableSchema};
+use std::sync::Arc;
+
+/// An open `TableScanOpen` cursor. It remembers the last row it read and reads
+/// the rows after it batch by batch, in primary key order for full scans and in
+/// index key order for index scans, so rows deleted meanwhile are skipped and
+/// updated rows are read in their latest version. Cursors belong to the call
+/// frame that opened them and are dropped with it, or when their module is
+/// upgraded or unloaded.
+#[derive(Debug)]
+pub(crate) struct TableCursor {
+    pub module_name: String,
+    pub module_arc: Arc<Module>,
+    pub table_name: String,
+    /// Set when the table has a view: the rows the reader can't see are dropped
+    /// before the window is applied.
+    pub view_schema: Option<TableSchema>,
+    pub index: Option<TableScanIndex>,
+    pub reverse: bool,
+    pub after: Option<ScanPosition>,
+    /// Set once the last row matching the scan was read.
+    pub exhausted: bool,
+    pub skip: u64,
+    pub remaining: Option<u64>,
+    pub batch_size: usize,
+}
+
+/// A row of a scan, by index key for index scans and primary key.
+#[derive(Debug, Clone)]
+pub(crate) struct ScanPosition {
+    index_key: Option<IndexKey>,
+    primary_key: IndexKey,
+}
+
+impl TableCursor {
+    pub fn is_done(&self) -> bool {
+        self.exhausted || self.remaining == Some(0)
+    }
+
+    /// Drop the rows before the cursor's offset and stop at its limit.
+    pub fn take_window<T>(&mut self, items: Vec<T>) -> Vec<T> {
+        let skipped = (self.skip as usize).min(items.len());
+        self.skip -= skipped as u64;
+        let mut items: Vec<T> = items.into_iter().skip(skipped).collect();
+        if let Some(remaining) = &mut self.remaining {
+            items.truncate(*remaining as usize);
+            *remaining -= items.len() as u64;
+        }
+        items
+    }
+}
+
+impl Table {
+    /// Up to `count` rows after `after` of a full scan (`index: None`), in primary
+    /// key order, or of an index query, in index key then primary key order.
+    /// `reverse` walks the same order backwards.
+    pub(crate) fn scan_batch(
+        &self,
+        index: Option<(&str, &IndexQuery)>,
+        reverse: bool,
+        after: Option<&ScanPosition>,
+        count: usize,
+    ) -> Result<Vec<(ScanPosition, Row)>, IntersticeError> {
+        let Some((field_name, query)) = index else {
+            let order = self.scan_order.get_or_init(|| {
+                let mut keys: Vec<IndexKey> = self.primary_key_index.keys().cloned().collect();
+                keys.sort_unstable();
+                keys
+            });
+            let keys: Box<dyn Iterator<Item = &IndexKey>> = if reverse {
+                let end = after.map_or(order.len(), |after| {
+                    order.partition_point(|key| *key < after.primary_key)
+                });
+                Box::new(order[..end].iter().rev())
+            } else {
+                let start = after.map_or(0, |after| {
+                    order.partition_point(|key| *key <= after.primary_key)
+                });
+                Box::new(order[start..].iter())
+            };
+            return Ok(keys
+                .take(count)
+                .map(|primary_key| {
+                    let position = ScanPosition {
+                        index_key: None,
+                        primary_key: primary_key.clone(),
+                    };
+                    (position, self.rows[self.primary_key_index[primary_key]].clone())
+                })
+                .collect());
+        };
+
+        let from = after.and_then(|after| after.index_key.as_ref());
+        let entries = self
+            .table_index(field_name)?
+            .scan_entries(query, reverse, from, &self.schema.name)?;
+        let mut batch = Vec::new();
+        for (index_key, positions) in entries {
+            if batch.len() == count {
+                break;
+            }
+            let mut rows = positions
+                .iter()
+                .filter_map(|&position| self.rows.get(position))
+                .filter_map(|row| Some((IndexKey::try_from(&row.primary_key).ok()?, row)))
+                .collect::<Vec<_>>();
+            rows.sort_by(|(left, _), (right, _)| left.cmp(right));
+            if reverse {
+                rows.reverse();
+            }
+            let resumed = after.filter(|after| after.index_key.as_ref() == Some(index_key));
+            for (primary_key, row) in rows {
+                let read = resumed.is_some_and(|after| {
+                    if reverse {
+                        primary_key >= after.primary_key
+                    } else {
+                        primary_key <= after.primary_key
+                    }
+                });
+                if read {
+                    continue;
+                }
+                let position = ScanPosition {
+                    index_key: Some(index_key.clone()),
+                    primary_key,
+                };
+                batch.push((position, row.clone()));
+                if batch.len() == count {
+                    break;
+                }
+            }
+        }
+        Ok(batch)
+    }
+}
+
+/// Order `items` as requested and keep the `offset`/`limit` window.
+pub(crate) fn apply_scan_options<T>(mut items: Vec<T>, options: &ScanOptions) -> Vec<T> {
+    if options.reverse {
+        items.reverse();
+    }
+    let offset = (options.offset as usize).min(items.len());
+    items.drain(..offset);
+    if let Some(limit) = options.limit {
+        items.truncate(limit as usize);
+    }
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use interstice_abi::{
+        FieldDef, IndexSchema, IndexType, IntersticeType, IntersticeValue, PersistenceKind,
+        TableLimits, TableVisibility,
+    };
+
+    fn player(id: u64, score: u32) -> Row {
+        Row {
+            primary_key: IntersticeValue::U64(id),
+            entries: vec![IntersticeValue::U32(score)],
+        }
+    }
+
+    fn players() -> Table {
+        let field = |name: &str, field_type| FieldDef {
+            name: name.into(),
+            field_type,
+            default: None,
+        };
+        let mut table = Table::new(TableSchema {
+            name: "players".into(),
+            type_name: "Players".into(),
+            visibility: TableVisibility::Private,
+            fields: vec![field("score", IntersticeType::U32)],
+            primary_key: field("id", IntersticeType::U64),
+            primary_key_auto_inc: false,
+            indexes: vec![IndexSchema {
+                field_name: "score".into(),
+                index_type: IndexType::BTree,
+                unique: false,
+                auto_inc: false,
+                fields: Vec::new(),
+            }],
+            persistence: PersistenceKind::Logged,
+            limits: TableLimits::default(),
+            migrations: Vec::new(),
+            view: None,
+        });
+        for (id, score) in [(3, 20), (1, 30), (4, 10), (2, 20), (5, 40)] {
+            table.insert(player(id, score)).unwrap();
+        }
+        table
+    }
+
+    /// Ids of every row of a scan read two at a time, running `between` after the
+    /// first batch.
+    fn scan_ids(
+        table: &mut Table,
+        index: Option<(&str, &IndexQuery)>,
+        reverse: bool,
+        between: impl FnOnce(&mut Table),
+    ) -> Vec<u64> {
+        let mut between = Some(between);
+        let (mut ids, mut after) = (Vec::new(), None);
+        loop {
+            let batch = table.scan_batch(index, reverse, after.as_ref(), 2).unwrap();
+            ids.extend(batch.iter().map(|(_, row)| match row.primary_key {
+                IntersticeValue::U64(id) => id,
+                _ => unreachable!(),
+            }));
+            match batch.last() {
+                Some((position, _)) if batch.len() == 2 => after = Some(position.clone()),
+                _ => return ids,
+            }
+            if let Some(between) = between.take() {
+                between(table);
+            }
+        }
+    }
+
+    #[test]
+    fn scans_resume_after_the_last_row_read() {
+        let mut table = players();
+        assert_eq!(scan_ids(&mut table, None, false, |_| {}), vec![1, 2, 3, 4, 5]);
+        assert_eq!(scan_ids(&mut table, None, true, |_| {}), vec![5, 4, 3, 2, 1]);
+
+        // Index order, ties broken by primary key. A row whose key moves ahead of
+        // the cursor is read again, and rows deleted ahead of it are skipped.
+        let all = IndexQuery::All;
+        let ids = scan_ids(&mut table, Some(("score", &all)), false, |table| {
+            table.update(player(4, 50)).unwrap();
+            table.delete(&IndexKey::U64(1)).unwrap();
+        });
+        assert_eq!(ids, vec![4, 2, 3, 5, 4]);
+
+        let mut table = players();
+        let at_least_20 = IndexQuery::Gte(IndexKey::U32(20));
+        let ids = scan_ids(&mut table, Some(("score", &at_least_20)), true, |_| {});
+        assert_eq!(ids, vec![5, 1, 3, 2]);
+    }
+
+    #[test]
+    fn scan_options_window_and_reverse() {
+        let items: Vec<u32> = (0..10).collect();
+        let options = ScanOptions {
+            offset: 2,
+            limit: Some(3),
+            reverse: false,
+        };
+        assert_eq!(apply_scan_options(items.clone(), &options), vec![2, 3, 4]);
+        let options = ScanOptions {
+            reverse: true,
+            ..options
+        };
+        assert_eq!(apply_scan_options(items.clone(), &options), vec![7, 6, 5]);
+        let options = ScanOptions {
+            offset: 20,
+            ..ScanOptions::default()
+        };
+        assert!(apply_scan_options(items, &options).is_empty());
+    }
+}
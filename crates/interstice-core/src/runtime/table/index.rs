@@ -2,13 +2,43 @@ use crate::{
     IntersticeError,
     runtime::table::auto_inc::{AutoIncCounter, IndexImpl},
 };
-use interstice_abi::{IndexKey, IndexQuery, IndexSchema, IndexType, IntersticeType, Row};
+use interstice_abi::{
+    IndexKey, IndexQuery, IndexSchema, IndexType, IntersticeType, ModuleSchema, Row,
+};
 use std::collections::BTreeMap;
 use wgpu::naga::FastHashMap;
 
+/// Check that every index of the module's tables is keyed by fields of its table.
+pub(crate) fn validate_table_indexes(module_schema: &ModuleSchema) -> Result<(), IntersticeError> {
+    for table in &module_schema.tables {
+        for index in &table.indexes {
+            let invalid = |reason: String| IntersticeError::InvalidTableIndex {
+                module_name: module_schema.name.clone(),
+                table_name: table.name.clone(),
+                index_name: index.field_name.clone(),
+                reason,
+            };
+            if let Some(field_name) = index
+                .key_fields()
+                .iter()
+                .find(|field_name| !table.fields.iter().any(|field| &field.name == *field_name))
+            {
+                return Err(invalid(format!("field '{}' not found", field_name)));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Index entries (key and row positions) matched by a scan.
+pub type IndexEntries<'a> = Box<dyn Iterator<Item = (&'a IndexKey, &'a Vec<usize>)> + 'a>;
+
+#[derive(Clone)]
 pub struct TableIndex {
     pub field_name: String,
-    pub field_index: usize,
+    /// Positions of the key fields in the row entries; more than one for composite
+    /// indexes, whose keys are [`IndexKey::Tuple`]s.
+    pub field_indices: Vec<usize>,
     pub unique: bool,
     pub auto_inc: bool,
     pub auto_inc_counter: Option<AutoIncCounter>,
@@ -16,7 +46,7 @@ pub struct TableIndex {
 }
 
 impl TableIndex {
-    pub fn new(schema: &IndexSchema, field_index: usize, field_type: &IntersticeType) -> Self {
+    pub fn new(schema: &IndexSchema, field_indices: Vec<usize>, field_type: &IntersticeType) -> Self {
         let index = match schema.index_type {
             IndexType::Hash => IndexImpl::Hash(FastHashMap::default()),
             IndexType::BTree => IndexImpl::BTree(BTreeMap::new()),
@@ -28,7 +58,7 @@ impl TableIndex {
         };
         Self {
             field_name: schema.field_name.clone(),
-            field_index,
+            field_indices,
             unique: schema.unique || schema.auto_inc,
             auto_inc: schema.auto_inc,
             auto_inc_counter,
@@ -37,10 +67,20 @@ impl TableIndex {
     }
 
     pub fn key_from_row(&self, row: &Row) -> Result<IndexKey, IntersticeError> {
-        row.entries
-            .get(self.field_index)
-            .ok_or_else(|| IntersticeError::Internal("Index field out of bounds".to_string()))
-            .and_then(|value| value.clone().try_into().map_err(IntersticeError::Internal))
+        let field_key = |field_index: usize| {
+            row.entries
+                .get(field_index)
+                .ok_or_else(|| IntersticeError::Internal("Index field out of bounds".to_string()))
+                .and_then(|value| value.clone().try_into().map_err(IntersticeError::Internal))
+        };
+        match self.field_indices.as_slice() {
+            [field_index] => field_key(*field_index),
+            field_indices => field_indices
+                .iter()
+                .map(|&field_index| field_key(field_index))
+                .collect::<Result<Vec<_>, _>>()
+                .map(IndexKey::Tuple),
+        }
     }
 
     pub fn sync_auto_inc_from_row(
@@ -59,7 +99,7 @@ impl TableIndex {
         })?;
         let value = row
             .entries
-            .get(self.field_index)
+            .get(self.field_indices[0])
             .ok_or_else(|| IntersticeError::Internal("Index field out of bounds".to_string()))?;
         counter.sync_from_value(value)
     }
@@ -144,65 +184,122 @@ impl TableIndex {
         query: &IndexQuery,
         table_name: &str,
     ) -> Result<Vec<usize>, IntersticeError> {
-        match (&self.index, query) {
+        Ok(self
+            .scan_entries(query, false, None, table_name)?
+            .flat_map(|(_, positions)| positions.iter().copied())
+            .collect())
+    }
+
+    /// Entries of the index matching `query`, in key order or reversed, starting at
+    /// the key `from` (which must match the query) when set.
+    ///
+    /// Composite keys sort by their fields in order, so the keys starting with a
+    /// prefix are contiguous and ordered by the field that follows it: a prefix scan
+    /// starts at the lower bound and stops at the first key past the prefix or the
+    /// upper bound.
+    pub fn scan_entries<'a>(
+        &'a self,
+        query: &'a IndexQuery,
+        reverse: bool,
+        from: Option<&IndexKey>,
+        table_name: &str,
+    ) -> Result<IndexEntries<'a>, IntersticeError> {
+        use std::ops::Bound::{Excluded, Included, Unbounded};
+        let unsupported = || IntersticeError::IndexQueryUnsupported {
+            table_name: table_name.to_string(),
+            field_name: self.field_name.clone(),
+        };
+        let map = match (&self.index, query) {
             (IndexImpl::Hash(map), IndexQuery::Eq(key)) => {
-                Ok(map.get(key).cloned().unwrap_or_default())
+                return Ok(Box::new(map.get_key_value(key).into_iter()));
             }
-            (IndexImpl::Hash(_), _) => Err(IntersticeError::IndexQueryUnsupported {
-                table_name: table_name.to_string(),
-                field_name: self.field_name.clone(),
-            }),
-            (IndexImpl::BTree(map), IndexQuery::Eq(key)) => {
-                Ok(map.get(key).cloned().unwrap_or_default())
+            (IndexImpl::Hash(_), _) => return Err(unsupported()),
+            (IndexImpl::BTree(_), IndexQuery::Prefix { .. }) if self.field_indices.len() < 2 => {
+                return Err(unsupported());
             }
-            (IndexImpl::BTree(map), IndexQuery::Lt(key)) => Ok(map
-                .range(..key.clone())
-                .flat_map(|(_, v)| v.clone())
-                .collect()),
-            (IndexImpl::BTree(map), IndexQuery::Lte(key)) => Ok(map
-                .range(..=key.clone())
-                .flat_map(|(_, v)| v.clone())
-                .collect()),
-            (IndexImpl::BTree(map), IndexQuery::Gt(key)) => Ok(map
-                .range((
-                    std::ops::Bound::Excluded(key.clone()),
-                    std::ops::Bound::Unbounded,
-                ))
-                .flat_map(|(_, v)| v.clone())
-                .collect()),
-            (IndexImpl::BTree(map), IndexQuery::Gte(key)) => Ok(map
-                .range((
-                    std::ops::Bound::Included(key.clone()),
-                    std::ops::Bound::Unbounded,
-                ))
-                .flat_map(|(_, v)| v.clone())
-                .collect()),
-            (
-                IndexImpl::BTree(map),
-                IndexQuery::Range {
-                    min,
-                    max,
-                    include_min,
-                    include_max,
-                },
-            ) => {
-                use std::ops::Bound::{Excluded, Included};
-                let lower = if *include_min {
-                    Included(min.clone())
-                } else {
-                    Excluded(min.clone())
-                };
-                let upper = if *include_max {
-                    Included(max.clone())
-                } else {
-                    Excluded(max.clone())
-                };
-                Ok(map
-                    .range((lower, upper))
-                    .flat_map(|(_, v)| v.clone())
-                    .collect())
+            (IndexImpl::BTree(map), _) => map,
+        };
+        let bound = |key: &IndexKey, included: bool| {
+            if included {
+                Included(key.clone())
+            } else {
+                Excluded(key.clone())
             }
-        }
+        };
+        let (lower, upper) = match query {
+            IndexQuery::Eq(key) => (Included(key.clone()), Included(key.clone())),
+            IndexQuery::All => (Unbounded, Unbounded),
+            IndexQuery::Lt(key) => (Unbounded, Excluded(key.clone())),
+            IndexQuery::Lte(key) => (Unbounded, Included(key.clone())),
+            IndexQuery::Gt(key) => (Excluded(key.clone()), Unbounded),
+            IndexQuery::Gte(key) => (Included(key.clone()), Unbounded),
+            IndexQuery::Range {
+                min,
+                max,
+                include_min,
+                include_max,
+            } => (bound(min, *include_min), bound(max, *include_max)),
+            IndexQuery::Prefix { prefix, min, .. } => {
+                let mut start = prefix.clone();
+                start.extend(min.clone());
+                (Included(IndexKey::Tuple(start)), Unbounded)
+            }
+        };
+        let (lower, upper) = match from {
+            Some(from) if reverse => (lower, Included(from.clone())),
+            Some(from) => (Included(from.clone()), upper),
+            None => (lower, upper),
+        };
+        let entries = map.range((lower, upper));
+        let IndexQuery::Prefix {
+            prefix,
+            min,
+            max,
+            include_min,
+            include_max,
+        } = query
+        else {
+            return Ok(if reverse {
+                Box::new(entries.rev())
+            } else {
+                Box::new(entries)
+            });
+        };
+
+        // The field following the prefix, for the keys starting with it.
+        let next = move |key: &'a IndexKey| match key {
+            IndexKey::Tuple(fields) if fields.starts_with(prefix) => Some(fields.get(prefix.len())),
+            _ => None,
+        };
+        let below_max = move |next: Option<&IndexKey>| match (max, next) {
+            (Some(max), Some(next)) => next < max || (*include_max && next == max),
+            _ => true,
+        };
+        let above_min = move |next: Option<&IndexKey>| match (min, next) {
+            (Some(min), Some(next)) => next > min || (*include_min && next == min),
+            _ => true,
+        };
+        let in_prefix = move |(key, _): &(&'a IndexKey, &'a Vec<usize>)| next(key).is_some();
+        let matches = move |(key, _): &(&'a IndexKey, &'a Vec<usize>)| {
+            next(key).is_some_and(|next| below_max(next) && above_min(next))
+        };
+        Ok(if reverse {
+            Box::new(
+                entries
+                    .rev()
+                    .skip_while(move |entry| {
+                        !in_prefix(entry) || !below_max(next(entry.0).flatten())
+                    })
+                    .take_while(in_prefix)
+                    .filter(matches),
+            )
+        } else {
+            Box::new(
+                entries
+                    .take_while(move |entry| in_prefix(entry) && below_max(next(entry.0).flatten()))
+                    .filter(matches),
+            )
+        })
     }
 
     fn insert_into_hash(
@@ -305,3 +402,127 @@ impl TableIndex {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestRuntime;
+    use crate::testing::fixture::FixtureModule;
+    use interstice_abi::{IntersticeType, IntersticeValue, PersistenceKind};
+
+    fn composite_index(unique: bool) -> TableIndex {
+        let schema = IndexSchema {
+            field_name: "owner_created_at".into(),
+            index_type: IndexType::BTree,
+            unique,
+            auto_inc: false,
+            fields: vec!["owner".into(), "created_at".into()],
+        };
+        TableIndex::new(&schema, vec![0, 1], &IntersticeType::String)
+    }
+
+    fn row(owner: &str, created_at: u64) -> Row {
+        Row {
+            primary_key: IntersticeValue::U64(0),
+            entries: vec![
+                IntersticeValue::String(owner.into()),
+                IntersticeValue::U64(created_at),
+            ],
+        }
+    }
+
+    fn prefix(owner: &str, min: Option<u64>, max: Option<u64>, inclusive: bool) -> IndexQuery {
+        IndexQuery::Prefix {
+            prefix: vec![IndexKey::String(owner.into())],
+            min: min.map(IndexKey::U64),
+            max: max.map(IndexKey::U64),
+            include_min: inclusive,
+            include_max: inclusive,
+        }
+    }
+
+    #[test]
+    fn prefix_scans_stay_within_the_prefix() {
+        let mut index = composite_index(false);
+        let rows = [row("ada", 5), row("bob", 1), row("ada", 1), row("ada", 9), row("adam", 3)];
+        for (position, row) in rows.iter().enumerate() {
+            index.insert(position, index.key_from_row(row).unwrap(), "t").unwrap();
+        }
+
+        assert_eq!(index.scan(&prefix("ada", None, None, true), "t").unwrap(), vec![2, 0, 3]);
+        assert_eq!(index.scan(&prefix("ada", Some(1), Some(9), false), "t").unwrap(), vec![0]);
+        assert_eq!(index.scan(&prefix("ada", Some(5), None, true), "t").unwrap(), vec![0, 3]);
+        assert_eq!(index.scan(&prefix("ada", None, Some(5), true), "t").unwrap(), vec![2, 0]);
+        assert!(index.scan(&prefix("carl", None, None, true), "t").unwrap().is_empty());
+
+        let eq = IndexQuery::Eq(index.key_from_row(&row("ada", 9)).unwrap());
+        assert_eq!(index.scan(&eq, "t").unwrap(), vec![3]);
+        assert_eq!(index.scan(&IndexQuery::All, "t").unwrap(), vec![2, 0, 3, 4, 1]);
+    }
+
+    #[test]
+    fn unique_composite_keys_are_enforced() {
+        let mut index = composite_index(true);
+        index.insert(0, index.key_from_row(&row("ada", 1)).unwrap(), "t").unwrap();
+        index.insert(1, index.key_from_row(&row("ada", 2)).unwrap(), "t").unwrap();
+        assert!(matches!(
+            index.insert(2, index.key_from_row(&row("ada", 1)).unwrap(), "t"),
+            Err(IntersticeError::UniqueConstraintViolation { .. })
+        ));
+    }
+
+    #[test]
+    fn byte_vectors_written_before_bytes_share_keys_with_bytes() {
+        let schema = IndexSchema {
+            field_name: "avatar".into(),
+            index_type: IndexType::Hash,
+            unique: true,
+            auto_inc: false,
+            fields: Vec::new(),
+        };
+        let mut index = TableIndex::new(&schema, vec![0], &IntersticeType::Bytes);
+        let old_row = Row {
+            primary_key: IntersticeValue::U64(0),
+            entries: vec![IntersticeValue::Vec(vec![
+                IntersticeValue::U8(1),
+                IntersticeValue::U8(2),
+            ])],
+        };
+        index.insert(0, index.key_from_row(&old_row).unwrap(), "t").unwrap();
+
+        let eq = IndexQuery::Eq(IndexKey::Bytes(vec![1, 2]));
+        assert_eq!(index.scan(&eq, "t").unwrap(), vec![0]);
+        let new_row = Row {
+            primary_key: IntersticeValue::U64(1),
+            entries: vec![IntersticeValue::Bytes(vec![1, 2])],
+        };
+        assert!(matches!(
+            index.insert(1, index.key_from_row(&new_row).unwrap(), "t"),
+            Err(IntersticeError::UniqueConstraintViolation { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn indexes_on_unknown_fields_are_rejected_at_load() {
+        let runtime = TestRuntime::starting_at(1_000).unwrap();
+        let module = FixtureModule::new("typo")
+            .table(
+                "items",
+                PersistenceKind::Ephemeral,
+                &[("sku", IntersticeType::U64)],
+            )
+            .with_table(|table| {
+                table.indexes.push(IndexSchema {
+                    field_name: "skuu".into(),
+                    index_type: IndexType::Hash,
+                    unique: false,
+                    auto_inc: false,
+                    fields: Vec::new(),
+                })
+            });
+        assert!(matches!(
+            runtime.load_module(&module.wasm()).await,
+            Err(IntersticeError::InvalidTableIndex { .. })
+        ));
+    }
+}
@@ -1,17 +1,81 @@
 mod auto_inc;
+mod cursor;
 mod index;
+mod pending;
+mod query;
 
-pub(crate) use auto_inc::{AutoIncCounter, TableAutoIncSnapshot};
+pub(crate) use auto_inc::{AutoIncCounter, TableAutoIncSnapshot, rewind_auto_inc};
+pub(crate) use cursor::{TableCursor, apply_scan_options};
+pub(crate) use index::validate_table_indexes;
+pub(crate) use pending::PendingTableWrites;
 
 use crate::IntersticeError;
 use index::*;
 use interstice_abi::{IndexKey, IndexQuery, Row, TableSchema};
+use std::collections::HashMap;
+use std::sync::OnceLock;
 use wgpu::naga::FastHashMap;
 
+/// Net rows and bytes the writes a reducer call queued for one table add to it.
+/// Counted against the table quota together with the live rows; deletes and
+/// shrinking updates make them negative.
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct TableUsage {
+    pub rows: i64,
+    pub bytes: i64,
+}
+
+/// Quota usage of the writes a reducer call queued for one table.
+#[derive(Debug, Default)]
+pub(crate) struct PendingTableUsage {
+    pub usage: TableUsage,
+    /// Bytes counted for each stored row the queued writes replaced, `None` once
+    /// deleted.
+    touched: HashMap<IndexKey, Option<usize>>,
+}
+
+impl PendingTableUsage {
+    pub fn insert(&mut self, row_bytes: usize) {
+        self.usage.rows += 1;
+        self.usage.bytes += row_bytes as i64;
+    }
+
+    /// Bytes the stored row `key` of `stored_bytes` counts for once the queued
+    /// writes are applied, `None` when one of them deletes it.
+    pub fn row_bytes(&self, key: &IndexKey, stored_bytes: usize) -> Option<usize> {
+        self.touched.get(key).copied().unwrap_or(Some(stored_bytes))
+    }
+
+    /// Account for replacing the stored row `key` with a row of `row_bytes`.
+    pub fn update(&mut self, key: IndexKey, stored_bytes: usize, row_bytes: usize) {
+        if let Some(counted) = self.row_bytes(&key, stored_bytes) {
+            self.usage.bytes += row_bytes as i64 - counted as i64;
+            self.touched.insert(key, Some(row_bytes));
+        }
+    }
+
+    /// Account for deleting the stored row `key`.
+    pub fn delete(&mut self, key: IndexKey, stored_bytes: usize) {
+        if let Some(counted) = self.row_bytes(&key, stored_bytes) {
+            self.usage.rows -= 1;
+            self.usage.bytes -= counted as i64;
+            self.touched.insert(key, None);
+        }
+    }
+}
+
+/// Cloning copies the rows and indexes; auto-inc counters stay shared with the
+/// original, like the snapshots of [`Table::auto_inc_snapshot`].
+#[derive(Clone)]
 pub struct Table {
     pub schema: TableSchema,
     rows: Vec<Row>,
+    /// Summed encoded size of `rows`, only tracked when the table has a byte quota.
+    bytes_used: usize,
     primary_key_index: FastHashMap<IndexKey, usize>,
+    /// Sorted primary keys for scan cursors, built by the first full scan batch
+    /// after rows were added or removed so point writes keep a hash index only.
+    scan_order: OnceLock<Vec<IndexKey>>,
     indexes: Vec<TableIndex>,
     primary_key_auto_inc: bool,
     primary_key_auto_inc_counter: Option<AutoIncCounter>,
@@ -30,13 +94,13 @@ impl Table {
             .indexes
             .iter()
             .filter_map(|index_schema| {
-                field_positions
-                    .get(&index_schema.field_name)
-                    .and_then(|&field_index| {
-                        schema.fields.get(field_index).map(|field_def| {
-                            TableIndex::new(index_schema, field_index, &field_def.field_type)
-                        })
-                    })
+                let field_indices = index_schema
+                    .key_fields()
+                    .iter()
+                    .map(|field_name| field_positions.get(field_name).copied())
+                    .collect::<Option<Vec<_>>>()?;
+                let field_def = schema.fields.get(field_indices[0])?;
+                Some(TableIndex::new(index_schema, field_indices, &field_def.field_type))
             })
             .collect();
         let primary_key_auto_inc = schema.primary_key_auto_inc;
@@ -49,7 +113,9 @@ impl Table {
         Self {
             schema,
             rows: Vec::new(),
+            bytes_used: 0,
             primary_key_index: FastHashMap::default(),
+            scan_order: OnceLock::new(),
             indexes,
             primary_key_auto_inc,
             primary_key_auto_inc_counter,
@@ -87,6 +153,70 @@ impl Table {
         Ok(())
     }
 
+    /// Encoded size of `row` as counted by the byte quota (0 when the table has none).
+    pub(crate) fn quota_row_bytes(&self, row: &Row) -> usize {
+        if self.schema.limits.max_bytes.is_none() {
+            return 0;
+        }
+        interstice_abi::encode(row).map(|bytes| bytes.len()).unwrap_or(0)
+    }
+
+    /// Check that inserting one more row of `row_bytes` on top of `pending` keeps the
+    /// table within its `max_rows` / `max_bytes` quota.
+    pub(crate) fn validate_quota(
+        &self,
+        pending: TableUsage,
+        row_bytes: usize,
+    ) -> Result<(), IntersticeError> {
+        let limits = &self.schema.limits;
+        let over_rows = limits
+            .max_rows
+            .filter(|max_rows| self.rows.len() as i64 + pending.rows >= *max_rows as i64);
+        if let Some(max_rows) = over_rows {
+            return Err(IntersticeError::TableQuotaExceeded {
+                table_name: self.schema.name.clone(),
+                quota: format!("max_rows = {}", max_rows),
+            });
+        }
+        self.validate_byte_quota(pending, row_bytes as i64)
+    }
+
+    /// Check that `added_bytes` more on top of `pending` keeps the table within its
+    /// `max_bytes` quota.
+    pub(crate) fn validate_byte_quota(
+        &self,
+        pending: TableUsage,
+        added_bytes: i64,
+    ) -> Result<(), IntersticeError> {
+        let over_bytes = self.schema.limits.max_bytes.filter(|max_bytes| {
+            self.bytes_used as i64 + pending.bytes + added_bytes > *max_bytes as i64
+        });
+        if let Some(max_bytes) = over_bytes {
+            return Err(IntersticeError::TableQuotaExceeded {
+                table_name: self.schema.name.clone(),
+                quota: format!("max_bytes = {}", max_bytes),
+            });
+        }
+        Ok(())
+    }
+
+    /// Encoded size of the stored row `key` as counted by the byte quota (0 when the
+    /// table has none or the row is missing).
+    pub(crate) fn quota_stored_bytes(&self, key: &IndexKey) -> usize {
+        self.get_by_primary_key(key)
+            .map(|row| self.quota_row_bytes(row))
+            .unwrap_or(0)
+    }
+
+    /// Bytes that replacing the stored row with `row` adds to the byte quota,
+    /// negative when the row shrinks (0 when the table has no byte quota).
+    pub(crate) fn quota_update_bytes(&self, row: &Row) -> i64 {
+        let stored_bytes = IndexKey::try_from(&row.primary_key)
+            .map(|key| self.quota_stored_bytes(&key))
+            .unwrap_or(0);
+        self.quota_row_bytes(row) as i64 - stored_bytes as i64
+    }
+
     pub fn validate_update(&self, row: &Row) -> Result<(), IntersticeError> {
         let primary_key_value: IndexKey = IndexKey::try_from(&row.primary_key)
             .map_err(|err| IntersticeError::Internal(err))?;
@@ -158,6 +288,8 @@ impl Table {
         }
 
         self.primary_key_index.insert(primary_key_value, index);
+        self.scan_order.take();
+        self.bytes_used += self.quota_row_bytes(&row);
         self.rows.push(row);
         Ok(())
     }
@@ -177,6 +309,8 @@ impl Table {
         }
 
         self.primary_key_index.insert(primary_key_value, index);
+        self.scan_order.take();
+        self.bytes_used += self.quota_row_bytes(&row);
         self.rows.push(row);
         Ok(())
     }
@@ -185,7 +319,9 @@ impl Table {
         let primary_key_value: IndexKey = IndexKey::try_from(&row.primary_key)
             .map_err(|err| IntersticeError::Internal(err))?;
         if let Some(&index) = self.primary_key_index.get(&primary_key_value) {
+            let new_bytes = self.quota_row_bytes(&row);
             let old_row = std::mem::replace(&mut self.rows[index], row);
+            self.bytes_used = (self.bytes_used + new_bytes).saturating_sub(self.quota_row_bytes(&old_row));
             for table_index in &mut self.indexes {
                 let old_key = table_index.key_from_row(&old_row)?;
                 let new_key = table_index.key_from_row(&self.rows[index])?;
@@ -201,6 +337,8 @@ impl Table {
         if let Some(&index) = self.primary_key_index.get(primary_key_value) {
             let deleted_row = self.rows.swap_remove(index);
             self.primary_key_index.remove(primary_key_value);
+            self.scan_order.take();
+            self.bytes_used = self.bytes_used.saturating_sub(self.quota_row_bytes(&deleted_row));
 
             for table_index in &mut self.indexes {
                 let deleted_key = table_index.key_from_row(&deleted_row)?;
@@ -236,7 +374,9 @@ impl Table {
         // a fresh Arc::new(0), those old snapshots would issue IDs that collide
         // with new inserts once the new counter reaches the same values.
         self.rows.clear();
+        self.bytes_used = 0;
         self.primary_key_index.clear();
+        self.scan_order.take();
         for index in &mut self.indexes {
             match &mut index.index {
                 crate::runtime::table::auto_inc::IndexImpl::Hash(m) => m.clear(),
@@ -260,20 +400,29 @@ impl Table {
         field_name: &str,
         query: &IndexQuery,
     ) -> Result<Vec<&Row>, IntersticeError> {
-        let table_index = self
-            .indexes
+        let positions = self.index_positions(field_name, query)?;
+        Ok(positions
+            .into_iter()
+            .filter_map(|pos| self.rows.get(pos))
+            .collect())
+    }
+
+    fn index_positions(
+        &self,
+        field_name: &str,
+        query: &IndexQuery,
+    ) -> Result<Vec<usize>, IntersticeError> {
+        self.table_index(field_name)?.scan(query, &self.schema.name)
+    }
+
+    fn table_index(&self, field_name: &str) -> Result<&TableIndex, IntersticeError> {
+        self.indexes
             .iter()
             .find(|index| index.field_name == field_name)
             .ok_or_else(|| IntersticeError::IndexNotFound {
                 table_name: self.schema.name.clone(),
                 field_name: field_name.to_string(),
-            })?;
-
-        let positions = table_index.scan(query, &self.schema.name)?;
-        Ok(positions
-            .into_iter()
-            .filter_map(|pos| self.rows.get(pos))
-            .collect())
+            })
     }
 
     pub fn snapshot_rows(&self) -> Vec<Row> {
@@ -0,0 +1,252 @@
+use super::{Table, TableUsage};
+use crate::IntersticeError;
+use interstice_abi::{IndexKey, Row};
+use std::collections::HashMap;
+
+/// Writes an atomic transaction queued for one table, checked one at a time
+/// against the live table as if the ones before had been applied, without
+/// copying its rows.
+#[derive(Default)]
+pub(crate) struct PendingTableWrites {
+    /// Row left by the queued writes for each primary key they touched, `None`
+    /// once deleted.
+    rows: HashMap<IndexKey, Option<Row>>,
+    /// Set by a queued clear: the live rows no longer count.
+    cleared: bool,
+    /// Keys of the unique indexes held by the rows in `rows`, with the primary key
+    /// holding them, by position of the index in the table.
+    unique_keys: HashMap<usize, HashMap<IndexKey, IndexKey>>,
+    usage: TableUsage,
+}
+
+impl PendingTableWrites {
+    pub fn insert(&mut self, table: &Table, row: &Row) -> Result<(), IntersticeError> {
+        let primary_key_value = row_primary_key(row)?;
+        if self.row(table, &primary_key_value).is_some() {
+            return Err(IntersticeError::UniqueConstraintViolation {
+                table_name: table.schema.name.clone(),
+                field_name: table.schema.primary_key.name.clone(),
+            });
+        }
+        let row_bytes = table.quota_row_bytes(row);
+        table.validate_quota(self.usage, row_bytes)?;
+        for (position, table_index) in table.indexes.iter().enumerate() {
+            if table_index.unique {
+                let key = table_index.key_from_row(row)?;
+                self.validate_unique(table, position, &key, &primary_key_value)?;
+            }
+        }
+
+        self.usage.rows += 1;
+        self.usage.bytes += row_bytes as i64;
+        self.replace(table, primary_key_value, Some(row.clone()))
+    }
+
+    pub fn update(&mut self, table: &Table, row: &Row) -> Result<(), IntersticeError> {
+        let primary_key_value = row_primary_key(row)?;
+        let existing_row =
+            self.row(table, &primary_key_value)
+                .ok_or_else(|| IntersticeError::RowNotFound {
+                    primary_key_value: primary_key_value.clone(),
+                })?;
+        let added_bytes =
+            table.quota_row_bytes(row) as i64 - table.quota_row_bytes(existing_row) as i64;
+
+        for (position, table_index) in table.indexes.iter().enumerate() {
+            let old_key = table_index.key_from_row(existing_row)?;
+            let new_key = table_index.key_from_row(row)?;
+
+            if table_index.auto_inc && old_key != new_key {
+                return Err(IntersticeError::AutoIncUpdateNotAllowed {
+                    table_name: table.schema.name.clone(),
+                    field_name: table_index.field_name.clone(),
+                });
+            }
+            if table_index.unique && old_key != new_key {
+                self.validate_unique(table, position, &new_key, &primary_key_value)?;
+            }
+        }
+        table.validate_byte_quota(self.usage, added_bytes)?;
+
+        self.usage.bytes += added_bytes;
+        self.replace(table, primary_key_value, Some(row.clone()))
+    }
+
+    /// A missing row is skipped, as on commit.
+    pub fn delete(
+        &mut self,
+        table: &Table,
+        primary_key_value: &IndexKey,
+    ) -> Result<(), IntersticeError> {
+        let Some(existing_row) = self.row(table, primary_key_value) else {
+            return Ok(());
+        };
+        let row_bytes = table.quota_row_bytes(existing_row);
+        self.usage.rows -= 1;
+        self.usage.bytes -= row_bytes as i64;
+        self.replace(table, primary_key_value.clone(), None)
+    }
+
+    pub fn clear(&mut self, table: &Table) {
+        self.rows.clear();
+        self.unique_keys.clear();
+        self.cleared = true;
+        self.usage = TableUsage {
+            rows: -(table.rows.len() as i64),
+            bytes: -(table.bytes_used as i64),
+        };
+    }
+
+    /// Row `primary_key_value` once the queued writes are applied.
+    fn row<'a>(&'a self, table: &'a Table, primary_key_value: &IndexKey) -> Option<&'a Row> {
+        match self.rows.get(primary_key_value) {
+            Some(row) => row.as_ref(),
+            None if self.cleared => None,
+            None => table.get_by_primary_key(primary_key_value),
+        }
+    }
+
+    /// Check that no row other than `primary_key_value` holds `key` in the unique
+    /// index at `position` once the queued writes are applied.
+    fn validate_unique(
+        &self,
+        table: &Table,
+        position: usize,
+        key: &IndexKey,
+        primary_key_value: &IndexKey,
+    ) -> Result<(), IntersticeError> {
+        let mut held = self
+            .unique_keys
+            .get(&position)
+            .and_then(|keys| keys.get(key))
+            .is_some_and(|holder| holder != primary_key_value);
+        // Live rows touched by the queued writes hold the keys recorded above.
+        if !held && !self.cleared {
+            for &row_position in table.indexes[position].positions(key).into_iter().flatten() {
+                let holder = row_primary_key(&table.rows[row_position])?;
+                if holder != *primary_key_value && !self.rows.contains_key(&holder) {
+                    held = true;
+                    break;
+                }
+            }
+        }
+        if held {
+            return Err(IntersticeError::UniqueConstraintViolation {
+                table_name: table.schema.name.clone(),
+                field_name: table.indexes[position].field_name.clone(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Record `row` as what the queued writes leave for `primary_key_value`.
+    fn replace(
+        &mut self,
+        table: &Table,
+        primary_key_value: IndexKey,
+        row: Option<Row>,
+    ) -> Result<(), IntersticeError> {
+        for (position, table_index) in table.indexes.iter().enumerate() {
+            if !table_index.unique {
+                continue;
+            }
+            let old_key = self
+                .row(table, &primary_key_value)
+                .map(|row| table_index.key_from_row(row))
+                .transpose()?;
+            let new_key = row
+                .as_ref()
+                .map(|row| table_index.key_from_row(row))
+                .transpose()?;
+            let keys = self.unique_keys.entry(position).or_default();
+            if let Some(old_key) = old_key
+                && keys.get(&old_key) == Some(&primary_key_value)
+            {
+                keys.remove(&old_key);
+            }
+            if let Some(new_key) = new_key {
+                keys.insert(new_key, primary_key_value.clone());
+            }
+        }
+        self.rows.insert(primary_key_value, row);
+        Ok(())
+    }
+}
+
+fn row_primary_key(row: &Row) -> Result<IndexKey, IntersticeError> {
+    IndexKey::try_from(&row.primary_key).map_err(IntersticeError::Internal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use interstice_abi::{
+        FieldDef, IndexSchema, IndexType, IntersticeType, IntersticeValue, PersistenceKind,
+        TableLimits, TableSchema, TableVisibility,
+    };
+
+    fn user(id: u64, name: &str) -> Row {
+        Row {
+            primary_key: IntersticeValue::U64(id),
+            entries: vec![IntersticeValue::String(name.into())],
+        }
+    }
+
+    /// Users with a unique name, at most two of them, holding `names`.
+    fn users(names: &[(u64, &str)]) -> Table {
+        let field = |name: &str, field_type| FieldDef {
+            name: name.into(),
+            field_type,
+            default: None,
+        };
+        let mut table = Table::new(TableSchema {
+            name: "users".into(),
+            type_name: "Users".into(),
+            visibility: TableVisibility::Private,
+            fields: vec![field("name", IntersticeType::String)],
+            primary_key: field("id", IntersticeType::U64),
+            primary_key_auto_inc: false,
+            indexes: vec![IndexSchema {
+                field_name: "name".into(),
+                index_type: IndexType::Hash,
+                unique: true,
+                auto_inc: false,
+                fields: Vec::new(),
+            }],
+            persistence: PersistenceKind::Ephemeral,
+            limits: TableLimits {
+                max_rows: Some(2),
+                ..TableLimits::default()
+            },
+            migrations: Vec::new(),
+            view: None,
+        });
+        for (id, name) in names {
+            table.insert(user(*id, name)).unwrap();
+        }
+        table
+    }
+
+    #[test]
+    fn writes_are_checked_on_top_of_the_ones_queued_before() {
+        let table = users(&[(1, "ada"), (2, "bob")]);
+        let mut pending = PendingTableWrites::default();
+
+        // Full, and the name is taken by a live row.
+        assert!(pending.insert(&table, &user(3, "cy")).is_err());
+        assert!(pending.update(&table, &user(2, "ada")).is_err());
+
+        pending.update(&table, &user(1, "ann")).unwrap();
+        pending.update(&table, &user(2, "ada")).unwrap();
+        pending.delete(&table, &IndexKey::U64(1)).unwrap();
+        pending.insert(&table, &user(3, "ann")).unwrap();
+        assert!(pending.insert(&table, &user(4, "dan")).is_err());
+        assert!(pending.insert(&table, &user(2, "eve")).is_err());
+        assert!(pending.update(&table, &user(3, "ada")).is_err());
+
+        pending.clear(&table);
+        pending.insert(&table, &user(1, "ada")).unwrap();
+        pending.insert(&table, &user(2, "bob")).unwrap();
+        assert!(pending.insert(&table, &user(3, "cy")).is_err());
+    }
+}
@@ -0,0 +1,277 @@
+//! Host-side evaluation of `TableQuery` filters.
+//!
+//! The candidate rows come from the cheapest access path among the filter's
+//! top-level conditions: a primary key lookup, then an equality (or `In`) lookup on
+//! a single-field index, then a range on a btree index, and a full scan otherwise.
+//! The whole filter is then checked on every candidate.
+
+use super::{Table, auto_inc::IndexImpl, index::TableIndex};
+use crate::IntersticeError;
+use interstice_abi::{CompareOp, IndexKey, IndexQuery, Row, RowFilter, compare_values};
+use std::cmp::Ordering;
+use std::collections::HashSet;
+
+enum Access<'a> {
+    PrimaryKeys(Vec<IndexKey>),
+    Index {
+        index: &'a TableIndex,
+        queries: Vec<IndexQuery>,
+    },
+}
+
+impl Access<'_> {
+    fn cost(&self) -> u8 {
+        match self {
+            Access::PrimaryKeys(_) => 0,
+            Access::Index { queries, .. } if queries.iter().all(|q| matches!(q, IndexQuery::Eq(_))) => 1,
+            Access::Index { .. } => 2,
+        }
+    }
+}
+
+impl Table {
+    /// Rows matching `filter`, sorted on `order_by` when set.
+    pub fn query(
+        &self,
+        filter: Option<&RowFilter>,
+        order_by: Option<&str>,
+    ) -> Result<Vec<&Row>, IntersticeError> {
+        let invalid = |reason: String| IntersticeError::InvalidTableQuery {
+            table_name: self.schema.name.clone(),
+            reason,
+        };
+        if let Some(filter) = filter {
+            filter.validate(&self.schema).map_err(invalid)?;
+        }
+        if let Some(field) = order_by
+            && self.schema.field_position(field).is_none()
+        {
+            return Err(invalid(format!("cannot order by unknown field '{}'", field)));
+        }
+
+        let access = filter.and_then(|filter| {
+            let conditions = match filter {
+                RowFilter::And(filters) => filters.iter().collect(),
+                filter => vec![filter],
+            };
+            conditions
+                .into_iter()
+                .filter_map(|condition| self.access_for(condition))
+                .min_by_key(Access::cost)
+        });
+
+        let mut sorted = false;
+        let positions: Vec<usize> = match access {
+            Some(Access::PrimaryKeys(keys)) => {
+                let mut seen = HashSet::new();
+                keys.iter()
+                    .filter_map(|key| self.primary_key_index.get(key).copied())
+                    .filter(|position| seen.insert(*position))
+                    .collect()
+            }
+            Some(Access::Index { index, queries }) => {
+                sorted = queries.len() == 1 && Some(index.field_name.as_str()) == order_by;
+                let mut seen = HashSet::new();
+                let mut positions = Vec::new();
+                for query in &queries {
+                    let matched = index.scan(query, &self.schema.name)?;
+                    positions.extend(matched.into_iter().filter(|position| seen.insert(*position)));
+                }
+                positions
+            }
+            None => match order_by.and_then(|field| self.btree_index(field)) {
+                Some(index) => {
+                    sorted = true;
+                    index.scan(&IndexQuery::All, &self.schema.name)?
+                }
+                None => (0..self.rows.len()).collect(),
+            },
+        };
+
+        let mut rows: Vec<&Row> = positions
+            .into_iter()
+            .filter_map(|position| self.rows.get(position))
+            .filter(|row| filter.is_none_or(|filter| filter.matches(&self.schema, row)))
+            .collect();
+        if let (Some(field), false) = (order_by, sorted) {
+            rows.sort_by(|left, right| {
+                let left = self.schema.field_value(left, field);
+                let right = self.schema.field_value(right, field);
+                match (left, right) {
+                    (Some(left), Some(right)) => compare_values(left, right).unwrap_or(Ordering::Equal),
+                    _ => Ordering::Equal,
+                }
+            });
+        }
+        Ok(rows)
+    }
+
+    fn access_for(&self, condition: &RowFilter) -> Option<Access<'_>> {
+        let (field, queries) = match condition {
+            RowFilter::Compare { field, op, value } => {
+                let key = IndexKey::try_from(value).ok()?;
+                let query = match op {
+                    CompareOp::Eq => IndexQuery::Eq(key),
+                    CompareOp::Lt => IndexQuery::Lt(key),
+                    CompareOp::Lte => IndexQuery::Lte(key),
+                    CompareOp::Gt => IndexQuery::Gt(key),
+                    CompareOp::Gte => IndexQuery::Gte(key),
+                    CompareOp::Ne => return None,
+                };
+                (field, vec![query])
+            }
+            RowFilter::In { field, values } => {
+                let keys = values
+                    .iter()
+                    .map(|value| IndexKey::try_from(value).ok().map(IndexQuery::Eq))
+                    .collect::<Option<Vec<_>>>()?;
+                (field, keys)
+            }
+            _ => return None,
+        };
+
+        let all_eq = queries.iter().all(|query| matches!(query, IndexQuery::Eq(_)));
+        if *field == self.schema.primary_key.name {
+            if !all_eq {
+                return None;
+            }
+            let keys = queries
+                .into_iter()
+                .filter_map(|query| match query {
+                    IndexQuery::Eq(key) => Some(key),
+                    _ => None,
+                })
+                .collect();
+            return Some(Access::PrimaryKeys(keys));
+        }
+
+        let index = self
+            .indexes
+            .iter()
+            .find(|index| index.field_indices.len() == 1 && index.field_name == *field)?;
+        if !all_eq && !matches!(index.index, IndexImpl::BTree(_)) {
+            return None;
+        }
+        Some(Access::Index { index, queries })
+    }
+
+    fn btree_index(&self, field: &str) -> Option<&TableIndex> {
+        self.indexes.iter().find(|index| {
+            index.field_indices.len() == 1
+                && index.field_name == field
+                && matches!(index.index, IndexImpl::BTree(_))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use interstice_abi::{
+        FieldDef, IndexSchema, IndexType, IntersticeType, IntersticeValue, PersistenceKind,
+        TableLimits, TableSchema, TableVisibility,
+    };
+
+    fn table() -> Table {
+        let field = |name: &str, field_type| FieldDef {
+            name: name.into(),
+            field_type,
+            default: None,
+        };
+        let index = |field_name: &str, index_type| IndexSchema {
+            field_name: field_name.into(),
+            index_type,
+            unique: false,
+            auto_inc: false,
+            fields: Vec::new(),
+        };
+        let mut table = Table::new(TableSchema {
+            name: "players".into(),
+            type_name: "Players".into(),
+            visibility: TableVisibility::Private,
+            fields: vec![
+                field("name", IntersticeType::String),
+                field("score", IntersticeType::U32),
+                field("team", IntersticeType::U32),
+            ],
+            primary_key: field("id", IntersticeType::U64),
+            primary_key_auto_inc: false,
+            indexes: vec![index("score", IndexType::BTree), index("team", IndexType::Hash)],
+            persistence: PersistenceKind::Logged,
+            limits: TableLimits::default(),
+            migrations: Vec::new(),
+            view: None,
+        });
+        for (id, name, score, team) in [(1, "ada", 30, 1), (2, "bob", 10, 2), (3, "cyd", 20, 1), (4, "dan", 40, 2)] {
+            table
+                .insert(Row {
+                    primary_key: IntersticeValue::U64(id),
+                    entries: vec![
+                        IntersticeValue::String(name.into()),
+                        IntersticeValue::U32(score),
+                        IntersticeValue::U32(team),
+                    ],
+                })
+                .unwrap();
+        }
+        table
+    }
+
+    fn ids(rows: Vec<&Row>) -> Vec<u64> {
+        rows.into_iter()
+            .map(|row| match row.primary_key {
+                IntersticeValue::U64(id) => id,
+                _ => unreachable!(),
+            })
+            .collect()
+    }
+
+    fn compare(field: &str, op: CompareOp, value: u32) -> RowFilter {
+        RowFilter::Compare {
+            field: field.into(),
+            op,
+            value: IntersticeValue::U32(value),
+        }
+    }
+
+    #[test]
+    fn filters_use_indexes_and_check_every_condition() {
+        let table = table();
+        let filter = RowFilter::And(vec![
+            compare("team", CompareOp::Eq, 1),
+            compare("score", CompareOp::Gt, 15),
+            RowFilter::StartsWith {
+                field: "name".into(),
+                prefix: "c".into(),
+            },
+        ]);
+        assert_eq!(ids(table.query(Some(&filter), None).unwrap()), vec![3]);
+
+        let by_id = RowFilter::In {
+            field: "id".into(),
+            values: vec![IntersticeValue::U64(4), IntersticeValue::U64(2), IntersticeValue::U64(4)],
+        };
+        assert_eq!(ids(table.query(Some(&by_id), None).unwrap()), vec![4, 2]);
+    }
+
+    #[test]
+    fn results_are_ordered() {
+        let table = table();
+        // Ordered by walking the btree index.
+        assert_eq!(ids(table.query(None, Some("score")).unwrap()), vec![2, 3, 1, 4]);
+        // Ordered by sorting the matched rows.
+        let team_two = compare("team", CompareOp::Eq, 2);
+        assert_eq!(ids(table.query(Some(&team_two), Some("name")).unwrap()), vec![2, 4]);
+    }
+
+    #[test]
+    fn unknown_fields_are_rejected() {
+        let table = table();
+        let filter = compare("level", CompareOp::Eq, 1);
+        assert!(matches!(
+            table.query(Some(&filter), None),
+            Err(IntersticeError::InvalidTableQuery { .. })
+        ));
+        assert!(table.query(None, Some("level")).is_err());
+    }
+}
@@ -0,0 +1,375 @@
+//! Fixed-timestep tick subscriptions (`#[reducer(on = "tick(8ms)")]`).
+//!
+//! Each subscription is driven by its own task on the runtime's monotonic clock:
+//! tick `n` is due `n + 1` periods after the subscription started, so reducer
+//! latency does not make the ticks drift. A tick is queued only once the previous
+//! one completed; the ticks that came due in the meantime are handled according to
+//! the subscription's [`TickOverrun`] policy.
+//!
+//! Under a virtual clock there are no tasks: the ticks come due as the clock is
+//! advanced, like the schedules, with the clock's millisecond resolution.
+
+use crate::{
+    error::IntersticeError,
+    runtime::{
+        Runtime,
+        reducer::{CompletionToken, JobSource, ReducerJob},
+    },
+};
+use interstice_abi::{
+    IntersticeValue, ModuleSchema, SubscriptionEventSchema, TickEvent, TickOverrun,
+};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+struct TickTask {
+    reducer_name: String,
+    event: SubscriptionEventSchema,
+    driver: TickDriver,
+}
+
+enum TickDriver {
+    Task(JoinHandle<()>),
+    /// Clock of a tick following the virtual clock, in microseconds since the epoch.
+    Virtual(TickClock),
+}
+
+impl TickTask {
+    fn stop(&self) {
+        if let TickDriver::Task(handle) = &self.driver {
+            handle.abort();
+        }
+    }
+}
+
+/// Running tick tasks of every loaded module.
+pub(crate) struct Ticks {
+    by_module: Mutex<HashMap<String, Vec<TickTask>>>,
+}
+
+impl Ticks {
+    pub fn new() -> Self {
+        Self {
+            by_module: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+/// Check that every tick subscription has a non-zero period.
+pub(crate) fn validate_tick_subscriptions(
+    module_schema: &ModuleSchema,
+) -> Result<(), IntersticeError> {
+    for sub in &module_schema.subscriptions {
+        if let SubscriptionEventSchema::Tick { period_us: 0, .. } = sub.event {
+            return Err(IntersticeError::InvalidSubscription {
+                module_name: module_schema.name.clone(),
+                reducer_name: sub.reducer_name.clone(),
+                reason: "tick period must be greater than zero".into(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Monotonic clock of a single tick subscription, with times in microseconds.
+struct TickClock {
+    start: u64,
+    period_us: u64,
+    overrun: TickOverrun,
+    next_index: u64,
+    last_delivery: u64,
+}
+
+impl TickClock {
+    fn new(start: u64, period_us: u64, overrun: TickOverrun) -> Self {
+        Self {
+            start,
+            period_us,
+            overrun,
+            next_index: 0,
+            last_delivery: start,
+        }
+    }
+
+    fn next_deadline(&self) -> u64 {
+        self.start
+            .saturating_add(self.period_us.saturating_mul(self.next_index + 1))
+    }
+
+    /// The tick to deliver at `now`, which is at or after `next_deadline()`.
+    fn advance(&mut self, now: u64) -> TickEvent {
+        let due = now.saturating_sub(self.start) / self.period_us;
+        let pending = due.saturating_sub(self.next_index).max(1);
+        let elapsed_us = now.saturating_sub(self.last_delivery);
+        self.last_delivery = now;
+
+        let (steps, skipped) = match self.overrun {
+            // Deliver the oldest pending tick; the next one is already due.
+            TickOverrun::CatchUp => (1, 0),
+            TickOverrun::Skip => (1, pending - 1),
+            TickOverrun::Coalesce => (pending, 0),
+        };
+        let index = self.next_index + steps + skipped - 1;
+        self.next_index = index + 1;
+        TickEvent {
+            index,
+            elapsed_us,
+            steps,
+            skipped,
+        }
+    }
+}
+
+impl Runtime {
+    /// Start the tick subscriptions of a loaded or upgraded module. Ticks already
+    /// running with the same period and policy keep their clock; the ones the
+    /// module no longer declares are stopped.
+    pub(crate) fn sync_ticks(self: &Arc<Self>, module_schema: &ModuleSchema) {
+        let mut by_module = self.ticks.by_module.lock();
+        let tasks = by_module.entry(module_schema.name.clone()).or_default();
+        tasks.retain(|task| {
+            let declared = module_schema
+                .subscriptions
+                .iter()
+                .any(|sub| sub.reducer_name == task.reducer_name && sub.event == task.event);
+            if !declared {
+                task.stop();
+            }
+            declared
+        });
+
+        for sub in &module_schema.subscriptions {
+            let SubscriptionEventSchema::Tick { period_us, overrun } = sub.event else {
+                continue;
+            };
+            if tasks
+                .iter()
+                .any(|task| task.reducer_name == sub.reducer_name && task.event == sub.event)
+            {
+                continue;
+            }
+            let driver = if self.clock.is_virtual() {
+                let now_us = self.clock.now_unix_ms().saturating_mul(1_000);
+                TickDriver::Virtual(TickClock::new(now_us, period_us, overrun))
+            } else {
+                TickDriver::Task(self.tokio_handle.spawn(run_tick(
+                    self.clone(),
+                    module_schema.name.clone(),
+                    sub.reducer_name.clone(),
+                    period_us,
+                    overrun,
+                )))
+            };
+            tasks.push(TickTask {
+                reducer_name: sub.reducer_name.clone(),
+                event: sub.event.clone(),
+                driver,
+            });
+        }
+    }
+
+    /// Stop the tick subscriptions of a module being unloaded or removed.
+    pub(crate) fn stop_ticks(&self, module_name: &str) {
+        if let Some(tasks) = self.ticks.by_module.lock().remove(module_name) {
+            for task in tasks {
+                task.stop();
+            }
+        }
+    }
+
+    /// When the earliest tick following the virtual clock is due, rounded up to
+    /// the millisecond.
+    pub(crate) fn next_virtual_tick(&self) -> Option<u64> {
+        self.ticks
+            .by_module
+            .lock()
+            .values()
+            .flatten()
+            .filter_map(|task| match &task.driver {
+                TickDriver::Virtual(clock) => Some(clock.next_deadline().div_ceil(1_000)),
+                TickDriver::Task(_) => None,
+            })
+            .min()
+    }
+
+    /// Queue one tick of every tick subscription following the virtual clock that
+    /// came due, each holding a fork of `completion`.
+    pub(crate) fn fire_due_virtual_ticks(&self, completion: &CompletionToken) {
+        let now_us = self.clock.now_unix_ms().saturating_mul(1_000);
+        let mut by_module = self.ticks.by_module.lock();
+        for (module_name, tasks) in by_module.iter_mut() {
+            for task in tasks.iter_mut() {
+                let TickDriver::Virtual(clock) = &mut task.driver else {
+                    continue;
+                };
+                if clock.next_deadline() > now_us {
+                    continue;
+                }
+                let tick = clock.advance(now_us);
+                let _ = self.reducer_ingress.send(tick_job(
+                    self,
+                    module_name,
+                    &task.reducer_name,
+                    tick,
+                    completion.fork(),
+                ));
+            }
+        }
+    }
+}
+
+fn tick_job(
+    runtime: &Runtime,
+    module_name: &str,
+    reducer_name: &str,
+    tick: TickEvent,
+    completion: CompletionToken,
+) -> ReducerJob {
+    ReducerJob {
+        module_name: module_name.to_string(),
+        reducer_name: reducer_name.to_string(),
+        input: IntersticeValue::Vec(vec![tick.into()]),
+        caller_node_id: runtime.network_handle.node_id,
+        caller_module_name: String::new(),
+        request_id: None,
+        completion: Some(completion),
+        outcome: None,
+        source: JobSource::Input,
+        rng_seed: None,
+    }
+}
+
+async fn run_tick(
+    runtime: Arc<Runtime>,
+    module_name: String,
+    reducer_name: String,
+    period_us: u64,
+    overrun: TickOverrun,
+) {
+    let origin = Instant::now();
+    let mut clock = TickClock::new(0, period_us, overrun);
+    loop {
+        tokio::time::sleep_until(origin + Duration::from_micros(clock.next_deadline())).await;
+        let tick = clock.advance(origin.elapsed().as_micros() as u64);
+        let (completion, completed) = CompletionToken::new();
+        let job = tick_job(&runtime, &module_name, &reducer_name, tick, completion);
+        if runtime.reducer_ingress.send(job).is_err() {
+            return;
+        }
+        // The token is dropped once the reducer (and what it triggered) has run.
+        let _ = completed.await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use interstice_abi::SubscriptionSchema;
+
+    fn at(clock: &TickClock, ms: u64) -> u64 {
+        clock.start + ms * 1_000
+    }
+
+    #[test]
+    fn on_time_ticks_are_consecutive() {
+        let mut clock = TickClock::new(1_000_000, 8_000, TickOverrun::Skip);
+        assert_eq!(clock.next_deadline(), at(&clock, 8));
+        let tick = clock.advance(at(&clock, 8));
+        assert_eq!((tick.index, tick.elapsed_us, tick.steps, tick.skipped), (0, 8_000, 1, 0));
+        let tick = clock.advance(at(&clock, 17));
+        assert_eq!((tick.index, tick.elapsed_us, tick.steps, tick.skipped), (1, 9_000, 1, 0));
+        assert_eq!(clock.next_deadline(), at(&clock, 24));
+    }
+
+    #[test]
+    fn skip_drops_missed_ticks() {
+        let mut clock = TickClock::new(1_000_000, 8_000, TickOverrun::Skip);
+        clock.advance(at(&clock, 8));
+        // Ticks 1, 2 and 3 came due while tick 0 was running.
+        let tick = clock.advance(at(&clock, 35));
+        assert_eq!((tick.index, tick.steps, tick.skipped), (3, 1, 2));
+        assert_eq!(clock.next_deadline(), at(&clock, 40));
+    }
+
+    #[test]
+    fn catch_up_runs_every_missed_tick() {
+        let mut clock = TickClock::new(1_000_000, 8_000, TickOverrun::CatchUp);
+        clock.advance(at(&clock, 8));
+        let indexes: Vec<u64> = (0..3).map(|_| clock.advance(at(&clock, 35)).index).collect();
+        assert_eq!(indexes, vec![1, 2, 3]);
+        assert_eq!(clock.next_deadline(), at(&clock, 40));
+    }
+
+    #[test]
+    fn coalesce_merges_missed_ticks() {
+        let mut clock = TickClock::new(1_000_000, 8_000, TickOverrun::Coalesce);
+        clock.advance(at(&clock, 8));
+        let tick = clock.advance(at(&clock, 35));
+        assert_eq!((tick.index, tick.elapsed_us, tick.steps, tick.skipped), (3, 27_000, 3, 0));
+        assert_eq!(clock.next_deadline(), at(&clock, 40));
+    }
+
+    #[tokio::test]
+    async fn ticks_follow_the_virtual_clock() {
+        use crate::testing::TestRuntime;
+        use crate::testing::fixture::{FixtureModule, Step, row};
+        use interstice_abi::{IntersticeType, PersistenceKind};
+
+        let fields = [("time", IntersticeType::F64), ("random", IntersticeType::F64)];
+        let zero = row(1, vec![IntersticeValue::F64(0.0), IntersticeValue::F64(0.0)]);
+        let mut world = FixtureModule::new("world")
+            .table("frames", PersistenceKind::Ephemeral, &fields)
+            .reducer("setup", vec![Step::Insert { table: "frames".into(), row: zero }])
+            .reducer(
+                "step",
+                vec![Step::UpdateTimeAndRandom { table: "frames".into(), id: 1 }],
+            );
+        world.schema.subscriptions.push(SubscriptionSchema {
+            reducer_name: "step".into(),
+            event: SubscriptionEventSchema::Tick {
+                period_us: 8_000,
+                overrun: TickOverrun::Skip,
+            },
+            filter: None,
+        });
+        let runtime = TestRuntime::starting_at(1_000).unwrap();
+        runtime.load_module(&world.wasm()).await.unwrap();
+        runtime.call_reducer("world", "setup", Vec::new()).await.unwrap();
+
+        // The last tick due within each advance ran at its deadline.
+        let last_tick_time = || runtime.rows("world", "frames").unwrap()[0].entries[0].clone();
+        runtime.advance(Duration::from_millis(10)).await;
+        assert_eq!(last_tick_time(), IntersticeValue::F64(1_008.0));
+        runtime.advance(Duration::from_millis(10)).await;
+        assert_eq!(last_tick_time(), IntersticeValue::F64(1_016.0));
+        runtime.advance(Duration::from_millis(20)).await;
+        assert_eq!(last_tick_time(), IntersticeValue::F64(1_040.0));
+        assert_eq!(runtime.now_unix_ms(), 1_040);
+    }
+
+    #[test]
+    fn zero_tick_periods_are_rejected() {
+        let mut schema = ModuleSchema::empty();
+        schema.subscriptions.push(SubscriptionSchema {
+            reducer_name: "step".into(),
+            event: SubscriptionEventSchema::Tick {
+                period_us: 0,
+                overrun: TickOverrun::Skip,
+            },
+            filter: None,
+        });
+        assert!(matches!(
+            validate_tick_subscriptions(&schema),
+            Err(IntersticeError::InvalidSubscription { .. })
+        ));
+        schema.subscriptions[0].event = SubscriptionEventSchema::Tick {
+            period_us: 8_000,
+            overrun: TickOverrun::Skip,
+        };
+        assert!(validate_tick_subscriptions(&schema).is_ok());
+    }
+}
@@ -0,0 +1,892 @@
+//! Table time travel: checkpoints, branches and rewinds.
+//!
+//! A module saves the rows of its tables (replicas excepted) as named
+//! checkpoints and rewinds to them, or forks a branch it later discards, putting
+//! back the rows the branch started from, or promotes into its parent branch.
+//! Branches nest from the root branch `main`. These calls are queued with the
+//! reducer's table writes and applied when it commits, so a failed reducer
+//! leaves the timeline untouched. Changed rows emit the usual table events and
+//! are persisted like any write; logged tables also record the rewinds and the
+//! branch lineage in their log, which `rows_as_of` reads the past rows from.
+//!
+//! Checkpoints and branch starting points hold a copy of the rows and the table
+//! layouts they were taken with, so they stay usable across module upgrades.
+//! They are persisted with the module's data through the
+//! [`TableStore`](crate::persistence::TableStore), each in a file of its own
+//! next to the timeline, so a change only writes the rows it saves.
+
+use crate::{
+    error::IntersticeError,
+    logger::{LogLevel, LogSource},
+    persistence::{LogOperation, SnapshotPlan},
+    runtime::{Runtime, event::EventInstance, table::Table, transaction::Transaction},
+};
+use interstice_abi::{CheckpointInfo, IndexKey, PersistenceKind, Row, TableSchema};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// Root branch of every module.
+pub(crate) const MAIN_BRANCH: &str = "main";
+
+/// Checkpoints and branches of every loaded module.
+pub(crate) struct TimeTravel {
+    timelines: Mutex<HashMap<String, Timeline>>,
+}
+
+impl TimeTravel {
+    pub fn new() -> Self {
+        Self {
+            timelines: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+/// Time travel change of a reducer, applied when it commits.
+#[derive(Debug, Clone)]
+pub enum TimeTravelOperation {
+    Checkpoint { name: String },
+    Rewind { checkpoint: String },
+    DeleteCheckpoint { name: String },
+    Fork { branch: String },
+    Discard,
+    Promote,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct SavedTable {
+    schema: TableSchema,
+    rows: Vec<Row>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Checkpoint {
+    branch: String,
+    created_unix_ms: u64,
+    sequences: Vec<(String, u64)>,
+    /// Persisted in a file of their own.
+    #[serde(skip)]
+    tables: Vec<SavedTable>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Branch {
+    name: String,
+    /// Rows the branch was forked from, persisted in a file of their own.
+    #[serde(skip)]
+    base: Vec<SavedTable>,
+}
+
+/// Saved rows of a timeline, each persisted in a file of their own.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum TimelineRows {
+    Checkpoint(String),
+    Branch(String),
+}
+
+/// Checkpoints and branches of a module.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub(crate) struct Timeline {
+    checkpoints: BTreeMap<String, Checkpoint>,
+    /// Branches forked from `main`, the current one last.
+    branches: Vec<Branch>,
+}
+
+impl Timeline {
+    fn current_branch(&self) -> &str {
+        self.branches
+            .last()
+            .map_or(MAIN_BRANCH, |branch| branch.name.as_str())
+    }
+
+    fn branch_names(&self) -> Vec<String> {
+        std::iter::once(MAIN_BRANCH.to_string())
+            .chain(self.branches.iter().map(|branch| branch.name.clone()))
+            .collect()
+    }
+
+    /// Check `operation` against the timeline once `pending` operations applied.
+    fn check(
+        &self,
+        pending: &[TimeTravelOperation],
+        operation: &TimeTravelOperation,
+    ) -> Result<(), String> {
+        let mut outline = TimelineOutline {
+            checkpoints: self
+                .checkpoints
+                .iter()
+                .map(|(name, checkpoint)| (name.clone(), checkpoint.branch.clone()))
+                .collect(),
+            branches: self.branch_names(),
+        };
+        for pending in pending {
+            let _ = outline.apply(pending);
+        }
+        outline.apply(operation)
+    }
+
+    /// Saved rows that a checked rewind or discard puts back into the tables.
+    fn restored_tables(&self, operation: &TimeTravelOperation) -> Option<Vec<SavedTable>> {
+        match operation {
+            TimeTravelOperation::Rewind { checkpoint } => {
+                Some(self.checkpoints[checkpoint].tables.clone())
+            }
+            TimeTravelOperation::Discard => self.branches.last().map(|branch| branch.base.clone()),
+            _ => None,
+        }
+    }
+
+    /// Saved rows a checked `operation` drops from the timeline.
+    fn dropped_rows(&self, operation: &TimeTravelOperation) -> Vec<TimelineRows> {
+        match operation {
+            TimeTravelOperation::DeleteCheckpoint { name } => {
+                vec![TimelineRows::Checkpoint(name.clone())]
+            }
+            TimeTravelOperation::Discard => {
+                let branch = &self.branches.last().unwrap().name;
+                self.checkpoints
+                    .iter()
+                    .filter(|(_, checkpoint)| checkpoint.branch == *branch)
+                    .map(|(name, _)| TimelineRows::Checkpoint(name.clone()))
+                    .chain(std::iter::once(TimelineRows::Branch(branch.clone())))
+                    .collect()
+            }
+            TimeTravelOperation::Promote => {
+                vec![TimelineRows::Branch(self.branches.last().unwrap().name.clone())]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Saved rows an applied `operation` added to the timeline.
+    fn saved_rows(&self, operation: &TimeTravelOperation) -> Option<(TimelineRows, &Vec<SavedTable>)> {
+        match operation {
+            TimeTravelOperation::Checkpoint { name } => Some((
+                TimelineRows::Checkpoint(name.clone()),
+                &self.checkpoints[name].tables,
+            )),
+            TimeTravelOperation::Fork { branch } => Some((
+                TimelineRows::Branch(branch.clone()),
+                &self.branches.last().unwrap().base,
+            )),
+            _ => None,
+        }
+    }
+
+    /// Apply a checked `operation` to the timeline, saving the rows of `tables` for
+    /// checkpoints and forks.
+    fn advance(
+        &mut self,
+        operation: &TimeTravelOperation,
+        tables: &mut HashMap<String, Table>,
+        created_unix_ms: u64,
+        sequences: Vec<(String, u64)>,
+    ) {
+        match operation {
+            TimeTravelOperation::Checkpoint { name } => {
+                let checkpoint = Checkpoint {
+                    branch: self.current_branch().to_string(),
+                    created_unix_ms,
+                    sequences,
+                    tables: save_tables(tables),
+                };
+                self.checkpoints.insert(name.clone(), checkpoint);
+            }
+            TimeTravelOperation::Rewind { .. } => {}
+            TimeTravelOperation::DeleteCheckpoint { name } => {
+                self.checkpoints.remove(name);
+            }
+            TimeTravelOperation::Fork { branch } => {
+                self.branches.push(Branch {
+                    name: branch.clone(),
+                    base: save_tables(tables),
+                });
+            }
+            TimeTravelOperation::Discard => {
+                let branch = self.branches.pop().unwrap();
+                self.checkpoints
+                    .retain(|_, checkpoint| checkpoint.branch != branch.name);
+            }
+            TimeTravelOperation::Promote => {
+                let branch = self.branches.pop().unwrap();
+                let parent = self.current_branch().to_string();
+                for checkpoint in self.checkpoints.values_mut() {
+                    if checkpoint.branch == branch.name {
+                        checkpoint.branch = parent.clone();
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Names of the checkpoints and branches of a timeline, to check operations
+/// against without copying rows.
+struct TimelineOutline {
+    /// Checkpoint name to branch.
+    checkpoints: HashMap<String, String>,
+    /// Branches from `main`.
+    branches: Vec<String>,
+}
+
+impl TimelineOutline {
+    fn apply(&mut self, operation: &TimeTravelOperation) -> Result<(), String> {
+        match operation {
+            TimeTravelOperation::Checkpoint { name } => {
+                if name.is_empty() {
+                    return Err("Checkpoint names can't be empty".into());
+                }
+                let branch = self.branches.last().cloned().unwrap_or_default();
+                self.checkpoints.insert(name.clone(), branch);
+            }
+            TimeTravelOperation::Rewind { checkpoint } => {
+                if !self.checkpoints.contains_key(checkpoint) {
+                    return Err(format!("No checkpoint named '{}'", checkpoint));
+                }
+            }
+            TimeTravelOperation::DeleteCheckpoint { name } => {
+                if self.checkpoints.remove(name).is_none() {
+                    return Err(format!("No checkpoint named '{}'", name));
+                }
+            }
+            TimeTravelOperation::Fork { branch } => {
+                if branch.is_empty() {
+                    return Err("Branch names can't be empty".into());
+                }
+                if self.branches.contains(branch) {
+                    return Err(format!("Branch '{}' already exists", branch));
+                }
+                self.branches.push(branch.clone());
+            }
+            TimeTravelOperation::Discard | TimeTravelOperation::Promote => {
+                if self.branches.len() < 2 {
+                    return Err(format!(
+                        "The '{}' branch can't be discarded or promoted",
+                        MAIN_BRANCH
+                    ));
+                }
+                let branch = self.branches.pop().unwrap();
+                let parent = self.branches.last().unwrap().clone();
+                if matches!(operation, TimeTravelOperation::Discard) {
+                    self.checkpoints.retain(|_, owner| *owner != branch);
+                } else {
+                    for owner in self.checkpoints.values_mut() {
+                        if *owner == branch {
+                            *owner = parent.clone();
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The module's own tables, in name order: replicas mirror tables of other nodes.
+fn own_tables(tables: &mut HashMap<String, Table>) -> Vec<(&String, &mut Table)> {
+    let mut own = tables
+        .iter_mut()
+        .filter(|(name, _)| !name.starts_with("__replica__"))
+        .collect::<Vec<_>>();
+    own.sort_by(|a, b| a.0.cmp(b.0));
+    own
+}
+
+fn save_tables(tables: &mut HashMap<String, Table>) -> Vec<SavedTable> {
+    own_tables(tables)
+        .into_iter()
+        .map(|(_, table)| SavedTable {
+            schema: table.schema.clone(),
+            rows: table.snapshot_rows(),
+        })
+        .collect()
+}
+
+/// Rows of `saved` for each of the module's tables, migrated to the current
+/// layouts. Tables that were not saved get no rows.
+fn restored_rows(
+    module_name: &str,
+    tables: &mut HashMap<String, Table>,
+    saved: Vec<SavedTable>,
+) -> Result<Vec<(String, Vec<Row>)>, IntersticeError> {
+    let mut saved = saved
+        .into_iter()
+        .map(|table| (table.schema.name.clone(), table))
+        .collect::<HashMap<_, _>>();
+    own_tables(tables)
+        .into_iter()
+        .map(|(table_name, table)| {
+            let rows = match saved.remove(table_name) {
+                Some(saved) if table.schema.same_layout(&saved.schema) => saved.rows,
+                Some(saved) => saved
+                    .rows
+                    .into_iter()
+                    .map(|row| table.schema.migrate_row(&saved.schema, row))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|reason| IntersticeError::SchemaMigrationFailed {
+                        module_name: module_name.to_string(),
+                        table_name: table_name.clone(),
+                        reason,
+                    })?,
+                None => Vec::new(),
+            };
+            Ok((table_name.clone(), rows))
+        })
+        .collect()
+}
+
+impl Runtime {
+    /// Load the checkpoints and branches a module left persisted.
+    pub(crate) fn restore_timeline(&self, module_name: &str) {
+        let timeline = match self.read_timeline(module_name) {
+            Ok(timeline) => timeline,
+            Err(err) => {
+                // Like table layouts, a timeline written by a node with an older ABI
+                // may not decode anymore.
+                self.logger.log(
+                    &format!(
+                        "Dropped the checkpoints and branches of '{}': {}",
+                        module_name, err
+                    ),
+                    LogSource::Runtime,
+                    LogLevel::Warning,
+                );
+                Timeline::default()
+            }
+        };
+        self.time_travel
+            .timelines
+            .lock()
+            .insert(module_name.to_string(), timeline);
+    }
+
+    fn read_timeline(&self, module_name: &str) -> Result<Timeline, IntersticeError> {
+        let Some(mut timeline) = self.persistence.read_timeline(module_name)? else {
+            return Ok(Timeline::default());
+        };
+        for (name, checkpoint) in &mut timeline.checkpoints {
+            checkpoint.tables = self
+                .persistence
+                .read_timeline_rows(module_name, &TimelineRows::Checkpoint(name.clone()))?;
+        }
+        for branch in &mut timeline.branches {
+            branch.base = self
+                .persistence
+                .read_timeline_rows(module_name, &TimelineRows::Branch(branch.name.clone()))?;
+        }
+        Ok(timeline)
+    }
+
+    pub(crate) fn drop_timeline(&self, module_name: &str) {
+        self.time_travel.timelines.lock().remove(module_name);
+    }
+
+    /// Check an operation of the running reducer against the module's timeline,
+    /// including the operations it queued before.
+    pub(crate) fn check_time_travel(
+        &self,
+        module_name: &str,
+        operation: &TimeTravelOperation,
+    ) -> Result<(), String> {
+        let mut pending = Vec::new();
+        crate::runtime::reducer::for_each_pending_transaction(|transaction| {
+            if let Transaction::TimeTravel {
+                module_name: pending_module,
+                operation,
+            } = transaction
+                && pending_module == module_name
+            {
+                pending.push(operation.clone());
+            }
+        });
+        let timelines = self.time_travel.timelines.lock();
+        match timelines.get(module_name) {
+            Some(timeline) => timeline.check(&pending, operation),
+            None => Timeline::default().check(&pending, operation),
+        }
+    }
+
+    pub(crate) fn list_checkpoints(&self, module_name: &str) -> Vec<CheckpointInfo> {
+        let timelines = self.time_travel.timelines.lock();
+        let Some(timeline) = timelines.get(module_name) else {
+            return Vec::new();
+        };
+        timeline
+            .checkpoints
+            .iter()
+            .map(|(name, checkpoint)| CheckpointInfo {
+                name: name.clone(),
+                branch: checkpoint.branch.clone(),
+                created_unix_ms: checkpoint.created_unix_ms,
+                sequences: checkpoint.sequences.clone(),
+            })
+            .collect()
+    }
+
+    pub(crate) fn list_branches(&self, module_name: &str) -> Vec<String> {
+        self.time_travel
+            .timelines
+            .lock()
+            .get(module_name)
+            .map_or_else(|| vec![MAIN_BRANCH.to_string()], Timeline::branch_names)
+    }
+
+    /// Apply a committed time travel operation to the module's tables, which the
+    /// caller holds locked.
+    pub(crate) fn apply_time_travel(
+        &self,
+        module_name: &str,
+        tables: &mut HashMap<String, Table>,
+        operation: TimeTravelOperation,
+        has_subscriptions: bool,
+        events: &mut Vec<EventInstance>,
+        snapshots: &mut Vec<(SnapshotPlan, Vec<Row>)>,
+    ) -> Result<(), IntersticeError> {
+        let mut timelines = self.time_travel.timelines.lock();
+        let timeline = timelines.entry(module_name.to_string()).or_default();
+        // Checked when queued, but another reducer of the module may have changed
+        // the timeline since.
+        if let Err(reason) = timeline.check(&[], &operation) {
+            self.logger.log(
+                &format!(
+                    "Skipped {:?} of '{}' on commit: {}",
+                    operation, module_name, reason
+                ),
+                LogSource::Runtime,
+                LogLevel::Warning,
+            );
+            return Ok(());
+        }
+
+        let sequences = match operation {
+            TimeTravelOperation::Checkpoint { .. } => own_tables(tables)
+                .into_iter()
+                .filter_map(|(table_name, _)| {
+                    self.persistence
+                        .table_sequence(module_name, table_name)
+                        .map(|sequence| (table_name.clone(), sequence))
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+        let branch = timeline.current_branch().to_string();
+        let dropped = timeline.dropped_rows(&operation);
+        let changes = TableChanges {
+            module_name,
+            has_subscriptions,
+            events,
+            snapshots,
+        };
+        // The table writes come first: a failed one leaves the timeline untouched.
+        match (&operation, timeline.restored_tables(&operation)) {
+            (TimeTravelOperation::Rewind { .. }, Some(saved)) => {
+                self.replace_rows(tables, saved, changes, |rows| LogOperation::Restore { rows })?;
+            }
+            (TimeTravelOperation::Discard, Some(saved)) => {
+                self.replace_rows(tables, saved, changes, |rows| LogOperation::Discard {
+                    branch: branch.clone(),
+                    rows,
+                })?;
+            }
+            (TimeTravelOperation::Fork { branch: forked }, _) => {
+                self.log_lineage(module_name, tables, changes.snapshots, || LogOperation::Fork {
+                    branch: forked.clone(),
+                })?;
+            }
+            (TimeTravelOperation::Promote, _) => {
+                self.log_lineage(module_name, tables, changes.snapshots, || {
+                    LogOperation::Promote {
+                        branch: branch.clone(),
+                    }
+                })?;
+            }
+            _ => {}
+        }
+        timeline.advance(&operation, tables, self.clock.now_unix_ms(), sequences);
+
+        // Saved rows are written before the timeline refers to them and removed
+        // once it no longer does.
+        if let Some((rows, saved)) = timeline.saved_rows(&operation) {
+            self.persistence
+                .persist_timeline_rows(module_name, &rows, Some(saved))?;
+        }
+        self.persistence.persist_timeline(module_name, timeline)?;
+        for rows in dropped {
+            self.persistence
+                .persist_timeline_rows::<Vec<SavedTable>>(module_name, &rows, None)?;
+        }
+        Ok(())
+    }
+
+    /// Copy of a module's timeline, for an atomic commit to check its operations
+    /// against.
+    pub(crate) fn timeline_copy(&self, module_name: &str) -> Timeline {
+        self.time_travel
+            .timelines
+            .lock()
+            .get(module_name)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Dry run of [`apply_time_travel`](Self::apply_time_travel) on copies of the
+    /// module's timeline and tables, without persisting or emitting anything.
+    pub(crate) fn check_time_travel_commit(
+        &self,
+        module_name: &str,
+        timeline: &mut Timeline,
+        tables: &mut HashMap<String, Table>,
+        operation: &TimeTravelOperation,
+    ) -> Result<(), IntersticeError> {
+        // Skipped on commit as well.
+        if timeline.check(&[], operation).is_err() {
+            return Ok(());
+        }
+        if let Some(saved) = timeline.restored_tables(operation) {
+            for (table_name, rows) in restored_rows(module_name, tables, saved)? {
+                tables.get_mut(&table_name).unwrap().restore_from_rows(rows)?;
+            }
+        }
+        timeline.advance(operation, tables, 0, Vec::new());
+        Ok(())
+    }
+
+    /// Replace the rows of the module's tables with saved ones, migrating them
+    /// to the current layouts. Tables that were not saved are emptied.
+    fn replace_rows(
+        &self,
+        tables: &mut HashMap<String, Table>,
+        saved: Vec<SavedTable>,
+        changes: TableChanges<'_>,
+        log_operation: impl Fn(Vec<Row>) -> LogOperation,
+    ) -> Result<(), IntersticeError> {
+        let TableChanges {
+            module_name,
+            has_subscriptions,
+            events,
+            snapshots,
+        } = changes;
+        for (table_name, rows) in restored_rows(module_name, tables, saved)? {
+            let table = tables.get_mut(&table_name).unwrap();
+            let table_name = &table_name;
+            let old_rows = if has_subscriptions {
+                table.snapshot_rows()
+            } else {
+                Vec::new()
+            };
+            table.restore_from_rows(rows.clone())?;
+            if has_subscriptions {
+                diff_events(module_name, table_name, old_rows, &rows, events)?;
+            }
+
+            match table.schema.persistence {
+                PersistenceKind::Stateful => {
+                    self.persistence
+                        .persist_stateful_clear(module_name, table_name)?;
+                    for row in &rows {
+                        let primary_key = IndexKey::try_from(&row.primary_key)
+                            .map_err(IntersticeError::Internal)?;
+                        self.persistence.persist_stateful_insert(
+                            module_name,
+                            table_name,
+                            &primary_key,
+                            row,
+                        )?;
+                    }
+                }
+                PersistenceKind::Logged => {
+                    if let Some(plan) = self.persistence.record_logged_operation(
+                        module_name,
+                        table_name,
+                        log_operation(rows),
+                    )? {
+                        snapshots.push((plan, table.snapshot_rows()));
+                    }
+                }
+                PersistenceKind::Ephemeral => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Record a branch change in the log of each logged table of the module.
+    fn log_lineage(
+        &self,
+        module_name: &str,
+        tables: &mut HashMap<String, Table>,
+        snapshots: &mut Vec<(SnapshotPlan, Vec<Row>)>,
+        log_operation: impl Fn() -> LogOperation,
+    ) -> Result<(), IntersticeError> {
+        for (table_name, table) in own_tables(tables) {
+            if table.schema.persistence != PersistenceKind::Logged {
+                continue;
+            }
+            if let Some(plan) =
+                self.persistence
+                    .record_logged_operation(module_name, table_name, log_operation())?
+            {
+                snapshots.push((plan, table.snapshot_rows()));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Where [`Runtime::replace_rows`] reports what it changed.
+struct TableChanges<'a> {
+    module_name: &'a str,
+    has_subscriptions: bool,
+    events: &'a mut Vec<EventInstance>,
+    snapshots: &'a mut Vec<(SnapshotPlan, Vec<Row>)>,
+}
+
+/// Table events turning `old_rows` into `new_rows`, matched by primary key.
+fn diff_events(
+    module_name: &str,
+    table_name: &str,
+    old_rows: Vec<Row>,
+    new_rows: &[Row],
+    events: &mut Vec<EventInstance>,
+) -> Result<(), IntersticeError> {
+    let mut old_by_key = HashMap::new();
+    let mut old_keys = Vec::new();
+    for row in old_rows {
+        let key = IndexKey::try_from(&row.primary_key).map_err(IntersticeError::Internal)?;
+        old_keys.push(key.clone());
+        old_by_key.insert(key, row);
+    }
+    for row in new_rows {
+        let key = IndexKey::try_from(&row.primary_key).map_err(IntersticeError::Internal)?;
+        match old_by_key.remove(&key) {
+            Some(old_row) if old_row == *row => {}
+            Some(old_row) => events.push(EventInstance::TableUpdateEvent {
+                source_node_id: None,
+                filter: None,
+                sequence: 0,
+                module_name: module_name.to_string(),
+                table_name: table_name.to_string(),
+                old_row,
+                new_row: row.clone(),
+            }),
+            None => events.push(EventInstance::TableInsertEvent {
+                source_node_id: None,
+                filter: None,
+                sequence: 0,
+                module_name: module_name.to_string(),
+                table_name: table_name.to_string(),
+                inserted_row: row.clone(),
+            }),
+        }
+    }
+    // Deleted rows in the order the table held them.
+    for key in old_keys {
+        if let Some(deleted_row) = old_by_key.remove(&key) {
+            events.push(EventInstance::TableDeleteEvent {
+                source_node_id: None,
+                filter: None,
+                sequence: 0,
+                module_name: module_name.to_string(),
+                table_name: table_name.to_string(),
+                deleted_row,
+            });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestRuntime;
+    use crate::testing::fixture::{FixtureModule, Step, row};
+    use interstice_abi::{IntersticeType, IntersticeValue, PersistenceKind};
+
+    fn checkpoint(name: &str) -> TimeTravelOperation {
+        TimeTravelOperation::Checkpoint { name: name.into() }
+    }
+
+    #[test]
+    fn operations_are_checked_after_the_pending_ones() {
+        let timeline = Timeline::default();
+        let rewind = TimeTravelOperation::Rewind {
+            checkpoint: "start".into(),
+        };
+        assert!(timeline.check(&[], &rewind).is_err());
+        assert!(timeline.check(&[checkpoint("start")], &rewind).is_ok());
+        assert!(timeline.check(&[], &TimeTravelOperation::Discard).is_err());
+
+        let fork = TimeTravelOperation::Fork {
+            branch: "trial".into(),
+        };
+        assert!(timeline.check(std::slice::from_ref(&fork), &fork).is_err());
+        // Discarding a branch drops the checkpoints created on it.
+        let pending = [fork, checkpoint("start"), TimeTravelOperation::Discard];
+        assert!(timeline.check(&pending, &rewind).is_err());
+        let pending = [
+            TimeTravelOperation::Fork {
+                branch: "trial".into(),
+            },
+            checkpoint("start"),
+            TimeTravelOperation::Promote,
+        ];
+        assert!(timeline.check(&pending, &rewind).is_ok());
+    }
+
+    #[test]
+    fn diff_events_match_rows_by_primary_key() {
+        use interstice_abi::IntersticeValue;
+        let row = |id: u64, name: &str| Row {
+            primary_key: IntersticeValue::U64(id),
+            entries: vec![IntersticeValue::String(name.into())],
+        };
+        let mut events = Vec::new();
+        diff_events(
+            "game",
+            "players",
+            vec![row(1, "a"), row(2, "b"), row(3, "c")],
+            &[row(1, "a"), row(2, "B"), row(4, "d")],
+            &mut events,
+        )
+        .unwrap();
+        assert!(matches!(
+            events.as_slice(),
+            [
+                EventInstance::TableUpdateEvent { .. },
+                EventInstance::TableInsertEvent { .. },
+                EventInstance::TableDeleteEvent { .. },
+            ]
+        ));
+    }
+
+    #[tokio::test]
+    async fn reducers_checkpoint_rewind_and_fork_their_tables() {
+        use interstice_abi::{HostCall, TimeTravelCall};
+        let runtime = TestRuntime::starting_at(1_000).unwrap();
+        let score = |value| Step::Update {
+            table: "scores".into(),
+            row: row(1, vec![IntersticeValue::U64(value)]),
+        };
+        let time_travel = |call| Step::HostCall(HostCall::TimeTravel(call));
+        let module = FixtureModule::new("game")
+            .table(
+                "scores",
+                PersistenceKind::Logged,
+                &[("value", IntersticeType::U64)],
+            )
+            .reducer(
+                "start",
+                vec![
+                    Step::Insert {
+                        table: "scores".into(),
+                        row: row(1, vec![IntersticeValue::U64(1)]),
+                    },
+                    time_travel(TimeTravelCall::Checkpoint {
+                        name: "start".into(),
+                    }),
+                ],
+            )
+            .reducer("play", vec![score(2)])
+            .reducer(
+                "rewind",
+                vec![time_travel(TimeTravelCall::Rewind {
+                    checkpoint: "start".into(),
+                })],
+            )
+            .reducer(
+                "try",
+                vec![
+                    time_travel(TimeTravelCall::Fork {
+                        branch: "trial".into(),
+                    }),
+                    score(3),
+                    time_travel(TimeTravelCall::Checkpoint {
+                        name: "tried".into(),
+                    }),
+                ],
+            )
+            .reducer("discard", vec![time_travel(TimeTravelCall::Discard)])
+            .reducer("promote", vec![time_travel(TimeTravelCall::Promote)])
+            .reducer(
+                "fork_then_fail",
+                vec![
+                    time_travel(TimeTravelCall::Fork {
+                        branch: "lost".into(),
+                    }),
+                    Step::Spin,
+                ],
+            )
+            .with_reducer(|reducer| reducer.fuel = Some(10_000));
+        runtime.load_module(&module.wasm()).await.unwrap();
+        let value = || runtime.rows("game", "scores").unwrap()[0].entries[0].clone();
+        let checkpoints = || {
+            runtime
+                .runtime()
+                .list_checkpoints("game")
+                .into_iter()
+                .map(|checkpoint| (checkpoint.name, checkpoint.branch))
+                .collect::<Vec<_>>()
+        };
+
+        runtime
+            .call_reducer("game", "start", Vec::new())
+            .await
+            .unwrap();
+        runtime
+            .call_reducer("game", "play", Vec::new())
+            .await
+            .unwrap();
+        assert_eq!(value(), IntersticeValue::U64(2));
+        runtime
+            .call_reducer("game", "rewind", Vec::new())
+            .await
+            .unwrap();
+        assert_eq!(value(), IntersticeValue::U64(1));
+
+        // A discarded branch takes its rows and checkpoints with it.
+        runtime
+            .call_reducer("game", "try", Vec::new())
+            .await
+            .unwrap();
+        assert_eq!(value(), IntersticeValue::U64(3));
+        assert_eq!(
+            runtime.runtime().list_branches("game"),
+            vec!["main", "trial"]
+        );
+        runtime
+            .call_reducer("game", "discard", Vec::new())
+            .await
+            .unwrap();
+        assert_eq!(value(), IntersticeValue::U64(1));
+        assert_eq!(
+            checkpoints(),
+            vec![("start".to_string(), "main".to_string())]
+        );
+
+        // A promoted one keeps them.
+        runtime
+            .call_reducer("game", "try", Vec::new())
+            .await
+            .unwrap();
+        runtime
+            .call_reducer("game", "promote", Vec::new())
+            .await
+            .unwrap();
+        assert_eq!(value(), IntersticeValue::U64(3));
+        assert_eq!(runtime.runtime().list_branches("game"), vec!["main"]);
+        assert_eq!(
+            checkpoints(),
+            vec![
+                ("start".to_string(), "main".to_string()),
+                ("tried".to_string(), "main".to_string())
+            ]
+        );
+
+        assert!(
+            runtime
+                .call_reducer("game", "fork_then_fail", Vec::new())
+                .await
+                .is_err()
+        );
+        assert_eq!(runtime.runtime().list_branches("game"), vec!["main"]);
+    }
+}
@@ -1,12 +1,20 @@
+use std::collections::HashMap;
 use std::convert::TryInto;
 use std::sync::Arc;
 
-use interstice_abi::{IndexKey, PersistenceKind, Row};
+use interstice_abi::{IndexKey, PersistenceKind, Row, ScheduleInfo};
+use parking_lot::{Mutex, MutexGuard};
 
 use crate::{
     error::IntersticeError,
     persistence::{LogOperation, SnapshotPlan},
-    runtime::{Runtime, event::EventInstance, module::Module},
+    runtime::{
+        Runtime,
+        event::EventInstance,
+        module::Module,
+        table::{PendingTableWrites, Table, TableAutoIncSnapshot, TableUsage, rewind_auto_inc},
+        time_travel::{TimeTravelOperation, Timeline},
+    },
 };
 
 #[derive(Debug)]
@@ -30,9 +38,199 @@ pub enum Transaction {
         module_name: String,
         table_name: String,
     },
+    /// Checkpoint, rewind or branch change, applied in order with the writes.
+    TimeTravel {
+        module_name: String,
+        operation: TimeTravelOperation,
+    },
+    /// Schedule created by a reducer, registered once its writes are applied.
+    Schedule {
+        module_name: String,
+        schedule: ScheduleInfo,
+    },
+    /// Schedule cancelled by a reducer, removed once its writes are applied.
+    CancelSchedule {
+        module_name: String,
+        schedule_id: u64,
+    },
+}
+
+/// Tables of one module, locked by an atomic commit.
+type Tables = HashMap<String, Table>;
+
+/// What the segments of an atomic transaction checked so far leave in the
+/// tables of one module.
+enum ModuleCheck {
+    /// Writes checked against the locked tables.
+    Writes(HashMap<String, PendingTableWrites>),
+    /// Copies of the tables and timeline, for modules the transaction travels in
+    /// time: a rewind replaces whole tables.
+    Copied {
+        tables: Tables,
+        timeline: Option<Timeline>,
+    },
+}
+
+/// Changes made while holding the tables locked, finished once they are released.
+#[derive(Default)]
+struct AppliedTransactions {
+    events: Vec<EventInstance>,
+    /// Deferred snapshot work: collected under the lock, executed after.
+    snapshots: Vec<(SnapshotPlan, Vec<Row>)>,
+    /// Schedule changes, made once every write went through.
+    schedule_changes: Vec<Transaction>,
 }
 
 impl Runtime {
+    /// Apply the segments of an atomic transaction, holding the tables of every
+    /// module they write locked from the check through the last write, so that
+    /// no other call commits in between. Every segment is checked before any is
+    /// applied: when the check fails nothing is, and the auto-inc ids drawn through
+    /// `auto_inc_snapshots` are given back. A storage error while applying stops
+    /// the commit, and the segments applied before it stay committed.
+    pub(crate) fn apply_atomic_transaction(
+        &self,
+        segments: Vec<(Arc<Module>, Vec<Transaction>)>,
+        auto_inc_snapshots: Vec<TableAutoIncSnapshot>,
+        module_name: &str,
+        reducer_name: &str,
+    ) -> Result<Vec<EventInstance>, IntersticeError> {
+        // An upgraded module shares the tables of the one it replaced.
+        let mut locks: Vec<Arc<Mutex<Tables>>> = Vec::new();
+        for (module, _) in &segments {
+            if !locks.iter().any(|lock| Arc::ptr_eq(lock, &module.tables)) {
+                locks.push(module.tables.clone());
+            }
+        }
+        // Taken in address order so that two commits cannot deadlock.
+        locks.sort_by_key(|lock| Arc::as_ptr(lock) as usize);
+        let mut tables: Vec<MutexGuard<'_, Tables>> =
+            locks.iter().map(|lock| lock.lock()).collect();
+
+        if let Err(err) = self.check_transactions(&segments, &locks, &tables) {
+            rewind_auto_inc(auto_inc_snapshots);
+            return Err(IntersticeError::TransactionAborted {
+                module: module_name.into(),
+                reducer: reducer_name.into(),
+                reason: err.to_string(),
+            });
+        }
+        let mut applied = AppliedTransactions::default();
+        let mut result = Ok(());
+        for (module, transactions) in segments {
+            let position = lock_position(&locks, &module);
+            result =
+                self.apply_locked_transactions(transactions, &mut tables[position], &mut applied);
+            if result.is_err() {
+                break;
+            }
+        }
+        drop(tables);
+
+        self.finish_committed_transactions(applied, result)
+    }
+
+    /// Check the segments of an atomic transaction in order against the locked
+    /// tables, so that a write that would fail is reported before any segment is
+    /// applied. Only the queued writes are tracked, except for modules the
+    /// transaction travels in time, whose tables are copied.
+    fn check_transactions(
+        &self,
+        segments: &[(Arc<Module>, Vec<Transaction>)],
+        locks: &[Arc<Mutex<Tables>>],
+        tables: &[MutexGuard<'_, Tables>],
+    ) -> Result<(), IntersticeError> {
+        let mut checks: Vec<ModuleCheck> = locks
+            .iter()
+            .zip(tables)
+            .map(|(lock, tables)| {
+                let travels = segments.iter().any(|(module, transactions)| {
+                    Arc::ptr_eq(&module.tables, lock)
+                        && transactions.iter().any(|transaction| {
+                            matches!(transaction, Transaction::TimeTravel { .. })
+                        })
+                });
+                if travels {
+                    ModuleCheck::Copied {
+                        tables: (**tables).clone(),
+                        timeline: None,
+                    }
+                } else {
+                    ModuleCheck::Writes(HashMap::new())
+                }
+            })
+            .collect();
+
+        for (module, transactions) in segments {
+            let position = lock_position(locks, module);
+            for transaction in transactions {
+                match &mut checks[position] {
+                    ModuleCheck::Writes(pending) => {
+                        check_write(&tables[position], pending, transaction)?
+                    }
+                    ModuleCheck::Copied { tables, timeline } => {
+                        self.check_copied_write(tables, timeline, transaction)?
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Dry run of `transaction` on copies of the module's tables and timeline.
+    fn check_copied_write(
+        &self,
+        tables: &mut Tables,
+        timeline: &mut Option<Timeline>,
+        transaction: &Transaction,
+    ) -> Result<(), IntersticeError> {
+        match transaction {
+            Transaction::Insert {
+                module_name,
+                table_name,
+                new_row,
+            } => {
+                let table = copied_table(tables, module_name, table_name)?;
+                table.validate_quota(TableUsage::default(), table.quota_row_bytes(new_row))?;
+                table.insert(new_row.clone())?;
+            }
+            Transaction::Update {
+                module_name,
+                table_name,
+                update_row,
+            } => {
+                let table = copied_table(tables, module_name, table_name)?;
+                table.validate_update(update_row)?;
+                table.validate_byte_quota(
+                    TableUsage::default(),
+                    table.quota_update_bytes(update_row),
+                )?;
+                table.update(update_row.clone())?;
+            }
+            Transaction::Delete {
+                module_name,
+                table_name,
+                deleted_row_id,
+            } => {
+                // A missing row is skipped on commit as well.
+                let _ = copied_table(tables, module_name, table_name)?.delete(deleted_row_id);
+            }
+            Transaction::Clear {
+                module_name,
+                table_name,
+            } => copied_table(tables, module_name, table_name)?.clear(),
+            Transaction::TimeTravel {
+                module_name,
+                operation,
+            } => {
+                let timeline = timeline.get_or_insert_with(|| self.timeline_copy(module_name));
+                self.check_time_travel_commit(module_name, timeline, tables, operation)?;
+            }
+            Transaction::Schedule { .. } | Transaction::CancelSchedule { .. } => {}
+        }
+        Ok(())
+    }
+
     /// Hot path: apply all transactions from a single reducer call, holding
     /// `module.tables` locked exactly once for the full batch. Avoids the
     /// per-transaction lock/unlock overhead, and skips the `Row::clone()`
@@ -46,118 +244,95 @@ impl Runtime {
             return Ok(Vec::new());
         }
 
+        let mut tables = module.tables.lock();
+        // Calls inserting into the same table run concurrently and each checked the
+        // quotas against its own writes only: check them again against the rows
+        // committed since, before applying anything.
+        let segment = [(module.clone(), transactions)];
+        if writes_limited_table(&tables, &segment[0].1) {
+            self.check_transactions(
+                &segment,
+                std::slice::from_ref(&module.tables),
+                std::slice::from_ref(&tables),
+            )?;
+        }
+        let [(_, transactions)] = segment;
+
+        let mut applied = AppliedTransactions::default();
+        let result = self.apply_locked_transactions(transactions, &mut tables, &mut applied);
+        drop(tables);
+        self.finish_committed_transactions(applied, result)
+    }
+
+    /// Apply `transactions` to the tables of their module, which the caller holds
+    /// locked.
+    fn apply_locked_transactions(
+        &self,
+        transactions: Vec<Transaction>,
+        tables: &mut Tables,
+        applied: &mut AppliedTransactions,
+    ) -> Result<(), IntersticeError> {
         let has_subscriptions = self
             .active_subscription_count
             .load(std::sync::atomic::Ordering::Relaxed)
             > 0;
+        let AppliedTransactions {
+            events,
+            snapshots,
+            schedule_changes,
+        } = applied;
 
-        let mut events = Vec::new();
-        // Deferred snapshot work: collected under the lock, executed after.
-        let mut snapshots: Vec<(SnapshotPlan, Vec<Row>)> = Vec::new();
-
-        {
-            let mut tables = module.tables.lock();
-
-            for transaction in transactions {
-                match transaction {
-                    Transaction::Insert {
-                        module_name,
-                        table_name,
-                        new_row,
-                    } => {
-                        let table = tables.get_mut(&table_name).ok_or_else(|| {
-                            IntersticeError::TableNotFound {
-                                module_name: module_name.clone(),
-                                table_name: table_name.clone(),
-                            }
-                        })?;
-                        let persistence_kind = table.schema.persistence.clone();
-
-                        // Clone only when actually needed after the insert.
-                        // Hot path (ephemeral + no subscriptions): zero allocations.
-                        let need_copy = has_subscriptions
-                            || !matches!(persistence_kind, PersistenceKind::Ephemeral);
-                        let row_copy = need_copy.then(|| new_row.clone());
-                        table.insert_trusted(new_row)?;
-
-                        if let Some(row) = row_copy {
-                            if has_subscriptions {
-                                events.push(EventInstance::TableInsertEvent {
-                                    source_node_id: None,
-                                    module_name: module_name.clone(),
-                                    table_name: table_name.clone(),
-                                    inserted_row: row.clone(),
-                                });
-                            }
-                            match &persistence_kind {
-                                PersistenceKind::Stateful => {
-                                    let pk = row_primary_key(&row)?;
-                                    self.persistence.persist_stateful_insert(
-                                        &module_name,
-                                        &table_name,
-                                        &pk,
-                                        &row,
-                                    )?;
-                                }
-                                PersistenceKind::Logged => {
-                                    let pk = row_primary_key(&row)?;
-                                    if let Some(plan) = self.persistence.record_logged_operation(
-                                        &module_name,
-                                        &table_name,
-                                        LogOperation::Insert {
-                                            primary_key: pk,
-                                            row: Some(row.clone()),
-                                        },
-                                    )? {
-                                        snapshots.push((plan, table.snapshot_rows()));
-                                    }
-                                }
-                                PersistenceKind::Ephemeral => {}
-                            }
+        for transaction in transactions {
+            match transaction {
+                Transaction::Insert {
+                    module_name,
+                    table_name,
+                    new_row,
+                } => {
+                    let table = tables.get_mut(&table_name).ok_or_else(|| {
+                        IntersticeError::TableNotFound {
+                            module_name: module_name.clone(),
+                            table_name: table_name.clone(),
                         }
-                    }
+                    })?;
+                    let persistence_kind = table.schema.persistence.clone();
 
-                    Transaction::Update {
-                        module_name,
-                        table_name,
-                        update_row,
-                    } => {
-                        let table = tables.get_mut(&table_name).ok_or_else(|| {
-                            IntersticeError::TableNotFound {
-                                module_name: module_name.clone(),
-                                table_name: table_name.clone(),
-                            }
-                        })?;
-                        let persistence_kind = table.schema.persistence.clone();
-                        let old_row = table.update(update_row.clone())?;
+                    // Clone only when actually needed after the insert.
+                    // Hot path (ephemeral + no subscriptions): zero allocations.
+                    let need_copy = has_subscriptions
+                        || !matches!(persistence_kind, PersistenceKind::Ephemeral);
+                    let row_copy = need_copy.then(|| new_row.clone());
+                    table.insert_trusted(new_row)?;
 
+                    if let Some(row) = row_copy {
                         if has_subscriptions {
-                            events.push(EventInstance::TableUpdateEvent {
+                            events.push(EventInstance::TableInsertEvent {
                                 source_node_id: None,
+                                filter: None,
+                                sequence: 0,
                                 module_name: module_name.clone(),
                                 table_name: table_name.clone(),
-                                old_row,
-                                new_row: update_row.clone(),
+                                inserted_row: row.clone(),
                             });
                         }
-                        match persistence_kind {
+                        match &persistence_kind {
                             PersistenceKind::Stateful => {
-                                let pk = row_primary_key(&update_row)?;
-                                self.persistence.persist_stateful_update(
+                                let pk = row_primary_key(&row)?;
+                                self.persistence.persist_stateful_insert(
                                     &module_name,
                                     &table_name,
                                     &pk,
-                                    &update_row,
+                                    &row,
                                 )?;
                             }
                             PersistenceKind::Logged => {
-                                let pk = row_primary_key(&update_row)?;
+                                let pk = row_primary_key(&row)?;
                                 if let Some(plan) = self.persistence.record_logged_operation(
                                     &module_name,
                                     &table_name,
-                                    LogOperation::Update {
+                                    LogOperation::Insert {
                                         primary_key: pk,
-                                        row: Some(update_row),
+                                        row: Some(row.clone()),
                                     },
                                 )? {
                                     snapshots.push((plan, table.snapshot_rows()));
@@ -166,106 +341,223 @@ impl Runtime {
                             PersistenceKind::Ephemeral => {}
                         }
                     }
+                }
 
-                    Transaction::Delete {
-                        module_name,
-                        table_name,
-                        deleted_row_id,
-                    } => {
-                        let table = tables.get_mut(&table_name).ok_or_else(|| {
-                            IntersticeError::TableNotFound {
-                                module_name: module_name.clone(),
-                                table_name: table_name.clone(),
-                            }
-                        })?;
-                        let persistence_kind = table.schema.persistence.clone();
-
-                        if let Ok(deleted_row) = table.delete(&deleted_row_id) {
-                            if has_subscriptions {
-                                events.push(EventInstance::TableDeleteEvent {
-                                    source_node_id: None,
-                                    module_name: module_name.clone(),
-                                    table_name: table_name.clone(),
-                                    deleted_row,
-                                });
-                            }
+                Transaction::Update {
+                    module_name,
+                    table_name,
+                    update_row,
+                } => {
+                    let table = tables.get_mut(&table_name).ok_or_else(|| {
+                        IntersticeError::TableNotFound {
+                            module_name: module_name.clone(),
+                            table_name: table_name.clone(),
                         }
-                        match persistence_kind {
-                            PersistenceKind::Stateful => {
-                                self.persistence.persist_stateful_delete(
-                                    &module_name,
-                                    &table_name,
-                                    &deleted_row_id,
-                                )?;
-                            }
-                            PersistenceKind::Logged => {
-                                if let Some(plan) = self.persistence.record_logged_operation(
-                                    &module_name,
-                                    &table_name,
-                                    LogOperation::Delete {
-                                        primary_key: deleted_row_id,
-                                    },
-                                )? {
-                                    snapshots.push((plan, table.snapshot_rows()));
-                                }
+                    })?;
+                    let persistence_kind = table.schema.persistence.clone();
+                    let old_row = table.update(update_row.clone())?;
+
+                    if has_subscriptions {
+                        events.push(EventInstance::TableUpdateEvent {
+                            source_node_id: None,
+                            filter: None,
+                            sequence: 0,
+                            module_name: module_name.clone(),
+                            table_name: table_name.clone(),
+                            old_row,
+                            new_row: update_row.clone(),
+                        });
+                    }
+                    match persistence_kind {
+                        PersistenceKind::Stateful => {
+                            let pk = row_primary_key(&update_row)?;
+                            self.persistence.persist_stateful_update(
+                                &module_name,
+                                &table_name,
+                                &pk,
+                                &update_row,
+                            )?;
+                        }
+                        PersistenceKind::Logged => {
+                            let pk = row_primary_key(&update_row)?;
+                            if let Some(plan) = self.persistence.record_logged_operation(
+                                &module_name,
+                                &table_name,
+                                LogOperation::Update {
+                                    primary_key: pk,
+                                    row: Some(update_row),
+                                },
+                            )? {
+                                snapshots.push((plan, table.snapshot_rows()));
                             }
-                            PersistenceKind::Ephemeral => {}
                         }
+                        PersistenceKind::Ephemeral => {}
                     }
+                }
 
-                    Transaction::Clear {
-                        module_name,
-                        table_name,
-                    } => {
-                        let table = tables.get_mut(&table_name).ok_or_else(|| {
-                            IntersticeError::TableNotFound {
-                                module_name: module_name.clone(),
-                                table_name: table_name.clone(),
-                            }
-                        })?;
-                        let persistence_kind = table.schema.persistence.clone();
-                        let deleted_rows = if has_subscriptions {
-                            table.snapshot_rows()
-                        } else {
-                            vec![]
-                        };
-                        table.clear();
-
-                        for deleted_row in deleted_rows {
-                            events.push(EventInstance::TableDeleteEvent {
-                                source_node_id: None,
-                                module_name: module_name.clone(),
-                                table_name: table_name.clone(),
-                                deleted_row,
-                            });
+                Transaction::Delete {
+                    module_name,
+                    table_name,
+                    deleted_row_id,
+                } => {
+                    let table = tables.get_mut(&table_name).ok_or_else(|| {
+                        IntersticeError::TableNotFound {
+                            module_name: module_name.clone(),
+                            table_name: table_name.clone(),
                         }
-                        match persistence_kind {
-                            PersistenceKind::Stateful => {
-                                self.persistence
-                                    .persist_stateful_clear(&module_name, &table_name)?;
+                    })?;
+                    let persistence_kind = table.schema.persistence.clone();
+
+                    if let Ok(deleted_row) = table.delete(&deleted_row_id)
+                        && has_subscriptions
+                    {
+                        events.push(EventInstance::TableDeleteEvent {
+                            source_node_id: None,
+                            filter: None,
+                            sequence: 0,
+                            module_name: module_name.clone(),
+                            table_name: table_name.clone(),
+                            deleted_row,
+                        });
+                    }
+                    match persistence_kind {
+                        PersistenceKind::Stateful => {
+                            self.persistence.persist_stateful_delete(
+                                &module_name,
+                                &table_name,
+                                &deleted_row_id,
+                            )?;
+                        }
+                        PersistenceKind::Logged => {
+                            if let Some(plan) = self.persistence.record_logged_operation(
+                                &module_name,
+                                &table_name,
+                                LogOperation::Delete {
+                                    primary_key: deleted_row_id,
+                                },
+                            )? {
+                                snapshots.push((plan, table.snapshot_rows()));
                             }
-                            PersistenceKind::Logged => {
-                                if let Some(plan) = self.persistence.record_logged_operation(
-                                    &module_name,
-                                    &table_name,
-                                    LogOperation::Clear,
-                                )? {
-                                    snapshots.push((plan, table.snapshot_rows()));
-                                }
+                        }
+                        PersistenceKind::Ephemeral => {}
+                    }
+                }
+
+                Transaction::Clear {
+                    module_name,
+                    table_name,
+                } => {
+                    let table = tables.get_mut(&table_name).ok_or_else(|| {
+                        IntersticeError::TableNotFound {
+                            module_name: module_name.clone(),
+                            table_name: table_name.clone(),
+                        }
+                    })?;
+                    let persistence_kind = table.schema.persistence.clone();
+                    let deleted_rows = if has_subscriptions {
+                        table.snapshot_rows()
+                    } else {
+                        vec![]
+                    };
+                    table.clear();
+
+                    for deleted_row in deleted_rows {
+                        events.push(EventInstance::TableDeleteEvent {
+                            source_node_id: None,
+                            filter: None,
+                            sequence: 0,
+                            module_name: module_name.clone(),
+                            table_name: table_name.clone(),
+                            deleted_row,
+                        });
+                    }
+                    match persistence_kind {
+                        PersistenceKind::Stateful => {
+                            self.persistence
+                                .persist_stateful_clear(&module_name, &table_name)?;
+                        }
+                        PersistenceKind::Logged => {
+                            if let Some(plan) = self.persistence.record_logged_operation(
+                                &module_name,
+                                &table_name,
+                                LogOperation::Clear,
+                            )? {
+                                snapshots.push((plan, table.snapshot_rows()));
                             }
-                            PersistenceKind::Ephemeral => {}
                         }
+                        PersistenceKind::Ephemeral => {}
                     }
                 }
+
+                Transaction::TimeTravel {
+                    module_name,
+                    operation,
+                } => {
+                    self.apply_time_travel(
+                        &module_name,
+                        tables,
+                        operation,
+                        has_subscriptions,
+                        events,
+                        snapshots,
+                    )?;
+                }
+
+                change @ (Transaction::Schedule { .. } | Transaction::CancelSchedule { .. }) => {
+                    schedule_changes.push(change);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Finish the transactions applied before `result` was returned. When applying
+    /// stopped on an error, the ones applied before it stay committed: their
+    /// events are sent and their schedule changes made before the error is
+    /// returned.
+    fn finish_committed_transactions(
+        &self,
+        applied: AppliedTransactions,
+        result: Result<(), IntersticeError>,
+    ) -> Result<Vec<EventInstance>, IntersticeError> {
+        let events = self.finish_applied_transactions(applied)?;
+        match result {
+            Ok(()) => Ok(events),
+            Err(err) => {
+                self.dispatch_events(events);
+                Err(err)
+            }
+        }
+    }
+
+    /// Make the schedule changes and logged snapshots of applied transactions,
+    /// once the tables are unlocked.
+    fn finish_applied_transactions(
+        &self,
+        applied: AppliedTransactions,
+    ) -> Result<Vec<EventInstance>, IntersticeError> {
+        for change in applied.schedule_changes {
+            match change {
+                Transaction::Schedule {
+                    module_name,
+                    schedule,
+                } => self.add_schedule(&module_name, schedule),
+                Transaction::CancelSchedule {
+                    module_name,
+                    schedule_id,
+                } => {
+                    self.cancel_schedule(&module_name, schedule_id);
+                }
+                _ => unreachable!(),
             }
-        } // tables lock released here
+        }
 
         // Execute any deferred logged snapshots outside the tables lock.
-        for (plan, rows) in snapshots {
+        for (plan, rows) in applied.snapshots {
             self.persistence.snapshot_logged_table(plan, rows)?;
         }
 
-        Ok(events)
+        Ok(applied.events)
     }
 
     pub(crate) fn apply_transaction(
@@ -336,6 +628,8 @@ impl Runtime {
                 if has_subscriptions {
                     events.push(EventInstance::TableInsertEvent {
                         source_node_id: None,
+                        filter: None,
+                        sequence: 0,
                         module_name,
                         table_name,
                         inserted_row: new_row.clone(),
@@ -416,6 +710,8 @@ impl Runtime {
                 if has_subscriptions {
                     events.push(EventInstance::TableUpdateEvent {
                         source_node_id: None,
+                        filter: None,
+                        sequence: 0,
                         module_name,
                         table_name,
                         old_row,
@@ -496,6 +792,8 @@ impl Runtime {
                     if has_subscriptions {
                         events.push(EventInstance::TableDeleteEvent {
                             source_node_id: None,
+                            filter: None,
+                            sequence: 0,
                             module_name,
                             table_name,
                             deleted_row,
@@ -573,6 +871,8 @@ impl Runtime {
                     for deleted_row in deleted_rows {
                         events.push(EventInstance::TableDeleteEvent {
                             source_node_id: None,
+                            filter: None,
+                            sequence: 0,
                             module_name: module_name.clone(),
                             table_name: table_name.clone(),
                             deleted_row,
@@ -603,6 +903,20 @@ impl Runtime {
                     }
                 }
             }
+
+            Transaction::TimeTravel { module_name, .. } => {
+                return Err(IntersticeError::Internal(format!(
+                    "Time travel of '{}' is only applied with the writes of a reducer",
+                    module_name
+                )));
+            }
+            Transaction::Schedule { module_name, .. }
+            | Transaction::CancelSchedule { module_name, .. } => {
+                return Err(IntersticeError::Internal(format!(
+                    "Schedules of '{}' are only changed with the writes of a reducer",
+                    module_name
+                )));
+            }
         }
 
         if log_transaction {
@@ -623,3 +937,271 @@ fn row_primary_key(row: &Row) -> Result<IndexKey, IntersticeError> {
         .try_into()
         .map_err(|err| IntersticeError::Internal(format!("Failed to convert primary key: {}", err)))
 }
+
+/// Position of the lock on the tables of `module` in `locks`.
+fn lock_position(locks: &[Arc<Mutex<Tables>>], module: &Module) -> usize {
+    locks
+        .iter()
+        .position(|lock| Arc::ptr_eq(lock, &module.tables))
+        .expect("tables of every segment are locked")
+}
+
+/// Whether `transactions` write a table with a row or byte quota.
+fn writes_limited_table(tables: &Tables, transactions: &[Transaction]) -> bool {
+    transactions.iter().any(|transaction| {
+        let table_name = match transaction {
+            Transaction::Insert { table_name, .. } | Transaction::Update { table_name, .. } => {
+                table_name
+            }
+            _ => return false,
+        };
+        tables.get(table_name).is_some_and(|table| {
+            let limits = &table.schema.limits;
+            limits.max_rows.is_some() || limits.max_bytes.is_some()
+        })
+    })
+}
+
+fn copied_table<'a>(
+    tables: &'a mut Tables,
+    module_name: &str,
+    table_name: &str,
+) -> Result<&'a mut Table, IntersticeError> {
+    tables
+        .get_mut(table_name)
+        .ok_or_else(|| IntersticeError::TableNotFound {
+            module_name: module_name.to_string(),
+            table_name: table_name.to_string(),
+        })
+}
+
+/// Check `transaction` against the locked `tables` and the writes queued before it.
+fn check_write(
+    tables: &Tables,
+    pending: &mut HashMap<String, PendingTableWrites>,
+    transaction: &Transaction,
+) -> Result<(), IntersticeError> {
+    let (module_name, table_name) = match transaction {
+        Transaction::Insert {
+            module_name,
+            table_name,
+            ..
+        }
+        | Transaction::Update {
+            module_name,
+            table_name,
+            ..
+        }
+        | Transaction::Delete {
+            module_name,
+            table_name,
+            ..
+        }
+        | Transaction::Clear {
+            module_name,
+            table_name,
+        } => (module_name, table_name),
+        _ => return Ok(()),
+    };
+    let table = tables
+        .get(table_name)
+        .ok_or_else(|| IntersticeError::TableNotFound {
+            module_name: module_name.clone(),
+            table_name: table_name.clone(),
+        })?;
+    let writes = pending.entry(table_name.clone()).or_default();
+    match transaction {
+        Transaction::Insert { new_row, .. } => writes.insert(table, new_row),
+        Transaction::Update { update_row, .. } => writes.update(table, update_row),
+        Transaction::Delete { deleted_row_id, .. } => writes.delete(table, deleted_row_id),
+        Transaction::Clear { .. } => {
+            writes.clear(table);
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::IntersticeError;
+    use crate::testing::TestRuntime;
+    use crate::testing::fixture::{FixtureModule, Step, row};
+    use interstice_abi::{
+        CallReducerRequest, HostCall, IndexSchema, IndexType, IntersticeType, IntersticeValue,
+        ModuleSelection, NodeSelection, PersistenceKind, ReducerSchema,
+    };
+
+    #[tokio::test]
+    async fn atomic_calls_commit_nothing_when_a_later_segment_fails() {
+        let runtime = TestRuntime::starting_at(1_000).unwrap();
+        // Both items pass the unique index check on their own, but not together.
+        let item = |id| Step::Insert {
+            table: "items".into(),
+            row: row(id, vec![IntersticeValue::U64(7)]),
+        };
+        let inventory = FixtureModule::new("inventory")
+            .table(
+                "items",
+                PersistenceKind::Stateful,
+                &[("sku", IntersticeType::U64)],
+            )
+            .with_table(|table| {
+                table.indexes.push(IndexSchema {
+                    field_name: "sku".into(),
+                    index_type: IndexType::Hash,
+                    unique: true,
+                    auto_inc: false,
+                    fields: Vec::new(),
+                })
+            })
+            .reducer("add_first", vec![item(1)])
+            .reducer("add_second", vec![item(2)]);
+        let call = |reducer: &str| {
+            Step::HostCall(HostCall::CallReducer(CallReducerRequest {
+                node_selection: NodeSelection::Current,
+                module_selection: ModuleSelection::Other("inventory".into()),
+                reducer_name: reducer.into(),
+                input: IntersticeValue::Vec(Vec::new()),
+            }))
+        };
+        let shop = FixtureModule::new("shop")
+            .table(
+                "orders",
+                PersistenceKind::Stateful,
+                &[("total", IntersticeType::U64)],
+            )
+            .reducer(
+                "buy",
+                vec![
+                    Step::Insert {
+                        table: "orders".into(),
+                        row: row(1, vec![IntersticeValue::U64(10)]),
+                    },
+                    call("add_first"),
+                    call("add_second"),
+                ],
+            )
+            .with_reducer(|reducer| reducer.atomic = true);
+        runtime.load_module(&inventory.wasm()).await.unwrap();
+        runtime.load_module(&shop.wasm()).await.unwrap();
+
+        assert!(matches!(
+            runtime.call_reducer("shop", "buy", Vec::new()).await,
+            Err(IntersticeError::TransactionAborted { .. })
+        ));
+        assert!(runtime.rows("shop", "orders").unwrap().is_empty());
+        assert!(runtime.rows("inventory", "items").unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn concurrent_inserts_cannot_commit_past_the_row_quota() {
+        let runtime = TestRuntime::starting_at(1_000).unwrap();
+        // Inserts into the same table run concurrently: both calls insert while
+        // the table is empty, then commit.
+        let claim = |id| {
+            vec![
+                Step::Insert {
+                    table: "slots".into(),
+                    row: row(id, vec![IntersticeValue::U64(id)]),
+                },
+                Step::Wait {
+                    iterations: 20_000_000,
+                },
+            ]
+        };
+        let inserts_only = |reducer: &mut ReducerSchema| {
+            reducer.reads.clear();
+            reducer.updates.clear();
+            reducer.deletes.clear();
+        };
+        let module = FixtureModule::new("quota")
+            .table(
+                "slots",
+                PersistenceKind::Ephemeral,
+                &[("owner", IntersticeType::U64)],
+            )
+            .with_table(|table| table.limits.max_rows = Some(1))
+            .reducer("claim_first", claim(1))
+            .with_reducer(inserts_only)
+            .reducer("claim_second", claim(2))
+            .with_reducer(inserts_only);
+        runtime.load_module(&module.wasm()).await.unwrap();
+
+        let (first, second) = tokio::join!(
+            runtime.call_reducer("quota", "claim_first", Vec::new()),
+            runtime.call_reducer("quota", "claim_second", Vec::new()),
+        );
+        // A call that ran after the other saw its row and skipped the insert.
+        for result in [first, second] {
+            assert!(matches!(
+                result,
+                Ok(()) | Err(IntersticeError::TableQuotaExceeded { .. })
+            ));
+        }
+        assert_eq!(runtime.rows("quota", "slots").unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn aborted_atomic_calls_give_back_their_auto_inc_ids() {
+        let runtime = TestRuntime::starting_at(1_000).unwrap();
+        // Both items draw an id, then fail the unique index check together.
+        let item = || Step::Insert {
+            table: "items".into(),
+            row: row(0, vec![IntersticeValue::U64(7)]),
+        };
+        let inventory = FixtureModule::new("inventory")
+            .table(
+                "items",
+                PersistenceKind::Stateful,
+                &[("sku", IntersticeType::U64)],
+            )
+            .with_table(|table| {
+                table.primary_key_auto_inc = true;
+                table.indexes.push(IndexSchema {
+                    field_name: "sku".into(),
+                    index_type: IndexType::Hash,
+                    unique: true,
+                    auto_inc: false,
+                    fields: Vec::new(),
+                })
+            })
+            .reducer("add", vec![item()])
+            .reducer("add_then_fail", vec![item(), Step::Spin])
+            .with_reducer(|reducer| {
+                reducer.atomic = true;
+                reducer.fuel = Some(10_000);
+            });
+        let call = || {
+            Step::HostCall(HostCall::CallReducer(CallReducerRequest {
+                node_selection: NodeSelection::Current,
+                module_selection: ModuleSelection::Other("inventory".into()),
+                reducer_name: "add".into(),
+                input: IntersticeValue::Vec(Vec::new()),
+            }))
+        };
+        let shop = FixtureModule::new("shop")
+            .reducer("buy_twice", vec![call(), call()])
+            .with_reducer(|reducer| reducer.atomic = true);
+        runtime.load_module(&inventory.wasm()).await.unwrap();
+        runtime.load_module(&shop.wasm()).await.unwrap();
+
+        assert!(matches!(
+            runtime.call_reducer("shop", "buy_twice", Vec::new()).await,
+            Err(IntersticeError::TransactionAborted { .. })
+        ));
+        assert!(
+            runtime
+                .call_reducer("inventory", "add_then_fail", Vec::new())
+                .await
+                .is_err()
+        );
+        runtime
+            .call_reducer("inventory", "add", Vec::new())
+            .await
+            .unwrap();
+        let items = runtime.rows("inventory", "items").unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].primary_key, IntersticeValue::U64(0));
+    }
+}
@@ -0,0 +1,597 @@
+//! Table views (row-level security).
+//!
+//! A public table may name a query of its module with `#[table(public, view = "...")]`.
+//! Whenever another module or node reads the table — scans, index scans, primary key
+//! lookups, table event fan-out and remote table syncs — the query is called with the
+//! row and the reader as caller (`caller_module_name`, empty for remote nodes, and
+//! `caller_node_id`), and rows it rejects are left out. An update that moves a row
+//! into or out of a module's view reaches its insert or delete subscribers as an
+//! insert or a delete. The owning module always sees every row.
+
+use crate::{
+    error::IntersticeError,
+    logger::{LogLevel, LogSource},
+    node::NodeId,
+    runtime::{Runtime, event::EventInstance, event::SubscriptionTarget},
+};
+use interstice_abi::{
+    IntersticeType, IntersticeValue, ModuleSchema, Row, RowFilter, SubscriptionEventSchema,
+    TableSchema,
+};
+
+/// Check that every table view names a query taking one row of the table and
+/// returning `bool`.
+pub(crate) fn validate_table_views(module_schema: &ModuleSchema) -> Result<(), IntersticeError> {
+    for table in &module_schema.tables {
+        let Some(view) = &table.view else {
+            continue;
+        };
+        let invalid = |reason: String| IntersticeError::InvalidTableView {
+            module_name: module_schema.name.clone(),
+            table_name: table.name.clone(),
+            reason,
+        };
+        let query = module_schema
+            .queries
+            .iter()
+            .find(|query| &query.name == view)
+            .ok_or_else(|| invalid(format!("query '{}' not found", view)))?;
+        let row_type = IntersticeType::Named(table.type_name.clone()).to_string();
+        let takes_row = query.arguments.len() == 1
+            && query.arguments[0].field_type.to_string() == row_type;
+        if !takes_row || !matches!(query.return_type, IntersticeType::Bool) {
+            return Err(invalid(format!(
+                "query '{}' must have the signature `fn(QueryContext, {}) -> bool`",
+                view, table.type_name
+            )));
+        }
+    }
+    Ok(())
+}
+
+impl Runtime {
+    /// Keep the rows of `module_name`'s table that the reader may see.
+    pub(crate) fn filter_visible_rows(
+        &self,
+        module_name: &str,
+        table_schema: &TableSchema,
+        rows: Vec<Row>,
+        reader_node_id: NodeId,
+        reader_module_name: &str,
+    ) -> Result<Vec<Row>, IntersticeError> {
+        if !self.view_applies(module_name, table_schema, reader_node_id, reader_module_name) {
+            return Ok(rows);
+        }
+        let visible = self.view_verdicts(
+            module_name,
+            table_schema,
+            &rows,
+            reader_node_id,
+            reader_module_name,
+        )?;
+        Ok(rows
+            .into_iter()
+            .zip(visible)
+            .filter_map(|(row, visible)| visible.then_some(row))
+            .collect())
+    }
+
+    /// Run the view query on every row in one batch.
+    fn view_verdicts(
+        &self,
+        module_name: &str,
+        table_schema: &TableSchema,
+        rows: &[Row],
+        reader_node_id: NodeId,
+        reader_module_name: &str,
+    ) -> Result<Vec<bool>, IntersticeError> {
+        let view = table_schema.view.as_ref().unwrap();
+        let args = rows
+            .iter()
+            .map(|row| IntersticeValue::Vec(vec![table_schema.row_value(row)]));
+        self.call_query_batch(module_name, view, args, reader_node_id, reader_module_name)?
+            .into_iter()
+            .map(|verdict| match verdict {
+                IntersticeValue::Bool(visible) => Ok(visible),
+                other => Err(IntersticeError::InvalidTableView {
+                    module_name: module_name.to_string(),
+                    table_name: table_schema.name.clone(),
+                    reason: format!("query '{}' returned {:?} instead of a bool", view, other),
+                }),
+            })
+            .collect()
+    }
+
+    fn view_applies(
+        &self,
+        module_name: &str,
+        table_schema: &TableSchema,
+        reader_node_id: NodeId,
+        reader_module_name: &str,
+    ) -> bool {
+        let is_owner =
+            reader_node_id == self.network_handle.node_id && reader_module_name == module_name;
+        table_schema.view.is_some() && !is_owner
+    }
+
+    /// Check the filter of a remote node's table subscription against the table.
+    pub(crate) fn validate_subscription_filter(
+        &self,
+        event: &SubscriptionEventSchema,
+        filter: &RowFilter,
+    ) -> Result<(), IntersticeError> {
+        let (module_name, table_name) = match event {
+            SubscriptionEventSchema::Insert {
+                module_name,
+                table_name,
+                ..
+            }
+            | SubscriptionEventSchema::Update {
+                module_name,
+                table_name,
+                ..
+            }
+            | SubscriptionEventSchema::Delete {
+                module_name,
+                table_name,
+                ..
+            } => (module_name, table_name),
+            _ => return Ok(()),
+        };
+        let modules = self.modules.lock();
+        let module = modules.get(module_name).ok_or_else(|| {
+            IntersticeError::ModuleNotFound(
+                module_name.clone(),
+                "When handling subscription request".to_string(),
+            )
+        })?;
+        let table_schema = module
+            .schema
+            .tables
+            .iter()
+            .find(|table| &table.name == table_name)
+            .ok_or_else(|| IntersticeError::TableNotFound {
+                module_name: module_name.clone(),
+                table_name: table_name.clone(),
+            })?;
+        filter
+            .validate(table_schema)
+            .map_err(|reason| IntersticeError::InvalidTableQuery {
+                table_name: table_name.clone(),
+                reason,
+            })
+    }
+
+    /// Subscribers of a table event, each with the event it receives or `None` when
+    /// the event is hidden from it, followed by the insert and delete subscribers an
+    /// update moves the row in or out of the view or filter of.
+    pub(crate) fn table_event_deliveries(
+        &self,
+        event: &EventInstance,
+    ) -> Vec<(SubscriptionTarget, Option<EventInstance>)> {
+        let mut out = Vec::new();
+        for target in self.find_subscriptions(event).unwrap_or_default() {
+            let visible = self.visible_table_event(event, &target);
+            out.push((target, visible));
+        }
+        out.extend(
+            self.view_transition_events(event)
+                .into_iter()
+                .map(|(target, event)| (target, Some(event))),
+        );
+        out
+    }
+
+    /// The table event as `target` may receive it, or `None` when it must not see it.
+    /// Rows are checked against the table view and, for remote subscribers, the
+    /// filter they subscribed with. An update is only forwarded when both rows are
+    /// visible (see [`Runtime::view_transition_events`] for the others), except to
+    /// unfiltered remote subscribers, which replicate the table: they get an insert
+    /// when the row starts being visible to them and a delete when it stops, which
+    /// keeps their copy consistent. Converted events keep the sequence number of the
+    /// update.
+    pub(crate) fn visible_table_event(
+        &self,
+        event: &EventInstance,
+        target: &SubscriptionTarget,
+    ) -> Option<EventInstance> {
+        let (module_name, table_name) = match event {
+            EventInstance::TableInsertEvent {
+                source_node_id: None,
+                module_name,
+                table_name,
+                ..
+            }
+            | EventInstance::TableUpdateEvent {
+                source_node_id: None,
+                module_name,
+                table_name,
+                ..
+            }
+            | EventInstance::TableDeleteEvent {
+                source_node_id: None,
+                module_name,
+                table_name,
+                ..
+            } => (module_name, table_name),
+            _ => return Some(event.clone()),
+        };
+        let filter = match target {
+            SubscriptionTarget::Remote { filter, .. } => filter.as_ref(),
+            SubscriptionTarget::Local { .. } => None,
+        };
+        let Some(table_schema) = self.filtered_table_schema(module_name, table_name, filter)
+        else {
+            return Some(event.clone());
+        };
+        let (reader_node_id, reader_module_name) = match target {
+            SubscriptionTarget::Local { module, .. } => (self.network_handle.node_id, module.as_str()),
+            SubscriptionTarget::Remote { node_id, .. } => (*node_id, ""),
+        };
+        let visible = |rows: &[Row]| {
+            self.rows_visible_to(
+                module_name,
+                &table_schema,
+                filter,
+                rows,
+                reader_node_id,
+                reader_module_name,
+            )
+        };
+
+        match event {
+            EventInstance::TableInsertEvent { inserted_row, .. } => {
+                visible(std::slice::from_ref(inserted_row))[0].then(|| event.clone())
+            }
+            EventInstance::TableDeleteEvent { deleted_row, .. } => {
+                visible(std::slice::from_ref(deleted_row))[0].then(|| event.clone())
+            }
+            EventInstance::TableUpdateEvent {
+                sequence,
+                old_row,
+                new_row,
+                ..
+            } => match (visible(&[old_row.clone(), new_row.clone()]).as_slice(), target) {
+                (&[true, true], _) => Some(event.clone()),
+                (&[false, true], SubscriptionTarget::Remote { filter: None, .. }) => {
+                    Some(EventInstance::TableInsertEvent {
+                        source_node_id: None,
+                        filter: None,
+                        sequence: *sequence,
+                        module_name: module_name.clone(),
+                        table_name: table_name.clone(),
+                        inserted_row: new_row.clone(),
+                    })
+                }
+                (&[true, false], SubscriptionTarget::Remote { filter: None, .. }) => {
+                    Some(EventInstance::TableDeleteEvent {
+                        source_node_id: None,
+                        filter: None,
+                        sequence: *sequence,
+                        module_name: module_name.clone(),
+                        table_name: table_name.clone(),
+                        deleted_row: old_row.clone(),
+                    })
+                }
+                _ => None,
+            },
+            _ => Some(event.clone()),
+        }
+    }
+
+    /// Events for the local insert and delete subscribers of a table with a view, and
+    /// the remote ones that subscribed with a filter, for an update that changes
+    /// whether its row is visible to them: the row enters the view of an insert
+    /// subscriber it became visible to, and leaves the view of a delete subscriber it
+    /// became hidden from. Both keep the sequence number of the update.
+    pub(crate) fn view_transition_events(
+        &self,
+        event: &EventInstance,
+    ) -> Vec<(SubscriptionTarget, EventInstance)> {
+        let EventInstance::TableUpdateEvent {
+            source_node_id: None,
+            sequence,
+            module_name,
+            table_name,
+            old_row,
+            new_row,
+            ..
+        } = event
+        else {
+            return Vec::new();
+        };
+        let view_schema = self.filtered_table_schema(module_name, table_name, None);
+        let inserted = EventInstance::TableInsertEvent {
+            source_node_id: None,
+            filter: None,
+            sequence: *sequence,
+            module_name: module_name.clone(),
+            table_name: table_name.clone(),
+            inserted_row: new_row.clone(),
+        };
+        let deleted = EventInstance::TableDeleteEvent {
+            source_node_id: None,
+            filter: None,
+            sequence: *sequence,
+            module_name: module_name.clone(),
+            table_name: table_name.clone(),
+            deleted_row: old_row.clone(),
+        };
+        let rows = [old_row.clone(), new_row.clone()];
+
+        let mut out = Vec::new();
+        for (converted, transition) in [(inserted, [false, true]), (deleted, [true, false])] {
+            for target in self.find_subscriptions(&converted).unwrap_or_default() {
+                let (filter, reader_node_id, reader_module_name) = match &target {
+                    SubscriptionTarget::Local { module, .. } => {
+                        (None, self.network_handle.node_id, module.as_str())
+                    }
+                    SubscriptionTarget::Remote {
+                        node_id,
+                        filter: Some(filter),
+                    } => (Some(filter), *node_id, ""),
+                    // Unfiltered remote subscribers get converted updates instead.
+                    SubscriptionTarget::Remote { filter: None, .. } => continue,
+                };
+                let table_schema = match (&view_schema, filter) {
+                    (Some(table_schema), _) => table_schema.clone(),
+                    (None, Some(filter)) => {
+                        match self.filtered_table_schema(module_name, table_name, Some(filter)) {
+                            Some(table_schema) => table_schema,
+                            None => continue,
+                        }
+                    }
+                    (None, None) => continue,
+                };
+                let visible = self.rows_visible_to(
+                    module_name,
+                    &table_schema,
+                    filter,
+                    &rows,
+                    reader_node_id,
+                    reader_module_name,
+                );
+                if visible[..] == transition {
+                    out.push((target, converted.clone()));
+                }
+            }
+        }
+        out
+    }
+
+    /// Schema of the table when its rows must be checked, that is when it has a
+    /// view or the subscriber a filter.
+    fn filtered_table_schema(
+        &self,
+        module_name: &str,
+        table_name: &str,
+        filter: Option<&RowFilter>,
+    ) -> Option<TableSchema> {
+        self.modules.lock().get(module_name).and_then(|module| {
+            module
+                .schema
+                .tables
+                .iter()
+                .find(|table| {
+                    table.name == table_name && (table.view.is_some() || filter.is_some())
+                })
+                .cloned()
+        })
+    }
+
+    /// Whether each row passes `filter` and the table view for the reader. Rows the
+    /// view fails on are hidden.
+    fn rows_visible_to(
+        &self,
+        module_name: &str,
+        table_schema: &TableSchema,
+        filter: Option<&RowFilter>,
+        rows: &[Row],
+        reader_node_id: NodeId,
+        reader_module_name: &str,
+    ) -> Vec<bool> {
+        let in_view = if self.view_applies(module_name, table_schema, reader_node_id, reader_module_name) {
+            self.view_verdicts(module_name, table_schema, rows, reader_node_id, reader_module_name)
+                .unwrap_or_else(|err| {
+                    self.logger.log(
+                        &format!(
+                            "Hiding rows of '{}.{}': {}",
+                            module_name, table_schema.name, err
+                        ),
+                        LogSource::Runtime,
+                        LogLevel::Error,
+                    );
+                    vec![false; rows.len()]
+                })
+        } else {
+            vec![true; rows.len()]
+        };
+        rows.iter()
+            .zip(in_view)
+            .map(|(row, in_view)| {
+                in_view && filter.is_none_or(|filter| filter.matches(table_schema, row))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::node::NodeId;
+    use crate::runtime::event::{EventInstance, SubscriptionTarget};
+    use crate::testing::TestRuntime;
+    use crate::testing::fixture::{FixtureModule, Step, TableEvent, row};
+    use interstice_abi::{
+        CompareOp, IntersticeType, IntersticeValue, NodeSelection, PersistenceKind, RowFilter,
+        SubscriptionEventSchema,
+    };
+
+    #[tokio::test]
+    async fn updates_moving_rows_across_a_view_reach_insert_and_delete_subscribers() {
+        let runtime = TestRuntime::starting_at(1_000).unwrap();
+        let post = |public| row(1, vec![IntersticeValue::Bool(public)]);
+        let blog = FixtureModule::new("blog")
+            .table(
+                "posts",
+                PersistenceKind::Ephemeral,
+                &[("public", IntersticeType::Bool)],
+            )
+            .view("posts", "public_posts")
+            .reducer(
+                "draft",
+                vec![Step::Insert {
+                    table: "posts".into(),
+                    row: post(false),
+                }],
+            )
+            .reducer(
+                "publish",
+                vec![Step::Update {
+                    table: "posts".into(),
+                    row: post(true),
+                }],
+            )
+            .reducer(
+                "retract",
+                vec![Step::Update {
+                    table: "posts".into(),
+                    row: post(false),
+                }],
+            );
+        let seen = |id| Step::Insert {
+            table: "seen".into(),
+            row: row(id, Vec::new()),
+        };
+        let reader = FixtureModule::new("reader")
+            .table("seen", PersistenceKind::Ephemeral, &[])
+            .reducer("on_insert", vec![seen(1)])
+            .reducer("on_update", vec![seen(2)])
+            .reducer("on_delete", vec![seen(3)])
+            .on_table_event("on_insert", TableEvent::Insert, "blog", "posts")
+            .on_table_event("on_update", TableEvent::Update, "blog", "posts")
+            .on_table_event("on_delete", TableEvent::Delete, "blog", "posts");
+        runtime.load_module(&blog.wasm()).await.unwrap();
+        runtime.load_module(&reader.wasm()).await.unwrap();
+        let seen_ids = || {
+            let mut ids = runtime
+                .rows("reader", "seen")
+                .unwrap()
+                .into_iter()
+                .map(|row| row.primary_key)
+                .collect::<Vec<_>>();
+            ids.sort_by_key(|id| id.to_string());
+            ids
+        };
+
+        runtime
+            .call_reducer("blog", "draft", Vec::new())
+            .await
+            .unwrap();
+        assert!(seen_ids().is_empty());
+
+        runtime
+            .call_reducer("blog", "publish", Vec::new())
+            .await
+            .unwrap();
+        assert_eq!(seen_ids(), vec![IntersticeValue::U64(1)]);
+
+        runtime
+            .call_reducer("blog", "retract", Vec::new())
+            .await
+            .unwrap();
+        assert_eq!(
+            seen_ids(),
+            vec![IntersticeValue::U64(1), IntersticeValue::U64(3)]
+        );
+    }
+
+    #[tokio::test]
+    async fn updates_moving_rows_across_a_remote_filter_reach_insert_and_delete_subscribers() {
+        let runtime = TestRuntime::starting_at(1_000).unwrap();
+        let blog = FixtureModule::new("blog").table(
+            "posts",
+            PersistenceKind::Ephemeral,
+            &[("public", IntersticeType::Bool)],
+        );
+        runtime.load_module(&blog.wasm()).await.unwrap();
+        let public = RowFilter::Compare {
+            field: "public".into(),
+            op: CompareOp::Eq,
+            value: IntersticeValue::Bool(true),
+        };
+        let (inserts, updates, deletes) = (
+            NodeId::from_u128(1),
+            NodeId::from_u128(2),
+            NodeId::from_u128(3),
+        );
+        let schema = |event: TableEvent| {
+            let (node_selection, module_name, table_name) = (
+                NodeSelection::Current,
+                "blog".to_string(),
+                "posts".to_string(),
+            );
+            match event {
+                TableEvent::Insert => SubscriptionEventSchema::Insert {
+                    node_selection,
+                    module_name,
+                    table_name,
+                },
+                TableEvent::Update => SubscriptionEventSchema::Update {
+                    node_selection,
+                    module_name,
+                    table_name,
+                },
+                TableEvent::Delete => SubscriptionEventSchema::Delete {
+                    node_selection,
+                    module_name,
+                    table_name,
+                },
+            }
+        };
+        for (node_id, event) in [
+            (inserts, TableEvent::Insert),
+            (updates, TableEvent::Update),
+            (deletes, TableEvent::Delete),
+        ] {
+            runtime
+                .runtime()
+                .add_node_subscription(node_id, schema(event), Some(public.clone()));
+        }
+        let post = |public| row(1, vec![IntersticeValue::Bool(public)]);
+        let deliveries = |old_public, new_public| {
+            let update = EventInstance::TableUpdateEvent {
+                source_node_id: None,
+                filter: None,
+                sequence: 0,
+                module_name: "blog".into(),
+                table_name: "posts".into(),
+                old_row: post(old_public),
+                new_row: post(new_public),
+            };
+            let mut delivered = runtime
+                .runtime()
+                .table_event_deliveries(&update)
+                .into_iter()
+                .filter_map(|(target, event)| match (target, event?) {
+                    (SubscriptionTarget::Remote { node_id, .. }, event) => Some((
+                        node_id,
+                        match event {
+                            EventInstance::TableInsertEvent { .. } => TableEvent::Insert,
+                            EventInstance::TableUpdateEvent { .. } => TableEvent::Update,
+                            EventInstance::TableDeleteEvent { .. } => TableEvent::Delete,
+                            other => panic!("unexpected event {:?}", other),
+                        },
+                    )),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            delivered.sort_by_key(|(node_id, _)| *node_id);
+            delivered
+        };
+
+        assert_eq!(deliveries(false, true), vec![(inserts, TableEvent::Insert)]);
+        assert_eq!(deliveries(true, true), vec![(updates, TableEvent::Update)]);
+        assert_eq!(deliveries(true, false), vec![(deletes, TableEvent::Delete)]);
+        assert!(deliveries(false, false).is_empty());
+    }
+}
@@ -1,9 +1,12 @@
-use super::StoreState;
+use super::{MemoryLimitExceeded, StoreState};
 use crate::error::IntersticeError;
-use interstice_abi::{IntersticeValue, ModuleSchema, decode, encode};
+use interstice_abi::{
+    IntersticeValue, ModuleSchema, decode, encode, get_query_wrapper_name,
+    get_reducer_wrapper_name,
+};
 use serde::Serialize;
-use std::collections::HashMap;
-use wasmtime::{Func, Instance, Memory, Store};
+use std::{collections::HashMap, sync::Arc};
+use wasmtime::{Func, Instance, Memory, Store, Trap};
 
 pub struct WasmInstance {
     pub store: Store<StoreState>,
@@ -21,6 +24,13 @@ pub struct WasmInstance {
 
 impl WasmInstance {
     pub fn new(mut store: Store<StoreState>, instance: Instance) -> Result<Self, IntersticeError> {
+        // Outside of a budgeted reducer/query call the instance runs unmetered
+        // (schema loading, scratch allocation, ...).
+        store
+            .set_fuel(u64::MAX)
+            .map_err(|err| IntersticeError::Internal(format!("Couldn't set fuel: {}", err)))?;
+        store.limiter(|state| state);
+
         let memory = instance
             .get_memory(&mut store, "memory")
             .ok_or(IntersticeError::MissingExport("memory"))?;
@@ -95,6 +105,49 @@ impl WasmInstance {
         Ok(())
     }
 
+    /// Bind the module schema to this instance and apply its memory ceiling. Fails
+    /// if the instance already uses more linear memory than the module allows.
+    pub fn set_schema(&mut self, schema: Arc<ModuleSchema>) -> Result<(), IntersticeError> {
+        if let Some(limit_mb) = schema.limits.max_memory_mb {
+            let limit_bytes = limit_mb as usize * 1024 * 1024;
+            if self.memory.data_size(&self.store) > limit_bytes {
+                return Err(IntersticeError::MemoryLimitExceeded {
+                    module: schema.name.clone(),
+                    limit_mb,
+                });
+            }
+            self.store.data_mut().max_memory_bytes = Some(limit_bytes);
+        }
+        self.store.data_mut().module_schema = schema;
+        Ok(())
+    }
+
+    /// Give the next guest call `fuel` units of fuel (`None` = unmetered).
+    fn refuel(&mut self, fuel: Option<u64>) -> Result<(), IntersticeError> {
+        self.store
+            .set_fuel(fuel.unwrap_or(u64::MAX))
+            .map_err(|err| IntersticeError::Internal(format!("Couldn't set fuel: {}", err)))
+    }
+
+    /// Map a failed guest call, turning fuel and memory exhaustion into typed errors.
+    fn call_error(&self, err: wasmtime::Error, function: &str, fuel: Option<u64>) -> IntersticeError {
+        let schema = &self.store.data().module_schema;
+        if err.downcast_ref::<MemoryLimitExceeded>().is_some() {
+            return IntersticeError::MemoryLimitExceeded {
+                module: schema.name.clone(),
+                limit_mb: schema.limits.max_memory_mb.unwrap_or_default(),
+            };
+        }
+        match (err.downcast_ref::<Trap>(), fuel) {
+            (Some(Trap::OutOfFuel), Some(fuel)) => IntersticeError::FuelExhausted {
+                module: schema.name.clone(),
+                function: function.into(),
+                fuel,
+            },
+            _ => IntersticeError::WasmTrap(err.to_string()),
+        }
+    }
+
     pub fn load_schema(&mut self) -> Result<ModuleSchema, IntersticeError> {
         let func = self
             .instance
@@ -144,9 +197,14 @@ impl WasmInstance {
 
     pub fn call_reducer(
         &mut self,
-        func_name: &str,
+        reducer: &str,
         args: impl Serialize,
+        fuel: Option<u64>,
     ) -> Result<(), IntersticeError> {
+        let func_name = get_reducer_wrapper_name(reducer);
+        let func_name = func_name.as_str();
+        self.refuel(None)?;
+
         let args_bytes = encode(&args).map_err(|err| {
             IntersticeError::Internal(format!("failed to serialize reducer arguments: {}", err))
         })?;
@@ -173,25 +231,31 @@ impl WasmInstance {
             .or_else(|| self.instance.get_func(&mut self.store, func_name))
             .ok_or_else(|| IntersticeError::WasmFuncNotFound(func_name.into()))?;
 
-        let reducer = func
+        let reducer_func = func
             .typed::<(i32, i32), ()>(&self.store)
             .map_err(|_| IntersticeError::BadSignature(func_name.into()))?;
 
-        reducer
-            .call(
-                &mut self.store,
-                (self.scratch_ptr, args_bytes.len() as i32),
-            )
-            .map_err(|e| IntersticeError::WasmTrap(e.to_string()))?;
+        self.refuel(fuel)?;
+        let result = reducer_func.call(
+            &mut self.store,
+            (self.scratch_ptr, args_bytes.len() as i32),
+        );
+        self.refuel(None)?;
+        result.map_err(|e| self.call_error(e, reducer, fuel))?;
 
         Ok(())
     }
 
     pub fn call_query(
         &mut self,
-        func_name: &str,
+        query: &str,
         args: impl Serialize,
+        fuel: Option<u64>,
     ) -> Result<IntersticeValue, IntersticeError> {
+        let func_name = get_query_wrapper_name(query);
+        let func_name = func_name.as_str();
+        self.refuel(None)?;
+
         let args_bytes = encode(&args).map_err(|err| {
             IntersticeError::Internal(format!("failed to serialize query arguments: {}", err))
         })?;
@@ -215,16 +279,17 @@ impl WasmInstance {
             .or_else(|| self.instance.get_func(&mut self.store, func_name))
             .ok_or_else(|| IntersticeError::WasmFuncNotFound(func_name.into()))?;
 
-        let query = func
+        let query_func = func
             .typed::<(i32, i32), i64>(&self.store)
             .map_err(|_| IntersticeError::BadSignature(func_name.into()))?;
 
-        let packed = query
-            .call(
-                &mut self.store,
-                (self.scratch_ptr, args_bytes.len() as i32),
-            )
-            .map_err(|e| IntersticeError::WasmTrap(e.to_string()))?;
+        self.refuel(fuel)?;
+        let result = query_func.call(
+            &mut self.store,
+            (self.scratch_ptr, args_bytes.len() as i32),
+        );
+        self.refuel(None)?;
+        let packed = result.map_err(|e| self.call_error(e, query, fuel))?;
 
         let res_ptr = (packed >> 32) as i32;
         let res_len = (packed & 0xffffffff) as i32;
@@ -252,3 +317,40 @@ impl WasmInstance {
         Ok(out)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::error::IntersticeError;
+    use crate::testing::TestRuntime;
+    use crate::testing::fixture::{FixtureModule, Step, row};
+    use interstice_abi::{IntersticeType, IntersticeValue, PersistenceKind};
+
+    #[tokio::test]
+    async fn reducers_out_of_fuel_fail_without_writing() {
+        let runtime = TestRuntime::starting_at(1_000).unwrap();
+        let insert = |id| Step::Insert {
+            table: "counters".into(),
+            row: row(id, vec![IntersticeValue::U64(id)]),
+        };
+        let module = FixtureModule::new("fuel")
+            .table(
+                "counters",
+                PersistenceKind::Ephemeral,
+                &[("value", IntersticeType::U64)],
+            )
+            .reducer("count", vec![insert(1)])
+            .reducer("spin", vec![insert(2), Step::Spin])
+            .with_reducer(|reducer| reducer.fuel = Some(10_000));
+        runtime.load_module(&module.wasm()).await.unwrap();
+
+        runtime
+            .call_reducer("fuel", "count", Vec::new())
+            .await
+            .unwrap();
+        assert!(matches!(
+            runtime.call_reducer("fuel", "spin", Vec::new()).await,
+            Err(IntersticeError::FuelExhausted { fuel: 10_000, .. })
+        ));
+        assert_eq!(runtime.rows("fuel", "counters").unwrap().len(), 1);
+    }
+}
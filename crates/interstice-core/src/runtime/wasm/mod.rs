@@ -2,7 +2,8 @@ pub mod instance;
 pub mod linker;
 
 use interstice_abi::ModuleSchema;
-use std::sync::Arc;
+use std::{fmt, sync::Arc};
+use wasmtime::ResourceLimiter;
 
 use crate::runtime::Runtime;
 
@@ -15,6 +16,9 @@ pub struct StoreState {
     /// re-running the side-effecting insert, so the transaction is applied
     /// exactly once.  See `linker.rs` `interstice_insert_row`.
     pub pending_insert_response: Option<Vec<u8>>,
+    /// Linear-memory ceiling from `ModuleLimits::max_memory_mb`, in bytes.
+    /// `None` until the schema is known, and for modules without a ceiling.
+    pub max_memory_bytes: Option<usize>,
 }
 
 impl StoreState {
@@ -23,7 +27,56 @@ impl StoreState {
             runtime,
             module_schema: Arc::new(ModuleSchema::empty()),
             pending_insert_response: None,
+            max_memory_bytes: None,
         }
     }
 }
 
+/// Trap payload raised when a guest grows its memory past `max_memory_bytes`.
+#[derive(Debug)]
+pub struct MemoryLimitExceeded {
+    pub limit_bytes: usize,
+    pub desired_bytes: usize,
+}
+
+impl fmt::Display for MemoryLimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "memory growth to {} bytes exceeds the {} bytes limit",
+            self.desired_bytes, self.limit_bytes
+        )
+    }
+}
+
+impl std::error::Error for MemoryLimitExceeded {}
+
+impl ResourceLimiter for StoreState {
+    fn memory_growing(
+        &mut self,
+        _current: usize,
+        desired: usize,
+        _maximum: Option<usize>,
+    ) -> wasmtime::Result<bool> {
+        match self.max_memory_bytes {
+            // Trap instead of failing the grow, so the violation is reported as
+            // a typed error rather than an opaque allocation failure in the guest.
+            Some(limit) if desired > limit => Err(MemoryLimitExceeded {
+                limit_bytes: limit,
+                desired_bytes: desired,
+            }
+            .into()),
+            _ => Ok(true),
+        }
+    }
+
+    fn table_growing(
+        &mut self,
+        _current: usize,
+        _desired: usize,
+        _maximum: Option<usize>,
+    ) -> wasmtime::Result<bool> {
+        Ok(true)
+    }
+}
+
@@ -0,0 +1,438 @@
+//! In-process runtime for testing modules.
+//!
+//! A [`TestRuntime`] runs modules the way a node does, except that their tables
+//! are kept in memory, no network listener is opened, GPU calls fail and audio is
+//! played nowhere. Its clock is virtual: schedules run when it is advanced. Calls
+//! return once the reducer and everything it triggered (subscriptions, cascading
+//! events) has run, so tests can inspect the tables right after.
+
+use crate::{
+    error::IntersticeError,
+    logger::Logger,
+    network::{Network, config::NetworkConfig},
+    node::NodeId,
+    persistence::{NodeIdentity, PeerKeyStore, PeerTokenStore, TableStore},
+    runtime::{
+        Runtime,
+        access::{AccessGuard, AccessRoles},
+        clock::{Clock, system_unix_ms},
+        event::EventInstance,
+        host_calls::{audio::AudioState, file::FileSandbox},
+        module::Module,
+        recorder::TablesSnapshot,
+        reducer::{CompletionToken, JobSource, ReducerJob},
+    },
+};
+use interstice_abi::{IntersticeValue, ModuleSchema, Row, ScheduleInfo};
+use parking_lot::Mutex;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use uuid::Uuid;
+
+#[cfg(test)]
+pub(crate) mod fixture;
+
+/// Change of a table row, as the runtime emitted it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TableChange {
+    Insert {
+        module_name: String,
+        table_name: String,
+        row: Row,
+    },
+    Update {
+        module_name: String,
+        table_name: String,
+        old_row: Row,
+        new_row: Row,
+    },
+    Delete {
+        module_name: String,
+        table_name: String,
+        row: Row,
+    },
+}
+
+impl TableChange {
+    fn from_event(event: &EventInstance) -> Option<Self> {
+        match event {
+            EventInstance::TableInsertEvent {
+                module_name,
+                table_name,
+                inserted_row,
+                ..
+            } => Some(TableChange::Insert {
+                module_name: module_name.clone(),
+                table_name: table_name.clone(),
+                row: inserted_row.clone(),
+            }),
+            EventInstance::TableUpdateEvent {
+                module_name,
+                table_name,
+                old_row,
+                new_row,
+                ..
+            } => Some(TableChange::Update {
+                module_name: module_name.clone(),
+                table_name: table_name.clone(),
+                old_row: old_row.clone(),
+                new_row: new_row.clone(),
+            }),
+            EventInstance::TableDeleteEvent {
+                module_name,
+                table_name,
+                deleted_row,
+                ..
+            } => Some(TableChange::Delete {
+                module_name: module_name.clone(),
+                table_name: table_name.clone(),
+                row: deleted_row.clone(),
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn module_name(&self) -> &str {
+        match self {
+            TableChange::Insert { module_name, .. }
+            | TableChange::Update { module_name, .. }
+            | TableChange::Delete { module_name, .. } => module_name,
+        }
+    }
+
+    pub fn table_name(&self) -> &str {
+        match self {
+            TableChange::Insert { table_name, .. }
+            | TableChange::Update { table_name, .. }
+            | TableChange::Delete { table_name, .. } => table_name,
+        }
+    }
+}
+
+pub struct TestRuntime {
+    runtime: Arc<Runtime>,
+    table_changes: Arc<Mutex<Vec<TableChange>>>,
+    /// Directory backing the File authority, removed with the runtime.
+    file_root: PathBuf,
+}
+
+impl Drop for TestRuntime {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.file_root);
+    }
+}
+
+impl TestRuntime {
+    /// Start a runtime whose virtual clock reads the current time. Must be called
+    /// from within a Tokio runtime.
+    pub fn new() -> Result<Self, IntersticeError> {
+        Self::starting_at(system_unix_ms())
+    }
+
+    /// Start a runtime whose virtual clock reads `unix_ms`. Must be called from
+    /// within a Tokio runtime.
+    pub fn starting_at(unix_ms: u64) -> Result<Self, IntersticeError> {
+        Self::with_node_id(Uuid::new_v4(), unix_ms)
+    }
+
+    /// Start a runtime with the given node id, as a replay does to run the jobs
+    /// of a recorded node with the same seeds.
+    pub(crate) fn with_node_id(id: NodeId, unix_ms: u64) -> Result<Self, IntersticeError> {
+        let logger = Logger::with_sink(std::io::sink());
+        let (event_sender, mut emitted_events) = mpsc::unbounded_channel();
+        let (reducer_sender, reducer_receiver) = crossbeam_channel::unbounded::<ReducerJob>();
+        let access_guard = Arc::new(AccessGuard::default());
+
+        // Never listening, the network only gives the runtime a handle to send
+        // packets through, which no peer receives.
+        let network = Network::new(
+            id,
+            String::new(),
+            String::new(),
+            event_sender.clone(),
+            reducer_sender.clone(),
+            Arc::new(Mutex::new(PeerTokenStore::new_in_memory())),
+            Arc::new(NodeIdentity::generate()?),
+            Arc::new(Mutex::new(PeerKeyStore::new_in_memory(Default::default()))),
+            access_guard.clone(),
+            NetworkConfig::default(),
+            logger.clone(),
+        );
+        // Not named after the node: a replay runs with the id of the recorded node,
+        // possibly next to it.
+        let file_root = std::env::temp_dir()
+            .join("interstice-test")
+            .join(Uuid::new_v4().to_string());
+        let file_sandbox = FileSandbox::new(file_root.clone(), true, Vec::new());
+        let audio_state = Arc::new(Mutex::new(AudioState::new(
+            crate::runtime::host_calls::audio::start_silent_audio_thread(),
+        )));
+
+        let mut runtime = Runtime::new(
+            id,
+            None,
+            TableStore::in_memory(),
+            file_sandbox,
+            AccessRoles::default(),
+            event_sender,
+            network.get_handle(),
+            audio_state,
+            Arc::new(Mutex::new(None)),
+            Arc::new(tokio::sync::Notify::new()),
+            logger,
+            reducer_sender,
+            reducer_receiver,
+        )?;
+        runtime.clock = Clock::virtual_at(unix_ms);
+        let runtime = Arc::new(runtime);
+        access_guard.bind(&runtime);
+
+        let gpu_calls = runtime.take_gpu_call_receiver();
+        std::thread::spawn(move || {
+            while let Ok(request) = gpu_calls.recv() {
+                let _ = request.respond_to.send(Err(IntersticeError::Internal(
+                    "No GPU is available in the test runtime".into(),
+                )));
+            }
+        });
+
+        // Record the table changes on their way to the runtime event loop.
+        let table_changes = Arc::new(Mutex::new(Vec::new()));
+        let (forward, forwarded) = mpsc::unbounded_channel();
+        let recorded = table_changes.clone();
+        tokio::spawn(async move {
+            while let Some((event, completion)) = emitted_events.recv().await {
+                if let Some(change) = TableChange::from_event(&event) {
+                    recorded.lock().push(change);
+                }
+                if forward.send((event, completion)).is_err() {
+                    return;
+                }
+            }
+        });
+        tokio::spawn(Runtime::run(runtime.clone(), forwarded));
+
+        Ok(Self {
+            runtime,
+            table_changes,
+            file_root,
+        })
+    }
+
+    /// Load a module from its wasm binary, once its `init` and `load` reducers ran.
+    pub async fn load_module(&self, wasm_binary: &[u8]) -> Result<ModuleSchema, IntersticeError> {
+        self.load_module_restoring(wasm_binary, None).await
+    }
+
+    /// Load a module, restoring the given rows into its tables as a reload does.
+    pub(crate) async fn load_module_restoring(
+        &self,
+        wasm_binary: &[u8],
+        restored_tables: Option<TablesSnapshot>,
+    ) -> Result<ModuleSchema, IntersticeError> {
+        let module = Module::from_bytes(self.runtime.clone(), wasm_binary).await?;
+        let (completion, completed) = CompletionToken::new();
+        let schema = Runtime::load_module_with_completion(
+            self.runtime.clone(),
+            module,
+            Some(&completion),
+            restored_tables,
+        )
+        .await;
+        drop(completion);
+        let _ = completed.await;
+        schema
+    }
+
+    /// Upgrade a module and restore the given rows into its tables.
+    pub(crate) async fn upgrade_module_restoring(
+        &self,
+        wasm_binary: &[u8],
+        restored_tables: TablesSnapshot,
+    ) -> Result<ModuleSchema, IntersticeError> {
+        let module = Module::from_bytes(self.runtime.clone(), wasm_binary).await?;
+        let (completion, completed) = CompletionToken::new();
+        let schema = Runtime::upgrade_module_with_completion(
+            self.runtime.clone(),
+            module,
+            Some(&completion),
+            Some(restored_tables),
+        )
+        .await;
+        drop(completion);
+        let _ = completed.await;
+        schema
+    }
+
+    /// Call a reducer with its arguments, as the node itself would. Returns once
+    /// the reducer and the reducers its changes triggered have run.
+    pub async fn call_reducer(
+        &self,
+        module_name: &str,
+        reducer_name: &str,
+        args: Vec<IntersticeValue>,
+    ) -> Result<(), IntersticeError> {
+        self.run_job(ReducerJob {
+            module_name: module_name.to_string(),
+            reducer_name: reducer_name.to_string(),
+            input: IntersticeValue::Vec(args),
+            caller_node_id: self.runtime.node_id,
+            caller_module_name: String::new(),
+            request_id: None,
+            completion: None,
+            outcome: None,
+            source: JobSource::Input,
+            rng_seed: None,
+        })
+        .await
+        .map(|_| ())
+    }
+
+    /// Run a reducer job once it and what it triggered have run, and return its
+    /// result.
+    pub(crate) async fn run_job(
+        &self,
+        mut job: ReducerJob,
+    ) -> Result<Option<u64>, IntersticeError> {
+        let (completion, completed) = CompletionToken::new();
+        let (outcome, result) = oneshot::channel();
+        job.completion = Some(completion);
+        job.outcome = Some(outcome);
+        self.runtime
+            .reducer_ingress
+            .send(job)
+            .map_err(|_| IntersticeError::Internal("The test runtime stopped".into()))?;
+        let result = result
+            .await
+            .map_err(|_| IntersticeError::Internal("The reducer call was dropped".into()))?;
+        let _ = completed.await;
+        result
+    }
+
+    /// Call a query with its arguments and return its result.
+    pub async fn call_query(
+        &self,
+        module_name: &str,
+        query_name: &str,
+        args: Vec<IntersticeValue>,
+    ) -> Result<IntersticeValue, IntersticeError> {
+        let runtime = self.runtime.clone();
+        let module_name = module_name.to_string();
+        let query_name = query_name.to_string();
+        tokio::task::spawn_blocking(move || {
+            runtime.call_query(
+                &module_name,
+                &query_name,
+                IntersticeValue::Vec(args),
+                runtime.node_id,
+                "",
+            )
+        })
+        .await
+        .map_err(|err| IntersticeError::Internal(format!("Query call panicked: {err}")))?
+    }
+
+    /// Current rows of a table.
+    pub fn rows(&self, module_name: &str, table_name: &str) -> Result<Vec<Row>, IntersticeError> {
+        let module = self.runtime.modules.lock().get(module_name).cloned().ok_or_else(|| {
+            IntersticeError::ModuleNotFound(
+                module_name.to_string(),
+                "When reading a table in the test runtime".into(),
+            )
+        })?;
+        let tables = module.tables.lock();
+        let table = tables
+            .get(table_name)
+            .ok_or_else(|| IntersticeError::TableNotFound {
+                module_name: module_name.to_string(),
+                table_name: table_name.to_string(),
+            })?;
+        Ok(table.scan().to_vec())
+    }
+
+    /// Pending schedules of a module.
+    pub fn schedules(&self, module_name: &str) -> Vec<ScheduleInfo> {
+        self.runtime.list_schedules(module_name)
+    }
+
+    pub fn now_unix_ms(&self) -> u64 {
+        self.runtime.clock.now_unix_ms()
+    }
+
+    /// Move the clock forward and run the schedules and ticks that came due, in
+    /// the order they came due. Recurring schedules run once per interval or cron
+    /// occurrence crossed, and ticks once per period. Returns once they and what
+    /// they triggered have run.
+    pub async fn advance(&self, duration: Duration) {
+        let target = self.now_unix_ms() + duration.as_millis() as u64;
+        loop {
+            let next_run = [self.runtime.next_schedule_run(), self.runtime.next_virtual_tick()]
+                .into_iter()
+                .flatten()
+                .min()
+                .filter(|next_run| *next_run <= target);
+            let now = self.now_unix_ms();
+            let step = next_run.map_or(target, |next_run| next_run.max(now));
+            self.runtime
+                .clock
+                .advance(Duration::from_millis(step - now));
+
+            let (completion, completed) = CompletionToken::new();
+            self.runtime.fire_due_schedules(Some(&completion));
+            self.runtime.fire_due_virtual_ticks(&completion);
+            drop(completion);
+            let _ = completed.await;
+            if next_run.is_none() {
+                return;
+            }
+        }
+    }
+
+    /// Table changes emitted since the runtime started or the last call to
+    /// [`TestRuntime::take_table_changes`].
+    pub fn table_changes(&self) -> Vec<TableChange> {
+        self.table_changes.lock().clone()
+    }
+
+    pub fn take_table_changes(&self) -> Vec<TableChange> {
+        std::mem::take(&mut *self.table_changes.lock())
+    }
+
+    pub(crate) fn runtime(&self) -> &Arc<Runtime> {
+        &self.runtime
+    }
+
+    pub fn module_schema(&self, module_name: &str) -> Option<ModuleSchema> {
+        self.runtime
+            .modules
+            .lock()
+            .get(module_name)
+            .map(|module| (*module.schema).clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn files_are_removed_with_the_runtime() {
+        let runtime = TestRuntime::starting_at(1_000).unwrap();
+        let file_root = runtime.file_root.clone();
+        std::fs::create_dir_all(file_root.join("module")).unwrap();
+        std::fs::write(file_root.join("module").join("notes.txt"), b"notes").unwrap();
+        drop(runtime);
+        assert!(!file_root.exists());
+    }
+
+    #[tokio::test]
+    async fn clock_moves_only_when_advanced() {
+        let runtime = TestRuntime::starting_at(1_000).unwrap();
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        assert_eq!(runtime.now_unix_ms(), 1_000);
+        runtime.advance(Duration::from_secs(2)).await;
+        assert_eq!(runtime.now_unix_ms(), 3_000);
+    }
+}
@@ -0,0 +1,408 @@
+//! Hand-written modules for the runtime tests.
+//!
+//! A [`FixtureModule`] pairs a module schema with reducers made of a few fixed
+//! steps and table views, and compiles them to a WAT module implementing the module
+//! ABI (`interstice_describe`, `alloc`/`dealloc` and the reducer and query wrappers).
+//! Tests drive the runtime through real reducer calls with it, without building a
+//! module with the SDK. Every reducer may read and write every table of its module.
+
+use interstice_abi::{
+    FieldDef, HostCall, IndexKey, IntersticeType, IntersticeValue, ModuleLimits, ModuleSchema,
+    ModuleSelection, ModuleVisibility, NodeSelection, PersistenceKind, QuerySchema,
+    ReducerSchema, ReducerTableRef, Row, SubscriptionEventSchema, SubscriptionSchema,
+    TableLimits, TableSchema, TableVisibility, Version, encode, get_query_wrapper_name,
+    get_reducer_wrapper_name,
+};
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// What a fixture reducer does, in order.
+#[derive(Debug)]
+pub(crate) enum Step {
+    Insert {
+        table: String,
+        row: Row,
+    },
+    Update {
+        table: String,
+        row: Row,
+    },
+    /// Delete row `id` of a table.
+    Delete {
+        table: String,
+        id: u64,
+    },
+    /// Update row `id` of a table with two `f64` fields to the job's time and a
+    /// random number.
+    UpdateTimeAndRandom {
+        table: String,
+        id: u64,
+    },
+    /// Make a host call, ignoring its response.
+    HostCall(HostCall),
+    /// Loop until the call runs out of fuel.
+    Spin,
+    /// Loop `iterations` times, keeping the call running while others run.
+    Wait {
+        iterations: u32,
+    },
+}
+
+pub(crate) struct FixtureModule {
+    pub schema: ModuleSchema,
+    reducers: Vec<(String, Vec<Step>)>,
+    views: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TableEvent {
+    Insert,
+    Update,
+    Delete,
+}
+
+impl FixtureModule {
+    pub fn new(name: &str) -> Self {
+        Self {
+            schema: ModuleSchema::new(
+                name,
+                Version {
+                    major: 0,
+                    minor: 1,
+                    patch: 0,
+                },
+                ModuleVisibility::Public,
+                Vec::new(),
+                Vec::new(),
+                Vec::new(),
+                Vec::new(),
+                HashMap::new(),
+                Vec::new(),
+                Vec::new(),
+                Vec::new(),
+                Vec::new(),
+                ModuleLimits::default(),
+                Default::default(),
+            ),
+            reducers: Vec::new(),
+            views: Vec::new(),
+        }
+    }
+
+    /// Add a public table keyed by a `u64` `id`.
+    pub fn table(
+        mut self,
+        name: &str,
+        persistence: PersistenceKind,
+        fields: &[(&str, IntersticeType)],
+    ) -> Self {
+        self.schema.tables.push(TableSchema {
+            name: name.to_string(),
+            type_name: table_type_name(name),
+            visibility: TableVisibility::Public,
+            fields: fields
+                .iter()
+                .map(|(name, field_type)| FieldDef {
+                    name: name.to_string(),
+                    field_type: field_type.clone(),
+                    default: None,
+                })
+                .collect(),
+            primary_key: FieldDef {
+                name: "id".into(),
+                field_type: IntersticeType::U64,
+                default: None,
+            },
+            primary_key_auto_inc: false,
+            indexes: Vec::new(),
+            persistence,
+            limits: TableLimits::default(),
+            migrations: Vec::new(),
+            view: None,
+        });
+        self
+    }
+
+    /// Change the schema of the table added last.
+    pub fn with_table(mut self, change: impl FnOnce(&mut TableSchema)) -> Self {
+        change(self.schema.tables.last_mut().expect("no table to change"));
+        self
+    }
+
+    pub fn reducer(mut self, name: &str, steps: Vec<Step>) -> Self {
+        let tables = self
+            .schema
+            .tables
+            .iter()
+            .map(|table| ReducerTableRef {
+                node_selection: NodeSelection::Current,
+                module_selection: ModuleSelection::Current,
+                table_name: table.name.clone(),
+            })
+            .collect::<Vec<_>>();
+        self.schema.reducers.push(ReducerSchema::new(
+            name,
+            Vec::new(),
+            tables.clone(),
+            tables.clone(),
+            tables.clone(),
+            tables,
+        ));
+        self.reducers.push((name.to_string(), steps));
+        self
+    }
+
+    /// Change the schema of the reducer added last.
+    pub fn with_reducer(mut self, change: impl FnOnce(&mut ReducerSchema)) -> Self {
+        change(
+            self.schema
+                .reducers
+                .last_mut()
+                .expect("no reducer to change"),
+        );
+        self
+    }
+
+    /// Add a table view: a query of the table's rows returning whether their last
+    /// field, a `bool`, is `true`.
+    pub fn view(mut self, table_name: &str, query_name: &str) -> Self {
+        let table = self
+            .schema
+            .tables
+            .iter_mut()
+            .find(|table| table.name == table_name)
+            .expect("no table for the view");
+        table.view = Some(query_name.to_string());
+        let row_type = IntersticeType::Named(table.type_name.clone());
+        self.schema.queries.push(QuerySchema::new(
+            query_name,
+            vec![FieldDef {
+                name: "row".into(),
+                field_type: row_type,
+                default: None,
+            }],
+            IntersticeType::Bool,
+        ));
+        self.views.push(query_name.to_string());
+        self
+    }
+
+    /// Subscribe a reducer to an event of a table of the current node.
+    pub fn on_table_event(
+        mut self,
+        reducer_name: &str,
+        event: TableEvent,
+        module_name: &str,
+        table_name: &str,
+    ) -> Self {
+        let (node_selection, module_name, table_name) = (
+            NodeSelection::Current,
+            module_name.to_string(),
+            table_name.to_string(),
+        );
+        let event = match event {
+            TableEvent::Insert => SubscriptionEventSchema::Insert {
+                node_selection,
+                module_name,
+                table_name,
+            },
+            TableEvent::Update => SubscriptionEventSchema::Update {
+                node_selection,
+                module_name,
+                table_name,
+            },
+            TableEvent::Delete => SubscriptionEventSchema::Delete {
+                node_selection,
+                module_name,
+                table_name,
+            },
+        };
+        self.schema.subscriptions.push(SubscriptionSchema {
+            reducer_name: reducer_name.to_string(),
+            event,
+            filter: None,
+        });
+        self
+    }
+
+    /// The module as WAT text, which the runtime loads like a wasm binary.
+    pub fn wasm(&self) -> Vec<u8> {
+        let mut data = Data::default();
+        let schema = data.push(&encode(&self.schema).unwrap());
+        let mut functions = String::new();
+
+        for (name, steps) in &self.reducers {
+            let mut body = String::new();
+            for step in steps {
+                match step {
+                    Step::Insert { table, row } => {
+                        let table_name = data.push(table.as_bytes());
+                        let row = data.push(&encode(row).unwrap());
+                        let _ = writeln!(
+                            body,
+                            "(drop (call $insert_row (i32.const {}) (i32.const {}) (i32.const {}) (i32.const {}) (i32.const {}) (i32.const {})))",
+                            table_name.0, table_name.1, row.0, row.1, RESPONSE, RESPONSE_CAPACITY
+                        );
+                    }
+                    Step::Update { table, row } => {
+                        let table_name = data.push(table.as_bytes());
+                        let row = data.push(&encode(row).unwrap());
+                        let _ = writeln!(
+                            body,
+                            "(drop (call $update_row (i32.const {}) (i32.const {}) (i32.const {}) (i32.const {})))",
+                            table_name.0, table_name.1, row.0, row.1
+                        );
+                    }
+                    Step::Delete { table, id } => {
+                        let table_name = data.push(table.as_bytes());
+                        let key = data.push(&encode(&IndexKey::U64(*id)).unwrap());
+                        let _ = writeln!(
+                            body,
+                            "(drop (call $delete_row (i32.const {}) (i32.const {}) (i32.const {}) (i32.const {})))",
+                            table_name.0, table_name.1, key.0, key.1
+                        );
+                    }
+                    Step::UpdateTimeAndRandom { table, id } => {
+                        let table_name = data.push(table.as_bytes());
+                        let encoded = encode(&row(
+                            *id,
+                            vec![IntersticeValue::F64(0.0), IntersticeValue::F64(0.0)],
+                        ))
+                        .unwrap();
+                        // The row ends with both `f64`s, each 8 bytes after its tag.
+                        let row = data.push(&encoded);
+                        let _ = writeln!(
+                            body,
+                            "(f64.store (i32.const {}) (f64.convert_i64_u (call $time)))\n(f64.store (i32.const {}) (f64.convert_i64_u (call $random)))\n(drop (call $update_row (i32.const {}) (i32.const {}) (i32.const {}) (i32.const {})))",
+                            row.0 + row.1 - 17,
+                            row.0 + row.1 - 8,
+                            table_name.0,
+                            table_name.1,
+                            row.0,
+                            row.1
+                        );
+                    }
+                    Step::HostCall(call) => {
+                        let call = data.push(&encode(call).unwrap());
+                        let _ = writeln!(
+                            body,
+                            "(drop (call $host_call (i32.const {}) (i32.const {})))",
+                            call.0, call.1
+                        );
+                    }
+                    Step::Spin => body.push_str("(loop $spin (br $spin))\n"),
+                    // The arguments are never read, so their pointer counts down.
+                    Step::Wait { iterations } => {
+                        let _ = writeln!(
+                            body,
+                            "(local.set 0 (i32.const {}))\n(loop $wait (local.set 0 (i32.sub (local.get 0) (i32.const 1))) (br_if $wait (local.get 0)))",
+                            iterations
+                        );
+                    }
+                }
+            }
+            let _ = writeln!(
+                functions,
+                "(func (export \"{}\") (param i32 i32)\n{})",
+                get_reducer_wrapper_name(name),
+                body
+            );
+        }
+
+        // A view's arguments end with the row, so with its last field.
+        let visible = data.push(&encode(&IntersticeValue::Bool(true)).unwrap());
+        let hidden = data.push(&encode(&IntersticeValue::Bool(false)).unwrap());
+        for name in &self.views {
+            let _ = writeln!(
+                functions,
+                "(func (export \"{}\") (param i32 i32) (result i64)\n(if (result i64) (i32.eq (i32.load8_u (i32.sub (i32.add (local.get 0) (local.get 1)) (i32.const 1))) (i32.const 1)) (then (i64.const {})) (else (i64.const {}))))",
+                get_query_wrapper_name(name),
+                packed(visible),
+                packed(hidden)
+            );
+        }
+
+        let heap = data.end.next_multiple_of(8);
+        let pages = heap / 65536 + 2;
+        format!(
+            r#"(module
+  (import "interstice" "interstice_host_call" (func $host_call (param i32 i32) (result i64)))
+  (import "interstice" "interstice_time" (func $time (result i64)))
+  (import "interstice" "interstice_random" (func $random (result i64)))
+  (import "interstice" "interstice_insert_row" (func $insert_row (param i32 i32 i32 i32 i32 i32) (result i32)))
+  (import "interstice" "interstice_update_row" (func $update_row (param i32 i32 i32 i32) (result i32)))
+  (import "interstice" "interstice_delete_row" (func $delete_row (param i32 i32 i32 i32) (result i32)))
+  (memory (export "memory") {pages})
+  (global $heap (mut i32) (i32.const {heap}))
+  (func (export "alloc") (param $len i32) (result i32)
+    (local $ptr i32)
+    (local.set $ptr (global.get $heap))
+    (global.set $heap (i32.add (global.get $heap) (local.get $len)))
+    (block $done
+      (loop $grow
+        (br_if $done (i32.le_u (global.get $heap) (i32.shl (memory.size) (i32.const 16))))
+        (drop (memory.grow (i32.const 1)))
+        (br $grow)))
+    (local.get $ptr))
+  (func (export "dealloc") (param i32 i32))
+  (func (export "interstice_describe") (result i64) (i64.const {schema}))
+{data}{functions})"#,
+            pages = pages,
+            heap = heap,
+            schema = packed(schema),
+            data = data.segments,
+            functions = functions,
+        )
+        .into_bytes()
+    }
+}
+
+/// Row type name of a fixture table.
+pub(crate) fn table_type_name(table_name: &str) -> String {
+    format!("{}Row", table_name)
+}
+
+/// Row keyed by `id`.
+pub(crate) fn row(id: u64, entries: Vec<IntersticeValue>) -> Row {
+    Row {
+        primary_key: IntersticeValue::U64(id),
+        entries,
+    }
+}
+
+/// Response buffer of `insert_row`, below the data segments.
+const RESPONSE: usize = 1024;
+const RESPONSE_CAPACITY: usize = 64 * 1024;
+
+/// Data segments of a fixture module, laid out one after the other.
+struct Data {
+    segments: String,
+    end: usize,
+}
+
+impl Default for Data {
+    fn default() -> Self {
+        Self {
+            segments: String::new(),
+            end: RESPONSE + RESPONSE_CAPACITY,
+        }
+    }
+}
+
+impl Data {
+    /// Place `bytes` and return their offset and length.
+    fn push(&mut self, bytes: &[u8]) -> (usize, usize) {
+        let offset = self.end.next_multiple_of(8);
+        let _ = write!(self.segments, "  (data (i32.const {}) \"", offset);
+        for byte in bytes {
+            let _ = write!(self.segments, "\\{:02x}", byte);
+        }
+        self.segments.push_str("\")\n");
+        self.end = offset + bytes.len();
+        (offset, bytes.len())
+    }
+}
+
+fn packed((offset, len): (usize, usize)) -> i64 {
+    ((offset as i64) << 32) | len as i64
+}
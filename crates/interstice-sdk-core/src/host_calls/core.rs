@@ -1,10 +1,18 @@
 use interstice_abi::{
     CallQueryRequest, CallQueryResponse, CallReducerRequest, CallReducerResponse, HostCall,
-    IndexKey, IndexQuery, InsertRowResponse, IntersticeValue, ModuleSelection, NodeSelection, Row,
-    ScheduleRequest, ScheduleResponse, TableGetByPrimaryKeyRequest, TableGetByPrimaryKeyResponse,
-    TableIndexScanRequest, TableIndexScanResponse, TableScanRequest, TableScanResponse, decode,
-    encode,
+    IndexKey, IndexQuery, InsertRowResponse, IntersticeValue, ModuleSelection, NodeSelection,
+    ReducerCallStatus, ReducerCallStatusRequest, ReducerCallStatusResponse, ReplicaStatus,
+    ReplicaStatusRequest, ReplicaStatusResponse, Row, RowFilter, ScanOptions,
+    CheckpointInfo, ScheduleCall, ScheduleInfo, ScheduleRequest, ScheduleResponse,
+    ScheduleTiming,
+    TableGetByPrimaryKeyRequest, TableGetByPrimaryKeyResponse, TableIndexScanRequest,
+    TableIndexScanResponse, TableQueryRequest, TableQueryResponse, TableScanCloseRequest, TableScanCloseResponse, TableScanIndex,
+    TableScanNextRequest, TableScanNextResponse, TableScanOpenRequest, TableScanOpenResponse,
+    TableScanRequest, TableScanResponse, TimeTravelCall, TimeTravelResponse, decode, encode,
 };
+use crate::ReducerCall;
+use std::collections::VecDeque;
+use std::ops::Bound;
 
 // Pre-allocated scratch buffer for serialising rows/keys before a direct host call.
 // WASM is single-threaded so a module-level static is safe to use as a reusable scratch area.
@@ -81,22 +89,146 @@ pub fn call_reducer(
     let pack = host_call(call);
     let response: CallReducerResponse = unpack(pack);
     match response {
-        CallReducerResponse::Ok => Ok(()),
+        CallReducerResponse::Ok { .. } => Ok(()),
         CallReducerResponse::Err(err) => Err(err),
     }
 }
 
-pub fn schedule(reducer_name: String, delay_ms: u64) -> Result<(), String> {
-    let call = HostCall::Schedule(ScheduleRequest {
+/// Send a reducer call to the node dependency `node_name`, returning as soon as
+/// it is sent.
+pub fn call_remote_reducer(
+    node_name: String,
+    module_selection: ModuleSelection,
+    reducer_name: String,
+    input: IntersticeValue,
+) -> Result<ReducerCall, String> {
+    let call = HostCall::CallReducer(CallReducerRequest {
+        node_selection: NodeSelection::Other(node_name),
+        module_selection,
         reducer_name,
-        delay_ms,
+        input,
     });
 
     let pack = host_call(call);
-    let response: ScheduleResponse = unpack(pack);
+    let response: CallReducerResponse = unpack(pack);
+    match response {
+        CallReducerResponse::Ok { call_id: Some(call_id) } => Ok(ReducerCall::new(call_id)),
+        CallReducerResponse::Ok { call_id: None } => {
+            Err("The host did not return an id for the remote reducer call".into())
+        }
+        CallReducerResponse::Err(err) => Err(err),
+    }
+}
+
+/// Current status of a remote reducer call of the calling module.
+pub fn reducer_call_status(call_id: String) -> Result<ReducerCallStatus, String> {
+    let pack = host_call(HostCall::ReducerCallStatus(ReducerCallStatusRequest { call_id }));
+    let response: ReducerCallStatusResponse = unpack(pack);
     match response {
-        ScheduleResponse::Ok => Ok(()),
+        ReducerCallStatusResponse::Ok(status) => Ok(status),
+        ReducerCallStatusResponse::Err(err) => Err(err),
+    }
+}
+
+fn schedule_call(call: ScheduleCall) -> Result<ScheduleResponse, String> {
+    let pack = host_call(HostCall::Schedule(call));
+    match unpack(pack) {
         ScheduleResponse::Err(err) => Err(err),
+        response => Ok(response),
+    }
+}
+
+pub fn schedule(request: ScheduleRequest) -> Result<u64, String> {
+    match schedule_call(ScheduleCall::Create(request))? {
+        ScheduleResponse::Scheduled(id) => Ok(id),
+        other => Err(format!("Unexpected schedule response: {:?}", other)),
+    }
+}
+
+pub fn cancel_schedule(schedule_id: u64) -> Result<bool, String> {
+    match schedule_call(ScheduleCall::Cancel { schedule_id })? {
+        ScheduleResponse::Cancelled(existed) => Ok(existed),
+        other => Err(format!("Unexpected schedule response: {:?}", other)),
+    }
+}
+
+pub fn list_schedules() -> Result<Vec<ScheduleInfo>, String> {
+    match schedule_call(ScheduleCall::List)? {
+        ScheduleResponse::List(schedules) => Ok(schedules),
+        other => Err(format!("Unexpected schedule response: {:?}", other)),
+    }
+}
+
+fn time_travel_call(call: TimeTravelCall) -> Result<TimeTravelResponse, String> {
+    let pack = host_call(HostCall::TimeTravel(call));
+    match unpack(pack) {
+        TimeTravelResponse::Err(err) => Err(err),
+        response => Ok(response),
+    }
+}
+
+fn time_travel_change(call: TimeTravelCall) -> Result<(), String> {
+    match time_travel_call(call)? {
+        TimeTravelResponse::Ok => Ok(()),
+        other => Err(format!("Unexpected time travel response: {:?}", other)),
+    }
+}
+
+pub fn checkpoint(name: String) -> Result<(), String> {
+    time_travel_change(TimeTravelCall::Checkpoint { name })
+}
+
+pub fn rewind(checkpoint: String) -> Result<(), String> {
+    time_travel_change(TimeTravelCall::Rewind { checkpoint })
+}
+
+pub fn delete_checkpoint(name: String) -> Result<(), String> {
+    time_travel_change(TimeTravelCall::DeleteCheckpoint { name })
+}
+
+pub fn fork_branch(branch: String) -> Result<(), String> {
+    time_travel_change(TimeTravelCall::Fork { branch })
+}
+
+pub fn discard_branch() -> Result<(), String> {
+    time_travel_change(TimeTravelCall::Discard)
+}
+
+pub fn promote_branch() -> Result<(), String> {
+    time_travel_change(TimeTravelCall::Promote)
+}
+
+pub fn list_checkpoints() -> Result<Vec<CheckpointInfo>, String> {
+    match time_travel_call(TimeTravelCall::ListCheckpoints)? {
+        TimeTravelResponse::Checkpoints(checkpoints) => Ok(checkpoints),
+        other => Err(format!("Unexpected time travel response: {:?}", other)),
+    }
+}
+
+pub fn branches() -> Result<Vec<String>, String> {
+    match time_travel_call(TimeTravelCall::Branches)? {
+        TimeTravelResponse::Branches(branches) => Ok(branches),
+        other => Err(format!("Unexpected time travel response: {:?}", other)),
+    }
+}
+
+/// Rows of a logged table of the calling module after its log entry `sequence`,
+/// with the branches from `main` the table was on then.
+pub fn table_as_of(table_name: String, sequence: u64) -> Result<(Vec<Row>, Vec<String>), String> {
+    match time_travel_call(TimeTravelCall::TableAsOf {
+        table_name,
+        sequence,
+    })? {
+        TimeTravelResponse::Rows { rows, branches } => Ok((rows, branches)),
+        other => Err(format!("Unexpected time travel response: {:?}", other)),
+    }
+}
+
+/// Sequence number of the last log entry of a logged table of the calling module.
+pub fn table_sequence(table_name: String) -> Result<Option<u64>, String> {
+    match time_travel_call(TimeTravelCall::TableSequence { table_name })? {
+        TimeTravelResponse::Sequence(sequence) => Ok(sequence),
+        other => Err(format!("Unexpected time travel response: {:?}", other)),
     }
 }
 
@@ -321,12 +453,23 @@ pub fn scan_index(
     table_name: String,
     field_name: String,
     query: IndexQuery,
+) -> Result<Vec<Row>, String> {
+    scan_index_with(module_selection, table_name, field_name, query, ScanOptions::default())
+}
+
+pub fn scan_index_with(
+    module_selection: ModuleSelection,
+    table_name: String,
+    field_name: String,
+    query: IndexQuery,
+    options: ScanOptions,
 ) -> Result<Vec<Row>, String> {
     let call = HostCall::TableIndexScan(TableIndexScanRequest {
         module_selection,
         table_name,
         field_name,
         query,
+        options,
     });
 
     let pack = host_call(call);
@@ -337,6 +480,175 @@ pub fn scan_index(
     }
 }
 
+pub fn query_table(
+    module_selection: ModuleSelection,
+    table_name: String,
+    filter: Option<RowFilter>,
+    order_by: Option<String>,
+    options: ScanOptions,
+) -> Result<Vec<Row>, String> {
+    let call = HostCall::TableQuery(TableQueryRequest {
+        module_selection,
+        table_name,
+        filter,
+        order_by,
+        options,
+    });
+
+    let pack = host_call(call);
+    let response: TableQueryResponse = unpack(pack);
+    match response {
+        TableQueryResponse::Ok { rows } => Ok(rows),
+        TableQueryResponse::Err(err) => Err(err),
+    }
+}
+
+/// Replication status of one of the calling module's `replicated_tables`.
+pub fn replica_status(
+    node_name: String,
+    module_name: String,
+    table_name: String,
+) -> Result<ReplicaStatus, String> {
+    let call = HostCall::ReplicaStatus(ReplicaStatusRequest {
+        node_name,
+        module_name,
+        table_name,
+    });
+
+    let pack = host_call(call);
+    let response: ReplicaStatusResponse = unpack(pack);
+    match response {
+        ReplicaStatusResponse::Ok(status) => Ok(status),
+        ReplicaStatusResponse::Err(err) => Err(err),
+    }
+}
+
+/// Btree index query matching the keys within `start` and `end`.
+pub fn index_query_from_bounds(start: Bound<IndexKey>, end: Bound<IndexKey>) -> IndexQuery {
+    let key = |bound| match bound {
+        Bound::Included(key) => Some((key, true)),
+        Bound::Excluded(key) => Some((key, false)),
+        Bound::Unbounded => None,
+    };
+    match (key(start), key(end)) {
+        (None, None) => IndexQuery::All,
+        (None, Some((max, true))) => IndexQuery::Lte(max),
+        (None, Some((max, false))) => IndexQuery::Lt(max),
+        (Some((min, true)), None) => IndexQuery::Gte(min),
+        (Some((min, false)), None) => IndexQuery::Gt(min),
+        (Some((min, include_min)), Some((max, include_max))) => IndexQuery::Range {
+            min,
+            max,
+            include_min,
+            include_max,
+        },
+    }
+}
+
+/// Rows read per `TableScanNext` call by [`scan_iter`].
+pub const DEFAULT_SCAN_BATCH_SIZE: u32 = 64;
+
+pub fn scan_open(
+    module_selection: ModuleSelection,
+    table_name: String,
+    index: Option<TableScanIndex>,
+    options: ScanOptions,
+    batch_size: u32,
+) -> Result<u64, String> {
+    let call = HostCall::TableScanOpen(TableScanOpenRequest {
+        module_selection,
+        table_name,
+        index,
+        options,
+        batch_size,
+    });
+
+    let pack = host_call(call);
+    let response: TableScanOpenResponse = unpack(pack);
+    match response {
+        TableScanOpenResponse::Ok { cursor_id } => Ok(cursor_id),
+        TableScanOpenResponse::Err(err) => Err(err),
+    }
+}
+
+/// Read the next batch of a cursor. Returns the rows and whether the scan is done.
+pub fn scan_next(cursor_id: u64) -> Result<(Vec<Row>, bool), String> {
+    let pack = host_call(HostCall::TableScanNext(TableScanNextRequest { cursor_id }));
+    let response: TableScanNextResponse = unpack(pack);
+    match response {
+        TableScanNextResponse::Ok { rows, done } => Ok((rows, done)),
+        TableScanNextResponse::Err(err) => Err(err),
+    }
+}
+
+pub fn scan_close(cursor_id: u64) -> Result<(), String> {
+    let pack = host_call(HostCall::TableScanClose(TableScanCloseRequest { cursor_id }));
+    let response: TableScanCloseResponse = unpack(pack);
+    match response {
+        TableScanCloseResponse::Ok => Ok(()),
+        TableScanCloseResponse::Err(err) => Err(err),
+    }
+}
+
+/// Open a cursor over a table or index scan and iterate its rows, fetching them
+/// from the host a batch at a time.
+pub fn scan_iter(
+    module_selection: ModuleSelection,
+    table_name: String,
+    index: Option<TableScanIndex>,
+    options: ScanOptions,
+) -> Result<TableScanIter, String> {
+    let cursor_id = scan_open(
+        module_selection,
+        table_name,
+        index,
+        options,
+        DEFAULT_SCAN_BATCH_SIZE,
+    )?;
+    Ok(TableScanIter {
+        cursor_id,
+        buffer: VecDeque::new(),
+        done: false,
+    })
+}
+
+/// Rows of an open scan cursor. A failed read (the cursor was invalidated, the
+/// module upgraded meanwhile...) is yielded as an error and ends the iteration.
+/// Dropping the iterator before the end closes the cursor.
+pub struct TableScanIter {
+    cursor_id: u64,
+    buffer: VecDeque<Row>,
+    done: bool,
+}
+
+impl Iterator for TableScanIter {
+    type Item = Result<Row, String>;
+
+    fn next(&mut self) -> Option<Result<Row, String>> {
+        while self.buffer.is_empty() && !self.done {
+            match scan_next(self.cursor_id) {
+                Ok((rows, done)) => {
+                    self.buffer.extend(rows);
+                    self.done = done;
+                }
+                Err(err) => {
+                    self.done = true;
+                    return Some(Err(err));
+                }
+            }
+        }
+        self.buffer.pop_front().map(Ok)
+    }
+}
+
+impl Drop for TableScanIter {
+    fn drop(&mut self) {
+        if !self.done {
+            let _ = scan_close(self.cursor_id);
+        }
+    }
+}
+
 use crate::{QueryContext, ReducerContext};
 
 use crate::host_calls::{host_call, unpack};
@@ -358,7 +670,44 @@ pub trait HostDeterministicRandom {
 }
 
 pub trait HostSchedule {
-    fn schedule(&self, reducer_name: &str, delay_ms: u64) -> Result<(), String>;
+    /// Run a reducer of this module that takes no arguments once, `delay_ms` from now.
+    fn schedule(&self, reducer_name: &str, delay_ms: u64) -> Result<u64, String>;
+    /// Run a reducer of this module with `args` once, at an interval or on a cron
+    /// expression. Returns the schedule id.
+    fn schedule_with(
+        &self,
+        reducer_name: &str,
+        args: Vec<IntersticeValue>,
+        timing: ScheduleTiming,
+    ) -> Result<u64, String>;
+    /// Cancel a pending schedule of this module. Returns whether it existed.
+    fn cancel_schedule(&self, schedule_id: u64) -> Result<bool, String>;
+    fn list_schedules(&self) -> Result<Vec<ScheduleInfo>, String>;
+}
+
+/// Checkpoints and branches of the module's tables. Changes take effect when the
+/// reducer commits, in order with its table writes.
+pub trait HostTimeTravel {
+    /// Save the rows of the module's tables under `name`, replacing a checkpoint
+    /// of the same name.
+    fn checkpoint(&self, name: &str) -> Result<(), String>;
+    /// Put back the rows saved by a checkpoint, which is kept.
+    fn rewind(&self, checkpoint: &str) -> Result<(), String>;
+    fn delete_checkpoint(&self, name: &str) -> Result<(), String>;
+    /// Start a branch from the current rows, which becomes the current branch.
+    fn fork_branch(&self, branch: &str) -> Result<(), String>;
+    /// Drop the current branch and its checkpoints, putting back the rows it was
+    /// forked from.
+    fn discard_branch(&self) -> Result<(), String>;
+    /// Keep the changes of the current branch in its parent.
+    fn promote_branch(&self) -> Result<(), String>;
+}
+
+/// Past state of the module's tables, readable from reducers and queries.
+pub trait HostTableHistory {
+    fn list_checkpoints(&self) -> Result<Vec<CheckpointInfo>, String>;
+    /// Branches from `main` to the current one.
+    fn branches(&self) -> Result<Vec<String>, String>;
 }
 
 impl<Caps> HostLog for ReducerContext<Caps> {
@@ -410,7 +759,74 @@ impl<Caps> HostDeterministicRandom for QueryContext<Caps> {
 }
 
 impl<Caps> HostSchedule for ReducerContext<Caps> {
-    fn schedule(&self, reducer_name: &str, delay_ms: u64) -> Result<(), String> {
-        schedule(reducer_name.to_string(), delay_ms)
+    fn schedule(&self, reducer_name: &str, delay_ms: u64) -> Result<u64, String> {
+        self.schedule_with(reducer_name, Vec::new(), ScheduleTiming::Once { delay_ms })
+    }
+
+    fn schedule_with(
+        &self,
+        reducer_name: &str,
+        args: Vec<IntersticeValue>,
+        timing: ScheduleTiming,
+    ) -> Result<u64, String> {
+        schedule(ScheduleRequest {
+            reducer_name: reducer_name.to_string(),
+            input: IntersticeValue::Vec(args),
+            timing,
+        })
+    }
+
+    fn cancel_schedule(&self, schedule_id: u64) -> Result<bool, String> {
+        cancel_schedule(schedule_id)
+    }
+
+    fn list_schedules(&self) -> Result<Vec<ScheduleInfo>, String> {
+        list_schedules()
+    }
+}
+
+impl<Caps> HostTimeTravel for ReducerContext<Caps> {
+    fn checkpoint(&self, name: &str) -> Result<(), String> {
+        checkpoint(name.to_string())
+    }
+
+    fn rewind(&self, checkpoint: &str) -> Result<(), String> {
+        rewind(checkpoint.to_string())
+    }
+
+    fn delete_checkpoint(&self, name: &str) -> Result<(), String> {
+        delete_checkpoint(name.to_string())
+    }
+
+    fn fork_branch(&self, branch: &str) -> Result<(), String> {
+        fork_branch(branch.to_string())
+    }
+
+    fn discard_branch(&self) -> Result<(), String> {
+        discard_branch()
+    }
+
+    fn promote_branch(&self) -> Result<(), String> {
+        promote_branch()
+    }
+}
+
+impl<Caps> HostTableHistory for ReducerContext<Caps> {
+    fn list_checkpoints(&self) -> Result<Vec<CheckpointInfo>, String> {
+        list_checkpoints()
+    }
+
+    fn branches(&self) -> Result<Vec<String>, String> {
+        branches()
+    }
+}
+
+impl<Caps> HostTableHistory for QueryContext<Caps> {
+    fn list_checkpoints(&self) -> Result<Vec<CheckpointInfo>, String> {
+        list_checkpoints()
+    }
+
+    fn branches(&self) -> Result<Vec<String>, String> {
+        branches()
     }
 }
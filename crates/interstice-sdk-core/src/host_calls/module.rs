@@ -13,6 +13,19 @@ pub fn load(node_selection: NodeSelection, wasm_binary: Vec<u8>) -> Result<(), S
     }
 }
 
+/// Replace a loaded module with a new version of it without unloading it.
+pub fn upgrade(node_selection: NodeSelection, wasm_binary: Vec<u8>) -> Result<(), String> {
+    let pack = host_call(HostCall::Module(ModuleCall::Upgrade {
+        node_selection,
+        wasm_binary,
+    }));
+    let response: ModuleCallResponse = unpack(pack);
+    match response {
+        ModuleCallResponse::Ok => Ok(()),
+        ModuleCallResponse::Err(err) => Err(err),
+    }
+}
+
 /// Unload a module while keeping its persisted table data intact.
 pub fn unload(node_selection: NodeSelection, module_name: String) -> Result<(), String> {
     let pack = host_call(HostCall::Module(ModuleCall::Unload {
@@ -50,6 +63,14 @@ impl ModuleAuthority {
         load(node_selection, wasm_binary)
     }
 
+    pub fn upgrade(
+        &self,
+        node_selection: NodeSelection,
+        wasm_binary: Vec<u8>,
+    ) -> Result<(), String> {
+        upgrade(node_selection, wasm_binary)
+    }
+
     pub fn unload(&self, node_selection: NodeSelection, module_name: String) -> Result<(), String> {
         unload(node_selection, module_name)
     }
@@ -2,10 +2,14 @@ pub mod caps;
 pub mod context;
 pub mod host_calls;
 pub mod module_toml;
+pub mod reducer_call;
 pub mod registry;
+pub mod table_query;
 pub mod table_row;
 
 pub use caps::*;
 pub use context::*;
 pub use host_calls::*;
+pub use reducer_call::ReducerCall;
+pub use table_query::{Column, Filter, TableQuery};
 pub use table_row::TableRow;
@@ -4,7 +4,7 @@
 
 use interstice_abi::{
     FieldDef, ModuleSchema, ModuleSelection, NodeSchema, NodeSelection, ReducerSchema,
-    ReducerTableRef,
+    ReducerTableRef, VersionReq,
 };
 use serde::Deserialize;
 use serde::de::Error as SerdeDeError;
@@ -63,6 +63,10 @@ struct ReducerTomlWire {
     updates: Vec<ReducerAccessWire>,
     #[serde(default)]
     deletes: Vec<ReducerAccessWire>,
+    #[serde(default)]
+    fuel: Option<u64>,
+    #[serde(default)]
+    atomic: bool,
 }
 
 fn wire_vec_to_refs(v: Vec<ReducerAccessWire>) -> Result<Vec<ReducerTableRef>, toml::de::Error> {
@@ -84,6 +88,9 @@ fn parse_reducer_toml_value(v: toml::Value) -> Result<ReducerSchema, toml::de::E
         inserts: wire_vec_to_refs(w.inserts)?,
         updates: wire_vec_to_refs(w.updates)?,
         deletes: wire_vec_to_refs(w.deletes)?,
+        fuel: w.fuel,
+        atomic: w.atomic,
+        access: None,
     })
 }
 
@@ -129,6 +136,24 @@ pub fn module_schema_from_toml_value(v: toml::Value) -> Result<ModuleSchema, tom
     Ok(schema)
 }
 
+/// Optional `version_requirement = "~0.5.1"` key of a module binding document. Without it
+/// the binding accepts versions semver-compatible with the bound `version` (`^major.minor.patch`).
+pub fn module_version_requirement_from_toml_str(
+    s: &str,
+) -> Result<Option<VersionReq>, toml::de::Error> {
+    let v: toml::Value = toml::from_str(s)?;
+    match v.get("version_requirement") {
+        None => Ok(None),
+        Some(toml::Value::String(req)) => req
+            .parse()
+            .map(Some)
+            .map_err(<toml::de::Error as SerdeDeError>::custom),
+        Some(_) => Err(<toml::de::Error as SerdeDeError>::custom(
+            "`version_requirement` must be a string",
+        )),
+    }
+}
+
 /// Parse a node binding document (`name` + `address` + `[[modules]]`).
 pub fn node_schema_from_toml_str(s: &str) -> Result<NodeSchema, toml::de::Error> {
     let v: toml::Value = toml::from_str(s)?;
@@ -0,0 +1,43 @@
+//! Reducer calls sent to other nodes.
+//!
+//! Bindings of a node dependency return a [`ReducerCall`] instead of waiting for
+//! the remote reducer to run. A reducer never blocks on the outcome: store the
+//! call id and poll it from a later reducer, e.g. a scheduled one:
+//!
+//! ```ignore
+//! let call = ctx.hello_node().hello().reducers.hello("Client".into())?;
+//! ctx.current.tables.hello_calls().insert(HelloCall { call_id: call.id().into() })?;
+//! ctx.schedule("check_hello_calls", 500)?;
+//!
+//! // later, in `check_hello_calls`
+//! match ReducerCall::new(row.call_id).status()? {
+//!     ReducerCallStatus::Pending => { /* not answered yet, check again later */ }
+//!     ReducerCallStatus::Committed { commit_sequence } => { /* ... */ }
+//!     status => ctx.log(&format!("hello did not commit: {:?}", status)),
+//! }
+//! ```
+
+use crate::host_calls::reducer_call_status;
+use interstice_abi::ReducerCallStatus;
+
+/// A reducer call sent to another node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReducerCall {
+    call_id: String,
+}
+
+impl ReducerCall {
+    /// Follow a call of this module from its id, e.g. one stored in a table.
+    pub fn new(call_id: String) -> Self {
+        Self { call_id }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.call_id
+    }
+
+    /// Current status of the call, `Pending` until the called node answers.
+    pub fn status(&self) -> Result<ReducerCallStatus, String> {
+        reducer_call_status(self.call_id.clone())
+    }
+}
@@ -23,7 +23,7 @@ pub type TableSchemaFn = fn() -> TableSchema;
 pub type ReducerSchemaFn = fn() -> ReducerSchema;
 pub type QuerySchemaFn = fn() -> QuerySchema;
 pub type SubscriptionSchemaFn = fn() -> SubscriptionSchema;
-pub type IntersticeTypeDefFn = fn() -> IntersticeTypeDef;
+pub type IntersticeTypeDefinitionsFn = fn(&mut HashMap<String, IntersticeTypeDef>);
 pub type ReplicatedTableSchemaFn = fn() -> Vec<ReplicatedTableSchema>;
 
 lazy_static::lazy_static! {
@@ -31,7 +31,7 @@ lazy_static::lazy_static! {
     pub static ref REDUCER_REGISTRY: Arc<Mutex<Vec<ReducerSchemaFn>>> = Arc::new(Mutex::new(Vec::new()));
     pub static ref QUERY_REGISTRY: Arc<Mutex<Vec<QuerySchemaFn>>> = Arc::new(Mutex::new(Vec::new()));
     pub static ref SUBSCRIPTION_REGISTRY: Arc<Mutex<Vec<SubscriptionSchemaFn>>> = Arc::new(Mutex::new(Vec::new()));
-    pub static ref INTERSTICE_TYPE_DEFINITION_REGISTRY: Arc<Mutex<Vec<IntersticeTypeDefFn>>> = Arc::new(Mutex::new(Vec::new()));
+    pub static ref INTERSTICE_TYPE_DEFINITION_REGISTRY: Arc<Mutex<Vec<IntersticeTypeDefinitionsFn>>> = Arc::new(Mutex::new(Vec::new()));
     pub static ref REPLICATED_TABLE_REGISTRY: Arc<Mutex<Vec<ReplicatedTableSchemaFn>>> = Arc::new(Mutex::new(Vec::new()));
 }
 
@@ -55,8 +55,9 @@ pub fn register_subscription(s: SubscriptionSchemaFn) {
     SUBSCRIPTION_REGISTRY.lock().unwrap().push(s);
 }
 
-/// Called by each `#[derive(IntersticeType)]` macro to register its TypeDef function
-pub fn register_type_def(s: IntersticeTypeDefFn) {
+/// Called by each `#[interstice_type]` macro, and for the generic type instances
+/// reducers and queries use, to register the type definitions they need
+pub fn register_type_definitions(s: IntersticeTypeDefinitionsFn) {
     INTERSTICE_TYPE_DEFINITION_REGISTRY.lock().unwrap().push(s);
 }
 
@@ -92,11 +93,10 @@ pub fn collect_subscriptions() -> Vec<SubscriptionSchema> {
 
 pub fn collect_type_definitions() -> HashMap<String, IntersticeTypeDef> {
     let mut result = HashMap::new();
-    for interstice_type_def_fn in INTERSTICE_TYPE_DEFINITION_REGISTRY.lock().unwrap().iter() {
-        let interstice_type_def = interstice_type_def_fn();
-        result.insert(interstice_type_def.get_name().clone(), interstice_type_def);
+    for type_definitions_fn in INTERSTICE_TYPE_DEFINITION_REGISTRY.lock().unwrap().iter() {
+        type_definitions_fn(&mut result);
     }
-    return result;
+    result
 }
 
 pub fn collect_replicated_tables() -> Vec<ReplicatedTableSchema> {
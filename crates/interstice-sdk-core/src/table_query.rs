@@ -0,0 +1,249 @@
+//! Typed filters for `TableQuery`, evaluated by the host.
+//!
+//! `#[table]` generates a `<Row>Columns` struct holding a [`Column`] per field, which
+//! table handles pass to the closures of `filter` and `order_by`:
+//!
+//! ```ignore
+//! let top = ctx.current.tables.player()
+//!     .filter(|p| p.score.gt(10) & p.team.is_in([1, 2]))
+//!     .order_by_desc(|p| p.score)
+//!     .limit(10)
+//!     .fetch();
+//! ```
+
+use crate::host_calls::query_table;
+use interstice_abi::{CompareOp, IntersticeValue, ModuleSelection, Row, RowFilter, ScanOptions};
+use std::marker::PhantomData;
+use std::ops::{BitAnd, BitOr, Not};
+
+/// A field of a table row, used to build filters on it.
+pub struct Column<T> {
+    name: &'static str,
+    _type: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for Column<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Column<T> {}
+
+impl<T> Column<T> {
+    pub const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            _type: PhantomData,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+impl<T: Into<IntersticeValue>> Column<T> {
+    fn compare(&self, op: CompareOp, value: T) -> Filter {
+        Filter(RowFilter::Compare {
+            field: self.name.to_string(),
+            op,
+            value: value.into(),
+        })
+    }
+
+    pub fn eq(&self, value: T) -> Filter {
+        self.compare(CompareOp::Eq, value)
+    }
+
+    pub fn ne(&self, value: T) -> Filter {
+        self.compare(CompareOp::Ne, value)
+    }
+
+    pub fn lt(&self, value: T) -> Filter {
+        self.compare(CompareOp::Lt, value)
+    }
+
+    pub fn lte(&self, value: T) -> Filter {
+        self.compare(CompareOp::Lte, value)
+    }
+
+    pub fn gt(&self, value: T) -> Filter {
+        self.compare(CompareOp::Gt, value)
+    }
+
+    pub fn gte(&self, value: T) -> Filter {
+        self.compare(CompareOp::Gte, value)
+    }
+
+    /// Values from `min` to `max`, both included.
+    pub fn between(&self, min: T, max: T) -> Filter {
+        self.gte(min).and(self.lte(max))
+    }
+
+    pub fn is_in(&self, values: impl IntoIterator<Item = T>) -> Filter {
+        Filter(RowFilter::In {
+            field: self.name.to_string(),
+            values: values.into_iter().map(Into::into).collect(),
+        })
+    }
+}
+
+impl Column<String> {
+    pub fn starts_with(&self, prefix: impl Into<String>) -> Filter {
+        Filter(RowFilter::StartsWith {
+            field: self.name.to_string(),
+            prefix: prefix.into(),
+        })
+    }
+}
+
+/// A row predicate. Combine with `&`, `|` and `!`, or `and`, `or` and `not`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Filter(pub RowFilter);
+
+impl Filter {
+    pub fn and(self, other: Filter) -> Filter {
+        match self.0 {
+            RowFilter::And(mut filters) => {
+                filters.push(other.0);
+                Filter(RowFilter::And(filters))
+            }
+            filter => Filter(RowFilter::And(vec![filter, other.0])),
+        }
+    }
+
+    pub fn or(self, other: Filter) -> Filter {
+        match self.0 {
+            RowFilter::Or(mut filters) => {
+                filters.push(other.0);
+                Filter(RowFilter::Or(filters))
+            }
+            filter => Filter(RowFilter::Or(vec![filter, other.0])),
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn not(self) -> Filter {
+        Filter(RowFilter::Not(Box::new(self.0)))
+    }
+}
+
+impl BitAnd for Filter {
+    type Output = Filter;
+
+    fn bitand(self, other: Filter) -> Filter {
+        self.and(other)
+    }
+}
+
+impl BitOr for Filter {
+    type Output = Filter;
+
+    fn bitor(self, other: Filter) -> Filter {
+        self.or(other)
+    }
+}
+
+impl Not for Filter {
+    type Output = Filter;
+
+    fn not(self) -> Filter {
+        Filter::not(self)
+    }
+}
+
+/// A query on a table, run by the host when fetched. `C` is the table's columns
+/// struct.
+pub struct TableQuery<T, C> {
+    module_selection: ModuleSelection,
+    table_name: &'static str,
+    columns: C,
+    filter: Option<Filter>,
+    order_by: Option<&'static str>,
+    options: ScanOptions,
+    _row: PhantomData<fn() -> T>,
+}
+
+impl<T, C> TableQuery<T, C> {
+    pub fn new(module_selection: ModuleSelection, table_name: &'static str, columns: C) -> Self {
+        Self {
+            module_selection,
+            table_name,
+            columns,
+            filter: None,
+            order_by: None,
+            options: ScanOptions::default(),
+            _row: PhantomData,
+        }
+    }
+
+    /// Keep the rows matching `predicate`, in addition to the previous filters.
+    pub fn filter(mut self, predicate: impl FnOnce(&C) -> Filter) -> Self {
+        let filter = predicate(&self.columns);
+        self.filter = Some(match self.filter.take() {
+            Some(previous) => previous.and(filter),
+            None => filter,
+        });
+        self
+    }
+
+    pub fn order_by<V>(mut self, column: impl FnOnce(&C) -> Column<V>) -> Self {
+        self.order_by = Some(column(&self.columns).name());
+        self.options.reverse = false;
+        self
+    }
+
+    pub fn order_by_desc<V>(mut self, column: impl FnOnce(&C) -> Column<V>) -> Self {
+        self.order_by = Some(column(&self.columns).name());
+        self.options.reverse = true;
+        self
+    }
+
+    pub fn offset(mut self, offset: u64) -> Self {
+        self.options.offset = offset;
+        self
+    }
+
+    pub fn limit(mut self, limit: u64) -> Self {
+        self.options.limit = Some(limit);
+        self
+    }
+}
+
+impl<T, C> TableQuery<T, C>
+where
+    T: TryFrom<Row>,
+    T::Error: std::fmt::Debug,
+{
+    pub fn fetch(self) -> Vec<T> {
+        query_table(
+            self.module_selection,
+            self.table_name.to_string(),
+            self.filter.map(|filter| filter.0),
+            self.order_by.map(str::to_string),
+            self.options,
+        )
+        .expect("Table query failed")
+        .into_iter()
+        .map(|row| row.try_into().expect("Failed to convert Row"))
+        .collect()
+    }
+
+    pub fn first(self) -> Option<T> {
+        self.limit(1).fetch().into_iter().next()
+    }
+}
+
+impl<T, C> IntoIterator for TableQuery<T, C>
+where
+    T: TryFrom<Row>,
+    T::Error: std::fmt::Debug,
+{
+    type Item = T;
+    type IntoIter = std::vec::IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.fetch().into_iter()
+    }
+}
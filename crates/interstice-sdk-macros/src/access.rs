@@ -0,0 +1,82 @@
+use quote::quote;
+use syn::Meta;
+
+/// Whether `meta` is one of the access options shared by `#[reducer]` and `#[query]`.
+pub fn is_access_attr(meta: &Meta) -> bool {
+    matches!(meta, Meta::NameValue(nv)
+        if nv.path.is_ident("allow_nodes")
+            || nv.path.is_ident("allow_roles")
+            || nv.path.is_ident("access_policy"))
+}
+
+/// Parse `allow_nodes = ["…"]`, `allow_roles = ["…"]` and `access_policy = "…"` into
+/// an `Option<AccessPolicy>` overriding the module default (`None` when absent).
+pub fn parse_access_policy(
+    attributes: &syn::punctuated::Punctuated<Meta, syn::Token![,]>,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let mut declared = false;
+    let mut nodes = Vec::new();
+    let mut roles = Vec::new();
+    let mut policy = quote! { None };
+
+    for meta in attributes {
+        let Meta::NameValue(nv) = meta else {
+            continue;
+        };
+        if nv.path.is_ident("allow_nodes") {
+            nodes = string_list(&nv.value, "allow_nodes")?;
+        } else if nv.path.is_ident("allow_roles") {
+            roles = string_list(&nv.value, "allow_roles")?;
+        } else if nv.path.is_ident("access_policy") {
+            let syn::Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Str(lit),
+                ..
+            }) = &nv.value
+            else {
+                return Err(syn::Error::new_spanned(
+                    &nv.value,
+                    "`access_policy` expects the name of a query, e.g. `access_policy = \"can_call\"`",
+                ));
+            };
+            let name = lit.value();
+            policy = quote! { Some(#name.to_string()) };
+        } else {
+            continue;
+        }
+        declared = true;
+    }
+
+    if !declared {
+        return Ok(quote! { None });
+    }
+    Ok(quote! {
+        Some(interstice_sdk::AccessPolicy {
+            nodes: vec![#(#nodes.to_string()),*],
+            roles: vec![#(#roles.to_string()),*],
+            policy: #policy,
+        })
+    })
+}
+
+fn string_list(value: &syn::Expr, option: &str) -> syn::Result<Vec<String>> {
+    let error = || {
+        syn::Error::new_spanned(
+            value,
+            format!("`{option}` expects a list of string literals, e.g. `{option} = [\"…\"]`"),
+        )
+    };
+    let syn::Expr::Array(array) = value else {
+        return Err(error());
+    };
+    array
+        .elems
+        .iter()
+        .map(|elem| match elem {
+            syn::Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Str(lit),
+                ..
+            }) => Ok(lit.value()),
+            _ => Err(error()),
+        })
+        .collect()
+}
@@ -11,11 +11,13 @@ pub fn validate_index_key_type(ty: &Type) -> Result<(), String> {
                 .ok_or_else(|| "Unsupported index key type".to_string())?;
             let ident = last_segment.ident.to_string();
             match ident.as_str() {
-                "u8" | "u32" | "u64" | "i32" | "i64" | "bool" | "String" => Ok(()),
+                "u8" | "u16" | "u32" | "u64" | "u128" | "i8" | "i16" | "i32" | "i64" | "i128"
+                | "bool" | "char" | "String" => Ok(()),
                 "f32" | "f64" => Err(
-                    "Float types are not supported as index keys. Use an integer, bool, String, Option, or tuple."
+                    "Float types are not supported as index keys. Use an integer, bool, char, String, Vec<u8>, Option, or tuple."
                         .to_string(),
                 ),
+                "Vec" if is_byte_vec(last_segment) => Ok(()),
                 "Option" => {
                     if let syn::PathArguments::AngleBracketed(args) = &last_segment.arguments {
                         let mut inner_ty = None;
@@ -41,7 +43,7 @@ pub fn validate_index_key_type(ty: &Type) -> Result<(), String> {
                     }
                 }
                 _ => Err(format!(
-                    "Type `{}` is not supported as an index key. Use an integer, bool, String, Option, or tuple.",
+                    "Type `{}` is not supported as an index key. Use an integer, bool, char, String, Vec<u8>, Option, or tuple.",
                     ty.to_token_stream()
                 )),
             }
@@ -53,8 +55,19 @@ pub fn validate_index_key_type(ty: &Type) -> Result<(), String> {
             Ok(())
         }
         _ => Err(format!(
-            "Type `{}` is not supported as an index key. Use an integer, bool, String, Option, or tuple.",
+            "Type `{}` is not supported as an index key. Use an integer, bool, char, String, Vec<u8>, Option, or tuple.",
             ty.to_token_stream()
         )),
     }
 }
+
+/// Whether `segment` is `Vec<u8>`.
+fn is_byte_vec(segment: &syn::PathSegment) -> bool {
+    let syn::PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return false;
+    };
+    matches!(
+        args.args.first(),
+        Some(syn::GenericArgument::Type(Type::Path(inner))) if inner.path.is_ident("u8")
+    )
+}
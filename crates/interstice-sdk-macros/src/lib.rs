@@ -1,3 +1,4 @@
+mod access;
 mod caps_bounds;
 mod context_caps;
 mod index_key;
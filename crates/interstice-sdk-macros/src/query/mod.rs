@@ -10,9 +10,12 @@ use crate::query::{schema::get_register_schema_function, wrapper::get_wrapper_fu
 
 fn validate_query_attrs(attributes: &syn::punctuated::Punctuated<Meta, syn::Token![,]>) -> syn::Result<()> {
     for meta in attributes {
+        if crate::access::is_access_attr(meta) {
+            continue;
+        }
         return Err(syn::Error::new_spanned(
             meta,
-            "unsupported #[query] option; declare reads via `QueryContext<Caps>` and `where Caps: CanRead<Row>`",
+            "unsupported #[query] option; use `allow_nodes`/`allow_roles`/`access_policy` for remote access control (reads are declared via `QueryContext<Caps>` and `where Caps: CanRead<Row>`)",
         ));
     }
     Ok(())
@@ -94,6 +97,11 @@ pub fn query_macro(attr: TokenStream, item: TokenStream) -> TokenStream {
         return e.into_compile_error().into();
     }
 
+    let access = match crate::access::parse_access_policy(&attributes) {
+        Ok(access) => access,
+        Err(e) => return e.into_compile_error().into(),
+    };
+
     let arg_names: Vec<_> = input_fn
         .sig
         .inputs
@@ -121,6 +129,7 @@ pub fn query_macro(attr: TokenStream, item: TokenStream) -> TokenStream {
         arg_names,
         arg_types,
         caps_extend_body,
+        access,
     );
 
     quote! {
@@ -1,12 +1,15 @@
 use quote::{ToTokens, quote};
 use syn::{Ident, ItemFn, Pat, Type};
 
+use crate::interstice_type::register_generic_instances;
+
 pub fn get_register_schema_function(
     query_ident: Ident,
     input_fn: ItemFn,
     arg_names: Vec<&Box<Pat>>,
     arg_types: Vec<&Box<Type>>,
     caps_extend_body: proc_macro2::TokenStream,
+    access: proc_macro2::TokenStream,
 ) -> proc_macro2::TokenStream {
     let query_schema_fn = syn::Ident::new(
         &format!("interstice_{}_query_schema", query_ident),
@@ -25,11 +28,23 @@ pub fn get_register_schema_function(
                 interstice_sdk::FieldDef {
                     name: #arg_name_str.to_string(),
                     field_type: interstice_sdk::IntersticeType::from_str(#arg_type_str).unwrap(),
+                    default: None,
                 }
             }
         },
     );
 
+    let generic_instances = register_generic_instances(
+        arg_types
+            .iter()
+            .skip(1)
+            .map(|ty| ty.as_ref())
+            .chain(match &input_fn.sig.output {
+                syn::ReturnType::Type(_, ty) => Some(ty.as_ref()),
+                syn::ReturnType::Default => None,
+            }),
+    );
+
     let return_type = match &input_fn.sig.output {
         syn::ReturnType::Default => quote! { interstice_sdk::IntersticeType::Void },
         syn::ReturnType::Type(_, ty) => {
@@ -50,11 +65,13 @@ pub fn get_register_schema_function(
                 #return_type,
                 reads,
             )
+            .with_access(#access)
         }
 
         #[interstice_sdk::init]
         fn #register_query_schema_fn() {
             interstice_sdk::registry::register_query(#query_schema_fn);
+            #generic_instances
         }
     }
 }
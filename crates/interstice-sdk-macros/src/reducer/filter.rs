@@ -0,0 +1,315 @@
+//! `filter = "..."` of remote table subscriptions, compiled into a `RowFilter`.
+//!
+//! The filter is a boolean expression over the fields of the row, checked against
+//! the table schema found in `src/bindings`. Struct fields are reached by path:
+//!
+//! ```ignore
+//! #[reducer(
+//!     on = "agar-server-example.agar-server.player.insert",
+//!     filter = "radius >= 10.0 && within(pos.x, pos.y, (0.0, 0.0), (500.0, 500.0))"
+//! )]
+//! ```
+//!
+//! Supported: `==`, `!=`, `<`, `<=`, `>`, `>=` against a literal, `&&`, `||`, `!`,
+//! `field.between(min, max)`, `field.is_in([a, b])`, `field.starts_with("prefix")`
+//! and `within(x, y, (min_x, min_y), (max_x, max_y))` for a box on two fields.
+
+use std::collections::HashMap;
+
+use interstice_abi::{IntersticeType, IntersticeTypeDef, TableSchema};
+use proc_macro2::{Literal, TokenStream};
+use quote::quote;
+use syn::{BinOp, Expr, Lit, LitStr, Member, UnOp};
+
+pub fn filter_tokens(
+    filter: &LitStr,
+    table: &TableSchema,
+    type_definitions: &HashMap<String, IntersticeTypeDef>,
+) -> syn::Result<TokenStream> {
+    let expr: Expr = filter.parse()?;
+    FilterCompiler {
+        table,
+        type_definitions,
+    }
+    .filter(&expr)
+}
+
+struct FilterCompiler<'a> {
+    table: &'a TableSchema,
+    type_definitions: &'a HashMap<String, IntersticeTypeDef>,
+}
+
+impl FilterCompiler<'_> {
+    fn filter(&self, expr: &Expr) -> syn::Result<TokenStream> {
+        match expr {
+            Expr::Paren(paren) => self.filter(&paren.expr),
+            Expr::Group(group) => self.filter(&group.expr),
+            Expr::Unary(unary) if matches!(unary.op, UnOp::Not(_)) => {
+                let inner = self.filter(&unary.expr)?;
+                Ok(quote! {interstice_sdk::RowFilter::Not(Box::new(#inner))})
+            }
+            Expr::Binary(binary) => match binary.op {
+                BinOp::And(_) => {
+                    let filters = self.filters(expr, |op| matches!(op, BinOp::And(_)))?;
+                    Ok(quote! {interstice_sdk::RowFilter::And(vec![#(#filters),*])})
+                }
+                BinOp::Or(_) => {
+                    let filters = self.filters(expr, |op| matches!(op, BinOp::Or(_)))?;
+                    Ok(quote! {interstice_sdk::RowFilter::Or(vec![#(#filters),*])})
+                }
+                op => {
+                    let op = match op {
+                        BinOp::Eq(_) => quote! {Eq},
+                        BinOp::Ne(_) => quote! {Ne},
+                        BinOp::Lt(_) => quote! {Lt},
+                        BinOp::Le(_) => quote! {Lte},
+                        BinOp::Gt(_) => quote! {Gt},
+                        BinOp::Ge(_) => quote! {Gte},
+                        _ => {
+                            return Err(syn::Error::new_spanned(
+                                binary,
+                                "Unsupported operator in filter; use comparisons, `&&`, `||` and `!`",
+                            ));
+                        }
+                    };
+                    let (field, field_type) = self.field(&binary.left)?;
+                    let value = self.value(&binary.right, &field_type)?;
+                    Ok(compare(&field, op, value))
+                }
+            },
+            Expr::MethodCall(call) => {
+                let (field, field_type) = self.field(&call.receiver)?;
+                let args: Vec<&Expr> = call.args.iter().collect();
+                match (call.method.to_string().as_str(), args.as_slice()) {
+                    ("between", [min, max]) => {
+                        let min = self.value(min, &field_type)?;
+                        let max = self.value(max, &field_type)?;
+                        let low = compare(&field, quote! {Gte}, min);
+                        let high = compare(&field, quote! {Lte}, max);
+                        Ok(quote! {interstice_sdk::RowFilter::And(vec![#low, #high])})
+                    }
+                    ("is_in", [Expr::Array(array)]) => {
+                        let values = array
+                            .elems
+                            .iter()
+                            .map(|value| self.value(value, &field_type))
+                            .collect::<syn::Result<Vec<_>>>()?;
+                        Ok(quote! {
+                            interstice_sdk::RowFilter::In {
+                                field: #field.to_string(),
+                                values: vec![#(#values),*],
+                            }
+                        })
+                    }
+                    ("starts_with", [Expr::Lit(syn::ExprLit { lit: Lit::Str(prefix), .. })])
+                        if matches!(field_type, IntersticeType::String) =>
+                    {
+                        Ok(quote! {
+                            interstice_sdk::RowFilter::StartsWith {
+                                field: #field.to_string(),
+                                prefix: #prefix.to_string(),
+                            }
+                        })
+                    }
+                    _ => Err(syn::Error::new_spanned(
+                        call,
+                        "Expected `field.between(min, max)`, `field.is_in([...])` or `field.starts_with(\"...\")` on a String field",
+                    )),
+                }
+            }
+            Expr::Call(call) if matches!(&*call.func, Expr::Path(path) if path.path.is_ident("within")) => {
+                let args: Vec<&Expr> = call.args.iter().collect();
+                let [x, y, Expr::Tuple(min), Expr::Tuple(max)] = args.as_slice() else {
+                    return Err(syn::Error::new_spanned(
+                        call,
+                        "Expected `within(x, y, (min_x, min_y), (max_x, max_y))`",
+                    ));
+                };
+                let (Some(min_x), Some(min_y), Some(max_x), Some(max_y)) = (
+                    min.elems.first(),
+                    min.elems.get(1),
+                    max.elems.first(),
+                    max.elems.get(1),
+                ) else {
+                    return Err(syn::Error::new_spanned(call, "Box corners must be `(x, y)` pairs"));
+                };
+                let mut bounds = Vec::new();
+                for (axis, min, max) in [(x, min_x, max_x), (y, min_y, max_y)] {
+                    let (field, field_type) = self.field(axis)?;
+                    bounds.push(compare(&field, quote! {Gte}, self.value(min, &field_type)?));
+                    bounds.push(compare(&field, quote! {Lte}, self.value(max, &field_type)?));
+                }
+                Ok(quote! {interstice_sdk::RowFilter::And(vec![#(#bounds),*])})
+            }
+            _ => Err(syn::Error::new_spanned(
+                expr,
+                "Unsupported filter expression; expected a comparison of a field with a literal",
+            )),
+        }
+    }
+
+    /// Operands of a chain of the same boolean operator, flattened.
+    fn filters(&self, expr: &Expr, same_op: fn(&BinOp) -> bool) -> syn::Result<Vec<TokenStream>> {
+        match expr {
+            Expr::Binary(binary) if same_op(&binary.op) => {
+                let mut filters = self.filters(&binary.left, same_op)?;
+                filters.extend(self.filters(&binary.right, same_op)?);
+                Ok(filters)
+            }
+            expr => Ok(vec![self.filter(expr)?]),
+        }
+    }
+
+    /// Dotted name and type of the field `expr` designates.
+    fn field(&self, expr: &Expr) -> syn::Result<(String, IntersticeType)> {
+        match expr {
+            Expr::Path(path) => {
+                let Some(name) = path.path.get_ident().map(|ident| ident.to_string()) else {
+                    return Err(syn::Error::new_spanned(path, "Expected a field name"));
+                };
+                let field = std::iter::once(&self.table.primary_key)
+                    .chain(&self.table.fields)
+                    .find(|field| field.name == name)
+                    .ok_or_else(|| {
+                        syn::Error::new_spanned(
+                            path,
+                            format!("Table '{}' has no field '{}'", self.table.name, name),
+                        )
+                    })?;
+                Ok((name, field.field_type.clone()))
+            }
+            Expr::Field(field) => {
+                let (base, base_type) = self.field(&field.base)?;
+                let Member::Named(member) = &field.member else {
+                    return Err(syn::Error::new_spanned(field, "Expected a named field"));
+                };
+                let member_name = member.to_string();
+                let member_type = match &base_type {
+                    IntersticeType::Named(type_name) => match self.type_definitions.get(type_name) {
+                        Some(IntersticeTypeDef::Struct { fields, .. }) => fields
+                            .iter()
+                            .find(|field| field.name == member_name)
+                            .map(|field| field.field_type.clone()),
+                        _ => None,
+                    },
+                    _ => None,
+                };
+                let member_type = member_type.ok_or_else(|| {
+                    syn::Error::new_spanned(
+                        field,
+                        format!("Field '{}' of type {} has no field '{}'", base, base_type, member_name),
+                    )
+                })?;
+                Ok((format!("{}.{}", base, member_name), member_type))
+            }
+            expr => Err(syn::Error::new_spanned(expr, "Expected a field name, e.g. `score` or `pos.x`")),
+        }
+    }
+
+    /// `IntersticeValue` of the literal `expr` for a field of type `field_type`.
+    fn value(&self, expr: &Expr, field_type: &IntersticeType) -> syn::Result<TokenStream> {
+        let mismatch = || {
+            syn::Error::new_spanned(
+                expr,
+                format!("Expected a literal of the field type {}", field_type),
+            )
+        };
+        let (negative, lit) = match expr {
+            Expr::Lit(lit) => (false, &lit.lit),
+            Expr::Unary(unary) if matches!(unary.op, UnOp::Neg(_)) => match &*unary.expr {
+                Expr::Lit(lit) => (true, &lit.lit),
+                _ => return Err(mismatch()),
+            },
+            Expr::Path(path) if path.path.is_ident("None") => {
+                return match field_type {
+                    IntersticeType::Option(_) => Ok(quote! {interstice_sdk::IntersticeValue::Option(None)}),
+                    _ => Err(mismatch()),
+                };
+            }
+            _ => return Err(mismatch()),
+        };
+        let number = || {
+            let digits = match lit {
+                Lit::Int(int) => int.base10_digits(),
+                Lit::Float(float) => float.base10_digits(),
+                _ => return None,
+            };
+            Some(if negative { format!("-{}", digits) } else { digits.to_string() })
+        };
+        macro_rules! int_value {
+            ($variant:ident, $ty:ty, $suffixed:ident) => {{
+                let value = match lit {
+                    Lit::Int(_) => number().and_then(|digits| digits.parse::<$ty>().ok()),
+                    _ => None,
+                }
+                .ok_or_else(mismatch)?;
+                let value = Literal::$suffixed(value);
+                Ok(quote! {interstice_sdk::IntersticeValue::$variant(#value)})
+            }};
+        }
+        match field_type {
+            IntersticeType::U8 => int_value!(U8, u8, u8_suffixed),
+            IntersticeType::U16 => int_value!(U16, u16, u16_suffixed),
+            IntersticeType::U32 => int_value!(U32, u32, u32_suffixed),
+            IntersticeType::U64 => int_value!(U64, u64, u64_suffixed),
+            IntersticeType::U128 => int_value!(U128, u128, u128_suffixed),
+            IntersticeType::I8 => int_value!(I8, i8, i8_suffixed),
+            IntersticeType::I16 => int_value!(I16, i16, i16_suffixed),
+            IntersticeType::I32 => int_value!(I32, i32, i32_suffixed),
+            IntersticeType::I64 => int_value!(I64, i64, i64_suffixed),
+            IntersticeType::I128 => int_value!(I128, i128, i128_suffixed),
+            IntersticeType::F32 => {
+                let value = number().and_then(|digits| digits.parse::<f32>().ok())
+                    .filter(|value| value.is_finite())
+                    .ok_or_else(mismatch)?;
+                let value = Literal::f32_suffixed(value);
+                Ok(quote! {interstice_sdk::IntersticeValue::F32(#value)})
+            }
+            IntersticeType::F64 => {
+                let value = number().and_then(|digits| digits.parse::<f64>().ok())
+                    .filter(|value| value.is_finite())
+                    .ok_or_else(mismatch)?;
+                let value = Literal::f64_suffixed(value);
+                Ok(quote! {interstice_sdk::IntersticeValue::F64(#value)})
+            }
+            IntersticeType::Bool => match lit {
+                Lit::Bool(value) if !negative => Ok(quote! {interstice_sdk::IntersticeValue::Bool(#value)}),
+                _ => Err(mismatch()),
+            },
+            IntersticeType::Char => match lit {
+                Lit::Char(value) if !negative => Ok(quote! {interstice_sdk::IntersticeValue::Char(#value)}),
+                _ => Err(mismatch()),
+            },
+            IntersticeType::Bytes => match lit {
+                Lit::ByteStr(value) if !negative => {
+                    Ok(quote! {interstice_sdk::IntersticeValue::Bytes(#value.to_vec())})
+                }
+                _ => Err(mismatch()),
+            },
+            IntersticeType::String => match lit {
+                Lit::Str(value) if !negative => {
+                    Ok(quote! {interstice_sdk::IntersticeValue::String(#value.to_string())})
+                }
+                _ => Err(mismatch()),
+            },
+            IntersticeType::Option(inner) => {
+                let value = self.value(expr, inner)?;
+                Ok(quote! {interstice_sdk::IntersticeValue::Option(Some(Box::new(#value)))})
+            }
+            _ => Err(syn::Error::new_spanned(
+                expr,
+                format!("Fields of type {} cannot be used in a filter", field_type),
+            )),
+        }
+    }
+}
+
+fn compare(field: &str, op: TokenStream, value: TokenStream) -> TokenStream {
+    quote! {
+        interstice_sdk::RowFilter::Compare {
+            field: #field.to_string(),
+            op: interstice_sdk::CompareOp::#op,
+            value: #value,
+        }
+    }
+}
@@ -1,3 +1,4 @@
+mod filter;
 mod schema;
 mod subscription;
 mod wrapper;
@@ -16,10 +17,14 @@ fn validate_reducer_attrs(attributes: &syn::punctuated::Punctuated<Meta, syn::To
     for meta in attributes {
         match meta {
             Meta::NameValue(nv) if nv.path.is_ident("on") => {}
+            Meta::NameValue(nv) if nv.path.is_ident("filter") => {}
+            Meta::NameValue(nv) if nv.path.is_ident("fuel") => {}
+            Meta::Path(path) if path.is_ident("atomic") => {}
+            meta if crate::access::is_access_attr(meta) => {}
             _ => {
                 return Err(syn::Error::new_spanned(
                     meta,
-                    "unsupported #[reducer] option; use only `on = \"…\"` for subscriptions (table access is declared via `ReducerContext<Caps>` and `where Caps: CanRead<Row> + …`)",
+                    "unsupported #[reducer] option; use `on = \"…\"` for subscriptions, `filter = \"…\"` to filter remote table events, `fuel = N` for a CPU budget, `atomic` for all-or-nothing nested calls or `allow_nodes`/`allow_roles`/`access_policy` for remote access control (table access is declared via `ReducerContext<Caps>` and `where Caps: CanRead<Row> + …`)",
                 ));
             }
         }
@@ -27,6 +32,32 @@ fn validate_reducer_attrs(attributes: &syn::punctuated::Punctuated<Meta, syn::To
     Ok(())
 }
 
+/// Parse the optional `fuel = N` per-call budget override.
+fn parse_reducer_fuel(
+    attributes: &syn::punctuated::Punctuated<Meta, syn::Token![,]>,
+) -> syn::Result<Option<u64>> {
+    for meta in attributes {
+        let Meta::NameValue(nv) = meta else {
+            continue;
+        };
+        if !nv.path.is_ident("fuel") {
+            continue;
+        }
+        if let syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Int(lit),
+            ..
+        }) = &nv.value
+        {
+            return lit.base10_parse::<u64>().map(Some);
+        }
+        return Err(syn::Error::new_spanned(
+            &nv.value,
+            "`fuel` expects an integer literal, e.g. `fuel = 10_000_000`",
+        ));
+    }
+    Ok(None)
+}
+
 pub fn reducer_macro(attr: TokenStream, item: TokenStream) -> TokenStream {
     let mut input_fn = parse_macro_input!(item as ItemFn);
     let reducer_ident = input_fn.sig.ident.clone();
@@ -126,6 +157,18 @@ pub fn reducer_macro(attr: TokenStream, item: TokenStream) -> TokenStream {
         return e.into_compile_error().into();
     }
 
+    let fuel = match parse_reducer_fuel(&attributes) {
+        Ok(fuel) => fuel,
+        Err(e) => return e.into_compile_error().into(),
+    };
+    let access = match crate::access::parse_access_policy(&attributes) {
+        Ok(access) => access,
+        Err(e) => return e.into_compile_error().into(),
+    };
+    let atomic = attributes
+        .iter()
+        .any(|meta| matches!(meta, Meta::Path(path) if path.is_ident("atomic")));
+
     let (register_subscription, use_table_subscription) =
         get_register_subscription_function(reducer_ident.clone(), attributes);
 
@@ -161,6 +204,9 @@ pub fn reducer_macro(attr: TokenStream, item: TokenStream) -> TokenStream {
         arg_names,
         arg_types,
         caps_extend_body,
+        fuel,
+        atomic,
+        access,
     );
 
     quote! {
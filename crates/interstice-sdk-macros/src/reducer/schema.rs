@@ -1,11 +1,16 @@
 use quote::quote;
 use syn::{Ident, Pat, Type};
 
+use crate::interstice_type::register_generic_instances;
+
 pub fn get_register_schema_function(
     reducer_ident: Ident,
     arg_names: Vec<&Box<Pat>>,
     arg_types: Vec<&Box<Type>>,
     caps_extend_body: proc_macro2::TokenStream,
+    fuel: Option<u64>,
+    atomic: bool,
+    access: proc_macro2::TokenStream,
 ) -> proc_macro2::TokenStream {
     let reducer_schema_fn = syn::Ident::new(
         &format!("interstice_{}_schema", reducer_ident),
@@ -24,11 +29,20 @@ pub fn get_register_schema_function(
                 interstice_sdk::FieldDef {
                     name: #arg_name_str.to_string(),
                     field_type: interstice_sdk::IntersticeType::from_str(#arg_type_str).unwrap(),
+                    default: None,
                 }
             }
         },
     );
 
+    let generic_instances =
+        register_generic_instances(arg_types.iter().skip(1).map(|ty| ty.as_ref()));
+
+    let fuel = match fuel {
+        Some(fuel) => quote! { Some(#fuel) },
+        None => quote! { None },
+    };
+
     quote! {
         fn #reducer_schema_fn() -> interstice_sdk::ReducerSchema {
             let mut reads = Vec::new();
@@ -44,11 +58,15 @@ pub fn get_register_schema_function(
                 updates,
                 deletes,
             )
+            .with_fuel(#fuel)
+            .with_atomic(#atomic)
+            .with_access(#access)
         }
 
         #[interstice_sdk::init]
         fn #register_reducer_schema_fn() {
             interstice_sdk::registry::register_reducer(#reducer_schema_fn);
+            #generic_instances
         }
     }
 }
@@ -10,6 +10,7 @@ use std::{
 use toml::Value as TomlValue;
 
 use crate::path_segments::segments_from_dotted_str;
+use crate::reducer::filter::filter_tokens;
 
 pub fn get_register_subscription_function(
     reducer_ident: Ident,
@@ -37,6 +38,23 @@ pub fn get_register_subscription_function(
 
     let mut use_table_subscription = false;
 
+    let filter = attributes.iter().find_map(|arg| match arg {
+        Meta::NameValue(nv) if nv.path.is_ident("filter") => Some(&nv.value),
+        _ => None,
+    });
+    let filter = match filter {
+        None => None,
+        Some(Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Str(litstr),
+            ..
+        })) => Some(litstr),
+        Some(value) => {
+            let msg = "`filter` expects a string, e.g. `filter = \"score > 10 && team == 1\"`";
+            return (syn::Error::new_spanned(value, msg).into_compile_error(), false);
+        }
+    };
+    let mut filter_used = false;
+
     let subscription = attributes.iter().find_map(|arg| {
         let bindings_dir = Path::new(&manifest_dir).join("src").join("bindings");
         if let Meta::NameValue(nv) = arg {
@@ -44,11 +62,35 @@ pub fn get_register_subscription_function(
                 if let Expr::Lit(expr_lit) = &nv.value {
                     if let syn::Lit::Str(litstr) = &expr_lit.lit {
                         let content = litstr.value();
+                        if let Some(args) = content
+                            .strip_prefix("tick(")
+                            .and_then(|rest| rest.strip_suffix(')'))
+                        {
+                            let (period_us, overrun) = match parse_tick(args) {
+                                Ok(tick) => tick,
+                                Err(msg) => {
+                                    return Some(syn::Error::new(litstr.span(), msg).into_compile_error());
+                                }
+                            };
+                            return Some(
+                                quote! {
+                                    interstice_sdk::SubscriptionSchema {
+                                        reducer_name: stringify!(#reducer_ident).to_string(),
+                                        filter: None,
+                                        event: interstice_sdk::SubscriptionEventSchema::Tick {
+                                            period_us: #period_us,
+                                            overrun: #overrun,
+                                        }
+                                    }
+                                }
+                            );
+                        }
                         if let Some(path) = content.strip_prefix("file_recursive:") {
                             return Some(
                                 quote! {
                                     interstice_sdk::SubscriptionSchema {
                                         reducer_name: stringify!(#reducer_ident).to_string(),
+                                        filter: None,
                                         event: interstice_sdk::SubscriptionEventSchema::File {
                                             path: #path.to_string(),
                                             recursive: true,
@@ -62,6 +104,7 @@ pub fn get_register_subscription_function(
                                 quote! {
                                     interstice_sdk::SubscriptionSchema {
                                         reducer_name: stringify!(#reducer_ident).to_string(),
+                                        filter: None,
                                         event: interstice_sdk::SubscriptionEventSchema::File {
                                             path: #path.to_string(),
                                             recursive: false,
@@ -79,6 +122,7 @@ pub fn get_register_subscription_function(
                         let mut node_names: HashSet<String> = HashSet::new();
                         let mut module_names: HashMap<String, HashSet<String>> = HashMap::new();
                         let mut module_tables: HashMap<(String, String), HashSet<String>> = HashMap::new();
+                        let mut node_tables = HashMap::new();
 
                         module_names.insert("".into(), HashSet::new()); // Current node represented with empty name
                         
@@ -105,8 +149,12 @@ pub fn get_register_subscription_function(
                                                     let mname = module.name.to_lowercase();
                                                     module_names.get_mut(&nname).unwrap().insert(mname.clone());
                                                     let mut set = HashSet::new();
-                                                    for t in module.tables {
+                                                    for t in &module.tables {
                                                         set.insert(t.name.to_lowercase());
+                                                        node_tables.insert(
+                                                            (nname.clone(), mname.clone(), t.name.to_lowercase()),
+                                                            (t.clone(), module.type_definitions.clone()),
+                                                        );
                                                     }
                                                     module_tables.insert((nname.clone(), mname), set.clone());
                                                 }
@@ -200,6 +248,29 @@ pub fn get_register_subscription_function(
                                 (node_selection, module_name, table_name, event_name)
                             };
 
+                            // Filters are evaluated by the remote node, against its table schema.
+                            let row_filter = match filter {
+                                Some(filter)
+                                    if segments.len() == 4
+                                        && matches!(event_name.as_str(), "insert" | "update" | "delete") =>
+                                {
+                                    let key = (segments[0].clone(), module_name.clone(), table_name.clone());
+                                    let Some((table, type_definitions)) = node_tables.get(&key) else {
+                                        let msg = format!(
+                                            "Schema of table '{}' not found in src/bindings; it is needed to check the filter",
+                                            table_name
+                                        );
+                                        return Some(syn::Error::new(filter.span(), msg).into_compile_error());
+                                    };
+                                    filter_used = true;
+                                    match filter_tokens(filter, table, type_definitions) {
+                                        Ok(row_filter) => quote! {Some(#row_filter)},
+                                        Err(err) => return Some(err.into_compile_error()),
+                                    }
+                                }
+                                _ => quote! {None},
+                            };
+
                             match event_name.as_str() {
                                 "insert" => {
                                     use_table_subscription = true;
@@ -207,6 +278,7 @@ pub fn get_register_subscription_function(
                                             quote! {
                                                 interstice_sdk::SubscriptionSchema {
                                                     reducer_name: stringify!(#reducer_ident).to_string(),
+                                                    filter: #row_filter,
                                                     event: interstice_sdk::SubscriptionEventSchema::Insert {
                                                         node_selection: #node_selection,
                                                         module_name: #module_name.to_string(),
@@ -221,6 +293,7 @@ pub fn get_register_subscription_function(
                                             quote! {
                                                 interstice_sdk::SubscriptionSchema {
                                                     reducer_name: stringify!(#reducer_ident).to_string(),
+                                                    filter: #row_filter,
                                                     event: interstice_sdk::SubscriptionEventSchema::Update {
                                                         node_selection: #node_selection,
                                                         module_name: #module_name.to_string(),
@@ -235,6 +308,7 @@ pub fn get_register_subscription_function(
                                             quote! {
                                                 interstice_sdk::SubscriptionSchema {
                                                     reducer_name: stringify!(#reducer_ident).to_string(),
+                                                    filter: #row_filter,
                                                     event: interstice_sdk::SubscriptionEventSchema::Delete {
                                                         node_selection: #node_selection,
                                                         module_name: #module_name.to_string(),
@@ -258,6 +332,7 @@ pub fn get_register_subscription_function(
                                         quote! {
                                             interstice_sdk::SubscriptionSchema {
                                                 reducer_name: stringify!(#reducer_ident).to_string(),
+                                                filter: None,
                                                 event: interstice_sdk::SubscriptionEventSchema::ReplicaSync {
                                                     node_name: #node_name.to_string(),
                                                     module_name: #module_name.to_string(),
@@ -282,6 +357,7 @@ pub fn get_register_subscription_function(
                                             quote! {
                                                 interstice_sdk::SubscriptionSchema {
                                                     reducer_name: stringify!(#reducer_ident).to_string(),
+                                                    filter: None,
                                                     event: interstice_sdk::SubscriptionEventSchema::Init
                                                 }
                                             }
@@ -292,6 +368,7 @@ pub fn get_register_subscription_function(
                                             quote! {
                                                 interstice_sdk::SubscriptionSchema {
                                                     reducer_name: stringify!(#reducer_ident).to_string(),
+                                                    filter: None,
                                                     event: interstice_sdk::SubscriptionEventSchema::Load
                                                 }
                                             }
@@ -302,6 +379,7 @@ pub fn get_register_subscription_function(
                                             quote! {
                                                 interstice_sdk::SubscriptionSchema {
                                                     reducer_name: stringify!(#reducer_ident).to_string(),
+                                                    filter: None,
                                                     event: interstice_sdk::SubscriptionEventSchema::Input
                                                 }
                                             }
@@ -312,6 +390,7 @@ pub fn get_register_subscription_function(
                                             quote! {
                                                 interstice_sdk::SubscriptionSchema {
                                                     reducer_name: stringify!(#reducer_ident).to_string(),
+                                                    filter: None,
                                                     event: interstice_sdk::SubscriptionEventSchema::AudioOutput
                                                 }
                                             }
@@ -322,6 +401,7 @@ pub fn get_register_subscription_function(
                                             quote! {
                                                 interstice_sdk::SubscriptionSchema {
                                                     reducer_name: stringify!(#reducer_ident).to_string(),
+                                                    filter: None,
                                                     event: interstice_sdk::SubscriptionEventSchema::AudioInput
                                                 }
                                             }
@@ -332,6 +412,7 @@ pub fn get_register_subscription_function(
                                             quote! {
                                                 interstice_sdk::SubscriptionSchema {
                                                     reducer_name: stringify!(#reducer_ident).to_string(),
+                                                    filter: None,
                                                     event: interstice_sdk::SubscriptionEventSchema::Render
                                                 }
                                             }
@@ -342,6 +423,7 @@ pub fn get_register_subscription_function(
                                             quote! {
                                                 interstice_sdk::SubscriptionSchema {
                                                     reducer_name: stringify!(#reducer_ident).to_string(),
+                                                    filter: None,
                                                     event: interstice_sdk::SubscriptionEventSchema::Network
                                                 }
                                             }
@@ -352,6 +434,7 @@ pub fn get_register_subscription_function(
                                             quote! {
                                                 interstice_sdk::SubscriptionSchema {
                                                     reducer_name: stringify!(#reducer_ident).to_string(),
+                                                    filter: None,
                                                     event: interstice_sdk::SubscriptionEventSchema::ModuleLoad
                                                 }
                                             }
@@ -362,6 +445,7 @@ pub fn get_register_subscription_function(
                                             quote! {
                                                 interstice_sdk::SubscriptionSchema {
                                                     reducer_name: stringify!(#reducer_ident).to_string(),
+                                                    filter: None,
                                                     event: interstice_sdk::SubscriptionEventSchema::ModuleRemove
                                                 }
                                             }
@@ -372,6 +456,7 @@ pub fn get_register_subscription_function(
                                             quote! {
                                                 interstice_sdk::SubscriptionSchema {
                                                     reducer_name: stringify!(#reducer_ident).to_string(),
+                                                    filter: None,
                                                     event: interstice_sdk::SubscriptionEventSchema::Connect
                                                 }
                                             }
@@ -382,6 +467,7 @@ pub fn get_register_subscription_function(
                                             quote! {
                                                 interstice_sdk::SubscriptionSchema {
                                                     reducer_name: stringify!(#reducer_ident).to_string(),
+                                                    filter: None,
                                                     event: interstice_sdk::SubscriptionEventSchema::Disconnect
                                                 }
                                             }
@@ -391,7 +477,7 @@ pub fn get_register_subscription_function(
                                     return Some(
                                         syn::Error::new_spanned(
                                             litstr,
-                                            "Expected 'init', 'load', 'input', 'audio_output', 'audio_input', 'render', 'module_load', 'module_remove', 'connect', 'disconnect', 'file:<path>', 'file_recursive:<path>' or formats: '[module].[table].[event]' or '[node].[module].[table].[event]'",
+                                            "Expected 'init', 'load', 'input', 'audio_output', 'audio_input', 'render', 'module_load', 'module_remove', 'connect', 'disconnect', 'tick(<period>[, <overrun>])', 'file:<path>', 'file_recursive:<path>' or formats: '[module].[table].[event]' or '[node].[module].[table].[event]'",
                                         )
                                         .to_compile_error()
                                         .into(),
@@ -404,7 +490,7 @@ pub fn get_register_subscription_function(
                 return Some(
                     syn::Error::new_spanned(
                         &nv.value,
-                        "Expected 'init', 'load', 'input', 'audio_output', 'audio_input', 'render', 'module_load', 'module_remove', 'connect', 'disconnect', 'file:<path>', 'file_recursive:<path>' or formats: '[module].[table].[event]' or '[node].[module].[table].[event]'",
+                        "Expected 'init', 'load', 'input', 'audio_output', 'audio_input', 'render', 'module_load', 'module_remove', 'connect', 'disconnect', 'tick(<period>[, <overrun>])', 'file:<path>', 'file_recursive:<path>' or formats: '[module].[table].[event]' or '[node].[module].[table].[event]'",
                     )
                     .to_compile_error()
                     .into(),
@@ -414,6 +500,13 @@ pub fn get_register_subscription_function(
         None
     });
 
+    if let Some(filter) = filter
+        && !filter_used
+    {
+        let msg = "`filter` is only supported on remote table events: '[node].[module].[table].[insert|update|delete]'";
+        return (syn::Error::new(filter.span(), msg).into_compile_error(), false);
+    }
+
     let register_subscription = if let Some(subscription_schema) = subscription {
         quote! {
             fn #subscription_schema_fn() -> interstice_sdk::SubscriptionSchema {
@@ -431,3 +524,45 @@ pub fn get_register_subscription_function(
 
     return (register_subscription, use_table_subscription);
 }
+
+/// Parse the arguments of `tick(8ms)` / `tick(8ms, catch_up)` into the period in
+/// microseconds and the overrun policy.
+fn parse_tick(args: &str) -> Result<(u64, proc_macro2::TokenStream), String> {
+    let mut parts = args.split(',').map(str::trim);
+    let period = parts.next().unwrap_or_default();
+    let overrun = match parts.next() {
+        None | Some("skip") => quote! {interstice_sdk::TickOverrun::Skip},
+        Some("catch_up") => quote! {interstice_sdk::TickOverrun::CatchUp},
+        Some("coalesce") => quote! {interstice_sdk::TickOverrun::Coalesce},
+        Some(other) => {
+            return Err(format!(
+                "Unknown tick overrun policy '{}'. Expected 'skip', 'catch_up' or 'coalesce'",
+                other
+            ));
+        }
+    };
+    if parts.next().is_some() {
+        return Err("Expected 'tick(<period>)' or 'tick(<period>, <overrun>)'".into());
+    }
+
+    let (amount, unit_us) = if let Some(amount) = period.strip_suffix("us") {
+        (amount, 1)
+    } else if let Some(amount) = period.strip_suffix("ms") {
+        (amount, 1_000)
+    } else if let Some(amount) = period.strip_suffix('s') {
+        (amount, 1_000_000)
+    } else {
+        return Err(format!(
+            "Tick period '{}' needs a unit: 'us', 'ms' or 's' (e.g. 'tick(8ms)')",
+            period
+        ));
+    };
+    let period_us = amount
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .and_then(|amount| amount.checked_mul(unit_us))
+        .filter(|period_us| *period_us > 0)
+        .ok_or_else(|| format!("Tick period '{}' must be a positive whole number", period))?;
+    Ok((period_us, overrun))
+}
@@ -44,15 +44,64 @@ pub fn table_macro(attr: TokenStream, item: TokenStream) -> TokenStream {
     );
 
     let mut visibility = quote! { interstice_sdk::TableVisibility::Private };
+    let mut is_public = false;
     let mut persistence = quote! { interstice_sdk::PersistenceKind::Logged };
+    let mut max_rows = quote! { None };
+    let mut max_bytes = quote! { None };
+    let mut view_query: Option<String> = None;
+    let mut composite_specs = Vec::new();
 
     for arg in args.iter() {
-        if let Meta::Path(nv) = arg {
+        if let Meta::List(list) = arg {
+            if !list.path.is_ident("index") {
+                return quote! { compile_error!("Invalid table attribute syntax") }.into();
+            }
+            match parse_composite_index(list) {
+                Ok(spec) => composite_specs.push(spec),
+                Err(message) => return quote! { compile_error!(#message); }.into(),
+            }
+            continue;
+        }
+        if let Meta::NameValue(nv) = arg {
+            if nv.path.is_ident("view") {
+                match &nv.value {
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(lit),
+                        ..
+                    }) => view_query = Some(lit.value()),
+                    _ => {
+                        return quote! { compile_error!("Table views expect the name of a query, e.g. `view = \"visible_rows\"`") }.into();
+                    }
+                }
+                continue;
+            }
+            let limit = match &nv.value {
+                syn::Expr::Lit(syn::ExprLit {
+                    lit: syn::Lit::Int(lit),
+                    ..
+                }) => match lit.base10_parse::<u64>() {
+                    Ok(limit) => limit,
+                    Err(err) => return err.into_compile_error().into(),
+                },
+                _ => {
+                    return quote! { compile_error!("Table quotas expect an integer literal, e.g. `max_rows = 10_000`") }.into();
+                }
+            };
+            if nv.path.is_ident("max_rows") {
+                max_rows = quote! { Some(#limit) };
+            } else if nv.path.is_ident("max_bytes") {
+                max_bytes = quote! { Some(#limit) };
+            } else {
+                return quote! { compile_error!("Invalid table attribute. Expected 'max_rows = N', 'max_bytes = N' or 'view = \"query\"'") }.into();
+            }
+        } else if let Meta::Path(nv) = arg {
             if nv.is_ident("public") {
                 visibility = quote! { interstice_sdk::TableVisibility::Public };
+                is_public = true;
                 continue;
             } else if nv.is_ident("private") {
                 visibility = quote! { interstice_sdk::TableVisibility::Private };
+                is_public = false;
                 continue;
             } else if nv.is_ident("ephemeral") {
                 persistence = quote! { interstice_sdk::PersistenceKind::Ephemeral };
@@ -71,6 +120,14 @@ pub fn table_macro(attr: TokenStream, item: TokenStream) -> TokenStream {
         }
     }
 
+    if view_query.is_some() && !is_public {
+        return quote! { compile_error!("Table views only apply to `public` tables") }.into();
+    }
+    let view = match view_query {
+        Some(query_name) => quote! { Some(#query_name.to_string()) },
+        None => quote! { None },
+    };
+
     // Generate the entries and primary key
     let fields = match &input.fields {
         syn::Fields::Named(f) => &f.named,
@@ -88,7 +145,9 @@ pub fn table_macro(attr: TokenStream, item: TokenStream) -> TokenStream {
     )> = None;
     let mut schema_fields = Vec::new();
     let mut entry_fields = Vec::new();
+    let mut entry_types = Vec::new();
     let mut index_schemas = Vec::new();
+    let mut migrations = Vec::new();
     let mut indexed_fields: Vec<(
         syn::Ident,
         String,
@@ -210,6 +269,7 @@ pub fn table_macro(attr: TokenStream, item: TokenStream) -> TokenStream {
                     index_type: #index_type,
                     unique: #unique,
                     auto_inc: #auto_inc,
+                    fields: Vec::new(),
                 }
             });
 
@@ -223,6 +283,61 @@ pub fn table_macro(attr: TokenStream, item: TokenStream) -> TokenStream {
             ));
         }
 
+        if let Some(attr) = field
+            .attrs
+            .iter()
+            .find(|attr| attr.path().is_ident("migrate"))
+        {
+            if is_pk {
+                return quote! {compile_error!("#[migrate] cannot be used on #[primary_key] fields");}.into();
+            }
+
+            let args = match attr.parse_args_with(
+                syn::punctuated::Punctuated::<Meta, syn::Token![,]>::parse_terminated,
+            ) {
+                Ok(args) => args,
+                Err(_) => {
+                    return quote! {compile_error!("Invalid #[migrate(...)] syntax");}.into();
+                }
+            };
+
+            let mut renamed_from = quote! { None };
+            let mut default = quote! { None };
+            for arg in args {
+                match arg {
+                    Meta::NameValue(nv) if nv.path.is_ident("rename_from") => match &nv.value {
+                        syn::Expr::Lit(syn::ExprLit {
+                            lit: syn::Lit::Str(name),
+                            ..
+                        }) => renamed_from = quote! { Some(#name.to_string()) },
+                        _ => {
+                            return quote! {compile_error!("#[migrate(rename_from = ...)] expects a string literal");}.into();
+                        }
+                    },
+                    Meta::NameValue(nv) if nv.path.is_ident("default") => {
+                        let value = nv.value;
+                        default = quote! {
+                            Some(Into::<interstice_sdk::IntersticeValue>::into({
+                                let value: #field_ty_ident = #value;
+                                value
+                            }))
+                        };
+                    }
+                    _ => {
+                        return quote! {compile_error!("Invalid #[migrate] argument. Use rename_from = \"old_name\" and/or default = value");}.into();
+                    }
+                }
+            }
+
+            migrations.push(quote! {
+                interstice_sdk::FieldMigration {
+                    field_name: #field_name.to_string(),
+                    renamed_from: #renamed_from,
+                    default: #default,
+                }
+            });
+        }
+
         if is_pk {
             if primary_key.is_some() {
                 return quote! {compile_error!("Only one #[primary_key] field is allowed");}.into();
@@ -237,10 +352,21 @@ pub fn table_macro(attr: TokenStream, item: TokenStream) -> TokenStream {
             ));
         } else {
             entry_fields.push(field_ident.clone());
+            entry_types.push(field_ty_ident.clone());
+            let default = if has_interstice_default(field) {
+                quote! {
+                    Some(Into::<interstice_sdk::IntersticeValue>::into(
+                        <#field_ty_ident as Default>::default(),
+                    ))
+                }
+            } else {
+                quote! { None }
+            };
             schema_fields.push(quote! {
                 interstice_sdk::FieldDef {
                     name: #field_name.to_string(),
                     field_type: #field_ty,
+                    default: #default,
                 }
             });
         }
@@ -266,12 +392,76 @@ pub fn table_macro(attr: TokenStream, item: TokenStream) -> TokenStream {
         }
     }
 
+    let mut composite_indexes = Vec::new();
+    for spec in composite_specs {
+        let mut key_fields = Vec::new();
+        for field_ident in &spec.fields {
+            let Some(field) = fields
+                .iter()
+                .find(|field| field.ident.as_ref() == Some(field_ident))
+            else {
+                let message = format!("Index field '{}' is not a field of the table", field_ident);
+                return quote! {compile_error!(#message);}.into();
+            };
+            if field_ident == pk_ident {
+                return quote! {compile_error!("The primary key cannot be part of a composite index");}.into();
+            }
+            if let Err(message) = validate_index_key_type(&field.ty) {
+                return quote! {compile_error!(#message);}.into();
+            }
+            key_fields.push((field_ident.clone(), field.ty.clone()));
+        }
+
+        let index_name = spec.name.unwrap_or_else(|| {
+            spec.fields
+                .iter()
+                .map(|field| field.to_string())
+                .collect::<Vec<_>>()
+                .join("_")
+        });
+        let taken = indexed_fields.iter().any(|indexed| indexed.1 == index_name)
+            || composite_indexes
+                .iter()
+                .any(|index: &CompositeIndex| index.name == index_name);
+        if taken {
+            let message = format!(
+                "Index name '{}' is already used; give the composite index a `name = \"...\"`",
+                index_name
+            );
+            return quote! {compile_error!(#message);}.into();
+        }
+
+        let index_type = if spec.is_btree {
+            quote! { interstice_sdk::IndexType::BTree }
+        } else {
+            quote! { interstice_sdk::IndexType::Hash }
+        };
+        let field_names = spec.fields.iter().map(|field| field.to_string());
+        let unique = spec.unique;
+        index_schemas.push(quote! {
+            interstice_sdk::IndexSchema {
+                field_name: #index_name.to_string(),
+                index_type: #index_type,
+                unique: #unique,
+                auto_inc: false,
+                fields: vec![#(#field_names.to_string()),*],
+            }
+        });
+        composite_indexes.push(CompositeIndex {
+            name: index_name,
+            fields: key_fields,
+            unique: spec.unique,
+            is_btree: spec.is_btree,
+        });
+    }
+
     // Generate the output struct without the primary key attribute
     let mut output_struct = input.clone();
     if let syn::Fields::Named(fields) = &mut output_struct.fields {
         for field in fields.named.iter_mut() {
             field.attrs.retain(|a| !a.path().is_ident("primary_key"));
             field.attrs.retain(|a| !a.path().is_ident("index"));
+            field.attrs.retain(|a| !a.path().is_ident("migrate"));
         }
     }
 
@@ -288,6 +478,7 @@ pub fn table_macro(attr: TokenStream, item: TokenStream) -> TokenStream {
             struct_ident.span(),
         );
         let fn_get = syn::Ident::new(&format!("get_by_{}", index_name), struct_ident.span());
+        let fn_iter = syn::Ident::new(&format!("iter_by_{}", index_name), struct_ident.span());
 
         index_read_methods.push(quote! {
             pub fn #fn_eq(&self, value: #index_ty_ident) -> Vec<#struct_ident>
@@ -410,11 +601,71 @@ pub fn table_macro(attr: TokenStream, item: TokenStream) -> TokenStream {
                     .map(|x| x.into())
                     .collect()
                 }
+
+                /// Rows within `range` in key order, read in batches.
+                pub fn #fn_iter<R>(
+                    &self,
+                    range: R,
+                    options: interstice_sdk::ScanOptions,
+                ) -> impl Iterator<Item = #struct_ident> + use<R, Caps>
+                where
+                    R: std::ops::RangeBounds<#index_ty_ident>,
+                    Caps: interstice_sdk::CanRead<#struct_ident>,
+                {
+                    let to_key = |value: #index_ty_ident| {
+                        TryInto::<interstice_sdk::IndexKey>::try_into(Into::<interstice_sdk::IntersticeValue>::into(value)).expect("Failed to convert IntersticeValue to IndexKey")
+                    };
+                    let query = interstice_sdk::host_calls::index_query_from_bounds(
+                        range.start_bound().cloned().map(to_key),
+                        range.end_bound().cloned().map(to_key),
+                    );
+                    interstice_sdk::host_calls::scan_iter(
+                        interstice_sdk::ModuleSelection::Current,
+                        #table_name.to_string(),
+                        Some(interstice_sdk::TableScanIndex {
+                            field_name: #index_name.to_string(),
+                            query,
+                        }),
+                        options,
+                    )
+                    .expect("Index scan failed")
+                    .map(|x| x.expect("Index scan failed").into())
+                }
             });
         }
     }
 
+    for index in &composite_indexes {
+        index_read_methods.push(composite_index_methods(struct_ident, &table_name, index));
+    }
+
+    let columns_struct = syn::Ident::new(&format!("{}Columns", struct_name), struct_ident.span());
+    let entry_names: Vec<String> = entry_fields.iter().map(|field| field.to_string()).collect();
+
     let read_table_imp = quote! {
+        /// Rows matching `predicate`, filtered by the host (using the table's
+        /// indexes when it can).
+        pub fn filter(
+            &self,
+            predicate: impl FnOnce(&#columns_struct) -> interstice_sdk::Filter,
+        ) -> interstice_sdk::TableQuery<#struct_ident, #columns_struct>
+        where
+            Caps: interstice_sdk::CanRead<#struct_ident>,
+        {
+            self.query().filter(predicate)
+        }
+
+        pub fn query(&self) -> interstice_sdk::TableQuery<#struct_ident, #columns_struct>
+        where
+            Caps: interstice_sdk::CanRead<#struct_ident>,
+        {
+            interstice_sdk::TableQuery::new(
+                interstice_sdk::ModuleSelection::Current,
+                #table_name_lit,
+                #columns_struct::new(),
+            )
+        }
+
         pub fn scan(&self) -> Vec<#struct_ident>
         where
             Caps: interstice_sdk::CanRead<#struct_ident>,
@@ -426,6 +677,33 @@ pub fn table_macro(attr: TokenStream, item: TokenStream) -> TokenStream {
                 .collect()
         }
 
+        /// Iterate the table's rows in primary key order, reading them from the host
+        /// in batches, so stopping early does not copy the rest of the table. Panics
+        /// when a batch can't be read, e.g. after the module was upgraded.
+        pub fn scan_iter(&self) -> impl Iterator<Item = #struct_ident> + use<Caps>
+        where
+            Caps: interstice_sdk::CanRead<#struct_ident>,
+        {
+            self.scan_iter_with(interstice_sdk::ScanOptions::default())
+        }
+
+        pub fn scan_iter_with(
+            &self,
+            options: interstice_sdk::ScanOptions,
+        ) -> impl Iterator<Item = #struct_ident> + use<Caps>
+        where
+            Caps: interstice_sdk::CanRead<#struct_ident>,
+        {
+            interstice_sdk::host_calls::scan_iter(
+                interstice_sdk::ModuleSelection::Current,
+                #table_name.to_string(),
+                None,
+                options,
+            )
+            .expect("Table scan failed")
+            .map(|x| x.expect("Table scan failed").into())
+        }
+
         pub fn get(&self, primary_key: #pk_type_ident) -> Option<#struct_ident>
         where
             Caps: interstice_sdk::CanRead<#struct_ident>,
@@ -439,6 +717,25 @@ pub fn table_macro(attr: TokenStream, item: TokenStream) -> TokenStream {
             .map(|row| row.into())
         }
 
+        /// Rows of a logged table as they were after its log entry `sequence`.
+        pub fn as_of(&self, sequence: u64) -> Result<Vec<#struct_ident>, String>
+        where
+            Caps: interstice_sdk::CanRead<#struct_ident>,
+        {
+            let (rows, _branches) =
+                interstice_sdk::host_calls::table_as_of(#table_name.to_string(), sequence)?;
+            Ok(rows.into_iter().map(|x| x.into()).collect())
+        }
+
+        /// Sequence number of the last log entry of a logged table, `None` for
+        /// other tables.
+        pub fn sequence(&self) -> Result<Option<u64>, String>
+        where
+            Caps: interstice_sdk::CanRead<#struct_ident>,
+        {
+            interstice_sdk::host_calls::table_sequence(#table_name.to_string())
+        }
+
         #(#index_read_methods)*
     };
 
@@ -570,10 +867,17 @@ pub fn table_macro(attr: TokenStream, item: TokenStream) -> TokenStream {
                 primary_key: interstice_sdk::FieldDef {
                     name: #pk_name.to_string(),
                     field_type: #pk_type.into(),
+                    default: None,
                 },
                 primary_key_auto_inc: #pk_auto_inc,
                 indexes: vec![#(#index_schemas),*],
                 persistence: #persistence,
+                limits: interstice_sdk::TableLimits {
+                    max_rows: #max_rows,
+                    max_bytes: #max_bytes,
+                },
+                migrations: vec![#(#migrations),*],
+                view: #view,
             }
         }
         #[interstice_sdk::init]
@@ -581,6 +885,27 @@ pub fn table_macro(attr: TokenStream, item: TokenStream) -> TokenStream {
             interstice_sdk::registry::register_table(#schema_fn);
         }
 
+        /// Columns of the table, to build `filter` and `order_by` queries.
+        pub struct #columns_struct {
+            pub #pk_ident: interstice_sdk::Column<#pk_type_ident>,
+            #(pub #entry_fields: interstice_sdk::Column<#entry_types>,)*
+        }
+
+        impl #columns_struct {
+            pub const fn new() -> Self {
+                Self {
+                    #pk_ident: interstice_sdk::Column::new(#pk_name),
+                    #(#entry_fields: interstice_sdk::Column::new(#entry_names),)*
+                }
+            }
+        }
+
+        impl Default for #columns_struct {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
         pub struct #table_edit_handle_struct<Caps>{
             _caps: std::marker::PhantomData<Caps>,
         }
@@ -691,3 +1016,218 @@ pub fn table_macro(attr: TokenStream, item: TokenStream) -> TokenStream {
     }
     .into()
 }
+
+/// `#[table(index(btree, unique, fields = [owner, created_at], name = "..."))]`.
+struct CompositeIndexSpec {
+    name: Option<String>,
+    fields: Vec<syn::Ident>,
+    unique: bool,
+    is_btree: bool,
+}
+
+struct CompositeIndex {
+    name: String,
+    fields: Vec<(syn::Ident, syn::Type)>,
+    unique: bool,
+    is_btree: bool,
+}
+
+fn parse_composite_index(list: &syn::MetaList) -> Result<CompositeIndexSpec, String> {
+    let args = list
+        .parse_args_with(syn::punctuated::Punctuated::<Meta, syn::Token![,]>::parse_terminated)
+        .map_err(|_| "Invalid #[table(index(...))] syntax".to_string())?;
+
+    let mut spec = CompositeIndexSpec {
+        name: None,
+        fields: Vec::new(),
+        unique: false,
+        is_btree: false,
+    };
+    let mut has_type = false;
+    for arg in args {
+        match arg {
+            Meta::Path(path) if path.is_ident("hash") => {
+                spec.is_btree = false;
+                has_type = true;
+            }
+            Meta::Path(path) if path.is_ident("btree") => {
+                spec.is_btree = true;
+                has_type = true;
+            }
+            Meta::Path(path) if path.is_ident("unique") => spec.unique = true,
+            Meta::NameValue(nv) if nv.path.is_ident("fields") => {
+                let syn::Expr::Array(array) = &nv.value else {
+                    return Err("#[table(index(fields = [...]))] expects a list of field names".into());
+                };
+                for elem in &array.elems {
+                    match elem {
+                        syn::Expr::Path(path) if path.path.get_ident().is_some() => {
+                            spec.fields.push(path.path.get_ident().unwrap().clone());
+                        }
+                        _ => {
+                            return Err("#[table(index(fields = [...]))] expects a list of field names".into());
+                        }
+                    }
+                }
+            }
+            Meta::NameValue(nv) if nv.path.is_ident("name") => match &nv.value {
+                syn::Expr::Lit(syn::ExprLit {
+                    lit: syn::Lit::Str(name),
+                    ..
+                }) => spec.name = Some(name.value()),
+                _ => return Err("#[table(index(name = ...))] expects a string literal".into()),
+            },
+            _ => {
+                return Err("Invalid #[table(index(...))] argument. Use hash or btree, unique, fields = [...] and name = \"...\"".into());
+            }
+        }
+    }
+
+    if !has_type {
+        return Err("#[table(index(...))] requires an index type (hash or btree)".into());
+    }
+    if spec.fields.len() < 2 {
+        return Err("#[table(index(...))] needs at least two fields; use #[index(...)] on the field for single-field indexes".into());
+    }
+    Ok(spec)
+}
+
+/// Typed accessors of a composite index: a lookup on the full key and, for btree
+/// indexes, scans on every leading prefix of the key, optionally bounded on the
+/// field following the prefix.
+fn composite_index_methods(
+    struct_ident: &syn::Ident,
+    table_name: &str,
+    index: &CompositeIndex,
+) -> proc_macro2::TokenStream {
+    let span = struct_ident.span();
+    let index_name = &index.name;
+    let to_key = |value: proc_macro2::TokenStream| {
+        quote! {
+            TryInto::<interstice_sdk::IndexKey>::try_into(Into::<interstice_sdk::IntersticeValue>::into(#value)).expect("Failed to convert IntersticeValue to IndexKey")
+        }
+    };
+    let scan = |query: proc_macro2::TokenStream| {
+        quote! {
+            interstice_sdk::host_calls::scan_index(
+                interstice_sdk::ModuleSelection::Current,
+                #table_name.to_string(),
+                #index_name.to_string(),
+                #query,
+            )
+            .expect("Index scan failed")
+            .into_iter()
+            .map(|x| x.into())
+            .collect()
+        }
+    };
+
+    let idents: Vec<&syn::Ident> = index.fields.iter().map(|(ident, _)| ident).collect();
+    let types: Vec<&syn::Type> = index.fields.iter().map(|(_, ty)| ty).collect();
+    let keys: Vec<_> = idents.iter().map(|ident| to_key(quote! { #ident })).collect();
+
+    let fn_eq = syn::Ident::new(&format!("scan_by_{}_eq", index_name), span);
+    let eq_scan = scan(quote! {
+        interstice_sdk::IndexQuery::Eq(interstice_sdk::IndexKey::Tuple(vec![#(#keys),*]))
+    });
+    let mut methods = vec![quote! {
+        pub fn #fn_eq(&self, #(#idents: #types),*) -> Vec<#struct_ident>
+        where
+            Caps: interstice_sdk::CanRead<#struct_ident>,
+        {
+            #eq_scan
+        }
+    }];
+
+    if index.unique {
+        let fn_get = syn::Ident::new(&format!("get_by_{}", index_name), span);
+        methods.push(quote! {
+            pub fn #fn_get(&self, #(#idents: #types),*) -> Option<#struct_ident>
+            where
+                Caps: interstice_sdk::CanRead<#struct_ident>,
+            {
+                self.#fn_eq(#(#idents),*).into_iter().next()
+            }
+        });
+    }
+
+    if index.is_btree {
+        for prefix_len in 1..index.fields.len() {
+            let prefix_idents = &idents[..prefix_len];
+            let prefix_types = &types[..prefix_len];
+            let prefix_keys = &keys[..prefix_len];
+            let next_type = types[prefix_len];
+            let prefix_name = prefix_idents
+                .iter()
+                .map(|ident| ident.to_string())
+                .collect::<Vec<_>>()
+                .join("_");
+            let fn_prefix = syn::Ident::new(&format!("scan_by_{}_with_{}", index_name, prefix_name), span);
+            let fn_prefix_range = syn::Ident::new(
+                &format!("scan_by_{}_with_{}_range", index_name, prefix_name),
+                span,
+            );
+            let min_key = to_key(quote! { min });
+            let max_key = to_key(quote! { max });
+            let prefix_scan = scan(quote! {
+                interstice_sdk::IndexQuery::Prefix {
+                    prefix: vec![#(#prefix_keys),*],
+                    min: None,
+                    max: None,
+                    include_min: false,
+                    include_max: false,
+                }
+            });
+            let prefix_range_scan = scan(quote! {
+                interstice_sdk::IndexQuery::Prefix {
+                    prefix: vec![#(#prefix_keys),*],
+                    min: min.map(|min| #min_key),
+                    max: max.map(|max| #max_key),
+                    include_min,
+                    include_max,
+                }
+            });
+            methods.push(quote! {
+                pub fn #fn_prefix(&self, #(#prefix_idents: #prefix_types),*) -> Vec<#struct_ident>
+                where
+                    Caps: interstice_sdk::CanRead<#struct_ident>,
+                {
+                    #prefix_scan
+                }
+
+                pub fn #fn_prefix_range(
+                    &self,
+                    #(#prefix_idents: #prefix_types,)*
+                    min: Option<#next_type>,
+                    max: Option<#next_type>,
+                    include_min: bool,
+                    include_max: bool,
+                ) -> Vec<#struct_ident>
+                where
+                    Caps: interstice_sdk::CanRead<#struct_ident>,
+                {
+                    #prefix_range_scan
+                }
+            });
+        }
+    }
+
+    quote! { #(#methods)* }
+}
+
+/// Whether the field is marked `#[interstice(default)]`, which older rows lacking
+/// it are migrated with.
+fn has_interstice_default(field: &syn::Field) -> bool {
+    let mut default = false;
+    for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("interstice")) {
+        let _ = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("default") {
+                default = true;
+            } else if meta.input.peek(syn::Token![=]) {
+                meta.value()?.parse::<syn::Expr>()?;
+            }
+            Ok(())
+        });
+    }
+    default
+}
@@ -5,7 +5,10 @@ mod reducer;
 mod table;
 mod type_definition;
 
-use interstice_sdk_core::module_toml::{module_schema_from_toml_str, node_schema_from_toml_str};
+use interstice_sdk_core::module_toml::{
+    module_schema_from_toml_str, module_version_requirement_from_toml_str,
+    node_schema_from_toml_str,
+};
 use node::{get_current_node_code, get_node_code};
 use proc_macro2::TokenStream;
 use quote::quote;
@@ -33,13 +36,26 @@ pub fn generate_bindings() {
                 match module_schema_from_toml_str(&content) {
                     Ok(module_schema) => {
                         let module_name = module_schema.name.clone();
-                        let major = module_schema.version.major;
-                        let minor = module_schema.version.minor;
-                        let patch = module_schema.version.patch;
+                        let version_requirement = module_version_requirement_from_toml_str(&content)
+                            .unwrap_or_else(|err| {
+                                panic!("Invalid binding {}: {}", path.display(), err.message())
+                            })
+                            .unwrap_or_else(|| module_schema.version.clone().into())
+                            .to_string();
+                        let bound_schema = interstice_abi::encode(&module_schema.clone().to_public())
+                            .expect("Couldn't encode bound module schema");
                         module_dependency_entries.push(quote! {
                             interstice_sdk::ModuleDependency {
                                 module_name: #module_name.to_string(),
-                                version: interstice_sdk::Version { major: #major, minor: #minor, patch: #patch },
+                                version: #version_requirement.parse().unwrap(),
+                                bound_schema: Some(Box::new(
+                                    interstice_sdk::decode(&[#(#bound_schema),*]).map_err(|err| {
+                                        format!(
+                                            "Couldn't decode the bound schema of module '{}': {}",
+                                            #module_name, err
+                                        )
+                                    })?,
+                                )),
                             }
                         });
                         modules_schema.push(module_schema);
@@ -132,10 +148,10 @@ pub fn generate_bindings() {
         }
 
         #[allow(non_snake_case)]
-        pub fn __GET_INTERSTICE_MODULE_DEPENDENCIES() -> Vec<interstice_sdk::ModuleDependency> {
-            vec![
+        pub fn __GET_INTERSTICE_MODULE_DEPENDENCIES() -> Result<Vec<interstice_sdk::ModuleDependency>, String> {
+            Ok(vec![
                 #(#module_dependency_entries),*
-            ]
+            ])
         }
 
         #[allow(non_snake_case)]
@@ -42,7 +42,7 @@ pub fn get_module_code(module_schema: ModuleSchema, node_selection: NodeSelectio
     let type_definition_items: Vec<TokenStream> = module_schema
         .type_definitions
         .values()
-        .map(get_type_definition_code)
+        .map(|type_def| get_type_definition_code(type_def, &module_schema.type_definitions))
         .collect();
 
     let module_tables_name = module_tables_ident.to_string();
@@ -1,7 +1,9 @@
 use interstice_abi::{NodeSelection, QuerySchema};
 use proc_macro2::{Span, TokenStream};
 use quote::quote;
-use syn::{Ident, LitStr, Type};
+use syn::{Ident, LitStr};
+
+use crate::bindings::type_definition::rust_type;
 
 pub fn get_query_code(
     module_name: &String,
@@ -12,16 +14,14 @@ pub fn get_query_code(
     let method_name = Ident::new(&query_schema.name, span);
     let module_name_lit = LitStr::new(module_name, span);
     let query_name_lit = LitStr::new(&query_schema.name, span);
-    let return_type: Type = syn::parse_str(&query_schema.return_type.to_string())
-        .expect("Failed to parse query return type");
+    let return_type = rust_type(&query_schema.return_type);
 
     let argument_defs: Vec<TokenStream> = query_schema
         .arguments
         .iter()
         .map(|arg| {
             let arg_ident = Ident::new(&arg.name, span);
-            let arg_type: Type = syn::parse_str(&arg.field_type.to_string())
-                .expect("Failed to parse query argument type");
+            let arg_type = rust_type(&arg.field_type);
             quote! { #arg_ident: #arg_type }
         })
         .collect();
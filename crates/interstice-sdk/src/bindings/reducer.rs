@@ -1,7 +1,9 @@
 use interstice_abi::{NodeSelection, ReducerSchema};
 use proc_macro2::{Span, TokenStream};
 use quote::quote;
-use syn::{Ident, LitStr, Type};
+use syn::{Ident, LitStr};
+
+use crate::bindings::type_definition::rust_type;
 
 pub fn get_reducer_code(
     module_name: &String,
@@ -18,8 +20,7 @@ pub fn get_reducer_code(
         .iter()
         .map(|arg| {
             let arg_ident = Ident::new(&arg.name, span);
-            let arg_type: Type = syn::parse_str(&arg.field_type.to_string())
-                .expect("Failed to parse reducer argument type");
+            let arg_type = rust_type(&arg.field_type);
             quote! { #arg_ident: #arg_type }
         })
         .collect();
@@ -33,22 +34,31 @@ pub fn get_reducer_code(
         })
         .collect();
 
-    let node_selection_tokens = match node_selection {
-        NodeSelection::Current => quote! { interstice_sdk::NodeSelection::Current },
+    // Reducers of other nodes run after the call returns: their bindings hand back
+    // the call to follow its outcome with.
+    match node_selection {
+        NodeSelection::Current => quote! {
+            pub fn #method_name(&self, #(#argument_defs),*) -> Result<(), String> {
+                interstice_sdk::host_calls::call_reducer(
+                    interstice_sdk::NodeSelection::Current,
+                    interstice_sdk::ModuleSelection::Other(#module_name_lit.to_string()),
+                    #reducer_name_lit.to_string(),
+                    interstice_sdk::IntersticeValue::Vec(vec![#(#argument_values),*]),
+                )
+            }
+        },
         NodeSelection::Other(node_name) => {
             let node_name_lit = LitStr::new(node_name, span);
-            quote! { interstice_sdk::NodeSelection::Other(#node_name_lit.to_string()) }
-        }
-    };
-
-    quote! {
-        pub fn #method_name(&self, #(#argument_defs),*) -> Result<(), String> {
-            interstice_sdk::host_calls::call_reducer(
-                #node_selection_tokens,
-                interstice_sdk::ModuleSelection::Other(#module_name_lit.to_string()),
-                #reducer_name_lit.to_string(),
-                interstice_sdk::IntersticeValue::Vec(vec![#(#argument_values),*]),
-            )
+            quote! {
+                pub fn #method_name(&self, #(#argument_defs),*) -> Result<interstice_sdk::ReducerCall, String> {
+                    interstice_sdk::host_calls::call_remote_reducer(
+                        #node_name_lit.to_string(),
+                        interstice_sdk::ModuleSelection::Other(#module_name_lit.to_string()),
+                        #reducer_name_lit.to_string(),
+                        interstice_sdk::IntersticeValue::Vec(vec![#(#argument_values),*]),
+                    )
+                }
+            }
         }
     }
 }
@@ -1,8 +1,10 @@
-use interstice_abi::TableSchema;
+use interstice_abi::{IndexSchema, TableSchema};
 use proc_macro2::TokenStream;
 use quote::{format_ident, quote};
 use syn::{self, LitStr, Type};
 
+use crate::bindings::type_definition::rust_type;
+
 pub fn get_table_code(
     table_schema: TableSchema,
     module_tables_name: &str,
@@ -39,6 +41,23 @@ pub fn get_table_code(
     };
     let module_sel_ts = quote! { interstice_sdk::ModuleSelection::Other(#module_lit.to_string()) };
 
+    // Tables of other nodes are local replicas, which can report how far behind they are.
+    let replica_status_method = if ref_node_name == "current" {
+        quote! {}
+    } else {
+        let node_lit = LitStr::new(ref_node_name, span);
+        quote! {
+            /// Replication status of this replica against its source table.
+            pub fn replica_status(&self) -> Result<interstice_sdk::ReplicaStatus, String> {
+                interstice_sdk::host_calls::replica_status(
+                    #node_lit.to_string(),
+                    #module_lit.to_string(),
+                    #schema_table_name_lit.to_string(),
+                )
+            }
+        }
+    };
+
     let into_row_entries: Vec<TokenStream> = table_schema
         .fields
         .iter()
@@ -58,23 +77,47 @@ pub fn get_table_code(
         .collect();
 
     let primary_key = syn::Ident::new(&table_schema.primary_key.name, span);
-    let primary_key_type: Type = syn::parse_str(&table_schema.primary_key.field_type.to_string())
-        .expect("Failed to parse primary key type");
+    let primary_key_type = rust_type(&table_schema.primary_key.field_type);
+    let primary_key_name_lit = LitStr::new(&table_schema.primary_key.name, span);
+
+    let columns_struct_name = format_ident!("{}Columns", table_schema.type_name);
+    let column_idents: Vec<syn::Ident> = table_schema
+        .fields
+        .iter()
+        .map(|field| syn::Ident::new(&field.name, span))
+        .collect();
+    let column_names: Vec<LitStr> = table_schema
+        .fields
+        .iter()
+        .map(|field| LitStr::new(&field.name, span))
+        .collect();
+    let column_types: Vec<Type> = table_schema
+        .fields
+        .iter()
+        .map(|field| rust_type(&field.field_type))
+        .collect();
 
     let index_methods: Vec<TokenStream> = table_schema
         .indexes
         .iter()
         .map(|index| {
+            if index.is_composite() {
+                return get_composite_index_code(
+                    &table_schema,
+                    index,
+                    &table_struct_name,
+                    &table_name_lit,
+                    &module_selection_tokens,
+                );
+            }
             let index_name = &index.field_name;
             let index_name_lit = LitStr::new(index_name, span);
-            let index_field_type_str = table_schema
+            let index_type = table_schema
                 .fields
                 .iter()
                 .find(|f| f.name == *index_name)
-                .map(|f| f.field_type.to_string())
+                .map(|f| rust_type(&f.field_type))
                 .expect("Index field type not found in table fields");
-            let index_type: Type =
-                syn::parse_str(&index_field_type_str).expect("Failed to parse index field type");
 
             let fn_eq = format_ident!("scan_by_{}_eq", index_name);
             let fn_get = format_ident!("get_by_{}", index_name);
@@ -83,6 +126,7 @@ pub fn get_table_code(
             let fn_gt = format_ident!("scan_by_{}_gt", index_name);
             let fn_gte = format_ident!("scan_by_{}_gte", index_name);
             let fn_range = format_ident!("scan_by_{}_range", index_name);
+            let fn_iter = format_ident!("iter_by_{}", index_name);
 
             let unique_method = if index.unique {
                 quote! {
@@ -203,6 +247,37 @@ pub fn get_table_code(
                         .map(|x| x.try_into().unwrap())
                         .collect()
                     }
+
+                    /// Rows within `range` in key order, read in batches.
+                    pub fn #fn_iter<R>(
+                        &self,
+                        range: R,
+                        options: interstice_sdk::ScanOptions,
+                    ) -> impl Iterator<Item = #table_struct_name> + use<R, Caps>
+                    where
+                        R: std::ops::RangeBounds<#index_type>,
+                        Caps: interstice_sdk::CanRead<#table_struct_name>,
+                    {
+                        let to_key = |value: #index_type| {
+                            TryInto::<interstice_sdk::IndexKey>::try_into(Into::<interstice_sdk::IntersticeValue>::into(value))
+                                .expect("Failed to convert IntersticeValue to IndexKey")
+                        };
+                        let query = interstice_sdk::host_calls::index_query_from_bounds(
+                            range.start_bound().cloned().map(to_key),
+                            range.end_bound().cloned().map(to_key),
+                        );
+                        interstice_sdk::host_calls::scan_iter(
+                            #module_selection_tokens,
+                            #table_name_lit.to_string(),
+                            Some(interstice_sdk::TableScanIndex {
+                                field_name: #index_name_lit.to_string(),
+                                query,
+                            }),
+                            options,
+                        )
+                        .expect("Index scan failed")
+                        .map(|x| x.expect("Index scan failed").try_into().unwrap())
+                    }
                 }
             } else {
                 quote! {}
@@ -280,6 +355,27 @@ pub fn get_table_code(
             _caps: std::marker::PhantomData<Caps>,
         }
 
+        /// Columns of the table, to build `filter` and `order_by` queries.
+        pub struct #columns_struct_name {
+            pub #primary_key: interstice_sdk::Column<#primary_key_type>,
+            #(pub #column_idents: interstice_sdk::Column<#column_types>,)*
+        }
+
+        impl #columns_struct_name {
+            pub const fn new() -> Self {
+                Self {
+                    #primary_key: interstice_sdk::Column::new(#primary_key_name_lit),
+                    #(#column_idents: interstice_sdk::Column::new(#column_names),)*
+                }
+            }
+        }
+
+        impl Default for #columns_struct_name {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
         impl Into<interstice_sdk::Row> for #table_struct_name {
             fn into(self) -> interstice_sdk::Row {
                 interstice_sdk::Row {
@@ -302,6 +398,29 @@ pub fn get_table_code(
         }
 
         impl<Caps> #table_handle_struct_name<Caps> {
+            /// Rows matching `predicate`, filtered by the host (using the table's
+            /// indexes when it can).
+            pub fn filter(
+                &self,
+                predicate: impl FnOnce(&#columns_struct_name) -> interstice_sdk::Filter,
+            ) -> interstice_sdk::TableQuery<#table_struct_name, #columns_struct_name>
+            where
+                Caps: interstice_sdk::CanRead<#table_struct_name>,
+            {
+                self.query().filter(predicate)
+            }
+
+            pub fn query(&self) -> interstice_sdk::TableQuery<#table_struct_name, #columns_struct_name>
+            where
+                Caps: interstice_sdk::CanRead<#table_struct_name>,
+            {
+                interstice_sdk::TableQuery::new(
+                    #module_selection_tokens,
+                    #table_name_lit,
+                    #columns_struct_name::new(),
+                )
+            }
+
             pub fn scan(&self) -> Vec<#table_struct_name>
             where
                 Caps: interstice_sdk::CanRead<#table_struct_name>,
@@ -316,6 +435,33 @@ pub fn get_table_code(
                 .collect()
             }
 
+            /// Iterate the table's rows in primary key order, reading them from the host
+            /// in batches, so stopping early does not copy the rest of the table. Panics
+            /// when a batch can't be read, e.g. after the module was upgraded.
+            pub fn scan_iter(&self) -> impl Iterator<Item = #table_struct_name> + use<Caps>
+            where
+                Caps: interstice_sdk::CanRead<#table_struct_name>,
+            {
+                self.scan_iter_with(interstice_sdk::ScanOptions::default())
+            }
+
+            pub fn scan_iter_with(
+                &self,
+                options: interstice_sdk::ScanOptions,
+            ) -> impl Iterator<Item = #table_struct_name> + use<Caps>
+            where
+                Caps: interstice_sdk::CanRead<#table_struct_name>,
+            {
+                interstice_sdk::host_calls::scan_iter(
+                    #module_selection_tokens,
+                    #table_name_lit.to_string(),
+                    None,
+                    options,
+                )
+                .expect("Table scan failed")
+                .map(|x| x.expect("Table scan failed").try_into().unwrap())
+            }
+
             pub fn get(&self, primary_key: #primary_key_type) -> Option<#table_struct_name>
             where
                 Caps: interstice_sdk::CanRead<#table_struct_name>,
@@ -331,6 +477,8 @@ pub fn get_table_code(
             }
 
             #(#index_methods)*
+
+            #replica_status_method
         }
 
         impl<Caps> IntoIterator for #table_handle_struct_name<Caps>
@@ -356,3 +504,138 @@ pub fn get_table_code(
         }
     }
 }
+
+/// Typed accessors of a composite index: a lookup on the full key and, for btree
+/// indexes, scans on every leading prefix of the key, optionally bounded on the
+/// field following the prefix.
+fn get_composite_index_code(
+    table_schema: &TableSchema,
+    index: &IndexSchema,
+    table_struct_name: &syn::Ident,
+    table_name_lit: &LitStr,
+    module_selection_tokens: &TokenStream,
+) -> TokenStream {
+    let span = proc_macro2::Span::call_site();
+    let index_name = &index.field_name;
+    let index_name_lit = LitStr::new(index_name, span);
+    let to_key = |value: TokenStream| {
+        quote! {
+            TryInto::<interstice_sdk::IndexKey>::try_into(Into::<interstice_sdk::IntersticeValue>::into(#value))
+                .expect("Failed to convert IntersticeValue to IndexKey")
+        }
+    };
+    let scan = |query: TokenStream| {
+        quote! {
+            interstice_sdk::host_calls::scan_index(
+                #module_selection_tokens,
+                #table_name_lit.to_string(),
+                #index_name_lit.to_string(),
+                #query,
+            )
+            .expect("Index scan failed")
+            .into_iter()
+            .map(|x| x.try_into().unwrap())
+            .collect()
+        }
+    };
+
+    let idents: Vec<syn::Ident> = index
+        .fields
+        .iter()
+        .map(|field| syn::Ident::new(field, span))
+        .collect();
+    let types: Vec<Type> = index
+        .fields
+        .iter()
+        .map(|field_name| {
+            table_schema
+                .fields
+                .iter()
+                .find(|f| f.name == *field_name)
+                .map(|f| rust_type(&f.field_type))
+                .expect("Index field type not found in table fields")
+        })
+        .collect();
+    let keys: Vec<TokenStream> = idents.iter().map(|ident| to_key(quote! { #ident })).collect();
+
+    let fn_eq = format_ident!("scan_by_{}_eq", index_name);
+    let eq_scan = scan(quote! {
+        interstice_sdk::IndexQuery::Eq(interstice_sdk::IndexKey::Tuple(vec![#(#keys),*]))
+    });
+    let mut methods = vec![quote! {
+        pub fn #fn_eq(&self, #(#idents: #types),*) -> Vec<#table_struct_name>
+        where
+            Caps: interstice_sdk::CanRead<#table_struct_name>,
+        {
+            #eq_scan
+        }
+    }];
+
+    if index.unique {
+        let fn_get = format_ident!("get_by_{}", index_name);
+        methods.push(quote! {
+            pub fn #fn_get(&self, #(#idents: #types),*) -> Option<#table_struct_name>
+            where
+                Caps: interstice_sdk::CanRead<#table_struct_name>,
+            {
+                self.#fn_eq(#(#idents),*).into_iter().next()
+            }
+        });
+    }
+
+    if index.index_type == interstice_abi::IndexType::BTree {
+        for prefix_len in 1..idents.len() {
+            let prefix_idents = &idents[..prefix_len];
+            let prefix_types = &types[..prefix_len];
+            let prefix_keys = &keys[..prefix_len];
+            let next_type = &types[prefix_len];
+            let prefix_name = index.fields[..prefix_len].join("_");
+            let fn_prefix = format_ident!("scan_by_{}_with_{}", index_name, prefix_name);
+            let fn_prefix_range = format_ident!("scan_by_{}_with_{}_range", index_name, prefix_name);
+            let min_key = to_key(quote! { min });
+            let max_key = to_key(quote! { max });
+            let prefix_scan = scan(quote! {
+                interstice_sdk::IndexQuery::Prefix {
+                    prefix: vec![#(#prefix_keys),*],
+                    min: None,
+                    max: None,
+                    include_min: false,
+                    include_max: false,
+                }
+            });
+            let prefix_range_scan = scan(quote! {
+                interstice_sdk::IndexQuery::Prefix {
+                    prefix: vec![#(#prefix_keys),*],
+                    min: min.map(|min| #min_key),
+                    max: max.map(|max| #max_key),
+                    include_min,
+                    include_max,
+                }
+            });
+            methods.push(quote! {
+                pub fn #fn_prefix(&self, #(#prefix_idents: #prefix_types),*) -> Vec<#table_struct_name>
+                where
+                    Caps: interstice_sdk::CanRead<#table_struct_name>,
+                {
+                    #prefix_scan
+                }
+
+                pub fn #fn_prefix_range(
+                    &self,
+                    #(#prefix_idents: #prefix_types,)*
+                    min: Option<#next_type>,
+                    max: Option<#next_type>,
+                    include_min: bool,
+                    include_max: bool,
+                ) -> Vec<#table_struct_name>
+                where
+                    Caps: interstice_sdk::CanRead<#table_struct_name>,
+                {
+                    #prefix_range_scan
+                }
+            });
+        }
+    }
+
+    quote! { #(#methods)* }
+}
@@ -1,43 +1,78 @@
-use interstice_abi::IntersticeTypeDef;
+use std::collections::HashMap;
+
+use interstice_abi::{IntersticeType, IntersticeTypeDef};
 use proc_macro2::Span;
 use quote::quote;
 use syn::{Ident, Type};
 
-pub fn get_type_definition_code(type_def: &IntersticeTypeDef) -> proc_macro2::TokenStream {
+pub fn get_type_definition_code(
+    type_def: &IntersticeTypeDef,
+    type_definitions: &HashMap<String, IntersticeTypeDef>,
+) -> proc_macro2::TokenStream {
     let span = Span::call_site();
+    let name = type_def.get_name();
+    if is_variant_struct(name) {
+        // Defined inline, as the fields of its enum variant.
+        return quote! {};
+    }
+    let name_ident = Ident::new(&type_ident_name(name), span);
+    let rename = (name_ident != name).then(|| quote! { #[interstice(rename = #name)] });
+
     let tokens = match type_def {
-        IntersticeTypeDef::Struct { name, fields } => {
-            let name_ident = Ident::new(name, span);
-            let field_defs = fields.iter().map(|field| {
-                let field_ident = Ident::new(&field.name, span);
-                let field_type: Type = syn::parse_str(&field.field_type.to_string())
-                    .expect("Failed to parse struct field type");
-                quote! { pub #field_ident: #field_type }
-            });
+        IntersticeTypeDef::Struct { fields, .. } => {
+            let is_tuple_struct = !fields.is_empty()
+                && fields
+                    .iter()
+                    .enumerate()
+                    .all(|(index, field)| field.name == index.to_string());
+            if is_tuple_struct {
+                let field_types = fields.iter().map(|field| rust_type(&field.field_type));
+                quote! {
+                    #[derive(interstice_sdk::interstice_abi_macros::IntersticeType)]
+                    #rename
+                    pub struct #name_ident(#(pub #field_types),*);
+                }
+            } else {
+                let field_defs = fields.iter().map(|field| {
+                    let field_ident = Ident::new(&field.name, span);
+                    let field_type = rust_type(&field.field_type);
+                    quote! { pub #field_ident: #field_type }
+                });
 
-            quote! {
-                #[derive(interstice_sdk::interstice_abi_macros::IntersticeType)]
-                pub struct #name_ident {
-                    #(#field_defs,)*
+                quote! {
+                    #[derive(interstice_sdk::interstice_abi_macros::IntersticeType)]
+                    #rename
+                    pub struct #name_ident {
+                        #(#field_defs,)*
+                    }
                 }
             }
         }
-        IntersticeTypeDef::Enum { name, variants } => {
-            let name_ident = Ident::new(name, span);
+        IntersticeTypeDef::Enum { variants, .. } => {
             let variant_defs = variants.iter().map(|variant| {
                 let variant_ident = Ident::new(&variant.name, span);
                 match &variant.field_type {
-                    interstice_abi::IntersticeType::Void => quote! { #variant_ident },
-                    interstice_abi::IntersticeType::Tuple(interstice_types) => {
-                        let tuple_types = interstice_types.iter().map(|t| {
-                            syn::parse_str::<Type>(&t.to_string())
-                                .expect("Failed to parse tuple variant type")
-                        });
+                    IntersticeType::Void => quote! { #variant_ident },
+                    IntersticeType::Tuple(interstice_types) => {
+                        let tuple_types = interstice_types.iter().map(rust_type);
                         quote! { #variant_ident(#(#tuple_types),*) }
                     }
+                    IntersticeType::Named(variant_struct)
+                        if *variant_struct == format!("{}::{}", name, variant.name) =>
+                    {
+                        let fields = match type_definitions.get(variant_struct) {
+                            Some(IntersticeTypeDef::Struct { fields, .. }) => fields.as_slice(),
+                            _ => &[],
+                        };
+                        let field_defs = fields.iter().map(|field| {
+                            let field_ident = Ident::new(&field.name, span);
+                            let field_type = rust_type(&field.field_type);
+                            quote! { #field_ident: #field_type }
+                        });
+                        quote! { #variant_ident { #(#field_defs),* } }
+                    }
                     field_type => {
-                        let inner_type: Type = syn::parse_str(&field_type.to_string())
-                            .expect("Failed to parse enum variant type");
+                        let inner_type = rust_type(field_type);
                         quote! { #variant_ident(#inner_type) }
                     }
                 }
@@ -45,6 +80,7 @@ pub fn get_type_definition_code(type_def: &IntersticeTypeDef) -> proc_macro2::To
 
             quote! {
                 #[derive(interstice_sdk::interstice_abi_macros::IntersticeType)]
+                #rename
                 pub enum #name_ident {
                     #(#variant_defs,)*
                 }
@@ -54,3 +90,44 @@ pub fn get_type_definition_code(type_def: &IntersticeTypeDef) -> proc_macro2::To
 
     tokens
 }
+
+/// Rust type of `ty` in the bindings.
+pub fn rust_type(ty: &IntersticeType) -> Type {
+    syn::parse_str(&binding_type(ty).to_string()).expect("Failed to parse binding type")
+}
+
+fn binding_type(ty: &IntersticeType) -> IntersticeType {
+    match ty {
+        IntersticeType::Vec(inner) => IntersticeType::Vec(Box::new(binding_type(inner))),
+        IntersticeType::Option(inner) => IntersticeType::Option(Box::new(binding_type(inner))),
+        IntersticeType::Tuple(types) => IntersticeType::Tuple(types.iter().map(binding_type).collect()),
+        IntersticeType::Map(key, value) => {
+            IntersticeType::Map(Box::new(binding_type(key)), Box::new(binding_type(value)))
+        }
+        IntersticeType::Named(name) => IntersticeType::Named(type_ident_name(name)),
+        other => other.clone(),
+    }
+}
+
+/// Whether `name` is the payload struct of a struct-like enum variant, named
+/// `Enum::Variant`.
+fn is_variant_struct(name: &str) -> bool {
+    name.contains("::") && !name.ends_with('>')
+}
+
+/// Identifier the type definition `name` has in the bindings. Instances of generic
+/// types are named after their type arguments, e.g. `PagePlayer` for `Page<Player>`.
+fn type_ident_name(name: &str) -> String {
+    if !name.contains('<') {
+        return name.to_string();
+    }
+    name.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().collect::<String>() + chars.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
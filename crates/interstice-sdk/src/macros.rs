@@ -77,31 +77,9 @@ macro_rules! interstice_module {
         interstice_module!(visibility: Private, authorities: [], replicated_tables: []);
     };
 
-    (visibility: $vis:ident) => {
-        interstice_module!(visibility: $vis, authorities: [], replicated_tables: []);
-    };
-
-    (authorities: [$($auth:ident),* $(,)?]) => {
-        interstice_module!(visibility: Private, authorities: [$($auth),*], replicated_tables: []);
-    };
-
-    (replicated_tables: [$($rep:literal),* $(,)?]) => {
-        interstice_module!(visibility: Private, authorities: [], replicated_tables: [$($rep),*]);
-    };
-
-    (visibility: $vis:ident, authorities: [$($auth:ident),* $(,)?]) => {
-        interstice_module!(visibility: $vis, authorities: [$($auth),*], replicated_tables: []);
-    };
-
-    (visibility: $vis:ident, replicated_tables: [$($rep:literal),* $(,)?]) => {
-        interstice_module!(visibility: $vis, authorities: [], replicated_tables: [$($rep),*]);
-    };
-
-    (authorities: [$($auth:ident),* $(,)?], replicated_tables: [$($rep:literal),* $(,)?]) => {
-        interstice_module!(visibility: Private, authorities: [$($auth),*], replicated_tables: [$($rep),*]);
-    };
-
-    (visibility: $vis:ident, authorities: [$($auth:ident),* $(,)?], replicated_tables: [$($rep:literal),* $(,)?]) => {
+    // Trailing `key: value` pairs are module options (see `@option` below), e.g.
+    // `interstice_module!(visibility: Public, fuel: 50_000_000, allow_roles: ["admin"])`.
+    (visibility: $vis:ident, authorities: [$($auth:ident),* $(,)?], replicated_tables: [$($rep:literal),* $(,)?] $(, $opt:ident: $val:tt)*) => {
         $(
             interstice_module!(@impl_authority $auth);
         )*
@@ -135,6 +113,16 @@ macro_rules! interstice_module {
         const __INTERSTICE_AUTHORITIES: &[interstice_abi::Authority] = &[
             $(interstice_abi::Authority::$auth),*
         ];
+        fn __interstice_module_options() -> (interstice_abi::ModuleLimits, interstice_abi::AccessPolicy) {
+            #[allow(unused_mut)]
+            let mut limits = interstice_abi::ModuleLimits::unlimited();
+            #[allow(unused_mut)]
+            let mut access = interstice_abi::AccessPolicy::default();
+            $(
+                interstice_module!(@option limits, access, $opt, $val);
+            )*
+            (limits, access)
+        }
 
         #[unsafe(no_mangle)]
         pub extern "C" fn interstice_describe() -> i64 {
@@ -165,18 +153,82 @@ macro_rules! interstice_module {
                 }
             }
 
+            let __interstice_module_dependencies = bindings::__GET_INTERSTICE_MODULE_DEPENDENCIES()
+                .unwrap_or_else(|error| panic!("{}", error));
+
+            let (__interstice_limits, __interstice_access) = __interstice_module_options();
             interstice_sdk::macros::describe_module(
                 __INTERSTICE_MODULE_NAME,
                 __INTERSTICE_MODULE_VERSION,
                 __INTERSTICE_VISIBILITY,
                 __INTERSTICE_AUTHORITIES,
-                bindings::__GET_INTERSTICE_MODULE_DEPENDENCIES(),
+                __interstice_module_dependencies,
                 __interstice_node_dependencies,
                 __interstice_replicated_tables,
+                __interstice_limits,
+                __interstice_access,
             )
         }
 
     };
+
+    // Fewer sections, ordered from the most specific: an option value can be any
+    // token tree, so a shorter arm would otherwise take a section for an option.
+    (visibility: $vis:ident, authorities: [$($auth:ident),* $(,)?] $(, $opt:ident: $val:tt)*) => {
+        interstice_module!(visibility: $vis, authorities: [$($auth),*], replicated_tables: [] $(, $opt: $val)*);
+    };
+
+    (visibility: $vis:ident, replicated_tables: [$($rep:literal),* $(,)?] $(, $opt:ident: $val:tt)*) => {
+        interstice_module!(visibility: $vis, authorities: [], replicated_tables: [$($rep),*] $(, $opt: $val)*);
+    };
+
+    (authorities: [$($auth:ident),* $(,)?], replicated_tables: [$($rep:literal),* $(,)?] $(, $opt:ident: $val:tt)*) => {
+        interstice_module!(visibility: Private, authorities: [$($auth),*], replicated_tables: [$($rep),*] $(, $opt: $val)*);
+    };
+
+    (visibility: $vis:ident $(, $opt:ident: $val:tt)*) => {
+        interstice_module!(visibility: $vis, authorities: [], replicated_tables: [] $(, $opt: $val)*);
+    };
+
+    (authorities: [$($auth:ident),* $(,)?] $(, $opt:ident: $val:tt)*) => {
+        interstice_module!(visibility: Private, authorities: [$($auth),*], replicated_tables: [] $(, $opt: $val)*);
+    };
+
+    (replicated_tables: [$($rep:literal),* $(,)?] $(, $opt:ident: $val:tt)*) => {
+        interstice_module!(visibility: Private, authorities: [], replicated_tables: [$($rep),*] $(, $opt: $val)*);
+    };
+
+    // Options only. Matched last so sections are never taken for options.
+    ($($opt:ident: $val:tt),+) => {
+        interstice_module!(visibility: Private, authorities: [], replicated_tables: [] $(, $opt: $val)*);
+    };
+
+    // Module options
+
+    (@option $limits:ident, $access:ident, fuel, $val:literal) => {
+        $limits.fuel = Some($val);
+    };
+
+    (@option $limits:ident, $access:ident, max_memory_mb, $val:literal) => {
+        $limits.max_memory_mb = Some($val);
+    };
+
+    (@option $limits:ident, $access:ident, allow_nodes, [$($node:literal),* $(,)?]) => {
+        $access.nodes = vec![$($node.to_string()),*];
+    };
+
+    (@option $limits:ident, $access:ident, allow_roles, [$($role:literal),* $(,)?]) => {
+        $access.roles = vec![$($role.to_string()),*];
+    };
+
+    (@option $limits:ident, $access:ident, access_policy, $val:literal) => {
+        $access.policy = Some($val.to_string());
+    };
+
+    (@option $limits:ident, $access:ident, $other:ident, $val:tt) => {
+        compile_error!(concat!("unknown interstice_module! option `", stringify!($other), "`"));
+    };
+
     // Authorites calls
 
     (@impl_authority Input) => {
@@ -236,6 +288,7 @@ macro_rules! interstice_module {
     };
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn describe_module(
     name: &str,
     version: &str,
@@ -244,6 +297,8 @@ pub fn describe_module(
     module_dependencies: Vec<ModuleDependency>,
     node_dependencies: Vec<NodeDependency>,
     replicated_tables: Vec<interstice_abi::ReplicatedTableSchema>,
+    limits: interstice_abi::ModuleLimits,
+    access: interstice_abi::AccessPolicy,
 ) -> i64 {
     let reducers = interstice_sdk_core::registry::collect_reducers();
     let queries = interstice_sdk_core::registry::collect_queries();
@@ -284,6 +339,8 @@ pub fn describe_module(
         module_dependencies,
         node_dependencies,
         replicated_tables,
+        limits,
+        access,
     };
 
     let bytes = encode(&schema).unwrap();
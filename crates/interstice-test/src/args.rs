@@ -0,0 +1,40 @@
+use interstice_core::interstice_abi::IntersticeValue;
+
+/// Arguments of a reducer or query call: `()` for none, a tuple otherwise
+/// (`(name,)` for a single argument).
+pub trait CallArgs {
+    fn into_args(self) -> Vec<IntersticeValue>;
+}
+
+impl CallArgs for () {
+    fn into_args(self) -> Vec<IntersticeValue> {
+        Vec::new()
+    }
+}
+
+impl CallArgs for Vec<IntersticeValue> {
+    fn into_args(self) -> Vec<IntersticeValue> {
+        self
+    }
+}
+
+macro_rules! impl_call_args {
+    ($($arg:ident),+) => {
+        impl<$($arg: Into<IntersticeValue>),+> CallArgs for ($($arg,)+) {
+            #[allow(non_snake_case)]
+            fn into_args(self) -> Vec<IntersticeValue> {
+                let ($($arg,)+) = self;
+                vec![$($arg.into()),+]
+            }
+        }
+    };
+}
+
+impl_call_args!(A);
+impl_call_args!(A, B);
+impl_call_args!(A, B, C);
+impl_call_args!(A, B, C, D);
+impl_call_args!(A, B, C, D, E);
+impl_call_args!(A, B, C, D, E, F);
+impl_call_args!(A, B, C, D, E, F, G);
+impl_call_args!(A, B, C, D, E, F, G, H);
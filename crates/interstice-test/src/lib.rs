@@ -0,0 +1,207 @@
+//! Test Interstice modules from `cargo test`, without a node or a window.
+//!
+//! A [`TestNode`] runs compiled modules in-process on a
+//! [`TestRuntime`](interstice_core::testing::TestRuntime): tables live in memory,
+//! nothing listens on the network, GPU calls fail and audio is discarded. Calls
+//! return once everything they triggered has run, and the clock only moves when
+//! advanced, so schedules and ticks run exactly when a test says so.
+//!
+//! ```ignore
+//! #[tokio::test]
+//! async fn greets() {
+//!     let node = TestNode::new().unwrap();
+//!     let hello = node
+//!         .load_module(build_module(env!("CARGO_MANIFEST_DIR")).unwrap())
+//!         .await
+//!         .unwrap();
+//!     hello.call("hello", ("Ada".to_string(),)).await.unwrap();
+//!     let greetings: Vec<Greetings> = hello.rows("greetings").unwrap();
+//!     assert_eq!(greetings[0].greeting, "Hello, Ada!");
+//! }
+//! ```
+
+mod args;
+mod wasm;
+
+pub use args::CallArgs;
+pub use interstice_core::IntersticeError;
+pub use interstice_core::interstice_abi::{IntersticeValue, ModuleSchema, Row, ScheduleInfo};
+pub use interstice_core::testing::TableChange;
+pub use wasm::build_module;
+
+use interstice_core::testing::TestRuntime;
+use std::fmt::Debug;
+use std::time::Duration;
+
+pub struct TestNode {
+    runtime: TestRuntime,
+}
+
+impl TestNode {
+    /// Start a node whose clock reads the current time. Must be called from within
+    /// a Tokio runtime, e.g. in a `#[tokio::test]`.
+    pub fn new() -> Result<Self, IntersticeError> {
+        Ok(Self {
+            runtime: TestRuntime::new()?,
+        })
+    }
+
+    /// Start a node whose clock reads `unix_ms`, for tests depending on the date.
+    pub fn starting_at(unix_ms: u64) -> Result<Self, IntersticeError> {
+        Ok(Self {
+            runtime: TestRuntime::starting_at(unix_ms)?,
+        })
+    }
+
+    /// Load a compiled module, once its `init` and `load` reducers ran. Modules
+    /// must be loaded after the modules they depend on.
+    pub async fn load_module(
+        &self,
+        wasm_binary: impl AsRef<[u8]>,
+    ) -> Result<TestModule<'_>, IntersticeError> {
+        let schema = self.runtime.load_module(wasm_binary.as_ref()).await?;
+        Ok(self.module(&schema.name))
+    }
+
+    /// A loaded module.
+    pub fn module(&self, name: &str) -> TestModule<'_> {
+        TestModule {
+            node: self,
+            name: name.to_string(),
+        }
+    }
+
+    pub fn now_unix_ms(&self) -> u64 {
+        self.runtime.now_unix_ms()
+    }
+
+    /// Move the clock forward, running the schedules and ticks that come due on
+    /// the way.
+    pub async fn advance(&self, duration: Duration) {
+        self.runtime.advance(duration).await;
+    }
+
+    /// Table changes of every module since the node started or the last
+    /// [`TestNode::take_table_changes`].
+    pub fn table_changes(&self) -> Vec<TableChange> {
+        self.runtime.table_changes()
+    }
+
+    pub fn take_table_changes(&self) -> Vec<TableChange> {
+        self.runtime.take_table_changes()
+    }
+
+    pub fn runtime(&self) -> &TestRuntime {
+        &self.runtime
+    }
+}
+
+/// Module loaded on a [`TestNode`].
+pub struct TestModule<'a> {
+    node: &'a TestNode,
+    name: String,
+}
+
+impl TestModule<'_> {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn schema(&self) -> Option<ModuleSchema> {
+        self.node.runtime.module_schema(&self.name)
+    }
+
+    /// Call a reducer. Returns once it and the reducers it triggered have run.
+    pub async fn call(&self, reducer: &str, args: impl CallArgs) -> Result<(), IntersticeError> {
+        self.node
+            .runtime
+            .call_reducer(&self.name, reducer, args.into_args())
+            .await
+    }
+
+    /// Call a query and convert its result.
+    pub async fn query<R>(&self, query: &str, args: impl CallArgs) -> Result<R, IntersticeError>
+    where
+        R: TryFrom<IntersticeValue>,
+        R::Error: Debug,
+    {
+        let value = self
+            .node
+            .runtime
+            .call_query(&self.name, query, args.into_args())
+            .await?;
+        R::try_from(value).map_err(|err| {
+            IntersticeError::Internal(format!(
+                "Couldn't convert the result of query '{}.{}': {:?}",
+                self.name, query, err
+            ))
+        })
+    }
+
+    /// Current rows of a table, converted to its row type.
+    pub fn rows<T>(&self, table: &str) -> Result<Vec<T>, IntersticeError>
+    where
+        T: TryFrom<Row>,
+        T::Error: Debug,
+    {
+        self.raw_rows(table)?
+            .into_iter()
+            .map(|row| {
+                T::try_from(row).map_err(|err| {
+                    IntersticeError::Internal(format!(
+                        "Couldn't convert a row of table '{}.{}': {:?}",
+                        self.name, table, err
+                    ))
+                })
+            })
+            .collect()
+    }
+
+    pub fn raw_rows(&self, table: &str) -> Result<Vec<Row>, IntersticeError> {
+        self.node.runtime.rows(&self.name, table)
+    }
+
+    /// Changes of one of the module's tables, in the order they were emitted.
+    pub fn table_changes(&self, table: &str) -> Vec<TableChange> {
+        self.node
+            .runtime
+            .table_changes()
+            .into_iter()
+            .filter(|change| change.module_name() == self.name && change.table_name() == table)
+            .collect()
+    }
+
+    /// Pending schedules of the module.
+    pub fn schedules(&self) -> Vec<ScheduleInfo> {
+        self.node.runtime.schedules(&self.name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tuples_become_call_arguments() {
+        assert!(().into_args().is_empty());
+        assert_eq!(
+            (1u32, "name".to_string(), true).into_args(),
+            vec![
+                IntersticeValue::U32(1),
+                IntersticeValue::String("name".into()),
+                IntersticeValue::Bool(true),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn calls_report_missing_modules() {
+        let node = TestNode::starting_at(0).unwrap();
+        let module = node.module("missing");
+        assert!(module.call("reducer", (1u8,)).await.is_err());
+        assert!(module.query::<u32>("query", ()).await.is_err());
+        assert!(module.raw_rows("table").is_err());
+        node.advance(Duration::from_secs(60)).await;
+        assert_eq!(node.now_unix_ms(), 60_000);
+    }
+}
@@ -0,0 +1,74 @@
+use interstice_core::IntersticeError;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+#[derive(Deserialize)]
+struct Manifest {
+    package: Package,
+}
+
+#[derive(Deserialize)]
+struct Package {
+    name: String,
+}
+
+/// Build the module project at `project_path` for wasm32 and read its binary.
+///
+/// The module is built in the `interstice-test` directory of the target directory
+/// of the tests, apart from the build of the tests themselves.
+pub fn build_module(project_path: impl AsRef<Path>) -> Result<Vec<u8>, IntersticeError> {
+    let project_path = project_path.as_ref();
+    let manifest_path = project_path.join("Cargo.toml");
+    let manifest = std::fs::read_to_string(&manifest_path).map_err(|err| {
+        IntersticeError::Internal(format!("Failed to read {}: {err}", manifest_path.display()))
+    })?;
+    let manifest: Manifest = toml::from_str(&manifest).map_err(|err| {
+        IntersticeError::Internal(format!("Failed to parse {}: {err}", manifest_path.display()))
+    })?;
+
+    let target_dir = target_dir().join("interstice-test");
+    let output = Command::new(std::env::var("CARGO").unwrap_or_else(|_| "cargo".into()))
+        .args(["build", "--release", "--target", "wasm32-unknown-unknown"])
+        .arg("--manifest-path")
+        .arg(&manifest_path)
+        .arg("--target-dir")
+        .arg(&target_dir)
+        // Flags meant for the test build would apply to the module too.
+        .env_remove("RUSTFLAGS")
+        .env_remove("CARGO_ENCODED_RUSTFLAGS")
+        .output()
+        .map_err(|err| IntersticeError::Internal(format!("Failed to run cargo: {err}")))?;
+    if !output.status.success() {
+        return Err(IntersticeError::Internal(format!(
+            "Cargo build failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+
+    let wasm_path = wasm_path(&target_dir, &manifest.package.name);
+    std::fs::read(&wasm_path).map_err(|err| {
+        IntersticeError::Internal(format!("Failed to read {}: {err}", wasm_path.display()))
+    })
+}
+
+/// Target directory of the running tests: `CARGO_TARGET_DIR` when set, otherwise
+/// the one holding the test binary (`<target>/<profile>/deps/<test>`).
+fn target_dir() -> PathBuf {
+    if let Some(target_dir) = std::env::var_os("CARGO_TARGET_DIR") {
+        return PathBuf::from(target_dir);
+    }
+    std::env::current_exe()
+        .ok()
+        .and_then(|test_binary| test_binary.ancestors().nth(3).map(Path::to_path_buf))
+        .unwrap_or_else(std::env::temp_dir)
+}
+
+fn wasm_path(target_dir: &Path, package_name: &str) -> PathBuf {
+    // Cargo converts hyphens to underscores in output filenames
+    target_dir
+        .join("wasm32-unknown-unknown")
+        .join("release")
+        .join(package_name.replace('-', "_"))
+        .with_extension("wasm")
+}
@@ -0,0 +1,54 @@
+//! Builds `modules/examples/hello` and drives it through a [`TestNode`].
+
+use interstice_test::{IntersticeValue, TestNode, build_module};
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::Duration;
+
+const HELLO_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../modules/examples/hello");
+
+/// Whether the toolchain can build modules, i.e. has the wasm32 standard library.
+fn wasm_target_installed() -> bool {
+    Command::new(std::env::var("RUSTC").unwrap_or_else(|_| "rustc".into()))
+        .args(["--print", "sysroot"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| {
+            PathBuf::from(String::from_utf8_lossy(&output.stdout).trim())
+                .join("lib/rustlib/wasm32-unknown-unknown")
+                .exists()
+        })
+        .unwrap_or(false)
+}
+
+#[tokio::test]
+async fn hello_example_stores_greetings() {
+    if !wasm_target_installed() {
+        eprintln!("skipped: the wasm32-unknown-unknown target is not installed");
+        return;
+    }
+    let wasm = build_module(HELLO_PATH).unwrap();
+    let node = TestNode::starting_at(0).unwrap();
+    let hello = node.load_module(wasm).await.unwrap();
+    assert_eq!(hello.name(), "hello-example");
+
+    hello.call("hello", ("Ada".to_string(),)).await.unwrap();
+    let rows = hello.raw_rows("greetings").unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].entries[0], IntersticeValue::String("Hello, Ada!".into()));
+
+    let found: IntersticeValue = hello
+        .query("find_greetings", ("Hello".to_string(),))
+        .await
+        .unwrap();
+    let IntersticeValue::Vec(found) = found else {
+        panic!("find_greetings returns a list, got {:?}", found);
+    };
+    assert_eq!(found.len(), 1);
+
+    // `load` scheduled a reducer a second ahead.
+    assert_eq!(hello.schedules().len(), 1);
+    node.advance(Duration::from_secs(1)).await;
+    assert!(hello.schedules().is_empty());
+}
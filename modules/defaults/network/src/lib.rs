@@ -11,9 +11,8 @@ mod dns;
 //   3. HTTP GET   — host → DNS → TCP → HTTP/1.1 GET → assembled response
 //
 // Everything is async: an app calls a reducer with its own `req_id`, then reads
-// the result from a public table (stamped with `owner` = the calling module, so
-// each app filters to its own rows — proper per-app visibility waits on table
-// views, see the README TODO).
+// the result from a public table. Rows are stamped with `owner` = the calling
+// module and the tables' views only let that module see them.
 interstice_module!(visibility: Public, authorities: [Network]);
 
 /// Public resolver used for DNS queries (Cloudflare). Hard-coded: choosing a
@@ -80,9 +79,9 @@ pub struct HttpJob {
     redirects: u32,
 }
 
-// ── Public result tables (apps subscribe, filter by `owner`) ────────────────────
+// ── Public result tables (apps subscribe, see only their own rows) ──────────────
 
-#[table(public)]
+#[table(public, view = "connection_view")]
 #[derive(Debug)]
 pub struct Connection {
     #[primary_key(auto_inc)]
@@ -94,7 +93,7 @@ pub struct Connection {
     pub error: String,
 }
 
-#[table(public)]
+#[table(public, view = "inbound_view")]
 #[derive(Debug)]
 pub struct Inbound {
     #[primary_key(auto_inc)]
@@ -104,7 +103,7 @@ pub struct Inbound {
     pub data: Vec<u8>,
 }
 
-#[table(public)]
+#[table(public, view = "conn_closed_view")]
 #[derive(Debug)]
 pub struct ConnClosed {
     #[primary_key(auto_inc)]
@@ -113,7 +112,7 @@ pub struct ConnClosed {
     pub handle: u64,
 }
 
-#[table(public)]
+#[table(public, view = "resolved_view")]
 #[derive(Debug)]
 pub struct Resolved {
     #[primary_key(auto_inc)]
@@ -125,7 +124,7 @@ pub struct Resolved {
     pub error: String,
 }
 
-#[table(public)]
+#[table(public, view = "http_response_view", index(btree, fields = [owner, req_id]))]
 #[derive(Debug)]
 pub struct HttpResponse {
     #[primary_key(auto_inc)]
@@ -145,6 +144,33 @@ pub struct HttpResponse {
     pub final_tls: bool,
 }
 
+// Table views: other modules only see the rows they own.
+
+#[query]
+fn connection_view(ctx: QueryContext, row: Connection) -> bool {
+    row.owner == ctx.caller_module_name
+}
+
+#[query]
+fn inbound_view(ctx: QueryContext, row: Inbound) -> bool {
+    row.owner == ctx.caller_module_name
+}
+
+#[query]
+fn conn_closed_view(ctx: QueryContext, row: ConnClosed) -> bool {
+    row.owner == ctx.caller_module_name
+}
+
+#[query]
+fn resolved_view(ctx: QueryContext, row: Resolved) -> bool {
+    row.owner == ctx.caller_module_name
+}
+
+#[query]
+fn http_response_view(ctx: QueryContext, row: HttpResponse) -> bool {
+    row.owner == ctx.caller_module_name
+}
+
 // ── Lifecycle ───────────────────────────────────────────────────────────────────
 
 #[reducer(on = "load")]
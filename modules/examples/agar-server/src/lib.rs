@@ -43,11 +43,11 @@ where
 {
     ctx.log("agar-server ready");
     spawn_missing_foods(&ctx);
-    ctx.schedule("tick", DT_MS).expect("Couldn't schedule tick");
 }
 
-#[reducer]
-pub fn tick<Caps>(ctx: ReducerContext<Caps>)
+/// Runs every `DT_MS`; ticks missed while a slow one was running are skipped.
+#[reducer(on = "tick(8ms)")]
+pub fn tick<Caps>(ctx: ReducerContext<Caps>, _tick: TickEvent)
 where
     Caps: CanRead<Food>
         + CanInsert<Food>
@@ -127,7 +127,6 @@ where
     }
 
     spawn_missing_foods(&ctx);
-    ctx.schedule("tick", DT_MS).expect("Couldn't schedule tick");
 }
 
 fn collides(a: &Vec2, ar: f32, b: &Vec2, br: f32) -> bool {
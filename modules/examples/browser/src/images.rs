@@ -93,8 +93,7 @@ where
         .current
         .tables
         .imagewaiter()
-        .scan()
-        .into_iter()
+        .scan_iter()
         .filter(|w| w.req_id == req_id)
         .map(|w| w.element_id)
         .collect();
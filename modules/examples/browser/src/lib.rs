@@ -333,7 +333,7 @@ where
         .get(0)
         .map(|b| b.pressed)
         .unwrap_or(false);
-    let enter_down = ctx.input().tables.keystate().scan().iter().any(|k| {
+    let enter_down = ctx.input().tables.keystate().scan_iter().any(|k| {
         k.pressed && (k.code == KeyCode::Enter as u32 || k.code == KeyCode::NumpadEnter as u32)
     });
 
@@ -11,19 +11,68 @@ use crate::bindings::{
 };
 use interstice_sdk::*;
 
+/// Remote hello calls whose outcome was not reported yet.
+#[table]
+pub struct HelloCall {
+    #[primary_key]
+    call_id: String,
+}
+
 #[reducer(on = "load")]
-fn caller(ctx: ReducerContext) {
+fn caller<Caps>(ctx: ReducerContext<Caps>)
+where
+    Caps: CanInsert<HelloCall>,
+{
     ctx.log("Calling remote hello...");
-    if let Err(err) = ctx
+    let call = match ctx
         .hello_example()
         .hello_example()
         .reducers
         .hello("Client !".to_string())
     {
-        ctx.log(&format!("Failed to call remote hello: {}", err));
+        Ok(call) => call,
+        Err(err) => {
+            ctx.log(&format!("Failed to call remote hello: {}", err));
+            return;
+        }
+    };
+    if let Err(err) = ctx.current.tables.hellocall().insert(HelloCall {
+        call_id: call.id().to_string(),
+    }) {
+        ctx.log(&format!("Failed to store remote hello call: {:?}", err));
         return;
     }
-    ctx.log("hello remote called !");
+    if let Err(err) = ctx.schedule("check_hello_calls", 500) {
+        ctx.log(&format!("Failed to schedule hello call check: {}", err));
+    }
+}
+
+/// Report the outcome of the remote hello calls, checking again later while the
+/// node has not answered.
+#[reducer]
+fn check_hello_calls<Caps>(ctx: ReducerContext<Caps>)
+where
+    Caps: CanRead<HelloCall> + CanDelete<HelloCall>,
+{
+    let mut pending = false;
+    for hello_call in ctx.current.tables.hellocall().scan() {
+        match ReducerCall::new(hello_call.call_id.clone()).status() {
+            Ok(ReducerCallStatus::Pending) => {
+                pending = true;
+                continue;
+            }
+            Ok(ReducerCallStatus::Committed { commit_sequence }) => ctx.log(&format!(
+                "hello remote committed (commit sequence {})",
+                commit_sequence
+            )),
+            Ok(status) => ctx.log(&format!("hello remote did not commit: {:?}", status)),
+            Err(err) => ctx.log(&format!("Failed to follow remote hello: {}", err)),
+        }
+        let _ = ctx.current.tables.hellocall().delete(hello_call.call_id);
+    }
+    if pending && let Err(err) = ctx.schedule("check_hello_calls", 500) {
+        ctx.log(&format!("Failed to schedule hello call check: {}", err));
+    }
 }
 
 #[reducer(on = "hello-example.hello-example.greetings.insert")]
@@ -46,3 +95,14 @@ where
         ctx.log(&format!("Remote greeting row: {:?}", greeting.greeting));
     }
 }
+
+#[reducer(
+    on = "hello-example.hello-example.greetings.insert",
+    filter = r#"greeting.starts_with("Hello, Client")"#
+)]
+fn on_client_greeting(ctx: ReducerContext, inserted_row: Greetings) {
+    ctx.log(&format!(
+        "Caller received a greeting for a client: {:?}",
+        inserted_row.greeting
+    ));
+}
@@ -72,3 +72,32 @@ where
 {
     ctx.current.tables.greetings().scan()
 }
+
+#[query]
+fn first_greetings<Caps>(ctx: QueryContext<Caps>, count: u32) -> Vec<Greetings>
+where
+    Caps: CanRead<Greetings>,
+{
+    let options = ScanOptions {
+        limit: Some(count as u64),
+        ..Default::default()
+    };
+    ctx.current
+        .tables
+        .greetings()
+        .iter_by_greeting(.., options)
+        .collect()
+}
+
+#[query]
+fn find_greetings<Caps>(ctx: QueryContext<Caps>, prefix: String) -> Vec<Greetings>
+where
+    Caps: CanRead<Greetings>,
+{
+    ctx.current
+        .tables
+        .greetings()
+        .filter(|g| g.greeting.starts_with(prefix))
+        .order_by_desc(|g| g.id)
+        .fetch()
+}
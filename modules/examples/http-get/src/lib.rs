@@ -5,15 +5,11 @@ use interstice_sdk::*;
 // authority itself, this module asks the default `network` module to fetch a URL
 // for it. The broker resolves DNS, opens the TCP socket, sends the GET and
 // assembles the response, then hands the result back as a row in its public
-// `HttpResponse` table (stamped with this module's name so we filter to our own
-// request). Proves multiple apps can share the single NIC through the normal
-// cross-module ABI.
+// `HttpResponse` table, whose view only lets this module see its own rows.
+// Proves multiple apps can share the single NIC through the normal cross-module
+// ABI.
 interstice_module!(visibility: Private);
 
-/// This module's schema name — the broker stamps results with the caller's name,
-/// so we ignore rows that belong to other apps.
-const ME: &str = "http-get-example";
-
 /// Inspectable copy of the fetched result.
 #[table(public)]
 #[derive(Debug)]
@@ -58,9 +54,6 @@ fn on_http<Caps>(ctx: ReducerContext<Caps>, row: HttpResponse)
 where
     Caps: CanInsert<HttpResult>,
 {
-    if row.owner != ME {
-        return; // belongs to another app
-    }
     if !row.error.is_empty() {
         ctx.log(&format!(
             "http-get: request {} failed: {}",